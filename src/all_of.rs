@@ -0,0 +1,212 @@
+use serde_json::{Map, Value};
+
+use crate::ParseSchemaError;
+
+/// The keywords of a JSON Schema with its `allOf` combined into one schema of the values that
+/// fit all of its schemas, along with the schema's own other keywords.
+///
+/// Properties are those of all of them, and required if any requires them, with the schemas of
+/// a property that several define combined in turn. Types and enums are those they have in
+/// common, the tightest of their bounds apply, and of keywords that can't be combined, such as
+/// `format` or `pattern`, the first one wins. An `anyOf` or `oneOf` of several of them is one of
+/// the combinations of a variant of each.
+pub(crate) fn flatten_all_of(
+    schema_obj: &Map<String, Value>,
+) -> Result<Map<String, Value>, ParseSchemaError> {
+    let Some(all_of) = schema_obj.get("allOf") else {
+        return Ok(schema_obj.clone());
+    };
+    let parts = all_of
+        .as_array()
+        .ok_or_else(|| ParseSchemaError::InvalidSchema("allOf must be an array".to_string()))?;
+
+    let mut combined: Map<String, Value> = schema_obj
+        .iter()
+        .filter(|(key, _)| *key != "allOf")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    for part in parts {
+        let part = part.as_object().ok_or_else(|| {
+            ParseSchemaError::InvalidSchema("allOf items must be objects".to_string())
+        })?;
+        for (key, value) in flatten_all_of(part)? {
+            match combined.get_mut(&key) {
+                Some(existing) => combine(&key, existing, value)?,
+                None => {
+                    combined.insert(key, value);
+                }
+            }
+        }
+    }
+    // parts often only add properties to a base object, without saying that they are objects
+    if !combined.contains_key("type")
+        && ["properties", "required"]
+            .iter()
+            .any(|key| combined.contains_key(*key))
+    {
+        combined.insert("type".to_string(), Value::from("object"));
+    }
+    Ok(combined)
+}
+
+/// The types of a `type` keyword, which is either one type or an array of them.
+fn types(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(types) => types.iter().collect(),
+        value => vec![value],
+    }
+}
+
+/// Combines the value of a keyword of one schema of an `allOf` with that of another.
+fn combine(key: &str, existing: &mut Value, value: Value) -> Result<(), ParseSchemaError> {
+    match key {
+        "type" => {
+            let mut common: Vec<Value> = types(existing)
+                .into_iter()
+                .filter(|t| types(&value).contains(t))
+                .cloned()
+                .collect();
+            *existing = match common.len() {
+                0 => {
+                    return Err(ParseSchemaError::InvalidSchema(format!(
+                        "allOf of schemas of different types, {} and {}",
+                        existing, value
+                    )))
+                }
+                1 => common.pop().unwrap(),
+                _ => Value::Array(common),
+            };
+        }
+        "enum" => {
+            let values = value.as_array().cloned().unwrap_or_default();
+            if let Value::Array(existing_values) = existing {
+                existing_values.retain(|v| values.contains(v));
+                if existing_values.is_empty() {
+                    return Err(ParseSchemaError::InvalidSchema(
+                        "allOf of enums without values in common".to_string(),
+                    ));
+                }
+            }
+        }
+        "required" => {
+            if let (Value::Array(names), Value::Array(more)) = (existing, value) {
+                for name in more {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+        "properties" => {
+            if let (Value::Object(properties), Value::Object(more)) = (existing, value) {
+                for (name, schema) in more {
+                    match properties.get_mut(&name) {
+                        Some(property) => {
+                            *property = serde_json::json!({ "allOf": [property.take(), schema] })
+                        }
+                        None => {
+                            properties.insert(name, schema);
+                        }
+                    }
+                }
+            }
+        }
+        "items" | "additionalProperties" => match (&*existing, value) {
+            (Value::Bool(allowed), Value::Bool(more)) => *existing = Value::Bool(*allowed && more),
+            (Value::Bool(false), _) => {}
+            (_, value @ Value::Bool(false)) => *existing = value,
+            (Value::Object(_), value @ Value::Object(_)) => {
+                *existing = serde_json::json!({ "allOf": [existing.take(), value] })
+            }
+            _ => {}
+        },
+        "anyOf" | "oneOf" if *existing != value => {
+            // one of the combinations of a variant of each
+            let variants = existing.as_array().cloned().unwrap_or_default();
+            *existing = Value::Array(
+                variants
+                    .into_iter()
+                    .map(
+                        |variant| serde_json::json!({ "allOf": [variant, { key: value.clone() }] }),
+                    )
+                    .collect(),
+            );
+        }
+        "minimum" | "exclusiveMinimum" | "minLength" | "minItems" | "minProperties"
+            if value.as_f64() > existing.as_f64() =>
+        {
+            *existing = value
+        }
+        "maximum" | "exclusiveMaximum" | "maxLength" | "maxItems" | "maxProperties"
+            if value.as_f64() < existing.as_f64() =>
+        {
+            *existing = value
+        }
+        "uniqueItems" if value == Value::Bool(true) => *existing = value,
+        "nullable" if value == Value::Bool(false) => *existing = value,
+        // the first value of keywords that can't be combined wins
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn flatten(schema: Value) -> Result<Value, ParseSchemaError> {
+        flatten_all_of(schema.as_object().unwrap()).map(Value::Object)
+    }
+
+    #[test]
+    fn combines_the_keywords_of_all_schemas() {
+        let schema = json!({
+            "description": "A user",
+            "allOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "integer", "minimum": 0},
+                        "role": {"type": "string", "enum": ["admin", "user", "guest"]}
+                    },
+                    "required": ["id"]
+                },
+                {
+                    "properties": {
+                        "id": {"maximum": 100},
+                        "role": {"enum": ["user", "guest", "bot"]},
+                        "email": {"type": ["string", "null"], "format": "email"}
+                    },
+                    "required": ["email"]
+                },
+                {"allOf": [{"properties": {"email": {"type": "string"}}}]}
+            ]
+        });
+
+        let flattened = flatten(schema).unwrap();
+        assert_eq!(flattened["type"], "object");
+        assert_eq!(flattened["description"], "A user");
+        assert_eq!(flattened["required"], json!(["id", "email"]));
+        let properties = &flattened["properties"];
+        assert_eq!(
+            flatten(properties["id"].clone()).unwrap(),
+            json!({"type": "integer", "minimum": 0, "maximum": 100})
+        );
+        assert_eq!(
+            flatten(properties["role"].clone()).unwrap(),
+            json!({"type": "string", "enum": ["user", "guest"]})
+        );
+        assert_eq!(
+            flatten(properties["email"].clone()).unwrap(),
+            json!({"type": "string", "format": "email"})
+        );
+    }
+
+    #[test]
+    fn rejects_schemas_that_nothing_fits() {
+        assert!(flatten(json!({"allOf": [{"type": "string"}, {"type": "integer"}]})).is_err());
+        assert!(flatten(json!({"allOf": [{"enum": ["a"]}, {"enum": ["b"]}]})).is_err());
+        assert!(flatten(json!({"allOf": {"type": "string"}})).is_err());
+    }
+}
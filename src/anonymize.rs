@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+
+use crate::{produce_with, NumberType, ProduceOptions, SchemaState, StringType};
+
+/// How many synthetic values are tried for a value before one that replaces another value too
+/// is kept.
+const MAX_ATTEMPTS: usize = 8;
+
+/// Replaces the strings and numbers of real data with synthetic ones, keeping the keys of
+/// objects, the lengths of arrays and the nesting of the data as they are, so that data such as
+/// production payloads can be shared as test fixtures. Values are replaced according to the
+/// schema inferred from the data: strings of a format, such as emails or dates, by others of
+/// the format, enum values by variants of the enum, numbers by others in the range of the
+/// schema, and other strings by strings of the same length, with each letter and digit replaced
+/// by another letter of the same case or digit. Booleans, `null`s and the tags of tagged unions
+/// are kept.
+///
+/// The same value is replaced by the same synthetic value wherever it occurs, so that IDs and
+/// the fields that refer to them still join, and different values are replaced by different
+/// ones where possible. With a seed, the same data is always replaced in the same way.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_schema, Anonymizer, InferenceOptions};
+/// use serde_json::json;
+///
+/// let record = json!({"name": "Ada Lovelace", "code": "AB-12", "tags": ["x", "y"], "vip": true});
+/// let schema = infer_schema(record.clone(), &InferenceOptions::default());
+///
+/// let anonymized = Anonymizer::new(&schema, Some(42)).anonymize(&record);
+/// let name = anonymized["name"].as_str().unwrap();
+/// assert_ne!(name, "Ada Lovelace");
+/// assert_eq!(name.len(), "Ada Lovelace".len());
+/// assert_eq!(&anonymized["code"].as_str().unwrap()[2..3], "-");
+/// assert_eq!(anonymized["tags"].as_array().unwrap().len(), 2);
+/// assert_eq!(anonymized["vip"], true);
+/// ```
+pub struct Anonymizer<'a> {
+    schema: &'a SchemaState,
+    rng: StdRng,
+    /// The synthetic values that replace each value replaced so far, as serialized JSON.
+    replacements: HashMap<String, Value>,
+    /// The serialized synthetic values used so far.
+    used: HashSet<String>,
+}
+
+impl<'a> Anonymizer<'a> {
+    /// Creates an anonymizer of values of `schema`, whose replacements are random unless a seed
+    /// is given.
+    pub fn new(schema: &'a SchemaState, seed: Option<u64>) -> Self {
+        Anonymizer {
+            schema,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            replacements: HashMap::new(),
+            used: HashSet::new(),
+        }
+    }
+
+    /// Returns `value` with its strings and numbers replaced by synthetic ones. Values that are
+    /// anonymized one after the other, such as the records of JSON lines, share replacements.
+    pub fn anonymize(&mut self, value: &Value) -> Value {
+        let schema = self.schema;
+        self.replace(schema, value)
+    }
+
+    fn replace(&mut self, schema: &SchemaState, value: &Value) -> Value {
+        match (schema, value) {
+            (_, Value::Null | Value::Bool(_)) => value.clone(),
+            (SchemaState::Nullable(inner), _) => self.replace(inner, value),
+            (SchemaState::Union(variants), _) => {
+                match variants.iter().find(|variant| fits(variant, value)) {
+                    Some(variant) => self.replace(variant, value),
+                    None => self.replace(&SchemaState::Indefinite, value),
+                }
+            }
+            (SchemaState::Mixed(variants), _) => {
+                match variants.iter().find(|(variant, _)| fits(variant, value)) {
+                    Some((variant, _)) => self.replace(variant, value),
+                    None => self.replace(&SchemaState::Indefinite, value),
+                }
+            }
+            (SchemaState::TaggedUnion { tag, variants }, Value::Object(fields)) => {
+                let variant = fields
+                    .get(tag)
+                    .and_then(Value::as_str)
+                    .and_then(|name| variants.get(name))
+                    .unwrap_or(&SchemaState::Indefinite);
+                let mut replaced = self.replace(variant, value);
+                if let (Some(replaced), Some(tag_value)) =
+                    (replaced.as_object_mut(), fields.get(tag))
+                {
+                    replaced.insert(tag.clone(), tag_value.clone());
+                }
+                replaced
+            }
+            (
+                SchemaState::Object {
+                    required, optional, ..
+                },
+                Value::Object(fields),
+            ) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, field)| {
+                        let schema = required
+                            .get(key)
+                            .or_else(|| optional.get(key))
+                            .unwrap_or(&SchemaState::Indefinite);
+                        (key.clone(), self.replace(schema, field))
+                    })
+                    .collect(),
+            ),
+            (SchemaState::Map { schema, .. }, Value::Object(fields)) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), self.replace(schema, field)))
+                    .collect(),
+            ),
+            (SchemaState::Array { schema, .. }, Value::Array(items)) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.replace(schema, item))
+                    .collect(),
+            ),
+            // tuples keep the elements that the input has, even if it has more or fewer
+            (SchemaState::Tuple(positions), Value::Array(items)) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| match positions.get(index) {
+                        Some(position) => self.replace(position, item),
+                        None => self.replace(&SchemaState::Indefinite, item),
+                    })
+                    .collect(),
+            ),
+            // values of other shapes, such as coordinates, are produced whole
+            (
+                SchemaState::Coordinates(_)
+                | SchemaState::GeoPoint { .. }
+                | SchemaState::Money { .. }
+                | SchemaState::ExtendedJson(_),
+                _,
+            ) => self.produce(schema),
+            (_, Value::Object(fields)) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, field)| {
+                        (key.clone(), self.replace(&SchemaState::Indefinite, field))
+                    })
+                    .collect(),
+            ),
+            (_, Value::Array(items)) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.replace(&SchemaState::Indefinite, item))
+                    .collect(),
+            ),
+            (_, Value::String(_) | Value::Number(_)) => self.replace_scalar(schema, value),
+        }
+    }
+
+    /// Replaces a string or number by the synthetic value that replaced it before, or a new one
+    /// that hasn't replaced another value, if one turns up.
+    fn replace_scalar(&mut self, schema: &SchemaState, value: &Value) -> Value {
+        let original = value.to_string();
+        if let Some(replacement) = self.replacements.get(&original) {
+            return replacement.clone();
+        }
+        let mut replacement = Value::Null;
+        for _ in 0..MAX_ATTEMPTS {
+            replacement = self.synthesize(schema, value);
+            if !self.used.contains(&replacement.to_string()) {
+                break;
+            }
+        }
+        self.used.insert(replacement.to_string());
+        self.replacements.insert(original, replacement.clone());
+        replacement
+    }
+
+    fn synthesize(&mut self, schema: &SchemaState, value: &Value) -> Value {
+        match (schema, value) {
+            (
+                SchemaState::String(
+                    StringType::Unknown { .. }
+                    | StringType::Numeric { .. }
+                    | StringType::FixedWidthNumeric { .. },
+                ),
+                Value::String(string),
+            ) => Value::String(replace_chars(string, &mut self.rng)),
+            (SchemaState::String(StringType::Charset { class, .. }), Value::String(string)) => {
+                let chars = class.chars();
+                Value::String(
+                    string
+                        .chars()
+                        .map(|_| chars[self.rng.gen_range(0..chars.len())])
+                        .collect(),
+                )
+            }
+            (SchemaState::String(_), Value::String(_)) => self.produce(schema),
+            (
+                SchemaState::Number(
+                    NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. },
+                ),
+                Value::Number(_),
+            ) => Value::from(self.rng.gen_range(*min..=*max)),
+            (SchemaState::Number(_), Value::Number(_)) => self.produce(schema),
+            (_, Value::String(string)) => Value::String(replace_chars(string, &mut self.rng)),
+            (_, Value::Number(number)) => {
+                let digits = replace_chars(&number.to_string(), &mut self.rng);
+                serde_json::from_str(&digits).unwrap_or_else(|_| value.clone())
+            }
+            _ => value.clone(),
+        }
+    }
+
+    fn produce(&mut self, schema: &SchemaState) -> Value {
+        let options = ProduceOptions {
+            seed: Some(self.rng.gen()),
+            ..Default::default()
+        };
+        produce_with(schema, 1, &options)
+    }
+}
+
+/// Whether a value is of the kind of values a schema describes, as a string, number, object or
+/// array.
+fn fits(schema: &SchemaState, value: &Value) -> bool {
+    match (schema, value) {
+        (SchemaState::Nullable(inner), _) => fits(inner, value),
+        (SchemaState::Union(variants), _) => variants.iter().any(|variant| fits(variant, value)),
+        (SchemaState::Mixed(variants), _) => {
+            variants.iter().any(|(variant, _)| fits(variant, value))
+        }
+        (SchemaState::Literal(values), _) => values.contains(value),
+        (SchemaState::String(_), Value::String(_))
+        | (SchemaState::Number(_), Value::Number(_))
+        | (SchemaState::Boolean, Value::Bool(_))
+        | (
+            SchemaState::Array { .. } | SchemaState::Coordinates(_) | SchemaState::Tuple(_),
+            Value::Array(_),
+        )
+        | (
+            SchemaState::Object { .. }
+            | SchemaState::Map { .. }
+            | SchemaState::TaggedUnion { .. }
+            | SchemaState::GeoPoint { .. }
+            | SchemaState::Money { .. }
+            | SchemaState::ExtendedJson(_),
+            Value::Object(_),
+        ) => true,
+        _ => false,
+    }
+}
+
+/// Replaces each ASCII letter of a string by another of the same case, each other letter by an
+/// ASCII lowercase one, and each digit by another digit, keeping a leading zero of a run of
+/// digits a zero, and other leading digits nonzero, so that numbers keep their number of
+/// digits. Other characters, such as spaces and punctuation, are kept.
+fn replace_chars<R: Rng + ?Sized>(string: &str, rng: &mut R) -> String {
+    let mut previous_is_digit = false;
+    string
+        .chars()
+        .map(|c| {
+            let leading = !previous_is_digit;
+            previous_is_digit = c.is_ascii_digit();
+            match c {
+                '0' if leading => '0',
+                '1'..='9' if leading => char::from(rng.gen_range(b'1'..=b'9')),
+                '0'..='9' => char::from(rng.gen_range(b'0'..=b'9')),
+                'A'..='Z' => char::from(rng.gen_range(b'A'..=b'Z')),
+                c if c.is_alphabetic() => char::from(rng.gen_range(b'a'..=b'z')),
+                c => c,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, InferenceOptions};
+    use serde_json::json;
+
+    #[test]
+    fn keeps_the_structure_and_replaces_the_values() {
+        let input = json!([
+            {"id": 101, "email": "ada@example.com", "name": "Ada", "tags": ["a", "b"], "score": 1.5},
+            {"id": 102, "email": "alan@example.com", "name": "Alan", "tags": [], "score": null},
+        ]);
+        let schema = infer_schema(input.clone(), &InferenceOptions::default());
+
+        let anonymized = Anonymizer::new(&schema, Some(1)).anonymize(&input);
+
+        let (before, after) = (input.as_array().unwrap(), anonymized.as_array().unwrap());
+        assert_eq!(after.len(), 2);
+        for (before, after) in before.iter().zip(after) {
+            let keys = |value: &Value| {
+                value
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(keys(before), keys(after));
+            assert_ne!(before["email"], after["email"]);
+            assert!(after["email"].as_str().unwrap().contains('@'));
+            assert_eq!(
+                before["name"].as_str().unwrap().len(),
+                after["name"].as_str().unwrap().len()
+            );
+            assert_eq!(
+                before["tags"].as_array().unwrap().len(),
+                after["tags"].as_array().unwrap().len()
+            );
+            let id = after["id"].as_i64().unwrap();
+            assert!((101..=102).contains(&id));
+        }
+        assert_eq!(after[1]["score"], Value::Null);
+        assert_eq!(
+            Anonymizer::new(&schema, Some(1)).anonymize(&input),
+            anonymized
+        );
+    }
+
+    #[test]
+    fn replaces_the_same_value_the_same_way_everywhere() {
+        let input = json!({
+            "users": [{"id": "u-1"}, {"id": "u-2"}],
+            "orders": [{"user": "u-2"}, {"user": "u-1"}, {"user": "u-2"}],
+        });
+        let schema = infer_schema(input.clone(), &InferenceOptions::default());
+
+        let anonymized = Anonymizer::new(&schema, Some(3)).anonymize(&input);
+
+        let users = &anonymized["users"];
+        let orders = &anonymized["orders"];
+        assert_ne!(users[0]["id"], users[1]["id"]);
+        assert_eq!(orders[0]["user"], users[1]["id"]);
+        assert_eq!(orders[1]["user"], users[0]["id"]);
+        assert_eq!(orders[2]["user"], users[1]["id"]);
+    }
+
+    #[test]
+    fn replaced_characters_keep_their_kind() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let original = "Zoë 042-19 x.y";
+        let replaced: Vec<char> = replace_chars(original, &mut rng).chars().collect();
+
+        let kind = |c: char| match c {
+            'A'..='Z' => 'A',
+            '0'..='9' => '9',
+            c if c.is_alphabetic() => 'a',
+            c => c,
+        };
+        let kinds: String = replaced.iter().copied().map(kind).collect();
+        assert_eq!(kinds, "Aaa 999-99 a.a");
+        assert!(replaced[2].is_ascii_lowercase());
+        // the leading zero stays, and the leading digit of 19 is still nonzero
+        assert_eq!(replaced[4], '0');
+        assert_ne!(replaced[8], '0');
+    }
+}
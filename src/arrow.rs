@@ -0,0 +1,98 @@
+use std::io::Cursor;
+
+use arrow_ipc::reader::{FileReader, StreamReader};
+use serde_json::Value;
+
+use crate::columnar::{invalid, read_batches};
+use crate::DrivelError;
+
+/// The start of a file of the Arrow IPC file format, which holds a stream.
+const FILE_MAGIC: &[u8] = b"ARROW1";
+
+/// Reads the rows of an Arrow IPC stream, or of a file of the Arrow IPC file format, also
+/// known as Feather, as records, each an object of its columns, with structs as objects, lists
+/// as arrays and maps as objects of their keys. Dates are read as `YYYY-MM-DD` strings and
+/// timestamps as RFC 3339 date-times, as drivel infers them from JSON; binary values that
+/// aren't UTF-8 are read as base64. With `max_rows`, reading stops after that many rows.
+///
+/// Dictionary-encoded fields are supported, as are record batches compressed with LZ4 or zstd.
+///
+/// # Example
+///
+/// ```no_run
+/// let stream = std::fs::read("events.arrows").unwrap();
+/// let records = drivel::read_arrow(&stream, None).unwrap();
+/// ```
+pub fn read_arrow(input: &[u8], max_rows: Option<usize>) -> Result<Vec<Value>, DrivelError> {
+    if input.starts_with(FILE_MAGIC) {
+        let file = FileReader::try_new(Cursor::new(input), None).map_err(invalid)?;
+        read_batches(file, max_rows)
+    } else {
+        let stream = StreamReader::try_new(input, None).map_err(invalid)?;
+        read_batches(stream, max_rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::types::Int8Type;
+    use arrow_array::{ArrayRef, DictionaryArray, Int32Array, RecordBatch};
+    use arrow_ipc::writer::{FileWriter, IpcWriteOptions, StreamWriter};
+    use arrow_ipc::CompressionType;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_record_batches_of_streams_and_files() {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "kind",
+                Arc::new(DictionaryArray::<Int8Type>::from_iter(["a", "b", "b"])),
+            ),
+        ])
+        .unwrap();
+        // batches of one dictionary, as files have to be
+        let batches = [batch.slice(0, 2), batch.slice(2, 1)];
+        let schema = batches[0].schema();
+        let records = [
+            json!({"id": 1, "kind": "a"}),
+            json!({"id": 2, "kind": "b"}),
+            json!({"id": 3, "kind": "b"}),
+        ];
+        for compression in [
+            None,
+            Some(CompressionType::LZ4_FRAME),
+            Some(CompressionType::ZSTD),
+        ] {
+            let options = IpcWriteOptions::default()
+                .try_with_compression(compression)
+                .unwrap();
+            let mut stream = vec![];
+            let mut writer =
+                StreamWriter::try_new_with_options(&mut stream, &schema, options.clone()).unwrap();
+            batches
+                .iter()
+                .for_each(|batch| writer.write(batch).unwrap());
+            writer.finish().unwrap();
+            drop(writer);
+            let mut file = vec![];
+            let mut writer = FileWriter::try_new_with_options(&mut file, &schema, options).unwrap();
+            batches
+                .iter()
+                .for_each(|batch| writer.write(batch).unwrap());
+            writer.finish().unwrap();
+            drop(writer);
+
+            for input in [&stream, &file] {
+                assert_eq!(read_arrow(input, None).unwrap(), records);
+                assert_eq!(read_arrow(input, Some(1)).unwrap(), records[..1]);
+            }
+            assert!(read_arrow(&file[..file.len() - 1], None).is_err());
+        }
+        assert!(read_arrow(b"ARROW1 not quite arrow", None).is_err());
+    }
+}
@@ -0,0 +1,916 @@
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::codegen::{pascal_case, unique};
+use crate::infer::tuple_as_array;
+use crate::{ExtendedJsonType, NumberType, ParseSchemaError, SchemaState, StringType};
+
+/// How many levels deep a named type that refers to itself, such as the node of a tree, is
+/// expanded when parsed, after which it is read as null.
+const MAX_RECURSION_DEPTH: usize = 3;
+
+/// Whether a name can be the name of an Avro field or type, or an enum symbol: letters, digits
+/// and underscores, not starting with a digit.
+fn is_avro_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Turns a field name into the name of an Avro field, e.g. `first-name` into `first_name`.
+/// Names that would start with a digit are prefixed with `_`.
+fn avro_name(name: &str) -> String {
+    let mut avro: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !is_avro_name(&avro) {
+        avro.insert(0, '_');
+    }
+    avro
+}
+
+fn number_type(number: &NumberType) -> &'static str {
+    let (min, max) = match number {
+        NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } => return "double",
+        NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. } => (*min, *max),
+        NumberType::Multiple { min, max, .. } => (*min as i64, *max as i64),
+        NumberType::Enum { variants: values } | NumberType::Flags { values } => (
+            values.keys().next().copied().unwrap_or_default(),
+            values.keys().next_back().copied().unwrap_or_default(),
+        ),
+    };
+    if i32::try_from(min).is_ok() && i32::try_from(max).is_ok() {
+        "int"
+    } else {
+        "long"
+    }
+}
+
+fn timestamp() -> Value {
+    json!({"type": "long", "logicalType": "timestamp-millis"})
+}
+
+/// What tells the types of a union apart, as a union can't have two types of the same kind,
+/// such as two arrays, unless they are named types with different names.
+fn union_kind(avro: &Value) -> String {
+    match avro {
+        Value::Object(object) => match object["type"].as_str() {
+            Some("record" | "enum" | "fixed") => object["name"].to_string(),
+            _ => object["type"].to_string(),
+        },
+        avro => avro.to_string(),
+    }
+}
+
+/// The union of types, which are flattened if they are unions themselves, with `null` first,
+/// and without the later ones of types of the same kind as an earlier one.
+fn union(types: Vec<Value>) -> Value {
+    let mut variants = vec![];
+    let mut kinds = vec![];
+    for avro in types.into_iter().flat_map(|avro| match avro {
+        Value::Array(variants) => variants,
+        avro => vec![avro],
+    }) {
+        let kind = union_kind(&avro);
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+            variants.push(avro);
+        }
+    }
+    if let Some(null) = variants.iter().position(|avro| avro == "null") {
+        let null = variants.remove(null);
+        variants.insert(0, null);
+    }
+    match variants.len() {
+        1 => variants.pop().unwrap(),
+        _ => Value::Array(variants),
+    }
+}
+
+fn nullable(avro: Value) -> Value {
+    union(vec![json!("null"), avro])
+}
+
+/// A field of a record, whose default is null if it can be null, with the name of the field
+/// it is of if that isn't a valid Avro name.
+fn field(key: &str, name: String, avro: Value) -> Value {
+    let mut field = Map::new();
+    field.insert("name".to_string(), json!(name));
+    if name != key {
+        field.insert("x-drivel-name".to_string(), json!(key));
+    }
+    let can_be_null = avro == "null" || avro.get(0).is_some_and(|first| first == "null");
+    field.insert("type".to_string(), avro);
+    if can_be_null {
+        field.insert("default".to_string(), Value::Null);
+    }
+    Value::Object(field)
+}
+
+/// The named types of Avro output, whose names have to be unique across the whole schema.
+#[derive(Default)]
+struct Types {
+    names: Vec<String>,
+    /// The names of the recursive definitions whose records are being declared, with the
+    /// names of the records, by which the values of them within them refer to them.
+    recursive: Vec<(String, String)>,
+}
+
+impl Types {
+    /// Names a record or enum after the field it is the type of, prefixed with the name of the
+    /// type that has the field if another type has that name, and numbered if that one does too.
+    fn name(&mut self, name: &str, parent: &str) -> String {
+        let mut declared = pascal_case(name);
+        if self.names.contains(&declared) {
+            declared = format!("{}{}", parent, declared);
+        }
+        let base = declared.clone();
+        let mut n = 2;
+        while self.names.contains(&declared) {
+            declared = format!("{}{}", base, n);
+            n += 1;
+        }
+        self.names.push(declared.clone());
+        declared
+    }
+
+    fn record(&mut self, declared: String, fields: Vec<(&str, Value)>) -> Value {
+        let names = unique(fields.iter().map(|(key, _)| avro_name(key)));
+        let fields: Vec<Value> = fields
+            .into_iter()
+            .zip(names)
+            .map(|((key, avro), name)| field(key, name, avro))
+            .collect();
+        json!({"type": "record", "name": declared, "fields": fields})
+    }
+
+    /// The record of an object, or of a recursive definition of one, `recursive`, whose values
+    /// within it are of the record by name.
+    fn object(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> Value {
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            unreachable!("only objects are records")
+        };
+        let keys: Vec<(&String, &SchemaState, bool)> = required
+            .iter()
+            .map(|(key, field)| (key, field, false))
+            .chain(optional.iter().map(|(key, field)| (key, field, true)))
+            .collect();
+
+        // the name of the record is taken before those of the types of its fields
+        let declared = self.name(name, parent);
+        if let Some(definition) = recursive {
+            self.recursive
+                .push((definition.to_string(), declared.clone()));
+        }
+        let fields: Vec<(&str, Value)> = keys
+            .into_iter()
+            .map(|(key, field, optional)| {
+                let avro = self.avro_type(field, key, &declared);
+                (key.as_str(), if optional { nullable(avro) } else { avro })
+            })
+            .collect();
+        if recursive.is_some() {
+            self.recursive.pop();
+        }
+        self.record(declared, fields)
+    }
+
+    fn string_type(&mut self, string: &StringType, name: &str, parent: &str) -> Value {
+        match string {
+            StringType::UUID => json!({"type": "string", "logicalType": "uuid"}),
+            StringType::IsoDate => json!({"type": "int", "logicalType": "date"}),
+            StringType::DateTimeISO8601 | StringType::DateTimeRFC2822 => timestamp(),
+            StringType::Enum { variants }
+                if !variants.is_empty() && variants.iter().all(|v| is_avro_name(v)) =>
+            {
+                json!({"type": "enum", "name": self.name(name, parent), "symbols": variants})
+            }
+            _ => json!("string"),
+        }
+    }
+
+    /// The Avro type of a schema, where `name` is the name of the field it is of, after which
+    /// the records and enums it declares are named, and `parent` that of the type of the field.
+    fn avro_type(&mut self, schema: &SchemaState, name: &str, parent: &str) -> Value {
+        match schema {
+            SchemaState::Initial | SchemaState::Null => json!("null"),
+            SchemaState::Nullable(inner) => nullable(self.avro_type(inner, name, parent)),
+            SchemaState::Boolean => json!("boolean"),
+            SchemaState::Literal(values) => {
+                self.avro_type(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                self.object(schema, name, parent, Some(definition))
+            }
+            SchemaState::Recursive { schema, .. } => self.avro_type(schema, name, parent),
+            SchemaState::Reference(definition) => {
+                match self
+                    .recursive
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| name == definition)
+                {
+                    Some((_, declared)) => json!(declared),
+                    // only objects are records, which another recursive definition can't be
+                    None => json!("string"),
+                }
+            }
+            SchemaState::Number(number) => json!(number_type(number)),
+            SchemaState::String(string) => self.string_type(string, name, parent),
+            SchemaState::Array { schema, .. } => {
+                let items = self.avro_type(schema, &format!("{}_item", name), parent);
+                json!({"type": "array", "items": items})
+            }
+            SchemaState::Map { schema, .. } => {
+                let values = self.avro_type(schema, &format!("{}_value", name), parent);
+                json!({"type": "map", "values": values})
+            }
+            SchemaState::Object { .. } => self.object(schema, name, parent, None),
+            SchemaState::ExtendedJson(ExtendedJsonType::ObjectId) => json!("string"),
+            SchemaState::ExtendedJson(ExtendedJsonType::Date) => timestamp(),
+            SchemaState::ExtendedJson(ExtendedJsonType::Long { .. }) => json!("long"),
+            SchemaState::Coordinates(_) => json!({"type": "array", "items": "double"}),
+            // Avro has no tuples, so their elements are of the union of the types of positions
+            SchemaState::Tuple(positions) => {
+                self.avro_type(&tuple_as_array(positions.clone()), name, parent)
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                ..
+            } => {
+                let declared = self.name(name, parent);
+                self.record(
+                    declared,
+                    vec![
+                        (latitude_key, json!("double")),
+                        (longitude_key, json!("double")),
+                    ],
+                )
+            }
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => {
+                let declared = self.name(name, parent);
+                let variants = currencies.clone();
+                let currency =
+                    self.string_type(&StringType::Enum { variants }, currency_key, &declared);
+                self.record(
+                    declared,
+                    vec![
+                        (amount_key, json!(number_type(amount))),
+                        (currency_key, currency),
+                    ],
+                )
+            }
+            SchemaState::Union(variants) => union(
+                variants
+                    .iter()
+                    .map(|variant| self.avro_type(variant, name, parent))
+                    .collect(),
+            ),
+            SchemaState::Mixed(variants) => union(
+                variants
+                    .iter()
+                    .map(|(variant, _)| self.avro_type(variant, name, parent))
+                    .collect(),
+            ),
+            SchemaState::TaggedUnion { variants, .. } => union(
+                variants
+                    .iter()
+                    .map(|(tag, variant)| self.avro_type(variant, tag, name))
+                    .collect(),
+            ),
+            // Avro has no type for any value
+            SchemaState::Indefinite => json!("string"),
+        }
+    }
+}
+
+/// Renders a schema as an Apache Avro schema, such as that of the messages of a Kafka topic.
+/// The root is a record named `name`, of the fields of the records, or of those of the elements
+/// of an array at the root. Each nested object is a record named after its field, optional and
+/// nullable fields are unions with null that default to null, enums of strings that are valid
+/// symbols are enums, and UUIDs, dates and date-times have the `uuid`, `date` and
+/// `timestamp-millis` logical types. Values without a type of their own are strings.
+pub(crate) fn to_avro(schema: &SchemaState, name: &str) -> Value {
+    let records = match schema {
+        SchemaState::Array { schema, .. } => schema,
+        schema => schema,
+    };
+    Types::default().avro_type(records, name, "")
+}
+
+fn invalid(message: impl Into<String>) -> ParseSchemaError {
+    ParseSchemaError::InvalidAvroSchema(message.into())
+}
+
+/// The full name of a named type, with its namespace, such as `com.example.User`.
+fn full_name(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn primitive(name: &str) -> Option<SchemaState> {
+    Some(match name {
+        "null" => SchemaState::Null,
+        "boolean" => SchemaState::Boolean,
+        "int" => SchemaState::Number(NumberType::Integer {
+            min: i32::MIN.into(),
+            max: i32::MAX.into(),
+        }),
+        "long" => SchemaState::Number(NumberType::Integer {
+            min: i64::MIN,
+            max: i64::MAX,
+        }),
+        "float" | "double" => SchemaState::Number(NumberType::Float {
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            non_finite: Default::default(),
+        }),
+        "string" | "bytes" => SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: None,
+            max_length: None,
+        }),
+        _ => return None,
+    })
+}
+
+/// The schema of a type with a logical type, such as a `long` that is a `timestamp-millis`,
+/// which is `base` if the logical type is not one drivel knows.
+fn logical_type(object: &Map<String, Value>, base: SchemaState) -> SchemaState {
+    let logical_type = object.get("logicalType").and_then(Value::as_str);
+    match (logical_type, &base) {
+        (Some("uuid"), SchemaState::String(_)) => SchemaState::String(StringType::UUID),
+        (Some("date"), SchemaState::Number(_)) => SchemaState::String(StringType::IsoDate),
+        (
+            Some(
+                "timestamp-millis"
+                | "timestamp-micros"
+                | "timestamp-nanos"
+                | "local-timestamp-millis"
+                | "local-timestamp-micros"
+                | "local-timestamp-nanos",
+            ),
+            SchemaState::Number(_),
+        ) => SchemaState::String(StringType::DateTimeISO8601),
+        (Some("decimal"), SchemaState::String(_)) => {
+            let precision = object.get("precision").and_then(Value::as_i64).unwrap_or(1);
+            let scale = object.get("scale").and_then(Value::as_i64).unwrap_or(0);
+            let limit = 10f64.powi((precision - scale).clamp(0, 300) as i32);
+            SchemaState::Number(NumberType::Float {
+                min: -limit,
+                max: limit,
+                non_finite: Default::default(),
+            })
+        }
+        _ => base,
+    }
+}
+
+/// Parses an Avro schema, with the named types defined so far, to which later types can refer.
+#[derive(Default)]
+struct Parser<'a> {
+    /// The definition of each named type, and the namespace it was defined in, by full name.
+    named: HashMap<String, (&'a Map<String, Value>, Option<String>)>,
+    /// How many times each named type is being expanded, by full name.
+    expanding: HashMap<String, usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(
+        &mut self,
+        avro: &'a Value,
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        match avro {
+            Value::String(name) => self.named_or_primitive(name, namespace),
+            Value::Array(variants) => self.union(variants, namespace),
+            Value::Object(object) => self.complex(object, namespace),
+            avro => Err(invalid(format!(
+                "a type must be a name, an object or a union, not {}",
+                avro
+            ))),
+        }
+    }
+
+    fn named_or_primitive(
+        &mut self,
+        name: &str,
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        if let Some(primitive) = primitive(name) {
+            return Ok(primitive);
+        }
+        let (definition, namespace) = self
+            .named
+            .get(&full_name(name, namespace))
+            .or_else(|| self.named.get(name))
+            .cloned()
+            .ok_or_else(|| invalid(format!("unknown type '{}'", name)))?;
+        self.named_type(definition, namespace.as_deref())
+    }
+
+    fn complex(
+        &mut self,
+        object: &'a Map<String, Value>,
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        let avro_type = object
+            .get("type")
+            .ok_or_else(|| invalid("a type object must have a 'type'"))?;
+        let Value::String(type_name) = avro_type else {
+            return self.parse(avro_type, namespace);
+        };
+        match type_name.as_str() {
+            "record" | "error" | "enum" | "fixed" => self.named_type(object, namespace),
+            "array" => {
+                let items = object
+                    .get("items")
+                    .ok_or_else(|| invalid("an array must have 'items'"))?;
+                Ok(SchemaState::Array {
+                    min_length: 0,
+                    max_length: 16,
+                    lengths: None,
+                    unique_items: false,
+                    schema: Box::new(self.parse(items, namespace)?),
+                })
+            }
+            "map" => {
+                let values = object
+                    .get("values")
+                    .ok_or_else(|| invalid("a map must have 'values'"))?;
+                Ok(SchemaState::Map {
+                    min_length: 0,
+                    max_length: 16,
+                    keys: StringType::Unknown {
+                        strings_seen: vec![],
+                        chars_seen: vec![],
+                        min_length: None,
+                        max_length: None,
+                    },
+                    schema: Box::new(self.parse(values, namespace)?),
+                })
+            }
+            name => {
+                let base = self.named_or_primitive(name, namespace)?;
+                Ok(logical_type(object, base))
+            }
+        }
+    }
+
+    fn named_type(
+        &mut self,
+        object: &'a Map<String, Value>,
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        let name = object
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("a record, enum or fixed type must have a 'name'"))?;
+        let full_name = full_name(
+            name,
+            object
+                .get("namespace")
+                .and_then(Value::as_str)
+                .or(namespace),
+        );
+        self.named
+            .insert(full_name.clone(), (object, namespace.map(str::to_string)));
+
+        let depth = self.expanding.entry(full_name.clone()).or_default();
+        if *depth == MAX_RECURSION_DEPTH {
+            return Ok(SchemaState::Null);
+        }
+        *depth += 1;
+        // the types in a record are in its namespace
+        let own_namespace = full_name.rsplit_once('.').map(|(namespace, _)| namespace);
+        let parsed = match object["type"].as_str() {
+            Some("enum") => parse_enum(object),
+            Some("fixed") => parse_fixed(object),
+            _ => self.record(object, own_namespace),
+        };
+        *self.expanding.get_mut(&full_name).unwrap() -= 1;
+        parsed
+    }
+
+    fn record(
+        &mut self,
+        object: &'a Map<String, Value>,
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        let fields = object
+            .get("fields")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("a record must have an array of 'fields'"))?;
+        let mut required = IndexMap::new();
+        for field in fields {
+            let name = field
+                .get("x-drivel-name")
+                .or_else(|| field.get("name"))
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("a field must have a 'name'"))?;
+            let avro_type = field
+                .get("type")
+                .ok_or_else(|| invalid(format!("field '{}' must have a 'type'", name)))?;
+            required.insert(name.to_string(), self.parse(avro_type, namespace)?);
+        }
+        Ok(SchemaState::Object {
+            required,
+            optional: IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        })
+    }
+
+    fn union(
+        &mut self,
+        variants: &'a [Value],
+        namespace: Option<&str>,
+    ) -> Result<SchemaState, ParseSchemaError> {
+        let mut nullable = false;
+        let mut schemas = vec![];
+        for variant in variants {
+            if variant.is_array() {
+                return Err(invalid("a union can't contain another union"));
+            }
+            match self.parse(variant, namespace)? {
+                SchemaState::Null => nullable = true,
+                schema => schemas.push(schema),
+            }
+        }
+        let schema = match schemas.len() {
+            0 => return Ok(SchemaState::Null),
+            1 => schemas.pop().unwrap(),
+            _ => SchemaState::Union(schemas),
+        };
+        Ok(if nullable {
+            SchemaState::Nullable(Box::new(schema))
+        } else {
+            schema
+        })
+    }
+}
+
+fn parse_enum(object: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
+    let symbols = object
+        .get("symbols")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("an enum must have an array of 'symbols'"))?;
+    let variants = symbols
+        .iter()
+        .map(|symbol| {
+            symbol
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid("the symbols of an enum must be strings"))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(SchemaState::String(StringType::Enum { variants }))
+}
+
+fn parse_fixed(object: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
+    let size = object
+        .get("size")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid("a fixed type must have a 'size'"))? as usize;
+    let fixed = SchemaState::String(StringType::Unknown {
+        strings_seen: vec![],
+        chars_seen: vec![],
+        min_length: Some(size),
+        max_length: Some(size),
+    });
+    Ok(logical_type(object, fixed))
+}
+
+/// Parses an Apache Avro schema into the schema it describes, such as to produce data from it,
+/// as the JSON a record would be written as. Records are objects whose fields are all required,
+/// as fields are never left out of Avro records, and unions with null are nullable. The
+/// `uuid`, `date` and timestamp logical types are read as strings in those formats. Named
+/// types can be referred to by name after they are defined, and those that refer to
+/// themselves are expanded a few levels deep.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{parse_avro_schema, SchemaState, StringType};
+/// use serde_json::json;
+///
+/// let avro = json!({
+///     "type": "record",
+///     "name": "User",
+///     "fields": [
+///         {"name": "id", "type": {"type": "string", "logicalType": "uuid"}},
+///         {"name": "email", "type": ["null", "string"], "default": null},
+///     ],
+/// });
+/// let SchemaState::Object { required, .. } = parse_avro_schema(&avro).unwrap() else {
+///     panic!("records are objects");
+/// };
+/// assert_eq!(required["id"], SchemaState::String(StringType::UUID));
+/// assert!(matches!(required["email"], SchemaState::Nullable(_)));
+/// ```
+pub fn parse_avro_schema(avro: &Value) -> Result<SchemaState, ParseSchemaError> {
+    Parser::default().parse(avro, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, EnumInference, InferenceOptions};
+    use indexmap::IndexMap;
+
+    #[test]
+    fn describes_records_as_avro_records() {
+        let input = json!([
+            {
+                "id": "550e8400-e29b-41d4-a716-446655440000",
+                "status": "active",
+                "joined": "2023-12-25",
+                "seen-at": "2023-12-25T10:30:00Z",
+                "score": 12,
+                "address": {"city": "Delft", "zip": null},
+                "tags": ["a"],
+            },
+            {
+                "id": "6ba7b810-9dad-11d1-80b4-00c04fd430c8",
+                "status": "active",
+                "joined": "2024-01-02",
+                "seen-at": "2024-01-02T08:00:00Z",
+                "score": 3000000000i64,
+                "address": {"city": "Leiden", "zip": "2311"},
+                "tags": [],
+                "nickname": "x",
+            },
+            {
+                "id": "7ba7b810-9dad-11d1-80b4-00c04fd430c8",
+                "status": "banned",
+                "joined": "2024-01-03",
+                "seen-at": "2024-01-03T08:00:00Z",
+                "score": 1,
+                "address": {"city": "Gouda", "zip": "2611"},
+                "tags": ["b", "c"],
+            },
+        ]);
+        let options = InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.7,
+                max_values: None,
+                min_sample_size: 3,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            ..Default::default()
+        };
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            to_avro(&schema, "User"),
+            json!({
+                "type": "record",
+                "name": "User",
+                "fields": [
+                    {"name": "id", "type": {"type": "string", "logicalType": "uuid"}},
+                    {"name": "status", "type": {
+                        "type": "enum",
+                        "name": "Status",
+                        "symbols": ["active", "banned"],
+                    }},
+                    {"name": "joined", "type": {"type": "int", "logicalType": "date"}},
+                    {
+                        "name": "seen_at",
+                        "x-drivel-name": "seen-at",
+                        "type": {"type": "long", "logicalType": "timestamp-millis"},
+                    },
+                    {"name": "score", "type": "long"},
+                    {"name": "address", "type": {
+                        "type": "record",
+                        "name": "Address",
+                        "fields": [
+                            {"name": "city", "type": "string"},
+                            {"name": "zip", "type": ["null", "string"], "default": null},
+                        ],
+                    }},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}},
+                    {"name": "nickname", "type": ["null", "string"], "default": null},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn names_types_uniquely_and_keeps_unions_valid() {
+        let input = json!({
+            "user": {"name": "a", "address": {"city": "x"}},
+            "company": {"address": {"street": "y"}},
+            "kind": "not a symbol!",
+        });
+        let schema = infer_schema(input, &InferenceOptions::default());
+
+        let avro = to_avro(&schema, "root");
+        let fields = avro["fields"].as_array().unwrap();
+        let names: Vec<&Value> = fields.iter().map(|field| &field["name"]).collect();
+        assert_eq!(names, ["user", "company", "kind"]);
+        assert_eq!(avro["name"], "Root");
+        assert_eq!(fields[0]["type"]["fields"][1]["type"]["name"], "Address");
+        assert_eq!(
+            fields[1]["type"]["fields"][0]["type"]["name"],
+            "CompanyAddress"
+        );
+        // the values of an enum have to be valid names to be its symbols
+        assert_eq!(fields[2]["type"], "string");
+
+        let array = |schema: SchemaState| SchemaState::Array {
+            min_length: 0,
+            max_length: 1,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        };
+        let union = SchemaState::Nullable(Box::new(SchemaState::Union(vec![
+            SchemaState::Boolean,
+            array(SchemaState::Boolean),
+            array(SchemaState::Null),
+        ])));
+        // a union has null first, and at most one array
+        assert_eq!(
+            to_avro(&union, "root"),
+            json!(["null", "boolean", {"type": "array", "items": "boolean"}])
+        );
+    }
+
+    #[test]
+    fn parses_named_types_and_references_to_them() {
+        let avro = json!({
+            "type": "record",
+            "name": "Order",
+            "namespace": "com.example",
+            "fields": [
+                {"name": "status", "type": {
+                    "type": "enum", "name": "Status", "symbols": ["OPEN", "CLOSED"],
+                }},
+                {"name": "previous_status", "type": ["null", "Status"]},
+                {"name": "placed", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+                {"name": "lines", "type": {"type": "array", "items": {
+                    "type": "record",
+                    "name": "Line",
+                    "fields": [
+                        {"name": "sku", "type": {"type": "fixed", "name": "Sku", "size": 8}},
+                        {"name": "price", "type": {
+                            "type": "bytes", "logicalType": "decimal", "precision": 6, "scale": 2,
+                        }},
+                    ],
+                }}},
+                {"name": "extra", "type": {"type": "map", "values": "com.example.Line"}},
+                {"name": "note", "type": ["null", "string", "long"]},
+            ],
+        });
+        let status = SchemaState::String(StringType::Enum {
+            variants: ["CLOSED".to_string(), "OPEN".to_string()].into(),
+        });
+
+        let SchemaState::Object { required, .. } = parse_avro_schema(&avro).unwrap() else {
+            panic!("records are objects");
+        };
+
+        assert_eq!(required["status"], status);
+        assert_eq!(
+            required["previous_status"],
+            SchemaState::Nullable(Box::new(status))
+        );
+        assert_eq!(
+            required["placed"],
+            SchemaState::String(StringType::DateTimeISO8601)
+        );
+        assert_eq!(required["day"], SchemaState::String(StringType::IsoDate));
+        let SchemaState::Array { schema: line, .. } = &required["lines"] else {
+            panic!("arrays are arrays");
+        };
+        let SchemaState::Map { schema: value, .. } = &required["extra"] else {
+            panic!("maps are maps");
+        };
+        assert_eq!(line, value);
+        let SchemaState::Object { required: line, .. } = line.as_ref() else {
+            panic!("records are objects");
+        };
+        assert!(matches!(
+            line["sku"],
+            SchemaState::String(StringType::Unknown {
+                min_length: Some(8),
+                max_length: Some(8),
+                ..
+            })
+        ));
+        assert!(matches!(
+            line["price"],
+            SchemaState::Number(NumberType::Float { max, .. }) if max == 10_000.0
+        ));
+        assert!(matches!(
+            &required["note"],
+            SchemaState::Nullable(inner) if matches!(inner.as_ref(), SchemaState::Union(v) if v.len() == 2)
+        ));
+    }
+
+    #[test]
+    fn expands_recursive_types_a_few_levels_deep() {
+        let avro = json!({
+            "type": "record",
+            "name": "Node",
+            "fields": [
+                {"name": "value", "type": "int"},
+                {"name": "next", "type": ["null", "Node"]},
+            ],
+        });
+
+        let mut schema = parse_avro_schema(&avro).unwrap();
+        let mut depth = 0;
+        while let SchemaState::Object { required, .. } = schema {
+            depth += 1;
+            schema = match &required["next"] {
+                SchemaState::Nullable(next) => next.as_ref().clone(),
+                next => next.clone(),
+            };
+        }
+        assert_eq!(depth, MAX_RECURSION_DEPTH);
+        assert_eq!(schema, SchemaState::Null);
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_records_by_name() {
+        let node = SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(SchemaState::Object {
+                required: IndexMap::from_iter([(
+                    "next".to_string(),
+                    SchemaState::Nullable(Box::new(SchemaState::Reference("node".to_string()))),
+                )]),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        };
+        assert_eq!(
+            to_avro(&node, "Node"),
+            json!({
+                "type": "record",
+                "name": "Node",
+                "fields": [{"name": "next", "type": ["null", "Node"], "default": null}],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        for avro in [
+            json!("User"),
+            json!({"type": "record", "name": "User"}),
+            json!({"type": "array"}),
+            json!(["null", ["string"]]),
+            json!(42),
+        ] {
+            assert!(
+                matches!(
+                    parse_avro_schema(&avro),
+                    Err(ParseSchemaError::InvalidAvroSchema(_))
+                ),
+                "{}",
+                avro
+            );
+        }
+    }
+
+    #[test]
+    fn described_schemas_parse_back_with_the_original_field_names() {
+        let input = json!({"first-name": "a", "id": 1, "tags": ["x"]});
+        let schema = infer_schema(input, &InferenceOptions::default());
+
+        let parsed = parse_avro_schema(&to_avro(&schema, "person")).unwrap();
+
+        assert_eq!(parsed.signature(), schema.signature());
+    }
+}
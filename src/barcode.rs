@@ -0,0 +1,150 @@
+use rand::Rng;
+
+use crate::StringType;
+
+/// The check digit of the first 12 digits of an EAN-13, whose digits are weighted 1 and 3 in
+/// turn, such that the weighted sum with the check digit is a multiple of 10.
+fn ean_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, digit)| u32::from(*digit) * if i % 2 == 1 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// The check character of the first 9 digits of an ISBN-10, whose digits are weighted 10 down
+/// to 2, such that the weighted sum with the check character is a multiple of 11. A check of 10
+/// is written as `X`.
+fn isbn10_check_character(digits: &[u8]) -> char {
+    let sum: u32 = digits
+        .iter()
+        .zip((2..=10).rev())
+        .map(|(digit, weight)| u32::from(*digit) * weight)
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        check => char::from(b'0' + check as u8),
+    }
+}
+
+/// The values of the digits of a string, if it is made of ASCII digits only.
+fn digits(s: &[u8]) -> Option<Vec<u8>> {
+    s.iter()
+        .map(|b| b.is_ascii_digit().then(|| b - b'0'))
+        .collect()
+}
+
+fn is_ean13(s: &str) -> bool {
+    s.len() == 13
+        && digits(s.as_bytes()).is_some_and(|digits| ean_check_digit(&digits[..12]) == digits[12])
+}
+
+fn is_isbn10(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 10
+        && digits(&bytes[..9])
+            .is_some_and(|digits| char::from(bytes[9]) == isbn10_check_character(&digits))
+}
+
+/// Recognises ISBN-10s, ISBN-13s (the EAN-13s of books, starting with 978 or 979) and other
+/// EAN-13 barcodes, without separators, by validating their check digits.
+pub(crate) fn infer_barcode(s: &str) -> Option<StringType> {
+    if is_isbn10(s) {
+        Some(StringType::Isbn10)
+    } else if is_ean13(s) && (s.starts_with("978") || s.starts_with("979")) {
+        Some(StringType::Isbn13)
+    } else if is_ean13(s) {
+        Some(StringType::Ean13)
+    } else {
+        None
+    }
+}
+
+fn random_digits<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Vec<u8> {
+    (0..n).map(|_| rng.gen_range(0..10)).collect()
+}
+
+fn with_ean_check_digit(mut digits: Vec<u8>) -> String {
+    digits.push(ean_check_digit(&digits));
+    digits.into_iter().map(|d| char::from(b'0' + d)).collect()
+}
+
+/// Produces a synthetic ISBN-10 with a valid check character.
+pub(crate) fn produce_isbn10<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let digits = random_digits(9, rng);
+    let check = isbn10_check_character(&digits);
+    digits
+        .into_iter()
+        .map(|d| char::from(b'0' + d))
+        .chain([check])
+        .collect()
+}
+
+/// Produces a synthetic ISBN-13, with the 978 prefix of books and a valid check digit.
+pub(crate) fn produce_isbn13<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut digits = vec![9, 7, 8];
+    digits.extend(random_digits(9, rng));
+    with_ean_check_digit(digits)
+}
+
+/// Produces a synthetic EAN-13 with a valid check digit, with a prefix other than those of
+/// books, so that it isn't taken for an ISBN.
+pub(crate) fn produce_ean13<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut digits = vec![rng.gen_range(0..9)];
+    digits.extend(random_digits(11, rng));
+    with_ean_check_digit(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn recognises_barcodes_by_their_check_digits() {
+        for (value, string_type) in [
+            ("0306406152", StringType::Isbn10),
+            ("080442957X", StringType::Isbn10),
+            ("9780306406157", StringType::Isbn13),
+            ("9791034304455", StringType::Isbn13),
+            ("4006381333931", StringType::Ean13),
+            ("0012345678905", StringType::Ean13),
+        ] {
+            assert_eq!(infer_barcode(value), Some(string_type), "{}", value);
+        }
+        for value in [
+            "0306406153",
+            "9780306406158",
+            "4006381333932",
+            "X306406152",
+            "978-0306406157",
+            "03064061é",
+            "123",
+        ] {
+            assert_eq!(infer_barcode(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn produced_values_pass_check_digits() {
+        for _ in 0..100 {
+            let isbn10 = produce_isbn10(&mut thread_rng());
+            assert_eq!(
+                infer_barcode(&isbn10),
+                Some(StringType::Isbn10),
+                "{}",
+                isbn10
+            );
+            let isbn13 = produce_isbn13(&mut thread_rng());
+            assert_eq!(
+                infer_barcode(&isbn13),
+                Some(StringType::Isbn13),
+                "{}",
+                isbn13
+            );
+            let ean13 = produce_ean13(&mut thread_rng());
+            assert_eq!(infer_barcode(&ean13), Some(StringType::Ean13), "{}", ean13);
+        }
+    }
+}
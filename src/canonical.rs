@@ -0,0 +1,149 @@
+use serde_json::Value;
+
+use crate::float_format::fixed_point;
+
+/// Writes a float the same way wherever drivel runs: in fixed-point notation with the fewest
+/// digits that read back as the same float, such as `0.0000001`, or for floats too large or
+/// small for that, in scientific notation with a lowercase `e`, such as `2.5e22`. Negative zero
+/// is written as `0.0`.
+fn canonical_float(value: f64) -> String {
+    if value == 0.0 {
+        return "0.0".to_string();
+    }
+    fixed_point(value).unwrap_or_else(|| format!("{:e}", value))
+}
+
+fn write_value(value: &Value, indent: Option<&str>, depth: usize, out: &mut String) {
+    let newline = |out: &mut String, depth: usize| {
+        if let Some(indent) = indent {
+            out.push('\n');
+            out.push_str(&indent.repeat(depth));
+        }
+    };
+    match value {
+        Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => out.push_str(&i.to_string()),
+            (_, Some(u), _) => out.push_str(&u.to_string()),
+            (_, _, Some(f)) => out.push_str(&canonical_float(f)),
+            _ => out.push_str(&n.to_string()),
+        },
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_value(item, indent, depth + 1, out);
+            }
+            newline(out, depth);
+            out.push(']');
+        }
+        Value::Object(fields) if fields.is_empty() => out.push_str("{}"),
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            out.push('{');
+            for (i, (key, field)) in fields.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push_str(if indent.is_some() { ": " } else { ":" });
+                write_value(field, indent, depth + 1, out);
+            }
+            newline(out, depth);
+            out.push('}');
+        }
+        // `null`, booleans and strings, where strings only escape `"`, `\` and control
+        // characters, the latter as `\n`, `\t` and the like or `\u00XX`
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// Serializes a JSON value canonically, so that the same value is always written as the same
+/// bytes, whichever platform or version of its dependencies drivel was built with, such as for
+/// golden fixtures that are checked in. Canonical JSON here is:
+///
+/// * the fields of objects sorted by their names, compared by Unicode code points
+/// * integers written in full, without a decimal point or exponent
+/// * floats written as by [`FixedPointFormatter`](crate::FixedPointFormatter), with a decimal
+///   point, and in scientific notation such as `1e-20` only if fixed-point notation would need
+///   more than about 20 digits; negative zero is `0.0`
+/// * strings written as UTF-8, escaping only `"`, `\` and control characters
+/// * minified if `indent` is `None`, and otherwise pretty-printed with that many spaces per
+///   level, with empty arrays and objects as `[]` and `{}`
+/// * ended by a single line feed, with line feeds (never carriage returns) between lines
+///
+/// # Example
+///
+/// ```
+/// use drivel::to_canonical_json;
+/// use serde_json::json;
+///
+/// let value = json!({"name": "drivel", "version": 1e-7, "authors": []});
+/// assert_eq!(
+///     to_canonical_json(&value, None),
+///     "{\"authors\":[],\"name\":\"drivel\",\"version\":0.0000001}\n"
+/// );
+/// ```
+pub fn to_canonical_json(value: &Value, indent: Option<usize>) -> String {
+    let indent = indent.map(|indent| " ".repeat(indent));
+    let mut out = String::new();
+    write_value(value, indent.as_deref(), 0, &mut out);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matches_the_golden_output() {
+        let value = json!({
+            "zeta": 1,
+            "alpha": {"b": [1.5, -0.0, 1e-20, 2.5e22, 120000000000000000.0], "a": {}},
+            "Éclair": "line\nbreak \"quoted\" \u{1}",
+            "empty": [],
+            "big": u64::MAX,
+            "flag": true,
+            "none": null
+        });
+        let golden = concat!(
+            "{\n",
+            "  \"alpha\": {\n",
+            "    \"a\": {},\n",
+            "    \"b\": [\n",
+            "      1.5,\n",
+            "      0.0,\n",
+            "      1e-20,\n",
+            "      2.5e22,\n",
+            "      120000000000000000.0\n",
+            "    ]\n",
+            "  },\n",
+            "  \"big\": 18446744073709551615,\n",
+            "  \"empty\": [],\n",
+            "  \"flag\": true,\n",
+            "  \"none\": null,\n",
+            "  \"zeta\": 1,\n",
+            "  \"Éclair\": \"line\\nbreak \\\"quoted\\\" \\u0001\"\n",
+            "}\n"
+        );
+        assert_eq!(to_canonical_json(&value, Some(2)), golden);
+    }
+
+    #[test]
+    fn minified_output_reads_back_as_the_same_value() {
+        let value = json!([{"b": 0.30000000000000004, "a": -3}, "x", [[]]]);
+        let canonical = to_canonical_json(&value, None);
+        assert_eq!(
+            canonical,
+            "[{\"a\":-3,\"b\":0.30000000000000004},\"x\",[[]]]\n"
+        );
+        assert_eq!(serde_json::from_str::<Value>(&canonical).unwrap(), value);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::{SchemaState, StringType};
+
+/// Turns a field name into the name of a type, e.g. `shipping_address` or `shippingAddress` into
+/// `ShippingAddress`. Names that would start with a digit are prefixed with `T`.
+pub(crate) fn pascal_case(name: &str) -> String {
+    let mut pascal = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper {
+                pascal.push(c.to_ascii_uppercase());
+            } else {
+                pascal.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if pascal.is_empty() || pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        pascal.insert(0, 'T');
+    }
+    pascal
+}
+
+/// Turns a field name into snake case, e.g. `userId` or `user-id` into `user_id`. Names that
+/// would start with a digit are prefixed with `field_`.
+pub(crate) fn snake_case_words(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 && !snake.is_empty() && !snake.ends_with('_') {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            // a boundary in `userId`, and at the end of an acronym in `HTTPStatus`
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    let mut snake = snake.trim_end_matches('_').to_string();
+    if snake.is_empty() || snake.starts_with(|c: char| c.is_ascii_digit()) {
+        snake.insert_str(0, "field_");
+    }
+    snake
+}
+
+/// Makes each name unique by numbering the ones that were given before, as field and variant
+/// names that differ only in case and punctuation, like `userId` and `user_id`, become one.
+pub(crate) fn unique(names: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen: Vec<String> = vec![];
+    for name in names {
+        let mut unique = name.clone();
+        let mut n = 2;
+        while seen.contains(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+        seen.push(unique);
+    }
+    seen
+}
+
+/// Strings of no particular format or length.
+pub(crate) fn any_string_type() -> StringType {
+    StringType::Unknown {
+        strings_seen: vec![],
+        chars_seen: vec![],
+        min_length: None,
+        max_length: None,
+    }
+}
+
+/// A string of no particular format, such as the value of an extended JSON `{"$oid": …}`.
+pub(crate) fn any_string() -> SchemaState {
+    SchemaState::String(any_string_type())
+}
+
+/// An object of the given fields, all of them required.
+pub(crate) fn object(fields: Vec<(&str, SchemaState)>) -> SchemaState {
+    SchemaState::Object {
+        required: fields
+            .into_iter()
+            .map(|(key, schema)| (key.to_string(), schema))
+            .collect(),
+        optional: Default::default(),
+        access: Default::default(),
+        annotations: Default::default(),
+    }
+}
+
+/// The named declarations of generated code, such as the interfaces of TypeScript or the
+/// structs and enums of Rust, of whatever a format declares, `D`.
+///
+/// A declaration is started before the types of its fields are rendered, which may declare
+/// more types, and finished once they are, so that declarations can be listed both in the
+/// order in which they were reached, outermost first, and in the order in which they were
+/// finished, in which each comes after those that it refers to.
+pub(crate) struct Declarations<D> {
+    /// The name and declaration of each type, in the order in which they were first reached,
+    /// or `None` for one that is still being rendered.
+    declared: Vec<Option<(String, D)>>,
+    /// The indices of the declarations in `declared`, in the order in which they were finished.
+    finished: Vec<usize>,
+    /// Names that the generated code uses, which its own types mustn't shadow.
+    reserved: &'static [&'static str],
+    /// The names given to declarations that are still being rendered, by their indices, for
+    /// recursive types whose fields refer to them by name.
+    named: Vec<(usize, String)>,
+    /// The names of the recursive definitions that are being declared, with the names of their
+    /// declarations, which the values of them within them refer to.
+    recursive: Vec<(String, String)>,
+}
+
+impl<D: PartialEq> Declarations<D> {
+    pub(crate) fn new(reserved: &'static [&'static str]) -> Self {
+        Declarations {
+            declared: vec![],
+            finished: vec![],
+            reserved,
+            named: vec![],
+            recursive: vec![],
+        }
+    }
+
+    fn is_taken(&self, name: &str) -> bool {
+        self.reserved.contains(&name)
+            || self
+                .declared
+                .iter()
+                .flatten()
+                .any(|(taken, _)| taken == name)
+            || self.named.iter().any(|(_, taken)| taken == name)
+    }
+
+    /// A name that no other type has, after `name`, prefixed with the name of the type that
+    /// holds it, `parent`, or numbered.
+    fn free_name(&self, name: &str, parent: &str) -> String {
+        let mut declared = name.to_string();
+        if self.is_taken(&declared) {
+            declared = format!("{}{}", parent, name);
+        }
+        let base = declared.clone();
+        let mut n = 2;
+        while self.is_taken(&declared) {
+            declared = format!("{}{}", base, n);
+            n += 1;
+        }
+        declared
+    }
+
+    /// Starts a declaration, whose fields or variants may reach other types, to be finished
+    /// with [`Declarations::finish`] once they are rendered.
+    pub(crate) fn start(&mut self) -> usize {
+        self.declared.push(None);
+        self.declared.len() - 1
+    }
+
+    /// Names a declaration that has been started, before it is finished, so that the values of
+    /// the recursive definition it declares, `definition`, can refer to it from within it, by
+    /// [`Declarations::recursive`]. It is named as [`Declarations::finish`] would, and keeps its
+    /// name, rather than sharing the declaration of a type that is declared the same.
+    pub(crate) fn name(
+        &mut self,
+        index: usize,
+        definition: &str,
+        name: &str,
+        parent: &str,
+    ) -> String {
+        let named = self.free_name(name, parent);
+        self.named.push((index, named.clone()));
+        self.recursive.push((definition.to_string(), named.clone()));
+        named
+    }
+
+    /// The name of the declaration of a recursive definition that is being declared, the
+    /// innermost if it is within itself, or `None` if it isn't being declared.
+    pub(crate) fn recursive(&self, definition: &str) -> Option<&str> {
+        self.recursive
+            .iter()
+            .rev()
+            .find(|(name, _)| name == definition)
+            .map(|(_, declared)| declared.as_str())
+    }
+
+    /// Declares a type, named `name` unless another type has that name already, in which case
+    /// it is prefixed with the name of the type that holds it, `parent`, or numbered. Types that
+    /// are declared the same share one declaration, whose name is returned.
+    pub(crate) fn finish(
+        &mut self,
+        index: usize,
+        declaration: D,
+        name: &str,
+        parent: &str,
+    ) -> String {
+        if let Some(position) = self.named.iter().position(|(named, _)| *named == index) {
+            let (_, named) = self.named.remove(position);
+            self.recursive.retain(|(_, declared)| *declared != named);
+            self.declared[index] = Some((named.clone(), declaration));
+            self.finished.push(index);
+            return named;
+        }
+        let existing = self
+            .declared
+            .iter()
+            .flatten()
+            .find(|(_, other)| *other == declaration)
+            .map(|(name, _)| name.clone());
+        if let Some(existing) = existing {
+            // the fields of a copy can only refer to types declared before it
+            self.declared.truncate(index);
+            self.finished.retain(|&finished| finished < index);
+            return existing;
+        }
+        let declared = self.free_name(name, parent);
+        self.declared[index] = Some((declared.clone(), declaration));
+        self.finished.push(index);
+        declared
+    }
+
+    /// The declarations in the order in which they were first reached, outermost first.
+    pub(crate) fn into_reached(self) -> impl Iterator<Item = (String, D)> {
+        self.declared.into_iter().flatten()
+    }
+
+    /// The declarations in the order in which they were finished, each after the declarations
+    /// it refers to, for languages in which a type must be defined before it is used.
+    pub(crate) fn into_finished(mut self) -> impl Iterator<Item = (String, D)> {
+        let finished = std::mem::take(&mut self.finished);
+        finished
+            .into_iter()
+            .filter_map(move |index| self.declared[index].take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_types_and_fields() {
+        assert_eq!(pascal_case("shipping_address"), "ShippingAddress");
+        assert_eq!(pascal_case("1st"), "T1st");
+        assert_eq!(snake_case_words("HTTPStatus"), "http_status");
+        assert_eq!(snake_case_words("userId"), "user_id");
+        assert_eq!(
+            unique(["id".to_string(), "id".to_string(), "id2".to_string()]),
+            ["id", "id2", "id22"]
+        );
+    }
+
+    #[test]
+    fn lists_declarations_as_reached_and_as_finished() {
+        let mut declarations: Declarations<&str> = Declarations::new(&["Box"]);
+        let order = declarations.start();
+        let address = declarations.start();
+        assert_eq!(
+            declarations.finish(address, "city", "Address", "Order"),
+            "Address"
+        );
+        let billing = declarations.start();
+        // the same declaration is shared
+        assert_eq!(
+            declarations.finish(billing, "city", "Billing", "Order"),
+            "Address"
+        );
+        let other = declarations.start();
+        assert_eq!(
+            declarations.finish(other, "zip", "Address", "Order"),
+            "OrderAddress"
+        );
+        let boxed = declarations.start();
+        assert_eq!(declarations.finish(boxed, "size", "Box", ""), "Box2");
+        assert_eq!(declarations.finish(order, "address", "Order", ""), "Order");
+
+        let names = |declarations: Vec<(String, &str)>| {
+            declarations
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        let mut reached: Declarations<&str> = Declarations::new(&[]);
+        let outer = reached.start();
+        let inner = reached.start();
+        reached.finish(inner, "inner", "Inner", "Outer");
+        reached.finish(outer, "outer", "Outer", "");
+        assert_eq!(names(reached.into_reached().collect()), ["Outer", "Inner"]);
+        assert_eq!(
+            names(declarations.into_finished().collect()),
+            ["Address", "OrderAddress", "Box2", "Order"]
+        );
+    }
+
+    #[test]
+    fn names_recursive_declarations_before_they_are_finished() {
+        let mut declarations: Declarations<&str> = Declarations::new(&[]);
+        let shared = declarations.start();
+        declarations.finish(shared, "children", "Leaf", "");
+        let node = declarations.start();
+        assert_eq!(declarations.name(node, "tree", "Node", ""), "Node");
+        assert_eq!(declarations.recursive("tree"), Some("Node"));
+        let other = declarations.start();
+        // the name is taken while the recursive type is rendered
+        assert_eq!(
+            declarations.finish(other, "value", "Node", "Tree"),
+            "TreeNode"
+        );
+        // and kept, although another type is declared the same
+        assert_eq!(declarations.finish(node, "children", "Other", ""), "Node");
+        // which is no longer referred to once it is finished
+        assert_eq!(declarations.recursive("tree"), None);
+        let names: Vec<String> = declarations.into_reached().map(|(name, _)| name).collect();
+        assert_eq!(names, ["Leaf", "Node", "TreeNode"]);
+    }
+}
@@ -0,0 +1,571 @@
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Date32Type, Date64Type, Decimal128Type, Decimal256Type, Decimal32Type, Decimal64Type,
+    DurationMicrosecondType, DurationMillisecondType, DurationNanosecondType, DurationSecondType,
+    Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrayRef, ArrowPrimitiveType, OffsetSizeTrait, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Fields, TimeUnit};
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Timelike};
+use serde_json::{Map, Number, Value};
+
+use crate::DrivelError;
+
+/// An error in columnar input, such as a Parquet file.
+pub(crate) fn invalid(message: impl std::fmt::Display) -> DrivelError {
+    DrivelError::Parse(message.to_string())
+}
+
+fn per_second(unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+/// A number, or `null` for one that isn't finite, which JSON has no number for.
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+/// A decimal of `scale` digits after the point, from its unscaled integer: an integer if it has
+/// none, and otherwise a float, which is as close as JSON numbers get.
+fn decimal(unscaled: i128, scale: i32) -> Value {
+    if scale <= 0 {
+        if let Some(n) = 10i128
+            .checked_pow(scale.unsigned_abs())
+            .and_then(|factor| unscaled.checked_mul(factor))
+        {
+            if let Ok(n) = i64::try_from(n) {
+                return Value::from(n);
+            }
+            if let Ok(n) = u64::try_from(n) {
+                return Value::from(n);
+            }
+        }
+    }
+    scientific(unscaled, scale)
+}
+
+/// A float of `unscaled` times ten to the power of `-scale`.
+fn scientific(unscaled: impl std::fmt::Display, scale: i32) -> Value {
+    float(
+        format!("{}e{}", unscaled, -scale)
+            .parse()
+            .unwrap_or(f64::NAN),
+    )
+}
+
+/// A date, as `YYYY-MM-DD`, from the number of days since the Unix epoch.
+fn date(days: i64) -> Value {
+    NaiveDate::from_ymd_opt(1970, 1, 1)
+        .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::try_days(days)?))
+        .map_or(Value::Null, |date| {
+            Value::String(date.format("%Y-%m-%d").to_string())
+        })
+}
+
+/// A timestamp, as an RFC 3339 date-time in UTC, from the time since the Unix epoch. Timestamps
+/// that aren't of any particular time zone are read as UTC too.
+fn timestamp(since_epoch: i64, unit: TimeUnit) -> Value {
+    let per_second = per_second(unit);
+    let nanos = since_epoch.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    DateTime::from_timestamp(since_epoch.div_euclid(per_second), nanos as u32)
+        .map_or(Value::Null, |timestamp| {
+            Value::String(timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        })
+}
+
+/// A time of day, as `HH:MM:SS` with any fraction of a second, from the time since midnight.
+fn time(since_midnight: i64, unit: TimeUnit) -> Value {
+    let per_second = per_second(unit);
+    let nanos = since_midnight.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    u32::try_from(since_midnight.div_euclid(per_second))
+        .ok()
+        .and_then(|seconds| {
+            chrono::NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos as u32)
+        })
+        .map_or(Value::Null, |time| {
+            Value::String(match time.nanosecond() {
+                0 => time.format("%H:%M:%S").to_string(),
+                _ => time.format("%H:%M:%S%.f").to_string(),
+            })
+        })
+}
+
+/// Binary data, as a string if it is UTF-8, and otherwise as base64.
+fn binary(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Value::String(s.to_string()),
+        Err(_) => Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+    }
+}
+
+/// A UUID, in its hyphenated form, from its 16 bytes.
+fn uuid(bytes: &[u8]) -> Value {
+    match uuid::Uuid::from_slice(bytes) {
+        Ok(uuid) => Value::String(uuid.hyphenated().to_string()),
+        Err(_) => binary(bytes),
+    }
+}
+
+/// A string of a field, parsed if its extension type says it is JSON.
+fn string(field: &Field, s: &str) -> Value {
+    match field.extension_type_name() {
+        Some("arrow.json") => serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.into())),
+        _ => Value::String(s.to_string()),
+    }
+}
+
+/// Binary data of a field, as a UUID or JSON if its extension type says it is one.
+fn bytes(field: &Field, bytes: &[u8]) -> Value {
+    match field.extension_type_name() {
+        Some("arrow.uuid") => uuid(bytes),
+        Some("arrow.json") => serde_json::from_slice(bytes).unwrap_or_else(|_| binary(bytes)),
+        _ => binary(bytes),
+    }
+}
+
+/// The values of a column of strings.
+fn strings<'a>(field: &Field, strings: impl IntoIterator<Item = Option<&'a str>>) -> Vec<Value> {
+    strings
+        .into_iter()
+        .map(|s| string(field, s.unwrap_or_default()))
+        .collect()
+}
+
+/// The values of a column of binary data.
+fn binaries<'a>(field: &Field, binaries: impl IntoIterator<Item = Option<&'a [u8]>>) -> Vec<Value> {
+    binaries
+        .into_iter()
+        .map(|b| bytes(field, b.unwrap_or_default()))
+        .collect()
+}
+
+/// The values of a column of primitives, each converted by `convert`.
+fn primitive<T: ArrowPrimitiveType>(
+    array: &dyn Array,
+    convert: impl Fn(T::Native) -> Value,
+) -> Vec<Value> {
+    array
+        .as_primitive::<T>()
+        .values()
+        .iter()
+        .map(|&n| convert(n))
+        .collect()
+}
+
+/// The values of a column of lists, whose elements are the ranges of `elements` between each
+/// start and end.
+fn lists(
+    child: &Field,
+    elements: &dyn Array,
+    ranges: impl Iterator<Item = (usize, usize)>,
+) -> Result<Vec<Value>, DrivelError> {
+    let elements = values(child, elements)?;
+    ranges
+        .map(|(start, end)| {
+            elements
+                .get(start..end)
+                .map(|elements| Value::Array(elements.to_vec()))
+                .ok_or_else(|| invalid("an offset is outside its list"))
+        })
+        .collect()
+}
+
+fn offsets<O: OffsetSizeTrait>(offsets: &[O]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    offsets
+        .windows(2)
+        .map(|range| (range[0].as_usize(), range[1].as_usize()))
+}
+
+/// The values of a column of `field`, with structs as objects, lists as arrays and maps as
+/// objects of their keys.
+fn values(field: &Field, array: &dyn Array) -> Result<Vec<Value>, DrivelError> {
+    use TimeUnit::*;
+
+    let mut values = match array.data_type() {
+        DataType::Null => vec![Value::Null; array.len()],
+        DataType::Boolean => array
+            .as_boolean()
+            .values()
+            .iter()
+            .map(Value::from)
+            .collect(),
+        DataType::Int8 => primitive::<Int8Type>(array, Value::from),
+        DataType::Int16 => primitive::<Int16Type>(array, Value::from),
+        DataType::Int32 => primitive::<Int32Type>(array, Value::from),
+        DataType::Int64 => primitive::<Int64Type>(array, Value::from),
+        DataType::UInt8 => primitive::<UInt8Type>(array, Value::from),
+        DataType::UInt16 => primitive::<UInt16Type>(array, Value::from),
+        DataType::UInt32 => primitive::<UInt32Type>(array, Value::from),
+        DataType::UInt64 => primitive::<UInt64Type>(array, Value::from),
+        DataType::Float16 => primitive::<Float16Type>(array, |f| float(f.to_f64())),
+        DataType::Float32 => primitive::<Float32Type>(array, |f| float(f64::from(f))),
+        DataType::Float64 => primitive::<Float64Type>(array, float),
+        &DataType::Decimal32(_, scale) => {
+            primitive::<Decimal32Type>(array, |n| decimal(i128::from(n), i32::from(scale)))
+        }
+        &DataType::Decimal64(_, scale) => {
+            primitive::<Decimal64Type>(array, |n| decimal(i128::from(n), i32::from(scale)))
+        }
+        &DataType::Decimal128(_, scale) => {
+            primitive::<Decimal128Type>(array, |n| decimal(n, i32::from(scale)))
+        }
+        &DataType::Decimal256(_, scale) => primitive::<Decimal256Type>(array, |n| {
+            let scale = i32::from(scale);
+            n.to_i128()
+                .map_or_else(|| scientific(n, scale), |n| decimal(n, scale))
+        }),
+        DataType::Date32 => primitive::<Date32Type>(array, |days| date(i64::from(days))),
+        DataType::Date64 => {
+            primitive::<Date64Type>(array, |millis| date(millis.div_euclid(86_400_000)))
+        }
+        DataType::Time32(Second) => {
+            primitive::<Time32SecondType>(array, |n| time(i64::from(n), Second))
+        }
+        DataType::Time32(_) => {
+            primitive::<Time32MillisecondType>(array, |n| time(i64::from(n), Millisecond))
+        }
+        DataType::Time64(Microsecond) => {
+            primitive::<Time64MicrosecondType>(array, |n| time(n, Microsecond))
+        }
+        DataType::Time64(_) => primitive::<Time64NanosecondType>(array, |n| time(n, Nanosecond)),
+        DataType::Timestamp(Second, _) => {
+            primitive::<TimestampSecondType>(array, |n| timestamp(n, Second))
+        }
+        DataType::Timestamp(Millisecond, _) => {
+            primitive::<TimestampMillisecondType>(array, |n| timestamp(n, Millisecond))
+        }
+        DataType::Timestamp(Microsecond, _) => {
+            primitive::<TimestampMicrosecondType>(array, |n| timestamp(n, Microsecond))
+        }
+        DataType::Timestamp(Nanosecond, _) => {
+            primitive::<TimestampNanosecondType>(array, |n| timestamp(n, Nanosecond))
+        }
+        DataType::Duration(Second) => primitive::<DurationSecondType>(array, Value::from),
+        DataType::Duration(Millisecond) => primitive::<DurationMillisecondType>(array, Value::from),
+        DataType::Duration(Microsecond) => primitive::<DurationMicrosecondType>(array, Value::from),
+        DataType::Duration(Nanosecond) => primitive::<DurationNanosecondType>(array, Value::from),
+        DataType::Utf8 => strings(field, array.as_string::<i32>()),
+        DataType::LargeUtf8 => strings(field, array.as_string::<i64>()),
+        DataType::Utf8View => strings(field, array.as_string_view()),
+        DataType::Binary => binaries(field, array.as_binary::<i32>()),
+        DataType::LargeBinary => binaries(field, array.as_binary::<i64>()),
+        DataType::BinaryView => binaries(field, array.as_binary_view()),
+        DataType::FixedSizeBinary(_) => binaries(field, array.as_fixed_size_binary()),
+        DataType::List(child) => {
+            let list = array.as_list::<i32>();
+            lists(child, list.values(), offsets(list.value_offsets()))?
+        }
+        DataType::LargeList(child) => {
+            let list = array.as_list::<i64>();
+            lists(child, list.values(), offsets(list.value_offsets()))?
+        }
+        DataType::ListView(child) => {
+            let list = array.as_list_view::<i32>();
+            let ranges = list.value_offsets().iter().zip(list.value_sizes());
+            lists(
+                child,
+                list.values(),
+                ranges.map(|(&start, &size)| (start as usize, (start + size) as usize)),
+            )?
+        }
+        DataType::LargeListView(child) => {
+            let list = array.as_list_view::<i64>();
+            let ranges = list.value_offsets().iter().zip(list.value_sizes());
+            lists(
+                child,
+                list.values(),
+                ranges.map(|(&start, &size)| (start as usize, (start + size) as usize)),
+            )?
+        }
+        DataType::FixedSizeList(child, _) => {
+            let list = array.as_fixed_size_list();
+            let size = list.value_length() as usize;
+            let ranges = (0..list.len()).map(|i| {
+                let start = list.value_offset(i) as usize;
+                (start, start + size)
+            });
+            lists(child, list.values(), ranges)?
+        }
+        DataType::Struct(fields) => {
+            let array = array.as_struct();
+            objects(fields, array.columns(), array.len())?
+        }
+        DataType::Map(..) => {
+            let map = array.as_map();
+            let fields = map.entries().fields();
+            let keys = values(&fields[0], map.keys())?;
+            let entries = values(&fields[1], map.values())?;
+            offsets(map.value_offsets())
+                .map(|(start, end)| {
+                    let mut object = Map::new();
+                    for (key, value) in keys[start..end].iter().zip(&entries[start..end]) {
+                        let key = match key {
+                            Value::String(s) => s.clone(),
+                            Value::Null => continue,
+                            key => key.to_string(),
+                        };
+                        object.insert(key, value.clone());
+                    }
+                    Value::Object(object)
+                })
+                .collect()
+        }
+        DataType::Dictionary(..) => {
+            let dictionary = array.as_any_dictionary();
+            let entries = values(field, dictionary.values())?;
+            // the keys of nulls may be any index
+            dictionary
+                .normalized_keys()
+                .into_iter()
+                .map(|key| entries.get(key).cloned().unwrap_or(Value::Null))
+                .collect()
+        }
+        data_type => {
+            return Err(invalid(format!(
+                "the column {} is of the type {}, which isn't supported",
+                field.name(),
+                data_type
+            )))
+        }
+    };
+    if let Some(nulls) = array.nulls() {
+        for (value, valid) in values.iter_mut().zip(nulls.iter()) {
+            if !valid {
+                *value = Value::Null;
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// The values of columns of `fields`, each `len` long, as objects of a value of each.
+fn objects(fields: &Fields, columns: &[ArrayRef], len: usize) -> Result<Vec<Value>, DrivelError> {
+    let mut objects = vec![Map::new(); len];
+    for (field, column) in fields.iter().zip(columns) {
+        for (object, value) in objects.iter_mut().zip(values(field, column)?) {
+            object.insert(field.name().clone(), value);
+        }
+    }
+    Ok(objects.into_iter().map(Value::Object).collect())
+}
+
+/// The rows of record batches as records, each an object of its columns. Dates are read as
+/// `YYYY-MM-DD` strings and timestamps as RFC 3339 date-times, as drivel infers them from
+/// JSON; binary values that aren't UTF-8 are read as base64. With `max_rows`, reading stops
+/// after that many rows.
+pub(crate) fn read_batches(
+    batches: impl IntoIterator<Item = Result<RecordBatch, ArrowError>>,
+    max_rows: Option<usize>,
+) -> Result<Vec<Value>, DrivelError> {
+    let max_rows = max_rows.unwrap_or(usize::MAX);
+    let mut records = vec![];
+    for batch in batches {
+        if records.len() >= max_rows {
+            break;
+        }
+        let batch = batch.map_err(invalid)?;
+        let rows = objects(
+            batch.schema_ref().fields(),
+            batch.columns(),
+            batch.num_rows(),
+        )?;
+        let wanted = max_rows - records.len();
+        records.extend(rows.into_iter().take(wanted));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::builder::{MapBuilder, StringBuilder, UInt8Builder};
+    use arrow_array::{
+        Decimal128Array, DictionaryArray, FixedSizeBinaryArray, Float64Array, Int64Array,
+        ListArray, StringArray, StructArray, Time64MicrosecondArray, TimestampMillisecondArray,
+    };
+    use arrow_schema::Schema;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn converts_columnar_values_to_json() {
+        assert_eq!(date(19_723), json!("2024-01-01"));
+        assert_eq!(date(-1), json!("1969-12-31"));
+        assert_eq!(
+            timestamp(1_704_067_200_123, TimeUnit::Millisecond),
+            json!("2024-01-01T00:00:00.123Z")
+        );
+        assert_eq!(
+            timestamp(-1, TimeUnit::Second),
+            json!("1969-12-31T23:59:59Z")
+        );
+        assert_eq!(
+            time(3_723_000_000, TimeUnit::Microsecond),
+            json!("01:02:03")
+        );
+        assert_eq!(decimal(12_345, 2), json!(123.45));
+        assert_eq!(decimal(12, -2), json!(1200));
+        assert_eq!(float(f64::NAN), Value::Null);
+        assert_eq!(binary(b"drivel"), json!("drivel"));
+        assert_eq!(binary(&[0xff, 0xfe, 0x00, 0x01]), json!("//4AAQ=="));
+        assert_eq!(
+            uuid(&[0x55; 16]),
+            json!("55555555-5555-5555-5555-555555555555")
+        );
+    }
+
+    fn extension(name: &str, field: Field) -> Field {
+        field.with_metadata(HashMap::from([(
+            "ARROW:extension:name".to_string(),
+            name.to_string(),
+        )]))
+    }
+
+    #[test]
+    fn reads_nested_and_encoded_columns_of_record_batches() {
+        let days = ListArray::from_iter_primitive::<Date32Type, _, _>([
+            Some(vec![Some(0), Some(1)]),
+            None,
+            Some(vec![Some(19_723)]),
+        ]);
+        let point = StructArray::from(vec![
+            (
+                Arc::new(Field::new("x", DataType::Float64, true)),
+                Arc::new(Float64Array::from(vec![
+                    Some(1.5),
+                    None,
+                    Some(f64::INFINITY),
+                ])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new(
+                    "at",
+                    DataType::Time64(TimeUnit::Microsecond),
+                    false,
+                )),
+                Arc::new(Time64MicrosecondArray::from(vec![0, 3_723_000_000, 1])),
+            ),
+        ]);
+        let mut counts = MapBuilder::new(None, StringBuilder::new(), UInt8Builder::new());
+        counts.keys().append_value("b");
+        counts.values().append_value(2);
+        counts.append(true).unwrap();
+        counts.append(false).unwrap();
+        counts.append(true).unwrap();
+        let counts = counts.finish();
+        let columns: Vec<(Field, ArrayRef)> = vec![
+            (
+                Field::new("id", DataType::Int64, false),
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+            ),
+            (
+                Field::new_dictionary("kind", DataType::Int32, DataType::Utf8, true),
+                Arc::new(DictionaryArray::<Int32Type>::from_iter([
+                    Some("a"),
+                    None,
+                    Some("a"),
+                ])),
+            ),
+            (
+                Field::new("days", days.data_type().clone(), true),
+                Arc::new(days),
+            ),
+            (
+                Field::new("point", point.data_type().clone(), false),
+                Arc::new(point),
+            ),
+            (
+                Field::new("counts", counts.data_type().clone(), true),
+                Arc::new(counts),
+            ),
+            (
+                Field::new("price", DataType::Decimal128(10, 2), false),
+                Arc::new(
+                    Decimal128Array::from(vec![12_345, -1, 0])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+            ),
+            (
+                Field::new(
+                    "seen",
+                    DataType::Timestamp(TimeUnit::Millisecond, Some("+01:00".into())),
+                    false,
+                ),
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![1_704_067_200_123, 0, -1])
+                        .with_timezone("+01:00"),
+                ),
+            ),
+            (
+                extension(
+                    "arrow.uuid",
+                    Field::new("uuid", DataType::FixedSizeBinary(16), false),
+                ),
+                Arc::new(
+                    FixedSizeBinaryArray::try_from_iter([[0x55; 16], [0; 16], [0xff; 16]].iter())
+                        .unwrap(),
+                ),
+            ),
+            (
+                extension("arrow.json", Field::new("extra", DataType::Utf8, false)),
+                Arc::new(StringArray::from(vec![r#"{"a": [1]}"#, "null", "not json"])),
+            ),
+        ];
+        let (fields, columns): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+        let records = [
+            json!({
+                "id": 1,
+                "kind": "a",
+                "days": ["1970-01-01", "1970-01-02"],
+                "point": {"x": 1.5, "at": "00:00:00"},
+                "counts": {"b": 2},
+                "price": 123.45,
+                "seen": "2024-01-01T00:00:00.123Z",
+                "uuid": "55555555-5555-5555-5555-555555555555",
+                "extra": {"a": [1]},
+            }),
+            json!({
+                "id": 2,
+                "kind": null,
+                "days": null,
+                "point": {"x": null, "at": "01:02:03"},
+                "counts": null,
+                "price": -0.01,
+                "seen": "1970-01-01T00:00:00Z",
+                "uuid": "00000000-0000-0000-0000-000000000000",
+                "extra": null,
+            }),
+            json!({
+                "id": 3,
+                "kind": "a",
+                "days": ["2024-01-01"],
+                "point": {"x": null, "at": "00:00:00.000001"},
+                "counts": {},
+                "price": 0.0,
+                "seen": "1969-12-31T23:59:59.999Z",
+                "uuid": "ffffffff-ffff-ffff-ffff-ffffffffffff",
+                "extra": "not json",
+            }),
+        ];
+        assert_eq!(read_batches([Ok(batch.clone())], None).unwrap(), records);
+        // a slice of a batch is read from where it starts
+        assert_eq!(
+            read_batches([Ok(batch.slice(1, 2)), Ok(batch)], Some(3)).unwrap(),
+            [&records[1..], &records[..1]].concat()
+        );
+    }
+}
@@ -0,0 +1,286 @@
+#[cfg(feature = "compression")]
+use std::io::BufReader;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+/// A format of compressed input or output, which data streams through as it is decompressed or
+/// compressed rather than all at once, by the `compression` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compression {
+    /// gzip, as in `.json.gz` files.
+    Gzip,
+    /// Zstandard, as in `.json.zst` files.
+    Zstd,
+}
+
+impl Compression {
+    /// The format of compressed data, by the magic number that it starts with, or `None` if it
+    /// isn't compressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::Compression;
+    ///
+    /// assert_eq!(Compression::detect(&[0x1f, 0x8b, 8, 0]), Some(Compression::Gzip));
+    /// assert_eq!(Compression::detect(b"{\"id\": 1}"), None);
+    /// ```
+    pub fn detect(start: &[u8]) -> Option<Compression> {
+        if start.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// The format of a file, by its extension, `.gz` or `.zst`.
+    pub fn of_path(path: &Path) -> Option<Compression> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gz") => Some(Compression::Gzip),
+            Some(extension) if extension.eq_ignore_ascii_case("zst") => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The extension of files of the format, without the `.`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Decompresses `input` while it is read. Fails if drivel was built without the
+    /// `compression` feature, and, as it is read, if the input isn't data of the format.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use drivel::Compression;
+    /// use std::io::Read;
+    ///
+    /// let file = std::fs::File::open("logs.json.gz").unwrap();
+    /// let mut json = String::new();
+    /// Compression::Gzip
+    ///     .decompress(file)
+    ///     .unwrap()
+    ///     .read_to_string(&mut json)
+    ///     .unwrap();
+    /// ```
+    pub fn decompress(self, input: impl Read + Send + 'static) -> io::Result<Decompressor> {
+        Ok(Decompressor {
+            input: self.decoder(input)?,
+        })
+    }
+
+    /// Compresses what is written to the returned writer into `out`, such as a file or the
+    /// stdout of this process. The output is complete once the writer is finished, or dropped.
+    /// Fails if drivel was built without the `compression` feature.
+    pub fn compress(self, out: impl Write + Send + 'static) -> io::Result<Compressor> {
+        Ok(Compressor {
+            encoder: Some(self.encoder(out)?),
+        })
+    }
+
+    #[cfg(feature = "compression")]
+    fn decoder(self, input: impl Read + Send + 'static) -> io::Result<Box<dyn BufRead + Send>> {
+        Ok(match self {
+            // a file of several gzip members, such as concatenated archives, is all of them
+            Compression::Gzip => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(input))),
+            Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(input)?)),
+        })
+    }
+
+    #[cfg(feature = "compression")]
+    fn encoder(self, out: impl Write + Send + 'static) -> io::Result<Box<dyn Encoder>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => {
+                Box::new(zstd::Encoder::new(out, zstd::DEFAULT_COMPRESSION_LEVEL)?)
+            }
+        })
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn decoder(self, _input: impl Read + Send + 'static) -> io::Result<Box<dyn BufRead + Send>> {
+        Err(self.unavailable())
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn encoder(self, _out: impl Write + Send + 'static) -> io::Result<Box<dyn Encoder>> {
+        Err(self.unavailable())
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn unavailable(self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{} data needs the `compression` feature, which drivel was built without",
+                match self {
+                    Compression::Gzip => "gzip",
+                    Compression::Zstd => "zstd",
+                }
+            ),
+        )
+    }
+}
+
+/// Decompressed input, read from [`Compression::decompress`].
+pub struct Decompressor {
+    input: Box<dyn BufRead + Send>,
+}
+
+impl Read for Decompressor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl BufRead for Decompressor {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.input.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.input.consume(amt)
+    }
+}
+
+/// The encoder of a format, which writes the end of the compressed data once it is finished.
+trait Encoder: Write + Send {
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+#[cfg(feature = "compression")]
+impl<W: Write + Send> Encoder for flate2::write::GzEncoder<W> {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        (*self).finish()?.flush()
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<W: Write + Send> Encoder for zstd::Encoder<'static, W> {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        (*self).finish()?.flush()
+    }
+}
+
+/// Output that is compressed while it is written, by [`Compression::compress`].
+pub struct Compressor {
+    /// The encoder that the output is written to, until it is finished.
+    encoder: Option<Box<dyn Encoder>>,
+}
+
+impl Compressor {
+    /// Ends the output, writing the rest of the compressed data, and failing if it couldn't be
+    /// written.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.end()
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        match self.encoder.take() {
+            Some(encoder) => encoder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    fn encoder(&mut self) -> io::Result<&mut Box<dyn Encoder>> {
+        self.encoder
+            .as_mut()
+            .ok_or_else(|| io::Error::other("the compressed output has been finished"))
+    }
+}
+
+impl Write for Compressor {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoder()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder()?.flush()
+    }
+}
+
+impl Drop for Compressor {
+    fn drop(&mut self) {
+        let _ = self.end();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_compression_by_magic_number_and_extension() {
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(&[0x1f]), None);
+        assert_eq!(Compression::detect(b""), None);
+        assert_eq!(
+            Compression::of_path(Path::new("logs/2024.json.GZ")),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::of_path(Path::new("fixture.ndjson.zst")),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::of_path(Path::new("gz")), None);
+        assert_eq!(Compression::Zstd.extension(), "zst");
+        assert_eq!(
+            crate::InputFormat::of_path(Path::new("rows.csv.gz")),
+            crate::InputFormat::Csv
+        );
+    }
+
+    /// Output that can be read back once it has been written, as it is shared.
+    #[cfg(feature = "compression")]
+    #[derive(Clone, Default)]
+    struct Shared(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "compression")]
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compresses_and_decompresses_in_either_format() {
+        let json = b"{\"id\": 1}\n".repeat(100);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let compressed = Shared::default();
+            let mut out = compression.compress(compressed.clone()).unwrap();
+            out.write_all(&json).unwrap();
+            out.finish().unwrap();
+
+            let compressed = compressed.0.lock().unwrap().clone();
+            assert_eq!(Compression::detect(&compressed), Some(compression));
+            let mut decompressed = vec![];
+            compression
+                .decompress(io::Cursor::new(compressed))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, json);
+        }
+        let mut garbage = Compression::Gzip
+            .decompress(io::Cursor::new(b"not gzip".to_vec()))
+            .unwrap();
+        assert!(garbage.read_to_end(&mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, NaiveDate, NaiveDateTime, Utc,
+};
+use fake::{Fake, Faker};
+use rand::Rng;
+
+use crate::StringType;
+
+/// Whether a chrono-style format pattern, such as `%d-%m-%Y %H:%M`, is free of unknown or
+/// incomplete specifiers.
+pub(crate) fn is_valid_date_format(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| item != Item::Error)
+}
+
+fn parses_with(s: &str, format: &str) -> bool {
+    DateTime::parse_from_str(s, format).is_ok()
+        || NaiveDateTime::parse_from_str(s, format).is_ok()
+        || NaiveDate::parse_from_str(s, format).is_ok()
+}
+
+/// Recognises a date-time in any of the given formats, keeping every format the string parses
+/// with, in the original order, so that ambiguous formats can be narrowed down by later values.
+pub(crate) fn infer_date_format(s: &str, formats: &[String]) -> Option<StringType> {
+    let matching: Vec<String> = formats
+        .iter()
+        .filter(|format| parses_with(s, format))
+        .cloned()
+        .collect();
+    if matching.is_empty() {
+        None
+    } else {
+        Some(StringType::CustomDateTime { formats: matching })
+    }
+}
+
+/// Produces a random date-time, written in the given format.
+pub(crate) fn produce_date_format<R: Rng + ?Sized>(format: &str, rng: &mut R) -> String {
+    let date_time: DateTime<Utc> = Faker.fake_with_rng(rng);
+    date_time.format(format).to_string()
+}
+
+/// The start of the Unix epoch, written in the given format.
+pub(crate) fn minimal_date_format(format: &str) -> String {
+    DateTime::UNIX_EPOCH.format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formats() -> Vec<String> {
+        vec!["%m/%d/%Y".to_string(), "%d/%m/%Y".to_string()]
+    }
+
+    #[test]
+    fn keeps_every_matching_format_in_order() {
+        assert_eq!(
+            infer_date_format("01/02/2024", &formats()),
+            Some(StringType::CustomDateTime { formats: formats() })
+        );
+        assert_eq!(
+            infer_date_format("13/02/2024", &formats()),
+            Some(StringType::CustomDateTime {
+                formats: vec!["%d/%m/%Y".to_string()]
+            })
+        );
+        assert_eq!(infer_date_format("2024-02-13", &formats()), None);
+    }
+
+    #[test]
+    fn recognises_date_times_with_and_without_offsets() {
+        for (s, format) in [
+            ("25-12-2023 10:30", "%d-%m-%Y %H:%M"),
+            ("25-12-2023 10:30 +0100", "%d-%m-%Y %H:%M %z"),
+        ] {
+            assert!(
+                infer_date_format(s, &[format.to_string()]).is_some(),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn validates_formats() {
+        assert!(is_valid_date_format("%d-%m-%Y %H:%M"));
+        assert!(!is_valid_date_format("%d-%m-%Y %Q"));
+    }
+
+    #[test]
+    fn produced_values_parse_with_their_format() {
+        for format in ["%m/%d/%Y", "%d-%m-%Y %H:%M", "%Y%m%dT%H%M%S%z"] {
+            for s in [
+                produce_date_format(format, &mut rand::thread_rng()),
+                minimal_date_format(format),
+            ] {
+                assert!(parses_with(&s, format), "{} {}", format, s);
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The subschemas directly nested in a JSON Schema generated by drivel, along with a hint for
+/// naming each of them (the name of the property it describes, etc.).
+fn children_mut<'a>(schema: &'a mut Value, hint: &str) -> Vec<(&'a mut Value, String)> {
+    let mut children = vec![];
+    let Some(schema_obj) = schema.as_object_mut() else {
+        return children;
+    };
+    for (key, child) in schema_obj.iter_mut() {
+        match (key.as_str(), child) {
+            ("properties", Value::Object(properties)) => {
+                children.extend(properties.iter_mut().map(|(k, v)| (v, k.clone())));
+            }
+            ("items", child @ Value::Object(_)) => children.push((child, format!("{}_item", hint))),
+            ("additionalProperties", child @ Value::Object(_)) => {
+                children.push((child, format!("{}_value", hint)))
+            }
+            ("oneOf" | "prefixItems", Value::Array(variants)) => {
+                children.extend(
+                    variants
+                        .iter_mut()
+                        .map(|v| (v, format!("{}_variant", hint))),
+                );
+            }
+            _ => {}
+        }
+    }
+    children
+}
+
+/// Whether a schema describes an object with properties, which is worth defining once and
+/// referring to wherever it is reused.
+fn is_definable(schema: &Value) -> bool {
+    schema["type"] == "object"
+        && schema
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .is_some_and(|p| !p.is_empty())
+}
+
+/// The serialized form of a schema, which is the same for schemas that only differ in the order
+/// of their `required` properties.
+fn canonical(schema: &Value) -> String {
+    fn sort_required(schema: &mut Value) {
+        match schema {
+            Value::Object(schema_obj) => {
+                for (key, value) in schema_obj.iter_mut() {
+                    match (key.as_str(), value) {
+                        ("required", Value::Array(names)) => {
+                            names.sort_by(|a, b| a.as_str().cmp(&b.as_str()))
+                        }
+                        (_, value) => sort_required(value),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(sort_required),
+            _ => {}
+        }
+    }
+
+    let mut schema = schema.clone();
+    sort_required(&mut schema);
+    schema.to_string()
+}
+
+fn count_subschemas(schema: &mut Value, hint: &str, counts: &mut HashMap<String, usize>) {
+    for (child, child_hint) in children_mut(schema, hint) {
+        if is_definable(child) {
+            *counts.entry(canonical(child)).or_insert(0) += 1;
+        }
+        count_subschemas(child, &child_hint, counts);
+    }
+}
+
+fn definition_name(hint: &str, names: &HashMap<String, String>, reserved: Option<&str>) -> String {
+    let base: String = hint
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let taken =
+        |name: &String| reserved == Some(name.as_str()) || names.values().any(|n| n == name);
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while taken(&name) {
+        name = format!("{}{}", base, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+struct Definitions {
+    /// The subschemas that occur more than once, by their serialized form.
+    reused: HashSet<String>,
+    /// The names of the definitions created so far, by the serialized form of their schema.
+    names: HashMap<String, String>,
+    /// Definitions that have been named, but whose own subschemas still need to be replaced.
+    pending: Vec<(String, Value, String)>,
+    /// A name that no definition may take, such as that of the root schema.
+    reserved: Option<String>,
+}
+
+impl Definitions {
+    fn replace_reused(&mut self, schema: &mut Value, hint: &str) {
+        for (child, child_hint) in children_mut(schema, hint) {
+            let serialized = canonical(child);
+            if !self.reused.contains(&serialized) {
+                self.replace_reused(child, &child_hint);
+                continue;
+            }
+
+            let name = match self.names.get(&serialized) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = definition_name(&child_hint, &self.names, self.reserved.as_deref());
+                    self.names.insert(serialized, name.clone());
+                    self.pending
+                        .push((name.clone(), child.clone(), child_hint.clone()));
+                    name
+                }
+            };
+            *child = serde_json::json!({ "$ref": format!("#/$defs/{}", name) });
+        }
+    }
+}
+
+/// Factors object subschemas that occur more than once in a JSON Schema into `$defs`, replacing
+/// every occurrence with a `$ref` to the definition.
+///
+/// Subschemas are compared by their JSON Schema, so schemas inferred from different samples are
+/// reused as long as they describe the same shape. Definitions are named after the property where
+/// their schema is first found, and the root schema itself is never factored out.
+pub(crate) fn factor_definitions(schema: &mut Value) {
+    factor(schema, "root", None, 2);
+}
+
+/// Factors every object subschema with properties in a JSON Schema into `$defs`, as
+/// [`factor_definitions`] does for those that occur more than once, so that no object is nested
+/// in another. Definitions are named after the property where their schema is first found, or
+/// after `root` for the items of an array at the root, and none is named `root`.
+pub(crate) fn factor_objects(schema: &mut Value, root: &str) {
+    factor(schema, root, Some(root), 1);
+}
+
+/// Factors the object subschemas that occur at least `min_count` times into `$defs`, with names
+/// hinted at by `root` for the subschemas of the root.
+fn factor(schema: &mut Value, root: &str, reserved: Option<&str>, min_count: usize) {
+    let mut counts = HashMap::new();
+    count_subschemas(schema, root, &mut counts);
+    let reused: HashSet<String> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= min_count)
+        .map(|(serialized, _)| serialized)
+        .collect();
+    if reused.is_empty() {
+        return;
+    }
+
+    let mut definitions = Definitions {
+        reused,
+        names: HashMap::new(),
+        pending: vec![],
+        reserved: reserved.map(str::to_string),
+    };
+    definitions.replace_reused(schema, root);
+
+    let mut defs = BTreeMap::new();
+    while let Some((name, mut definition, hint)) = definitions.pending.pop() {
+        definitions.replace_reused(&mut definition, &hint);
+        defs.insert(name, definition);
+    }
+
+    if let Some(schema_obj) = schema.as_object_mut() {
+        schema_obj.insert(
+            "$defs".to_string(),
+            Value::Object(defs.into_iter().collect()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn address() -> Value {
+        json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}, "zip": {"type": "string"}},
+            "required": ["city"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn factors_reused_objects_into_defs() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "billing": address(),
+                "shipping": address(),
+                "name": {"type": "string"}
+            }
+        });
+        factor_definitions(&mut schema);
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "billing": {"$ref": "#/$defs/billing"},
+                    "shipping": {"$ref": "#/$defs/billing"},
+                    "name": {"type": "string"}
+                },
+                "$defs": {"billing": address()}
+            })
+        );
+    }
+
+    #[test]
+    fn factors_nested_reuse_within_definitions() {
+        let customer = json!({
+            "type": "object",
+            "properties": {"home": address(), "work": address()}
+        });
+        let mut schema = json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {"buyer": customer, "seller": customer}
+            }
+        });
+        factor_definitions(&mut schema);
+
+        assert_eq!(
+            schema["items"]["properties"]["buyer"]["$ref"],
+            "#/$defs/buyer"
+        );
+        assert_eq!(
+            schema["items"]["properties"]["seller"]["$ref"],
+            "#/$defs/buyer"
+        );
+        assert_eq!(
+            schema["$defs"]["buyer"]["properties"]["home"]["$ref"],
+            "#/$defs/home"
+        );
+        assert_eq!(
+            schema["$defs"]["buyer"]["properties"]["work"]["$ref"],
+            "#/$defs/home"
+        );
+        assert_eq!(schema["$defs"]["home"], address());
+    }
+
+    #[test]
+    fn ignores_order_of_required_properties() {
+        let mut reordered = address();
+        reordered["required"] = json!(["zip", "city"]);
+        let mut ordered = address();
+        ordered["required"] = json!(["city", "zip"]);
+        let mut schema = json!({
+            "type": "object",
+            "properties": {"billing": ordered, "shipping": reordered}
+        });
+        factor_definitions(&mut schema);
+
+        assert_eq!(schema["properties"]["shipping"]["$ref"], "#/$defs/billing");
+    }
+
+    #[test]
+    fn leaves_schemas_without_reuse_untouched() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {"billing": address(), "tags": {"type": "array", "items": {"type": "string"}}}
+        });
+        let original = schema.clone();
+        factor_definitions(&mut schema);
+
+        assert_eq!(schema, original);
+    }
+
+    #[test]
+    fn names_definitions_uniquely() {
+        let other = json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "a": {"type": "object", "properties": {"item": address(), "x": address()}},
+                "b": {"type": "object", "properties": {"item": other, "y": other}}
+            }
+        });
+        factor_definitions(&mut schema);
+
+        let defs = schema["$defs"].as_object().unwrap();
+        assert_eq!(defs.len(), 2);
+        assert!(defs.contains_key("item") && defs.contains_key("item2"));
+    }
+}
@@ -0,0 +1,521 @@
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::Path;
+
+use crate::infer_string::infer_numeric_string;
+use crate::{DrivelError, NumberType, SchemaState, StringType, Table};
+
+/// The formats that input can be read in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// JSON, YAML or JSON lines, told apart by their contents, or TOML if it is none of them.
+    #[default]
+    Json,
+    /// Comma-separated values, with a header row of field names.
+    Csv,
+    /// Tab-separated values, with a header row of field names.
+    Tsv,
+    /// An XML document, with each element a field of its parent, and elements with attributes
+    /// or child elements objects of them.
+    Xml,
+    /// A TOML document, such as a config file, with its tables as objects and its dates and
+    /// times as strings.
+    Toml,
+    /// A Parquet file, whose rows are records, read as by `read_parquet`, with the `columnar`
+    /// feature.
+    Parquet,
+    /// An Arrow IPC stream or file, whose rows are records, read as by `read_arrow`, with the
+    /// `columnar` feature.
+    Arrow,
+}
+
+impl InputFormat {
+    /// The format of a file by its extension, `.csv`, `.tsv`, `.xml`, `.toml`, `.parquet` or
+    /// `.arrow` (or `.arrows`, `.ipc` or `.feather`), ignoring case, or JSON for any other file. The extension of a compressed file, such as `rows.csv.gz`, is
+    /// the one before that of its [`Compression`](crate::Compression).
+    pub fn of_path(path: &Path) -> InputFormat {
+        if crate::Compression::of_path(path).is_some() {
+            return InputFormat::of_path(Path::new(path.file_stem().unwrap_or_default()));
+        }
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => InputFormat::Csv,
+            Some(extension) if extension.eq_ignore_ascii_case("tsv") => InputFormat::Tsv,
+            Some(extension) if extension.eq_ignore_ascii_case("xml") => InputFormat::Xml,
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => InputFormat::Toml,
+            Some(extension) if extension.eq_ignore_ascii_case("parquet") => InputFormat::Parquet,
+            Some(extension)
+                if ["arrow", "arrows", "ipc", "feather"]
+                    .iter()
+                    .any(|arrow| extension.eq_ignore_ascii_case(arrow)) =>
+            {
+                InputFormat::Arrow
+            }
+            _ => InputFormat::Json,
+        }
+    }
+
+    /// The character that separates the fields of a row, if the format is delimited.
+    pub fn delimiter(self) -> Option<char> {
+        match self {
+            InputFormat::Csv => Some(','),
+            InputFormat::Tsv => Some('\t'),
+            _ => None,
+        }
+    }
+
+    /// Whether the format is binary rather than text, which is read as bytes, whatever the
+    /// encoding of text input.
+    pub fn is_binary(self) -> bool {
+        matches!(self, InputFormat::Parquet | InputFormat::Arrow)
+    }
+}
+
+/// Splits delimited input into rows of fields, as in RFC 4180: fields may be quoted with `"`,
+/// in which case they may hold the delimiter, line breaks and `""` for a quote. Rows end with
+/// `\n` or `\r\n`, and blank lines are skipped.
+fn split_rows(input: &str, delimiter: char) -> Result<Vec<Vec<String>>, DrivelError> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    // whether the current field was quoted, which tells an empty row from an empty field
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                let start = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(DrivelError::Parse(format!(
+                                "line {}: the quoted field is never closed",
+                                start
+                            )))
+                        }
+                    }
+                }
+                if !matches!(chars.peek(), None | Some('\r' | '\n'))
+                    && chars.peek() != Some(&delimiter)
+                {
+                    return Err(DrivelError::Parse(format!(
+                        "line {}: expected a delimiter after the quoted field",
+                        line
+                    )));
+                }
+            }
+            c if c == delimiter => {
+                row.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if !row.is_empty() || !field.is_empty() || quoted {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                quoted = false;
+                line += 1;
+            }
+            c => field.push(c),
+        }
+    }
+    if !row.is_empty() || !field.is_empty() || quoted {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// The types that the values of a column are read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Integer,
+    Float,
+    Boolean,
+    String,
+}
+
+/// The type of a column, from all of its values that aren't empty: integers if they all are,
+/// numbers if they all are, `true` and `false` in any case, or strings otherwise. Numbers with
+/// leading zeros, such as zip codes, are strings, so as not to lose the zeros.
+pub(crate) fn column_type(values: &[String]) -> ColumnType {
+    if values.is_empty() {
+        return ColumnType::String;
+    }
+    match infer_numeric_string(values) {
+        Some(StringType::Numeric {
+            number: NumberType::Integer { .. },
+        }) => ColumnType::Integer,
+        Some(StringType::Numeric { .. }) => ColumnType::Float,
+        _ if values.iter().all(|value| {
+            value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+        }) =>
+        {
+            ColumnType::Boolean
+        }
+        _ => ColumnType::String,
+    }
+}
+
+/// A field of a row as a value of its column's type, where an empty field is `null`.
+pub(crate) fn typed(field: String, column_type: ColumnType) -> Value {
+    if field.is_empty() {
+        return Value::Null;
+    }
+    match column_type {
+        ColumnType::Integer => field
+            .parse::<i64>()
+            .map_or(Value::String(field), Value::from),
+        ColumnType::Float => match field
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            Some(number) => Value::Number(number),
+            None => Value::String(field),
+        },
+        ColumnType::Boolean => Value::Bool(field.eq_ignore_ascii_case("true")),
+        ColumnType::String => Value::String(field),
+    }
+}
+
+/// Reads delimited input, such as CSV or TSV, whose first row is a header of field names, as
+/// an object for each of the other rows, with `--input-format`. The values of each column are
+/// typed by all of them together: integers, floats or booleans if every value of the column is
+/// one, or strings otherwise, while empty values are `null`. As with
+/// [`records_from_table`](crate::records_from_table), a row shorter than the header leaves out
+/// the fields of the missing columns.
+///
+/// Fails if a quoted field is never closed, if the header has the same name twice, or if a row
+/// is longer than the header.
+///
+/// # Example
+///
+/// ```
+/// use drivel::parse_delimited;
+/// use serde_json::json;
+///
+/// let input = "id,name,score,active\n1,Ada,9.5,TRUE\n2,\"Hopper, Grace\",7,false\n3,,,\n";
+/// let table = parse_delimited(input, ',').unwrap();
+/// assert_eq!(table.columns, vec!["id", "name", "score", "active"]);
+/// assert_eq!(
+///     table.records,
+///     vec![
+///         json!({"id": 1, "name": "Ada", "score": 9.5, "active": true}),
+///         json!({"id": 2, "name": "Hopper, Grace", "score": 7.0, "active": false}),
+///         json!({"id": 3, "name": null, "score": null, "active": null}),
+///     ]
+/// );
+/// ```
+pub fn parse_delimited(input: &str, delimiter: char) -> Result<Table, DrivelError> {
+    let mut rows = split_rows(input, delimiter)?.into_iter();
+    let columns = rows
+        .next()
+        .ok_or_else(|| DrivelError::Parse("expected a header row of field names".to_string()))?;
+    for (index, column) in columns.iter().enumerate() {
+        if columns[..index].contains(column) {
+            return Err(DrivelError::Parse(format!(
+                "the header has column '{}' twice",
+                column
+            )));
+        }
+    }
+    let rows: Vec<Vec<String>> = rows.collect();
+    if let Some((index, row)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, row)| row.len() > columns.len())
+    {
+        return Err(DrivelError::Parse(format!(
+            "row {} has {} fields, more than the {} columns of the header",
+            index + 1,
+            row.len(),
+            columns.len()
+        )));
+    }
+
+    let types: Vec<ColumnType> = (0..columns.len())
+        .map(|column| {
+            let values: Vec<String> = rows
+                .iter()
+                .filter_map(|row| row.get(column))
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .collect();
+            column_type(&values)
+        })
+        .collect();
+    let records = rows
+        .into_iter()
+        .map(|row| {
+            Value::Object(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(
+                        row.into_iter()
+                            .zip(&types)
+                            .map(|(field, column_type)| typed(field, *column_type)),
+                    )
+                    .collect::<Map<_, _>>(),
+            )
+        })
+        .collect();
+    Ok(Table { columns, records })
+}
+
+/// Writes produced records as delimited values, such as CSV, with a header row of the columns
+/// of their schema, with `produce --format csv`. There is a column for each field of the
+/// records, in the order of the schema, where the fields of nested objects are columns of their
+/// own, named by their paths, as in `address.city`. Other values that aren't strings, numbers
+/// or booleans, such as arrays, are written as JSON, and missing fields and `null`s are empty.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_schema, DelimitedWriter, InferenceOptions};
+/// use serde_json::json;
+///
+/// let records = json!([{"id": 1, "address": {"city": "Utrecht"}, "tags": ["a", "b"]}]);
+/// let schema = infer_schema(records.clone(), &InferenceOptions::default());
+/// let writer = DelimitedWriter::new(&schema, ',').unwrap();
+///
+/// let mut out = vec![];
+/// writer.write_header(&mut out).unwrap();
+/// writer.write_record(&mut out, &records[0]).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "id,address.city,tags\n1,Utrecht,\"[\"\"a\"\",\"\"b\"\"]\"\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelimitedWriter {
+    delimiter: char,
+    /// The path of the field of each column.
+    columns: Vec<Vec<String>>,
+}
+
+/// Adds the columns of the fields of an object, and of those of its nested objects, to
+/// `columns`.
+fn add_columns(schema: &SchemaState, path: &[String], columns: &mut Vec<Vec<String>>) {
+    let schema = match schema {
+        SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => inner,
+        schema => schema,
+    };
+    let keys: Vec<&String> = match schema {
+        SchemaState::Object {
+            required, optional, ..
+        } => required.keys().chain(optional.keys()).collect(),
+        SchemaState::GeoPoint {
+            latitude_key,
+            longitude_key,
+            ..
+        } => vec![latitude_key, longitude_key],
+        SchemaState::Money {
+            amount_key,
+            currency_key,
+            ..
+        } => vec![amount_key, currency_key],
+        _ => return columns.push(path.to_vec()),
+    };
+    for key in keys {
+        let field = match schema {
+            SchemaState::Object {
+                required, optional, ..
+            } => required.get(key).or_else(|| optional.get(key)),
+            _ => None,
+        };
+        let path: Vec<String> = path.iter().cloned().chain([key.clone()]).collect();
+        match field {
+            Some(field) => add_columns(field, &path, columns),
+            None => columns.push(path),
+        }
+    }
+}
+
+impl DelimitedWriter {
+    /// A writer of the records of a schema, which are the elements of an array at its root, or
+    /// the values of the schema otherwise, separating their fields by `delimiter`. `None` if the
+    /// records aren't objects.
+    pub fn new(schema: &SchemaState, delimiter: char) -> Option<DelimitedWriter> {
+        let records = match schema {
+            SchemaState::Array { schema, .. } => schema,
+            schema => schema,
+        };
+        let records = match records {
+            SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => inner,
+            records => records,
+        };
+        if !matches!(records, SchemaState::Object { .. }) {
+            return None;
+        }
+        let mut columns = vec![];
+        add_columns(records, &[], &mut columns);
+        Some(DelimitedWriter { delimiter, columns })
+    }
+
+    /// A field quoted as in RFC 4180 if it holds the delimiter, a quote or a line break.
+    fn quote(&self, field: &str) -> String {
+        if field.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn write_row(&self, out: &mut impl Write, fields: Vec<String>) -> std::io::Result<()> {
+        let fields: Vec<String> = fields.iter().map(|field| self.quote(field)).collect();
+        writeln!(out, "{}", fields.join(&self.delimiter.to_string()))
+    }
+
+    /// Writes the header row, of the name of each column.
+    pub fn write_header(&self, out: &mut impl Write) -> std::io::Result<()> {
+        self.write_row(
+            out,
+            self.columns.iter().map(|path| path.join(".")).collect(),
+        )
+    }
+
+    /// Writes a record as a row.
+    pub fn write_record(&self, out: &mut impl Write, record: &Value) -> std::io::Result<()> {
+        let fields = self
+            .columns
+            .iter()
+            .map(|path| {
+                let value = path
+                    .iter()
+                    .try_fold(record, |value, key| value.get(key))
+                    .unwrap_or(&Value::Null);
+                match value {
+                    Value::Null => String::new(),
+                    Value::String(string) => string.clone(),
+                    value => value.to_string(),
+                }
+            })
+            .collect();
+        self.write_row(out, fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema_from_iter, InferenceOptions, SchemaState};
+    use serde_json::json;
+
+    #[test]
+    fn reads_quoted_fields_and_line_endings() {
+        let input = "name\tnote\r\n\"Ada\"\t\"said \"\"hi\"\"\r\nand left\"\r\n\r\nGrace\t\"\"\n";
+        let table = parse_delimited(input, '\t').unwrap();
+        assert_eq!(
+            table.records,
+            vec![
+                json!({"name": "Ada", "note": "said \"hi\"\r\nand left"}),
+                json!({"name": "Grace", "note": null}),
+            ]
+        );
+
+        let reason = |input: &str| parse_delimited(input, ',').unwrap_err().to_string();
+        assert!(reason("").contains("header row"));
+        assert!(reason("a,a\n1,2").contains("column 'a' twice"));
+        assert!(reason("a\n1,2").contains("row 1 has 2 fields"));
+        assert!(reason("a\n\"1\n2").contains("line 2: the quoted field is never closed"));
+        assert!(reason("a,b\n\"1\"2,3").contains("line 2: expected a delimiter"));
+    }
+
+    #[test]
+    fn types_columns_by_all_of_their_values() {
+        let input = "zip,count,ratio,flag,mixed\n01234,1,1,true,1\n98765,-2,2.5,False,x\n,,,,\n";
+        let table = parse_delimited(input, ',').unwrap();
+        assert_eq!(
+            table.records[..2],
+            [
+                json!({"zip": "01234", "count": 1, "ratio": 1.0, "flag": true, "mixed": "1"}),
+                json!({"zip": "98765", "count": -2, "ratio": 2.5, "flag": false, "mixed": "x"}),
+            ]
+        );
+
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let SchemaState::Object { required, .. } = infer_schema_from_iter(table.records, &options)
+        else {
+            panic!("not an object");
+        };
+        assert_eq!(
+            required["count"],
+            SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Integer {
+                min: -2,
+                max: 1
+            })))
+        );
+        assert_eq!(
+            required["flag"],
+            SchemaState::Nullable(Box::new(SchemaState::Boolean))
+        );
+    }
+
+    #[test]
+    fn writes_records_as_rows_of_flattened_columns() {
+        let records = vec![
+            json!({"id": 1, "name": "Hopper, Grace", "address": {"city": "NY", "geo": {"lat": 1.5}}}),
+            json!({"id": 2, "name": "say \"hi\"", "address": null, "note": "a"}),
+        ];
+        let schema = infer_schema_from_iter(records.clone(), &InferenceOptions::default());
+        let writer = DelimitedWriter::new(&schema, ',').unwrap();
+        let mut out = vec![];
+        writer.write_header(&mut out).unwrap();
+        for record in &records {
+            writer.write_record(&mut out, record).unwrap();
+        }
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(
+            csv,
+            "id,name,address.city,address.geo.lat,note\n\
+             1,\"Hopper, Grace\",NY,1.5,\n\
+             2,\"say \"\"hi\"\"\",,,a\n"
+        );
+        // which reads back as the records, with nested objects flattened
+        let table = parse_delimited(&csv, ',').unwrap();
+        assert_eq!(table.records[0]["address.geo.lat"], json!(1.5));
+        assert_eq!(table.records[1]["name"], json!("say \"hi\""));
+
+        assert_eq!(DelimitedWriter::new(&SchemaState::Boolean, ','), None);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    avro::to_avro,
+    dot::to_dot,
+    graphql::to_graphql,
+    openapi::to_openapi_document,
+    proto::to_proto,
+    pydantic::to_pydantic,
+    rust_types::to_rust,
+    sampling::SampleCounts,
+    save_schema,
+    sql::to_sql,
+    typescript::{to_typescript, DEFAULT_TYPE_NAME},
+    zod::to_zod,
+    JsonSchemaOptions, NumberStats, OpenApiOptions, SchemaState, SqlDialect, ToJsonSchema,
+};
+
+/// The output formats in which an inferred schema can be described.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum DescribeFormat {
+    /// Human-readable description of the schema.
+    Human,
+    /// JSON Schema (draft 2020-12) document.
+    JsonSchema,
+    /// OpenAPI document, with the schema under `components/schemas`.
+    #[value(name = "openapi")]
+    OpenApi,
+    /// Directed graph in the DOT language of Graphviz, which can be rendered with e.g. `dot -Tpng`.
+    Dot,
+    /// TypeScript type definitions, with an interface for each object shape.
+    #[value(name = "typescript")]
+    TypeScript,
+    /// Rust type definitions that derive serde's `Serialize` and `Deserialize`, with a struct
+    /// for each object shape.
+    Rust,
+    /// SQL `CREATE TABLE` statements, with a table for each object shape and array.
+    Sql,
+    /// Apache Avro schema, with a record for each object shape.
+    Avro,
+    /// Protocol Buffers (proto3) message definitions, with a message for each object shape.
+    Proto,
+    /// Python classes of Pydantic models, with a model for each object shape.
+    Pydantic,
+    /// TypeScript schemas of Zod, with a schema and an inferred type for each object shape.
+    Zod,
+    /// GraphQL schema definition language, with an object type for each object shape and a
+    /// `Query` type that returns the root.
+    #[value(name = "graphql")]
+    GraphQl,
+    /// Compact, one-line signature of the types of the schema, such as
+    /// `{id:int, tags:[string], zip:string?}`, for comparing and logging schemas.
+    Signature,
+    /// Metrics of the size and complexity of the schema, as JSON: the number of fields, the
+    /// maximum depth, the number of unions and enums, and how many values there are of each type.
+    Metrics,
+    /// Summary of what was inferred about the values at each path, as JSON: their types,
+    /// format, bounds and enum values, and how often each field was present.
+    Json,
+    /// drivel's native format, as saved by `describe --save`, which keeps everything inferred
+    /// about the data, so that the schema can be loaded again or extended with more input. It
+    /// is always written as JSON on one line.
+    DrivelState,
+}
+
+/// When to color the human-readable description of a schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// When it is written to a terminal, unless the `NO_COLOR` environment variable is set or
+    /// `TERM` is `dumb`.
+    #[default]
+    Auto,
+    /// Always, such as for a pager that shows colors, like `less -R`.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color a description that is written to a terminal, or elsewhere.
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                terminal
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// A rendered description of a schema.
+#[derive(Debug, PartialEq)]
+pub enum Description {
+    /// Plain text, to be written out as-is.
+    Text(String),
+    /// A structured document, which can be serialized as JSON or YAML.
+    Document(serde_json::Value),
+}
+
+/// How many of the values of an enum, bitmask and the like the human-readable format lists by
+/// default.
+pub const DEFAULT_MAX_LIST_ITEMS: usize = 10;
+
+/// Options that customise how a schema is described.
+#[derive(Debug, Clone)]
+pub struct DescribeOptions {
+    /// Options for the JSON Schema format, which also apply to the schemas in OpenAPI documents.
+    /// Its [`value_counts`](JsonSchemaOptions::value_counts) are listed by the human-readable
+    /// format too.
+    pub json_schema: JsonSchemaOptions,
+    /// Options for the OpenAPI format.
+    pub openapi: OpenApiOptions,
+    /// The most values of each enum, bitmask and the like that the human-readable format lists,
+    /// followed by how many more there are, or `None` to list all of them. The other formats
+    /// always have all of them. Defaults to [`DEFAULT_MAX_LIST_ITEMS`].
+    pub max_list_items: Option<usize>,
+    /// The name of the root type in the TypeScript, Rust, Pydantic and Zod formats, of the field
+    /// of the `Query` type in the GraphQL format, of the root table in the SQL format, and of
+    /// the root record or message in the Avro and protobuf formats, in snake case. Defaults to
+    /// "InferredSchema".
+    pub type_name: Option<String>,
+    /// The dialect of SQL of the SQL format.
+    pub sql_dialect: SqlDialect,
+    /// The distributions of the numbers of the data that the schema was inferred from, if they
+    /// were tracked, which the human-readable format lists after the range of each number.
+    pub number_stats: Option<NumberStats>,
+    /// Whether every format has the fields of objects in the order of their names, rather than
+    /// in the order in which they were first seen, and the human-readable format lists them by
+    /// name rather than the required fields before the optional ones.
+    pub sort_keys: bool,
+    /// How many of the records read the schema was inferred from, if they were sampled, which
+    /// the human-readable format notes at its end, and the JSON Schema format as
+    /// `x-drivel-sample`, with the number of `sampled` and `read` records.
+    pub sample: Option<SampleCounts>,
+    /// Whether the human-readable format highlights field names, types and keywords such as
+    /// `optional` with ANSI colors, for a terminal.
+    pub color: bool,
+    /// The depth of nesting from which the human-readable format summarises objects by their
+    /// number of fields, such as `{…} (4 fields, 9 nested)`, rather than listing their fields,
+    /// for schemas with many nested fields. The fields of an object at the root are at depth
+    /// 1. Everything is described in full if it is `None`.
+    pub max_depth: Option<usize>,
+    /// Whether the human-readable format aligns the descriptions of the fields of each object
+    /// in a column, after the longest field name.
+    pub align_fields: bool,
+}
+
+impl Default for DescribeOptions {
+    fn default() -> Self {
+        DescribeOptions {
+            json_schema: JsonSchemaOptions::default(),
+            openapi: OpenApiOptions::default(),
+            max_list_items: Some(DEFAULT_MAX_LIST_ITEMS),
+            type_name: None,
+            sql_dialect: SqlDialect::default(),
+            number_stats: None,
+            sort_keys: false,
+            sample: None,
+            color: false,
+            max_depth: None,
+            align_fields: false,
+        }
+    }
+}
+
+impl DescribeFormat {
+    /// Renders the given schema in this format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{DescribeFormat, DescribeOptions, Description, SchemaState};
+    ///
+    /// let options = DescribeOptions::default();
+    /// let description = DescribeFormat::Human.render(&SchemaState::Boolean, &options);
+    /// assert_eq!(description, Description::Text("boolean".to_string()));
+    /// ```
+    pub fn render(&self, schema: &SchemaState, options: &DescribeOptions) -> Description {
+        if !options.sort_keys {
+            return self.render_in_order(schema, options);
+        }
+        let mut sorted = schema.clone();
+        sorted.sort_keys();
+        match self.render_in_order(&sorted, options) {
+            Description::Document(mut document) => {
+                document.sort_all_objects();
+                Description::Document(document)
+            }
+            text => text,
+        }
+    }
+
+    /// Renders the given schema in this format, with the fields of objects in the order of the
+    /// schema.
+    fn render_in_order(&self, schema: &SchemaState, options: &DescribeOptions) -> Description {
+        match self {
+            DescribeFormat::Human => {
+                let mut text = schema.to_string_pretty_with_options(options);
+                if let Some(sample) = options.sample {
+                    text.push_str(&format!("\n\n{}", sample));
+                }
+                Description::Text(text)
+            }
+            DescribeFormat::JsonSchema => {
+                let mut document = schema.to_json_schema_document_with(&options.json_schema);
+                if let (Some(sample), Some(document)) = (options.sample, document.as_object_mut()) {
+                    document.insert(
+                        "x-drivel-sample".to_string(),
+                        serde_json::json!({"sampled": sample.sampled, "read": sample.read}),
+                    );
+                }
+                Description::Document(document)
+            }
+            DescribeFormat::OpenApi => Description::Document(to_openapi_document(
+                schema,
+                &options.json_schema,
+                &options.openapi,
+            )),
+            DescribeFormat::Dot => Description::Text(to_dot(schema)),
+            DescribeFormat::TypeScript => Description::Text(to_typescript(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Rust => Description::Text(to_rust(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Sql => Description::Text(to_sql(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+                options.sql_dialect,
+            )),
+            DescribeFormat::Avro => Description::Document(to_avro(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Proto => Description::Text(to_proto(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Pydantic => Description::Text(to_pydantic(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Zod => Description::Text(to_zod(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::GraphQl => Description::Text(to_graphql(
+                schema,
+                options.type_name.as_deref().unwrap_or(DEFAULT_TYPE_NAME),
+            )),
+            DescribeFormat::Signature => Description::Text(schema.signature()),
+            DescribeFormat::Metrics => {
+                Description::Document(serde_json::to_value(schema.complexity()).unwrap())
+            }
+            DescribeFormat::Json => {
+                Description::Document(serde_json::to_value(schema.summary(options)).unwrap())
+            }
+            DescribeFormat::DrivelState => Description::Text(save_schema(schema)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NumberType;
+    use clap::ValueEnum;
+
+    fn sample_schema() -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: indexmap::IndexMap::from_iter([(
+                    "id".to_string(),
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 10 }),
+                )]),
+                optional: indexmap::IndexMap::from_iter([(
+                    "active".to_string(),
+                    SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+                )]),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        }
+    }
+
+    #[test]
+    fn every_format_produces_output() {
+        let schema = sample_schema();
+        for format in DescribeFormat::value_variants() {
+            match format.render(&schema, &DescribeOptions::default()) {
+                Description::Text(text) => assert!(!text.is_empty(), "{:?}", format),
+                Description::Document(document) => {
+                    assert!(
+                        document.as_object().is_some_and(|o| !o.is_empty()),
+                        "{:?}",
+                        format
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn human_format_truncates_long_lists() {
+        let variants = (0..25).map(|i| format!("v{:02}", i)).collect();
+        let schema = SchemaState::String(crate::StringType::Enum { variants });
+        let render = |max_list_items| {
+            let options = DescribeOptions {
+                max_list_items,
+                ..DescribeOptions::default()
+            };
+            match DescribeFormat::Human.render(&schema, &options) {
+                Description::Text(text) => text,
+                Description::Document(_) => unreachable!(),
+            }
+        };
+        assert_eq!(
+            render(Some(DEFAULT_MAX_LIST_ITEMS)),
+            "string (enum: v00, v01, v02, v03, v04, v05, v06, v07, v08, v09, … (+15 more))"
+        );
+        assert_eq!(render(Some(24)).matches("(+1 more)").count(), 1);
+        assert_eq!(render(Some(25)), schema.to_string_pretty());
+        assert_eq!(render(None), schema.to_string_pretty());
+
+        // machine-readable formats keep every value
+        let Description::Document(document) =
+            DescribeFormat::JsonSchema.render(&schema, &DescribeOptions::default())
+        else {
+            unreachable!()
+        };
+        assert_eq!(document["enum"].as_array().unwrap().len(), 25);
+    }
+
+    #[test]
+    fn json_schema_format_renders_document() {
+        let schema = sample_schema();
+        assert_eq!(
+            DescribeFormat::JsonSchema.render(&schema, &DescribeOptions::default()),
+            Description::Document(schema.to_json_schema_document())
+        );
+    }
+
+    #[test]
+    fn signature_format_renders_the_signature_of_the_schema() {
+        assert_eq!(
+            DescribeFormat::Signature.render(&sample_schema(), &DescribeOptions::default()),
+            Description::Text("[{active:bool|null?, id:int}]".to_string())
+        );
+    }
+
+    #[test]
+    fn metrics_format_renders_the_complexity_of_the_schema() {
+        let schema = sample_schema();
+        assert_eq!(
+            DescribeFormat::Metrics.render(&schema, &DescribeOptions::default()),
+            Description::Document(serde_json::to_value(schema.complexity()).unwrap())
+        );
+    }
+
+    #[test]
+    fn notes_how_many_records_were_sampled() {
+        let schema = sample_schema();
+        let options = DescribeOptions {
+            sample: Some(SampleCounts {
+                sampled: 10,
+                read: 1000,
+            }),
+            ..Default::default()
+        };
+        let Description::Document(document) = DescribeFormat::JsonSchema.render(&schema, &options)
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            document["x-drivel-sample"],
+            serde_json::json!({"sampled": 10, "read": 1000})
+        );
+        let Description::Text(text) = DescribeFormat::Human.render(&schema, &options) else {
+            unreachable!()
+        };
+        assert!(text.ends_with("\n\nSampled 10 of 1000 records"));
+    }
+
+    #[test]
+    fn human_format_colors_aligns_and_summarises_deep_objects() {
+        let schema = sample_schema();
+        let render =
+            |options: DescribeOptions| match DescribeFormat::Human.render(&schema, &options) {
+                Description::Text(text) => text,
+                Description::Document(_) => unreachable!(),
+            };
+        assert_eq!(
+            render(DescribeOptions {
+                align_fields: true,
+                ..Default::default()
+            }),
+            "[\n  {\n    \"id\":     int (1-10),\n    \"active\": optional nullable boolean\n  }\n] (1-2)"
+        );
+        assert_eq!(
+            render(DescribeOptions {
+                max_depth: Some(1),
+                ..Default::default()
+            }),
+            "[\n  {…} (2 fields)\n] (1-2)"
+        );
+        assert_eq!(
+            render(DescribeOptions {
+                max_depth: Some(0),
+                ..Default::default()
+            }),
+            "[{…} (2 fields)] (1-2)"
+        );
+
+        let colored = render(DescribeOptions {
+            color: true,
+            ..Default::default()
+        });
+        assert!(colored.contains("\x1b[1m\"id\"\x1b[0m: \x1b[36mint (1-10)\x1b[0m"));
+        assert!(colored.contains("\x1b[33moptional\x1b[0m \x1b[33mnullable\x1b[0m"));
+        let plain = colored.replace("\x1b[0m", "");
+        let plain = ["1", "33", "35", "36"].iter().fold(plain, |text, code| {
+            text.replace(&format!("\x1b[{}m", code), "")
+        });
+        assert_eq!(plain, render(DescribeOptions::default()));
+    }
+
+    #[test]
+    fn drivel_state_format_loads_as_the_schema() {
+        let schema = sample_schema();
+        let Description::Text(state) =
+            DescribeFormat::DrivelState.render(&schema, &DescribeOptions::default())
+        else {
+            unreachable!()
+        };
+        assert_eq!(crate::load_schema(&state).unwrap(), schema);
+    }
+}
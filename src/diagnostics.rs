@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether diagnostics are left out of stderr, see [`set_quiet`].
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The diagnostics collected by [`capture_diagnostics`] on this thread, if it is capturing.
+    static CAPTURED: RefCell<Option<Vec<Diagnostic>>> = const { RefCell::new(None) };
+}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Progress and other information that needs no action, such as the number of records read.
+    Info,
+    /// Something that drivel worked around, such as an unsupported keyword of a JSON Schema that
+    /// was ignored, and that might make the output differ from what was expected.
+    Warning,
+}
+
+/// A message that drivel writes to stderr, never to stdout, alongside its output. Errors are not
+/// diagnostics: functions that fail return them instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Info => write!(f, "{}", self.message),
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+        }
+    }
+}
+
+/// Sets whether diagnostics are left out of stderr, such as for scripts that only want to see
+/// errors. Diagnostics are still collected by [`capture_diagnostics`].
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Runs `f`, collecting the diagnostics emitted on this thread while it runs instead of writing
+/// them to stderr, and returns them along with its result. Diagnostics emitted on other threads,
+/// such as progress while producing data with several jobs, are written to stderr as usual.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{capture_diagnostics, parse_json_schema, Severity};
+/// use serde_json::json;
+///
+/// let schema = json!({"type": "string", "format": "color"});
+/// let (parsed, diagnostics) = capture_diagnostics(|| parse_json_schema(&schema));
+/// assert!(parsed.is_ok());
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Warning);
+/// ```
+pub fn capture_diagnostics<T>(f: impl FnOnce() -> T) -> (T, Vec<Diagnostic>) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let result = f();
+    let diagnostics = CAPTURED.with(|captured| captured.replace(outer));
+    (result, diagnostics.unwrap_or_default())
+}
+
+/// Writes a diagnostic to stderr, unless diagnostics are being captured on this thread or
+/// [quiet](set_quiet).
+pub fn emit(diagnostic: Diagnostic) {
+    let diagnostic = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(captured) => {
+            captured.push(diagnostic);
+            None
+        }
+        None => Some(diagnostic),
+    });
+    if let Some(diagnostic) = diagnostic {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!("{}", diagnostic);
+        }
+    }
+}
+
+/// Emits a [warning](Severity::Warning), written as `Warning: <message>`.
+pub fn warn(message: impl Into<String>) {
+    emit(Diagnostic {
+        severity: Severity::Warning,
+        message: message.into(),
+    });
+}
+
+/// Emits [information](Severity::Info), written as the message itself.
+pub fn info(message: impl Into<String>) {
+    emit(Diagnostic {
+        severity: Severity::Info,
+        message: message.into(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_diagnostics_of_the_current_thread() {
+        let ((), diagnostics) = capture_diagnostics(|| {
+            warn("first");
+            let ((), inner) = capture_diagnostics(|| info("inner"));
+            assert_eq!(inner.len(), 1);
+            std::thread::spawn(|| info("elsewhere")).join().unwrap();
+            info("second");
+        });
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Warning: first", "second"]
+        );
+    }
+}
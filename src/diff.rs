@@ -0,0 +1,555 @@
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::produce_options::{field_path, items_path};
+use crate::{NumberType, SchemaState, StringType};
+
+/// How a schema changed at a path, for a [`SchemaChange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChangeKind {
+    /// A field that the old schema doesn't have, which is required if every object has it.
+    FieldAdded { required: bool },
+    /// A field that the new schema doesn't have, which was required if every object had it.
+    FieldRemoved { required: bool },
+    /// A required field that objects may now leave out.
+    BecameOptional,
+    /// An optional field that every object now has.
+    BecameRequired,
+    /// A value that may now be `null`.
+    BecameNullable,
+    /// A value that may no longer be `null`.
+    BecameNonNullable,
+    /// A value of another type, such as an integer that became a string.
+    TypeChanged { from: String, to: String },
+    /// Values that an enum didn't have.
+    EnumValuesAdded { values: Vec<Value> },
+    /// Values that an enum no longer has.
+    EnumValuesRemoved { values: Vec<Value> },
+    /// A value of the tag field of a tagged union that it didn't have.
+    VariantAdded { tag: String, value: String },
+    /// A value of the tag field of a tagged union that it no longer has.
+    VariantRemoved { tag: String, value: String },
+}
+
+/// A difference between two schemas of the same data: where it is, how it changed, and whether
+/// the change is breaking, that is, whether data of the new schema may not fit the old one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// The path of the value, such as `orders[].total`, as used by
+    /// [`ProduceOptions`](crate::ProduceOptions), where the root is the empty path.
+    pub path: String,
+    pub kind: SchemaChangeKind,
+    pub breaking: bool,
+}
+
+fn join_values(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for SchemaChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let requirement = |required: bool| if required { "required" } else { "optional" };
+        match self {
+            SchemaChangeKind::FieldAdded { required } => {
+                write!(f, "{} field added", requirement(*required))
+            }
+            SchemaChangeKind::FieldRemoved { required } => {
+                write!(f, "{} field removed", requirement(*required))
+            }
+            SchemaChangeKind::BecameOptional => write!(f, "field became optional"),
+            SchemaChangeKind::BecameRequired => write!(f, "field became required"),
+            SchemaChangeKind::BecameNullable => write!(f, "value became nullable"),
+            SchemaChangeKind::BecameNonNullable => write!(f, "value is no longer nullable"),
+            SchemaChangeKind::TypeChanged { from, to } => {
+                write!(f, "type changed from {} to {}", from, to)
+            }
+            SchemaChangeKind::EnumValuesAdded { values } => {
+                write!(f, "enum values added: {}", join_values(values))
+            }
+            SchemaChangeKind::EnumValuesRemoved { values } => {
+                write!(f, "enum values removed: {}", join_values(values))
+            }
+            SchemaChangeKind::VariantAdded { tag, value } => {
+                write!(f, "variant added: {} = \"{}\"", tag, value)
+            }
+            SchemaChangeKind::VariantRemoved { tag, value } => {
+                write!(f, "variant removed: {} = \"{}\"", tag, value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}: {}", self.path, self.kind)
+        }
+    }
+}
+
+/// The kind of value that a schema describes, without its bounds or values, which tells when
+/// a value's type changed: a plain string and a string of a format are of different kinds, but
+/// strings of different lengths aren't.
+fn kind(schema: &SchemaState) -> String {
+    match schema {
+        SchemaState::Initial | SchemaState::Indefinite => "any".to_string(),
+        SchemaState::Null => "null".to_string(),
+        SchemaState::Nullable(schema) => kind(schema),
+        SchemaState::String(string_type) => match string_type {
+            StringType::Unknown { .. }
+            | StringType::Charset { .. }
+            | StringType::Pattern { .. }
+            | StringType::FixedWidthNumeric { .. }
+            | StringType::Numeric { .. } => "string".to_string(),
+            StringType::Enum { .. } | StringType::OpenEnum { .. } => "string (enum)".to_string(),
+            StringType::CustomDateTime { .. } => "string (datetime)".to_string(),
+            string_type => string_type.to_string(),
+        },
+        SchemaState::Number(
+            NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+        ) => "float".to_string(),
+        SchemaState::Number(NumberType::Enum { .. }) => "int (enum)".to_string(),
+        SchemaState::Number(_) => "int".to_string(),
+        SchemaState::Boolean => "bool".to_string(),
+        SchemaState::Literal(values) => kind(&SchemaState::literal_type(values)),
+        SchemaState::Recursive { schema, .. } => kind(schema),
+        SchemaState::Reference(name) => format!("recursive {}", name),
+        SchemaState::Array { .. } => "array".to_string(),
+        SchemaState::Object { .. } => "object".to_string(),
+        SchemaState::Map { .. } => "map".to_string(),
+        SchemaState::ExtendedJson(extended_json) => match extended_json {
+            crate::ExtendedJsonType::Long { .. } => "long".to_string(),
+            extended_json => extended_json.to_string(),
+        },
+        SchemaState::Coordinates(_) => "coordinates".to_string(),
+        SchemaState::Tuple(positions) => format!("tuple of {}", positions.len()),
+        SchemaState::GeoPoint { .. } => "geopoint".to_string(),
+        SchemaState::Money { .. } => "money".to_string(),
+        SchemaState::Union(_) | SchemaState::Mixed(_) => "union".to_string(),
+        SchemaState::TaggedUnion { .. } => "tagged union".to_string(),
+    }
+}
+
+/// Whether values of kind `to` always fit kind `from`, such as a string of a format for a plain
+/// string, or an integer for a float, so that the change isn't breaking.
+fn narrows(from: &str, to: &str) -> bool {
+    from == "any"
+        || (from == "string" && to.starts_with("string"))
+        || (from == "float" && to.starts_with("int"))
+        || (from == "int" && to == "int (enum)")
+}
+
+fn enum_values(schema: &SchemaState) -> Option<BTreeSet<String>> {
+    match schema {
+        SchemaState::String(StringType::Enum { variants }) => Some(variants.clone()),
+        _ => None,
+    }
+}
+
+/// A schema without its nullability, and whether it is nullable, where a value that was only
+/// ever `null` is of a type that is unknown.
+fn nullable(schema: &SchemaState) -> (&SchemaState, bool) {
+    static UNKNOWN: SchemaState = SchemaState::Initial;
+    match schema {
+        SchemaState::Null => (&UNKNOWN, true),
+        SchemaState::Nullable(schema) => (schema, true),
+        schema => (schema, false),
+    }
+}
+
+struct Differ {
+    changes: Vec<SchemaChange>,
+}
+
+impl Differ {
+    fn push(&mut self, path: &str, kind: SchemaChangeKind, breaking: bool) {
+        self.changes.push(SchemaChange {
+            path: path.to_string(),
+            kind,
+            breaking,
+        });
+    }
+
+    fn values(&mut self, path: &str, added: Vec<Value>, removed: Vec<Value>) {
+        if !added.is_empty() {
+            self.push(
+                path,
+                SchemaChangeKind::EnumValuesAdded { values: added },
+                true,
+            );
+        }
+        if !removed.is_empty() {
+            let kind = SchemaChangeKind::EnumValuesRemoved { values: removed };
+            self.push(path, kind, false);
+        }
+    }
+
+    fn fields(
+        &mut self,
+        path: &str,
+        (old_required, old_optional): (
+            &IndexMap<String, SchemaState>,
+            &IndexMap<String, SchemaState>,
+        ),
+        (new_required, new_optional): (
+            &IndexMap<String, SchemaState>,
+            &IndexMap<String, SchemaState>,
+        ),
+    ) {
+        let old: BTreeMap<&String, (&SchemaState, bool)> = old_required
+            .iter()
+            .map(|(key, schema)| (key, (schema, true)))
+            .chain(
+                old_optional
+                    .iter()
+                    .map(|(key, schema)| (key, (schema, false))),
+            )
+            .collect();
+        let new: BTreeMap<&String, (&SchemaState, bool)> = new_required
+            .iter()
+            .map(|(key, schema)| (key, (schema, true)))
+            .chain(
+                new_optional
+                    .iter()
+                    .map(|(key, schema)| (key, (schema, false))),
+            )
+            .collect();
+        let keys: BTreeSet<&&String> = old.keys().chain(new.keys()).collect();
+        for key in keys {
+            let path = field_path(path, key);
+            match (old.get(*key), new.get(*key)) {
+                (Some((old, old_required)), Some((new, new_required))) => {
+                    if old_required != new_required {
+                        if *new_required {
+                            self.push(&path, SchemaChangeKind::BecameRequired, false);
+                        } else {
+                            self.push(&path, SchemaChangeKind::BecameOptional, true);
+                        }
+                    }
+                    self.diff(&path, old, new);
+                }
+                (Some((_, required)), None) => {
+                    let kind = SchemaChangeKind::FieldRemoved {
+                        required: *required,
+                    };
+                    self.push(&path, kind, *required);
+                }
+                (None, Some((_, required))) => {
+                    let kind = SchemaChangeKind::FieldAdded {
+                        required: *required,
+                    };
+                    self.push(&path, kind, false);
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    fn diff(&mut self, path: &str, old: &SchemaState, new: &SchemaState) {
+        // a schema that has seen no values, such as that of an empty array's items, says nothing
+        // about them
+        if matches!(old, SchemaState::Initial) || matches!(new, SchemaState::Initial) {
+            return;
+        }
+        // a recursive definition is compared by its values, down to where they recurse
+        if let SchemaState::Recursive { schema, .. } = old {
+            return self.diff(path, schema, new);
+        }
+        if let SchemaState::Recursive { schema, .. } = new {
+            return self.diff(path, old, schema);
+        }
+        let ((old, old_nullable), (new, new_nullable)) = (nullable(old), nullable(new));
+        if old_nullable != new_nullable {
+            if new_nullable {
+                self.push(path, SchemaChangeKind::BecameNullable, true);
+            } else {
+                self.push(path, SchemaChangeKind::BecameNonNullable, false);
+            }
+        }
+        if matches!(old, SchemaState::Initial) || matches!(new, SchemaState::Initial) {
+            return;
+        }
+
+        match (old, new) {
+            (
+                SchemaState::Object {
+                    required: old_required,
+                    optional: old_optional,
+                    ..
+                },
+                SchemaState::Object {
+                    required: new_required,
+                    optional: new_optional,
+                    ..
+                },
+            ) => self.fields(
+                path,
+                (old_required, old_optional),
+                (new_required, new_optional),
+            ),
+            (SchemaState::Array { schema: old, .. }, SchemaState::Array { schema: new, .. }) => {
+                self.diff(&items_path(path), old, new)
+            }
+            // positions are told apart by their indices, as they can be of different kinds
+            (SchemaState::Tuple(old), SchemaState::Tuple(new)) if old.len() == new.len() => {
+                for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                    self.diff(&format!("{}[{}]", path, index), old, new);
+                }
+            }
+            (SchemaState::Map { schema: old, .. }, SchemaState::Map { schema: new, .. }) => {
+                self.diff(&field_path(path, "*"), old, new)
+            }
+            (
+                SchemaState::TaggedUnion {
+                    tag: old_tag,
+                    variants: old,
+                },
+                SchemaState::TaggedUnion {
+                    tag: new_tag,
+                    variants: new,
+                },
+            ) if old_tag == new_tag => {
+                for (value, old) in old {
+                    match new.get(value) {
+                        Some(new) => self.diff(path, old, new),
+                        None => {
+                            let kind = SchemaChangeKind::VariantRemoved {
+                                tag: old_tag.clone(),
+                                value: value.clone(),
+                            };
+                            self.push(path, kind, false);
+                        }
+                    }
+                }
+                for value in new.keys().filter(|value| !old.contains_key(*value)) {
+                    let kind = SchemaChangeKind::VariantAdded {
+                        tag: new_tag.clone(),
+                        value: value.clone(),
+                    };
+                    self.push(path, kind, true);
+                }
+            }
+            (
+                SchemaState::Number(NumberType::Enum { variants: old }),
+                SchemaState::Number(NumberType::Enum { variants: new }),
+            ) => {
+                let added = new.keys().filter(|value| !old.contains_key(value));
+                let removed = old.keys().filter(|value| !new.contains_key(value));
+                self.values(
+                    path,
+                    added.map(|value| Value::from(*value)).collect(),
+                    removed.map(|value| Value::from(*value)).collect(),
+                );
+            }
+            (old, new) if enum_values(old).is_some() && enum_values(new).is_some() => {
+                let (old, new) = (enum_values(old).unwrap(), enum_values(new).unwrap());
+                self.values(
+                    path,
+                    new.difference(&old).cloned().map(Value::from).collect(),
+                    old.difference(&new).cloned().map(Value::from).collect(),
+                );
+            }
+            (
+                SchemaState::Union(_) | SchemaState::Mixed(_),
+                SchemaState::Union(_) | SchemaState::Mixed(_),
+            ) if old.signature() != new.signature() => {
+                let kind = SchemaChangeKind::TypeChanged {
+                    from: old.signature(),
+                    to: new.signature(),
+                };
+                self.push(path, kind, true);
+            }
+            (old, new) => {
+                let (from, to) = (kind(old), kind(new));
+                if from != to {
+                    let breaking = !narrows(&from, &to);
+                    self.push(path, SchemaChangeKind::TypeChanged { from, to }, breaking);
+                }
+            }
+        }
+    }
+}
+
+impl SchemaState {
+    /// Returns the differences between this schema and a newer one of the same data, such as the
+    /// responses of an API before and after a release: the fields that were added and removed,
+    /// the values whose types, nullability or enum values changed, and whether each change is
+    /// breaking, that is, whether data of the new schema may not fit this one.
+    ///
+    /// Removing a required field, making a field optional or a value nullable, adding enum
+    /// values or variants, and changing a type are breaking. Adding fields, removing optional
+    /// fields or enum values, and narrowing a type, such as a plain string to an email address or
+    /// a float to an integer, aren't. An array that was empty on either side, whose items are
+    /// unknown, isn't compared. Changes are in the order of their paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions, SchemaChangeKind};
+    /// use serde_json::json;
+    ///
+    /// let options = InferenceOptions::default();
+    /// let old = infer_schema(json!({"id": 1, "name": "Ada", "debug": true}), &options);
+    /// let new = infer_schema(json!({"id": "a1", "name": "Ada", "email": "ada@example.com"}), &options);
+    ///
+    /// let changes = old.diff(&new);
+    /// let changes: Vec<String> = changes.iter().map(ToString::to_string).collect();
+    /// assert_eq!(
+    ///     changes,
+    ///     vec![
+    ///         "debug: required field removed",
+    ///         "email: required field added",
+    ///         "id: type changed from int to string",
+    ///     ]
+    /// );
+    /// assert!(old.diff(&new).iter().any(|change| change.breaking));
+    /// assert_eq!(old.diff(&old), vec![]);
+    /// ```
+    pub fn diff(&self, new: &SchemaState) -> Vec<SchemaChange> {
+        let mut differ = Differ { changes: vec![] };
+        differ.diff("", self, new);
+        differ.changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, InferenceOptions};
+    use serde_json::json;
+
+    fn options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    fn changes(old: &SchemaState, new: &SchemaState) -> Vec<(String, bool)> {
+        old.diff(new)
+            .into_iter()
+            .map(|change| (change.to_string(), change.breaking))
+            .collect()
+    }
+
+    #[test]
+    fn reports_changes_of_nested_fields_by_path() {
+        let old = infer_schema(
+            json!([
+                {"orders": [{"total": 1.5, "note": "x"}], "tags": {"a": 1}, "when": "soon"},
+                {"orders": [{"total": 2.5}], "tags": {"a": 2}, "when": "later"},
+            ]),
+            &options(),
+        );
+        let new = infer_schema(
+            json!([
+                {"orders": [{"total": 2, "note": null}], "tags": {"a": "1"}, "when": null},
+                {"orders": [{"total": 3, "note": "y"}], "tags": {"a": "2"}},
+            ]),
+            &options(),
+        );
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                ("[].orders[].note: field became required".to_string(), false),
+                ("[].orders[].note: value became nullable".to_string(), true),
+                (
+                    "[].orders[].total: type changed from float to int".to_string(),
+                    false
+                ),
+                (
+                    "[].tags.a: type changed from int to string".to_string(),
+                    true
+                ),
+                ("[].when: field became optional".to_string(), true),
+                ("[].when: value became nullable".to_string(), true),
+            ]
+        );
+        assert_eq!(changes(&new, &new), vec![]);
+    }
+
+    #[test]
+    fn reports_enum_values_and_variants() {
+        let status = |values: &[&str]| {
+            SchemaState::String(StringType::Enum {
+                variants: values.iter().map(|value| value.to_string()).collect(),
+            })
+        };
+        assert_eq!(
+            changes(
+                &status(&["active", "closed"]),
+                &status(&["active", "archived"])
+            ),
+            vec![
+                ("enum values added: \"archived\"".to_string(), true),
+                ("enum values removed: \"closed\"".to_string(), false),
+            ]
+        );
+        let plain = SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: Some(1),
+            max_length: Some(3),
+        });
+        assert_eq!(
+            changes(&plain, &status(&["a"])),
+            vec![(
+                "type changed from string to string (enum)".to_string(),
+                false
+            )]
+        );
+        assert_eq!(
+            changes(&status(&["a"]), &plain),
+            vec![(
+                "type changed from string (enum) to string".to_string(),
+                true
+            )]
+        );
+
+        let event = |variants: &[&str]| SchemaState::TaggedUnion {
+            tag: "type".to_string(),
+            variants: variants
+                .iter()
+                .map(|variant| (variant.to_string(), SchemaState::Initial))
+                .collect(),
+        };
+        assert_eq!(
+            changes(&event(&["click", "view"]), &event(&["click", "scroll"])),
+            vec![
+                ("variant removed: type = \"view\"".to_string(), false),
+                ("variant added: type = \"scroll\"".to_string(), true),
+            ]
+        );
+    }
+}
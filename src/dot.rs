@@ -0,0 +1,225 @@
+use crate::SchemaState;
+
+/// Quotes a string as a DOT identifier or label, which may then contain any characters.
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn length(min_length: usize, max_length: usize) -> String {
+    if min_length != max_length {
+        format!("({}-{})", min_length, max_length)
+    } else {
+        format!("({})", min_length)
+    }
+}
+
+struct DotGraph {
+    lines: Vec<String>,
+}
+
+impl DotGraph {
+    fn node(&mut self, path: &str, label: &str, shape: &str) {
+        self.lines.push(format!(
+            "  {} [label={}, shape={}];",
+            quote(path),
+            quote(label),
+            shape
+        ));
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &str, optional: bool) {
+        let style = if optional { ", style=dashed" } else { "" };
+        self.lines.push(format!(
+            "  {} -> {} [label={}{}];",
+            quote(from),
+            quote(to),
+            quote(label),
+            style
+        ));
+    }
+
+    /// Adds the node for the value at `path`, such as `$.orders[].total`, and for objects, arrays,
+    /// maps and unions, the nodes of the values they contain.
+    fn add(&mut self, schema: &SchemaState, path: &str, nullable: bool) {
+        let prefix = if nullable { "nullable " } else { "" };
+        match schema {
+            SchemaState::Nullable(inner) => self.add(inner, path, true),
+            SchemaState::Recursive { schema, .. } => self.add(schema, path, nullable),
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                self.node(path, &format!("{}object", prefix), "box");
+                let fields = required
+                    .iter()
+                    .map(|(key, schema)| (key, schema, false))
+                    .chain(optional.iter().map(|(key, schema)| (key, schema, true)))
+                    .collect::<Vec<_>>();
+                for (key, schema, optional) in fields {
+                    let child = format!("{}.{}", path, key);
+                    let label = if optional {
+                        format!("{} (optional)", key)
+                    } else {
+                        key.clone()
+                    };
+                    self.edge(path, &child, &label, optional);
+                    self.add(schema, &child, false);
+                }
+            }
+            SchemaState::Array {
+                min_length,
+                max_length,
+                schema,
+                ..
+            } => {
+                let label = format!("{}array {}", prefix, length(*min_length, *max_length));
+                self.node(path, &label, "box");
+                let child = format!("{}[]", path);
+                self.edge(path, &child, "[]", false);
+                self.add(schema, &child, false);
+            }
+            SchemaState::Tuple(positions) => {
+                self.node(path, &format!("{}tuple", prefix), "box");
+                for (index, position) in positions.iter().enumerate() {
+                    let child = format!("{}[{}]", path, index);
+                    self.edge(path, &child, &format!("[{}]", index), false);
+                    self.add(position, &child, false);
+                }
+            }
+            SchemaState::Map {
+                min_length,
+                max_length,
+                keys,
+                schema,
+            } => {
+                let label = format!(
+                    "{}map<{}> {}",
+                    prefix,
+                    keys,
+                    length(*min_length, *max_length)
+                );
+                self.node(path, &label, "box");
+                let child = format!("{}.*", path);
+                self.edge(path, &child, "*", false);
+                self.add(schema, &child, false);
+            }
+            SchemaState::Union(variants) => {
+                self.node(path, &format!("{}union", prefix), "diamond");
+                for (i, variant) in variants.iter().enumerate() {
+                    // the variants share the path of the union, so they are told apart by index
+                    let child = format!("{}|{}", path, i);
+                    self.edge(path, &child, &format!("variant {}", i + 1), false);
+                    self.add(variant, &child, false);
+                }
+            }
+            SchemaState::Mixed(variants) => {
+                self.node(path, &format!("{}union", prefix), "diamond");
+                let total = variants
+                    .iter()
+                    .map(|(_, count)| count)
+                    .sum::<usize>()
+                    .max(1);
+                for (i, (variant, count)) in variants.iter().enumerate() {
+                    let child = format!("{}|{}", path, i);
+                    let label = format!("{}%", (*count as f64 * 100.0 / total as f64).round());
+                    self.edge(path, &child, &label, false);
+                    self.add(variant, &child, false);
+                }
+            }
+            SchemaState::TaggedUnion { tag, variants } => {
+                self.node(path, &format!("{}union by {}", prefix, tag), "diamond");
+                for (i, (value, variant)) in variants.iter().enumerate() {
+                    let child = format!("{}|{}", path, i);
+                    self.edge(path, &child, &format!("{} = {}", tag, value), false);
+                    self.add(variant, &child, false);
+                }
+            }
+            SchemaState::Null if !nullable => self.node(path, "always null", "ellipse"),
+            SchemaState::Null => self.node(path, "null", "ellipse"),
+            _ => self.node(
+                path,
+                &format!("{}{}", prefix, schema.to_string_pretty()),
+                "ellipse",
+            ),
+        }
+    }
+}
+
+/// Renders a schema as a directed graph in the DOT language of Graphviz, with a node for each
+/// object, array, map, union and scalar value, and edges from each to the values it contains,
+/// labelled with the names of fields. Edges to optional fields are dashed.
+pub(crate) fn to_dot(schema: &SchemaState) -> String {
+    let mut graph = DotGraph { lines: vec![] };
+    graph.add(schema, "$", false);
+    format!(
+        "digraph schema {{\n  rankdir=LR;\n{}\n}}",
+        graph.lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NumberType, StringType};
+
+    #[test]
+    fn renders_nested_fields_as_edges() {
+        let schema = SchemaState::Array {
+            min_length: 1,
+            max_length: 3,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: indexmap::IndexMap::from_iter([(
+                    "id".to_string(),
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 10 }),
+                )]),
+                optional: indexmap::IndexMap::from_iter([(
+                    "tags".to_string(),
+                    SchemaState::Nullable(Box::new(SchemaState::Array {
+                        min_length: 0,
+                        max_length: 2,
+                        lengths: None,
+                        unique_items: false,
+                        schema: Box::new(SchemaState::String(StringType::UUID)),
+                    })),
+                )]),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        };
+        assert_eq!(
+            to_dot(&schema),
+            r#"digraph schema {
+  rankdir=LR;
+  "$" [label="array (1-3)", shape=box];
+  "$" -> "$[]" [label="[]"];
+  "$[]" [label="object", shape=box];
+  "$[]" -> "$[].id" [label="id"];
+  "$[].id" [label="int (1-10)", shape=ellipse];
+  "$[]" -> "$[].tags" [label="tags (optional)", style=dashed];
+  "$[].tags" [label="nullable array (0-2)", shape=box];
+  "$[].tags" -> "$[].tags[]" [label="[]"];
+  "$[].tags[]" [label="string (uuid)", shape=ellipse];
+}"#
+        );
+    }
+
+    #[test]
+    fn escapes_field_names_and_tells_union_variants_apart() {
+        let object = |key: &str| SchemaState::Object {
+            required: indexmap::IndexMap::from_iter([(key.to_string(), SchemaState::Boolean)]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let schema = SchemaState::Union(vec![object("say \"hi\""), object("ok")]);
+        let dot = to_dot(&schema);
+        assert!(dot.contains(r#""$" -> "$|1" [label="variant 2"];"#));
+        assert!(dot.contains(r#""$|0" -> "$|0.say \"hi\"" [label="say \"hi\""];"#));
+        assert_eq!(dot.matches("label=\"boolean\"").count(), 2);
+    }
+}
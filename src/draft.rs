@@ -0,0 +1,212 @@
+use serde_json::{Map, Value};
+
+/// The drafts of JSON Schema that documents can be generated for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaDraft {
+    /// Draft 2020-12, the latest, which drivel generates schemas in.
+    #[default]
+    #[value(name = "2020-12")]
+    Draft2020_12,
+    /// Draft 7, with tuples as arrays of `items`, definitions in `definitions`, and no
+    /// `$defs` or `prefixItems`.
+    #[value(name = "07")]
+    Draft07,
+    /// Draft 4, as draft 7, but with exclusive bounds as booleans beside `minimum` and
+    /// `maximum`, and `id` in place of `$id`.
+    #[value(name = "04")]
+    Draft04,
+}
+
+impl SchemaDraft {
+    /// The URI of the meta-schema of the draft, for the `$schema` of a document.
+    pub fn uri(&self) -> &'static str {
+        match self {
+            SchemaDraft::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+            SchemaDraft::Draft07 => "http://json-schema.org/draft-07/schema#",
+            SchemaDraft::Draft04 => "http://json-schema.org/draft-04/schema#",
+        }
+    }
+}
+
+/// The keywords whose values are maps of names to subschemas.
+const SCHEMA_MAPS: [&str; 4] = ["properties", "patternProperties", "$defs", "definitions"];
+
+/// The keywords whose values are a subschema, or for `items` of draft 7 and earlier, a list of
+/// them.
+const SCHEMAS: [&str; 10] = [
+    "items",
+    "additionalItems",
+    "additionalProperties",
+    "not",
+    "contains",
+    "propertyNames",
+    "if",
+    "then",
+    "else",
+    "unevaluatedProperties",
+];
+
+/// The keywords whose values are lists of subschemas.
+const SCHEMA_LISTS: [&str; 4] = ["oneOf", "anyOf", "allOf", "prefixItems"];
+
+/// Rewrites a JSON Schema document of draft 2020-12, as generated by drivel, into the given
+/// draft, for the keywords drivel generates that the draft spells differently. The values of
+/// `enum`, `default` and extension keywords are data, and are left as they are.
+pub(crate) fn convert(document: &mut Value, draft: SchemaDraft) {
+    if draft == SchemaDraft::Draft2020_12 {
+        return;
+    }
+    if let Some(document_obj) = document.as_object_mut() {
+        document_obj.insert("$schema".to_string(), Value::from(draft.uri()));
+        if let Some(defs) = document_obj.remove("$defs") {
+            document_obj.insert("definitions".to_string(), defs);
+        }
+    }
+    convert_schema(document, draft);
+}
+
+fn convert_schema(schema: &mut Value, draft: SchemaDraft) {
+    let Some(schema_obj) = schema.as_object_mut() else {
+        return;
+    };
+    convert_keywords(schema_obj, draft);
+    for (keyword, value) in schema_obj.iter_mut() {
+        match (keyword.as_str(), value) {
+            (keyword, Value::Object(schemas)) if SCHEMA_MAPS.contains(&keyword) => {
+                for schema in schemas.values_mut() {
+                    convert_schema(schema, draft);
+                }
+            }
+            (keyword, Value::Array(schemas))
+                if SCHEMA_LISTS.contains(&keyword) || keyword == "items" =>
+            {
+                for schema in schemas {
+                    convert_schema(schema, draft);
+                }
+            }
+            (keyword, schema) if SCHEMAS.contains(&keyword) => convert_schema(schema, draft),
+            _ => {}
+        }
+    }
+}
+
+fn convert_keywords(schema_obj: &mut Map<String, Value>, draft: SchemaDraft) {
+    if let Some(Value::String(reference)) = schema_obj.get_mut("$ref") {
+        if let Some(name) = reference.strip_prefix("#/$defs/") {
+            *reference = format!("#/definitions/{}", name);
+        }
+    }
+    // tuples list the schemas of their positions as `items`, and those of any more elements as
+    // `additionalItems`
+    if let Some(positions) = schema_obj.remove("prefixItems") {
+        if let Some(rest) = schema_obj.remove("items") {
+            schema_obj.insert("additionalItems".to_string(), rest);
+        }
+        schema_obj.insert("items".to_string(), positions);
+    }
+    // anchors are `$id`s of just a fragment before draft 2019-09
+    if let Some(Value::String(anchor)) = schema_obj.remove("$anchor") {
+        schema_obj.insert("$id".to_string(), Value::String(format!("#{}", anchor)));
+    }
+    if draft == SchemaDraft::Draft04 {
+        if let Some(id) = schema_obj.remove("$id") {
+            schema_obj.insert("id".to_string(), id);
+        }
+        for (exclusive, inclusive) in [
+            ("exclusiveMinimum", "minimum"),
+            ("exclusiveMaximum", "maximum"),
+        ] {
+            if let Some(bound @ Value::Number(_)) = schema_obj.remove(exclusive) {
+                schema_obj.insert(inclusive.to_string(), bound);
+                schema_obj.insert(exclusive.to_string(), Value::Bool(true));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "$schema": SchemaDraft::Draft2020_12.uri(),
+            "$id": "https://example.com/place.json",
+            "type": "object",
+            "properties": {
+                "position": {
+                    "type": "array",
+                    "prefixItems": [{"type": "number"}, {"type": "number"}],
+                    "items": false
+                },
+                "rating": {"type": "integer", "exclusiveMinimum": 0, "maximum": 5},
+                "owner": {"$ref": "#/$defs/owner"},
+                "prefixItems": {"type": "string", "x-drivel-value-counts": [{"value": "$id"}]}
+            },
+            "$defs": {
+                "owner": {"type": "object", "properties": {"$id": {"type": "string"}}},
+                "area": {
+                    "$anchor": "area",
+                    "type": "object",
+                    "properties": {"parts": {"type": "array", "items": {"$ref": "#area"}}}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn converts_to_draft_07() {
+        let mut schema = document();
+        convert(&mut schema, SchemaDraft::Draft07);
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(schema["$id"], "https://example.com/place.json");
+        assert_eq!(
+            schema["properties"]["position"],
+            json!({
+                "type": "array",
+                "items": [{"type": "number"}, {"type": "number"}],
+                "additionalItems": false
+            })
+        );
+        assert_eq!(schema["properties"]["rating"]["exclusiveMinimum"], 0);
+        assert_eq!(schema["properties"]["owner"]["$ref"], "#/definitions/owner");
+        assert_eq!(schema.get("$defs"), None);
+        // property names are not keywords
+        assert_eq!(
+            schema["definitions"]["owner"]["properties"]["$id"]["type"],
+            "string"
+        );
+        assert_eq!(schema["properties"]["prefixItems"]["type"], "string");
+        assert_eq!(schema["definitions"]["area"]["$id"], "#area");
+        assert_eq!(
+            schema["definitions"]["area"]["properties"]["parts"]["items"]["$ref"],
+            "#area"
+        );
+    }
+
+    #[test]
+    fn converts_to_draft_04() {
+        let mut schema = document();
+        convert(&mut schema, SchemaDraft::Draft04);
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-04/schema#");
+        assert_eq!(schema["id"], "https://example.com/place.json");
+        assert_eq!(schema.get("$id"), None);
+        assert_eq!(
+            schema["properties"]["rating"],
+            json!({"type": "integer", "minimum": 0, "exclusiveMinimum": true, "maximum": 5})
+        );
+        assert_eq!(
+            schema["definitions"]["owner"]["properties"]["$id"],
+            json!({"type": "string"})
+        );
+        assert_eq!(schema["definitions"]["area"]["id"], "#area");
+    }
+
+    #[test]
+    fn leaves_draft_2020_12_as_it_is() {
+        let mut schema = document();
+        convert(&mut schema, SchemaDraft::Draft2020_12);
+        assert_eq!(schema, document());
+    }
+}
@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::produce_options::field_path;
+
+/// What to do with objects of JSON input that have the same key more than once, such as
+/// `{"id": 1, "id": 2}`, of which only the last value is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DuplicateKeys {
+    /// Keep the last value of the key, without a word.
+    #[default]
+    KeepLast,
+    /// Keep the last value of the key, and warn about it.
+    Warn,
+    /// Fail to read the input.
+    Error,
+}
+
+/// A key that an object of JSON input has more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DuplicateKey {
+    /// The index of the value that holds the object, among the values of the input.
+    pub index: usize,
+    /// The path of the object in the value, such as `orders[0]`, where the root is the empty
+    /// path.
+    pub path: String,
+    pub key: String,
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "duplicate key {:?}", self.key)
+        } else {
+            write!(f, "duplicate key {:?} at {}", self.key, self.path)
+        }
+    }
+}
+
+/// Visits a value, recording the duplicate keys of its objects, as `serde_json` would otherwise
+/// silently keep the last value of each.
+struct Checked<'a> {
+    index: usize,
+    path: String,
+    duplicates: &'a RefCell<Vec<DuplicateKey>>,
+}
+
+impl<'de> DeserializeSeed<'de> for Checked<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Checked<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut i = 0;
+        while seq
+            .next_element_seed(Checked {
+                index: self.index,
+                path: format!("{}[{}]", self.path, i),
+                duplicates: self.duplicates,
+            })?
+            .is_some()
+        {
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value_seed(Checked {
+                index: self.index,
+                path: field_path(&self.path, &key),
+                duplicates: self.duplicates,
+            })?;
+            if !keys.insert(key.clone()) {
+                self.duplicates.borrow_mut().push(DuplicateKey {
+                    index: self.index,
+                    path: self.path.clone(),
+                    key,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The keys that objects of the JSON values of `input` have more than once, such as of a single
+/// document or of each line of JSON lines. Values after one that isn't JSON, such as YAML or
+/// JSON with comments, are not checked.
+pub(crate) fn find_duplicate_keys(input: &str) -> Vec<DuplicateKey> {
+    let duplicates = RefCell::new(vec![]);
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    for index in 0.. {
+        let checked = Checked {
+            index,
+            path: String::new(),
+            duplicates: &duplicates,
+        };
+        // stops at the end of the input, and at the first value that isn't JSON
+        if checked.deserialize(&mut deserializer).is_err() {
+            break;
+        }
+    }
+    duplicates.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_duplicate_keys_of_nested_objects() {
+        let input = r#"{"id": 1, "orders": [{"sku": "a"}, {"sku": "b", "sku": "c"}], "id": 2}
+            {"id": 3} {"a": {"b": 1, "b": 2}}"#;
+        let found = find_duplicate_keys(input)
+            .into_iter()
+            .map(|duplicate| (duplicate.index, duplicate.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (0, "duplicate key \"sku\" at orders[1]".to_string()),
+                (0, "duplicate key \"id\"".to_string()),
+                (2, "duplicate key \"b\" at a".to_string()),
+            ]
+        );
+        assert_eq!(find_duplicate_keys(r#"{"a": 1, "b": {"a": 2}}"#), vec![]);
+        assert_eq!(find_duplicate_keys("a: 1\na: 2"), vec![]);
+    }
+}
@@ -0,0 +1,96 @@
+use rand::Rng;
+
+use crate::StringType;
+
+lazy_static! {
+    static ref DURATION_REGEX: regex::Regex =
+        regex::Regex::new(r"^P(\d+Y)?(\d+M)?(\d+W)?(\d+D)?(T(\d+H)?(\d+M)?(\d+([.,]\d+)?S)?)?$")
+            .unwrap();
+}
+
+/// Whether a string is an ISO 8601 duration, such as `PT1H30M` or `P3DT12H`, with at least one
+/// component, and at least one time component after a `T`.
+fn is_duration(s: &str) -> bool {
+    s.len() > 1 && !s.ends_with('T') && DURATION_REGEX.is_match(s)
+}
+
+/// Recognises ISO 8601 durations.
+pub(crate) fn infer_duration(s: &str) -> Option<StringType> {
+    if s.starts_with('P') && is_duration(s) {
+        Some(StringType::Duration)
+    } else {
+        None
+    }
+}
+
+/// Produces a random ISO 8601 duration of up to a few years, with some of its date and time
+/// components, and never mixing weeks with other components (as RFC 3339 requires).
+pub(crate) fn produce_duration<R: Rng + ?Sized>(rng: &mut R) -> String {
+    if rng.gen_bool(0.1) {
+        return format!("P{}W", rng.gen_range(1..=52));
+    }
+    let components = [
+        ("Y", 0..=3),
+        ("M", 0..=11),
+        ("D", 0..=30),
+        ("H", 0..=23),
+        ("M", 0..=59),
+        ("S", 0..=59),
+    ]
+    .map(|(unit, range)| (unit, rng.gen_range(range)));
+    let format = |components: &[(&str, u32)]| {
+        components
+            .iter()
+            .filter(|(_, value)| *value > 0)
+            .map(|(unit, value)| format!("{}{}", value, unit))
+            .collect::<String>()
+    };
+    let (date, time) = components.split_at(3);
+    let (date, time) = (format(date), format(time));
+    match (date.is_empty(), time.is_empty()) {
+        (true, true) => "PT0S".to_string(),
+        (_, true) => format!("P{}", date),
+        _ => format!("P{}T{}", date, time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn recognises_durations() {
+        for duration in [
+            "PT1H30M",
+            "P3DT12H",
+            "P1Y2M10DT2H30M",
+            "P2W",
+            "PT0.5S",
+            "P0D",
+        ] {
+            assert_eq!(
+                infer_duration(duration),
+                Some(StringType::Duration),
+                "{}",
+                duration
+            );
+        }
+        for not_duration in ["P", "PT", "P1DT", "PT1H30", "1H30M", "P1H", "pt1h", "P-1D"] {
+            assert_eq!(infer_duration(not_duration), None, "{}", not_duration);
+        }
+    }
+
+    #[test]
+    fn produced_values_are_durations() {
+        for _ in 0..200 {
+            let duration = produce_duration(&mut thread_rng());
+            assert_eq!(
+                infer_duration(&duration),
+                Some(StringType::Duration),
+                "{}",
+                duration
+            );
+        }
+    }
+}
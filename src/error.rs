@@ -0,0 +1,142 @@
+use std::fmt;
+
+use crate::{DecodeError, LoadSchemaError, ParseSchemaError, ProduceError};
+
+/// Any error returned by drivel, for callers that combine several steps (such as decoding input,
+/// inferring or loading a schema, and producing data from it) and want a single error type.
+///
+/// Each of the library's functions returns the error type of its own step, which converts into
+/// this one with `?`.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{load_schema, try_produce_with, DrivelError, ProduceOptions};
+///
+/// fn produce_from_saved(saved: &str) -> Result<serde_json::Value, DrivelError> {
+///     let schema = load_schema(saved)?;
+///     Ok(try_produce_with(&schema, 1, &ProduceOptions::default())?)
+/// }
+///
+/// assert!(matches!(
+///     produce_from_saved("not a schema"),
+///     Err(DrivelError::LoadSchema(_))
+/// ));
+/// ```
+#[derive(Debug)]
+pub enum DrivelError {
+    /// Reading input or writing output failed.
+    Io(std::io::Error),
+    /// Input could not be decoded as text.
+    Decode(DecodeError),
+    /// Input is not valid JSON (or YAML).
+    Parse(String),
+    /// A JSON Schema could not be parsed, or uses features that are not supported.
+    Schema(ParseSchemaError),
+    /// A schema saved with [`save_schema`](crate::save_schema) could not be loaded.
+    LoadSchema(LoadSchemaError),
+    /// Data could not be produced from a schema.
+    Produce(ProduceError),
+}
+
+impl fmt::Display for DrivelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrivelError::Io(err) => write!(f, "{}", err),
+            DrivelError::Decode(err) => write!(f, "{}", err),
+            DrivelError::Parse(msg) => write!(f, "Invalid input: {}", msg),
+            DrivelError::Schema(err) => write!(f, "{}", err),
+            DrivelError::LoadSchema(err) => write!(f, "{}", err),
+            DrivelError::Produce(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DrivelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DrivelError::Io(err) => Some(err),
+            DrivelError::Decode(err) => Some(err),
+            DrivelError::Parse(_) => None,
+            DrivelError::Schema(err) => Some(err),
+            DrivelError::LoadSchema(err) => Some(err),
+            DrivelError::Produce(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for DrivelError {
+    fn from(err: std::io::Error) -> Self {
+        DrivelError::Io(err)
+    }
+}
+
+impl From<DecodeError> for DrivelError {
+    fn from(err: DecodeError) -> Self {
+        DrivelError::Decode(err)
+    }
+}
+
+impl From<serde_json::Error> for DrivelError {
+    fn from(err: serde_json::Error) -> Self {
+        DrivelError::Parse(err.to_string())
+    }
+}
+
+impl From<ParseSchemaError> for DrivelError {
+    fn from(err: ParseSchemaError) -> Self {
+        DrivelError::Schema(err)
+    }
+}
+
+impl From<LoadSchemaError> for DrivelError {
+    fn from(err: LoadSchemaError) -> Self {
+        DrivelError::LoadSchema(err)
+    }
+}
+
+impl From<ProduceError> for DrivelError {
+    fn from(err: ProduceError) -> Self {
+        DrivelError::Produce(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_input, parse_json_schema};
+    use serde_json::json;
+
+    fn decode_then_parse(bytes: &[u8]) -> Result<serde_json::Value, DrivelError> {
+        let schema: serde_json::Value = serde_json::from_str(&decode_input(bytes, None)?)?;
+        parse_json_schema(&schema)?;
+        Ok(schema)
+    }
+
+    #[test]
+    fn converts_errors_of_each_step() {
+        assert!(matches!(
+            decode_then_parse(&[0xC3, 0x28]),
+            Err(DrivelError::Decode(_))
+        ));
+        assert!(matches!(
+            decode_then_parse(b"{\"type\": "),
+            Err(DrivelError::Parse(_))
+        ));
+        assert!(matches!(
+            decode_then_parse(b"{\"type\": \"integer\", \"minimum\": 2, \"maximum\": 1}"),
+            Err(DrivelError::Schema(ParseSchemaError::ValidationFailed(_)))
+        ));
+        assert_eq!(
+            decode_then_parse(b"{\"type\": \"boolean\"}").unwrap(),
+            json!({"type": "boolean"})
+        );
+    }
+
+    #[test]
+    fn exposes_the_underlying_error_as_source() {
+        let err = DrivelError::from(std::io::Error::other("disk on fire"));
+        assert_eq!(err.to_string(), "disk on fire");
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
@@ -0,0 +1,677 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::{
+    produce::DEFAULT_MAX_RECURSION_DEPTH,
+    produce_options::{field_path, items_path},
+    ExtendedJsonType, FieldAccess, NumberType, ProduceOptions, SchemaState, StringType,
+};
+
+/// An estimate of the size of the data that would be produced from a schema, worked out from the
+/// schema alone, without producing any values.
+///
+/// The estimate is of the expected size: arrays and maps are as long as the average of their
+/// observed lengths, or halfway between their bounds, optional fields are present as often as
+/// they would be produced, and values that may be `null` are `null` half of the time. Overrides,
+/// transforms and generators are not taken into account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProduceEstimate {
+    /// The number of records: the elements of an array at the root, or the values produced if
+    /// there is no array at the root.
+    pub records: usize,
+    /// The number of fields of all objects, in all records, including those of nested objects.
+    pub fields: usize,
+    /// The approximate size of the data as minified JSON, in bytes. Pretty-printed JSON is
+    /// larger, by the whitespace of the indentation.
+    pub bytes: usize,
+}
+
+impl fmt::Display for ProduceEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "about {} records with {} fields, {} of minified JSON",
+            self.records,
+            self.fields,
+            human_bytes(self.bytes)
+        )
+    }
+}
+
+/// A number of bytes in the largest unit that it is at least one of, such as `1.5 MB`.
+pub(crate) fn human_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// The expected number of fields and minified size of a value of a schema.
+#[derive(Debug, Default, Clone, Copy)]
+struct Expected {
+    fields: f64,
+    bytes: f64,
+}
+
+impl Expected {
+    fn bytes(bytes: f64) -> Self {
+        Expected { fields: 0.0, bytes }
+    }
+
+    /// The expected size of `n` of these values, without the brackets and commas between them.
+    fn times(self, n: f64) -> Self {
+        Expected {
+            fields: self.fields * n,
+            bytes: self.bytes * n,
+        }
+    }
+
+    /// The expected size of a value that is one of these, each equally likely.
+    fn average(values: impl ExactSizeIterator<Item = Expected>) -> Self {
+        let n = values.len().max(1) as f64;
+        let total = values.fold(Expected::default(), |total, value| Expected {
+            fields: total.fields + value.fields,
+            bytes: total.bytes + value.bytes,
+        });
+        total.times(1.0 / n)
+    }
+
+    /// The expected size of a value that is one of these, each as likely as its weight.
+    fn weighted_average(values: impl Iterator<Item = (Expected, usize)>) -> Self {
+        let (total, weights) = values.fold(
+            (Expected::default(), 0),
+            |(total, weights), (value, weight)| {
+                (
+                    Expected {
+                        fields: total.fields + value.fields * weight as f64,
+                        bytes: total.bytes + value.bytes * weight as f64,
+                    },
+                    weights + weight,
+                )
+            },
+        );
+        total.times(1.0 / weights.max(1) as f64)
+    }
+}
+
+/// The minified size of `n` values of `size` each in an array, with their brackets and commas.
+fn array_bytes(n: f64, size: f64) -> f64 {
+    2.0 + n * size + (n - 1.0).max(0.0)
+}
+
+/// The number of digits of an integer, with its sign.
+fn integer_bytes(n: i64) -> f64 {
+    n.to_string().len() as f64
+}
+
+fn number_bytes(number_type: &NumberType) -> f64 {
+    match number_type {
+        // most integers drawn from a range have about as many digits as its widest end
+        NumberType::Integer { min, max } => integer_bytes(*min).max(integer_bytes(*max)),
+        NumberType::Sequence { start, end, .. } => integer_bytes(*start).max(integer_bytes(*end)),
+        NumberType::Enum { variants } if !variants.is_empty() => {
+            variants.keys().map(|n| integer_bytes(*n)).sum::<f64>() / variants.len() as f64
+        }
+        NumberType::Enum { .. } => 1.0,
+        NumberType::Flags { .. } => {
+            integer_bytes(NumberType::flag_mask(&number_type.flags())).max(1.0)
+        }
+        // floats are written with about as many digits as it takes to read them back
+        NumberType::Float { .. } => 18.0,
+        // multiples have as many decimals as their step, and about as many digits as the
+        // widest end of their range
+        NumberType::Multiple { .. } => {
+            let (lowest, highest) = number_type.multiples();
+            let bytes = |steps| number_type.multiple(steps).to_string().len() as f64;
+            bytes(lowest).max(bytes(highest))
+        }
+    }
+}
+
+/// The expected length of a string, without its quotes.
+fn string_length(string_type: &StringType) -> f64 {
+    let average_length = |variants: &std::collections::BTreeSet<String>| {
+        let total = variants.iter().map(|v| v.chars().count()).sum::<usize>();
+        total as f64 / variants.len().max(1) as f64
+    };
+    match string_type {
+        StringType::Unknown {
+            min_length,
+            max_length,
+            ..
+        } => {
+            let min = min_length.unwrap_or(0);
+            let max = max_length.unwrap_or(min.max(32));
+            (min + max) as f64 / 2.0
+        }
+        StringType::IsoDate => 10.0,
+        StringType::DateTimeISO8601 => 29.0,
+        StringType::DateTimeRFC2822 => 31.0,
+        // a format's fields are mostly about as long as their specifiers, such as `%Y` and `%m`
+        StringType::CustomDateTime { formats } => formats[0].len() as f64 + 2.0,
+        StringType::UUID => 36.0,
+        StringType::Email => 20.0,
+        StringType::Url => 30.0,
+        StringType::Hostname => 18.0,
+        StringType::Ipv4 => 13.0,
+        StringType::Ipv6 => 39.0,
+        StringType::FixedWidthNumeric { width } => *width as f64,
+        StringType::Numeric { number } => number_bytes(number),
+        StringType::CreditCard => 16.0,
+        StringType::Iban => 22.0,
+        StringType::Isbn10 => 10.0,
+        StringType::Isbn13 | StringType::Ean13 => 13.0,
+        StringType::Duration => 8.0,
+        StringType::Charset {
+            min_length,
+            max_length,
+            ..
+        } => (min_length + max_length) as f64 / 2.0,
+        // a pattern is about as long as the strings that match it, give or take its syntax
+        StringType::Pattern {
+            pattern,
+            min_length,
+            max_length,
+        } => {
+            let min = min_length.unwrap_or(0);
+            let max = max_length.unwrap_or(min.max(pattern.chars().count()));
+            (min + max) as f64 / 2.0
+        }
+        StringType::Enum { variants } => average_length(variants),
+        StringType::OpenEnum {
+            variants,
+            other_ratio,
+            other,
+        } => {
+            let other_ratio = other_ratio.clamp(0.0, 1.0);
+            other_ratio * string_length(other) + (1.0 - other_ratio) * average_length(variants)
+        }
+    }
+}
+
+fn string_bytes(string_type: &StringType) -> f64 {
+    2.0 + string_length(string_type)
+}
+
+/// The size of a field in an object, other than that of its value: its quoted name, the colon and
+/// the comma after it.
+fn field_bytes(key: &str) -> f64 {
+    key.len() as f64 + 4.0
+}
+
+/// The expected length of an array or map, from its observed lengths if they were tracked.
+fn expected_length(
+    min_length: usize,
+    max_length: usize,
+    lengths: Option<&std::collections::BTreeMap<usize, usize>>,
+) -> f64 {
+    match lengths {
+        Some(lengths) if !lengths.is_empty() => {
+            let total = lengths.values().sum::<usize>() as f64;
+            lengths
+                .iter()
+                .map(|(length, count)| (length * count) as f64)
+                .sum::<f64>()
+                / total
+        }
+        _ => (min_length + max_length) as f64 / 2.0,
+    }
+}
+
+/// A recursive definition with the values of itself within it expanded as many levels deep as
+/// values are produced, `max_depth`, and what would go deeper left out as it is when producing
+/// them, so that the sizes of its levels can be added up.
+fn expand<'s>(
+    schema: &'s SchemaState,
+    max_depth: usize,
+    recursion: &mut Vec<(&'s str, &'s SchemaState)>,
+) -> SchemaState {
+    let levels = |recursion: &[(&str, &SchemaState)], name: &str| {
+        recursion.iter().filter(|(n, _)| *n == name).count()
+    };
+    let cut = |name: &str| levels(recursion, name) > max_depth;
+    let too_deep = |schema: &SchemaState| {
+        recursion.iter().any(|(name, _)| cut(name)) && !schema.can_avoid(&cut)
+    };
+    let shallow = |variants: Vec<&'s SchemaState>| {
+        let shallow: Vec<_> = variants.iter().copied().filter(|v| !too_deep(v)).collect();
+        if shallow.is_empty() {
+            variants
+        } else {
+            shallow
+        }
+    };
+    let (schema, name) = match schema {
+        SchemaState::Recursive { name, schema } => (schema.as_ref(), name.as_str()),
+        SchemaState::Reference(name) => {
+            match recursion.iter().rev().find(|(n, _)| n == name).copied() {
+                Some((name, definition)) if !cut(name) => (definition, name),
+                _ => return SchemaState::Null,
+            }
+        }
+        SchemaState::Nullable(inner) if too_deep(inner) => return SchemaState::Null,
+        SchemaState::Nullable(inner) => {
+            return SchemaState::Nullable(Box::new(expand(inner, max_depth, recursion)))
+        }
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            unique_items,
+            schema,
+        } => {
+            let empty = too_deep(schema);
+            return SchemaState::Array {
+                min_length: if empty { 0 } else { *min_length },
+                max_length: if empty { 0 } else { *max_length },
+                lengths: lengths.clone().filter(|_| !empty),
+                unique_items: *unique_items,
+                schema: Box::new(expand(schema, max_depth, recursion)),
+            };
+        }
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => {
+            let empty = too_deep(schema);
+            return SchemaState::Map {
+                min_length: if empty { 0 } else { *min_length },
+                max_length: if empty { 0 } else { *max_length },
+                keys: keys.clone(),
+                schema: Box::new(expand(schema, max_depth, recursion)),
+            };
+        }
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            annotations,
+        } => {
+            let optional: Vec<_> = optional.iter().filter(|(_, f)| !too_deep(f)).collect();
+            return SchemaState::Object {
+                required: required
+                    .iter()
+                    .map(|(k, f)| (k.clone(), expand(f, max_depth, recursion)))
+                    .collect(),
+                optional: optional
+                    .into_iter()
+                    .map(|(k, f)| (k.clone(), expand(f, max_depth, recursion)))
+                    .collect(),
+                access: access.clone(),
+                annotations: annotations.clone(),
+            };
+        }
+        SchemaState::Tuple(positions) => {
+            return SchemaState::Tuple(
+                positions
+                    .iter()
+                    .map(|position| expand(position, max_depth, recursion))
+                    .collect(),
+            )
+        }
+        SchemaState::Union(variants) => {
+            let variants = shallow(variants.iter().collect());
+            return SchemaState::Union(
+                variants
+                    .into_iter()
+                    .map(|variant| expand(variant, max_depth, recursion))
+                    .collect(),
+            );
+        }
+        SchemaState::Mixed(variants) => {
+            let shallow = shallow(variants.iter().map(|(variant, _)| variant).collect());
+            return SchemaState::Mixed(
+                variants
+                    .iter()
+                    .filter(|(variant, _)| shallow.contains(&variant))
+                    .map(|(variant, count)| (expand(variant, max_depth, recursion), *count))
+                    .collect(),
+            );
+        }
+        SchemaState::TaggedUnion { tag, variants } => {
+            let shallow = shallow(variants.values().collect());
+            return SchemaState::TaggedUnion {
+                tag: tag.clone(),
+                variants: variants
+                    .iter()
+                    .filter(|(_, variant)| shallow.contains(variant))
+                    .map(|(value, variant)| (value.clone(), expand(variant, max_depth, recursion)))
+                    .collect(),
+            };
+        }
+        schema => return schema.clone(),
+    };
+    recursion.push((name, schema));
+    let expanded = expand(schema, max_depth, recursion);
+    recursion.pop();
+    expanded
+}
+
+fn expected(schema: &SchemaState, options: &ProduceOptions, path: &str) -> Expected {
+    match schema {
+        SchemaState::Initial | SchemaState::Null | SchemaState::Indefinite => Expected::bytes(4.0),
+        SchemaState::Recursive { .. } => {
+            let max_depth = options
+                .max_recursion_depth
+                .unwrap_or(DEFAULT_MAX_RECURSION_DEPTH);
+            expected(&expand(schema, max_depth, &mut vec![]), options, path)
+        }
+        // only a definition that always contains itself gets here, which has no values
+        SchemaState::Reference(_) => Expected::bytes(4.0),
+        SchemaState::Nullable(inner) => {
+            let null = options.null_probability.unwrap_or(0.5);
+            let value = expected(inner, options, path).times(1.0 - null);
+            Expected {
+                bytes: value.bytes + null * 4.0,
+                ..value
+            }
+        }
+        SchemaState::Boolean => Expected::bytes(4.5),
+        SchemaState::Literal(values) => Expected::bytes(
+            values
+                .iter()
+                .map(|value| value.to_string().len())
+                .sum::<usize>() as f64
+                / values.len().max(1) as f64,
+        ),
+        SchemaState::String(string_type) => Expected::bytes(string_bytes(string_type)),
+        SchemaState::Number(number_type) => Expected::bytes(number_bytes(number_type)),
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            schema,
+            ..
+        } => {
+            let n = expected_length(*min_length, *max_length, lengths.as_ref());
+            let item = expected(schema, options, &items_path(path));
+            Expected {
+                fields: item.fields * n,
+                bytes: array_bytes(n, item.bytes),
+            }
+        }
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => {
+            let n = expected_length(*min_length, *max_length, None);
+            let value = expected(schema, options, &field_path(path, "*"));
+            Expected {
+                fields: n * (1.0 + value.fields),
+                bytes: 2.0 + n * (string_bytes(keys) + 2.0 + value.bytes),
+            }
+        }
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            ..
+        } => {
+            let omitted = |key: &String| {
+                options.omit_read_only && access.get(key) == Some(&FieldAccess::ReadOnly)
+            };
+            let present = |key: &String| {
+                options.optional_include_probability.unwrap_or_else(|| {
+                    options
+                        .field_stats
+                        .as_ref()
+                        .and_then(|stats| stats.get(&field_path(path, key)))
+                        .map_or(0.5, |counts| counts.presence())
+                })
+            };
+            let fields = required
+                .iter()
+                .map(|field| (field, 1.0))
+                .chain(optional.iter().map(|field| (field, present(field.0))))
+                .filter(|((key, _), _)| !omitted(key));
+            let mut total = Expected::bytes(2.0);
+            for ((key, schema), probability) in fields {
+                let value = expected(schema, options, &field_path(path, key));
+                total.fields += probability * (1.0 + value.fields);
+                total.bytes += probability * (field_bytes(key) + value.bytes);
+            }
+            total
+        }
+        SchemaState::ExtendedJson(extended_type) => Expected::bytes(match extended_type {
+            // `{"$oid":"…"}` with 24 hex digits
+            ExtendedJsonType::ObjectId => 35.0,
+            // `{"$date":"…"}` with a date-time in milliseconds
+            ExtendedJsonType::Date => 36.0,
+            ExtendedJsonType::Long { min, max } => {
+                18.0 + integer_bytes(*min).max(integer_bytes(*max))
+            }
+        }),
+        SchemaState::Coordinates(positions) => {
+            let sizes = positions.iter().map(number_bytes).sum::<f64>();
+            Expected::bytes(array_bytes(positions.len() as f64, 0.0) + sizes)
+        }
+        SchemaState::Tuple(positions) => {
+            let positions: Vec<Expected> = positions
+                .iter()
+                .map(|position| expected(position, options, &items_path(path)))
+                .collect();
+            Expected {
+                fields: positions.iter().map(|position| position.fields).sum(),
+                bytes: array_bytes(positions.len() as f64, 0.0)
+                    + positions.iter().map(|position| position.bytes).sum::<f64>(),
+            }
+        }
+        SchemaState::GeoPoint {
+            latitude_key,
+            longitude_key,
+            latitude,
+            longitude,
+        } => Expected {
+            fields: 2.0,
+            bytes: 2.0
+                + field_bytes(latitude_key)
+                + field_bytes(longitude_key)
+                + number_bytes(latitude)
+                + number_bytes(longitude),
+        },
+        SchemaState::Money {
+            amount_key,
+            currency_key,
+            amount,
+            ..
+        } => Expected {
+            fields: 2.0,
+            // a quoted currency code is 5 bytes
+            bytes: 2.0
+                + field_bytes(amount_key)
+                + field_bytes(currency_key)
+                + number_bytes(amount)
+                + 5.0,
+        },
+        SchemaState::Union(variants) => Expected::average(
+            variants
+                .iter()
+                .map(|variant| expected(variant, options, path)),
+        ),
+        SchemaState::Mixed(variants) => Expected::weighted_average(
+            variants
+                .iter()
+                .map(|(variant, count)| (expected(variant, options, path), *count)),
+        ),
+        SchemaState::TaggedUnion { variants, .. } => Expected::average(
+            variants
+                .values()
+                .map(|variant| expected(variant, options, path)),
+        ),
+    }
+}
+
+/// Estimates the size of the data that [`produce_with`](crate::produce_with) would produce from
+/// `schema` with the same `repeat_n` and options, without producing it: for an array at the root,
+/// `repeat_n` elements if it is more than one, and otherwise as many as its bounds allow. Without
+/// an array at the root, a `repeat_n` of more than one estimates that many values in an array,
+/// as produced by [`try_produce_records`](crate::try_produce_records).
+///
+/// # Example
+///
+/// ```
+/// use drivel::{estimate_produce, ProduceOptions, SchemaState, StringType};
+///
+/// let schema = SchemaState::Array {
+///     min_length: 10,
+///     max_length: 20,
+///     lengths: None,
+///     unique_items: false,
+///     schema: Box::new(SchemaState::String(StringType::UUID)),
+/// };
+/// let estimate = estimate_produce(&schema, 1000, &ProduceOptions::default());
+/// assert_eq!(estimate.records, 1000);
+/// // 1000 quoted UUIDs, with commas between them and brackets around them
+/// assert_eq!(estimate.bytes, 39001);
+/// ```
+pub fn estimate_produce(
+    schema: &SchemaState,
+    repeat_n: usize,
+    options: &ProduceOptions,
+) -> ProduceEstimate {
+    let (records, total) = match schema {
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            schema,
+            ..
+        } => {
+            let n = if repeat_n > 1 {
+                repeat_n as f64
+            } else {
+                expected_length(*min_length, *max_length, lengths.as_ref())
+            };
+            let item = expected(schema, options, &items_path(""));
+            (
+                n,
+                Expected {
+                    fields: item.fields * n,
+                    bytes: array_bytes(n, item.bytes),
+                },
+            )
+        }
+        _ if repeat_n > 1 => {
+            let n = repeat_n as f64;
+            let record = expected(schema, options, "");
+            (
+                n,
+                Expected {
+                    fields: record.fields * n,
+                    bytes: array_bytes(n, record.bytes),
+                },
+            )
+        }
+        _ => (1.0, expected(schema, options, "")),
+    };
+    ProduceEstimate {
+        records: records.round() as usize,
+        fields: total.fields.round() as usize,
+        bytes: total.bytes.round() as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{produce_with, NumberType};
+    use indexmap::IndexMap;
+
+    fn order() -> SchemaState {
+        SchemaState::Object {
+            required: IndexMap::from([
+                (
+                    "id".to_string(),
+                    SchemaState::Number(NumberType::Integer {
+                        min: 1000,
+                        max: 9999,
+                    }),
+                ),
+                (
+                    "lines".to_string(),
+                    SchemaState::Array {
+                        min_length: 1,
+                        max_length: 5,
+                        lengths: None,
+                        unique_items: false,
+                        schema: Box::new(SchemaState::Object {
+                            required: IndexMap::from([(
+                                "sku".to_string(),
+                                SchemaState::String(StringType::UUID),
+                            )]),
+                            optional: IndexMap::from([("gift".to_string(), SchemaState::Boolean)]),
+                            access: Default::default(),
+                            annotations: Default::default(),
+                        }),
+                    },
+                ),
+            ]),
+            optional: IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        }
+    }
+
+    #[test]
+    fn counts_records_and_the_fields_of_nested_objects() {
+        let estimate = estimate_produce(&order(), 100, &ProduceOptions::default());
+        assert_eq!(estimate.records, 100);
+        // an id and lines, and three lines of a sku and half of the time a gift
+        assert_eq!(estimate.fields, 650);
+    }
+
+    #[test]
+    fn follows_the_length_distribution_of_arrays() {
+        let schema = SchemaState::Array {
+            min_length: 1,
+            max_length: 100,
+            lengths: Some(std::collections::BTreeMap::from([(1, 9), (91, 1)])),
+            unique_items: false,
+            schema: Box::new(order()),
+        };
+        let estimate = estimate_produce(&schema, 1, &ProduceOptions::default());
+        assert_eq!(estimate.records, 10);
+    }
+
+    #[test]
+    fn is_close_to_the_size_of_the_produced_data() {
+        let options = ProduceOptions::default();
+        let estimate = estimate_produce(&order(), 2000, &options);
+        let produced = (0..2000)
+            .map(|_| produce_with(&order(), 1, &options))
+            .collect::<Vec<_>>();
+        let actual = serde_json::Value::Array(produced).to_string().len() as f64;
+        let error = (estimate.bytes as f64 - actual).abs() / actual;
+        assert!(
+            error < 0.1,
+            "estimated {} bytes, produced {}",
+            estimate.bytes,
+            actual
+        );
+    }
+
+    #[test]
+    fn writes_sizes_in_readable_units() {
+        let estimate = ProduceEstimate {
+            records: 1000,
+            fields: 5000,
+            bytes: 1_500_000,
+        };
+        assert_eq!(
+            estimate.to_string(),
+            "about 1000 records with 5000 fields, 1.5 MB of minified JSON"
+        );
+        assert_eq!(human_bytes(999), "999 bytes");
+    }
+}
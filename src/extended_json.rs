@@ -0,0 +1,131 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use fake::{Fake, Faker};
+use rand::Rng;
+use serde_json::{Map, Value};
+
+use crate::ExtendedJsonType;
+
+fn is_object_id(s: &str) -> bool {
+    s.len() == 24 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_date(value: &Value) -> bool {
+    match value {
+        // relaxed format, used for dates between the years 1970 and 9999
+        Value::String(s) => DateTime::parse_from_rfc3339(s).is_ok(),
+        // canonical format, milliseconds since the epoch
+        Value::Object(object) => matches!(
+            as_single_entry(object),
+            Some(("$numberLong", Value::String(s))) if s.parse::<i64>().is_ok()
+        ),
+        // legacy format, milliseconds since the epoch
+        Value::Number(n) => n.is_i64(),
+        _ => false,
+    }
+}
+
+fn as_single_entry(object: &Map<String, Value>) -> Option<(&str, &Value)> {
+    if object.len() == 1 {
+        object.iter().next().map(|(k, v)| (k.as_str(), v))
+    } else {
+        None
+    }
+}
+
+/// Recognises an object that wraps a single value in MongoDB extended JSON, such as
+/// `{"$oid": "507f1f77bcf86cd799439011"}`.
+pub(crate) fn infer_extended_json(object: &Map<String, Value>) -> Option<ExtendedJsonType> {
+    match as_single_entry(object)? {
+        ("$oid", Value::String(s)) if is_object_id(s) => Some(ExtendedJsonType::ObjectId),
+        ("$date", value) if is_date(value) => Some(ExtendedJsonType::Date),
+        ("$numberLong", Value::String(s)) => {
+            let n = s.parse().ok()?;
+            Some(ExtendedJsonType::Long { min: n, max: n })
+        }
+        _ => None,
+    }
+}
+
+/// Produces a value of the given type, wrapped in the same way as MongoDB extended JSON.
+pub(crate) fn produce_extended_json<R: Rng + ?Sized>(
+    extended_type: &ExtendedJsonType,
+    rng: &mut R,
+) -> Value {
+    match extended_type {
+        ExtendedJsonType::ObjectId => {
+            let oid: String = (0..24)
+                .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap())
+                .collect();
+            serde_json::json!({ "$oid": oid })
+        }
+        ExtendedJsonType::Date => {
+            let date_time: DateTime<Utc> = Faker.fake_with_rng(rng);
+            serde_json::json!({ "$date": date_time.to_rfc3339_opts(SecondsFormat::Millis, true) })
+        }
+        ExtendedJsonType::Long { min, max } => {
+            let n = rng.gen_range(*min..=*max);
+            serde_json::json!({ "$numberLong": n.to_string() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn infer(value: Value) -> Option<ExtendedJsonType> {
+        infer_extended_json(value.as_object().unwrap())
+    }
+
+    #[test]
+    fn recognises_object_id() {
+        assert_eq!(
+            infer(json!({"$oid": "507f1f77bcf86cd799439011"})),
+            Some(ExtendedJsonType::ObjectId)
+        );
+        assert_eq!(infer(json!({"$oid": "not an object id"})), None);
+    }
+
+    #[test]
+    fn recognises_dates_in_every_format() {
+        for date in [
+            json!({"$date": "2024-01-01T12:00:00.000Z"}),
+            json!({"$date": {"$numberLong": "1704110400000"}}),
+            json!({"$date": 1704110400000_i64}),
+        ] {
+            assert_eq!(infer(date), Some(ExtendedJsonType::Date));
+        }
+        assert_eq!(infer(json!({"$date": "yesterday"})), None);
+    }
+
+    #[test]
+    fn recognises_long() {
+        assert_eq!(
+            infer(json!({"$numberLong": "-42"})),
+            Some(ExtendedJsonType::Long { min: -42, max: -42 })
+        );
+        assert_eq!(infer(json!({"$numberLong": "4.2"})), None);
+    }
+
+    #[test]
+    fn ignores_other_objects() {
+        assert_eq!(
+            infer(json!({"$oid": "507f1f77bcf86cd799439011", "x": 1})),
+            None
+        );
+        assert_eq!(infer(json!({"oid": "507f1f77bcf86cd799439011"})), None);
+    }
+
+    #[test]
+    fn produced_values_are_recognised() {
+        for extended_type in [
+            ExtendedJsonType::ObjectId,
+            ExtendedJsonType::Date,
+            ExtendedJsonType::Long { min: 5, max: 5 },
+        ] {
+            let value = produce_extended_json(&extended_type, &mut rand::thread_rng());
+            assert_eq!(infer(value), Some(extended_type));
+        }
+    }
+}
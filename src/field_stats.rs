@@ -0,0 +1,249 @@
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::produce_options::{field_path, items_path};
+
+/// How often a field was present in the objects it could occur in, and how often it was `null`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FieldCounts {
+    /// The number of objects at the field's parent path.
+    pub objects: usize,
+    /// The number of those objects that had the field.
+    pub present: usize,
+    /// The number of times the field was present with a `null` value.
+    pub null: usize,
+}
+
+impl FieldCounts {
+    /// The fraction of objects that had the field, between 0 and 1.
+    pub fn presence(&self) -> f64 {
+        ratio(self.present, self.objects)
+    }
+
+    /// The fraction of the field's values that were `null`, between 0 and 1.
+    pub fn null_ratio(&self) -> f64 {
+        ratio(self.null, self.present)
+    }
+}
+
+/// The fewest values a field must have had, all of them different, for it to be unique, as a
+/// handful of values are often all different by chance.
+pub const MIN_UNIQUE_VALUES: usize = 10;
+
+/// The most values of a field whose uniqueness is checked, after which the field is unique if
+/// those were, so that the hashes of the values of huge inputs don't fill up memory.
+const MAX_UNIQUE_VALUES_CHECKED: usize = 1_000_000;
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Statistics about the fields of the objects in some data, by their path (as used by
+/// [`ProduceOptions`](crate::ProduceOptions), such as `orders[].total`), including which
+/// fields are unique: those whose strings or integers, such as IDs and keys, were all
+/// different, of at least [`MIN_UNIQUE_VALUES`] of them.
+///
+/// # Example
+///
+/// ```
+/// use drivel::FieldStats;
+/// use serde_json::json;
+///
+/// let mut stats = FieldStats::default();
+/// stats.add(&json!([{"name": "a", "email": null}, {"name": "b"}]));
+///
+/// let email = stats.get("[].email").unwrap();
+/// assert_eq!(email.presence(), 0.5);
+/// assert_eq!(email.null_ratio(), 1.0);
+///
+/// for id in 0..10 {
+///     stats.add(&json!({"id": id, "kind": id % 2}));
+/// }
+/// assert!(stats.is_unique("id"));
+/// assert!(!stats.is_unique("kind"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FieldStats {
+    /// The number of objects seen at each path.
+    objects: HashMap<String, usize>,
+    /// The path of the parent object of each field, and the number of times the field was
+    /// present and was null, by the path of the field.
+    fields: HashMap<String, (String, usize, usize)>,
+    /// The hashes of the values of each field, while they are all different strings or
+    /// integers, or `None` once they aren't.
+    distinct: HashMap<String, Option<HashSet<u64>>>,
+}
+
+impl FieldStats {
+    /// Adds the fields of the objects in a value, at the root of the data, to the statistics.
+    /// Values that are all inferred as one schema, such as JSON lines, are added one by one.
+    pub fn add(&mut self, value: &Value) {
+        self.add_at(value, "");
+    }
+
+    fn add_at(&mut self, value: &Value, path: &str) {
+        match value {
+            Value::Object(fields) => {
+                *self.objects.entry(path.to_string()).or_insert(0) += 1;
+                for (key, field) in fields {
+                    let field_path = field_path(path, key);
+                    let (_, present, null) = self
+                        .fields
+                        .entry(field_path.clone())
+                        .or_insert_with(|| (path.to_string(), 0, 0));
+                    *present += 1;
+                    if field.is_null() {
+                        *null += 1;
+                    }
+                    self.add_distinct(field, &field_path);
+                    self.add_at(field, &field_path);
+                }
+            }
+            Value::Array(items) => {
+                let path = items_path(path);
+                for item in items {
+                    self.add_at(item, &path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds a value of the field at `path` to the values whose uniqueness is checked.
+    fn add_distinct(&mut self, value: &Value, path: &str) {
+        let distinct = self
+            .distinct
+            .entry(path.to_string())
+            .or_insert_with(|| Some(HashSet::new()));
+        let Some(hashes) = distinct else {
+            return;
+        };
+        match value {
+            Value::Null => {}
+            _ if hashes.len() >= MAX_UNIQUE_VALUES_CHECKED => {}
+            Value::String(_) | Value::Number(_) if !value.is_f64() => {
+                let mut hasher = DefaultHasher::new();
+                // as JSON, so that a string of digits differs from the integer
+                value.to_string().hash(&mut hasher);
+                if !hashes.insert(hasher.finish()) {
+                    *distinct = None;
+                }
+            }
+            _ => *distinct = None,
+        }
+    }
+
+    /// Whether the values of the field at `path` were all different strings or integers, of
+    /// at least [`MIN_UNIQUE_VALUES`] of them, ignoring `null`s.
+    pub fn is_unique(&self, path: &str) -> bool {
+        matches!(
+            self.distinct.get(path),
+            Some(Some(hashes)) if hashes.len() >= MIN_UNIQUE_VALUES
+        )
+    }
+
+    /// The paths of the fields that are [unique](Self::is_unique), in order.
+    pub fn unique_fields(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = (self.distinct.keys())
+            .map(String::as_str)
+            .filter(|path| self.is_unique(path))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// The counts for the field at `path`, if it was seen at all.
+    pub fn get(&self, path: &str) -> Option<FieldCounts> {
+        let (parent, present, null) = self.fields.get(path)?;
+        Some(FieldCounts {
+            objects: self.objects[parent],
+            present: *present,
+            null: *null,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counts_presence_and_nulls_of_nested_fields() {
+        let mut stats = FieldStats::default();
+        stats.add(&json!({"id": 1, "tags": ["a"], "address": {"city": null, "zip": "1234"}}));
+        stats.add(&json!({"id": 2, "address": {"city": "Utrecht"}}));
+        stats.add(&json!({"id": null, "address": null}));
+        stats.add(&json!({"id": 4}));
+
+        assert_eq!(
+            stats.get("id"),
+            Some(FieldCounts {
+                objects: 4,
+                present: 4,
+                null: 1
+            })
+        );
+        assert_eq!(stats.get("address").unwrap().presence(), 0.75);
+        assert_eq!(
+            stats.get("address.city"),
+            Some(FieldCounts {
+                objects: 2,
+                present: 2,
+                null: 1
+            })
+        );
+        assert_eq!(stats.get("address.zip").unwrap().presence(), 0.5);
+        assert_eq!(stats.get("address.zip").unwrap().null_ratio(), 0.0);
+        assert_eq!(stats.get("missing"), None);
+    }
+
+    #[test]
+    fn counts_objects_within_arrays() {
+        let mut stats = FieldStats::default();
+        stats.add(&json!([
+            {"orders": [{"total": 1}, {"total": null}, {}]},
+            {"orders": []}
+        ]));
+
+        assert_eq!(
+            stats.get("[].orders[].total"),
+            Some(FieldCounts {
+                objects: 3,
+                present: 2,
+                null: 1
+            })
+        );
+        assert_eq!(stats.get("[].orders").unwrap().presence(), 1.0);
+    }
+
+    #[test]
+    fn finds_the_fields_whose_values_were_all_different() {
+        let mut stats = FieldStats::default();
+        for i in 0..15 {
+            stats.add(&json!({
+                "id": i,
+                "sku": format!("SKU-{}", i),
+                "zip": if i % 3 == 0 { json!(null) } else { json!(format!("{:04}", i)) },
+                "price": i as f64 + 0.5,
+                "batch": i / 2,
+                "lines": [{"n": i}, {"n": i + 100}],
+            }));
+        }
+        assert_eq!(stats.unique_fields(), vec!["id", "lines[].n", "sku", "zip"]);
+        assert!(!stats.is_unique("price"));
+        assert!(!stats.is_unique("batch"));
+        assert!(!stats.is_unique("lines"));
+
+        // too few values to tell
+        let mut stats = FieldStats::default();
+        stats.add(&json!([{"id": 1}, {"id": 2}]));
+        assert!(!stats.is_unique("[].id"));
+    }
+}
@@ -0,0 +1,153 @@
+use rand::Rng;
+
+use crate::StringType;
+
+/// Whether a string of digits passes the Luhn checksum used by payment card numbers.
+fn is_luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = u32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn is_credit_card(s: &str) -> bool {
+    (13..=19).contains(&s.len())
+        && s.bytes().all(|b| b.is_ascii_digit())
+        && !s.starts_with('0')
+        && is_luhn_valid(s)
+}
+
+/// The remainder of dividing an IBAN by 97, after moving the country code and check digits to
+/// the end and replacing letters with numbers (A = 10, B = 11, etc.).
+fn iban_remainder(iban: &str) -> u32 {
+    let (head, tail) = iban.split_at(4);
+    tail.chars().chain(head.chars()).fold(0, |remainder, c| {
+        let value = c.to_digit(36).unwrap();
+        if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        }
+    })
+}
+
+fn is_iban(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    (15..=34).contains(&s.len())
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+        && bytes[4..]
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        && iban_remainder(s) == 1
+}
+
+/// Recognises payment card numbers and IBANs, in their electronic format without separators,
+/// by validating their checksums (Luhn and mod-97 respectively).
+pub(crate) fn infer_financial(s: &str) -> Option<StringType> {
+    if is_credit_card(s) {
+        Some(StringType::CreditCard)
+    } else if is_iban(s) {
+        Some(StringType::Iban)
+    } else {
+        None
+    }
+}
+
+fn random_digits<R: Rng + ?Sized>(n: usize, rng: &mut R) -> String {
+    (0..n)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+        .collect()
+}
+
+/// Produces a synthetic 16-digit card number with a Visa-like prefix and a valid check digit.
+pub(crate) fn produce_credit_card<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let partial = format!("4{}", random_digits(14, rng));
+    (0..10)
+        .map(|check| format!("{}{}", partial, check))
+        .find(|number| is_luhn_valid(number))
+        .unwrap()
+}
+
+/// Produces a synthetic IBAN with valid check digits, in the format of one of a few countries.
+pub(crate) fn produce_iban<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let bank: String = (0..4)
+        .map(|_| char::from(b'A' + rng.gen_range(0..26)))
+        .collect();
+    let (country, bban) = match rng.gen_range(0..3) {
+        0 => ("NL", format!("{}{}", bank, random_digits(10, rng))),
+        1 => ("GB", format!("{}{}", bank, random_digits(14, rng))),
+        _ => ("DE", random_digits(18, rng)),
+    };
+    let check = 98 - iban_remainder(&format!("{}00{}", country, bban));
+    format!("{}{:02}{}", country, check, bban)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn recognises_credit_cards() {
+        for number in ["4111111111111111", "5500005555555559", "378282246310005"] {
+            assert_eq!(
+                infer_financial(number),
+                Some(StringType::CreditCard),
+                "{}",
+                number
+            );
+        }
+        for number in [
+            "4111111111111112",
+            "0111111111111116",
+            "4111 1111 1111 1111",
+        ] {
+            assert_eq!(infer_financial(number), None, "{}", number);
+        }
+    }
+
+    #[test]
+    fn recognises_ibans() {
+        for iban in [
+            "NL91ABNA0417164300",
+            "GB29NWBK60161331926819",
+            "DE89370400440532013000",
+        ] {
+            assert_eq!(infer_financial(iban), Some(StringType::Iban), "{}", iban);
+        }
+        for iban in ["NL91ABNA0417164301", "nl91abna0417164300", "NL91"] {
+            assert_eq!(infer_financial(iban), None, "{}", iban);
+        }
+    }
+
+    #[test]
+    fn produced_values_pass_checksums() {
+        for _ in 0..100 {
+            let number = produce_credit_card(&mut thread_rng());
+            assert_eq!(
+                infer_financial(&number),
+                Some(StringType::CreditCard),
+                "{}",
+                number
+            );
+            let iban = produce_iban(&mut thread_rng());
+            assert_eq!(infer_financial(&iban), Some(StringType::Iban), "{}", iban);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use serde_json::ser::Formatter;
+use std::io;
+
+/// Formats a float in fixed-point notation, such as `0.0000001` rather than `1e-7`, with the
+/// fewest digits that still read back as the same float. Returns `None` for floats so large or
+/// small that fixed-point notation would need more than about 20 digits before or after the
+/// decimal point, which are still written in scientific notation.
+pub(crate) fn fixed_point(value: f64) -> Option<String> {
+    let magnitude = value.abs();
+    if !value.is_finite() || (magnitude != 0.0 && !(1e-15..1e21).contains(&magnitude)) {
+        return None;
+    }
+    // the `Display` implementation never uses an exponent
+    let s = value.to_string();
+    // keep a decimal point, so that the number still reads as a float
+    Some(if s.contains('.') {
+        s
+    } else {
+        format!("{}.0", s)
+    })
+}
+
+/// A JSON formatter that writes floats in fixed-point notation where feasible, and is otherwise
+/// the same as the formatter it wraps (such as serde_json's `PrettyFormatter`).
+///
+/// # Example
+///
+/// ```
+/// use drivel::FixedPointFormatter;
+/// use serde::Serialize;
+/// use serde_json::{json, ser::CompactFormatter, Serializer};
+///
+/// let mut out = vec![];
+/// let mut serializer = Serializer::with_formatter(&mut out, FixedPointFormatter::new(CompactFormatter));
+/// json!([0.0000001, 2.5e22]).serialize(&mut serializer).unwrap();
+///
+/// assert_eq!(String::from_utf8(out).unwrap(), "[0.0000001,2.5e22]");
+/// ```
+pub struct FixedPointFormatter<F> {
+    inner: F,
+}
+
+impl<F: Formatter> FixedPointFormatter<F> {
+    pub fn new(inner: F) -> Self {
+        FixedPointFormatter { inner }
+    }
+}
+
+impl<F: Formatter> Formatter for FixedPointFormatter<F> {
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        match fixed_point(value) {
+            Some(s) => writer.write_all(s.as_bytes()),
+            None => self.inner.write_f64(writer, value),
+        }
+    }
+
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.inner.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.inner.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object_key(writer)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object_value(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use serde_json::{json, ser::PrettyFormatter, Value};
+
+    fn to_fixed_point_string(value: &Value) -> String {
+        let mut out = vec![];
+        let formatter = FixedPointFormatter::new(PrettyFormatter::with_indent(b" "));
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        value.serialize(&mut serializer).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_small_and_large_floats_in_fixed_point() {
+        assert_eq!(fixed_point(1e-7).as_deref(), Some("0.0000001"));
+        assert_eq!(fixed_point(-1.5e-12).as_deref(), Some("-0.0000000000015"));
+        assert_eq!(fixed_point(1.2e17).as_deref(), Some("120000000000000000.0"));
+        assert_eq!(
+            fixed_point(0.1 + 0.2).as_deref(),
+            Some("0.30000000000000004")
+        );
+        assert_eq!(fixed_point(0.0).as_deref(), Some("0.0"));
+    }
+
+    #[test]
+    fn falls_back_to_scientific_notation_outside_range() {
+        assert_eq!(fixed_point(1e-20), None);
+        assert_eq!(fixed_point(3e300), None);
+        assert_eq!(fixed_point(f64::NAN), None);
+    }
+
+    #[test]
+    fn keeps_the_wrapped_layout() {
+        let value = json!({"a": [1e-7, 2], "b": 1e30});
+        assert_eq!(
+            to_fixed_point_string(&value),
+            "{\n \"a\": [\n  0.0000001,\n  2\n ],\n \"b\": 1e30\n}"
+        );
+        let parsed: Value = serde_json::from_str(&to_fixed_point_string(&value)).unwrap();
+        assert_eq!(parsed, value);
+    }
+}
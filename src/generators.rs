@@ -0,0 +1,273 @@
+use rand::RngCore;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+use crate::{produce::produce_string, GeneratorCommand, SchemaState, ToJsonSchema, ValueFormat};
+
+/// Generates values in place of those that drivel would produce for a schema, such as
+/// domain-specific identifiers. Generators are registered in a [`GeneratorRegistry`].
+///
+/// The random number generator is seeded like the one drivel would have used for the value, so
+/// that values from generators that only use it for randomness are reproducible with
+/// [`ProduceOptions::seed`](crate::ProduceOptions::seed) too. Any function or closure with the
+/// same signature as [`ValueGenerator::generate`] is a generator.
+pub trait ValueGenerator: Send + Sync {
+    /// Generates a value of `schema`. For a generator registered for a field, this is the schema
+    /// of the field, which may be nullable; for a generator registered for a format, it is the
+    /// string schema of that format.
+    fn generate(&self, schema: &SchemaState, rng: &mut dyn RngCore) -> Value;
+
+    /// Generates a value of `schema`, or `None` to have drivel produce the value as if there
+    /// were no generator, such as when the source of the values is unavailable. By default,
+    /// this always generates a value with [`generate`](Self::generate).
+    fn try_generate(&self, schema: &SchemaState, rng: &mut dyn RngCore) -> Option<Value> {
+        Some(self.generate(schema, rng))
+    }
+}
+
+impl<F> ValueGenerator for F
+where
+    F: Fn(&SchemaState, &mut dyn RngCore) -> Value + Send + Sync,
+{
+    fn generate(&self, schema: &SchemaState, rng: &mut dyn RngCore) -> Value {
+        self(schema, rng)
+    }
+}
+
+/// The generator that drivel uses for the strings of a format, for custom generators to build
+/// on, such as to add a prefix to the built-in values.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{FormatGenerator, ValueFormat, ValueGenerator, SchemaState, StringType};
+///
+/// let schema = SchemaState::String(StringType::Email);
+/// let email = FormatGenerator(ValueFormat::Email).generate(&schema, &mut rand::thread_rng());
+/// assert!(email.as_str().unwrap().contains('@'));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatGenerator(pub ValueFormat);
+
+impl ValueGenerator for FormatGenerator {
+    fn generate(&self, _schema: &SchemaState, rng: &mut dyn RngCore) -> Value {
+        Value::String(produce_string(&self.0.string_type(), None, rng))
+    }
+}
+
+/// Custom generators for the values of particular fields, or for the strings of particular
+/// formats, for use in [`ProduceOptions::generators`](crate::ProduceOptions::generators).
+///
+/// The value at a path is produced by the first of these that applies:
+///
+/// 1. a [`FieldOverride`](crate::FieldOverride) for the path, unless it only sets a
+///    `null_probability` (and `null` isn't picked)
+/// 2. the generator registered for the path, or else the
+///    [`command`](crate::ProduceOptions::commands) of the config for it
+/// 3. for strings of a format, the generator registered for the format, which also produces the
+///    strings of an override's `format`
+/// 4. drivel's built-in generation for the schema
+///
+/// A generator that declines a value, with [`ValueGenerator::try_generate`], leaves it to the
+/// next of these. Transforms are applied to the value afterwards, whichever produced it. Nullable fields are
+/// `null` at random before a format generator is asked for a value, but a field generator
+/// decides for itself.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{produce_with, ProduceOptions, SchemaState, StringType, ValueFormat};
+/// use rand::{Rng, RngCore};
+/// use serde_json::json;
+/// use indexmap::IndexMap;
+///
+/// let schema = SchemaState::Object {
+///     required: IndexMap::from([
+///         ("id".to_string(), SchemaState::String(StringType::UUID)),
+///         ("contact".to_string(), SchemaState::String(StringType::Email)),
+///     ]),
+///     optional: IndexMap::new(),
+///     access: Default::default(),
+///     annotations: Default::default(),
+/// };
+/// let mut options = ProduceOptions::default();
+/// options.generators.register_field("id", |_: &SchemaState, rng: &mut dyn RngCore| {
+///     json!(format!("CUST-{:06}", rng.gen_range(0..1_000_000)))
+/// });
+/// options.generators.register_format(ValueFormat::Email, |_: &SchemaState, _: &mut dyn RngCore| {
+///     json!("someone@example.com")
+/// });
+///
+/// let value = produce_with(&schema, 1, &options);
+/// assert!(value["id"].as_str().unwrap().starts_with("CUST-"));
+/// assert_eq!(value["contact"], json!("someone@example.com"));
+/// ```
+#[derive(Clone, Default)]
+pub struct GeneratorRegistry {
+    fields: BTreeMap<String, Arc<dyn ValueGenerator>>,
+    formats: BTreeMap<ValueFormat, Arc<dyn ValueGenerator>>,
+}
+
+impl GeneratorRegistry {
+    /// Registers a generator for the values at a path, such as `[].id`, replacing any generator
+    /// registered for it before.
+    pub fn register_field(
+        &mut self,
+        path: impl Into<String>,
+        generator: impl ValueGenerator + 'static,
+    ) {
+        self.fields.insert(path.into(), Arc::new(generator));
+    }
+
+    /// Registers a generator for the strings of a format, wherever they occur, replacing any
+    /// generator registered for it before.
+    pub fn register_format(
+        &mut self,
+        format: ValueFormat,
+        generator: impl ValueGenerator + 'static,
+    ) {
+        self.formats.insert(format, Arc::new(generator));
+    }
+
+    pub(crate) fn for_field(&self, path: &str) -> Option<&dyn ValueGenerator> {
+        self.fields.get(path).map(|generator| generator.as_ref())
+    }
+
+    pub(crate) fn for_format(&self, format: ValueFormat) -> Option<&dyn ValueGenerator> {
+        self.formats
+            .get(&format)
+            .map(|generator| generator.as_ref())
+    }
+
+    /// The paths that generators are registered for.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &String> {
+        self.fields.keys()
+    }
+
+    /// Adds the generators of `other`, which win where both have one for the same path or
+    /// format.
+    pub(crate) fn extend(&mut self, other: GeneratorRegistry) {
+        self.fields.extend(other.fields);
+        self.formats.extend(other.formats);
+    }
+}
+
+impl fmt::Debug for GeneratorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeneratorRegistry")
+            .field("fields", &self.fields.keys().collect::<Vec<_>>())
+            .field("formats", &self.formats.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PartialEq for GeneratorRegistry {
+    /// Registries are only equal if they have the same generators for the same paths and formats,
+    /// since generators can't be compared otherwise.
+    fn eq(&self, other: &Self) -> bool {
+        fn same<K: PartialEq>(
+            a: &BTreeMap<K, Arc<dyn ValueGenerator>>,
+            b: &BTreeMap<K, Arc<dyn ValueGenerator>>,
+        ) -> bool {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((ka, va), (kb, vb))| ka == kb && Arc::ptr_eq(va, vb))
+        }
+        same(&self.fields, &other.fields) && same(&self.formats, &other.formats)
+    }
+}
+
+/// How many values a [`GeneratorCommand`] is asked for at a time, unless it says otherwise.
+pub const DEFAULT_COMMAND_BATCH_SIZE: usize = 100;
+
+/// Generates the values at a path with a [`GeneratorCommand`], running it whenever the values
+/// it wrote last have all been used. Once it fails, it declines every value, for drivel to
+/// produce them as usual, and keeps why, to be warned about once the data has been produced.
+pub(crate) struct CommandGenerator<'a> {
+    path: &'a str,
+    command: &'a GeneratorCommand,
+    batch: Mutex<CommandBatch>,
+}
+
+#[derive(Default)]
+struct CommandBatch {
+    values: VecDeque<Value>,
+    failure: Option<String>,
+}
+
+impl<'a> CommandGenerator<'a> {
+    pub(crate) fn new(path: &'a str, command: &'a GeneratorCommand) -> Self {
+        CommandGenerator {
+            path,
+            command,
+            batch: Mutex::default(),
+        }
+    }
+
+    /// Why the command failed, if it did.
+    pub(crate) fn failure(&self) -> Option<String> {
+        self.batch.lock().unwrap().failure.clone()
+    }
+
+    /// Runs the command for `count` values of `schema`, returning the values it wrote, or why
+    /// it failed.
+    fn run(&self, schema: &SchemaState, count: usize, seed: u64) -> Result<Vec<Value>, String> {
+        let (program, args) = self
+            .command
+            .command
+            .split_first()
+            .ok_or("no program was given")?;
+        let output = Command::new(program)
+            .args(args)
+            .env("DRIVEL_PATH", self.path)
+            .env("DRIVEL_COUNT", count.to_string())
+            .env("DRIVEL_SEED", seed.to_string())
+            .env("DRIVEL_SCHEMA", schema.to_json_schema().to_string())
+            .stdin(Stdio::null())
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|err| format!("{} could not be run: {}", program, err))?;
+        if !output.status.success() {
+            return Err(format!("{} exited with {}", program, output.status));
+        }
+        let output = String::from_utf8(output.stdout)
+            .map_err(|_| format!("{} wrote output that isn't UTF-8", program))?;
+        let values: Vec<Value> = output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string()))
+            })
+            .collect();
+        if values.is_empty() {
+            return Err(format!("{} wrote no values", program));
+        }
+        Ok(values)
+    }
+}
+
+impl ValueGenerator for CommandGenerator<'_> {
+    fn generate(&self, schema: &SchemaState, rng: &mut dyn RngCore) -> Value {
+        self.try_generate(schema, rng).unwrap_or(Value::Null)
+    }
+
+    fn try_generate(&self, schema: &SchemaState, rng: &mut dyn RngCore) -> Option<Value> {
+        let mut batch = self.batch.lock().unwrap();
+        if batch.values.is_empty() && batch.failure.is_none() {
+            let count = self
+                .command
+                .batch_size
+                .unwrap_or(DEFAULT_COMMAND_BATCH_SIZE)
+                .max(1);
+            match self.run(schema, count, rng.next_u64()) {
+                Ok(values) => batch.values.extend(values),
+                Err(reason) => batch.failure = Some(reason),
+            }
+        }
+        batch.values.pop_front()
+    }
+}
@@ -0,0 +1,520 @@
+use std::collections::BTreeSet;
+
+use crate::codegen::{self, any_string, object, pascal_case, unique};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// Names of the types that every schema has, which its own types mustn't shadow.
+const RESERVED_NAMES: &[&str] = &[
+    "Boolean",
+    "Float",
+    "ID",
+    "Int",
+    "JSON",
+    "Mutation",
+    "Query",
+    "String",
+    "Subscription",
+];
+
+/// Whether a name is a GraphQL name, such as of a field or an enum value. Names starting with
+/// `__` are reserved for introspection.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__")
+}
+
+/// Turns a name into camel case, e.g. `first-name` into `firstName`. Names that would start
+/// with a digit are prefixed with `_`.
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        // pascal case prefixes them with `T`
+        return format!("_{}", &pascal[1..]);
+    }
+    let mut chars = pascal.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// The name of the field of a type for a field name: the name itself if it is a GraphQL name,
+/// or in camel case otherwise.
+fn field_name(key: &str) -> String {
+    if is_name(key) {
+        key.to_string()
+    } else {
+        camel_case(key)
+    }
+}
+
+/// Whether the values of a schema are of an object type, as objects with fields.
+fn is_object_type(schema: &SchemaState) -> bool {
+    matches!(
+        schema,
+        SchemaState::Object { required, optional, .. } if !required.is_empty() || !optional.is_empty()
+    )
+}
+
+/// A type definition, without its name.
+#[derive(PartialEq)]
+enum Definition {
+    /// An object type, of the lines of its fields.
+    Type(Vec<String>),
+    /// An enum type, of its values.
+    Enum(Vec<String>),
+    /// A union of object types.
+    Union(Vec<String>),
+}
+
+/// The type definitions of GraphQL output, an object type for each object shape and an enum
+/// for each enum of strings.
+struct Definitions {
+    /// The definition of each type.
+    types: codegen::Declarations<Definition>,
+    /// Whether any value has no GraphQL type, for which the `JSON` scalar is declared.
+    json: bool,
+}
+
+impl Definitions {
+    /// The custom scalar of values of no GraphQL type.
+    fn json(&mut self) -> String {
+        self.json = true;
+        "JSON".to_string()
+    }
+
+    /// Defines an object type for an object, with a field for each of its fields, those that
+    /// are required and not nullable marked as non-null. Objects without fields are `JSON`, as
+    /// object types have at least one field. The type of a recursive definition, `recursive`, is
+    /// named before its fields are rendered, so that the values of it within it can refer to it.
+    fn object(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> String {
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            unreachable!("only objects are defined as object types")
+        };
+        if required.is_empty() && optional.is_empty() {
+            return self.json();
+        }
+        let index = self.types.start();
+        if let Some(definition) = recursive {
+            self.types.name(index, definition, name, parent);
+        }
+        let keys: Vec<(&String, bool)> = required
+            .keys()
+            .map(|key| (key, false))
+            .chain(optional.keys().map(|key| (key, true)))
+            .collect();
+        let field_names = unique(keys.iter().map(|(key, _)| field_name(key)));
+        let mut lines = vec![];
+        for ((key, is_optional), field_name) in keys.into_iter().zip(field_names) {
+            let field = if is_optional {
+                &optional[key]
+            } else {
+                &required[key]
+            };
+            let mut field_type = self.type_of(field, &pascal_case(key), name);
+            if is_optional {
+                field_type = field_type.trim_end_matches('!').to_string();
+            }
+            lines.push(format!("{}: {}", field_name, field_type));
+        }
+        self.types
+            .finish(index, Definition::Type(lines), name, parent)
+    }
+
+    /// Defines an enum of the given strings, if they are all names that can be enum values,
+    /// or `String` otherwise.
+    fn string_enum(&mut self, variants: &BTreeSet<String>, name: &str, parent: &str) -> String {
+        if !variants.iter().all(|variant| {
+            is_name(variant) && !matches!(variant.as_str(), "true" | "false" | "null")
+        }) {
+            return "String!".to_string();
+        }
+        let index = self.types.start();
+        let values = variants.iter().cloned().collect();
+        format!(
+            "{}!",
+            self.types
+                .finish(index, Definition::Enum(values), name, parent)
+        )
+    }
+
+    /// Defines a union of the object types of the given variants, or `JSON` if any of them
+    /// isn't an object.
+    fn union<'a>(
+        &mut self,
+        variants: impl IntoIterator<Item = (String, &'a SchemaState)>,
+        name: &str,
+        parent: &str,
+    ) -> String {
+        let variants: Vec<(String, &SchemaState)> = variants.into_iter().collect();
+        if !variants.iter().all(|(_, variant)| is_object_type(variant)) {
+            return self.json();
+        }
+        let index = self.types.start();
+        let mut members: Vec<String> = vec![];
+        for (variant_name, variant) in variants {
+            let member = self.object(variant, &variant_name, name, None);
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+        format!(
+            "{}!",
+            self.types
+                .finish(index, Definition::Union(members), name, parent)
+        )
+    }
+
+    /// The GraphQL type of the values of a schema, non-null unless they can be null, defining
+    /// types for the objects, enums and unions among them, named after `name`, the name of the
+    /// field they are in as a type.
+    fn type_of(&mut self, schema: &SchemaState, name: &str, parent: &str) -> String {
+        match schema {
+            SchemaState::Initial | SchemaState::Indefinite | SchemaState::Null => self.json(),
+            SchemaState::Literal(values) => {
+                self.type_of(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                let object = self.object(schema, name, parent, Some(definition));
+                format!("{}!", object)
+            }
+            SchemaState::Recursive { schema, .. } => self.type_of(schema, name, parent),
+            // only objects are defined, so another recursive definition can't be referred to
+            SchemaState::Reference(definition) => match self.types.recursive(definition) {
+                Some(declared) => format!("{}!", declared),
+                None => self.json(),
+            },
+            SchemaState::Nullable(inner) => self
+                .type_of(inner, name, parent)
+                .trim_end_matches('!')
+                .to_string(),
+            SchemaState::String(StringType::Enum { variants }) => {
+                self.string_enum(variants, name, parent)
+            }
+            SchemaState::String(StringType::UUID) => "ID!".to_string(),
+            SchemaState::String(_) => "String!".to_string(),
+            // integers of GraphQL are of 32 bits
+            SchemaState::Number(NumberType::Integer { min, max })
+                if *min < i32::MIN as i64 || *max > i32::MAX as i64 =>
+            {
+                "Float!".to_string()
+            }
+            SchemaState::Number(
+                NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+            ) => "Float!".to_string(),
+            SchemaState::Number(_) => "Int!".to_string(),
+            SchemaState::Boolean => "Boolean!".to_string(),
+            SchemaState::Array { schema, .. } => format!(
+                "[{}]!",
+                self.type_of(schema, &format!("{}Item", name), parent)
+            ),
+            SchemaState::Object { .. } => {
+                let object = self.object(schema, name, parent, None);
+                format!("{}!", object)
+            }
+            SchemaState::Map { .. } => format!("{}!", self.json()),
+            SchemaState::ExtendedJson(extended) => {
+                let key = match extended {
+                    ExtendedJsonType::ObjectId => "$oid",
+                    ExtendedJsonType::Date => "$date",
+                    ExtendedJsonType::Long { .. } => "$numberLong",
+                };
+                let object = self.object(&object(vec![(key, any_string())]), name, parent, None);
+                format!("{}!", object)
+            }
+            SchemaState::Coordinates(_) => "[Float!]!".to_string(),
+            SchemaState::Tuple(_) => format!("[{}]!", self.json()),
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            } => {
+                let object = self.object(
+                    &object(vec![
+                        (latitude_key, SchemaState::Number(latitude.clone())),
+                        (longitude_key, SchemaState::Number(longitude.clone())),
+                    ]),
+                    name,
+                    parent,
+                    None,
+                );
+                format!("{}!", object)
+            }
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => {
+                let object = self.object(
+                    &object(vec![
+                        (amount_key, SchemaState::Number(amount.clone())),
+                        (
+                            currency_key,
+                            SchemaState::String(StringType::Enum {
+                                variants: currencies.clone(),
+                            }),
+                        ),
+                    ]),
+                    name,
+                    parent,
+                    None,
+                );
+                format!("{}!", object)
+            }
+            SchemaState::Union(variants) => self.union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variant)| (format!("{}Variant{}", name, i + 1), variant)),
+                name,
+                parent,
+            ),
+            SchemaState::Mixed(variants) => self.union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (variant, _))| (format!("{}Variant{}", name, i + 1), variant)),
+                name,
+                parent,
+            ),
+            SchemaState::TaggedUnion { variants, .. } => self.union(
+                variants
+                    .iter()
+                    .map(|(value, variant)| (format!("{}{}", name, pascal_case(value)), variant)),
+                name,
+                parent,
+            ),
+        }
+    }
+}
+
+/// Renders a schema as a GraphQL schema stub in the schema definition language: an object type
+/// for each object shape, named after the field it is in, an enum for each enum of strings
+/// whose values are all GraphQL names, and a `Query` type with a field returning the root,
+/// named after `name`. Fields that are required and never null are non-null, arrays are lists,
+/// and unions and tagged unions of objects are unions of their object types. Values of no
+/// GraphQL type, such as maps and unions of scalars, are of a custom `JSON` scalar. The values
+/// of a recursive definition within it are of its object type.
+pub(crate) fn to_graphql(schema: &SchemaState, name: &str) -> String {
+    let mut definitions = Definitions {
+        types: codegen::Declarations::new(RESERVED_NAMES),
+        json: false,
+    };
+    let root = definitions.type_of(schema, &pascal_case(name), "");
+    let mut output = vec![];
+    if definitions.json {
+        output.push("scalar JSON".to_string());
+    }
+    output.push(format!(
+        "type Query {{\n  {}: {}\n}}",
+        camel_case(name),
+        root
+    ));
+    for (name, definition) in definitions.types.into_reached() {
+        output.push(match definition {
+            Definition::Type(lines) => {
+                let body: String = lines.iter().map(|line| format!("  {}\n", line)).collect();
+                format!("type {} {{\n{}}}", name, body)
+            }
+            Definition::Enum(values) => {
+                let body: String = values
+                    .iter()
+                    .map(|value| format!("  {}\n", value))
+                    .collect();
+                format!("enum {} {{\n{}}}", name, body)
+            }
+            Definition::Union(members) => format!("union {} = {}", name, members.join(" | ")),
+        });
+    }
+    output.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn defines_an_object_type_for_each_object_shape() {
+        let address = object(vec![("city", any_string())]);
+        let mut schema = object(vec![
+            (
+                "id",
+                SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+            ),
+            ("uuid", SchemaState::String(StringType::UUID)),
+            ("status", strings(&["open", "closed"])),
+            ("size", strings(&["x-large", "small"])),
+            ("shipping_address", address.clone()),
+            ("billing-address", SchemaState::Nullable(Box::new(address))),
+            (
+                "total",
+                SchemaState::Number(NumberType::Integer {
+                    min: 0,
+                    max: 1 << 40,
+                }),
+            ),
+        ]);
+        if let SchemaState::Object { optional, .. } = &mut schema {
+            optional.insert(
+                "tags".to_string(),
+                array(SchemaState::Nullable(Box::new(any_string()))),
+            );
+            optional.insert(
+                "attributes".to_string(),
+                SchemaState::Map {
+                    min_length: 1,
+                    max_length: 1,
+                    keys: StringType::UUID,
+                    schema: Box::new(SchemaState::Boolean),
+                },
+            );
+        }
+        assert_eq!(
+            to_graphql(&array(schema), "orders"),
+            r#"scalar JSON
+
+type Query {
+  orders: [OrdersItem!]!
+}
+
+type OrdersItem {
+  id: Int!
+  uuid: ID!
+  status: Status!
+  size: String!
+  shipping_address: ShippingAddress!
+  billingAddress: ShippingAddress
+  total: Float!
+  tags: [String]
+  attributes: JSON
+}
+
+enum Status {
+  closed
+  open
+}
+
+type ShippingAddress {
+  city: String!
+}"#
+        );
+    }
+
+    #[test]
+    fn defines_unions_of_object_types() {
+        let click = object(vec![
+            ("type", strings(&["click"])),
+            (
+                "x",
+                SchemaState::Number(NumberType::Integer { min: 0, max: 1 }),
+            ),
+        ]);
+        let key = object(vec![("type", strings(&["key"]))]);
+        let schema = object(vec![
+            (
+                "event",
+                SchemaState::TaggedUnion {
+                    tag: "type".to_string(),
+                    variants: BTreeMap::from_iter([
+                        ("click".to_string(), click),
+                        ("key".to_string(), key),
+                    ]),
+                },
+            ),
+            (
+                "value",
+                SchemaState::Union(vec![SchemaState::Boolean, any_string()]),
+            ),
+            ("1st", SchemaState::Boolean),
+        ]);
+        assert_eq!(
+            to_graphql(&schema, "Log"),
+            r#"scalar JSON
+
+type Query {
+  log: Log!
+}
+
+type Log {
+  event: Event!
+  value: JSON
+  _1st: Boolean!
+}
+
+union Event = EventClick | EventKey
+
+type EventClick {
+  type: Type!
+  x: Int!
+}
+
+enum Type {
+  click
+}
+
+type EventKey {
+  type: EventKeyType!
+}
+
+enum EventKeyType {
+  key
+}"#
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_object_types() {
+        let tree = SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(object(vec![(
+                "children",
+                array(SchemaState::Reference("node".to_string())),
+            )])),
+        };
+        assert_eq!(
+            to_graphql(&tree, "tree"),
+            r#"type Query {
+  tree: Tree!
+}
+
+type Tree {
+  children: [Tree!]!
+}"#
+        );
+    }
+}
@@ -0,0 +1,589 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::value::{Error as DeError, StrDeserializer};
+use serde::Deserialize;
+
+use crate::codegen::snake_case_words;
+use crate::diagnostics::warn;
+use crate::{NumberType, SchemaState, StringType, ValueFormat};
+
+/// What a [`TypeHint`] makes the values at its path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintKind {
+    /// Strings of a format, such as `uuid` or `date-time`, named as in JSON Schema.
+    Format(ValueFormat),
+    /// An enum of the values seen.
+    Enum,
+    /// Strings of no particular format.
+    String,
+}
+
+impl fmt::Display for HintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HintKind::Format(format) => write!(f, "{}", format),
+            HintKind::Enum => write!(f, "enum"),
+            HintKind::String => write!(f, "string"),
+        }
+    }
+}
+
+/// Forces the classification of the values at a path, whatever inference concluded, for fields
+/// where its detectors misfire on ambiguous data. Parsed from `PATH=TYPE`, such as
+/// `users.*.id=uuid`, `created_at=date-time` or `status=enum`.
+///
+/// A path is the dot-separated list of field names leading to a value from the root, where `*`
+/// matches every element of an array, every field of an object and the values of a map. As in
+/// the paths of [`ProduceOptions`](crate::ProduceOptions), `[]` also refers to the elements of
+/// an array, so `users[].id` is the same as `users.*.id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeHint {
+    pub path: String,
+    pub kind: HintKind,
+}
+
+impl FromStr for HintKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        Ok(match kind {
+            "enum" => HintKind::Enum,
+            "string" => HintKind::String,
+            _ => HintKind::Format(
+                ValueFormat::deserialize(StrDeserializer::<DeError>::new(kind)).map_err(|_| {
+                    format!(
+                        "unknown type '{}', expected one of email, uuid, date, date-time, uri, \
+                         hostname, duration, enum or string",
+                        kind
+                    )
+                })?,
+            ),
+        })
+    }
+}
+
+impl FromStr for TypeHint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, kind) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected PATH=TYPE, such as id=uuid, got '{}'", s))?;
+        Ok(TypeHint {
+            path: path.to_string(),
+            kind: kind.parse()?,
+        })
+    }
+}
+
+/// Classifies the values of fields by the names of the fields, such as `created_at` as
+/// `date-time`, where the values themselves say nothing of what they are, as with
+/// `--infer-from-names`. Parsed from `PATTERN=TYPE`, with the types of [`TypeHint`], such as
+/// `*_at=date-time` or `sku=string`.
+///
+/// A pattern is a field name, which may start or end with `*` to match any prefix or suffix of
+/// a name. Names are matched in snake case, so `*_at` also matches `createdAt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameHint {
+    pub pattern: String,
+    pub kind: HintKind,
+}
+
+const DATE_TIME: HintKind = HintKind::Format(ValueFormat::DateTime);
+const DATE: HintKind = HintKind::Format(ValueFormat::Date);
+const UUID: HintKind = HintKind::Format(ValueFormat::Uuid);
+const EMAIL: HintKind = HintKind::Format(ValueFormat::Email);
+const URI: HintKind = HintKind::Format(ValueFormat::Uri);
+const HOSTNAME: HintKind = HintKind::Format(ValueFormat::Hostname);
+const DURATION: HintKind = HintKind::Format(ValueFormat::Duration);
+
+/// The patterns of [`NameHint::defaults`]. Phone numbers and names have no format, but are
+/// strings, which `--realistic` produces by their names.
+const DEFAULT_NAME_HINTS: &[(&str, HintKind)] = &[
+    ("*_at", DATE_TIME),
+    ("timestamp", DATE_TIME),
+    ("*_timestamp", DATE_TIME),
+    ("date", DATE),
+    ("*_date", DATE),
+    ("dob", DATE),
+    ("birthday", DATE),
+    ("id", UUID),
+    ("*_id", UUID),
+    ("uuid", UUID),
+    ("*_uuid", UUID),
+    ("guid", UUID),
+    ("email", EMAIL),
+    ("*_email", EMAIL),
+    ("email_address", EMAIL),
+    ("url", URI),
+    ("*_url", URI),
+    ("uri", URI),
+    ("*_uri", URI),
+    ("website", URI),
+    ("homepage", URI),
+    ("href", URI),
+    ("host", HOSTNAME),
+    ("hostname", HOSTNAME),
+    ("*_host", HOSTNAME),
+    ("domain", HOSTNAME),
+    ("duration", DURATION),
+    ("*_duration", DURATION),
+    ("phone", HintKind::String),
+    ("*_phone", HintKind::String),
+    ("phone_number", HintKind::String),
+    ("mobile", HintKind::String),
+    ("name", HintKind::String),
+    ("*_name", HintKind::String),
+];
+
+impl FromStr for NameHint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, kind) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected PATTERN=TYPE, such as *_at=date-time, got '{}'", s))?;
+        Ok(NameHint {
+            pattern: pattern.to_string(),
+            kind: kind.parse()?,
+        })
+    }
+}
+
+impl NameHint {
+    /// The hints of `--infer-from-names`: dates and times for names such as `created_at`,
+    /// `*_date` and `dob`, UUIDs for `id` and `*_id`, and emails, URIs, hostnames and durations
+    /// for fields named after them, along with strings for phone numbers and names.
+    pub fn defaults() -> Vec<NameHint> {
+        DEFAULT_NAME_HINTS
+            .iter()
+            .map(|(pattern, kind)| NameHint {
+                pattern: pattern.to_string(),
+                kind: *kind,
+            })
+            .collect()
+    }
+
+    /// Whether the pattern is a whole name, rather than one with a `*`.
+    fn is_exact(&self) -> bool {
+        !self.pattern.contains('*')
+    }
+
+    /// Whether the pattern matches `name`, in snake case.
+    fn matches(&self, name: &str) -> bool {
+        let pattern = self.pattern.to_ascii_lowercase();
+        match (pattern.strip_prefix('*'), pattern.strip_suffix('*')) {
+            (Some(suffix), _) if suffix.ends_with('*') => {
+                name.contains(suffix.trim_end_matches('*'))
+            }
+            (Some(suffix), _) => name.ends_with(suffix),
+            (None, Some(prefix)) => name.starts_with(prefix),
+            (None, None) => name == pattern,
+        }
+    }
+}
+
+/// The segments of the path of a hint, with the `[]` of array elements as `*`.
+fn segments(path: &str) -> Vec<String> {
+    path.replace("[]", ".*")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The schema of values classified as `kind`, or `None` if they can't be, such as an enum of
+/// strings whose values weren't kept.
+fn hinted(schema: &SchemaState, kind: HintKind) -> Option<SchemaState> {
+    Some(match (kind, schema) {
+        (_, SchemaState::Nullable(inner)) => SchemaState::Nullable(Box::new(hinted(inner, kind)?)),
+        (HintKind::Format(format), _) => SchemaState::String(format.string_type()),
+        (HintKind::String, SchemaState::String(string @ StringType::Unknown { .. })) => {
+            SchemaState::String(string.clone())
+        }
+        (
+            HintKind::String,
+            SchemaState::String(
+                StringType::Enum { variants } | StringType::OpenEnum { variants, .. },
+            ),
+        ) => SchemaState::String(StringType::Unknown {
+            strings_seen: variants.iter().cloned().collect(),
+            chars_seen: variants
+                .iter()
+                .flat_map(|variant| variant.chars())
+                .collect(),
+            min_length: variants.iter().map(|variant| variant.chars().count()).min(),
+            max_length: variants.iter().map(|variant| variant.chars().count()).max(),
+        }),
+        (HintKind::String, _) => SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: None,
+            max_length: None,
+        }),
+        (HintKind::Enum, SchemaState::String(StringType::Unknown { strings_seen, .. }))
+            if !strings_seen.is_empty() =>
+        {
+            SchemaState::String(StringType::Enum {
+                variants: strings_seen.iter().cloned().collect(),
+            })
+        }
+        (HintKind::Enum, SchemaState::String(StringType::OpenEnum { variants, .. })) => {
+            SchemaState::String(StringType::Enum {
+                variants: variants.clone(),
+            })
+        }
+        (
+            HintKind::Enum,
+            enumerated @ (SchemaState::String(StringType::Enum { .. })
+            | SchemaState::Number(NumberType::Enum { .. })),
+        ) => enumerated.clone(),
+        (HintKind::Enum, _) => return None,
+    })
+}
+
+/// Applies `hint` to the values at the path of `segments` in `schema`, returning whether there
+/// were any.
+fn apply_at(schema: &mut SchemaState, segments: &[String], hint: &TypeHint) -> bool {
+    let Some((head, rest)) = segments.split_first() else {
+        match hinted(schema, hint.kind) {
+            Some(hinted) => *schema = hinted,
+            None => warn(format!(
+                "the values of '{}' can't be an enum, as they weren't kept; ignoring --hint {}={}",
+                hint.path, hint.path, hint.kind
+            )),
+        }
+        return true;
+    };
+    match schema {
+        SchemaState::Nullable(inner) => apply_at(inner, segments, hint),
+        // every variant is applied to, rather than stopping at the first that has the path
+        SchemaState::Union(variants) => variants
+            .iter_mut()
+            .map(|variant| apply_at(variant, segments, hint))
+            .fold(false, |applied, variant_applied| applied | variant_applied),
+        SchemaState::Mixed(variants) => variants
+            .iter_mut()
+            .map(|(variant, _)| apply_at(variant, segments, hint))
+            .fold(false, |applied, variant_applied| applied | variant_applied),
+        SchemaState::TaggedUnion { variants, .. } => variants
+            .values_mut()
+            .map(|variant| apply_at(variant, segments, hint))
+            .fold(false, |applied, variant_applied| applied | variant_applied),
+        SchemaState::Array { schema, .. } | SchemaState::Map { schema, .. } if head == "*" => {
+            apply_at(schema, rest, hint)
+        }
+        SchemaState::Tuple(positions) if head == "*" => positions
+            .iter_mut()
+            .map(|position| apply_at(position, rest, hint))
+            .fold(false, |applied, position_applied| {
+                applied | position_applied
+            }),
+        SchemaState::Object {
+            required, optional, ..
+        } => required
+            .iter_mut()
+            .chain(optional.iter_mut())
+            .filter(|(key, _)| head == "*" || *key == head)
+            .map(|(_, field)| apply_at(field, rest, hint))
+            .fold(false, |applied, field_applied| applied | field_applied),
+        _ => false,
+    }
+}
+
+/// Whether values say nothing of what they are, as they were all `null` or empty strings, or
+/// there were none, so that the name of their field is the better guide.
+fn is_ambiguous(schema: &SchemaState) -> bool {
+    match schema {
+        SchemaState::Initial | SchemaState::Indefinite | SchemaState::Null => true,
+        SchemaState::Nullable(inner) => is_ambiguous(inner),
+        SchemaState::String(StringType::Unknown { max_length, .. }) => max_length.unwrap_or(0) == 0,
+        _ => false,
+    }
+}
+
+/// Applies the first of `hints` that matches the name of each field with ambiguous values in
+/// `schema`, preferring hints of whole names over patterns.
+fn apply_names(schema: &mut SchemaState, hints: &[NameHint]) {
+    match schema {
+        SchemaState::Nullable(inner) => apply_names(inner, hints),
+        SchemaState::Union(variants) => variants
+            .iter_mut()
+            .for_each(|variant| apply_names(variant, hints)),
+        SchemaState::Mixed(variants) => variants
+            .iter_mut()
+            .for_each(|(variant, _)| apply_names(variant, hints)),
+        SchemaState::TaggedUnion { variants, .. } => variants
+            .values_mut()
+            .for_each(|variant| apply_names(variant, hints)),
+        SchemaState::Array { schema, .. } | SchemaState::Map { schema, .. } => {
+            apply_names(schema, hints)
+        }
+        SchemaState::Tuple(positions) => positions
+            .iter_mut()
+            .for_each(|position| apply_names(position, hints)),
+        SchemaState::Object {
+            required, optional, ..
+        } => {
+            for (name, field) in required.iter_mut().chain(optional.iter_mut()) {
+                if !is_ambiguous(field) {
+                    apply_names(field, hints);
+                    continue;
+                }
+                let name = snake_case_words(name);
+                let hint = hints
+                    .iter()
+                    .find(|hint| hint.is_exact() && hint.matches(&name))
+                    .or_else(|| hints.iter().find(|hint| hint.matches(&name)));
+                let Some(hint) = hint else { continue };
+                // fields that were only ever `null` stay nullable
+                let hinted = match &*field {
+                    SchemaState::Null => hinted(&SchemaState::Indefinite, hint.kind)
+                        .map(|hinted| SchemaState::Nullable(Box::new(hinted))),
+                    values => hinted(values, hint.kind),
+                };
+                if let Some(hinted) = hinted {
+                    *field = hinted;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl SchemaState {
+    /// Classifies the values of fields by the names of the fields, where the values say
+    /// nothing of what they are, as they were all `null` or empty strings, or there were none,
+    /// as with `--infer-from-names`. Fields whose values were classified otherwise are left as
+    /// they are. Hints of whole names win over patterns, and earlier hints over later ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions, NameHint, SchemaState, StringType};
+    /// use serde_json::json;
+    ///
+    /// let schema = infer_schema(json!({"updatedAt": null}), &InferenceOptions::default());
+    /// let SchemaState::Object { required, .. } = schema.with_name_hints(&NameHint::defaults())
+    /// else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(
+    ///     required["updatedAt"],
+    ///     SchemaState::Nullable(Box::new(SchemaState::String(StringType::DateTimeISO8601)))
+    /// );
+    /// ```
+    pub fn with_name_hints(mut self, hints: &[NameHint]) -> SchemaState {
+        apply_names(&mut self, hints);
+        self
+    }
+
+    /// Forces the classification of the values at the paths of `hints`, whatever inference
+    /// concluded, as with `--hint`. Later hints win over earlier ones for the same values.
+    /// Warns about hints whose path matches no value, and enum hints for strings whose values
+    /// weren't kept, such as strings that were already classified as UUIDs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{SchemaState, StringType, TypeHint};
+    ///
+    /// let schema = SchemaState::String(StringType::Hostname);
+    /// // the empty path is that of the root
+    /// let hints = ["=string".parse::<TypeHint>().unwrap()];
+    /// assert!(matches!(
+    ///     schema.with_hints(&hints),
+    ///     SchemaState::String(StringType::Unknown { .. })
+    /// ));
+    /// ```
+    pub fn with_hints(mut self, hints: &[TypeHint]) -> SchemaState {
+        for hint in hints {
+            if !apply_at(&mut self, &segments(&hint.path), hint) {
+                warn(format!(
+                    "--hint {}={} matches no value, ignoring",
+                    hint.path, hint.kind
+                ));
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, infer_schema_from_iter, InferenceOptions};
+    use serde_json::json;
+
+    fn options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    fn hints(hints: &[&str]) -> Vec<TypeHint> {
+        hints.iter().map(|hint| hint.parse().unwrap()).collect()
+    }
+
+    fn field<'a>(schema: &'a SchemaState, path: &[&str]) -> &'a SchemaState {
+        path.iter()
+            .fold(schema, |schema, key| match (schema, *key) {
+                (SchemaState::Array { schema, .. }, "*") => schema,
+                (SchemaState::Nullable(inner), key) => field(inner, &[key]),
+                (SchemaState::Object { required, .. }, key) => &required[key],
+                (schema, key) => panic!("no field {} in {:?}", key, schema),
+            })
+    }
+
+    #[test]
+    fn parses_hints() {
+        assert_eq!(
+            "users.*.id=uuid".parse::<TypeHint>(),
+            Ok(TypeHint {
+                path: "users.*.id".to_string(),
+                kind: HintKind::Format(ValueFormat::Uuid),
+            })
+        );
+        assert_eq!(
+            "created_at=date-time".parse::<TypeHint>().unwrap().kind,
+            HintKind::Format(ValueFormat::DateTime)
+        );
+        assert_eq!(
+            "status=enum".parse::<TypeHint>().unwrap().kind,
+            HintKind::Enum
+        );
+        assert!("status".parse::<TypeHint>().is_err());
+        assert!("status=colour".parse::<TypeHint>().is_err());
+    }
+
+    #[test]
+    fn applies_hints_to_nested_and_wildcard_paths() {
+        let input = json!({
+            "users": [
+                {"id": "a1", "profile": {"status": "x", "created": "yesterday"}},
+                {"id": "b2", "profile": {"status": "y", "created": "today"}},
+                {"id": "c3", "profile": {"status": "x", "created": "today"}}
+            ],
+            "teams": {"red": {"lead": "ann"}, "blue": {"lead": "bob"}}
+        });
+        let schema = infer_schema(input, &options()).with_hints(&hints(&[
+            "users.*.id=uuid",
+            "users[].profile.created=date",
+            "users.*.profile.status=enum",
+            "teams.*.lead=email",
+        ]));
+        assert_eq!(
+            field(&schema, &["users", "*", "id"]),
+            &SchemaState::String(StringType::UUID)
+        );
+        assert_eq!(
+            field(&schema, &["users", "*", "profile", "created"]),
+            &SchemaState::String(StringType::IsoDate)
+        );
+        assert_eq!(
+            field(&schema, &["users", "*", "profile", "status"]),
+            &SchemaState::String(StringType::Enum {
+                variants: ["x", "y"].into_iter().map(String::from).collect(),
+            })
+        );
+        for team in ["red", "blue"] {
+            assert_eq!(
+                field(&schema, &["teams", team, "lead"]),
+                &SchemaState::String(StringType::Email)
+            );
+        }
+    }
+
+    #[test]
+    fn hints_the_ambiguous_values_of_fields_by_name() {
+        let input = json!({
+            "id": null,
+            "createdAt": "",
+            "website": "x",
+            "tags": [],
+            "owner": {"user_id": null, "phone": null},
+            "note": null
+        });
+        let schema = infer_schema(input, &options());
+        let mut name_hints: Vec<NameHint> = vec![
+            "note=duration".parse().unwrap(),
+            "*_id=string".parse().unwrap(),
+        ];
+        name_hints.extend(NameHint::defaults());
+        let hinted = schema.clone().with_name_hints(&name_hints);
+        let nullable = |schema| SchemaState::Nullable(Box::new(SchemaState::String(schema)));
+        // a whole name wins over a pattern given before it
+        assert_eq!(field(&hinted, &["id"]), &nullable(StringType::UUID));
+        assert_eq!(
+            field(&hinted, &["createdAt"]),
+            &SchemaState::String(StringType::DateTimeISO8601)
+        );
+        assert_eq!(field(&hinted, &["note"]), &nullable(StringType::Duration));
+        for path in [["owner", "user_id"], ["owner", "phone"]] {
+            assert!(matches!(
+                field(&hinted, &path),
+                SchemaState::Nullable(inner)
+                    if matches!(**inner, SchemaState::String(StringType::Unknown { .. }))
+            ));
+        }
+        // values that say what they are, and the elements of arrays, are left as they are
+        for name in ["website", "tags"] {
+            assert_eq!(field(&hinted, &[name]), field(&schema, &[name]));
+        }
+        assert!("*_at".parse::<NameHint>().is_err());
+        assert!("*_at=timestamp".parse::<NameHint>().is_err());
+    }
+
+    #[test]
+    fn hints_override_detected_formats_and_keep_nullability() {
+        let records = vec![
+            json!({"host": "example.com", "code": "abc"}),
+            json!({"host": "example.org", "code": null}),
+        ];
+        let schema = infer_schema_from_iter(records, &options());
+        assert_eq!(
+            field(&schema, &["host"]),
+            &SchemaState::String(StringType::Hostname)
+        );
+        let (schema, diagnostics) = crate::capture_diagnostics(|| {
+            schema.with_hints(&hints(&["host=string", "code=uuid", "missing=enum"]))
+        });
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["--hint missing=enum matches no value, ignoring"]
+        );
+        assert!(matches!(
+            field(&schema, &["host"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert_eq!(
+            field(&schema, &["code"]),
+            &SchemaState::Nullable(Box::new(SchemaState::String(StringType::UUID)))
+        );
+    }
+}
@@ -1,24 +1,403 @@
-use crate::{infer_string::infer_string_type, NumberType, SchemaState, StringType};
+use crate::{
+    barcode::infer_barcode,
+    date_format::infer_date_format,
+    duration::infer_duration,
+    extended_json::infer_extended_json,
+    financial::infer_financial,
+    infer_string::{
+        infer_character_class, infer_numeric_string, infer_string_type, infer_structure,
+        unknown_string_type, MAX_CHARS_SEEN, MAX_STRINGS_SEEN,
+    },
+    money::infer_money,
+    schema::geo_point_keys,
+    ExtendedJsonType, NonFiniteNumber, NumberType, SchemaState, StringType,
+};
+use indexmap::IndexMap;
 use rayon::prelude::*;
 use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The minimum number of values whose schemas are inferred on one thread, before being merged
+/// with those of other chunks, as inferring small arrays on many threads costs more than it
+/// gains.
+const PARALLEL_CHUNK_SIZE: usize = 1024;
 
 pub struct EnumInference {
     /// The maximum ratio of unique values to total values in a collection of strings for it to be considered an enum.
     pub max_unique_ratio: f64,
+    /// If set, the maximum number of distinct values of an enum, however many values there are in
+    /// all, as on large samples even a low ratio allows too many to be of use. A collection must
+    /// pass both this and the ratio test to be considered an enum.
+    pub max_values: Option<usize>,
     /// The minimum number of values in a collection of strings for enum inference to be applied.
     pub min_sample_size: usize,
+    /// Whether integer fields are also considered for enum inference, using the same thresholds.
+    pub include_numeric: bool,
+    /// Whether strings are compared after trimming whitespace and lowercasing them, so that
+    /// values such as "Active" and " ACTIVE " count as one. Each enum variant is then the most
+    /// common original spelling of its value.
+    pub normalize: bool,
+    /// If set, strings that are not an enum as a whole, but whose most common values cover at
+    /// least this fraction (such as 0.9) of the values seen, are inferred as an open-ended enum
+    /// of those values along with other strings. The most common values must pass the same
+    /// ratio test as an enum would.
+    pub open_tail_coverage: Option<f64>,
+}
+
+impl EnumInference {
+    /// Whether `variants` distinct values out of `total` are few enough to be an enum.
+    fn allows(&self, variants: usize, total: usize) -> bool {
+        variants as f64 / total as f64 <= self.max_unique_ratio
+            && self.max_values.is_none_or(|max| variants <= max)
+    }
+}
+
+pub struct FormatInferenceOptions {
+    /// The minimum number of values of a string field for its format (date, UUID, etc.) to be
+    /// inferred. Fields with fewer values are inferred as strings of no particular format.
+    pub min_sample_size: usize,
+}
+
+/// How the tag fields of tagged unions are found, for
+/// [`InferenceOptions::discriminator`].
+pub enum Discriminator {
+    /// Objects with a string value for this field, such as `type`, are variants of a tagged
+    /// union, with a shape for each value of the field.
+    Field(String),
+    /// Objects of different shapes are variants of a tagged union if a string field with few
+    /// values, which all objects have, tells the shapes apart.
+    Auto,
 }
 
+/// Options that customise how schemas are inferred. All of them are off by default, as in
+/// [`InferenceOptions::default`], inferring only the types, ranges and lengths of values and the
+/// formats of strings.
+///
+/// More options are added over time, so the struct can't be built with a struct expression
+/// outside of this crate: start from the defaults with [`InferenceOptions::builder`] instead, or
+/// set the fields of [`InferenceOptions::default`].
+///
+/// # Example
+///
+/// ```
+/// use drivel::{EnumInference, FormatInferenceOptions, InferenceOptions};
+///
+/// let options = InferenceOptions::builder()
+///     .infer_enums(EnumInference {
+///         max_unique_ratio: 0.1,
+///         max_values: None,
+///         min_sample_size: 10,
+///         include_numeric: false,
+///         normalize: false,
+///         open_tail_coverage: None,
+///     })
+///     .detect_formats(FormatInferenceOptions { min_sample_size: 5 })
+///     .union_objects(true)
+///     .build();
+/// assert!(options.union_objects);
+/// assert!(!options.extended_json);
+/// ```
+#[derive(Default)]
+#[non_exhaustive]
 pub struct InferenceOptions {
     pub enum_inference: Option<EnumInference>,
+    /// Options for inferring the formats of strings. Without these, the format of a string field
+    /// is inferred from however many values are seen.
+    pub format_inference: Option<FormatInferenceOptions>,
+    /// The maximum number of fields an object can have before it is treated as a map, with a
+    /// single schema for all of its values, instead of a record.
+    pub max_object_fields: Option<usize>,
+    /// Whether to keep objects of different shapes (i.e. with different sets of fields) apart as
+    /// variants of a union, instead of merging them into a single object.
+    pub union_objects: bool,
+    /// With `union_objects`, how much the sets of fields of two object shapes must overlap, as
+    /// the fraction of the fields of either that both have, for them to be merged into one
+    /// object with the fields that only some have as optional, rather than kept apart. At 1,
+    /// only shapes with the same fields are merged; at 0, all of them are. By default, only
+    /// shapes with the same fields are merged.
+    pub merge_threshold: Option<f64>,
+    /// If set, objects whose shape is selected by the value of a tag field, such as the `type`
+    /// of the events of an event log, are inferred as a tagged union of a shape for each value.
+    pub discriminator: Option<Discriminator>,
+    /// Whether to track how often each length of an array was observed, so that produced arrays
+    /// follow the same distribution of lengths rather than a uniform one.
+    pub length_distribution: bool,
+    /// Whether to recognise values wrapped in MongoDB extended JSON, such as `{"$oid": "..."}`,
+    /// instead of inferring them as objects.
+    pub extended_json: bool,
+    /// Whether to recognise arrays of two or three numbers as coordinates, such as `[lon, lat]`
+    /// or `[x, y, z]`, instead of inferring them as arrays of any length, and objects of just a
+    /// latitude and a longitude, such as `{"lat": 52.37, "lng": 4.89}`, as geographic points.
+    pub coordinates: bool,
+    /// Whether to recognise arrays of a few elements of different types, such as
+    /// `["Ada", 36, true]`, as tuples, with a schema for each position, instead of inferring
+    /// them as arrays of a union of the types. Arrays whose lengths differ are not tuples.
+    pub tuples: bool,
+    /// Whether to recognise payment card numbers and IBANs, by validating their checksums. A
+    /// field is only classified as such if every value seen passes the checksum.
+    pub financial_identifiers: bool,
+    /// Whether to recognise ISBN-10s, ISBN-13s and EAN-13 barcodes, by validating their check
+    /// digits. A field is only classified as such if every value seen passes the check, and as
+    /// EAN-13 if it has both ISBN-13s and other EAN-13s.
+    pub barcodes: bool,
+    /// Whether to recognise objects of just an amount and an ISO 4217 currency code, such as
+    /// `{"amount": 1299, "currency": "USD"}`, as money. Integer amounts are taken to be in minor
+    /// units, such as cents, and others to be decimal.
+    pub money: bool,
+    /// Whether to recognise ISO 8601 durations, such as `PT1H30M`. A field is only classified
+    /// as a duration if every value seen is one.
+    pub durations: bool,
+    /// Whether to infer string fields whose values are all numbers, such as `"42"` or `"3.14"`,
+    /// as numeric strings, with the range of the numbers. Strings with leading zeros are exempt,
+    /// and kept as fixed-width numeric strings.
+    pub numeric_strings: bool,
+    /// Whether the strings `NaN`, `Infinity` and `-Infinity` are floats that aren't finite, as
+    /// written in place of numbers by encoders such as Python's `json` module. See
+    /// [`quote_non_finite`](crate::quote_non_finite) to parse such input.
+    pub non_finite: bool,
+    /// Chrono-style format patterns, such as `%m/%d/%Y`, to recognise date-times in. A field is
+    /// only classified as a date-time of a format if every value seen parses with it; where
+    /// several formats fit, the one supplied first wins.
+    pub date_formats: Vec<String>,
+    /// Whether to recognise integer fields whose values nearly always increase from one value to
+    /// the next, such as auto-increment primary keys, and produce them as increasing sequences.
+    /// Integer fields are then not considered for numeric enums.
+    pub sequences: bool,
+    /// Whether to recognise integer fields that are bitmasks of flags, such as permissions, whose
+    /// values are combinations of a few powers of two, and produce them as random combinations
+    /// of the flags. A field is only classified as such if enough values are seen, every flag is
+    /// seen on its own, and at least half of the values are a single flag.
+    pub bitflags: bool,
+    /// Known sets of allowed values, such as HTTP methods or country codes. A string field is
+    /// classified as an enum of all of a vocabulary's values if every value seen belongs to it,
+    /// even if only some of them were seen; where several vocabularies fit, the one supplied
+    /// first wins.
+    pub vocabularies: Vec<Vocabulary>,
+    /// The most levels of objects and arrays that are nested in each value inferred, where an
+    /// object at the root is one level. Objects and arrays nested deeper are not looked into,
+    /// and are of any value, so that deeply nested input can't exhaust the stack or memory.
+    pub max_depth: Option<usize>,
+    /// The most keys an object can have before it is not looked into, and is of any value, such
+    /// as a map with millions of distinct keys, whose schema would otherwise have a field for
+    /// each. Unlike [`max_object_fields`](InferenceOptions::max_object_fields), which treats
+    /// such objects as maps once their fields have been inferred, the fields of objects with too
+    /// many keys are never inferred.
+    pub max_object_keys: Option<usize>,
+    /// Whether to recognise objects that are maps rather than records, and infer them as maps
+    /// with a single schema for all values, as with
+    /// [`max_object_fields`](InferenceOptions::max_object_fields). An object is a map if the
+    /// values of all of its fields are of one schema, and either its keys all look like data,
+    /// such as dates, UUIDs or numeric IDs, or it has hundreds of fields.
+    pub maps: bool,
+    /// Whether to keep values of different types apart, such as a field that is a string in
+    /// some records and a number in others, as the variants of a mixed schema with how many
+    /// values of each type were seen, instead of leaving them of any value. Produced values are
+    /// of each type as often as they were seen.
+    pub union_types: bool,
+}
+
+impl InferenceOptions {
+    /// A builder of options, starting from the defaults.
+    pub fn builder() -> InferenceOptionsBuilder {
+        InferenceOptionsBuilder::default()
+    }
+}
+
+/// Builds [`InferenceOptions`] one option at a time, starting from the defaults. Each method
+/// sets the option of the same name; see there for what it does.
+#[derive(Default)]
+pub struct InferenceOptionsBuilder {
+    options: InferenceOptions,
+}
+
+impl InferenceOptionsBuilder {
+    /// Infers string fields with few distinct values as enums, as
+    /// [`enum_inference`](InferenceOptions::enum_inference).
+    pub fn infer_enums(mut self, enum_inference: EnumInference) -> Self {
+        self.options.enum_inference = Some(enum_inference);
+        self
+    }
+
+    /// Infers the formats of strings once enough values have been seen, as
+    /// [`format_inference`](InferenceOptions::format_inference).
+    pub fn detect_formats(mut self, format_inference: FormatInferenceOptions) -> Self {
+        self.options.format_inference = Some(format_inference);
+        self
+    }
+
+    /// Sets [`max_object_fields`](InferenceOptions::max_object_fields).
+    pub fn max_object_fields(mut self, max_object_fields: usize) -> Self {
+        self.options.max_object_fields = Some(max_object_fields);
+        self
+    }
+
+    /// Sets [`union_objects`](InferenceOptions::union_objects).
+    pub fn union_objects(mut self, union_objects: bool) -> Self {
+        self.options.union_objects = union_objects;
+        self
+    }
+
+    /// Sets [`merge_threshold`](InferenceOptions::merge_threshold).
+    pub fn merge_threshold(mut self, merge_threshold: f64) -> Self {
+        self.options.merge_threshold = Some(merge_threshold);
+        self
+    }
+
+    /// Sets [`discriminator`](InferenceOptions::discriminator).
+    pub fn discriminator(mut self, discriminator: Discriminator) -> Self {
+        self.options.discriminator = Some(discriminator);
+        self
+    }
+
+    /// Sets [`length_distribution`](InferenceOptions::length_distribution).
+    pub fn length_distribution(mut self, length_distribution: bool) -> Self {
+        self.options.length_distribution = length_distribution;
+        self
+    }
+
+    /// Sets [`extended_json`](InferenceOptions::extended_json).
+    pub fn extended_json(mut self, extended_json: bool) -> Self {
+        self.options.extended_json = extended_json;
+        self
+    }
+
+    /// Sets [`coordinates`](InferenceOptions::coordinates).
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.options.coordinates = coordinates;
+        self
+    }
+
+    /// Sets [`tuples`](InferenceOptions::tuples).
+    pub fn tuples(mut self, tuples: bool) -> Self {
+        self.options.tuples = tuples;
+        self
+    }
+
+    /// Sets [`financial_identifiers`](InferenceOptions::financial_identifiers).
+    pub fn financial_identifiers(mut self, financial_identifiers: bool) -> Self {
+        self.options.financial_identifiers = financial_identifiers;
+        self
+    }
+
+    /// Sets [`barcodes`](InferenceOptions::barcodes).
+    pub fn barcodes(mut self, barcodes: bool) -> Self {
+        self.options.barcodes = barcodes;
+        self
+    }
+
+    /// Sets [`money`](InferenceOptions::money).
+    pub fn money(mut self, money: bool) -> Self {
+        self.options.money = money;
+        self
+    }
+
+    /// Sets [`durations`](InferenceOptions::durations).
+    pub fn durations(mut self, durations: bool) -> Self {
+        self.options.durations = durations;
+        self
+    }
+
+    /// Sets [`numeric_strings`](InferenceOptions::numeric_strings).
+    pub fn numeric_strings(mut self, numeric_strings: bool) -> Self {
+        self.options.numeric_strings = numeric_strings;
+        self
+    }
+
+    /// Sets [`non_finite`](InferenceOptions::non_finite).
+    pub fn non_finite(mut self, non_finite: bool) -> Self {
+        self.options.non_finite = non_finite;
+        self
+    }
+
+    /// Sets [`date_formats`](InferenceOptions::date_formats).
+    pub fn date_formats(mut self, date_formats: Vec<String>) -> Self {
+        self.options.date_formats = date_formats;
+        self
+    }
+
+    /// Sets [`sequences`](InferenceOptions::sequences).
+    pub fn sequences(mut self, sequences: bool) -> Self {
+        self.options.sequences = sequences;
+        self
+    }
+
+    /// Sets [`bitflags`](InferenceOptions::bitflags).
+    pub fn bitflags(mut self, bitflags: bool) -> Self {
+        self.options.bitflags = bitflags;
+        self
+    }
+
+    /// Sets [`vocabularies`](InferenceOptions::vocabularies).
+    pub fn vocabularies(mut self, vocabularies: Vec<Vocabulary>) -> Self {
+        self.options.vocabularies = vocabularies;
+        self
+    }
+
+    /// Sets [`max_depth`](InferenceOptions::max_depth).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets [`max_object_keys`](InferenceOptions::max_object_keys).
+    pub fn max_object_keys(mut self, max_object_keys: usize) -> Self {
+        self.options.max_object_keys = Some(max_object_keys);
+        self
+    }
+
+    /// Sets [`maps`](InferenceOptions::maps).
+    pub fn maps(mut self, maps: bool) -> Self {
+        self.options.maps = maps;
+        self
+    }
+
+    /// Sets [`union_types`](InferenceOptions::union_types).
+    pub fn union_types(mut self, union_types: bool) -> Self {
+        self.options.union_types = union_types;
+        self
+    }
+
+    /// The options built.
+    pub fn build(self) -> InferenceOptions {
+        self.options
+    }
+}
+
+/// A named, controlled vocabulary: the full set of values a string field may take.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    /// The name of the vocabulary, such as `http-methods`.
+    pub name: String,
+    pub values: std::collections::BTreeSet<String>,
 }
 
-fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
+pub(crate) fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
     match (initial, new) {
-        (SchemaState::Initial, s)
-        | (s, SchemaState::Initial)
-        | (SchemaState::Indefinite, s)
-        | (s, SchemaState::Indefinite) => s,
+        (SchemaState::Initial, s) | (s, SchemaState::Initial) => s,
+        // values of conflicting types stay of any type whatever else is seen, so that the
+        // result doesn't depend on the order that values are merged in
+        (SchemaState::Indefinite, SchemaState::Null | SchemaState::Nullable(_))
+        | (SchemaState::Null | SchemaState::Nullable(_), SchemaState::Indefinite) => {
+            SchemaState::Nullable(Box::new(SchemaState::Indefinite))
+        }
+        (SchemaState::Indefinite, _) | (_, SchemaState::Indefinite) => SchemaState::Indefinite,
+
+        // --- Literal merging ---
+        (SchemaState::Literal(mut values), SchemaState::Literal(second_values)) => {
+            for value in second_values {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            SchemaState::Literal(values)
+        }
+        // values of other types are merged as those that the literals would be inferred as, but
+        // null only makes them nullable
+        (SchemaState::Literal(values), other) | (other, SchemaState::Literal(values))
+            if !matches!(other, SchemaState::Null | SchemaState::Nullable(_)) =>
+        {
+            merge(SchemaState::literal_type(&values), other)
+        }
 
         // --- String merging ---
         (
@@ -53,8 +432,12 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
                 _ => None,
             };
 
-            chars_seen.extend(second_chars_seen);
-            strings_seen.extend(second_strings_seen);
+            // only the first of the strings and their characters are kept, so that the schema
+            // of a field of many different strings doesn't grow with the input
+            let room = MAX_CHARS_SEEN.saturating_sub(chars_seen.len());
+            chars_seen.extend(second_chars_seen.into_iter().take(room));
+            let room = MAX_STRINGS_SEEN.saturating_sub(strings_seen.len());
+            strings_seen.extend(second_strings_seen.into_iter().take(room));
 
             SchemaState::String(StringType::Unknown {
                 strings_seen,
@@ -64,9 +447,72 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
             })
         }
 
+        // a fixed-width numeric string absorbs plain strings of the same shape, such as
+        // "10001" alongside "00042"
+        (
+            SchemaState::String(StringType::FixedWidthNumeric { width }),
+            SchemaState::String(unknown @ StringType::Unknown { .. }),
+        )
+        | (
+            SchemaState::String(unknown @ StringType::Unknown { .. }),
+            SchemaState::String(StringType::FixedWidthNumeric { width }),
+        ) => {
+            if is_numeric_of_width(&unknown, width) {
+                SchemaState::String(StringType::FixedWidthNumeric { width })
+            } else {
+                SchemaState::String(unknown)
+            }
+        }
+
+        (
+            SchemaState::String(StringType::Charset {
+                class,
+                min_length,
+                max_length,
+            }),
+            SchemaState::String(StringType::Charset {
+                class: second_class,
+                min_length: second_min_length,
+                max_length: second_max_length,
+            }),
+        ) if class == second_class => SchemaState::String(StringType::Charset {
+            class,
+            min_length: cmp::min(min_length, second_min_length),
+            max_length: cmp::max(max_length, second_max_length),
+        }),
+
+        // ISBN-13s are the EAN-13s of books
+        (SchemaState::String(StringType::Isbn13), SchemaState::String(StringType::Ean13))
+        | (SchemaState::String(StringType::Ean13), SchemaState::String(StringType::Isbn13)) => {
+            SchemaState::String(StringType::Ean13)
+        }
+
         (s @ SchemaState::String(StringType::Unknown { .. }), SchemaState::String(_))
         | (SchemaState::String(_), s @ SchemaState::String(StringType::Unknown { .. })) => s,
 
+        // only the formats that every value parses with are kept, in order of preference
+        (
+            SchemaState::String(StringType::CustomDateTime { formats }),
+            SchemaState::String(StringType::CustomDateTime {
+                formats: second_formats,
+            }),
+        ) => {
+            let formats: Vec<String> = formats
+                .into_iter()
+                .filter(|format| second_formats.contains(format))
+                .collect();
+            SchemaState::String(if formats.is_empty() {
+                StringType::Unknown {
+                    strings_seen: vec![],
+                    chars_seen: vec![],
+                    min_length: None,
+                    max_length: None,
+                }
+            } else {
+                StringType::CustomDateTime { formats }
+            })
+        }
+
         (SchemaState::String(first_type), SchemaState::String(second_type)) => {
             if first_type == second_type {
                 SchemaState::String(first_type)
@@ -85,20 +531,28 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
             SchemaState::Number(NumberType::Float {
                 min: first_min,
                 max: first_max,
+                non_finite: mut first_non_finite,
             }),
             SchemaState::Number(NumberType::Float {
                 min: second_min,
                 max: second_max,
+                non_finite: second_non_finite,
             }),
-        ) => SchemaState::Number(NumberType::Float {
-            min: first_min.min(second_min),
-            max: first_max.max(second_max),
-        }),
+        ) => {
+            // `f64::min` and `f64::max` ignore the NaN range of only non-finite values
+            first_non_finite.extend(second_non_finite);
+            SchemaState::Number(NumberType::Float {
+                min: first_min.min(second_min),
+                max: first_max.max(second_max),
+                non_finite: first_non_finite,
+            })
+        }
 
         (
             SchemaState::Number(NumberType::Float {
                 min: first_min,
                 max: first_max,
+                non_finite,
             }),
             SchemaState::Number(NumberType::Integer {
                 min: second_min,
@@ -107,6 +561,7 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
         ) => SchemaState::Number(NumberType::Float {
             min: first_min.min(second_min as f64),
             max: first_max.max(second_max as f64),
+            non_finite,
         }),
 
         (
@@ -117,10 +572,12 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
             SchemaState::Number(NumberType::Float {
                 min: second_min,
                 max: second_max,
+                non_finite,
             }),
         ) => SchemaState::Number(NumberType::Float {
             min: (first_min as f64).min(second_min),
             max: (first_max as f64).max(second_max),
+            non_finite,
         }),
 
         (
@@ -137,6 +594,136 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
             max: cmp::max(first_max, second_max),
         }),
 
+        (
+            SchemaState::Number(NumberType::Enum {
+                variants: mut first_variants,
+            }),
+            SchemaState::Number(NumberType::Enum {
+                variants: second_variants,
+            }),
+        ) => {
+            for (value, count) in second_variants {
+                *first_variants.entry(value).or_insert(0) += count;
+            }
+            SchemaState::Number(NumberType::Enum {
+                variants: first_variants,
+            })
+        }
+
+        // the values of the second sequence were seen after those of the first
+        (
+            SchemaState::Number(NumberType::Sequence {
+                min,
+                max,
+                start,
+                end,
+                count,
+                increases,
+            }),
+            SchemaState::Number(NumberType::Sequence {
+                min: second_min,
+                max: second_max,
+                start: second_start,
+                end: second_end,
+                count: second_count,
+                increases: second_increases,
+            }),
+        ) => SchemaState::Number(NumberType::Sequence {
+            min: cmp::min(min, second_min),
+            max: cmp::max(max, second_max),
+            start,
+            end: second_end,
+            count: count + second_count,
+            increases: increases + second_increases + usize::from(second_start > end),
+        }),
+
+        (
+            SchemaState::Number(NumberType::Sequence { min, max, .. }),
+            other @ SchemaState::Number(_),
+        )
+        | (
+            other @ SchemaState::Number(_),
+            SchemaState::Number(NumberType::Sequence { min, max, .. }),
+        ) => merge(SchemaState::Number(NumberType::Integer { min, max }), other),
+
+        (
+            SchemaState::Number(NumberType::Flags { mut values }),
+            SchemaState::Number(NumberType::Flags {
+                values: second_values,
+            }),
+        ) => {
+            for (value, count) in second_values {
+                *values.entry(value).or_insert(0) += count;
+            }
+            SchemaState::Number(NumberType::Flags { values })
+        }
+
+        (SchemaState::Number(NumberType::Flags { values }), other @ SchemaState::Number(_))
+        | (other @ SchemaState::Number(_), SchemaState::Number(NumberType::Flags { values })) => {
+            merge(enum_as_range(&values), other)
+        }
+
+        (SchemaState::Number(NumberType::Enum { variants }), other @ SchemaState::Number(_))
+        | (other @ SchemaState::Number(_), SchemaState::Number(NumberType::Enum { variants })) => {
+            merge(enum_as_range(&variants), other)
+        }
+
+        (
+            SchemaState::Number(NumberType::Multiple {
+                step,
+                min,
+                max,
+                integer,
+            }),
+            SchemaState::Number(NumberType::Multiple {
+                step: second_step,
+                min: second_min,
+                max: second_max,
+                integer: second_integer,
+            }),
+        ) if step == second_step && integer == second_integer => {
+            SchemaState::Number(NumberType::Multiple {
+                step,
+                min: min.min(second_min),
+                max: max.max(second_max),
+                integer,
+            })
+        }
+
+        (
+            SchemaState::Number(NumberType::Multiple {
+                min, max, integer, ..
+            }),
+            other @ SchemaState::Number(_),
+        )
+        | (
+            other @ SchemaState::Number(_),
+            SchemaState::Number(NumberType::Multiple {
+                min, max, integer, ..
+            }),
+        ) => merge(multiples_as_range(min, max, integer), other),
+
+        // --- Extended JSON merging ---
+        (
+            SchemaState::ExtendedJson(ExtendedJsonType::Long {
+                min: first_min,
+                max: first_max,
+            }),
+            SchemaState::ExtendedJson(ExtendedJsonType::Long {
+                min: second_min,
+                max: second_max,
+            }),
+        ) => SchemaState::ExtendedJson(ExtendedJsonType::Long {
+            min: cmp::min(first_min, second_min),
+            max: cmp::max(first_max, second_max),
+        }),
+
+        (SchemaState::ExtendedJson(first_type), SchemaState::ExtendedJson(second_type))
+            if first_type == second_type =>
+        {
+            SchemaState::ExtendedJson(first_type)
+        }
+
         // --- Boolean merging ---
         (SchemaState::Boolean, SchemaState::Boolean) => SchemaState::Boolean,
 
@@ -145,58 +732,233 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
             SchemaState::Array {
                 min_length,
                 max_length,
+                lengths,
+                unique_items,
                 schema,
             },
             SchemaState::Array {
                 min_length: second_min_length,
                 max_length: second_max_length,
+                lengths: second_lengths,
+                unique_items: second_unique_items,
                 schema: second_schema,
             },
         ) => {
+            let unique_items = merge_unique_items(
+                (unique_items, max_length),
+                (second_unique_items, second_max_length),
+            );
             let min_length = cmp::min(min_length, second_min_length);
             let max_length = cmp::max(max_length, second_max_length);
+            let lengths = match (lengths, second_lengths) {
+                (Some(mut lengths), Some(second_lengths)) => {
+                    for (length, count) in second_lengths {
+                        *lengths.entry(length).or_insert(0) += count;
+                    }
+                    Some(lengths)
+                }
+                _ => None,
+            };
             let schema = Box::new(merge(*schema, *second_schema));
             SchemaState::Array {
                 min_length,
                 max_length,
+                lengths,
+                unique_items,
                 schema,
             }
         }
 
+        // --- Coordinates merging ---
+        (SchemaState::Coordinates(first_positions), SchemaState::Coordinates(second_positions))
+            if first_positions.len() == second_positions.len() =>
+        {
+            let positions = first_positions
+                .into_iter()
+                .zip(second_positions)
+                .map(|(first, second)| {
+                    match merge(SchemaState::Number(first), SchemaState::Number(second)) {
+                        SchemaState::Number(number_type) => number_type,
+                        _ => unreachable!(),
+                    }
+                })
+                .collect();
+            SchemaState::Coordinates(positions)
+        }
+
+        // --- Tuple merging ---
+        (SchemaState::Tuple(first_positions), SchemaState::Tuple(second_positions))
+            if first_positions.len() == second_positions.len() =>
+        {
+            SchemaState::Tuple(
+                first_positions
+                    .into_iter()
+                    .zip(second_positions)
+                    .map(|(first, second)| merge(first, second))
+                    .collect(),
+            )
+        }
+
+        (
+            SchemaState::Tuple(positions),
+            other @ (SchemaState::Array { .. }
+            | SchemaState::Tuple(_)
+            | SchemaState::Coordinates(_)),
+        )
+        | (
+            other @ (SchemaState::Array { .. } | SchemaState::Coordinates(_)),
+            SchemaState::Tuple(positions),
+        ) => merge(tuple_as_array(positions), other),
+
+        (
+            SchemaState::Coordinates(positions),
+            other @ (SchemaState::Array { .. } | SchemaState::Coordinates(_)),
+        )
+        | (other @ SchemaState::Array { .. }, SchemaState::Coordinates(positions)) => {
+            merge(coordinates_as_array(positions), other)
+        }
+
+        // --- Geographic point merging ---
+        (
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            },
+            SchemaState::GeoPoint {
+                latitude_key: second_latitude_key,
+                longitude_key: second_longitude_key,
+                latitude: second_latitude,
+                longitude: second_longitude,
+            },
+        ) if latitude_key == second_latitude_key && longitude_key == second_longitude_key => {
+            let number = |first, second| match merge(
+                SchemaState::Number(first),
+                SchemaState::Number(second),
+            ) {
+                SchemaState::Number(number_type) => number_type,
+                _ => unreachable!(),
+            };
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude: number(latitude, second_latitude),
+                longitude: number(longitude, second_longitude),
+            }
+        }
+
+        (
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            },
+            other @ (SchemaState::Object { .. } | SchemaState::GeoPoint { .. }),
+        )
+        | (
+            other @ SchemaState::Object { .. },
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            },
+        ) => merge(
+            geo_point_as_object(latitude_key, longitude_key, latitude, longitude),
+            other,
+        ),
+
+        // --- Money merging ---
+        (
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                mut currencies,
+            },
+            SchemaState::Money {
+                amount_key: second_amount_key,
+                currency_key: second_currency_key,
+                amount: second_amount,
+                currencies: second_currencies,
+            },
+        ) if amount_key == second_amount_key && currency_key == second_currency_key => {
+            let amount = match merge(
+                SchemaState::Number(amount),
+                SchemaState::Number(second_amount),
+            ) {
+                SchemaState::Number(number_type) => number_type,
+                _ => unreachable!(),
+            };
+            currencies.extend(second_currencies);
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            }
+        }
+
+        (
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            },
+            other @ (SchemaState::Object { .. } | SchemaState::Money { .. }),
+        )
+        | (
+            other @ SchemaState::Object { .. },
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            },
+        ) => merge(
+            money_as_object(amount_key, currency_key, amount, currencies),
+            other,
+        ),
+
         // --- Object merging ---
         (
             SchemaState::Object {
                 required: mut first_required,
                 optional: mut first_optional,
+                access: mut first_access,
+                annotations: mut first_annotations,
             },
             SchemaState::Object {
                 required: mut second_required,
                 optional: mut second_optional,
+                access: second_access,
+                annotations: second_annotations,
             },
         ) => {
-            let required_keys: std::collections::HashSet<String> = first_required
+            // the fields are kept in the order in which they were first seen
+            let required_keys: indexmap::IndexSet<String> = first_required
                 .keys()
                 .filter(|k| second_required.contains_key(*k))
                 .cloned()
                 .collect();
 
-            let optional_keys: std::collections::HashSet<String> = first_optional
+            let optional_keys: indexmap::IndexSet<String> = first_required
                 .keys()
+                .chain(first_optional.keys())
+                .chain(second_required.keys())
                 .chain(second_optional.keys())
-                .chain(
-                    first_required
-                        .keys()
-                        .chain(second_required.keys())
-                        .filter(|key| !required_keys.contains(*key)),
-                )
+                .filter(|key| !required_keys.contains(*key))
                 .cloned()
                 .collect();
 
-            let required: std::collections::HashMap<String, SchemaState> = required_keys
+            let required: IndexMap<String, SchemaState> = required_keys
                 .into_iter()
                 .map(|k| {
-                    let first = first_required.remove(&k);
-                    let second = second_required.remove(&k);
+                    let first = first_required.swap_remove(&k);
+                    let second = second_required.swap_remove(&k);
                     let merged = match (first, second) {
                         (Some(first), Some(second)) => merge(first, second),
                         (Some(first), None) => first,
@@ -207,15 +969,15 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
                 })
                 .collect();
 
-            let optional: std::collections::HashMap<String, SchemaState> = optional_keys
+            let optional: IndexMap<String, SchemaState> = optional_keys
                 .into_iter()
                 .map(|k| {
                     let first = first_required
-                        .remove(&k)
-                        .or_else(|| first_optional.remove(&k));
+                        .swap_remove(&k)
+                        .or_else(|| first_optional.swap_remove(&k));
                     let second = second_required
-                        .remove(&k)
-                        .or_else(|| second_optional.remove(&k));
+                        .swap_remove(&k)
+                        .or_else(|| second_optional.swap_remove(&k));
                     let merged = match (first, second) {
                         (Some(first), Some(second)) => merge(first, second),
                         (Some(first), None) => first,
@@ -226,7 +988,90 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
                 })
                 .collect();
 
-            SchemaState::Object { required, optional }
+            for (key, access) in second_access {
+                first_access.entry(key).or_insert(access);
+            }
+            for (key, keywords) in second_annotations {
+                first_annotations.entry(key).or_insert(keywords);
+            }
+
+            SchemaState::Object {
+                required,
+                optional,
+                access: first_access,
+                annotations: first_annotations,
+            }
+        }
+
+        // --- Union merging ---
+        (SchemaState::Union(mut variants), SchemaState::Union(second_variants)) => {
+            for variant in second_variants {
+                let signature = shape_signature(&variant);
+                match variants
+                    .iter()
+                    .position(|v| signature.is_some() && shape_signature(v) == signature)
+                {
+                    Some(idx) => {
+                        let existing = std::mem::replace(&mut variants[idx], SchemaState::Initial);
+                        variants[idx] = merge(existing, variant);
+                    }
+                    None => variants.push(variant),
+                }
+            }
+            SchemaState::Union(variants)
+        }
+
+        // --- Mixed merging ---
+        (SchemaState::Mixed(mut variants), SchemaState::Mixed(second_variants)) => {
+            for (variant, count) in second_variants {
+                match variants
+                    .iter()
+                    .position(|(v, _)| json_type(v) == json_type(&variant))
+                {
+                    Some(idx) => {
+                        let (existing, seen) =
+                            std::mem::replace(&mut variants[idx], (SchemaState::Initial, 0));
+                        variants[idx] = (merge(existing, variant), seen + count);
+                    }
+                    None => variants.push((variant, count)),
+                }
+            }
+            SchemaState::Mixed(variants)
+        }
+
+        // --- Tagged union merging ---
+        (
+            SchemaState::TaggedUnion { tag, mut variants },
+            SchemaState::TaggedUnion {
+                tag: second_tag,
+                variants: second_variants,
+            },
+        ) if tag == second_tag => {
+            for (value, variant) in second_variants {
+                let existing = variants.remove(&value).unwrap_or(SchemaState::Initial);
+                variants.insert(value, merge(existing, variant));
+            }
+            SchemaState::TaggedUnion { tag, variants }
+        }
+
+        // objects without a tag, or with another one, merge with the objects of every variant
+        (
+            SchemaState::TaggedUnion { variants, .. },
+            other @ (SchemaState::Object { .. }
+            | SchemaState::TaggedUnion { .. }
+            | SchemaState::Union(_)),
+        )
+        | (
+            other @ (SchemaState::Object { .. } | SchemaState::Union(_)),
+            SchemaState::TaggedUnion { variants, .. },
+        ) => merge(
+            variants.into_values().fold(SchemaState::Initial, merge),
+            other,
+        ),
+
+        (union @ SchemaState::Union(_), object @ SchemaState::Object { .. })
+        | (object @ SchemaState::Object { .. }, union @ SchemaState::Union(_)) => {
+            merge(union, SchemaState::Union(vec![object]))
         }
 
         // --- Null(able) merging ---
@@ -254,614 +1099,3638 @@ fn merge(initial: SchemaState, new: SchemaState) -> SchemaState {
     }
 }
 
-fn apply_enum_inner(s: StringType, opts: &EnumInference) -> StringType {
-    match &s {
+fn is_numeric_of_width(string_type: &StringType, width: usize) -> bool {
+    match string_type {
         StringType::Unknown { strings_seen, .. } => {
-            if strings_seen.len() < opts.min_sample_size {
-                return s;
-            }
-
-            let variants = strings_seen
-                .iter()
-                .cloned()
-                .collect::<std::collections::HashSet<_>>();
-
-            let unique_ratio = variants.len() as f64 / strings_seen.len() as f64;
-            if unique_ratio > opts.max_unique_ratio {
-                return s;
-            }
-
-            StringType::Enum { variants }
+            !strings_seen.is_empty()
+                && strings_seen
+                    .iter()
+                    .all(|s| s.len() == width && s.bytes().all(|b| b.is_ascii_digit()))
         }
-        _ => s,
+        _ => false,
     }
 }
 
-fn apply_enum_recursive(s: SchemaState, opts: &EnumInference) -> SchemaState {
+/// The distinct values among the strings seen, with how often each was seen. With
+/// `normalize`, strings that are equal after trimming and lowercasing are grouped, and the most
+/// common original spelling in each group (the first in sorted order, in case of a tie) is its
+/// value.
+fn variant_counts(strings_seen: &[String], normalize: bool) -> Vec<(String, usize)> {
+    let mut groups: BTreeMap<String, BTreeMap<&str, usize>> = BTreeMap::new();
+    for s in strings_seen {
+        let key = if normalize {
+            s.trim().to_lowercase()
+        } else {
+            s.clone()
+        };
+        *groups.entry(key).or_default().entry(s).or_insert(0) += 1;
+    }
+    groups
+        .into_values()
+        .filter_map(|spellings| {
+            let count = spellings.values().sum();
+            spellings
+                .into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+                .map(|(spelling, _)| (spelling.to_string(), count))
+        })
+        .collect()
+}
+
+/// Infers an open-ended enum of the most common values, if they cover at least `coverage` of the
+/// strings seen and are few enough to pass the ratio test of an enum.
+fn open_tail_enum(
+    strings_seen: &[String],
+    opts: &EnumInference,
+    coverage: f64,
+) -> Option<StringType> {
+    let mut counts = variant_counts(strings_seen, opts.normalize);
+    // the most common values first, in sorted order in case of a tie
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let total = strings_seen.len();
+    let mut covered = 0;
+    let mut variants = std::collections::BTreeSet::new();
+    for (variant, count) in counts {
+        if covered as f64 >= coverage * total as f64 {
+            break;
+        }
+        covered += count;
+        variants.insert(variant);
+    }
+    if covered == total || !opts.allows(variants.len(), total) {
+        return None;
+    }
+
+    let is_variant = |s: &String| {
+        if opts.normalize {
+            let key = s.trim().to_lowercase();
+            variants.iter().any(|v| v.trim().to_lowercase() == key)
+        } else {
+            variants.contains(s)
+        }
+    };
+    let other = strings_seen
+        .iter()
+        .filter(|s| !is_variant(s))
+        .fold(SchemaState::Initial, |other, s| {
+            merge(other, SchemaState::String(unknown_string_type(s)))
+        });
+    let SchemaState::String(other) = other else {
+        return None;
+    };
+    Some(StringType::OpenEnum {
+        variants,
+        other_ratio: (total - covered) as f64 / total as f64,
+        other: Box::new(other),
+    })
+}
+
+fn apply_enum_inner(s: StringType, opts: &EnumInference) -> StringType {
+    match &s {
+        StringType::Unknown { strings_seen, .. } => {
+            if strings_seen.len() < opts.min_sample_size {
+                return s;
+            }
+
+            let variants: std::collections::BTreeSet<_> =
+                variant_counts(strings_seen, opts.normalize)
+                    .into_iter()
+                    .map(|(variant, _)| variant)
+                    .collect();
+
+            if !opts.allows(variants.len(), strings_seen.len()) {
+                return match opts.open_tail_coverage {
+                    Some(coverage) => open_tail_enum(strings_seen, opts, coverage).unwrap_or(s),
+                    None => s,
+                };
+            }
+
+            StringType::Enum { variants }
+        }
+        _ => s,
+    }
+}
+
+/// Collapses an object into a map, merging the schemas of all of its fields into a single value
+/// schema and inferring the type of its keys from the field names.
+fn object_as_map(
+    required: IndexMap<String, SchemaState>,
+    optional: IndexMap<String, SchemaState>,
+) -> SchemaState {
+    let min_length = required.len();
+    let max_length = required.len() + optional.len();
+
+    let (keys, schema) = required.into_iter().chain(optional).fold(
+        (SchemaState::Initial, SchemaState::Initial),
+        |(keys, schema), (key, value)| {
+            (
+                merge(keys, SchemaState::String(infer_string_type(&key))),
+                merge(schema, value),
+            )
+        },
+    );
+    let keys = match keys {
+        SchemaState::String(keys) => keys,
+        _ => StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: None,
+            max_length: None,
+        },
+    };
+
+    SchemaState::Map {
+        min_length,
+        max_length,
+        keys,
+        schema: Box::new(schema),
+    }
+}
+
+/// Applies `f` to each of the schemas directly nested in the given schema (object fields, array
+/// elements, etc.), leaving the schema itself as-is.
+fn map_children(s: SchemaState, f: &impl Fn(SchemaState) -> SchemaState) -> SchemaState {
     match s {
-        SchemaState::String(s) => SchemaState::String(apply_enum_inner(s, opts)),
+        SchemaState::Nullable(inner) => SchemaState::Nullable(Box::new(f(*inner))),
         SchemaState::Array {
             min_length,
             max_length,
+            lengths,
+            unique_items,
             schema,
         } => SchemaState::Array {
             min_length,
             max_length,
-            schema: Box::new(apply_enum_recursive(*schema, opts)),
+            lengths,
+            unique_items,
+            schema: Box::new(f(*schema)),
+        },
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            annotations,
+        } => SchemaState::Object {
+            required: required.into_iter().map(|(k, v)| (k, f(v))).collect(),
+            optional: optional.into_iter().map(|(k, v)| (k, f(v))).collect(),
+            access,
+            annotations,
+        },
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema: Box::new(f(*schema)),
         },
-        SchemaState::Object { required, optional } => SchemaState::Object {
-            required: required
+        SchemaState::Tuple(positions) => SchemaState::Tuple(positions.into_iter().map(f).collect()),
+        SchemaState::Union(variants) => SchemaState::Union(variants.into_iter().map(f).collect()),
+        SchemaState::Mixed(variants) => SchemaState::Mixed(
+            variants
                 .into_iter()
-                .map(|(k, v)| (k, apply_enum_recursive(v, opts)))
+                .map(|(variant, count)| (f(variant), count))
                 .collect(),
-            optional: optional
+        ),
+        SchemaState::TaggedUnion { tag, variants } => SchemaState::TaggedUnion {
+            tag,
+            variants: variants
                 .into_iter()
-                .map(|(k, v)| (k, apply_enum_recursive(v, opts)))
+                .map(|(value, variant)| (value, f(variant)))
                 .collect(),
         },
-        SchemaState::Nullable(inner) => {
-            SchemaState::Nullable(Box::new(apply_enum_recursive(*inner, opts)))
-        }
         _ => s,
     }
 }
 
-/// Infer a schema, encoded as a SchemaState struct, from a JSON value.
-/// This function will recursively traverse the given JSON structure and return a SchemaState struct.
-///
-/// # Example
-///
-/// ```
-/// use serde_json::json;
-/// use std::collections::{HashMap, HashSet};
-/// use drivel::{infer_schema, SchemaState, StringType, NumberType, InferenceOptions};
-///
-/// let opts = InferenceOptions {
-///     enum_inference: None
-/// };
-///
-/// // Define a JSON value
-/// let input = json!({
-///     "name": "John",
-///     "age": 30,
-///     "is_student": false,
-///     "grades": [85, 92, 78]
-/// });
-///
-/// assert_eq!(
-///     infer_schema(input, &opts),
-///     SchemaState::Object {
-///         required: HashMap::from_iter([
-///             ("name".to_string(), SchemaState::String(StringType::Unknown {
-///                 strings_seen: vec!["John".to_string()],
-///                 chars_seen: vec!['J', 'o', 'h', 'n'],
-///                 min_length: Some(4),
-///                 max_length: Some(4)
-///             })),
-///             ("age".to_string(), SchemaState::Number(NumberType::Integer { min: 30, max: 30 })),
-///             ("is_student".to_string(), SchemaState::Boolean),
-///             ("grades".to_string(), SchemaState::Array {
-///                 min_length: 3,
-///                 max_length: 3,
-///                 schema: Box::new(SchemaState::Number(NumberType::Integer { min: 78, max: 92 }))
-///             }),
-///         ]),
-///         optional: HashMap::new()
-///     }
-/// );
-/// ```
-pub fn infer_schema(json: serde_json::Value, options: &InferenceOptions) -> SchemaState {
-    let inferred = match json {
-        serde_json::Value::Null => SchemaState::Null,
-        serde_json::Value::String(value) => SchemaState::String(infer_string_type(&value)),
-        serde_json::Value::Number(n) => SchemaState::Number(if n.is_f64() {
-            NumberType::Float {
-                min: n.as_f64().unwrap(),
-                max: n.as_f64().unwrap(),
-            }
-        } else {
-            NumberType::Integer {
-                min: n.as_i64().unwrap(),
-                max: n.as_i64().unwrap(),
-            }
-        }),
-        serde_json::Value::Bool(_) => SchemaState::Boolean,
-        serde_json::Value::Array(array) => SchemaState::Array {
-            min_length: array.len(),
-            max_length: array.len(),
-            schema: Box::new(infer_schema_from_iter(array, options)),
-        },
-        serde_json::Value::Object(object) => SchemaState::Object {
-            required: object
-                .into_iter()
-                .map(|(k, v)| (k, infer_schema(v, options)))
-                .collect(),
-            optional: std::collections::HashMap::new(),
-        },
+fn apply_field_cap_recursive(s: SchemaState, max_fields: usize) -> SchemaState {
+    let recurse = |s| apply_field_cap_recursive(s, max_fields);
+    match s {
+        SchemaState::Object {
+            required, optional, ..
+        } if required.len() + optional.len() > max_fields => {
+            map_children(object_as_map(required, optional), &recurse)
+        }
+        _ => map_children(s, &recurse),
+    }
+}
+
+fn apply_key_cap_recursive(s: SchemaState, max_keys: usize) -> SchemaState {
+    match s {
+        SchemaState::Object {
+            ref required,
+            ref optional,
+            ..
+        } if required.len() + optional.len() > max_keys => SchemaState::Indefinite,
+        _ => map_children(s, &|s| apply_key_cap_recursive(s, max_keys)),
+    }
+}
+
+/// The fewest fields an object of values of one schema needs to be recognised as a map when its
+/// keys don't look like data.
+const MIN_MAP_FIELDS: usize = 100;
+
+/// Whether an object key looks like data rather than the name of a field, such as a date, a
+/// UUID or a numeric ID.
+fn is_data_key(key: &str) -> bool {
+    (!key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()))
+        || !matches!(
+            infer_string_type(key),
+            StringType::Unknown { .. } | StringType::Hostname
+        )
+}
+
+/// Whether two values are of the same schema as far as telling maps apart from records goes: of
+/// the same type, nullable or not, and with the same fields if they are objects.
+fn same_shape(a: &SchemaState, b: &SchemaState) -> bool {
+    let strip = |s: &SchemaState| match s {
+        SchemaState::Nullable(inner) => std::mem::discriminant(inner.as_ref()),
+        _ => std::mem::discriminant(s),
     };
+    strip(a) == strip(b) && shape_signature(a) == shape_signature(b)
+}
 
-    if let Some(enum_opts) = &options.enum_inference {
-        apply_enum_recursive(inferred, enum_opts)
+/// Whether an object looks like a map rather than a record: its values are all of one schema,
+/// and either its keys all look like data or it has so many fields that it can hardly be a
+/// record. The values of objects with data keys only need to merge without conflicts, so that
+/// records in a map can have optional fields.
+fn is_map(
+    required: &IndexMap<String, SchemaState>,
+    optional: &IndexMap<String, SchemaState>,
+) -> bool {
+    let mut fields = required.iter().chain(optional);
+    let Some((_, first)) = fields.clone().next() else {
+        return false;
+    };
+    if required.len() + optional.len() >= 2 && fields.clone().all(|(key, _)| is_data_key(key)) {
+        let merged = fields.fold(SchemaState::Initial, |merged, (_, value)| {
+            merge(merged, value.clone())
+        });
+        !matches!(
+            merged,
+            SchemaState::Indefinite | SchemaState::Union(_) | SchemaState::Mixed(_)
+        )
     } else {
-        inferred
+        required.len() + optional.len() >= MIN_MAP_FIELDS
+            && fields.all(|(_, value)| same_shape(first, value))
     }
 }
 
-/// Infer a schema, encoded as a SchemaState struct, from an iterator of JSON values.
-///
-/// This function iterates over a collection of JSON values and infers the schema by
-/// merging schemas inferred from individual JSON values. The resulting schema reflects
-/// the combined schema of all JSON values in the iterator.
-///
-/// # Example
-///
-/// ```
-/// use serde_json::json;
-/// use std::collections::{HashMap, HashSet};
-/// use drivel::{infer_schema_from_iter, SchemaState, StringType, NumberType, InferenceOptions};
-///
-/// // Define a collection of JSON values
-/// let values = vec![
-///     json!({
-///         "name": "Alice",
-///         "age": 30,
-///         "is_student": true
-///     }),
-///     json!({
-///         "name": "Bob",
-///         "age": 25,
-///         "is_student": false
-///     })
-/// ];
-///
-/// let opts = InferenceOptions {
-///     enum_inference: None
-/// };
-///
-/// // Infer the schema from the iterator of JSON values
-/// let schema = infer_schema_from_iter(values, &opts);
-///
-/// assert_eq!(
-///     schema,
-///     SchemaState::Object {
-///         required: HashMap::from_iter([
-///             ("name".to_string(), SchemaState::String(StringType::Unknown {
-///                 strings_seen: vec!["Alice".to_string(), "Bob".to_string()],
-///                 chars_seen: vec!['A', 'l', 'i', 'c', 'e', 'B', 'o', 'b'],
-///                 min_length: Some(3),
-///                 max_length: Some(5)
-///             })),
-///             ("age".to_string(), SchemaState::Number(NumberType::Integer { min: 25, max: 30 })),
-///             ("is_student".to_string(), SchemaState::Boolean),
-///         ]),
-///         optional: HashMap::new()
-///     }
-/// );
-/// ```
-pub fn infer_schema_from_iter(
-    values: Vec<serde_json::Value>,
-    options: &InferenceOptions,
-) -> SchemaState {
-    values
-        .into_par_iter()
-        .map(|value| infer_schema(value, options))
-        .reduce(|| SchemaState::Initial, merge)
+fn apply_map_detection_recursive(s: SchemaState) -> SchemaState {
+    match map_children(s, &apply_map_detection_recursive) {
+        SchemaState::Object {
+            required, optional, ..
+        } if is_map(&required, &optional) => object_as_map(required, optional),
+        s => s,
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use serde_json::json;
+/// The integer range spanned by the values of a candidate numeric enum.
+fn enum_as_range(variants: &BTreeMap<i64, usize>) -> SchemaState {
+    match (variants.keys().next(), variants.keys().next_back()) {
+        (Some(&min), Some(&max)) => SchemaState::Number(NumberType::Integer { min, max }),
+        _ => SchemaState::Initial,
+    }
+}
 
-    use super::*;
+/// The range of integers or floats spanned by multiples of a step, once values of other number
+/// types are seen alongside them.
+fn multiples_as_range(min: f64, max: f64, integer: bool) -> SchemaState {
+    SchemaState::Number(if integer {
+        NumberType::Integer {
+            min: min as i64,
+            max: max as i64,
+        }
+    } else {
+        NumberType::Float {
+            min,
+            max,
+            non_finite: Default::default(),
+        }
+    })
+}
 
-    fn no_enum_options() -> InferenceOptions {
-        InferenceOptions {
-            enum_inference: None,
+fn apply_numeric_enum(n: NumberType, opts: &EnumInference) -> SchemaState {
+    match &n {
+        NumberType::Enum { variants } => {
+            let total = variants.values().sum::<usize>();
+            if total < opts.min_sample_size || !opts.allows(variants.len(), total) {
+                enum_as_range(variants)
+            } else {
+                SchemaState::Number(n)
+            }
         }
+        _ => SchemaState::Number(n),
     }
+}
 
-    #[test]
-    fn infers_null() {
-        let input = json!(null);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+fn apply_enum_recursive(s: SchemaState, opts: &EnumInference) -> SchemaState {
+    let recurse = |s| apply_enum_recursive(s, opts);
+    match s {
+        SchemaState::String(s) => SchemaState::String(apply_enum_inner(s, opts)),
+        SchemaState::Number(n) => apply_numeric_enum(n, opts),
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => SchemaState::Map {
+            min_length,
+            max_length,
+            keys: apply_enum_inner(keys, opts),
+            schema: Box::new(recurse(*schema)),
+        },
+        _ => map_children(s, &recurse),
+    }
+}
 
-        assert_eq!(schema, SchemaState::Null)
+/// The minimum number of values of a sequence.
+const MIN_SEQUENCE_LENGTH: usize = 3;
+/// The minimum fraction of the values of a sequence that must be greater than the value before
+/// them, allowing a few to be out of order.
+const MIN_INCREASING_RATIO: f64 = 0.9;
+
+/// Replaces candidate sequences whose values don't increase consistently enough with plain
+/// integer ranges.
+fn apply_sequence_recursive(s: SchemaState) -> SchemaState {
+    match s {
+        SchemaState::Number(NumberType::Sequence {
+            min,
+            max,
+            count,
+            increases,
+            ..
+        }) if count < MIN_SEQUENCE_LENGTH
+            || (increases as f64) < MIN_INCREASING_RATIO * (count - 1) as f64 =>
+        {
+            SchemaState::Number(NumberType::Integer { min, max })
+        }
+        _ => map_children(s, &apply_sequence_recursive),
     }
+}
 
-    #[test]
-    fn infers_string_unknown_type() {
-        let input = json!("foo");
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+/// The minimum number of values of bit flags.
+const MIN_FLAG_VALUES: usize = 20;
+/// The fewest flags of bit flags, with fewer being as likely a small range of integers.
+const MIN_FLAGS: usize = 3;
+/// The most flags of bit flags.
+const MAX_FLAGS: usize = 16;
 
-        assert_eq!(
-            schema,
-            SchemaState::String(StringType::Unknown {
-                strings_seen: vec!["foo".to_owned()],
-                chars_seen: vec!['f', 'o', 'o'],
-                min_length: Some(3),
-                max_length: Some(3)
+/// Whether the values of candidate bit flags look like a bitmask: there are enough of them, of
+/// a few flags that are each seen on their own, and at least half of them are a single flag
+/// rather than a combination, unlike the values of a range of integers such as 0 to 7.
+fn is_bitmask(number_type: &NumberType) -> bool {
+    let NumberType::Flags { values } = number_type else {
+        return false;
+    };
+    let total = values.values().sum::<usize>();
+    let single = values
+        .iter()
+        .filter(|(value, _)| value.count_ones() == 1)
+        .map(|(_, count)| count)
+        .sum::<usize>();
+    let flags = number_type.flags();
+    total >= MIN_FLAG_VALUES
+        && (MIN_FLAGS..=MAX_FLAGS).contains(&flags.len())
+        && flags.iter().all(|flag| values.contains_key(flag))
+        && 2 * single >= total
+}
+
+/// Replaces candidate bit flags that don't look like a bitmask with numeric enums, if they are
+/// enabled, or plain integer ranges.
+fn apply_flags_recursive(s: SchemaState, numeric_enums: bool) -> SchemaState {
+    match s {
+        SchemaState::Number(number_type @ NumberType::Flags { .. }) if is_bitmask(&number_type) => {
+            SchemaState::Number(number_type)
+        }
+        SchemaState::Number(NumberType::Flags { values }) if numeric_enums => {
+            SchemaState::Number(NumberType::Enum { variants: values })
+        }
+        SchemaState::Number(NumberType::Flags { values }) => enum_as_range(&values),
+        _ => map_children(s, &|s| apply_flags_recursive(s, numeric_enums)),
+    }
+}
+
+/// The fraction of the fields of either of two object shapes that both have, from 0 for shapes
+/// without any fields in common to 1 for shapes with the same fields.
+fn shape_overlap(first: &SchemaState, second: &SchemaState) -> f64 {
+    match (shape_signature(first), shape_signature(second)) {
+        (Some(first), Some(second)) if first.is_empty() && second.is_empty() => 1.0,
+        (Some(first), Some(second)) => {
+            first.intersection(&second).count() as f64 / first.union(&second).count() as f64
+        }
+        _ => 0.0,
+    }
+}
+
+/// Merges the object shapes of unions whose fields overlap by at least `threshold`, until no two
+/// shapes of a union do.
+fn apply_merge_threshold_recursive(s: SchemaState, threshold: f64) -> SchemaState {
+    match s {
+        SchemaState::Union(variants) => {
+            let mut merged: Vec<SchemaState> = vec![];
+            let mut pending = variants;
+            while let Some(variant) = pending.pop() {
+                match merged
+                    .iter()
+                    .position(|other| shape_overlap(other, &variant) >= threshold)
+                {
+                    // the merged shape may now overlap enough with shapes it didn't before
+                    Some(idx) => pending.push(merge(merged.remove(idx), variant)),
+                    None => merged.push(variant),
+                }
+            }
+            merged.reverse();
+            map_children(SchemaState::Union(merged), &|s| {
+                apply_merge_threshold_recursive(s, threshold)
             })
-        )
+        }
+        _ => map_children(s, &|s| apply_merge_threshold_recursive(s, threshold)),
     }
+}
 
-    #[test]
-    fn infers_string_iso_date() {
-        let input = json!("2013-01-12");
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+/// Replaces unions of a single object shape with that object.
+fn collapse_unions_recursive(s: SchemaState) -> SchemaState {
+    match s {
+        SchemaState::Union(mut variants) if variants.len() == 1 => {
+            collapse_unions_recursive(variants.remove(0))
+        }
+        _ => map_children(s, &collapse_unions_recursive),
+    }
+}
 
-        assert_eq!(schema, SchemaState::String(StringType::IsoDate))
+/// The most distinct values that a tag field found by [`Discriminator::Auto`] may have.
+const MAX_TAG_VALUES: usize = 20;
+
+/// Names that tag fields are commonly given, which are preferred over other fields that tell the
+/// shapes of a union apart just as well.
+const COMMON_TAG_NAMES: [&str; 6] = ["type", "kind", "event", "event_type", "tag", "@type"];
+
+/// The values of a tag field, and the indices of the shapes of a union with those values.
+type TagGroup = (BTreeSet<String>, Vec<usize>);
+
+/// Finds the tag field of a union of object shapes, and groups the shapes by its values into
+/// the variants of a tagged union. A tag field is a string field that every shape has, whose
+/// values split the shapes into at least two groups, where the shapes with a value in common
+/// are grouped together. Of several such fields, the one with the fewest values is the tag.
+fn find_discriminator(variants: &[SchemaState]) -> Option<SchemaState> {
+    let tag_values = |variant: &SchemaState, tag: &str| match variant {
+        SchemaState::Object { required, .. } => match required.get(tag) {
+            Some(SchemaState::String(StringType::Unknown { strings_seen, .. }))
+                if !strings_seen.is_empty() =>
+            {
+                Some(strings_seen.iter().cloned().collect::<BTreeSet<_>>())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let SchemaState::Object { required, .. } = variants.first()? else {
+        return None;
+    };
+    let mut candidates: Vec<_> = required
+        .keys()
+        .filter_map(|tag| {
+            let values: Vec<_> = variants
+                .iter()
+                .map(|variant| tag_values(variant, tag))
+                .collect::<Option<_>>()?;
+            // shapes with a value in common are merged, until the groups are disjoint
+            let mut groups: Vec<TagGroup> = vec![];
+            for (index, values) in values.into_iter().enumerate() {
+                let mut group = (values, vec![index]);
+                groups.retain(|(other_values, other_indices)| {
+                    if group.0.is_disjoint(other_values) {
+                        return true;
+                    }
+                    group.0.extend(other_values.iter().cloned());
+                    group.1.extend(other_indices);
+                    false
+                });
+                groups.push(group);
+            }
+            let distinct = groups.iter().map(|(values, _)| values.len()).sum::<usize>();
+            let common = COMMON_TAG_NAMES
+                .iter()
+                .position(|name| name == tag)
+                .unwrap_or(COMMON_TAG_NAMES.len());
+            (groups.len() > 1 && distinct <= MAX_TAG_VALUES)
+                .then_some((distinct, common, tag, groups))
+        })
+        .collect();
+    candidates.sort_by(|a, b| (a.0, a.1, a.2).cmp(&(b.0, b.1, b.2)));
+    let (_, _, tag, groups) = candidates.into_iter().next()?;
+
+    let mut tagged = BTreeMap::new();
+    for (values, indices) in groups {
+        let schema = indices
+            .into_iter()
+            .map(|index| variants[index].clone())
+            .fold(SchemaState::Initial, merge);
+        for value in values {
+            tagged.insert(value, schema.clone());
+        }
+    }
+    Some(SchemaState::TaggedUnion {
+        tag: tag.clone(),
+        variants: tagged,
+    })
+}
+
+/// Replaces unions of object shapes that have a tag field with tagged unions, and with
+/// `keep_unions` unset, merges the shapes of the others into a single object, as they would
+/// have been without looking for tags.
+fn apply_discriminators_recursive(s: SchemaState, keep_unions: bool) -> SchemaState {
+    let recurse = |s| apply_discriminators_recursive(s, keep_unions);
+    match s {
+        SchemaState::Union(variants) => {
+            let schema = match find_discriminator(&variants) {
+                Some(tagged) => tagged,
+                None if keep_unions => SchemaState::Union(variants),
+                None => variants.into_iter().fold(SchemaState::Initial, merge),
+            };
+            map_children(schema, &recurse)
+        }
+        _ => map_children(s, &recurse),
+    }
+}
+
+/// Whether the elements of the arrays of two schemas, each with whether its elements are
+/// distinct and its longest length, are distinct. Arrays of at most one element can't have
+/// duplicates, but say nothing about whether the elements are distinct either.
+pub(crate) fn merge_unique_items(
+    (unique_items, max_length): (bool, usize),
+    (second_unique_items, second_max_length): (bool, usize),
+) -> bool {
+    (unique_items || max_length <= 1)
+        && (second_unique_items || second_max_length <= 1)
+        && cmp::max(max_length, second_max_length) > 1
+}
+
+/// The JSON type of the values of a schema, by which the variants of a mixed schema are told
+/// apart.
+pub(crate) fn json_type(s: &SchemaState) -> &'static str {
+    match s {
+        SchemaState::Nullable(inner) => json_type(inner),
+        SchemaState::Null => "null",
+        SchemaState::Boolean => "boolean",
+        SchemaState::Literal(values) => json_type(&SchemaState::literal_type(values)),
+        SchemaState::Number(_) => "number",
+        SchemaState::String(_) => "string",
+        SchemaState::Array { .. } | SchemaState::Coordinates(_) | SchemaState::Tuple(_) => "array",
+        _ => "object",
+    }
+}
+
+/// Replaces mixed schemas of a single type, which all values are wrapped in while inferring,
+/// with the schema of that type, and orders the variants of the others by how many values of
+/// them were seen, most first.
+fn collapse_mixed_recursive(s: SchemaState) -> SchemaState {
+    match s {
+        SchemaState::Mixed(mut variants) if variants.len() == 1 => {
+            let (variant, _) = variants.remove(0);
+            collapse_mixed_recursive(variant)
+        }
+        SchemaState::Mixed(variants) => {
+            let mut variants: Vec<_> = variants
+                .into_iter()
+                .map(|(variant, count)| (collapse_mixed_recursive(variant), count))
+                .collect();
+            variants.sort_by(|(a, a_count), (b, b_count)| {
+                b_count.cmp(a_count).then(json_type(a).cmp(json_type(b)))
+            });
+            SchemaState::Mixed(variants)
+        }
+        _ => map_children(s, &collapse_mixed_recursive),
+    }
+}
+
+/// The set of field names of an object shape, used to decide which variant of a union an object
+/// belongs to.
+pub(crate) fn shape_signature(s: &SchemaState) -> Option<std::collections::BTreeSet<&String>> {
+    match s {
+        SchemaState::Object {
+            required, optional, ..
+        } => Some(required.keys().chain(optional.keys()).collect()),
+        _ => None,
+    }
+}
+
+/// Whether an array looks like a coordinate: two or three numbers.
+fn is_coordinates(array: &[serde_json::Value]) -> bool {
+    (2..=3).contains(&array.len()) && array.iter().all(|v| v.is_number())
+}
+
+/// The most elements a tuple may have, beyond which arrays are lists rather than records of
+/// positions.
+const MAX_TUPLE_LENGTH: usize = 8;
+
+/// Whether an array looks like a tuple: a few elements, of more than one type besides `null`.
+fn is_tuple(array: &[serde_json::Value]) -> bool {
+    let mut types = array
+        .iter()
+        .filter(|value| !value.is_null())
+        .map(std::mem::discriminant);
+    (2..=MAX_TUPLE_LENGTH).contains(&array.len())
+        && types
+            .next()
+            .is_some_and(|first| types.any(|other| other != first))
+}
+
+/// Recognises an object of a latitude and a longitude in their ranges, such as
+/// `{"lat": 52.37, "lng": 4.89}`, as a geographic point.
+fn infer_geo_point(object: &serde_json::Map<String, serde_json::Value>) -> Option<SchemaState> {
+    let (latitude_key, longitude_key) = geo_point_keys(object.keys())?;
+    let number = |key: &String, range: f64| {
+        let value = object[key].as_f64().filter(|value| value.abs() <= range)?;
+        Some(match object[key].as_i64() {
+            Some(n) => NumberType::Integer { min: n, max: n },
+            None => NumberType::Float {
+                min: value,
+                max: value,
+                non_finite: BTreeSet::new(),
+            },
+        })
+    };
+    Some(SchemaState::GeoPoint {
+        latitude: number(latitude_key, 90.0)?,
+        longitude: number(longitude_key, 180.0)?,
+        latitude_key: latitude_key.clone(),
+        longitude_key: longitude_key.clone(),
+    })
+}
+
+/// The fields of a geographic point as an object, for when points are merged with objects that
+/// aren't points with the same fields.
+fn geo_point_as_object(
+    latitude_key: String,
+    longitude_key: String,
+    latitude: NumberType,
+    longitude: NumberType,
+) -> SchemaState {
+    SchemaState::Object {
+        required: IndexMap::from([
+            (latitude_key, SchemaState::Number(latitude)),
+            (longitude_key, SchemaState::Number(longitude)),
+        ]),
+        optional: IndexMap::new(),
+        access: Default::default(),
+        annotations: Default::default(),
+    }
+}
+
+/// The fields of money as an object, for when it is merged with objects that aren't money with
+/// the same fields.
+fn money_as_object(
+    amount_key: String,
+    currency_key: String,
+    amount: NumberType,
+    currencies: BTreeSet<String>,
+) -> SchemaState {
+    let currency = currencies
+        .iter()
+        .map(|currency| SchemaState::String(unknown_string_type(currency)))
+        .reduce(merge)
+        .unwrap_or(SchemaState::Initial);
+    SchemaState::Object {
+        required: IndexMap::from([
+            (amount_key, SchemaState::Number(amount)),
+            (currency_key, currency),
+        ]),
+        optional: IndexMap::new(),
+        access: Default::default(),
+        annotations: Default::default(),
+    }
+}
+
+/// Whether an array has more than one element, all of them distinct strings, numbers or
+/// booleans.
+fn has_distinct_scalars(array: &[serde_json::Value]) -> bool {
+    let mut seen = HashSet::new();
+    array.len() > 1
+        && array.iter().all(|value| match value {
+            serde_json::Value::String(_)
+            | serde_json::Value::Number(_)
+            | serde_json::Value::Bool(_) => seen.insert(value.to_string()),
+            _ => false,
+        })
+}
+
+/// The numbers of coordinates as an array, for when coordinates are merged with arrays that
+/// are not coordinates of the same dimension.
+fn coordinates_as_array(positions: Vec<NumberType>) -> SchemaState {
+    let length = positions.len();
+    let schema = positions
+        .into_iter()
+        .map(SchemaState::Number)
+        .fold(SchemaState::Initial, merge);
+    SchemaState::Array {
+        min_length: length,
+        max_length: length,
+        lengths: None,
+        unique_items: false,
+        schema: Box::new(schema),
+    }
+}
+
+/// The positions of a tuple as an array, for when tuples are merged with arrays that aren't
+/// tuples of the same length.
+pub(crate) fn tuple_as_array(positions: Vec<SchemaState>) -> SchemaState {
+    let length = positions.len();
+    SchemaState::Array {
+        min_length: length,
+        max_length: length,
+        lengths: None,
+        unique_items: false,
+        schema: Box::new(positions.into_iter().fold(SchemaState::Initial, merge)),
+    }
+}
+
+/// Infers the type of a single string, in the formats enabled by the options.
+fn infer_string_format(value: &str, options: &InferenceOptions) -> StringType {
+    infer_date_format(value, &options.date_formats)
+        .or_else(|| {
+            options
+                .financial_identifiers
+                .then(|| infer_financial(value))
+                .flatten()
+        })
+        .or_else(|| options.barcodes.then(|| infer_barcode(value)).flatten())
+        .or_else(|| options.durations.then(|| infer_duration(value)).flatten())
+        .unwrap_or_else(|| infer_string_type(value))
+}
+
+/// Infers the format of a string field from all of the values seen, if there are at least the
+/// minimum number of them. The values are merged as they would have been during inference, so
+/// a format is only inferred if every value has it.
+fn apply_format_inner(
+    s: StringType,
+    format_opts: &FormatInferenceOptions,
+    options: &InferenceOptions,
+) -> StringType {
+    match &s {
+        StringType::Unknown { strings_seen, .. }
+            if !strings_seen.is_empty() && strings_seen.len() >= format_opts.min_sample_size =>
+        {
+            let inferred = strings_seen
+                .iter()
+                .map(|value| SchemaState::String(infer_string_format(value, options)))
+                .fold(SchemaState::Initial, merge);
+            match inferred {
+                // keep the original, which has the values seen for enum inference
+                SchemaState::String(StringType::Unknown { .. }) => s,
+                SchemaState::String(string_type) => string_type,
+                _ => s,
+            }
+        }
+        _ => s,
+    }
+}
+
+fn apply_format_recursive(
+    s: SchemaState,
+    format_opts: &FormatInferenceOptions,
+    options: &InferenceOptions,
+) -> SchemaState {
+    let recurse = |s| apply_format_recursive(s, format_opts, options);
+    match s {
+        SchemaState::String(s) => SchemaState::String(apply_format_inner(s, format_opts, options)),
+        _ => map_children(s, &recurse),
+    }
+}
+
+/// Classifies string fields whose values all belong to one of the vocabularies as an enum of the
+/// full vocabulary, not only of the values seen.
+fn apply_vocabulary_recursive(s: SchemaState, vocabularies: &[Vocabulary]) -> SchemaState {
+    match s {
+        SchemaState::String(StringType::Unknown {
+            ref strings_seen, ..
+        }) if !strings_seen.is_empty() => {
+            let vocabulary = vocabularies.iter().find(|vocabulary| {
+                strings_seen
+                    .iter()
+                    .all(|value| vocabulary.values.contains(value))
+            });
+            match vocabulary {
+                Some(vocabulary) => SchemaState::String(StringType::Enum {
+                    variants: vocabulary.values.clone(),
+                }),
+                None => s,
+            }
+        }
+        _ => map_children(s, &|s| apply_vocabulary_recursive(s, vocabularies)),
+    }
+}
+
+/// Classifies string fields of no particular format, with at least `min_sample_size` values
+/// that are all made of one class of characters, such as uppercase letters, as strings of that
+/// class, or that all share a structure, such as ids like `ORD-2024-00173`, as strings of its
+/// pattern.
+fn apply_character_class_recursive(s: SchemaState, min_sample_size: usize) -> SchemaState {
+    match s {
+        SchemaState::String(StringType::Unknown {
+            ref strings_seen,
+            min_length: Some(min_length),
+            max_length: Some(max_length),
+            ..
+        }) if strings_seen.len() >= min_sample_size => match infer_character_class(strings_seen) {
+            Some(class) => SchemaState::String(StringType::Charset {
+                class,
+                min_length,
+                max_length,
+            }),
+            None => match infer_structure(strings_seen) {
+                Some(pattern) => SchemaState::String(StringType::Pattern {
+                    pattern,
+                    min_length: None,
+                    max_length: None,
+                }),
+                None => s,
+            },
+        },
+        _ => map_children(s, &|s| apply_character_class_recursive(s, min_sample_size)),
+    }
+}
+
+/// Classifies string fields whose values are all numbers as numeric strings.
+fn apply_numeric_strings_recursive(s: SchemaState) -> SchemaState {
+    match s {
+        SchemaState::String(StringType::Unknown {
+            ref strings_seen, ..
+        }) => match infer_numeric_string(strings_seen) {
+            Some(numeric) => SchemaState::String(numeric),
+            None => s,
+        },
+        _ => map_children(s, &apply_numeric_strings_recursive),
+    }
+}
+
+/// Infers the schema of a single value.
+///
+/// With numeric enum inference enabled, integers are inferred as single-valued enum candidates,
+/// which are resolved to an enum or a range by enum inference. With `union_objects`, objects are
+/// inferred as single-variant unions, so that objects of different shapes are kept apart when
+/// merged.
+fn infer_raw(json: &serde_json::Value, options: &InferenceOptions) -> SchemaState {
+    infer_raw_at(json, options, 0)
+}
+
+/// Infers the schema of a value nested in `depth` levels of objects and arrays. With
+/// [`union_types`](InferenceOptions::union_types), the schemas of values other than null are
+/// wrapped in a mixed schema of their type, so that the values of each type are counted as the
+/// schemas are merged.
+fn infer_raw_at(json: &serde_json::Value, options: &InferenceOptions, depth: usize) -> SchemaState {
+    match infer_value_at(json, options, depth) {
+        schema @ (SchemaState::Null | SchemaState::Indefinite) => schema,
+        schema if options.union_types => SchemaState::Mixed(vec![(schema, 1)]),
+        schema => schema,
+    }
+}
+
+fn infer_value_at(
+    json: &serde_json::Value,
+    options: &InferenceOptions,
+    depth: usize,
+) -> SchemaState {
+    let numeric_enums = options
+        .enum_inference
+        .as_ref()
+        .is_some_and(|opts| opts.include_numeric);
+    match json {
+        serde_json::Value::Null => SchemaState::Null,
+        serde_json::Value::String(value)
+            if options.non_finite && NonFiniteNumber::from_token(value).is_some() =>
+        {
+            // NaN bounds are left as they are by the bounds of finite numbers merged in
+            SchemaState::Number(NumberType::Float {
+                min: f64::NAN,
+                max: f64::NAN,
+                non_finite: NonFiniteNumber::from_token(value).into_iter().collect(),
+            })
+        }
+        // formats are inferred once all values of a field have been seen, if a minimum number
+        // of values is required, or if the values may all be numbers
+        serde_json::Value::String(value)
+            if options.format_inference.is_some() || options.numeric_strings =>
+        {
+            SchemaState::String(unknown_string_type(value))
+        }
+        serde_json::Value::String(value) => {
+            SchemaState::String(infer_string_format(value, options))
+        }
+        serde_json::Value::Number(n) => SchemaState::Number(match n.as_i64() {
+            // integers too large for an i64 are treated as floats, like any other number that
+            // isn't an i64
+            None => {
+                let n = n.as_f64().unwrap_or_default();
+                NumberType::Float {
+                    min: n,
+                    max: n,
+                    non_finite: BTreeSet::new(),
+                }
+            }
+            Some(n) if options.sequences => NumberType::Sequence {
+                min: n,
+                max: n,
+                start: n,
+                end: n,
+                count: 1,
+                increases: 0,
+            },
+            Some(n) if options.bitflags && n >= 0 => NumberType::Flags {
+                values: BTreeMap::from([(n, 1)]),
+            },
+            Some(n) if numeric_enums => NumberType::Enum {
+                variants: BTreeMap::from([(n, 1)]),
+            },
+            Some(n) => NumberType::Integer { min: n, max: n },
+        }),
+        serde_json::Value::Bool(_) => SchemaState::Boolean,
+        // objects and arrays beyond the limits are not looked into
+        serde_json::Value::Array(_) | serde_json::Value::Object(_)
+            if options
+                .max_depth
+                .is_some_and(|max_depth| depth >= max_depth) =>
+        {
+            SchemaState::Indefinite
+        }
+        serde_json::Value::Object(object)
+            if options
+                .max_object_keys
+                .is_some_and(|max_keys| object.len() > max_keys) =>
+        {
+            SchemaState::Indefinite
+        }
+        serde_json::Value::Array(array) if options.coordinates && is_coordinates(array) => {
+            SchemaState::Coordinates(
+                array
+                    .iter()
+                    .map(|v| match v.as_i64() {
+                        Some(n) => NumberType::Integer { min: n, max: n },
+                        None => {
+                            let n = v.as_f64().unwrap_or_default();
+                            NumberType::Float {
+                                min: n,
+                                max: n,
+                                non_finite: BTreeSet::new(),
+                            }
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(array) if options.tuples && is_tuple(array) => SchemaState::Tuple(
+            array
+                .iter()
+                .map(|v| infer_raw_at(v, options, depth + 1))
+                .collect(),
+        ),
+        serde_json::Value::Array(array) => SchemaState::Array {
+            min_length: array.len(),
+            max_length: array.len(),
+            lengths: options
+                .length_distribution
+                .then(|| BTreeMap::from([(array.len(), 1)])),
+            unique_items: has_distinct_scalars(array),
+            schema: Box::new(
+                array
+                    .par_iter()
+                    .with_min_len(PARALLEL_CHUNK_SIZE)
+                    .fold(
+                        || SchemaState::Initial,
+                        |state, v| merge(state, infer_raw_at(v, options, depth + 1)),
+                    )
+                    .reduce(|| SchemaState::Initial, merge),
+            ),
+        },
+        serde_json::Value::Object(object) => {
+            if options.extended_json {
+                if let Some(extended_type) = infer_extended_json(object) {
+                    return SchemaState::ExtendedJson(extended_type);
+                }
+            }
+            if options.coordinates {
+                if let Some(geo_point) = infer_geo_point(object) {
+                    return geo_point;
+                }
+            }
+            if options.money {
+                if let Some(money) = infer_money(object) {
+                    return money;
+                }
+            }
+            let schema = SchemaState::Object {
+                required: object
+                    .iter()
+                    .map(|(k, v)| (k.clone(), infer_raw_at(v, options, depth + 1)))
+                    .collect(),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            };
+            if let Some(Discriminator::Field(tag)) = &options.discriminator {
+                if let Some(serde_json::Value::String(value)) = object.get(tag) {
+                    return SchemaState::TaggedUnion {
+                        tag: tag.clone(),
+                        variants: BTreeMap::from([(value.clone(), schema)]),
+                    };
+                }
+            }
+            // with a discriminator to be found, shapes are kept apart until it is looked for
+            if options.union_objects || matches!(options.discriminator, Some(Discriminator::Auto)) {
+                SchemaState::Union(vec![schema])
+            } else {
+                schema
+            }
+        }
+    }
+}
+
+/// An accumulator for incremental schema inference.
+///
+/// Values are observed one at a time, each being treated as another sample of the same schema,
+/// and the accumulated schema is returned by [`SchemaInferer::finish`]. Observing a value only
+/// infers its structure and merges it into the accumulated state; decisions that need to take
+/// all samples into account, such as enum inference, are deferred until `finish`.
+///
+/// Inferers that have observed disjoint sets of values (e.g. on different threads) can be
+/// combined with [`SchemaInferer::merge`].
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+/// use drivel::{InferenceOptions, SchemaInferer, SchemaState, NumberType};
+///
+/// let opts = InferenceOptions::default();
+///
+/// let mut inferer = SchemaInferer::new(&opts);
+/// inferer.observe(&json!(10));
+/// inferer.observe(&json!(20));
+///
+/// assert_eq!(
+///     inferer.finish(),
+///     SchemaState::Number(NumberType::Integer { min: 10, max: 20 })
+/// );
+/// ```
+pub struct SchemaInferer<'a> {
+    options: &'a InferenceOptions,
+    state: SchemaState,
+}
+
+impl<'a> SchemaInferer<'a> {
+    /// Creates an inferer that has not observed any values yet.
+    pub fn new(options: &'a InferenceOptions) -> Self {
+        SchemaInferer {
+            options,
+            state: SchemaState::Initial,
+        }
+    }
+
+    /// Observes a single value, merging its schema into the accumulated schema.
+    pub fn observe(&mut self, value: &serde_json::Value) {
+        let state = std::mem::replace(&mut self.state, SchemaState::Initial);
+        self.state = merge(state, infer_raw(value, self.options));
+    }
+
+    /// Observes a batch of values, as [`observe`](Self::observe) on each of them in turn would,
+    /// but inferring the schemas of chunks of them on separate threads and merging the partial
+    /// schemas. Batches smaller than a chunk are observed on the current thread.
+    pub fn observe_all(&mut self, values: &[serde_json::Value]) {
+        let observed = values
+            .par_iter()
+            .with_min_len(PARALLEL_CHUNK_SIZE)
+            .fold(
+                || SchemaState::Initial,
+                |state, value| merge(state, infer_raw(value, self.options)),
+            )
+            .reduce(|| SchemaState::Initial, merge);
+        let state = std::mem::replace(&mut self.state, SchemaState::Initial);
+        self.state = merge(state, observed);
+    }
+
+    /// Combines the values observed by two inferers into a single inferer.
+    pub fn merge(self, other: SchemaInferer<'a>) -> Self {
+        SchemaInferer {
+            options: self.options,
+            state: merge(self.state, other.state),
+        }
+    }
+
+    /// The number of nodes of the schema accumulated so far, as counted by
+    /// [`SchemaState::node_count`], which tells how much memory inference takes.
+    pub fn node_count(&self) -> usize {
+        self.state.node_count()
+    }
+
+    /// The schema of the values observed so far, as [`finish`](Self::finish) would return it,
+    /// while leaving the inferer free to observe more values. This is useful for reporting a
+    /// partial schema while streaming through a large input.
+    pub fn snapshot(&self) -> SchemaState {
+        finalize(self.state.clone(), self.options)
+    }
+
+    /// Finalizes inference, returning the schema of all values observed.
+    pub fn finish(self) -> SchemaState {
+        finalize(self.state, self.options)
+    }
+}
+
+/// Applies the inference steps that need all values to have been observed, such as recognising
+/// enums and formats, to an accumulated schema.
+fn finalize(state: SchemaState, options: &InferenceOptions) -> SchemaState {
+    let state = if options.union_types {
+        collapse_mixed_recursive(state)
+    } else {
+        state
+    };
+    let state = if matches!(options.discriminator, Some(Discriminator::Auto)) {
+        apply_discriminators_recursive(state, options.union_objects)
+    } else {
+        state
+    };
+    let state = match options.merge_threshold {
+        Some(threshold) if options.union_objects => {
+            apply_merge_threshold_recursive(state, threshold)
+        }
+        _ => state,
+    };
+    let state = if options.union_objects {
+        collapse_unions_recursive(state)
+    } else {
+        state
+    };
+    // objects of a few keys each can still have too many together, such as maps split across
+    // records
+    let state = match options.max_object_keys {
+        Some(max_keys) => apply_key_cap_recursive(state, max_keys),
+        None => state,
+    };
+    let state = if options.maps {
+        apply_map_detection_recursive(state)
+    } else {
+        state
+    };
+    let state = match options.max_object_fields {
+        Some(max_fields) => apply_field_cap_recursive(state, max_fields),
+        None => state,
+    };
+    let state = if options.sequences {
+        apply_sequence_recursive(state)
+    } else {
+        state
+    };
+    let state = if options.bitflags {
+        let numeric_enums = options
+            .enum_inference
+            .as_ref()
+            .is_some_and(|opts| opts.include_numeric);
+        apply_flags_recursive(state, numeric_enums)
+    } else {
+        state
+    };
+    // numbers are told apart from fixed-width numeric strings before their formats are inferred,
+    // which would leave only the strings without leading zeros of a field with both
+    let state = if options.numeric_strings {
+        apply_numeric_strings_recursive(state)
+    } else {
+        state
+    };
+    let state = match &options.format_inference {
+        Some(format_opts) => apply_format_recursive(state, format_opts, options),
+        None if options.numeric_strings => apply_format_recursive(
+            state,
+            &FormatInferenceOptions { min_sample_size: 1 },
+            options,
+        ),
+        None => state,
+    };
+    let state = if options.vocabularies.is_empty() {
+        state
+    } else {
+        apply_vocabulary_recursive(state, &options.vocabularies)
+    };
+    let state = if let Some(enum_opts) = &options.enum_inference {
+        apply_enum_recursive(state, enum_opts)
+    } else {
+        state
+    };
+    // classes of characters come last, so that enums of e.g. uppercase codes stay enums
+    match &options.format_inference {
+        Some(format_opts) => apply_character_class_recursive(state, format_opts.min_sample_size),
+        None => state,
+    }
+}
+
+/// Infer a schema, encoded as a SchemaState struct, from a JSON value.
+/// This function will recursively traverse the given JSON structure and return a SchemaState struct.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+/// use indexmap::IndexMap;
+/// use std::collections::HashSet;
+/// use drivel::{infer_schema, SchemaState, StringType, NumberType, InferenceOptions};
+///
+/// let opts = InferenceOptions::default();
+///
+/// // Define a JSON value
+/// let input = json!({
+///     "name": "John",
+///     "age": 30,
+///     "is_student": false,
+///     "grades": [85, 92, 78]
+/// });
+///
+/// assert_eq!(
+///     infer_schema(input, &opts),
+///     SchemaState::Object {
+///         required: IndexMap::from_iter([
+///             ("name".to_string(), SchemaState::String(StringType::Unknown {
+///                 strings_seen: vec!["John".to_string()],
+///                 chars_seen: vec!['J', 'o', 'h', 'n'],
+///                 min_length: Some(4),
+///                 max_length: Some(4)
+///             })),
+///             ("age".to_string(), SchemaState::Number(NumberType::Integer { min: 30, max: 30 })),
+///             ("is_student".to_string(), SchemaState::Boolean),
+///             ("grades".to_string(), SchemaState::Array {
+///                 min_length: 3,
+///                 max_length: 3,
+///                 lengths: None,
+///                 unique_items: true,
+///                 schema: Box::new(SchemaState::Number(NumberType::Integer { min: 78, max: 92 }))
+///             }),
+///         ]),
+///         optional: IndexMap::new(),
+///         access: Default::default(),
+///         annotations: Default::default(),
+///     }
+/// );
+/// ```
+pub fn infer_schema(json: serde_json::Value, options: &InferenceOptions) -> SchemaState {
+    infer_schema_ref(&json, options)
+}
+
+/// Infer a schema from a borrowed JSON value, like [`infer_schema`], leaving the value available
+/// for other uses (such as validating it against the inferred schema) without cloning it.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+/// use drivel::{infer_schema_ref, InferenceOptions, NumberType, SchemaState};
+///
+/// let opts = InferenceOptions::default();
+///
+/// let input = json!(42);
+/// let schema = infer_schema_ref(&input, &opts);
+///
+/// assert_eq!(schema, SchemaState::Number(NumberType::Integer { min: 42, max: 42 }));
+/// assert_eq!(input, json!(42));
+/// ```
+pub fn infer_schema_ref(json: &serde_json::Value, options: &InferenceOptions) -> SchemaState {
+    let mut inferer = SchemaInferer::new(options);
+    inferer.observe(json);
+    inferer.finish()
+}
+
+/// Infer a schema, encoded as a SchemaState struct, from an iterator of JSON values.
+///
+/// This function iterates over a collection of JSON values and infers the schema by
+/// merging schemas inferred from individual JSON values. The resulting schema reflects
+/// the combined schema of all JSON values in the iterator.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+/// use indexmap::IndexMap;
+/// use std::collections::HashSet;
+/// use drivel::{infer_schema_from_iter, SchemaState, StringType, NumberType, InferenceOptions};
+///
+/// // Define a collection of JSON values
+/// let values = vec![
+///     json!({
+///         "name": "Alice",
+///         "age": 30,
+///         "is_student": true
+///     }),
+///     json!({
+///         "name": "Bob",
+///         "age": 25,
+///         "is_student": false
+///     })
+/// ];
+///
+/// let opts = InferenceOptions::default();
+///
+/// // Infer the schema from the iterator of JSON values
+/// let schema = infer_schema_from_iter(values, &opts);
+///
+/// assert_eq!(
+///     schema,
+///     SchemaState::Object {
+///         required: IndexMap::from_iter([
+///             ("name".to_string(), SchemaState::String(StringType::Unknown {
+///                 strings_seen: vec!["Alice".to_string(), "Bob".to_string()],
+///                 chars_seen: vec!['A', 'l', 'i', 'c', 'e', 'B', 'o', 'b'],
+///                 min_length: Some(3),
+///                 max_length: Some(5)
+///             })),
+///             ("age".to_string(), SchemaState::Number(NumberType::Integer { min: 25, max: 30 })),
+///             ("is_student".to_string(), SchemaState::Boolean),
+///         ]),
+///         optional: IndexMap::new(),
+///         access: Default::default(),
+///         annotations: Default::default(),
+///     }
+/// );
+/// ```
+pub fn infer_schema_from_iter(
+    values: Vec<serde_json::Value>,
+    options: &InferenceOptions,
+) -> SchemaState {
+    values
+        .par_iter()
+        .with_min_len(PARALLEL_CHUNK_SIZE)
+        .fold(
+            || SchemaInferer::new(options),
+            |mut inferer, value| {
+                inferer.observe(value);
+                inferer
+            },
+        )
+        .reduce(|| SchemaInferer::new(options), SchemaInferer::merge)
+        .finish()
+}
+
+/// Splits several documents, such as the contents of multiple files, into the records they
+/// hold, so that a schema can be inferred for all of the records with [`infer_schema_from_iter`].
+///
+/// An array at the root of a document holds one record per element, while any other document
+/// (an object, or a scalar) is a single record. This way, files holding one record each can be
+/// combined with files holding many, and the schema describes a record either way.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+/// use drivel::records_from_documents;
+///
+/// let documents = vec![json!([{"id": 1}, {"id": 2}]), json!({"id": 3})];
+/// assert_eq!(
+///     records_from_documents(documents),
+///     vec![json!({"id": 1}), json!({"id": 2}), json!({"id": 3})]
+/// );
+/// ```
+pub fn records_from_documents(documents: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    documents
+        .into_iter()
+        .flat_map(|document| match document {
+            serde_json::Value::Array(records) => records,
+            record => vec![record],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CharacterClass, ToJsonSchema};
+    use serde_json::json;
+
+    use super::*;
+
+    fn no_enum_options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    #[test]
+    fn infers_integers_beyond_i64_as_floats() {
+        let input = json!([u64::MAX, 1]);
+        let options = InferenceOptions {
+            sequences: true,
+            ..no_enum_options()
+        };
+        assert_eq!(
+            infer_schema(input, &options),
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Number(NumberType::Float {
+                    min: 1.0,
+                    max: u64::MAX as f64,
+                    non_finite: Default::default(),
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_null() {
+        let input = json!(null);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::Null)
+    }
+
+    #[test]
+    fn keeps_a_bounded_sample_of_strings_of_no_particular_format() {
+        let options = no_enum_options();
+        let mut inferer = SchemaInferer::new(&options);
+        let mut observe = |n: usize| {
+            for i in 0..n {
+                inferer.observe(&json!(format!("value {} of many", i)));
+            }
+            match &inferer.state {
+                SchemaState::String(StringType::Unknown {
+                    strings_seen,
+                    chars_seen,
+                    ..
+                }) => (strings_seen.len(), chars_seen.len()),
+                other => panic!("{:?}", other),
+            }
+        };
+
+        let sizes = observe(MAX_STRINGS_SEEN + 1);
+        assert_eq!(sizes, (MAX_STRINGS_SEEN, MAX_CHARS_SEEN));
+        assert_eq!(observe(1000), sizes);
+    }
+
+    #[test]
+    fn infers_string_unknown_type() {
+        let input = json!("foo");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::String(StringType::Unknown {
+                strings_seen: vec!["foo".to_owned()],
+                chars_seen: vec!['f', 'o', 'o'],
+                min_length: Some(3),
+                max_length: Some(3)
+            })
+        )
+    }
+
+    #[test]
+    fn infers_string_iso_date() {
+        let input = json!("2013-01-12");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::IsoDate))
+    }
+
+    #[test]
+    fn infers_string_iso_date_time_rfc_2822() {
+        let input = json!("Thu, 18 Mar 2021 10:37:31 +0000");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::DateTimeRFC2822))
+    }
+
+    #[test]
+    fn infers_string_iso_date_time_rfc_3339_offset() {
+        let input = json!("2013-01-12T00:00:00.000+00:00");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::DateTimeISO8601))
+    }
+
+    #[test]
+    fn infers_string_iso_date_time_rfc_3339_utc() {
+        let input = json!("2013-01-12T00:00:00.000Z");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::DateTimeISO8601))
+    }
+
+    #[test]
+    fn infers_string_uuid() {
+        let input = json!("988c2c6d-df1b-4bb9-b837-6ba706c0b4ad");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::UUID))
+    }
+
+    #[test]
+    fn infers_string_email() {
+        let input = json!("test@example.com");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::Email))
+    }
+
+    #[test]
+    fn infers_string_url() {
+        let input = json!("https://somedomain.somehost.nl/somepage");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::Url))
+    }
+
+    #[test]
+    fn infers_string_hostname() {
+        let input = json!("somehost.com");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::String(StringType::Hostname))
+    }
+
+    #[test]
+    fn infers_ip_addresses() {
+        let options = no_enum_options();
+        for (input, expected) in [
+            (json!(["192.168.0.1", "10.0.0.255"]), StringType::Ipv4),
+            (json!(["2001:db8::8a2e:370:7334", "::1"]), StringType::Ipv6),
+        ] {
+            let SchemaState::Array { schema, .. } = infer_schema(input, &options) else {
+                panic!("not an array");
+            };
+            assert_eq!(*schema, SchemaState::String(expected));
+        }
+        // versions, times and addresses with leading zeros aren't addresses
+        for s in ["1.2.3", "12:30", "010.0.0.1", "256.0.0.1"] {
+            assert!(
+                matches!(infer_string_type(s), StringType::Unknown { .. }),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn infers_string_fixed_width_numeric() {
+        let input = json!("007");
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::String(StringType::FixedWidthNumeric { width: 3 })
+        )
+    }
+
+    #[test]
+    fn infers_array_fixed_width_numeric_with_unpadded_values() {
+        let input = json!(["10001", "00042", "12345"]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 3,
+                max_length: 3,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::String(StringType::FixedWidthNumeric {
+                    width: 5
+                }))
+            }
+        )
+    }
+
+    #[test]
+    fn infers_array_fixed_width_numeric_mixed_widths_as_unknown() {
+        let input = json!(["007", "1234"]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::String(StringType::Unknown {
+                    strings_seen: vec!["1234".to_owned()],
+                    chars_seen: vec!['1', '2', '3', '4'],
+                    min_length: Some(4),
+                    max_length: Some(4)
+                }))
+            }
+        )
+    }
+
+    #[test]
+    fn infers_number() {
+        let input = json!(42);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Number(NumberType::Integer { min: 42, max: 42 })
+        )
+    }
+
+    #[test]
+    fn infers_number_float() {
+        let input = json!(42.0);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Number(NumberType::Float {
+                min: 42.0,
+                max: 42.0,
+                non_finite: Default::default(),
+            })
+        )
+    }
+
+    #[test]
+    fn infers_boolean_true() {
+        let input = json!(true);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::Boolean)
+    }
+
+    #[test]
+    fn infers_boolean_false() {
+        let input = json!(false);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema, SchemaState::Boolean)
+    }
+
+    #[test]
+    fn infers_object() {
+        let input = json!({
+            "string": "foo",
+            "int": 10,
+            "float": 10.4,
+            "bool": false,
+            "array": ["baz"],
+            "null": null,
+            "object": {
+                "string": "foo"
+            }
+        });
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Object {
+                required: IndexMap::from_iter([
+                    (
+                        "string".to_string(),
+                        SchemaState::String(StringType::Unknown {
+                            strings_seen: vec!["foo".to_owned()],
+                            chars_seen: vec!['f', 'o', 'o'],
+                            min_length: Some(3),
+                            max_length: Some(3)
+                        })
+                    ),
+                    (
+                        "int".to_string(),
+                        SchemaState::Number(NumberType::Integer { min: 10, max: 10 })
+                    ),
+                    (
+                        "float".to_string(),
+                        SchemaState::Number(NumberType::Float {
+                            min: 10.4,
+                            max: 10.4,
+                            non_finite: Default::default(),
+                        })
+                    ),
+                    ("bool".to_string(), SchemaState::Boolean),
+                    (
+                        "array".to_string(),
+                        SchemaState::Array {
+                            min_length: 1,
+                            max_length: 1,
+                            lengths: None,
+                            unique_items: false,
+                            schema: Box::new(SchemaState::String(StringType::Unknown {
+                                strings_seen: vec!["baz".to_owned()],
+                                chars_seen: vec!['b', 'a', 'z'],
+                                min_length: Some(3),
+                                max_length: Some(3)
+                            }))
+                        }
+                    ),
+                    ("null".to_string(), SchemaState::Null),
+                    (
+                        "object".to_string(),
+                        SchemaState::Object {
+                            required: IndexMap::from_iter([(
+                                "string".to_owned(),
+                                SchemaState::String(StringType::Unknown {
+                                    strings_seen: vec!["foo".to_owned()],
+                                    chars_seen: vec!['f', 'o', 'o'],
+                                    min_length: Some(3),
+                                    max_length: Some(3)
+                                })
+                            )]),
+                            optional: IndexMap::new(),
+                            access: Default::default(),
+                            annotations: Default::default(),
+                        }
+                    ),
+                ]),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }
+        )
+    }
+
+    #[test]
+    fn infers_array_null() {
+        let input = json!([null, null]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Null)
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_string() {
+        let input = json!(["foo", "barbar"]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::String(StringType::Unknown {
+                    strings_seen: vec!["foo".to_owned(), "barbar".to_owned()],
+                    chars_seen: vec!['f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r'],
+                    min_length: Some(3),
+                    max_length: Some(6)
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_always_null_fields() {
+        let values = vec![json!({"a": null, "b": 1}), json!({"a": null, "b": 2})];
+        let schema = infer_schema_from_iter(values, &no_enum_options());
+
+        let SchemaState::Object { required, .. } = &schema else {
+            panic!("Expected an object schema, got {:?}", schema);
+        };
+        assert_eq!(required["a"], SchemaState::Null);
+        assert_eq!(required["a"].to_string_pretty(), "always null");
+        assert_eq!(
+            crate::ToJsonSchema::to_json_schema(&required["a"]),
+            json!({"type": "null"})
+        );
+        assert_eq!(crate::produce(&schema, 1)["a"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn widens_null_fields_to_nullable_once_typed() {
+        for values in [
+            vec![json!({"a": null}), json!({"a": null}), json!({"a": 5})],
+            vec![json!({"a": 5}), json!({"a": null})],
+        ] {
+            let schema = infer_schema_from_iter(values, &no_enum_options());
+            let SchemaState::Object { required, .. } = &schema else {
+                panic!("Expected an object schema, got {:?}", schema);
+            };
+            assert_eq!(
+                required["a"],
+                SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Integer {
+                    min: 5,
+                    max: 5
+                })))
+            );
+        }
+    }
+
+    #[test]
+    fn infers_records_of_documents_with_mixed_roots() {
+        let documents = vec![
+            json!([{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]),
+            json!({"id": 3}),
+            json!([]),
+            json!([{"id": 4, "name": null}]),
+        ];
+        let schema = infer_schema_from_iter(records_from_documents(documents), &no_enum_options());
+
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            panic!("Expected a record schema, got {:?}", schema);
+        };
+        assert_eq!(
+            required["id"],
+            SchemaState::Number(NumberType::Integer { min: 1, max: 4 })
+        );
+        assert!(matches!(optional["name"], SchemaState::Nullable(_)));
+    }
+
+    #[test]
+    fn infers_sequences_with_a_strong_increasing_trend() {
+        let options = InferenceOptions {
+            sequences: true,
+            vocabularies: vec![],
+            ..no_enum_options()
+        };
+        let infer = |ids: &[i64]| {
+            let records: Vec<_> = ids.iter().map(|id| json!({ "id": id })).collect();
+            match infer_schema_from_iter(records, &options) {
+                SchemaState::Object { mut required, .. } => required.shift_remove("id").unwrap(),
+                other => panic!("Expected an object schema, got {:?}", other),
+            }
+        };
+
+        // gaps are tolerated, as are a few values out of order
+        let ids: Vec<i64> = (1..=40).filter(|id| id % 7 != 0).chain([3]).collect();
+        assert_eq!(
+            infer(&ids),
+            SchemaState::Number(NumberType::Sequence {
+                min: 1,
+                max: 40,
+                start: 1,
+                end: 3,
+                count: ids.len(),
+                increases: ids.len() - 2,
+            })
+        );
+        assert_eq!(
+            infer(&[5, 1, 4, 2, 3]),
+            SchemaState::Number(NumberType::Integer { min: 1, max: 5 })
+        );
+        assert_eq!(
+            infer(&[1, 2]),
+            SchemaState::Number(NumberType::Integer { min: 1, max: 2 })
+        );
+    }
+
+    #[test]
+    fn infers_bitflags_of_single_flags_and_their_combinations() {
+        let options = InferenceOptions {
+            bitflags: true,
+            ..no_enum_options()
+        };
+        let infer = |values: Vec<i64>| {
+            let records: Vec<_> = values.iter().map(|v| json!({ "mode": v })).collect();
+            match infer_schema_from_iter(records, &options) {
+                SchemaState::Object { mut required, .. } => required.shift_remove("mode").unwrap(),
+                other => panic!("Expected an object schema, got {:?}", other),
+            }
+        };
+
+        let permissions: Vec<i64> = [1, 2, 4, 8, 1, 4, 5, 12, 2, 0]
+            .into_iter()
+            .cycle()
+            .take(30)
+            .collect();
+        let schema = infer(permissions);
+        let SchemaState::Number(number_type @ NumberType::Flags { .. }) = &schema else {
+            panic!("Expected flags, got {:?}", schema);
+        };
+        assert_eq!(number_type.flags(), vec![1, 2, 4, 8]);
+
+        // a range of small integers is made of bits too, but mostly of combinations of them
+        assert_eq!(
+            infer((0..40).map(|n| n % 8).collect()),
+            SchemaState::Number(NumberType::Integer { min: 0, max: 7 })
+        );
+        // too few values to tell, and values of flags that are never seen on their own
+        assert!(matches!(
+            infer(vec![1, 2, 4, 8]),
+            SchemaState::Number(NumberType::Integer { .. })
+        ));
+        assert!(matches!(
+            infer([1, 2, 3].into_iter().cycle().take(30).collect()),
+            SchemaState::Number(NumberType::Integer { .. })
+        ));
+        assert!(matches!(
+            infer(
+                [1, 2, 8, 1, 2]
+                    .into_iter()
+                    .cycle()
+                    .take(30)
+                    .chain([-1])
+                    .collect()
+            ),
+            SchemaState::Number(NumberType::Integer { min: -1, max: 8 })
+        ));
+    }
+
+    #[test]
+    fn formats_need_minimum_sample_size() {
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 3 }),
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let uuids = [
+            "48f41410-2d97-4d54-8bfa-aa4e22acca01",
+            "0e3a99a5-0201-4444-9ab1-8343fac56233",
+            "71884608-2760-4853-8c12-e11149c642cd",
+        ];
+        let infer = |values: &[&str]| {
+            infer_schema_from_iter(
+                values.iter().map(|v| json!({ "id": v })).collect(),
+                &options,
+            )
+        };
+        let id_schema = |schema: SchemaState| match schema {
+            SchemaState::Object { mut required, .. } => required.shift_remove("id").unwrap(),
+            other => panic!("Expected an object schema, got {:?}", other),
+        };
+
+        assert_eq!(
+            id_schema(infer(&uuids)),
+            SchemaState::String(StringType::UUID)
+        );
+        assert!(matches!(
+            id_schema(infer(&uuids[..2])),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            id_schema(infer(&[uuids[0], uuids[1], "not a uuid"])),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn infers_character_classes_of_whole_samples() {
+        let options = InferenceOptions {
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 3 }),
+            ..no_enum_options()
+        };
+        let infer = |values: &[&str]| {
+            infer_schema(
+                json!(values
+                    .iter()
+                    .map(|v| json!({ "code": v }))
+                    .collect::<Vec<_>>()),
+                &options,
+            )
+        };
+        let code = |schema: SchemaState| match schema {
+            SchemaState::Array { schema, .. } => match *schema {
+                SchemaState::Object { mut required, .. } => required.shift_remove("code").unwrap(),
+                other => panic!("Expected an object schema, got {:?}", other),
+            },
+            other => panic!("Expected an array schema, got {:?}", other),
+        };
+        let charset = |class, min_length, max_length| {
+            SchemaState::String(StringType::Charset {
+                class,
+                min_length,
+                max_length,
+            })
+        };
+
+        assert_eq!(
+            code(infer(&["NL", "USA", "DE"])),
+            charset(CharacterClass::Uppercase, 2, 3)
+        );
+        assert_eq!(
+            code(infer(&["alpha", "beta", "gamma"])),
+            charset(CharacterClass::Lowercase, 4, 5)
+        );
+        assert_eq!(
+            code(infer(&["9f86d081", "1b4f0e98", "deadbeef"])),
+            charset(CharacterClass::Hex, 8, 8)
+        );
+        assert_eq!(
+            code(infer(&["123", "4567", "89"])),
+            charset(CharacterClass::Digits, 2, 4)
+        );
+        // letters alone aren't hexadecimal
+        assert_eq!(
+            code(infer(&["cafe", "bead", "face"])),
+            charset(CharacterClass::Lowercase, 4, 4)
+        );
+        // the whole sample must share the class
+        for values in [["NL", "USA", "de"], ["NL", "USA", ""], ["a-1", "b2", "c-3"]] {
+            assert!(matches!(
+                code(infer(&values)),
+                SchemaState::String(StringType::Unknown { .. })
+            ));
+        }
+        // nor are classes inferred from fewer values than formats are
+        assert!(matches!(
+            code(infer(&["NL", "USA"])),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn infers_structures_of_ids() {
+        let options = InferenceOptions {
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 3 }),
+            ..no_enum_options()
+        };
+        let infer = |values: &[&str]| match infer_schema(json!(values), &options) {
+            SchemaState::Array { schema, .. } => *schema,
+            other => panic!("Expected an array schema, got {:?}", other),
+        };
+        let pattern = |pattern: &str| {
+            SchemaState::String(StringType::Pattern {
+                pattern: pattern.to_string(),
+                min_length: None,
+                max_length: None,
+            })
+        };
+
+        assert_eq!(
+            infer(&["ORD-2024-00173", "ORD-2025-01940", "ORD-2024-00002"]),
+            pattern("^ORD-[0-9]{4}-[0-9]{5}$")
+        );
+        assert_eq!(
+            infer(&["v1.2.10", "v1.10.3", "v2.0.0"]),
+            pattern("^v[0-9]\\.[0-9]{1,2}\\.[0-9]{1,2}$")
+        );
+        assert_eq!(
+            infer(&["AB-12", "CD-345", "EF-6"]),
+            pattern("^[A-Z]{2}-[0-9]{1,3}$")
+        );
+        // words and names have no structure, nor have strings of different structures
+        for values in [
+            ["Ada Lovelace", "Alan Turing", "Grace Hopper"],
+            ["north-east", "south-west", "north-west"],
+            ["ORD-1", "ORD-2", "ORD_3"],
+            ["x1", "y2", "z3"],
+        ] {
+            assert!(matches!(
+                infer(&values),
+                SchemaState::String(StringType::Unknown { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn normalized_enum_values_collapse() {
+        let input = json!(["Active", "active", " ACTIVE ", "active", "Closed", "closed "]);
+        let options = |normalize: bool| InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.5,
+                max_values: None,
+                min_sample_size: 2,
+                include_numeric: false,
+                normalize,
+                open_tail_coverage: None,
+            }),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        let item_schema = |schema: SchemaState| match schema {
+            SchemaState::Array { schema, .. } => *schema,
+            other => panic!("Expected an array schema, got {:?}", other),
+        };
+        assert_eq!(
+            item_schema(infer_schema(input.clone(), &options(true))),
+            SchemaState::String(StringType::Enum {
+                variants: ["active".to_string(), "Closed".to_string()].into(),
+            })
+        );
+        assert!(matches!(
+            item_schema(infer_schema(input, &options(false))),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn caps_the_number_of_enum_values() {
+        // 50 distinct values out of 1000 pass a ratio of 0.1, but not a cap of 20 values
+        let strings = (0..1000)
+            .map(|i| format!("v{}", i % 50))
+            .collect::<Vec<_>>();
+        let integers = (0..1000).map(|i| i % 50).collect::<Vec<_>>();
+        let options = |max_values| InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.1,
+                max_values,
+                min_sample_size: 1,
+                include_numeric: true,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            ..no_enum_options()
+        };
+        let items = |schema: SchemaState| match schema {
+            SchemaState::Array { schema, .. } => *schema,
+            _ => panic!("Expected an array"),
+        };
+
+        let capped = options(Some(20));
+        assert!(matches!(
+            items(infer_schema(json!(strings), &capped)),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert_eq!(
+            items(infer_schema(json!(integers), &capped)),
+            SchemaState::Number(NumberType::Integer { min: 0, max: 49 })
+        );
+
+        for max_values in [Some(50), None] {
+            assert!(matches!(
+                items(infer_schema(json!(strings), &options(max_values))),
+                SchemaState::String(StringType::Enum { variants }) if variants.len() == 50
+            ));
+            assert!(matches!(
+                items(infer_schema(json!(integers), &options(max_values))),
+                SchemaState::Number(NumberType::Enum { variants }) if variants.len() == 50
+            ));
+        }
+    }
+
+    #[test]
+    fn infers_array_string_enum() {
+        let input = json!(["foo", "barbar", "barbar", "foo"]);
+
+        let enum_opts = EnumInference {
+            max_unique_ratio: 0.5,
+            max_values: None,
+            min_sample_size: 2,
+            include_numeric: false,
+            normalize: false,
+            open_tail_coverage: None,
+        };
+        let options = InferenceOptions {
+            enum_inference: Some(enum_opts),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 4,
+                max_length: 4,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::String(StringType::Enum {
+                    variants: vec!["foo".to_owned(), "barbar".to_owned()]
+                        .into_iter()
+                        .collect()
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_string_enum_uniq_ratio_too_high() {
+        let input = json!(["foo", "barbar", "foo", "barbar"]);
+
+        let enum_opts = EnumInference {
+            max_unique_ratio: 0.4, // 2 unique values out of 4 = unique ratio of 0.5
+            max_values: None,
+            min_sample_size: 2,
+            include_numeric: false,
+            normalize: false,
+            open_tail_coverage: None,
+        };
+        let options = InferenceOptions {
+            enum_inference: Some(enum_opts),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 4,
+                max_length: 4,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::String(StringType::Unknown {
+                    strings_seen: vec![
+                        "foo".to_owned(),
+                        "barbar".to_owned(),
+                        "foo".to_owned(),
+                        "barbar".to_owned()
+                    ],
+                    chars_seen: vec![
+                        'f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r', 'f', 'o', 'o', 'b', 'a', 'r',
+                        'b', 'a', 'r'
+                    ],
+                    min_length: Some(3),
+                    max_length: Some(6)
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_string_enum_sample_size_too_small() {
+        let input = json!(["foo", "barbar", "foo", "barbar"]);
+
+        let enum_opts = EnumInference {
+            max_unique_ratio: 0.5,
+            max_values: None,
+            min_sample_size: 5, // sample size too small (4 vs 5)
+            include_numeric: false,
+            normalize: false,
+            open_tail_coverage: None,
+        };
+        let options = InferenceOptions {
+            enum_inference: Some(enum_opts),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 4,
+                max_length: 4,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::String(StringType::Unknown {
+                    strings_seen: vec![
+                        "foo".to_owned(),
+                        "barbar".to_owned(),
+                        "foo".to_owned(),
+                        "barbar".to_owned()
+                    ],
+                    chars_seen: vec![
+                        'f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r', 'f', 'o', 'o', 'b', 'a', 'r',
+                        'b', 'a', 'r'
+                    ],
+                    min_length: Some(3),
+                    max_length: Some(6)
+                }))
+            }
+        );
+    }
+
+    fn numeric_enum_options(include_numeric: bool) -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.5,
+                max_values: None,
+                min_sample_size: 2,
+                include_numeric,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    #[test]
+    fn infers_array_integer_enum() {
+        let input = json!([200, 404, 200, 200, 500, 404]);
+
+        let schema = infer_schema(input, &numeric_enum_options(true));
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 6,
+                max_length: 6,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Number(NumberType::Enum {
+                    variants: BTreeMap::from([(200, 3), (404, 2), (500, 1)])
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_integer_range_when_numeric_enums_disabled() {
+        let input = json!([200, 404, 200, 200, 500, 404]);
+
+        let schema = infer_schema(input, &numeric_enum_options(false));
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 6,
+                max_length: 6,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Number(NumberType::Integer {
+                    min: 200,
+                    max: 500
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_integer_range_when_uniq_ratio_too_high() {
+        let input = json!([1, 2, 3, 4]);
+
+        let schema = infer_schema(input, &numeric_enum_options(true));
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 4,
+                max_length: 4,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 4 }))
+            }
+        );
+    }
+
+    #[test]
+    fn prefers_integer_enum_over_contiguous_range() {
+        // 1..=3 is also a valid range, but with so few distinct values an enum is more precise
+        let input = json!([1, 2, 3, 1, 2, 3, 1, 2]);
+
+        let schema = infer_schema(input, &numeric_enum_options(true));
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 8,
+                max_length: 8,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Number(NumberType::Enum {
+                    variants: BTreeMap::from([(1, 3), (2, 3), (3, 2)])
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_float_when_integer_enum_mixes_with_floats() {
+        let input = json!([1, 1, 1, 2.5]);
+
+        let schema = infer_schema(input, &numeric_enum_options(true));
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 4,
+                max_length: 4,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Number(NumberType::Float {
+                    min: 1.0,
+                    max: 2.5,
+                    non_finite: Default::default(),
+                }))
+            }
+        );
+    }
+
+    #[test]
+    fn infers_array_string_mixed() {
+        let input = json!(["48f41410-2d97-4d54-8bfa-aa4e22acca01", "barbar"]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::String(StringType::Unknown {
+                    strings_seen: vec!["barbar".to_owned()],
+                    chars_seen: vec!['b', 'a', 'r', 'b', 'a', 'r'],
+                    min_length: Some(6),
+                    max_length: Some(6),
+                }))
+            }
+        )
+    }
+
+    #[test]
+    fn infers_array_number() {
+        let input = json!([100, 104]);
+        let options = no_enum_options();
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Number(NumberType::Integer {
+                    min: 100,
+                    max: 104
+                }))
+            }
+        )
     }
 
     #[test]
-    fn infers_string_iso_date_time_rfc_2822() {
-        let input = json!("Thu, 18 Mar 2021 10:37:31 +0000");
+    fn infers_array_number_float() {
+        let input = json!([100, 104.5]);
         let options = no_enum_options();
         let schema = infer_schema(input, &options);
 
-        assert_eq!(schema, SchemaState::String(StringType::DateTimeRFC2822))
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Number(NumberType::Float {
+                    min: 100.0,
+                    max: 104.5,
+                    non_finite: Default::default(),
+                }))
+            }
+        );
     }
 
     #[test]
-    fn infers_string_iso_date_time_rfc_3339_offset() {
-        let input = json!("2013-01-12T00:00:00.000+00:00");
+    fn infers_array_boolean() {
+        let input = json!([true, false]);
         let options = no_enum_options();
         let schema = infer_schema(input, &options);
 
-        assert_eq!(schema, SchemaState::String(StringType::DateTimeISO8601))
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Boolean)
+            }
+        );
     }
 
     #[test]
-    fn infers_string_iso_date_time_rfc_3339_utc() {
-        let input = json!("2013-01-12T00:00:00.000Z");
+    fn infers_array_object() {
+        let input = json!([
+            {
+                "foo": "bar",
+                "baz": 10,
+                "qux": true
+            },
+            {
+                "baz": null,
+                "qux": false
+            },
+            {
+                "foo": "barbar",
+                "baz": 20,
+                "qux": true
+            },
+        ]);
         let options = no_enum_options();
         let schema = infer_schema(input, &options);
 
-        assert_eq!(schema, SchemaState::String(StringType::DateTimeISO8601))
+        assert_eq!(
+            schema,
+            SchemaState::Array {
+                min_length: 3,
+                max_length: 3,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Object {
+                    required: IndexMap::from_iter([
+                        (
+                            "baz".to_owned(),
+                            SchemaState::Nullable(Box::new(SchemaState::Number(
+                                NumberType::Integer { min: 10, max: 20 }
+                            )))
+                        ),
+                        ("qux".to_owned(), SchemaState::Boolean),
+                    ]),
+                    optional: IndexMap::from_iter([(
+                        "foo".to_owned(),
+                        SchemaState::String(StringType::Unknown {
+                            strings_seen: vec!["bar".to_owned(), "barbar".to_owned()],
+                            chars_seen: vec!['b', 'a', 'r', 'b', 'a', 'r', 'b', 'a', 'r'],
+                            min_length: Some(3),
+                            max_length: Some(6)
+                        })
+                    )]),
+                    access: Default::default(),
+                    annotations: Default::default(),
+                })
+            }
+        )
     }
 
     #[test]
-    fn infers_string_uuid() {
-        let input = json!("988c2c6d-df1b-4bb9-b837-6ba706c0b4ad");
-        let options = no_enum_options();
+    fn tracks_array_length_distribution() {
+        let input = json!([{"tags": []}, {"tags": ["a"]}, {"tags": []}, {"tags": ["a", "b", "c"]}]);
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: true,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
         let schema = infer_schema(input, &options);
 
-        assert_eq!(schema, SchemaState::String(StringType::UUID))
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("Expected array");
+        };
+        let SchemaState::Object { required, .. } = *schema else {
+            panic!("Expected object");
+        };
+        match &required["tags"] {
+            SchemaState::Array {
+                min_length,
+                max_length,
+                lengths,
+                ..
+            } => {
+                assert_eq!((*min_length, *max_length), (0, 3));
+                assert_eq!(lengths, &Some(BTreeMap::from([(0, 2), (1, 1), (3, 1)])));
+            }
+            other => panic!("Expected array, got {:?}", other),
+        }
     }
 
     #[test]
-    fn infers_string_email() {
-        let input = json!("test@example.com");
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_extended_json_types() {
+        let values = vec![
+            json!({"_id": {"$oid": "507f1f77bcf86cd799439011"}, "n": {"$numberLong": "5"}}),
+            json!({"_id": {"$oid": "507f1f77bcf86cd799439012"}, "n": {"$numberLong": "-7"}}),
+        ];
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: true,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
 
-        assert_eq!(schema, SchemaState::String(StringType::Email))
+        let schema = infer_schema_from_iter(values, &options);
+
+        assert_eq!(
+            schema,
+            SchemaState::Object {
+                required: IndexMap::from_iter([
+                    (
+                        "_id".to_string(),
+                        SchemaState::ExtendedJson(ExtendedJsonType::ObjectId)
+                    ),
+                    (
+                        "n".to_string(),
+                        SchemaState::ExtendedJson(ExtendedJsonType::Long { min: -7, max: 5 })
+                    ),
+                ]),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }
+        );
+    }
+
+    fn coordinate_options(coordinates: bool) -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
     }
 
     #[test]
-    fn infers_string_url() {
-        let input = json!("https://somedomain.somehost.nl/somepage");
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_coordinates() {
+        let values = vec![
+            json!([4.89, 52.37]),
+            json!([-0.12, 51]),
+            json!([2.35, 48.86]),
+        ];
 
-        assert_eq!(schema, SchemaState::String(StringType::Url))
+        assert_eq!(
+            infer_schema_from_iter(values.clone(), &coordinate_options(true)),
+            SchemaState::Coordinates(vec![
+                NumberType::Float {
+                    min: -0.12,
+                    max: 4.89,
+                    non_finite: Default::default(),
+                },
+                NumberType::Float {
+                    min: 48.86,
+                    max: 52.37,
+                    non_finite: Default::default(),
+                },
+            ])
+        );
+        assert!(matches!(
+            infer_schema_from_iter(values, &coordinate_options(false)),
+            SchemaState::Array { .. }
+        ));
     }
 
     #[test]
-    fn infers_string_hostname() {
-        let input = json!("somehost.com");
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn coordinates_of_other_lengths_merge_into_arrays() {
+        let values = [json!([1, 2]), json!([1, 2, 3]), json!(["a", "b"])];
+        let options = coordinate_options(true);
 
-        assert_eq!(schema, SchemaState::String(StringType::Hostname))
+        assert_eq!(
+            infer_schema_from_iter(values[..2].to_vec(), &options),
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 3,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 3 })),
+            }
+        );
+        assert!(matches!(
+            infer_schema_from_iter(vec![values[0].clone(), values[2].clone()], &options),
+            SchemaState::Array { .. }
+        ));
     }
 
     #[test]
-    fn infers_number() {
-        let input = json!(42);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_tuples() {
+        let values = vec![json!(["Ada", 36, true]), json!(["Bo", 41, null])];
+        let options = InferenceOptions::builder().tuples(true).build();
 
         assert_eq!(
-            schema,
-            SchemaState::Number(NumberType::Integer { min: 42, max: 42 })
-        )
+            infer_schema_from_iter(values.clone(), &options),
+            SchemaState::Tuple(vec![
+                SchemaState::String(StringType::Unknown {
+                    strings_seen: vec!["Ada".to_string(), "Bo".to_string()],
+                    chars_seen: vec!['A', 'd', 'a', 'B', 'o'],
+                    min_length: Some(2),
+                    max_length: Some(3),
+                }),
+                SchemaState::Number(NumberType::Integer { min: 36, max: 41 }),
+                SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+            ])
+        );
+        assert!(matches!(
+            infer_schema_from_iter(values, &InferenceOptions::default()),
+            SchemaState::Array { .. }
+        ));
+        // arrays of a single type are arrays, not tuples
+        assert!(matches!(
+            infer_schema(json!([1, 2, 3]), &options),
+            SchemaState::Array { .. }
+        ));
     }
 
     #[test]
-    fn infers_number_float() {
-        let input = json!(42.0);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn tuples_of_other_lengths_merge_into_arrays() {
+        let options = InferenceOptions::builder().tuples(true).build();
 
-        assert_eq!(
-            schema,
-            SchemaState::Number(NumberType::Float {
-                min: 42.0,
-                max: 42.0
-            })
-        )
+        assert!(matches!(
+            infer_schema_from_iter(vec![json!(["a", 1]), json!(["b", 2, true])], &options),
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 3,
+                ..
+            }
+        ));
     }
 
     #[test]
-    fn infers_boolean_true() {
-        let input = json!(true);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_geo_points_with_any_spelling_of_their_keys() {
+        let values = vec![
+            json!({"Latitude": 52.37, "lng": 4.89}),
+            json!({"Latitude": 51, "lng": -0.12}),
+        ];
 
-        assert_eq!(schema, SchemaState::Boolean)
+        assert_eq!(
+            infer_schema_from_iter(values.clone(), &coordinate_options(true)),
+            SchemaState::GeoPoint {
+                latitude_key: "Latitude".to_string(),
+                longitude_key: "lng".to_string(),
+                latitude: NumberType::Float {
+                    min: 51.0,
+                    max: 52.37,
+                    non_finite: Default::default(),
+                },
+                longitude: NumberType::Float {
+                    min: -0.12,
+                    max: 4.89,
+                    non_finite: Default::default(),
+                },
+            }
+        );
+        assert!(matches!(
+            infer_schema_from_iter(values, &coordinate_options(false)),
+            SchemaState::Object { .. }
+        ));
+
+        // out of range, or with other fields, they are objects
+        for value in [
+            json!({"lat": 91, "lon": 0}),
+            json!({"lat": 0, "lon": -181}),
+            json!({"lat": 0, "lon": 0, "alt": 10}),
+            json!({"lat": "52.37", "lon": "4.89"}),
+        ] {
+            assert!(
+                matches!(
+                    infer_schema(value.clone(), &coordinate_options(true)),
+                    SchemaState::Object { .. }
+                ),
+                "{}",
+                value
+            );
+        }
     }
 
     #[test]
-    fn infers_boolean_false() {
-        let input = json!(false);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn geo_points_merge_with_other_objects_as_objects() {
+        let values = vec![
+            json!({"lat": 52.37, "lon": 4.89}),
+            json!({"lat": 51.5, "lon": -0.12, "name": "London"}),
+        ];
+        let schema = infer_schema_from_iter(values, &coordinate_options(true));
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            panic!("Expected an object, got {:?}", schema);
+        };
+        assert_eq!(required.len(), 2);
+        assert_eq!(optional.keys().collect::<Vec<_>>(), vec!["name"]);
 
-        assert_eq!(schema, SchemaState::Boolean)
+        // points with differently spelled keys are objects with all of those fields
+        let values = vec![json!({"lat": 1, "lon": 2}), json!({"lat": 3, "lng": 4})];
+        let schema = infer_schema_from_iter(values, &coordinate_options(true));
+        assert!(
+            matches!(&schema, SchemaState::Object { required, optional, .. } if required.len() == 1 && optional.len() == 2),
+            "{:?}",
+            schema
+        );
     }
 
     #[test]
-    fn infers_object() {
-        let input = json!({
-            "string": "foo",
-            "int": 10,
-            "float": 10.4,
-            "bool": false,
-            "array": ["baz"],
-            "null": null,
-            "object": {
-                "string": "foo"
+    fn infers_money_in_minor_units_or_decimal() {
+        let options = InferenceOptions {
+            money: true,
+            ..no_enum_options()
+        };
+        let values = vec![
+            json!({"amount_cents": 1299, "Currency": "USD"}),
+            json!({"amount_cents": 250, "Currency": "EUR"}),
+        ];
+        assert_eq!(
+            infer_schema_from_iter(values.clone(), &options),
+            SchemaState::Money {
+                amount_key: "amount_cents".to_string(),
+                currency_key: "Currency".to_string(),
+                amount: NumberType::Integer {
+                    min: 250,
+                    max: 1299
+                },
+                currencies: ["EUR", "USD"].into_iter().map(String::from).collect(),
             }
-        });
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+        );
+        assert!(matches!(
+            infer_schema_from_iter(values, &no_enum_options()),
+            SchemaState::Object { .. }
+        ));
 
-        assert_eq!(
-            schema,
-            SchemaState::Object {
-                required: std::collections::HashMap::from_iter([
-                    (
-                        "string".to_string(),
-                        SchemaState::String(StringType::Unknown {
-                            strings_seen: vec!["foo".to_owned()],
-                            chars_seen: vec!['f', 'o', 'o'],
-                            min_length: Some(3),
-                            max_length: Some(3)
-                        })
-                    ),
-                    (
-                        "int".to_string(),
-                        SchemaState::Number(NumberType::Integer { min: 10, max: 10 })
-                    ),
-                    (
-                        "float".to_string(),
-                        SchemaState::Number(NumberType::Float {
-                            min: 10.4,
-                            max: 10.4
-                        })
-                    ),
-                    ("bool".to_string(), SchemaState::Boolean),
-                    (
-                        "array".to_string(),
-                        SchemaState::Array {
-                            min_length: 1,
-                            max_length: 1,
-                            schema: Box::new(SchemaState::String(StringType::Unknown {
-                                strings_seen: vec!["baz".to_owned()],
-                                chars_seen: vec!['b', 'a', 'z'],
-                                min_length: Some(3),
-                                max_length: Some(3)
-                            }))
-                        }
-                    ),
-                    ("null".to_string(), SchemaState::Null),
-                    (
-                        "object".to_string(),
-                        SchemaState::Object {
-                            required: std::collections::HashMap::from_iter([(
-                                "string".to_owned(),
-                                SchemaState::String(StringType::Unknown {
-                                    strings_seen: vec!["foo".to_owned()],
-                                    chars_seen: vec!['f', 'o', 'o'],
-                                    min_length: Some(3),
-                                    max_length: Some(3)
-                                })
-                            )]),
-                            optional: std::collections::HashMap::new(),
-                        }
-                    ),
-                ]),
-                optional: std::collections::HashMap::new()
+        let values = vec![
+            json!({"value": 12.99, "currencyCode": "GBP"}),
+            json!({"currencyCode": "GBP", "value": 3}),
+        ];
+        assert!(matches!(
+            infer_schema_from_iter(values, &options),
+            SchemaState::Money {
+                amount: NumberType::Float { .. },
+                ..
             }
-        )
+        ));
+
+        // other currencies, amounts that aren't numbers, or other fields make them objects
+        for value in [
+            json!({"amount": 10, "currency": "XYZ"}),
+            json!({"amount": 10, "currency": "usd"}),
+            json!({"amount": "10.00", "currency": "USD"}),
+            json!({"amount": 10, "currency": "USD", "note": "tip"}),
+        ] {
+            assert!(
+                matches!(
+                    infer_schema(value.clone(), &options),
+                    SchemaState::Object { .. }
+                ),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn money_merges_with_other_objects_as_objects() {
+        let options = InferenceOptions {
+            money: true,
+            ..no_enum_options()
+        };
+        let values = vec![
+            json!({"amount": 1299, "currency": "USD"}),
+            json!({"amount": 500, "currency": "FAKE"}),
+        ];
+        let schema = infer_schema_from_iter(values, &options);
+        let SchemaState::Object { required, .. } = schema else {
+            panic!("Expected an object, got {:?}", schema);
+        };
+        assert!(matches!(
+            required["amount"],
+            SchemaState::Number(NumberType::Integer {
+                min: 500,
+                max: 1299
+            })
+        ));
+        assert!(matches!(required["currency"], SchemaState::String(_)));
     }
 
     #[test]
-    fn infers_array_null() {
-        let input = json!([null, null]);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_custom_date_formats_that_fit_every_value() {
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec!["%m/%d/%Y".to_string(), "%d/%m/%Y".to_string()],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let infer = |values: &[&str]| {
+            infer_schema_from_iter(values.iter().map(|v| json!(v)).collect(), &options)
+        };
+        let custom = |formats: &[&str]| {
+            SchemaState::String(StringType::CustomDateTime {
+                formats: formats.iter().map(|f| f.to_string()).collect(),
+            })
+        };
 
+        // ambiguous values prefer the format supplied first
         assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 2,
-                max_length: 2,
-                schema: Box::new(SchemaState::Null)
-            }
+            infer(&["01/02/2024", "03/04/2024"]),
+            custom(&["%m/%d/%Y", "%d/%m/%Y"])
         );
+        assert_eq!(infer(&["01/02/2024", "25/12/2023"]), custom(&["%d/%m/%Y"]));
+        assert!(matches!(
+            infer(&["12/25/2023", "25/12/2023"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer(&["01/02/2024", "soon"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
     }
 
     #[test]
-    fn infers_array_string() {
-        let input = json!(["foo", "barbar"]);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_financial_identifiers_only_if_all_checksums_pass() {
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: true,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let infer = |values: &[&str]| {
+            infer_schema_from_iter(values.iter().map(|v| json!(v)).collect(), &options)
+        };
 
         assert_eq!(
-            schema,
+            infer(&["4111111111111111", "5500005555555559"]),
+            SchemaState::String(StringType::CreditCard)
+        );
+        assert_eq!(
+            infer(&["NL91ABNA0417164300", "GB29NWBK60161331926819"]),
+            SchemaState::String(StringType::Iban)
+        );
+        assert!(matches!(
+            infer(&["4111111111111111", "4111111111111112"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer_schema(json!("4111111111111111"), &coordinate_options(false)),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn infers_barcodes_only_if_all_check_digits_pass() {
+        let options = InferenceOptions {
+            barcodes: true,
+            ..no_enum_options()
+        };
+        let infer = |values: &[&str]| {
+            infer_schema_from_iter(values.iter().map(|v| json!(v)).collect(), &options)
+        };
+
+        assert_eq!(
+            infer(&["0306406152", "080442957X"]),
+            SchemaState::String(StringType::Isbn10)
+        );
+        assert_eq!(
+            infer(&["9780306406157", "9791034304455"]),
+            SchemaState::String(StringType::Isbn13)
+        );
+        assert_eq!(
+            infer(&["9780306406157", "4006381333931"]),
+            SchemaState::String(StringType::Ean13)
+        );
+        // the right length, but one fails the checksum
+        assert!(matches!(
+            infer(&["4006381333931", "4006381333932"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer(&["0306406152", "9780306406157"]),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        // the same with a minimum number of values, once all of them have been seen
+        let options = InferenceOptions {
+            barcodes: true,
+            money: false,
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 2 }),
+            ..no_enum_options()
+        };
+        assert_eq!(
+            infer_schema(json!(["4006381333931", "9780306406157"]), &options),
             SchemaState::Array {
                 min_length: 2,
                 max_length: 2,
-                schema: Box::new(SchemaState::String(StringType::Unknown {
-                    strings_seen: vec!["foo".to_owned(), "barbar".to_owned()],
-                    chars_seen: vec!['f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r'],
-                    min_length: Some(3),
-                    max_length: Some(6)
-                }))
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::String(StringType::Ean13)),
             }
         );
+        assert!(matches!(
+            infer_schema(json!("4006381333931"), &no_enum_options()),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
     }
 
     #[test]
-    fn infers_array_string_enum() {
-        let input = json!(["foo", "barbar", "barbar", "foo"]);
-
-        let enum_opts = EnumInference {
-            max_unique_ratio: 0.5,
-            min_sample_size: 2,
+    fn infers_durations_only_if_enabled_and_all_values_are_durations() {
+        let options = |durations| InferenceOptions {
+            durations,
+            ..no_enum_options()
         };
-        let options = InferenceOptions {
-            enum_inference: Some(enum_opts),
+        let infer = |values: &[&str], durations| {
+            infer_schema_from_iter(
+                values.iter().map(|v| json!(v)).collect(),
+                &options(durations),
+            )
         };
 
-        let schema = infer_schema(input, &options);
-
         assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 4,
-                max_length: 4,
-                schema: Box::new(SchemaState::String(StringType::Enum {
-                    variants: vec!["foo".to_owned(), "barbar".to_owned()]
-                        .into_iter()
-                        .collect()
-                }))
-            }
+            infer(&["PT1H30M", "P3DT12H", "P2W"], true),
+            SchemaState::String(StringType::Duration)
         );
+        assert!(matches!(
+            infer(&["PT1H30M", "PT1H30"], true),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer(&["PT1H30M", "P3DT12H"], false),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
     }
 
     #[test]
-    fn infers_array_string_enum_uniq_ratio_too_high() {
-        let input = json!(["foo", "barbar", "foo", "barbar"]);
-
-        let enum_opts = EnumInference {
-            max_unique_ratio: 0.4, // 2 unique values out of 4 = unique ratio of 0.5
-            min_sample_size: 2,
+    fn infers_numeric_strings_only_if_enabled_and_all_values_are_numbers() {
+        let options = |numeric_strings| InferenceOptions {
+            numeric_strings,
+            ..no_enum_options()
         };
-        let options = InferenceOptions {
-            enum_inference: Some(enum_opts),
+        let infer = |values: &[&str], numeric_strings| {
+            infer_schema_from_iter(
+                values.iter().map(|v| json!(v)).collect(),
+                &options(numeric_strings),
+            )
         };
 
-        let schema = infer_schema(input, &options);
+        assert_eq!(
+            infer(&["42", "-7", "0"], true),
+            SchemaState::String(StringType::Numeric {
+                number: NumberType::Integer { min: -7, max: 42 }
+            })
+        );
+        assert_eq!(
+            infer(&["2.5", "42", "1e3"], true),
+            SchemaState::String(StringType::Numeric {
+                number: NumberType::Float {
+                    min: 2.5,
+                    max: 1000.0,
+                    non_finite: Default::default(),
+                }
+            })
+        );
+        // leading zeros would be lost, so these stay strings
+        assert_eq!(
+            infer(&["007", "042"], true),
+            SchemaState::String(StringType::FixedWidthNumeric { width: 3 })
+        );
+        assert!(matches!(
+            infer(&["007", "12"], true),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer(&["42", "forty-two"], true),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+        assert!(matches!(
+            infer(&["42", "3.14"], false),
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn infers_the_same_schema_from_borrowed_values() {
+        let input = json!([{"id": 1, "tags": ["a"], "name": null}, {"id": 2, "tags": []}]);
+        let options = no_enum_options();
+
+        assert_eq!(
+            infer_schema_ref(&input, &options),
+            infer_schema(input.clone(), &options)
+        );
+    }
+
+    fn union_options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: true,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    fn event_log() -> Vec<serde_json::Value> {
+        vec![
+            json!({"type": "click", "x": 1, "y": 2}),
+            json!({"type": "key", "key": "a"}),
+            json!({"type": "click", "x": 5, "y": 3}),
+        ]
+    }
+
+    fn object(fields: Vec<(&str, SchemaState)>) -> SchemaState {
+        SchemaState::Object {
+            required: fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            optional: IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        }
+    }
+
+    fn unknown_string(strings: Vec<&str>) -> SchemaState {
+        SchemaState::String(StringType::Unknown {
+            strings_seen: strings.iter().map(|s| s.to_string()).collect(),
+            chars_seen: strings.iter().flat_map(|s| s.chars()).collect(),
+            min_length: strings.iter().map(|s| s.len()).min(),
+            max_length: strings.iter().map(|s| s.len()).max(),
+        })
+    }
+
+    #[test]
+    fn infers_union_of_object_shapes() {
+        let schema = infer_schema(json!(event_log()), &union_options());
 
         assert_eq!(
             schema,
             SchemaState::Array {
-                min_length: 4,
-                max_length: 4,
-                schema: Box::new(SchemaState::String(StringType::Unknown {
-                    strings_seen: vec![
-                        "foo".to_owned(),
-                        "barbar".to_owned(),
-                        "foo".to_owned(),
-                        "barbar".to_owned()
-                    ],
-                    chars_seen: vec![
-                        'f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r', 'f', 'o', 'o', 'b', 'a', 'r',
-                        'b', 'a', 'r'
-                    ],
-                    min_length: Some(3),
-                    max_length: Some(6)
-                }))
+                min_length: 3,
+                max_length: 3,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Union(vec![
+                    object(vec![
+                        ("type", unknown_string(vec!["click", "click"])),
+                        (
+                            "x",
+                            SchemaState::Number(NumberType::Integer { min: 1, max: 5 })
+                        ),
+                        (
+                            "y",
+                            SchemaState::Number(NumberType::Integer { min: 2, max: 3 })
+                        ),
+                    ]),
+                    object(vec![
+                        ("type", unknown_string(vec!["key"])),
+                        ("key", unknown_string(vec!["a"])),
+                    ]),
+                ]))
             }
         );
     }
 
     #[test]
-    fn infers_array_string_enum_sample_size_too_small() {
-        let input = json!(["foo", "barbar", "foo", "barbar"]);
+    fn infers_union_of_object_shapes_from_iter() {
+        let schema = infer_schema_from_iter(event_log(), &union_options());
 
-        let enum_opts = EnumInference {
-            max_unique_ratio: 0.5,
-            min_sample_size: 5, // sample size too small (4 vs 5)
+        match schema {
+            SchemaState::Union(variants) => assert_eq!(variants.len(), 2),
+            other => panic!("Expected union, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn merges_shapes_whose_fields_overlap_by_the_threshold() {
+        // the first two users share three of their four fields, and the order shares none
+        let input = json!([
+            {"id": 1, "name": "a", "email": "a@example.com"},
+            {"id": 2, "name": "b", "email": "b@example.com", "phone": "555"},
+            {"order": 3, "total": 9.5},
+        ]);
+        let variants = |threshold| {
+            let options = InferenceOptions {
+                merge_threshold: Some(threshold),
+                ..union_options()
+            };
+            match infer_schema(input.clone(), &options) {
+                SchemaState::Array { schema, .. } => match *schema {
+                    SchemaState::Union(variants) => variants.len(),
+                    SchemaState::Object { .. } => 1,
+                    other => panic!("Expected union or object, got {:?}", other),
+                },
+                other => panic!("Expected array, got {:?}", other),
+            }
         };
+
+        assert_eq!(variants(0.75), 2);
+        assert_eq!(variants(0.8), 3);
+        // at the extremes, every shape is merged or only the same shapes are
+        assert_eq!(variants(0.0), 1);
+        assert_eq!(variants(1.0), 3);
+
         let options = InferenceOptions {
-            enum_inference: Some(enum_opts),
+            merge_threshold: Some(0.75),
+            ..union_options()
+        };
+        let SchemaState::Array { schema, .. } = infer_schema(input, &options) else {
+            panic!("Expected array");
         };
+        let SchemaState::Union(variants) = *schema else {
+            panic!("Expected union");
+        };
+        assert!(variants.iter().any(|variant| matches!(
+            variant,
+            SchemaState::Object { required, optional, .. }
+                if required.len() == 3 && optional.contains_key("phone")
+        )));
+    }
 
-        let schema = infer_schema(input, &options);
+    #[test]
+    fn collapses_union_of_a_single_shape() {
+        let input = json!([{"a": 1}, {"a": 2}]);
+
+        let schema = infer_schema(input, &union_options());
 
         assert_eq!(
             schema,
             SchemaState::Array {
-                min_length: 4,
-                max_length: 4,
-                schema: Box::new(SchemaState::String(StringType::Unknown {
-                    strings_seen: vec![
-                        "foo".to_owned(),
-                        "barbar".to_owned(),
-                        "foo".to_owned(),
-                        "barbar".to_owned()
-                    ],
-                    chars_seen: vec![
-                        'f', 'o', 'o', 'b', 'a', 'r', 'b', 'a', 'r', 'f', 'o', 'o', 'b', 'a', 'r',
-                        'b', 'a', 'r'
-                    ],
-                    min_length: Some(3),
-                    max_length: Some(6)
-                }))
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(object(vec![(
+                    "a",
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 2 })
+                )]))
             }
         );
     }
 
+    fn discriminator_options(discriminator: Discriminator) -> InferenceOptions {
+        InferenceOptions {
+            discriminator: Some(discriminator),
+            ..no_enum_options()
+        }
+    }
+
     #[test]
-    fn infers_array_string_mixed() {
-        let input = json!(["48f41410-2d97-4d54-8bfa-aa4e22acca01", "barbar"]);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn infers_tagged_union_of_a_named_discriminator() {
+        let mut values = event_log();
+        // shapes are told apart by the tag even where their fields are the same
+        values.push(json!({"type": "scroll", "x": 0, "y": 100}));
+        let schema = infer_schema_from_iter(
+            values,
+            &discriminator_options(Discriminator::Field("type".to_string())),
+        );
 
+        let SchemaState::TaggedUnion { tag, variants } = schema else {
+            panic!("Expected a tagged union, got {:?}", schema);
+        };
+        assert_eq!(tag, "type");
         assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 2,
-                max_length: 2,
-                schema: Box::new(SchemaState::String(StringType::Unknown {
-                    strings_seen: vec!["barbar".to_owned()],
-                    chars_seen: vec!['b', 'a', 'r', 'b', 'a', 'r'],
-                    min_length: Some(6),
-                    max_length: Some(6),
-                }))
-            }
-        )
+            variants.keys().collect::<Vec<_>>(),
+            vec!["click", "key", "scroll"]
+        );
+        assert_eq!(
+            variants["click"],
+            object(vec![
+                ("type", unknown_string(vec!["click", "click"])),
+                (
+                    "x",
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 5 })
+                ),
+                (
+                    "y",
+                    SchemaState::Number(NumberType::Integer { min: 2, max: 3 })
+                ),
+            ])
+        );
+
+        // objects without the tag are merged with the objects of every variant
+        let mut values = event_log();
+        values.push(json!({"x": 1}));
+        assert!(matches!(
+            infer_schema_from_iter(
+                values,
+                &discriminator_options(Discriminator::Field("type".to_string()))
+            ),
+            SchemaState::Object { .. }
+        ));
     }
 
     #[test]
-    fn infers_array_number() {
-        let input = json!([100, 104]);
-        let options = no_enum_options();
-        let schema = infer_schema(input, &options);
+    fn finds_the_discriminator_of_object_shapes() {
+        let mut values: Vec<_> = event_log()
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut value)| {
+                value["id"] = json!(format!("e{}", i));
+                value
+            })
+            .collect();
+        // shapes with a tag in common are the same variant
+        values.push(json!({"type": "click", "x": 0, "y": 0, "button": "left", "id": "e9"}));
+        let schema = infer_schema_from_iter(values, &discriminator_options(Discriminator::Auto));
 
+        // `id` tells the shapes apart too, but has more values
+        let SchemaState::TaggedUnion { tag, variants } = schema else {
+            panic!("Expected a tagged union, got {:?}", schema);
+        };
+        assert_eq!(tag, "type");
+        assert_eq!(variants.keys().collect::<Vec<_>>(), vec!["click", "key"]);
+        let SchemaState::Object { optional, .. } = &variants["click"] else {
+            panic!("Expected an object, got {:?}", variants["click"]);
+        };
+        assert!(optional.contains_key("button"));
+    }
+
+    #[test]
+    fn objects_without_a_discriminator_are_merged() {
+        let values = vec![json!({"a": 1, "b": true}), json!({"a": 2})];
+        let expected = infer_schema_from_iter(values.clone(), &no_enum_options());
+        assert!(matches!(expected, SchemaState::Object { .. }));
         assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 2,
-                max_length: 2,
-                schema: Box::new(SchemaState::Number(NumberType::Integer {
-                    min: 100,
-                    max: 104
-                }))
-            }
-        )
+            infer_schema_from_iter(values.clone(), &discriminator_options(Discriminator::Auto)),
+            expected
+        );
+
+        // and with `union_objects`, they stay apart
+        let options = InferenceOptions {
+            discriminator: Some(Discriminator::Auto),
+            ..union_options()
+        };
+        assert!(matches!(
+            infer_schema_from_iter(values, &options),
+            SchemaState::Union(variants) if variants.len() == 2
+        ));
     }
 
     #[test]
-    fn infers_array_number_float() {
-        let input = json!([100, 104.5]);
-        let options = no_enum_options();
+    fn infers_map_when_object_exceeds_field_cap() {
+        let input = json!([
+            {"counts": {"2024-01-01": 5, "2024-01-02": 7, "2024-01-03": 1}},
+            {"counts": {"2024-01-01": 2, "2024-01-04": 9}}
+        ]);
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: Some(3),
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
         let schema = infer_schema(input, &options);
 
         assert_eq!(
@@ -869,78 +4738,199 @@ mod tests {
             SchemaState::Array {
                 min_length: 2,
                 max_length: 2,
-                schema: Box::new(SchemaState::Number(NumberType::Float {
-                    min: 100.0,
-                    max: 104.5
-                }))
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Object {
+                    required: IndexMap::from_iter([(
+                        "counts".to_string(),
+                        SchemaState::Map {
+                            min_length: 1,
+                            max_length: 4,
+                            keys: StringType::IsoDate,
+                            schema: Box::new(SchemaState::Number(NumberType::Integer {
+                                min: 1,
+                                max: 9
+                            }))
+                        }
+                    )]),
+                    optional: IndexMap::new(),
+                    access: Default::default(),
+                    annotations: Default::default(),
+                })
             }
         );
     }
 
     #[test]
-    fn infers_array_boolean() {
-        let input = json!([true, false]);
-        let options = no_enum_options();
+    fn keeps_object_within_field_cap() {
+        let input = json!({"a": 1, "b": true});
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: Some(2),
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        let schema = infer_schema(input, &options);
+
+        assert!(matches!(schema, SchemaState::Object { .. }));
+    }
+
+    #[test]
+    fn leaves_objects_and_arrays_beyond_the_depth_limit_opaque() {
+        let input = json!({"id": 1, "a": {"b": {"c": [1]}, "tags": []}});
+        let options = InferenceOptions::builder().max_depth(2).build();
+
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(schema.signature(), "{a:{b:any, tags:any}, id:int}");
+    }
+
+    #[test]
+    fn leaves_objects_with_too_many_keys_opaque() {
+        let input = json!([
+            {"id": 1, "scores": {"a": 1, "b": 2, "c": 3, "d": 4}},
+            {"id": 2, "scores": {"e": 5}, "totals": {"w": 1, "x": 1}},
+            {"id": 3, "totals": {"y": 1, "z": 1}},
+        ]);
+        let options = InferenceOptions::builder().max_object_keys(3).build();
+
         let schema = infer_schema(input, &options);
 
-        assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 2,
-                max_length: 2,
-                schema: Box::new(SchemaState::Boolean)
-            }
-        );
+        // scores has too many keys in one object, and totals in all of them together
+        assert_eq!(schema.signature(), "[{id:int, scores:any?, totals:any?}]");
     }
 
     #[test]
-    fn infers_array_object() {
+    fn infers_objects_with_data_keys_as_maps() {
         let input = json!([
             {
-                "foo": "bar",
-                "baz": 10,
-                "qux": true
-            },
-            {
-                "baz": null,
-                "qux": false
+                "name": "a",
+                "visits": {"2024-01-01": 5, "2024-01-02": 7},
+                "by_id": {"17": {"x": 1}, "42": {"x": 2, "y": true}},
+                "mixed": {"2024-01-01": 5, "2024-01-02": "7"},
             },
             {
-                "foo": "barbar",
-                "baz": 20,
-                "qux": true
+                "name": "b",
+                "visits": {"2024-01-03": 1},
+                "by_id": {"8": {"x": 3}},
+                "mixed": {"2024-01-01": 6, "2024-01-02": "8"},
             },
         ]);
-        let options = no_enum_options();
+        let options = InferenceOptions::builder().maps(true).build();
+
         let schema = infer_schema(input, &options);
 
-        assert_eq!(
-            schema,
-            SchemaState::Array {
-                min_length: 3,
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("expected an array, got {schema:?}");
+        };
+        let SchemaState::Object { required, .. } = *schema else {
+            panic!("expected an object, got {schema:?}");
+        };
+        assert!(matches!(required["name"], SchemaState::String(_)));
+        assert!(matches!(
+            &required["visits"],
+            SchemaState::Map {
+                min_length: 0,
                 max_length: 3,
-                schema: Box::new(SchemaState::Object {
-                    required: std::collections::HashMap::from_iter([
-                        (
-                            "baz".to_owned(),
-                            SchemaState::Nullable(Box::new(SchemaState::Number(
-                                NumberType::Integer { min: 10, max: 20 }
-                            )))
-                        ),
-                        ("qux".to_owned(), SchemaState::Boolean),
-                    ]),
-                    optional: std::collections::HashMap::from_iter([(
-                        "foo".to_owned(),
-                        SchemaState::String(StringType::Unknown {
-                            strings_seen: vec!["bar".to_owned(), "barbar".to_owned()],
-                            chars_seen: vec!['b', 'a', 'r', 'b', 'a', 'r', 'b', 'a', 'r'],
-                            min_length: Some(3),
-                            max_length: Some(6)
-                        })
-                    )])
-                })
+                keys: StringType::IsoDate,
+                schema,
+            } if matches!(**schema, SchemaState::Number(NumberType::Integer { min: 1, max: 7 }))
+        ));
+        match &required["by_id"] {
+            SchemaState::Map { schema, .. } => {
+                assert_eq!(schema.signature(), "{x:int, y:bool?}");
             }
-        )
+            other => panic!("expected a map, got {other:?}"),
+        }
+        // values of conflicting types are more likely fields of a record
+        assert!(matches!(required["mixed"], SchemaState::Object { .. }));
+    }
+
+    #[test]
+    fn infers_objects_with_hundreds_of_fields_of_one_schema_as_maps() {
+        let fields = |n: usize| {
+            (0..n)
+                .map(|i| (format!("sensor_{i}"), json!(i)))
+                .collect::<serde_json::Map<_, _>>()
+        };
+        let options = InferenceOptions::builder().maps(true).build();
+
+        let schema = infer_schema(json!(fields(MIN_MAP_FIELDS)), &options);
+        assert!(
+            matches!(schema, SchemaState::Map { max_length, .. } if max_length == MIN_MAP_FIELDS)
+        );
+
+        // fewer fields are more likely a wide record
+        let schema = infer_schema(json!(fields(MIN_MAP_FIELDS - 1)), &options);
+        assert!(matches!(schema, SchemaState::Object { .. }));
+
+        let mut mixed = fields(MIN_MAP_FIELDS);
+        mixed.insert("sensor_0".to_string(), json!("broken"));
+        let schema = infer_schema(json!(mixed), &options);
+        assert!(matches!(schema, SchemaState::Object { .. }));
+    }
+
+    #[test]
+    fn keeps_values_of_different_types_apart_with_union_types() {
+        let input = json!([
+            {"id": "a1", "score": 1},
+            {"id": "a2", "score": null},
+            {"id": 3, "score": 2.5},
+            {"id": "a4", "score": 4},
+        ]);
+        let options = InferenceOptions::builder().union_types(true).build();
+
+        let schema = infer_schema(input, &options);
+
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("expected an array, got {schema:?}");
+        };
+        let SchemaState::Object { required, .. } = *schema else {
+            panic!("expected an object, got {schema:?}");
+        };
+        match &required["id"] {
+            SchemaState::Mixed(variants) => {
+                let kinds: Vec<_> = variants
+                    .iter()
+                    .map(|(variant, count)| (variant.signature(), *count))
+                    .collect();
+                assert_eq!(kinds, [("string".to_string(), 3), ("int".to_string(), 1)]);
+            }
+            other => panic!("expected a mixed schema, got {other:?}"),
+        }
+        // values of a single type aren't a union, whatever else is null
+        assert!(matches!(
+            &required["score"],
+            SchemaState::Nullable(inner) if matches!(**inner, SchemaState::Number(NumberType::Float { .. }))
+        ));
+
+        let options = InferenceOptions::default();
+        let schema = infer_schema(json!([1, "a"]), &options);
+        assert!(matches!(
+            schema,
+            SchemaState::Array { schema, .. } if *schema == SchemaState::Indefinite
+        ));
     }
 
     #[test]
@@ -961,10 +4951,37 @@ mod tests {
         ]);
         let enum_opts = EnumInference {
             max_unique_ratio: 0.5,
+            max_values: None,
             min_sample_size: 2,
+            include_numeric: false,
+            normalize: false,
+            open_tail_coverage: None,
         };
         let options = InferenceOptions {
             enum_inference: Some(enum_opts),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
         };
         let schema = infer_schema(input, &options);
 
@@ -973,8 +4990,10 @@ mod tests {
             SchemaState::Array {
                 min_length: 4,
                 max_length: 4,
+                lengths: None,
+                unique_items: false,
                 schema: Box::new(SchemaState::Object {
-                    required: std::collections::HashMap::from_iter([(
+                    required: IndexMap::from_iter([(
                         "foo".to_owned(),
                         SchemaState::String(StringType::Enum {
                             variants: vec!["bar".to_owned(), "baz".to_owned()]
@@ -982,12 +5001,126 @@ mod tests {
                                 .collect()
                         })
                     )]),
-                    optional: std::collections::HashMap::new()
+                    optional: IndexMap::new(),
+                    access: Default::default(),
+                    annotations: Default::default(),
                 })
             }
         )
     }
 
+    #[test]
+    fn infers_non_finite_values_as_floats() {
+        let options = InferenceOptions {
+            non_finite: true,
+            ..no_enum_options()
+        };
+        for (token, non_finite) in [
+            ("NaN", NonFiniteNumber::NaN),
+            ("Infinity", NonFiniteNumber::Infinity),
+            ("-Infinity", NonFiniteNumber::NegativeInfinity),
+        ] {
+            let input: serde_json::Value =
+                serde_json::from_str(&crate::quote_non_finite(&format!("[1, 2.5, {}]", token)))
+                    .unwrap();
+            assert_eq!(
+                infer_schema(input, &options),
+                SchemaState::Array {
+                    min_length: 3,
+                    max_length: 3,
+                    lengths: None,
+                    unique_items: true,
+                    schema: Box::new(SchemaState::Number(NumberType::Float {
+                        min: 1.0,
+                        max: 2.5,
+                        non_finite: BTreeSet::from([non_finite]),
+                    })),
+                },
+                "{}",
+                token
+            );
+        }
+
+        let schema = infer_schema(json!(["NaN", "Infinity"]), &options);
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("expected an array, got {:?}", schema);
+        };
+        let SchemaState::Number(NumberType::Float {
+            min, non_finite, ..
+        }) = schema.as_ref()
+        else {
+            panic!("expected a float, got {:?}", schema);
+        };
+        assert!(min.is_nan());
+        assert_eq!(non_finite.len(), 2);
+        assert_eq!(schema.to_string_pretty(), "float (NaN, Infinity)");
+        assert_eq!(
+            crate::parse_json_schema(&schema.to_json_schema()).unwrap(),
+            SchemaState::Number(NumberType::Float {
+                min: f64::NEG_INFINITY,
+                max: f64::INFINITY,
+                non_finite: non_finite.clone(),
+            })
+        );
+
+        // the strings are strings without the option
+        assert!(matches!(
+            infer_schema(json!("NaN"), &no_enum_options()),
+            SchemaState::String(_)
+        ));
+    }
+
+    #[test]
+    fn infers_unique_items_of_set_like_arrays() {
+        let input = json!([
+            {"tags": ["red", "green"], "readings": [3, 3, 4]},
+            {"tags": ["blue"], "readings": [1, 2]},
+            {"tags": [], "readings": [5]}
+        ]);
+        let schema = infer_schema(input, &no_enum_options());
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("expected an array, got {:?}", schema);
+        };
+        let SchemaState::Object { required, .. } = *schema else {
+            panic!("expected an object, got {:?}", schema);
+        };
+        assert!(matches!(
+            required["tags"],
+            SchemaState::Array {
+                unique_items: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            required["readings"],
+            SchemaState::Array {
+                unique_items: false,
+                ..
+            }
+        ));
+        assert_eq!(
+            required["tags"].to_json_schema()["uniqueItems"],
+            json!(true)
+        );
+        assert!(required["readings"]
+            .to_json_schema()
+            .get("uniqueItems")
+            .is_none());
+
+        // arrays of a single element say nothing about whether elements are distinct
+        let schema = infer_schema(json!([["a"], ["b"]]), &no_enum_options());
+        let SchemaState::Array { schema, .. } = schema else {
+            panic!("expected an array, got {:?}", schema);
+        };
+        assert!(matches!(
+            *schema,
+            SchemaState::Array {
+                unique_items: false,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn infers_nested_array() {
         let input = json!([[true, false], [false]]);
@@ -999,9 +5132,13 @@ mod tests {
             SchemaState::Array {
                 min_length: 2,
                 max_length: 2,
+                lengths: None,
+                unique_items: false,
                 schema: Box::new(SchemaState::Array {
                     min_length: 1,
                     max_length: 2,
+                    lengths: None,
+                    unique_items: true,
                     schema: Box::new(SchemaState::Boolean),
                 }),
             }
@@ -1022,6 +5159,8 @@ mod tests {
             SchemaState::Array {
                 min_length: 2,
                 max_length: 2,
+                lengths: None,
+                unique_items: false,
                 schema: Box::new(SchemaState::Nullable(Box::new(SchemaState::String(
                     StringType::Unknown {
                         strings_seen: vec!["foo".to_owned()],
@@ -1059,7 +5198,7 @@ mod tests {
         assert_eq!(
             schema,
             SchemaState::Object {
-                required: std::collections::HashMap::from_iter([
+                required: IndexMap::from_iter([
                     (
                         "baz".to_owned(),
                         SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Integer {
@@ -1069,7 +5208,7 @@ mod tests {
                     ),
                     ("qux".to_owned(), SchemaState::Boolean),
                 ]),
-                optional: std::collections::HashMap::from_iter([(
+                optional: IndexMap::from_iter([(
                     "foo".to_owned(),
                     SchemaState::String(StringType::Unknown {
                         strings_seen: vec!["bar".to_owned(), "barbar".to_owned()],
@@ -1077,8 +5216,462 @@ mod tests {
                         min_length: Some(3),
                         max_length: Some(6)
                     })
-                )])
+                )]),
+                access: Default::default(),
+                annotations: Default::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn incremental_inference_matches_batch() {
+        let input = vec![
+            json!({"foo": "bar", "baz": 10}),
+            json!({"baz": null}),
+            json!({"foo": "barbar", "baz": 20}),
+        ];
+        let options = no_enum_options();
+
+        let mut inferer = SchemaInferer::new(&options);
+        for value in &input {
+            inferer.observe(value);
+        }
+
+        assert_eq!(inferer.finish(), infer_schema_from_iter(input, &options));
+    }
+
+    #[test]
+    fn incremental_inference_merges_inferers() {
+        let options = no_enum_options();
+
+        let mut first = SchemaInferer::new(&options);
+        first.observe(&json!([1, 2]));
+        let mut second = SchemaInferer::new(&options);
+        second.observe(&json!([5]));
+
+        assert_eq!(
+            first.merge(second).finish(),
+            SchemaState::Array {
+                min_length: 1,
+                max_length: 2,
+                lengths: None,
+                unique_items: true,
+                schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 5 }))
+            }
+        );
+    }
+
+    #[test]
+    fn values_of_conflicting_types_stay_of_any_type() {
+        let options = no_enum_options();
+        let schema = |values: Vec<serde_json::Value>| infer_schema_from_iter(values, &options);
+        assert_eq!(
+            schema(vec![json!(1), json!("a"), json!(2)]),
+            SchemaState::Indefinite
+        );
+        assert_eq!(
+            schema(vec![json!(1), json!("a"), json!(null)]),
+            SchemaState::Nullable(Box::new(SchemaState::Indefinite))
+        );
+    }
+
+    #[test]
+    fn observing_in_parallel_matches_observing_in_turn() {
+        let input: Vec<_> = (0..3_000)
+            .map(|i| {
+                let mut record = json!({
+                    "id": match i % 3 {
+                        0 => json!(i),
+                        1 => json!(i as f64 / 3.0),
+                        _ => json!(null),
+                    },
+                    "tags": (0..i % 4).map(|t| format!("tag{}", t)).collect::<Vec<_>>(),
+                    "location": if i % 11 == 0 { json!([1.5, 2, 3]) } else { json!([i, 52.1]) },
+                    "price": if i % 13 == 0 { json!(null) } else { json!({"amount": i, "currency": "EUR"}) },
+                    // a string now and then among numbers is of any type
+                    "mixed": if i % 997 == 0 { json!("x") } else { json!(i) },
+                    "event": if i % 2 == 0 {
+                        json!({"type": "click", "x": i})
+                    } else {
+                        json!({"type": "scroll", "by": [i, i + 1]})
+                    },
+                });
+                if i % 7 == 0 {
+                    record["note"] = json!(format!("note {}", i));
+                }
+                if i == 2_345 {
+                    record = json!(null);
+                }
+                record
+            })
+            .collect();
+        let options = |union_objects| InferenceOptions {
+            union_objects,
+            discriminator: Some(Discriminator::Field("type".to_string())),
+            length_distribution: true,
+            coordinates: true,
+            tuples: false,
+            money: true,
+            ..no_enum_options()
+        };
+
+        for options in [options(false), options(true)] {
+            let mut in_turn = SchemaInferer::new(&options);
+            input.iter().for_each(|value| in_turn.observe(value));
+            let expected = in_turn.finish();
+
+            for batch_size in [1, 999, 1024, 3_000] {
+                let mut inferer = SchemaInferer::new(&options);
+                input
+                    .chunks(batch_size)
+                    .for_each(|batch| inferer.observe_all(batch));
+                assert_eq!(inferer.finish(), expected, "batches of {}", batch_size);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_inference_without_values_is_initial() {
+        let options = no_enum_options();
+        assert_eq!(SchemaInferer::new(&options).finish(), SchemaState::Initial);
+    }
+
+    #[test]
+    fn incremental_inference_matches_batch_on_large_input() {
+        let statuses = ["active", "inactive", "pending"];
+        let input: Vec<_> = (0..10_000)
+            .map(|i| {
+                let mut record = json!({
+                    "id": i + 1,
+                    "status": statuses[i % statuses.len()],
+                    "created": format!("2024-01-{:02}T10:00:00Z", i % 28 + 1),
+                    "tags": (0..i % 4).map(|t| format!("tag{}", t)).collect::<Vec<_>>(),
+                });
+                if i % 3 == 0 {
+                    record["score"] = json!(i as f64 / 7.0);
+                }
+                if i % 5 == 0 {
+                    record["score"] = json!(null);
+                }
+                record
+            })
+            .collect();
+        let options = InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.1,
+                max_values: None,
+                min_sample_size: 1,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 1 }),
+            length_distribution: true,
+            sequences: true,
+            vocabularies: vec![],
+            ..no_enum_options()
+        };
+
+        let mut inferer = SchemaInferer::new(&options);
+        let mut partial = None;
+        for (i, value) in input.iter().enumerate() {
+            inferer.observe(value);
+            if i == 100 {
+                partial = Some(inferer.snapshot());
+            }
+        }
+        let streamed = inferer.finish();
+        let batch = infer_schema_from_iter(input, &options);
+
+        // the described schema is derived from the inferred schema alone
+        assert_eq!(streamed, batch);
+        assert_ne!(partial, Some(batch));
+    }
+
+    fn vocabulary(name: &str, values: &[&str]) -> Vocabulary {
+        Vocabulary {
+            name: name.to_string(),
+            values: values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn infers_enum_of_full_vocabulary_from_subset() {
+        let options = InferenceOptions {
+            vocabularies: vec![
+                vocabulary("methods", &["GET", "POST", "PUT", "DELETE"]),
+                vocabulary("more-methods", &["GET", "POST", "PATCH"]),
+            ],
+            ..no_enum_options()
+        };
+        let input = json!([{"method": "GET", "path": "/a"}, {"method": "POST", "path": "/b"}]);
+
+        assert_eq!(
+            infer_schema(input, &options),
+            SchemaState::Array {
+                min_length: 2,
+                max_length: 2,
+                lengths: None,
+                unique_items: false,
+                schema: Box::new(SchemaState::Object {
+                    required: IndexMap::from_iter([
+                        (
+                            "method".to_string(),
+                            SchemaState::String(StringType::Enum {
+                                variants: ["DELETE", "GET", "POST", "PUT"]
+                                    .iter()
+                                    .map(|v| v.to_string())
+                                    .collect()
+                            })
+                        ),
+                        (
+                            "path".to_string(),
+                            SchemaState::String(StringType::Unknown {
+                                strings_seen: vec!["/a".to_string(), "/b".to_string()],
+                                chars_seen: vec!['/', 'a', '/', 'b'],
+                                min_length: Some(2),
+                                max_length: Some(2)
+                            })
+                        ),
+                    ]),
+                    optional: IndexMap::new(),
+                    access: Default::default(),
+                    annotations: Default::default(),
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_vocabulary_with_values_outside_it() {
+        let options = InferenceOptions {
+            vocabularies: vec![vocabulary("methods", &["GET", "POST"])],
+            ..no_enum_options()
+        };
+        let schema = infer_schema_from_iter(vec![json!("GET"), json!("FETCH")], &options);
+        assert!(matches!(
+            schema,
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn snapshot_leaves_inferer_usable() {
+        let options = no_enum_options();
+        let mut inferer = SchemaInferer::new(&options);
+        inferer.observe(&json!(1));
+        assert_eq!(
+            inferer.snapshot(),
+            SchemaState::Number(NumberType::Integer { min: 1, max: 1 })
+        );
+        inferer.observe(&json!(3));
+        assert_eq!(
+            inferer.finish(),
+            SchemaState::Number(NumberType::Integer { min: 1, max: 3 })
+        );
+    }
+
+    fn open_tail_options(coverage: f64) -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.05,
+                max_values: None,
+                min_sample_size: 1,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: Some(coverage),
+            }),
+            ..no_enum_options()
+        }
+    }
+
+    /// 95 values out of three countries, and five one-off values.
+    fn countries_with_tail() -> Vec<serde_json::Value> {
+        let countries = ["NL", "DE", "BE"];
+        (0..100)
+            .map(|i| match i {
+                0..95 => json!(countries[i % countries.len()]),
+                _ => json!(format!("other-{}", i)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn infers_enum_with_open_tail() {
+        let schema = infer_schema_from_iter(countries_with_tail(), &open_tail_options(0.9));
+        let SchemaState::String(StringType::OpenEnum {
+            variants,
+            other_ratio,
+            other,
+        }) = schema
+        else {
+            panic!("Expected an open enum, got {:?}", schema);
+        };
+        assert_eq!(
+            variants,
+            ["BE", "DE", "NL"].iter().map(|v| v.to_string()).collect()
+        );
+        assert_eq!(other_ratio, 0.05);
+        assert!(matches!(
+            *other,
+            StringType::Unknown {
+                min_length: Some(8),
+                max_length: Some(8),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn open_tail_needs_enough_coverage() {
+        let schema = infer_schema_from_iter(countries_with_tail(), &open_tail_options(0.99));
+        assert!(matches!(
+            schema,
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+
+        // without open tails, a tail of one-off values rules out an enum altogether
+        let mut options = open_tail_options(0.9);
+        if let Some(enum_inference) = &mut options.enum_inference {
+            enum_inference.open_tail_coverage = None;
+        }
+        let schema = infer_schema_from_iter(countries_with_tail(), &options);
+        assert!(matches!(
+            schema,
+            SchemaState::String(StringType::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn prefers_closed_enum_over_open_tail() {
+        let values = (0..100).map(|i| json!(["a", "b", "c"][i % 3])).collect();
+        assert_eq!(
+            infer_schema_from_iter(values, &open_tail_options(0.5)),
+            SchemaState::String(StringType::Enum {
+                variants: ["a", "b", "c"].iter().map(|v| v.to_string()).collect()
+            })
+        );
+    }
+
+    #[test]
+    fn infers_enum_across_values_from_iter() {
+        let input = vec![
+            json!({"status": "active"}),
+            json!({"status": "inactive"}),
+            json!({"status": "active"}),
+            json!({"status": "active"}),
+        ];
+        let options = InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.5,
+                max_values: None,
+                min_sample_size: 2,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+
+        assert_eq!(
+            infer_schema_from_iter(input, &options),
+            SchemaState::Object {
+                required: IndexMap::from_iter([(
+                    "status".to_owned(),
+                    SchemaState::String(StringType::Enum {
+                        variants: vec!["active".to_owned(), "inactive".to_owned()]
+                            .into_iter()
+                            .collect()
+                    })
+                )]),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
             }
         );
     }
+
+    #[test]
+    fn patterned_strings_survive_json_schema_round_trip() {
+        let records: Vec<_> = (0..20)
+            .map(|i| {
+                let code = ["AB", "CDE", "FGHI"][i % 3];
+                json!({
+                    "zip": format!("{:05}", 1000 + i * 7),
+                    "code": code,
+                    "hash": format!("{:x}", 4096 + i * 77),
+                    "count": (i * 3).to_string(),
+                    "sent": "Mon, 25 Dec 2023 10:30:00 +0000",
+                })
+            })
+            .collect();
+        let options = InferenceOptions {
+            format_inference: Some(FormatInferenceOptions { min_sample_size: 3 }),
+            numeric_strings: true,
+            ..no_enum_options()
+        };
+        let schema = infer_schema(json!(records), &options);
+        let SchemaState::Array { schema: record, .. } = &schema else {
+            panic!("expected an array, got {:?}", schema);
+        };
+
+        let parsed = crate::parse_json_schema(&schema.to_json_schema_document()).unwrap();
+        let SchemaState::Array {
+            schema: parsed_record,
+            ..
+        } = &parsed
+        else {
+            panic!("expected an array, got {:?}", parsed);
+        };
+        assert_eq!(parsed_record, record);
+
+        let produced = crate::try_produce_with(&parsed, 50, &Default::default()).unwrap();
+        for record in produced.as_array().unwrap() {
+            let zip = record["zip"].as_str().unwrap();
+            assert!(
+                zip.len() == 5 && zip.bytes().all(|b| b.is_ascii_digit()),
+                "{}",
+                zip
+            );
+            let code = record["code"].as_str().unwrap();
+            assert!((2..=4).contains(&code.len()), "{}", code);
+            assert!(code.bytes().all(|b| b.is_ascii_uppercase()), "{}", code);
+            let hash = record["hash"].as_str().unwrap();
+            assert!(
+                hash.len() == 4 && hash.bytes().all(|b| b.is_ascii_hexdigit()),
+                "{}",
+                hash
+            );
+            let count: i64 = record["count"].as_str().unwrap().parse().unwrap();
+            assert!((0..=57).contains(&count), "{}", count);
+            let sent = record["sent"].as_str().unwrap();
+            assert!(
+                chrono::DateTime::parse_from_rfc2822(sent).is_ok(),
+                "{}",
+                sent
+            );
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::StringType;
+use crate::{CharacterClass, NumberType, StringType};
 
 lazy_static! {
     static ref ISO_DATE_REGEX: regex::Regex = regex::Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
@@ -7,6 +7,8 @@ lazy_static! {
             .unwrap();
     static ref HOSTNAME_REGEX: regex::Regex =
         regex::Regex::new(r"^[a-zA-Z0-9\-]+\.[a-zA-Z]{2,}$").unwrap();
+    static ref NUMBER_REGEX: regex::Regex =
+        regex::Regex::new(r"^-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?$").unwrap();
     static ref EMAIL_REGEX: regex::Regex =
         regex::Regex::new(r"[a-zA-Z0-9]+@[a-zA-Z0-9]+\.[a-zA-Z]{2,}$").unwrap();
 }
@@ -27,6 +29,16 @@ fn email(s: &str) -> Option<StringType> {
     }
 }
 
+fn ip(s: &str) -> Option<StringType> {
+    if s.parse::<std::net::Ipv4Addr>().is_ok() {
+        Some(StringType::Ipv4)
+    } else if s.contains(':') && s.parse::<std::net::Ipv6Addr>().is_ok() {
+        Some(StringType::Ipv6)
+    } else {
+        None
+    }
+}
+
 fn url_host(s: &str) -> Option<StringType> {
     if s.contains('.') {
         if url::Url::parse(s).is_ok() {
@@ -56,17 +68,223 @@ fn dates(s: &str) -> Option<StringType> {
     None
 }
 
+fn fixed_width_numeric(s: &str) -> Option<StringType> {
+    if s.len() > 1 && s.starts_with('0') && s.bytes().all(|b| b.is_ascii_digit()) {
+        Some(StringType::FixedWidthNumeric { width: s.len() })
+    } else {
+        None
+    }
+}
+
 pub(crate) fn infer_string_type(s: &str) -> StringType {
-    for matcher in [uuid, email, url_host, dates] {
+    for matcher in [uuid, email, ip, url_host, dates, fixed_width_numeric] {
         if let Some(string_type) = matcher(s) {
             return string_type;
         }
     }
 
+    unknown_string_type(s)
+}
+
+/// The most strings of no particular format that are kept for enums and formats to be inferred
+/// from. Once a field has this many, the strings of later values are left out, as a field of so
+/// many values is hardly an enum of its first ones.
+pub(crate) const MAX_STRINGS_SEEN: usize = 10_000;
+
+/// The most characters of strings of no particular format that are kept for strings like them
+/// to be produced from.
+pub(crate) const MAX_CHARS_SEEN: usize = 100_000;
+
+/// The type of a string of no particular format, which keeps the string itself for later
+/// inference (of enums, etc.).
+pub(crate) fn unknown_string_type(s: &str) -> StringType {
     StringType::Unknown {
         strings_seen: vec![s.to_owned()],
-        chars_seen: s.chars().collect(),
+        chars_seen: s.chars().take(MAX_CHARS_SEEN).collect(),
         min_length: Some(s.len()),
         max_length: Some(s.len()),
     }
 }
+
+/// The numeric string type of strings that are all JSON numbers, such as `"42"` and `"3.14"`,
+/// with the range of the numbers. Strings with leading zeros, such as `"007"`, are not numbers,
+/// since the zeros would be dropped. As with numbers, integers too large for an `i64` make the
+/// numbers floats.
+pub(crate) fn infer_numeric_string(strings: &[String]) -> Option<StringType> {
+    if strings.is_empty() {
+        return None;
+    }
+    let mut integers = Some(NumberType::Integer {
+        min: i64::MAX,
+        max: i64::MIN,
+    });
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for s in strings {
+        if !NUMBER_REGEX.is_match(s) {
+            return None;
+        }
+        let n: f64 = s.parse().ok()?;
+        (min, max) = (min.min(n), max.max(n));
+        integers = match (integers, s.parse::<i64>()) {
+            (Some(NumberType::Integer { min, max }), Ok(n)) => Some(NumberType::Integer {
+                min: min.min(n),
+                max: max.max(n),
+            }),
+            _ => None,
+        };
+    }
+    let number = integers.unwrap_or(NumberType::Float {
+        min,
+        max,
+        non_finite: Default::default(),
+    });
+    Some(StringType::Numeric { number })
+}
+
+/// The class of characters that all of the strings are made of, trying the narrowest first. For
+/// strings of digits and the letters `a` to `f` to be hexadecimal, at least one of them must
+/// have a digit, or words such as "bead" would be too.
+pub(crate) fn infer_character_class(strings: &[String]) -> Option<CharacterClass> {
+    let all_in = |class: CharacterClass| {
+        strings
+            .iter()
+            .all(|s| !s.is_empty() && s.chars().all(|c| class.contains(c)))
+    };
+    let has_digit = || {
+        strings
+            .iter()
+            .any(|s| s.bytes().any(|b| b.is_ascii_digit()))
+    };
+    if strings.is_empty() {
+        None
+    } else if all_in(CharacterClass::Digits) {
+        Some(CharacterClass::Digits)
+    } else if all_in(CharacterClass::Hex) && has_digit() {
+        Some(CharacterClass::Hex)
+    } else if all_in(CharacterClass::Lowercase) {
+        Some(CharacterClass::Lowercase)
+    } else if all_in(CharacterClass::Uppercase) {
+        Some(CharacterClass::Uppercase)
+    } else {
+        None
+    }
+}
+
+/// A part of a string, for [`infer_structure`]: a run of characters of one class, or another
+/// character on its own, such as a separator.
+enum Segment<'a> {
+    Run(CharacterClass, &'a str),
+    Other(char),
+}
+
+impl Segment<'_> {
+    /// Whether the segments are at the same place in strings of the same structure, which for
+    /// other characters means that they are the same character.
+    fn is_like(&self, other: &Segment) -> bool {
+        match (self, other) {
+            (Segment::Run(class, _), Segment::Run(other_class, _)) => class == other_class,
+            (Segment::Other(c), Segment::Other(other_c)) => c == other_c,
+            _ => false,
+        }
+    }
+}
+
+/// The segments of a string, or `None` if it has whitespace, as text does rather than ids.
+fn segments(s: &str) -> Option<Vec<Segment<'_>>> {
+    let class_of = |c: char| {
+        [
+            CharacterClass::Digits,
+            CharacterClass::Uppercase,
+            CharacterClass::Lowercase,
+        ]
+        .into_iter()
+        .find(|class| class.contains(c))
+    };
+    let mut segments = vec![];
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            return None;
+        }
+        let Some(class) = class_of(c) else {
+            segments.push(Segment::Other(c));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        let end = rest
+            .find(|c| class_of(c) != Some(class))
+            .unwrap_or(rest.len());
+        segments.push(Segment::Run(class, &rest[..end]));
+        rest = &rest[end..];
+    }
+    Some(segments)
+}
+
+/// A regular expression of the structure that all of the strings share, such as
+/// `^ORD-[0-9]{4}-[0-9]{5}$` for `"ORD-2024-00173"` and `"ORD-2025-01940"`: the same runs of
+/// digits, uppercase and lowercase letters, with the lengths seen, between the same other
+/// characters. Runs of letters that are the same in every string, such as a prefix, are kept
+/// as they are. Only strings with digits and something that is the same in all of them, such
+/// as a separator, have a structure, so that words and names don't.
+pub(crate) fn infer_structure(strings: &[String]) -> Option<String> {
+    let segmented = strings
+        .iter()
+        .map(|s| segments(s))
+        .collect::<Option<Vec<_>>>()?;
+    let first = segmented.first()?;
+    if segmented.iter().any(|segments| {
+        segments.len() != first.len()
+            || segments
+                .iter()
+                .zip(first)
+                .any(|(segment, first)| !segment.is_like(first))
+    }) {
+        return None;
+    }
+
+    let mut pattern = "^".to_string();
+    let (mut has_digits, mut has_constant) = (false, false);
+    for (i, segment) in first.iter().enumerate() {
+        let runs: Vec<&str> = segmented
+            .iter()
+            .filter_map(|segments| match segments[i] {
+                Segment::Run(_, run) => Some(run),
+                Segment::Other(_) => None,
+            })
+            .collect();
+        match *segment {
+            Segment::Other(c) => {
+                has_constant = true;
+                // only what is special in JSON Schema's (ECMA-262) patterns too is escaped
+                if r"\^$.|?*+()[]{}".contains(c) {
+                    pattern.push('\\');
+                }
+                pattern.push(c);
+            }
+            Segment::Run(class, run)
+                if class != CharacterClass::Digits && runs.iter().all(|r| *r == run) =>
+            {
+                has_constant = true;
+                pattern.push_str(run);
+            }
+            Segment::Run(class, _) => {
+                has_digits |= class == CharacterClass::Digits;
+                let set = match class {
+                    CharacterClass::Uppercase => "[A-Z]",
+                    CharacterClass::Lowercase => "[a-z]",
+                    _ => "[0-9]",
+                };
+                let min = runs.iter().map(|r| r.len()).min().unwrap_or_default();
+                let max = runs.iter().map(|r| r.len()).max().unwrap_or_default();
+                pattern.push_str(set);
+                if min == max && min > 1 {
+                    pattern.push_str(&format!("{{{}}}", min));
+                } else if min != max {
+                    pattern.push_str(&format!("{{{},{}}}", min, max));
+                }
+            }
+        }
+    }
+    pattern.push('$');
+    (has_digits && has_constant).then_some(pattern)
+}
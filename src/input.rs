@@ -0,0 +1,1208 @@
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{BufRead, Read};
+
+use crate::diagnostics::warn;
+use crate::duplicate_keys::{find_duplicate_keys, DuplicateKey};
+use crate::{
+    parse_delimited, parse_jsonc, parse_toml, parse_xml, parse_yaml_documents, quote_non_finite,
+    quote_numbers, DrivelError, DuplicateKeys, InferenceOptions, InputFormat, SchemaInferer,
+    SchemaState, XmlOptions,
+};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// The text encodings that input can be decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputEncoding {
+    #[value(name = "utf-8")]
+    Utf8,
+    #[value(name = "utf-16le")]
+    Utf16Le,
+    #[value(name = "utf-16be")]
+    Utf16Be,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    InvalidUtf8(String),
+    InvalidUtf16(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidUtf8(msg) => write!(f, "Input is not valid UTF-8: {}", msg),
+            DecodeError::InvalidUtf16(msg) => write!(f, "Input is not valid UTF-16: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn detect_encoding(bytes: &[u8]) -> InputEncoding {
+    if bytes.starts_with(UTF16LE_BOM) {
+        InputEncoding::Utf16Le
+    } else if bytes.starts_with(UTF16BE_BOM) {
+        InputEncoding::Utf16Be
+    } else {
+        InputEncoding::Utf8
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String, DecodeError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(DecodeError::InvalidUtf16(
+            "input has an odd number of bytes".to_string(),
+        ));
+    }
+
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|e| DecodeError::InvalidUtf16(e.to_string()))
+}
+
+/// Decodes raw input bytes into a string.
+///
+/// If no encoding is given, the encoding is detected from the byte order mark (BOM), falling
+/// back to UTF-8 if there is none. A leading BOM matching the encoding is always stripped, so
+/// that it does not end up in front of the parsed document.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{decode_input, InputEncoding};
+///
+/// assert_eq!(decode_input(b"\xEF\xBB\xBF{}", None).unwrap(), "{}");
+/// assert_eq!(decode_input(b"\xFF\xFE{\0}\0", None).unwrap(), "{}");
+/// assert_eq!(decode_input(b"\0{\0}", Some(InputEncoding::Utf16Be)).unwrap(), "{}");
+/// ```
+pub fn decode_input(bytes: &[u8], encoding: Option<InputEncoding>) -> Result<String, DecodeError> {
+    let encoding = encoding.unwrap_or_else(|| detect_encoding(bytes));
+
+    match encoding {
+        InputEncoding::Utf8 => {
+            let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+            std::str::from_utf8(bytes)
+                .map(|s| s.to_owned())
+                .map_err(|e| DecodeError::InvalidUtf8(e.to_string()))
+        }
+        InputEncoding::Utf16Le => decode_utf16(
+            bytes.strip_prefix(UTF16LE_BOM).unwrap_or(bytes),
+            u16::from_le_bytes,
+        ),
+        InputEncoding::Utf16Be => decode_utf16(
+            bytes.strip_prefix(UTF16BE_BOM).unwrap_or(bytes),
+            u16::from_be_bytes,
+        ),
+    }
+}
+
+/// Options for reading JSON, YAML or line-based input with [`read_values`] and
+/// [`parse_values`], and input of other formats with [`InputFormat::parse`].
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// The encoding of the input, detected from its byte order mark if `None`.
+    pub encoding: Option<InputEncoding>,
+    /// Whether to accept the `NaN`, `Infinity` and `-Infinity` that some encoders write in
+    /// place of numbers, which are read as strings with [`quote_non_finite`].
+    pub allow_non_finite: bool,
+    /// Whether to read numbers as strings of the digits that were written, with
+    /// [`quote_numbers`], so that none are rounded to a float, such as IDs too large for a 64-bit
+    /// integer. Numbers of YAML outside of flow collections (`[...]` and `{...}`) are still read
+    /// as numbers.
+    pub numbers_as_strings: bool,
+    /// What to do with objects of JSON input that have the same key more than once, of which
+    /// only the last value is read.
+    pub on_duplicate_keys: DuplicateKeys,
+    /// Whether to skip lines of line-based input that can't be parsed, instead of failing.
+    pub skip_errors: bool,
+    /// Fail anyway if more than this many lines are skipped with `skip_errors`.
+    pub max_errors: Option<usize>,
+    /// Stop reading line-based input once this many values have been read.
+    pub max_values: Option<usize>,
+    /// How XML input is read, with [`InputFormat::parse`].
+    pub xml: XmlOptions,
+}
+
+/// The number of lines of line-based input that were read, and of those that were skipped
+/// because they couldn't be parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineCounts {
+    pub lines: usize,
+    pub skipped: usize,
+    /// Why the first of the skipped lines couldn't be parsed, along with its line number.
+    pub first_error: Option<String>,
+}
+
+/// The values parsed from an input by [`parse_values`] or [`InputFormat::parse`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedInput {
+    /// A single document, of JSON, YAML, XML or TOML.
+    Document(Value),
+    /// The values of line-based input, such as JSON lines, or of a stream of YAML documents,
+    /// which are each a record.
+    Records {
+        values: Vec<Value>,
+        /// The lines read, which are all zero for a stream of YAML documents.
+        counts: LineCounts,
+    },
+}
+
+impl ParsedInput {
+    /// The values parsed, where a single document is one value.
+    pub fn into_values(self) -> Vec<Value> {
+        match self {
+            ParsedInput::Document(value) => vec![value],
+            ParsedInput::Records { values, .. } => values,
+        }
+    }
+}
+
+/// Parses a document as strict JSON, then as JSON with comments and trailing commas, then as
+/// YAML, so that lenient JSON doesn't fall through to the YAML parser.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+///
+/// assert_eq!(drivel::parse_document("{\"a\": 1, /* b */}").unwrap(), json!({"a": 1}));
+/// assert_eq!(drivel::parse_document("a: 1").unwrap(), json!({"a": 1}));
+/// assert!(drivel::parse_document("a: 1\n---\nb: 2").is_err());
+/// ```
+pub fn parse_document(s: &str) -> Result<Value, DrivelError> {
+    let mut documents = parse_documents(s)?;
+    match documents.len() {
+        1 => Ok(documents.remove(0)),
+        n => Err(DrivelError::Parse(format!(
+            "expected a single YAML document, found {}",
+            n
+        ))),
+    }
+}
+
+/// Parses one JSON document like [`parse_document`], or any number of YAML documents separated
+/// by `---`.
+pub fn parse_documents(s: &str) -> Result<Vec<Value>, DrivelError> {
+    match serde_json::from_str(s).or_else(|json_err| parse_jsonc(s).map_err(|_| json_err)) {
+        Ok(v) => Ok(vec![v]),
+        Err(json_err) => parse_yaml_documents(s).map_err(|yaml_err| {
+            DrivelError::Parse(format!(
+                "JSON error: {}. YAML error: {}",
+                json_err, yaml_err
+            ))
+        }),
+    }
+}
+
+/// Whether input looks like JSON lines: more than one line, the first of which is a JSON
+/// document by itself.
+fn is_json_lines(input: &str) -> bool {
+    let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+    match (lines.next(), lines.next()) {
+        (Some(first), Some(_)) => serde_json::from_str::<Value>(first).is_ok(),
+        _ => false,
+    }
+}
+
+/// Parses input as JSON values written back to back, with or without whitespace between them,
+/// such as `{"a": 1}{"a": 2}`, as some producers write them without newlines. Returns `None`
+/// unless all of the input is made of at least two values.
+fn parse_json_stream(input: &str) -> Option<Vec<Value>> {
+    let values = serde_json::Deserializer::from_str(input)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    (values.len() > 1).then_some(values)
+}
+
+/// Warns about, or fails on, the duplicate keys of the JSON values of `input`, as the options
+/// ask, describing where each of them is with `locate`.
+fn check_duplicate_keys(
+    input: &str,
+    options: &ReadOptions,
+    locate: impl Fn(&DuplicateKey) -> String,
+) -> Result<(), DrivelError> {
+    if options.on_duplicate_keys == DuplicateKeys::KeepLast {
+        return Ok(());
+    }
+    for duplicate in find_duplicate_keys(input) {
+        match options.on_duplicate_keys {
+            DuplicateKeys::Error => return Err(DrivelError::Parse(locate(&duplicate))),
+            _ => warn(format!("{}; keeping the last value", locate(&duplicate))),
+        }
+    }
+    Ok(())
+}
+
+/// Makes a line of inline YAML parse the way it was meant to, as YAML parsers are stricter
+/// about it than those who write it by hand:
+///
+/// * a plain key directly followed by its value, such as `{a:1}`, which is the key `a:1`
+///   without a value in YAML, gets a space after its colon, unless it already has a `: ` later
+///   on, as in `{ns:key: 1}`
+/// * tabs after colons are replaced by spaces
+///
+/// Quoted strings and values, such as `http://example.com` or `12:30`, are left as they are.
+fn normalize_inline_yaml(line: &str) -> Cow<'_, str> {
+    if !line.contains(':') {
+        return Cow::Borrowed(line);
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len() + 8);
+    // whether each enclosing flow collection is a mapping, innermost last
+    let mut mappings: Vec<bool> = vec![];
+    let mut at_key = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                // copy the quoted string as is, where `"` escapes with `\` and `'` by doubling
+                let start = i;
+                i += 1;
+                while i < chars.len() {
+                    if c == '"' && chars[i] == '\\' {
+                        i += 1;
+                    } else if chars[i] == c {
+                        if c == '\'' && chars.get(i + 1) == Some(&'\'') {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+                at_key = false;
+                continue;
+            }
+            '{' | '[' => {
+                mappings.push(c == '{');
+                at_key = c == '{';
+            }
+            '}' | ']' => {
+                mappings.pop();
+                at_key = false;
+            }
+            ',' => at_key = mappings.last() == Some(&true),
+            ':' if chars.get(i + 1) == Some(&'\t') => {
+                out.push_str(": ");
+                i += 2;
+                at_key = false;
+                continue;
+            }
+            _ if c.is_whitespace() => {}
+            _ if at_key => {
+                // a plain key extends to the end of its entry
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| matches!(c, ',' | '{' | '}' | '[' | ']' | '"' | '\''))
+                    .map_or(chars.len(), |end| i + end);
+                let entry = chars[i..end]
+                    .iter()
+                    .collect::<String>()
+                    .replace(":\t", ": ");
+                let separated = entry.contains(": ");
+                match entry.find(':').filter(|_| !separated) {
+                    // a key directly followed by a quoted string or a collection, as in `{a:[1]}`
+                    Some(colon) if colon + 1 == entry.len() => {
+                        out.push_str(&entry);
+                        if end < chars.len() && chars[end] != ',' && chars[end] != '}' {
+                            out.push(' ');
+                        }
+                    }
+                    Some(colon) => {
+                        out.push_str(&entry[..=colon]);
+                        out.push(' ');
+                        out.push_str(&entry[colon + 1..]);
+                    }
+                    None => out.push_str(&entry),
+                }
+                i = end;
+                at_key = false;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+    Cow::Owned(out)
+}
+
+/// Parses a line of line-based input as a JSON document or, failing that, an inline YAML one,
+/// such as `{level: warn, tags: [a, b]}`. If neither works, the error is that of the parser
+/// that got further into the line, or JSON if they got as far, which is most likely the one it
+/// was meant for.
+fn parse_line(line: &str) -> Result<Value, String> {
+    let json_err = match serde_json::from_str(line) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+    if let Ok(value) = parse_jsonc(line) {
+        return Ok(value);
+    }
+    match parse_yaml_documents(&normalize_inline_yaml(line)) {
+        Ok(mut documents) if documents.len() == 1 => Ok(documents.remove(0)),
+        Ok(documents) if documents.is_empty() => Err("the line is empty".to_string()),
+        Ok(documents) => Err(format!(
+            "expected a single YAML document, found {}",
+            documents.len()
+        )),
+        // the column of a JSON error counts from 1, and the index of a YAML one from 0
+        Err(yaml_err) if json_err.column() > yaml_err.marker().index() => {
+            Err(format!("invalid JSON: {}", json_err))
+        }
+        Err(yaml_err) => Err(format!("invalid YAML: {}", yaml_err)),
+    }
+}
+
+/// Parses each line as a JSON or YAML document, whose non-finite numbers have already been
+/// quoted if need be.
+fn parse_quoted_lines<L: AsRef<str>>(
+    lines: impl Iterator<Item = L>,
+    options: &ReadOptions,
+    mut observe: impl FnMut(Value) -> bool,
+) -> Result<LineCounts, DrivelError> {
+    let mut counts = LineCounts::default();
+    let mut observed = 0;
+    for (idx, line) in lines.enumerate() {
+        if options.max_values.is_some_and(|max| observed >= max) {
+            break;
+        }
+        counts.lines += 1;
+        match parse_line(line.as_ref()) {
+            Ok(value) => {
+                check_duplicate_keys(line.as_ref(), options, |duplicate| {
+                    format!("line {}: {}", idx + 1, duplicate)
+                })?;
+                observed += 1;
+                if !observe(value) {
+                    break;
+                }
+            }
+            Err(err) if options.skip_errors => {
+                counts.skipped += 1;
+                if let Some(max_errors) = options.max_errors.filter(|&max| counts.skipped > max) {
+                    return Err(DrivelError::Parse(format!(
+                        "line {}: {}. Giving up after more than {} malformed lines",
+                        idx + 1,
+                        err,
+                        max_errors
+                    )));
+                }
+                counts
+                    .first_error
+                    .get_or_insert_with(|| format!("line {}: {}", idx + 1, err));
+            }
+            Err(err) => return Err(DrivelError::Parse(format!("line {}: {}", idx + 1, err))),
+        }
+    }
+    Ok(counts)
+}
+
+/// Parses each line of line-based input as a JSON or YAML document, passing the values to
+/// `observe` until it returns false, or until `max_values` have been read. Lines that can't be
+/// parsed fail, unless `skip_errors` is set.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{parse_lines, ReadOptions};
+///
+/// let options = ReadOptions { skip_errors: true, ..Default::default() };
+/// let mut values = vec![];
+/// let counts = parse_lines("{\"a\": 1}\n{oops\n{\"a\": 2}".lines(), &options, |value| {
+///     values.push(value);
+///     true
+/// })
+/// .unwrap();
+///
+/// assert_eq!(values.len(), 2);
+/// assert_eq!((counts.lines, counts.skipped), (3, 1));
+/// assert!(counts.first_error.unwrap().starts_with("line 2: "));
+/// ```
+pub fn parse_lines<L: AsRef<str>>(
+    lines: impl Iterator<Item = L>,
+    options: &ReadOptions,
+    observe: impl FnMut(Value) -> bool,
+) -> Result<LineCounts, DrivelError> {
+    if options.allow_non_finite || options.numbers_as_strings {
+        let lines = lines.map(|line| quote(line.as_ref(), options).into_owned());
+        parse_quoted_lines(lines, options, observe)
+    } else {
+        parse_quoted_lines(lines, options, observe)
+    }
+}
+
+/// Quotes the tokens of input that the options read as strings.
+fn quote<'a>(input: &'a str, options: &ReadOptions) -> Cow<'a, str> {
+    let input = if options.allow_non_finite {
+        quote_non_finite(input)
+    } else {
+        input.into()
+    };
+    if !options.numbers_as_strings {
+        return input;
+    }
+    match quote_numbers(&input) {
+        Cow::Owned(quoted) => Cow::Owned(quoted),
+        Cow::Borrowed(_) => input,
+    }
+}
+
+/// Parses input like [`parse_values`], passing each value of line-based input to `observe` as
+/// it is parsed, such as to report progress through a large input.
+pub fn parse_values_with(
+    input: &str,
+    options: &ReadOptions,
+    mut observe: impl FnMut(&Value),
+) -> Result<ParsedInput, DrivelError> {
+    let input = quote(input, options);
+    let documents = if is_json_lines(&input) {
+        None
+    } else {
+        parse_documents(&input).ok()
+    };
+    match documents {
+        Some(mut documents) if documents.len() == 1 => {
+            check_duplicate_keys(&input, options, |duplicate| duplicate.to_string())?;
+            return Ok(ParsedInput::Document(documents.remove(0)));
+        }
+        Some(documents) if !documents.is_empty() => {
+            return Ok(ParsedInput::Records {
+                values: documents,
+                counts: LineCounts::default(),
+            })
+        }
+        _ => {}
+    }
+
+    if !is_json_lines(&input) {
+        if let Some(mut values) = parse_json_stream(&input) {
+            // records are counted from 1, like lines
+            check_duplicate_keys(&input, options, |duplicate| {
+                format!("record {}: {}", duplicate.index + 1, duplicate)
+            })?;
+            values.truncate(options.max_values.unwrap_or(usize::MAX));
+            values.iter().for_each(&mut observe);
+            return Ok(ParsedInput::Records {
+                values,
+                counts: LineCounts::default(),
+            });
+        }
+    }
+
+    // unable to parse input as single document; try line-based format
+    let mut values = vec![];
+    let counts = parse_quoted_lines(input.lines(), options, |value| {
+        observe(&value);
+        values.push(value);
+        true
+    })?;
+    Ok(ParsedInput::Records { values, counts })
+}
+
+/// Parses input as a single JSON or YAML document, or as a stream of YAML documents that are
+/// each a record, or as JSON values written back to back, or failing that, as one JSON or YAML
+/// document per line, as drivel does with its input.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{parse_values, ParsedInput, ReadOptions};
+/// use serde_json::json;
+///
+/// let options = ReadOptions::default();
+/// assert_eq!(
+///     parse_values("[1, 2]", &options).unwrap(),
+///     ParsedInput::Document(json!([1, 2]))
+/// );
+/// assert_eq!(
+///     parse_values("{\"a\": 1}\n{\"a\": 2}\n", &options).unwrap().into_values(),
+///     vec![json!({"a": 1}), json!({"a": 2})]
+/// );
+/// ```
+pub fn parse_values(input: &str, options: &ReadOptions) -> Result<ParsedInput, DrivelError> {
+    parse_values_with(input, options, |_| {})
+}
+
+impl InputFormat {
+    /// Parses input of this format: JSON, YAML or line-based input as by [`parse_values`], or
+    /// failing that, a TOML document, such as a `Cargo.toml`, as by [`parse_toml`]; the rows of
+    /// delimited values as records, as by [`parse_delimited`]; an XML document, as by
+    /// [`parse_xml`]; a TOML document; or the rows of a binary format, such as Parquet, as
+    /// records, where the input is its bytes, as with [`InputFormat::parse_bytes`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{InputFormat, ParsedInput, ReadOptions};
+    /// use serde_json::json;
+    ///
+    /// let options = ReadOptions::default();
+    /// let manifest = "[package]\nname = \"drivel\"\n\n[dependencies]\nrand = \"0.8\"\n";
+    /// assert_eq!(
+    ///     InputFormat::Json.parse(manifest, &options).unwrap(),
+    ///     ParsedInput::Document(json!({
+    ///         "package": {"name": "drivel"},
+    ///         "dependencies": {"rand": "0.8"}
+    ///     }))
+    /// );
+    /// assert_eq!(
+    ///     InputFormat::Csv.parse("id\n1\n2\n", &options).unwrap().into_values(),
+    ///     vec![json!({"id": 1}), json!({"id": 2})]
+    /// );
+    /// ```
+    pub fn parse(self, input: &str, options: &ReadOptions) -> Result<ParsedInput, DrivelError> {
+        self.parse_with(input, options, |_| {})
+    }
+
+    /// Parses input like [`InputFormat::parse`], passing each value of line-based input to
+    /// `observe` as it is parsed, as with [`parse_values_with`].
+    pub fn parse_with(
+        self,
+        input: &str,
+        options: &ReadOptions,
+        observe: impl FnMut(&Value),
+    ) -> Result<ParsedInput, DrivelError> {
+        match self {
+            InputFormat::Json => match parse_values_with(input, options, observe) {
+                // YAML reads most TOML as one long string, if at all
+                parsed @ (Ok(ParsedInput::Document(Value::String(_)))
+                | Err(DrivelError::Parse(_))) => match parse_toml(input) {
+                    Ok(Value::Object(table)) if !table.is_empty() => {
+                        Ok(ParsedInput::Document(Value::Object(table)))
+                    }
+                    _ => parsed,
+                },
+                parsed => parsed,
+            },
+            InputFormat::Csv | InputFormat::Tsv => {
+                let delimiter = self.delimiter().unwrap_or(',');
+                parse_delimited(input, delimiter).map(|table| ParsedInput::Records {
+                    values: table.records,
+                    counts: LineCounts::default(),
+                })
+            }
+            InputFormat::Xml => parse_xml(input, &options.xml).map(ParsedInput::Document),
+            InputFormat::Toml => parse_toml(input).map(ParsedInput::Document),
+            InputFormat::Parquet | InputFormat::Arrow => {
+                self.parse_bytes(input.as_bytes(), options)
+            }
+        }
+    }
+
+    /// Parses input of this format from its bytes: the rows of a binary format, such as
+    /// Parquet, as records, or otherwise text, decoded as by [`decode_input`] and parsed as by
+    /// [`InputFormat::parse`]. Binary formats are read with the `columnar` feature, and are
+    /// otherwise an error.
+    pub fn parse_bytes(
+        self,
+        input: &[u8],
+        options: &ReadOptions,
+    ) -> Result<ParsedInput, DrivelError> {
+        if !self.is_binary() {
+            return self.parse(&decode_input(input, options.encoding)?, options);
+        }
+        let values = read_columnar(self, input, options.max_values)?;
+        Ok(ParsedInput::Records {
+            values,
+            counts: LineCounts::default(),
+        })
+    }
+}
+
+#[cfg(feature = "columnar")]
+fn read_columnar(
+    format: InputFormat,
+    input: &[u8],
+    max_rows: Option<usize>,
+) -> Result<Vec<Value>, DrivelError> {
+    match format {
+        InputFormat::Arrow => crate::read_arrow(input, max_rows),
+        _ => crate::read_parquet(input, max_rows),
+    }
+}
+
+#[cfg(not(feature = "columnar"))]
+fn read_columnar(
+    format: InputFormat,
+    _input: &[u8],
+    _max_rows: Option<usize>,
+) -> Result<Vec<Value>, DrivelError> {
+    Err(DrivelError::Parse(format!(
+        "drivel was built without the `columnar` feature, which reads {:?} input",
+        format
+    )))
+}
+
+/// Reads, decodes and parses input like [`parse_values`], returning its values: the single
+/// document, or each record of line-based input or of a stream of YAML documents.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{read_values, ReadOptions};
+/// use serde_json::json;
+///
+/// let input: &[u8] = b"- a: 1\n---\n- a: 2\n";
+/// assert_eq!(
+///     read_values(input, &ReadOptions::default()).unwrap(),
+///     vec![json!([{"a": 1}]), json!([{"a": 2}])]
+/// );
+/// ```
+pub fn read_values(
+    mut reader: impl Read,
+    options: &ReadOptions,
+) -> Result<Vec<Value>, DrivelError> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let input = decode_input(&bytes, options.encoding)?;
+    Ok(parse_values(&input, options)?.into_values())
+}
+
+/// Reads the start of some input, up to its second line that isn't blank, to tell whether it
+/// is line-based, such as JSON lines, the way [`parse_values`] would read it. Returns the bytes
+/// read, which are missing from `reader` in front of the rest of the input, and whether the
+/// input is line-based, in which case it can be read one line at a time with [`read_lines`]
+/// instead of all at once.
+///
+/// Input of UTF-16, which is only ever read all at once, is never line-based, and nothing of
+/// it is read.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{sniff_line_based, ReadOptions};
+/// use std::io::BufRead;
+///
+/// let mut reader: &[u8] = b"{\"a\": 1}\n{\"a\": 2}\n{\"a\": 3}\n";
+/// let (start, line_based) = sniff_line_based(&mut reader, &ReadOptions::default()).unwrap();
+/// assert!(line_based);
+/// assert_eq!(start, b"{\"a\": 1}\n{\"a\": 2}\n");
+/// assert_eq!(reader.lines().count(), 1);
+///
+/// let mut reader: &[u8] = b"[\n  1,\n  2\n]\n";
+/// assert!(!sniff_line_based(&mut reader, &ReadOptions::default()).unwrap().1);
+/// ```
+pub fn sniff_line_based(
+    reader: &mut impl BufRead,
+    options: &ReadOptions,
+) -> Result<(Vec<u8>, bool), DrivelError> {
+    let utf16 = match options.encoding {
+        Some(encoding) => encoding != InputEncoding::Utf8,
+        None => detect_encoding(reader.fill_buf()?) != InputEncoding::Utf8,
+    };
+    if utf16 {
+        return Ok((vec![], false));
+    }
+
+    let mut start = vec![];
+    let mut lines = 0;
+    while lines < 2 {
+        let from = start.len();
+        if reader.read_until(b'\n', &mut start)? == 0 {
+            break;
+        }
+        if !start[from..].trim_ascii().is_empty() {
+            lines += 1;
+        }
+    }
+    let line_based = match std::str::from_utf8(&start) {
+        Ok(text) => is_json_lines(&quote(text.trim_start_matches('\u{feff}'), options)),
+        Err(_) => false,
+    };
+    Ok((start, line_based))
+}
+
+/// Reads line-based input one line at a time, parsing each line and passing its value to
+/// `observe` like [`parse_lines`], so that input larger than memory can be read. The input is
+/// UTF-8, of which a byte order mark in front of the first line is dropped.
+pub fn read_lines(
+    reader: impl BufRead,
+    options: &ReadOptions,
+    observe: impl FnMut(Value) -> bool,
+) -> Result<LineCounts, DrivelError> {
+    let mut error = None;
+    let lines = reader
+        .lines()
+        .enumerate()
+        .map_while(|(idx, line)| match line {
+            // like `decode_input`, drop a byte order mark in front of the first line
+            Ok(line) => Some(match line.strip_prefix('\u{feff}') {
+                Some(rest) if idx == 0 => rest.to_string(),
+                _ => line,
+            }),
+            Err(err) => {
+                error = Some(err);
+                None
+            }
+        });
+    let counts = parse_lines(lines, options, observe);
+    match error {
+        Some(err) => Err(err.into()),
+        None => counts,
+    }
+}
+
+/// Infers the schema of the records of line-based input, such as JSON lines, reading and
+/// observing one line at a time, so that only the schema inferred so far is held in memory
+/// rather than all of the input. The schema is the same as that inferred by
+/// [`infer_schema_from_iter`](crate::infer_schema_from_iter) from all of the records.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_schema_from_reader, InferenceOptions, NumberType, ReadOptions, SchemaState};
+///
+/// let opts = InferenceOptions::default();
+/// let input: &[u8] = b"3\n1\n2\n";
+/// let schema = infer_schema_from_reader(input, &ReadOptions::default(), &opts).unwrap();
+/// assert_eq!(schema, SchemaState::Number(NumberType::Integer { min: 1, max: 3 }));
+/// ```
+pub fn infer_schema_from_reader(
+    reader: impl BufRead,
+    read_options: &ReadOptions,
+    options: &InferenceOptions,
+) -> Result<SchemaState, DrivelError> {
+    let mut inferer = SchemaInferer::new(options);
+    read_lines(reader, read_options, |value| {
+        inferer.observe(&value);
+        true
+    })?;
+    Ok(inferer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SAMPLE: &str = "{\"name\": \"Zoë\", \"tags\": [\"a\", \"b\"]}";
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn utf16be(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn decodes_plain_utf8() {
+        assert_eq!(decode_input(SAMPLE.as_bytes(), None).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn strips_utf8_bom() {
+        let input = [UTF8_BOM, SAMPLE.as_bytes()].concat();
+        assert_eq!(decode_input(&input, None).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn detects_utf16le_from_bom() {
+        let input = [UTF16LE_BOM, &utf16le(SAMPLE)].concat();
+        assert_eq!(decode_input(&input, None).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn detects_utf16be_from_bom() {
+        let input = [UTF16BE_BOM, &utf16be(SAMPLE)].concat();
+        assert_eq!(decode_input(&input, None).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn decodes_bomless_utf16_with_explicit_encoding() {
+        assert_eq!(
+            decode_input(&utf16le(SAMPLE), Some(InputEncoding::Utf16Le)).unwrap(),
+            SAMPLE
+        );
+        assert_eq!(
+            decode_input(&utf16be(SAMPLE), Some(InputEncoding::Utf16Be)).unwrap(),
+            SAMPLE
+        );
+    }
+
+    #[test]
+    fn decodes_bom_prefixed_input_as_json() {
+        let input = [UTF16LE_BOM, &utf16le(SAMPLE)].concat();
+        let decoded = decode_input(&input, None).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&decoded).unwrap();
+        assert_eq!(value["name"], "Zoë");
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let result = decode_input(&[0x7B, 0xC3, 0x28, 0x7D], None);
+        assert!(matches!(result, Err(DecodeError::InvalidUtf8(_))));
+    }
+
+    fn options() -> ReadOptions {
+        ReadOptions::default()
+    }
+
+    #[test]
+    fn parses_a_json_document() {
+        assert_eq!(
+            parse_values("{\"a\": [1, 2]}", &options()).unwrap(),
+            ParsedInput::Document(json!({"a": [1, 2]}))
+        );
+        // including one that spans several lines
+        assert_eq!(
+            parse_values("[\n  1,\n  2\n]\n", &options()).unwrap(),
+            ParsedInput::Document(json!([1, 2]))
+        );
+    }
+
+    #[test]
+    fn parses_toml_that_is_neither_json_nor_yaml() {
+        // which YAML reads as a single string
+        assert_eq!(
+            InputFormat::Json
+                .parse("a = 1\nb = \"x\"\n", &options())
+                .unwrap(),
+            ParsedInput::Document(json!({"a": 1, "b": "x"}))
+        );
+        assert_eq!(
+            InputFormat::Json
+                .parse("just some text", &options())
+                .unwrap(),
+            ParsedInput::Document(json!("just some text"))
+        );
+        assert_eq!(
+            InputFormat::Toml.parse("", &options()).unwrap(),
+            ParsedInput::Document(json!({}))
+        );
+        // the errors of JSON and YAML are kept for input that isn't TOML either
+        let err = InputFormat::Json
+            .parse("{\"a\": 1\n", &options())
+            .unwrap_err();
+        assert!(!err.to_string().contains("TOML"));
+    }
+
+    #[test]
+    fn parses_yaml_documents() {
+        assert_eq!(
+            parse_values("a: 1\nb: [x, y]\n", &options()).unwrap(),
+            ParsedInput::Document(json!({"a": 1, "b": ["x", "y"]}))
+        );
+        assert_eq!(
+            parse_values("a: 1\n---\na: 2\n", &options()).unwrap(),
+            ParsedInput::Records {
+                values: vec![json!({"a": 1}), json!({"a": 2})],
+                counts: LineCounts::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_json_lines() {
+        let input = "{\"a\": 1}\n{\"a\": 2}\n";
+        assert_eq!(
+            parse_values(input, &options()).unwrap(),
+            ParsedInput::Records {
+                values: vec![json!({"a": 1}), json!({"a": 2})],
+                counts: LineCounts {
+                    lines: 2,
+                    ..Default::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn parses_tricky_inline_yaml_lines() {
+        let cases = [
+            ("{a: 1, b: two}", json!({"a": 1, "b": "two"})),
+            ("{a:1, b:2}", json!({"a": 1, "b": 2})),
+            ("{a:1,b:two words}", json!({"a": 1, "b": "two words"})),
+            ("{a:\t1}", json!({"a": 1})),
+            ("{a:[1,2], b:{c:d}}", json!({"a": [1, 2], "b": {"c": "d"}})),
+            ("{a:\"x\", b:'it''s'}", json!({"a": "x", "b": "it's"})),
+            ("{\"a\": 1, b:2}", json!({"a": 1, "b": 2})),
+            ("{ns:key: 1}", json!({"ns:key": 1})),
+            ("{'k:x': 1, \"y:z\": 2}", json!({"k:x": 1, "y:z": 2})),
+            (
+                "{url:http://x.com/a, at: 12:30}",
+                json!({"url": "http://x.com/a", "at": "12:30"}),
+            ),
+            (
+                "{msg: \"a:b, c:d\", n:2}",
+                json!({"msg": "a:b, c:d", "n": 2}),
+            ),
+            ("{a:, b: ~}", json!({"a": null, "b": null})),
+            ("{a: 1,} # trailing comma", json!({"a": 1})),
+            ("[{a:1}, {b:2}]", json!([{"a": 1}, {"b": 2}])),
+            ("[1, two, [x:1]]", json!([1, "two", ["x:1"]])),
+            ("{a: &x 1, b: *x}", json!({"a": 1, "b": 1})),
+        ];
+        let lines = cases.iter().map(|(line, _)| *line);
+        let mut values = vec![];
+        parse_lines(lines, &options(), |value| {
+            values.push(value);
+            true
+        })
+        .unwrap();
+        let expected: Vec<_> = cases.into_iter().map(|(_, value)| value).collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn reports_the_error_of_the_parser_that_got_further() {
+        let error = |input: &str| parse_values(input, &options()).unwrap_err().to_string();
+
+        let yaml = error("{\"a\": 1}\n{a: 1, b: 2\n");
+        assert!(yaml.contains("line 2: invalid YAML"), "{}", yaml);
+        let json = error("{\"a\": 1}\n{\"a\": 1, \"b\": [2}\n");
+        assert!(json.contains("line 2: invalid JSON"), "{}", json);
+        let duplicate = error("{\"a\": 1}\n{a: 1, a: 2}\n");
+        assert!(duplicate.contains("duplicated key"), "{}", duplicate);
+    }
+
+    #[test]
+    fn skips_malformed_lines_up_to_a_maximum() {
+        let input = "{\"a\": 1}\n{oops\n{\"a\": 2}\n[nope\n";
+        assert!(matches!(
+            parse_values(input, &options()),
+            Err(DrivelError::Parse(_))
+        ));
+
+        let skipping = ReadOptions {
+            skip_errors: true,
+            ..options()
+        };
+        let ParsedInput::Records { values, counts } = parse_values(input, &skipping).unwrap()
+        else {
+            panic!("Expected records");
+        };
+        assert_eq!(values.len(), 2);
+        assert_eq!((counts.lines, counts.skipped), (4, 2));
+        assert!(
+            counts.first_error.as_ref().unwrap().starts_with("line 2: "),
+            "{:?}",
+            counts.first_error
+        );
+
+        let strict = ReadOptions {
+            max_errors: Some(1),
+            ..skipping
+        };
+        let err = parse_values(input, &strict).unwrap_err();
+        assert!(err.to_string().contains("line 4"), "{}", err);
+    }
+
+    #[test]
+    fn stops_after_the_maximum_number_of_values() {
+        let options = ReadOptions {
+            max_values: Some(2),
+            ..options()
+        };
+        let values = parse_values("1\n2\n3\n", &options).unwrap().into_values();
+        assert_eq!(values, vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn reads_decoded_input_with_non_finite_numbers() {
+        let options = ReadOptions {
+            allow_non_finite: true,
+            ..options()
+        };
+        let input = [UTF16LE_BOM, &utf16le("{\"x\": NaN}\n{\"x\": 1.5}")].concat();
+        assert_eq!(
+            read_values(input.as_slice(), &options).unwrap(),
+            vec![json!({"x": "NaN"}), json!({"x": 1.5})]
+        );
+        assert!(matches!(
+            read_values(&[0xC3, 0x28][..], &options),
+            Err(DrivelError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn reads_numbers_as_strings_without_losing_precision() {
+        let options = ReadOptions {
+            numbers_as_strings: true,
+            allow_non_finite: true,
+            ..options()
+        };
+        let document = "[{\"id\": 123456789012345678901234567890, \"version\": 1.10, \"x\": NaN}]";
+        assert_eq!(
+            parse_values(document, &options).unwrap(),
+            ParsedInput::Document(json!([
+                {"id": "123456789012345678901234567890", "version": "1.10", "x": "NaN"}
+            ]))
+        );
+        let lines = "{\"id\": 18446744073709551617}\n{\"id\": 9007199254740993}";
+        assert_eq!(
+            parse_values(lines, &options).unwrap().into_values(),
+            vec![
+                json!({"id": "18446744073709551617"}),
+                json!({"id": "9007199254740993"})
+            ]
+        );
+        // without the option, they are rounded to the nearest float
+        assert_eq!(
+            parse_values(lines, &ReadOptions::default())
+                .unwrap()
+                .into_values()[1]["id"],
+            json!(9007199254740993_i64)
+        );
+        assert_ne!(
+            parse_values(lines, &ReadOptions::default())
+                .unwrap()
+                .into_values()[0]["id"]
+                .to_string(),
+            "18446744073709551617"
+        );
+    }
+
+    #[test]
+    fn reads_concatenated_json_values() {
+        let input = "{\"id\": 1, \"tags\": [\"a\"]}{\"id\": 2, \"tags\": []} {\"id\": 3}\n[4]";
+        let mut observed = 0;
+        let ParsedInput::Records { values, .. } =
+            parse_values_with(input, &options(), |_| observed += 1).unwrap()
+        else {
+            panic!("Expected records");
+        };
+        assert_eq!(
+            values,
+            vec![
+                json!({"id": 1, "tags": ["a"]}),
+                json!({"id": 2, "tags": []}),
+                json!({"id": 3}),
+                json!([4]),
+            ]
+        );
+        assert_eq!(observed, 4);
+
+        let options = ReadOptions {
+            max_values: Some(2),
+            ..options()
+        };
+        assert_eq!(
+            parse_values("{}{}{}", &options)
+                .unwrap()
+                .into_values()
+                .len(),
+            2
+        );
+        // a value cut short is not a stream of values
+        assert!(parse_values("{\"a\": 1}{\"a\":", &ReadOptions::default()).is_err());
+    }
+
+    #[test]
+    fn sniffs_line_based_input_like_parse_values() {
+        let inputs = [
+            ("{\"a\": 1}\n{\"a\": 2}\n{\"a\": 3}", true),
+            ("\u{feff}[1]\n[2]", true),
+            ("{\"a\": 1}", false),
+            ("{\"a\":\n  1}\n", false),
+            ("- 1\n- 2\n", false),
+            ("", false),
+            // lines of inline YAML are only read as lines once the input isn't a document
+            ("{a: 1}\n{a: 2}", false),
+        ];
+        for (input, expected) in inputs {
+            let mut reader = input.as_bytes();
+            let (start, line_based) = sniff_line_based(&mut reader, &options()).unwrap();
+            assert_eq!(line_based, expected, "{:?}", input);
+            assert_eq!([start.as_slice(), reader].concat(), input.as_bytes());
+            if line_based {
+                // as decoded, without a byte order mark
+                let input = input.trim_start_matches('\u{feff}');
+                assert!(matches!(
+                    parse_values(input, &options()),
+                    Ok(ParsedInput::Records { counts, .. }) if counts.lines > 0
+                ));
+            }
+        }
+
+        // nothing of UTF-16 input is read
+        let input = [UTF16LE_BOM, &utf16le("1\n2\n")].concat();
+        let mut reader = input.as_slice();
+        assert_eq!(
+            sniff_line_based(&mut reader, &options()).unwrap(),
+            (vec![], false)
+        );
+        assert_eq!(reader.len(), input.len());
+
+        let mut lines = vec![];
+        let mut reader = "\u{feff}{\"a\": 1}\n{\"a\": 2}".as_bytes();
+        let counts = read_lines(&mut reader, &options(), |value| {
+            lines.push(value);
+            true
+        })
+        .unwrap();
+        assert_eq!(lines, vec![json!({"a": 1}), json!({"a": 2})]);
+        assert_eq!(counts.lines, 2);
+        assert!(matches!(
+            read_lines(&[0xC3, 0x28][..], &options(), |_| true),
+            Err(DrivelError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn handles_duplicate_keys_as_asked() {
+        let document = "{\"id\": 1, \"tags\": [{\"a\": 1, \"a\": 2}], \"id\": 2}";
+        let lines = "{\"id\": 1}\n{\"id\": 2, \"id\": 3}";
+        let stream = "{\"id\": 1}{\"id\": 2, \"id\": 3}";
+        let options = |on_duplicate_keys| ReadOptions {
+            on_duplicate_keys,
+            ..options()
+        };
+
+        let (values, diagnostics) = crate::capture_diagnostics(|| {
+            parse_values(document, &options(DuplicateKeys::KeepLast)).unwrap()
+        });
+        assert_eq!(
+            values,
+            ParsedInput::Document(json!({"id": 2, "tags": [{"a": 2}]}))
+        );
+        assert!(diagnostics.is_empty());
+
+        let (values, diagnostics) = crate::capture_diagnostics(|| {
+            parse_values(document, &options(DuplicateKeys::Warn)).unwrap()
+        });
+        assert_eq!(values.into_values()[0]["id"], json!(2));
+        let warnings = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                "duplicate key \"a\" at tags[0]; keeping the last value",
+                "duplicate key \"id\"; keeping the last value",
+            ]
+        );
+
+        for (input, location) in [
+            (document, "duplicate key \"a\" at tags[0]"),
+            (lines, "line 2: duplicate key \"id\""),
+            (stream, "record 2: duplicate key \"id\""),
+        ] {
+            let err = parse_values(input, &options(DuplicateKeys::Error)).unwrap_err();
+            assert!(matches!(err, DrivelError::Parse(_)));
+            assert!(err.to_string().contains(location), "{}", err);
+        }
+    }
+
+    #[test]
+    fn rejects_odd_length_utf16() {
+        let result = decode_input(&[0xFF, 0xFE, 0x7B], None);
+        assert!(matches!(result, Err(DecodeError::InvalidUtf16(_))));
+    }
+}
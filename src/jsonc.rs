@@ -0,0 +1,357 @@
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Removes `//` and `/* */` comments from JSON text, replacing them with whitespace so that
+/// positions in error messages still point at the right line, along with commas that are only
+/// followed by the end of an array or object.
+fn strip_comments_and_trailing_commas(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    // the position in `stripped` of a comma that may turn out to be trailing
+    let mut pending_comma: Option<usize> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                pending_comma = None;
+                stripped.push(c);
+                while let Some(c) = chars.next() {
+                    stripped.push(c);
+                    match c {
+                        '\\' => stripped.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        stripped.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    stripped.push(if c == '\n' { '\n' } else { ' ' });
+                    previous = Some(c);
+                }
+            }
+            ',' => {
+                // only a comma after a value can be trailing, so that `[,]` stays invalid
+                let follows_value = stripped
+                    .trim_end()
+                    .ends_with(|c| !matches!(c, '[' | '{' | ','));
+                pending_comma = follows_value.then_some(stripped.len());
+                stripped.push(c);
+            }
+            ']' | '}' => {
+                if let Some(position) = pending_comma.take() {
+                    stripped.replace_range(position..position + 1, " ");
+                }
+                stripped.push(c);
+            }
+            c if c.is_whitespace() => stripped.push(c),
+            c => {
+                pending_comma = None;
+                stripped.push(c);
+            }
+        }
+    }
+    stripped
+}
+
+/// Parses JSON that may contain comments and trailing commas, as found in hand-written
+/// configuration files (JSONC). Strict JSON parses to the same value as with `serde_json`.
+///
+/// # Example
+///
+/// ```
+/// use drivel::parse_jsonc;
+/// use serde_json::json;
+///
+/// let input = r#"{
+///     // the port to listen on
+///     "port": 8080,
+///     "hosts": ["a", "b",], /* trailing comma */
+/// }"#;
+/// assert_eq!(parse_jsonc(input).unwrap(), json!({"port": 8080, "hosts": ["a", "b"]}));
+/// ```
+pub fn parse_jsonc(s: &str) -> serde_json::Result<Value> {
+    serde_json::from_str(&strip_comments_and_trailing_commas(s))
+}
+
+/// Quotes the `NaN`, `Infinity` and `-Infinity` that some encoders, such as Python's `json`
+/// module and pandas, write in place of numbers, so that the text parses as JSON. The tokens
+/// become strings, which inference takes for floats that aren't finite with
+/// [`InferenceOptions::non_finite`](crate::InferenceOptions::non_finite).
+///
+/// Only tokens in the place of a value are quoted, not those within strings.
+///
+/// # Example
+///
+/// ```
+/// use drivel::quote_non_finite;
+///
+/// assert_eq!(
+///     quote_non_finite(r#"{"score": NaN, "note": "NaN"}"#),
+///     r#"{"score": "NaN", "note": "NaN"}"#
+/// );
+/// ```
+pub fn quote_non_finite(s: &str) -> Cow<'_, str> {
+    const TOKENS: [&str; 3] = ["-Infinity", "Infinity", "NaN"];
+    if !TOKENS.iter().any(|token| s.contains(token)) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut quoted = String::with_capacity(s.len());
+    // the position in `s` up to which it has been copied to `quoted`
+    let mut copied = 0;
+    let mut in_string = false;
+    // the last character outside of whitespace, to tell whether a value can start after it
+    let mut previous = None;
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            _ if in_string || c.is_ascii_whitespace() => {}
+            _ => {
+                let starts_value = previous.is_none_or(|c| matches!(c, b'[' | b'{' | b',' | b':'));
+                // bytes rather than `str`s, which can't be sliced within a character
+                let token = TOKENS.iter().find(|token| {
+                    bytes[i..].starts_with(token.as_bytes())
+                        && bytes[i + token.len()..]
+                            .iter()
+                            .find(|c| !c.is_ascii_whitespace())
+                            .is_none_or(|c| matches!(c, b']' | b'}' | b','))
+                });
+                if let Some(token) = token.filter(|_| starts_value) {
+                    quoted.push_str(&s[copied..i]);
+                    quoted.push('"');
+                    quoted.push_str(token);
+                    quoted.push('"');
+                    i += token.len();
+                    copied = i;
+                    previous = Some(b'"');
+                    continue;
+                }
+            }
+        }
+        if !c.is_ascii_whitespace() {
+            previous = Some(c);
+        }
+        i += 1;
+    }
+    quoted.push_str(&s[copied..]);
+    Cow::Owned(quoted)
+}
+
+/// The length of the JSON number at the start of `bytes`, such as `-12.5e3`, or 0 if there is
+/// none.
+fn number_length(bytes: &[u8]) -> usize {
+    let digits_from = |i: usize| {
+        bytes[i.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut i = usize::from(bytes.first() == Some(&b'-'));
+    match digits_from(i) {
+        0 => return 0,
+        // leading zeros aren't allowed
+        n if n > 1 && bytes[i] == b'0' => return 0,
+        n => i += n,
+    }
+    if bytes.get(i) == Some(&b'.') {
+        match digits_from(i + 1) {
+            0 => return 0,
+            n => i += 1 + n,
+        }
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+        match digits_from(i + 1 + sign) {
+            0 => return 0,
+            n => i += 1 + sign + n,
+        }
+    }
+    i
+}
+
+/// Quotes the numbers of JSON text, so that they parse as strings of exactly the digits that
+/// were written, such as IDs too large for a 64-bit integer, or versions such as `1.10`, which
+/// would otherwise lose digits as floats. Each line of JSON lines is quoted the same way.
+///
+/// Only numbers in the place of a value are quoted, not those within strings.
+///
+/// # Example
+///
+/// ```
+/// use drivel::quote_numbers;
+///
+/// assert_eq!(
+///     quote_numbers(r#"{"id": 123456789012345678901, "v": [1.10, -2e3], "n": "7"}"#),
+///     r#"{"id": "123456789012345678901", "v": ["1.10", "-2e3"], "n": "7"}"#
+/// );
+/// ```
+pub fn quote_numbers(s: &str) -> Cow<'_, str> {
+    if !s.bytes().any(|b| b.is_ascii_digit()) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut quoted = String::with_capacity(s.len() + 16);
+    // the position in `s` up to which it has been copied to `quoted`
+    let mut copied = 0;
+    let mut in_string = false;
+    // the last character outside of whitespace, and whether a line has ended since, to tell
+    // whether a value can start after it
+    let mut previous = None;
+    let mut new_line = true;
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            _ if in_string => {}
+            b'\n' => new_line = true,
+            b'-' | b'0'..=b'9' => {
+                let starts_value =
+                    new_line || previous.is_none_or(|c| matches!(c, b'[' | b'{' | b',' | b':'));
+                let end = i + number_length(&bytes[i..]);
+                let ends_value = bytes[end..]
+                    .iter()
+                    .find(|c| !matches!(c, b' ' | b'\t' | b'\r'))
+                    .is_none_or(|c| matches!(c, b']' | b'}' | b',' | b'\n'));
+                if end > i && starts_value && ends_value {
+                    quoted.push_str(&s[copied..i]);
+                    quoted.push('"');
+                    quoted.push_str(&s[i..end]);
+                    quoted.push('"');
+                    i = end;
+                    copied = i;
+                    previous = Some(b'"');
+                    new_line = false;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        if !c.is_ascii_whitespace() {
+            previous = Some(c);
+            new_line = false;
+        }
+        i += 1;
+    }
+    quoted.push_str(&s[copied..]);
+    Cow::Owned(quoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strips_line_and_block_comments() {
+        let input = "// leading\n{\"a\": /* inline */ 1, /* multi\nline */ \"b\": 2} // trailing";
+        assert_eq!(parse_jsonc(input).unwrap(), json!({"a": 1, "b": 2}));
+    }
+
+    #[test]
+    fn strips_trailing_commas() {
+        let input = "{\"a\": [1, 2, ], \"b\": {\"c\": true,\n},\n}";
+        assert_eq!(
+            parse_jsonc(input).unwrap(),
+            json!({"a": [1, 2], "b": {"c": true}})
+        );
+    }
+
+    #[test]
+    fn leaves_strings_untouched() {
+        let input = r#"{"url": "https://example.com/*path*/", "s": "a,]\"//", }"#;
+        assert_eq!(
+            parse_jsonc(input).unwrap(),
+            json!({"url": "https://example.com/*path*/", "s": "a,]\"//"})
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(parse_jsonc("[1,, 2]").is_err());
+        assert!(parse_jsonc("{,}").is_err());
+        assert!(parse_jsonc("key: value").is_err());
+    }
+
+    #[test]
+    fn quotes_each_non_finite_token() {
+        for (input, token) in [
+            ("[1.5, NaN]", "NaN"),
+            ("[1.5, Infinity]", "Infinity"),
+            ("[1.5, -Infinity]", "-Infinity"),
+        ] {
+            let value: Value = serde_json::from_str(&quote_non_finite(input)).unwrap();
+            assert_eq!(value, json!([1.5, token]), "{}", input);
+        }
+        assert_eq!(
+            quote_non_finite("{\"a\":NaN,\"b\": {\"c\": -Infinity }}"),
+            "{\"a\":\"NaN\",\"b\": {\"c\": \"-Infinity\" }}"
+        );
+    }
+
+    #[test]
+    fn leaves_non_finite_tokens_in_strings_untouched() {
+        for input in [
+            r#"{"a": "NaN", "b": "x\" NaN"}"#,
+            r#"["Infinity, NaN"]"#,
+            "[1, 2]",
+        ] {
+            assert_eq!(quote_non_finite(input), input);
+        }
+        // nor those that are part of another word
+        assert_eq!(quote_non_finite("[NaNa, Infinityx]"), "[NaNa, Infinityx]");
+    }
+
+    #[test]
+    fn quotes_numbers_as_written() {
+        let input =
+            "{\"id\": 123456789012345678901234567890, \"a\": [0, -1.50, 2E-7 ],\n\"b\":{\"c\":10}}";
+        let value: Value = serde_json::from_str(&quote_numbers(input)).unwrap();
+        assert_eq!(
+            value,
+            json!({"id": "123456789012345678901234567890", "a": ["0", "-1.50", "2E-7"], "b": {"c": "10"}})
+        );
+        // each line of JSON lines, even if it is a number by itself
+        assert_eq!(
+            quote_numbers("1\n{\"a\": 2}\n3"),
+            "\"1\"\n{\"a\": \"2\"}\n\"3\""
+        );
+    }
+
+    #[test]
+    fn leaves_numbers_in_strings_and_words_untouched() {
+        for input in [
+            r#"{"a": "12", "b": "x\" 3, 4"}"#,
+            "[true, null]",
+            "[01, 1.a, 2-3, -, 1e]",
+        ] {
+            assert_eq!(quote_numbers(input), input);
+        }
+    }
+
+    #[test]
+    fn reports_positions_in_original_input() {
+        let err = parse_jsonc("/* one\ntwo */\n{\"a\": x}").unwrap_err();
+        assert_eq!(err.line(), 3);
+    }
+}
@@ -1,13 +1,133 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod all_of;
+mod anonymize;
+#[cfg(feature = "columnar")]
+mod arrow;
+mod avro;
+mod barcode;
+mod canonical;
+mod codegen;
+#[cfg(feature = "columnar")]
+mod columnar;
+mod compression;
+mod date_format;
+mod definitions;
+mod delimited;
+mod describe;
+mod diagnostics;
+mod diff;
+mod dot;
+mod draft;
+mod duplicate_keys;
+mod duration;
+mod error;
+mod estimate;
+mod extended_json;
+mod field_stats;
+mod financial;
+mod float_format;
+mod generators;
+mod graphql;
+mod hints;
 mod infer;
 mod infer_string;
+mod input;
+mod jsonc;
+mod merge;
+mod metrics;
+mod money;
+mod number_stats;
+mod openapi;
+#[cfg(feature = "columnar")]
+mod parquet;
 mod parse_schema;
+mod pattern;
 mod produce;
+mod produce_options;
+mod progress;
+mod projection;
+mod proto;
+mod pydantic;
+mod realistic;
+mod refs;
+mod relations;
+mod report;
+mod rust_types;
+mod sampling;
+mod saved_schema;
 mod schema;
+mod select;
+mod serve;
+mod signature;
+mod split;
+mod sql;
+mod summary;
+mod tabular;
+mod toml;
+mod typescript;
+mod validate;
+mod value_counts;
+mod xml;
+mod yaml;
+mod zod;
 
+pub use anonymize::Anonymizer;
+#[cfg(feature = "columnar")]
+pub use arrow::read_arrow;
+pub use avro::parse_avro_schema;
+pub use canonical::to_canonical_json;
+pub use compression::{Compression, Compressor, Decompressor};
+pub use delimited::{parse_delimited, DelimitedWriter, InputFormat};
+pub use describe::*;
+pub use diagnostics::{capture_diagnostics, emit, info, set_quiet, warn, Diagnostic, Severity};
+pub use diff::{SchemaChange, SchemaChangeKind};
+pub use draft::SchemaDraft;
+pub use duplicate_keys::DuplicateKeys;
+pub use error::DrivelError;
+pub use estimate::{estimate_produce, ProduceEstimate};
+pub use field_stats::{FieldCounts, FieldStats, MIN_UNIQUE_VALUES};
+pub use float_format::FixedPointFormatter;
+pub use generators::{
+    FormatGenerator, GeneratorRegistry, ValueGenerator, DEFAULT_COMMAND_BATCH_SIZE,
+};
+pub use hints::{HintKind, NameHint, TypeHint};
 pub use infer::*;
+pub use input::*;
+pub use jsonc::{parse_jsonc, quote_non_finite, quote_numbers};
+pub use metrics::SchemaMetrics;
+pub use number_stats::{Distribution, NumberStats};
+pub use openapi::{OpenApiOptions, OpenApiVersion};
+#[cfg(feature = "columnar")]
+pub use parquet::read_parquet;
 pub use parse_schema::*;
-pub use produce::produce;
+pub use produce::{
+    produce, produce_minimal, produce_streaming, produce_streaming_with, produce_with,
+    try_produce_each, try_produce_minimal, try_produce_records, try_produce_while,
+    try_produce_with, ProduceError,
+};
+pub use produce_options::*;
+pub use progress::Progress;
+pub use realistic::Locale;
+pub use report::{
+    infer_schema_with_report, InferenceReport, InferenceWarning, InferenceWarningKind,
+};
+pub use sampling::{RateSampler, SampleCounts, SampleStrategy, Sampler};
+pub use saved_schema::*;
 pub use schema::*;
+pub use select::Selector;
+pub use serve::MockServer;
+pub use signature::ParseSignatureError;
+pub use split::{parse_size, Framing, SplitLimits, SplitSummary, SplitWriter};
+pub use sql::SqlDialect;
+pub use summary::{FieldSummary, SchemaSummary, ValueCount};
+pub use tabular::{records_from_table, table_from_records, Table};
+pub use toml::parse_toml;
+pub use validate::{
+    infer_then_validate, validate_records, validate_records_with, ValidationError,
+    ValidationErrorKind, ValidationOptions,
+};
+pub use value_counts::{ValueCounts, DEFAULT_MAX_DISTINCT_VALUES};
+pub use xml::{parse_xml, XmlOptions};
+pub use yaml::{parse_yaml_documents, to_yaml_string, to_yaml_string_with};
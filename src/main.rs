@@ -1,8 +1,19 @@
-use clap::{Parser, Subcommand};
-use drivel::{SchemaState, ToJsonSchema};
-use serde_json::Value;
-use serde_yaml2;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use drivel::{
+    DescribeFormat, DescribeOptions, Description, DrivelError, JsonSchemaOptions, NumericBounds,
+    OpenApiOptions, OpenApiVersion, SampleStrategy, Sampler, SchemaState, SqlDialect,
+    DEFAULT_MAX_LIST_ITEMS,
+};
 use jemallocator::Jemalloc;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
@@ -11,15 +22,402 @@ static GLOBAL: Jemalloc = Jemalloc;
 enum Mode {
     /// Describe the inferred schema for the input data
     Describe {
-        /// Output JSON Schema format instead of human-readable description
-        #[arg(long)]
+        /// The format in which to describe the schema. Default = human.
+        #[arg(long, value_enum)]
+        format: Option<DescribeFormat>,
+        /// Deprecated alias for `--format json-schema`
+        #[arg(long, conflicts_with = "format")]
         json_schema: bool,
+        /// Alias for `--format openapi`
+        #[arg(long, conflicts_with_all = ["format", "json_schema"])]
+        openapi: bool,
+        /// Alias for `--format dot`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi"])]
+        dot: bool,
+        /// Alias for `--format typescript`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot"])]
+        typescript: bool,
+        /// Alias for `--format rust`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript"])]
+        rust: bool,
+        /// Alias for `--format sql`, in the given dialect of SQL. Default = postgres
+        #[arg(
+            long,
+            value_enum,
+            value_name = "DIALECT",
+            num_args = 0..=1,
+            default_missing_value = "postgres",
+            conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust"]
+        )]
+        sql: Option<SqlDialect>,
+        /// Alias for `--format avro`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust", "sql"])]
+        avro: bool,
+        /// Alias for `--format proto`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust", "sql", "avro"])]
+        proto: bool,
+        /// Alias for `--format pydantic`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust", "sql", "avro", "proto"])]
+        pydantic: bool,
+        /// Alias for `--format zod`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust", "sql", "avro", "proto", "pydantic"])]
+        zod: bool,
+        /// Alias for `--format graphql`
+        #[arg(long, conflicts_with_all = ["format", "json_schema", "openapi", "dot", "typescript", "rust", "sql", "avro", "proto", "pydantic", "zod"])]
+        graphql: bool,
+        /// The version of OpenAPI to describe the schema for. Default = 3.1.
+        #[arg(long, value_enum, value_name = "VERSION")]
+        openapi_version: Option<OpenApiVersion>,
+        /// The name of the schema under `components/schemas` in OpenAPI output. Default = "InferredSchema".
+        #[arg(long, value_name = "NAME")]
+        openapi_name: Option<String>,
+        /// In OpenAPI output, make every nested object a component schema of its own, named after
+        /// its field and referred to with `$ref`, instead of nesting it in the schema of its parent
+        #[arg(long)]
+        openapi_split: bool,
+        /// The name of the root type in TypeScript, Rust, Pydantic and Zod output, of the field of
+        /// the `Query` type in GraphQL output, of the root table in SQL output, and of the root
+        /// record or message in Avro and protobuf output, in snake case. Default = "InferredSchema".
+        #[arg(long, value_name = "NAME")]
+        type_name: Option<String>,
+        /// Describe an array of objects that all have the same fields as a table of record
+        /// fields
+        #[arg(long, conflicts_with = "json_schema")]
+        as_table: bool,
+        /// Write JSON output, such as JSON Schema, OpenAPI, Avro or metrics, as minified JSON
+        #[arg(long, conflicts_with = "indent")]
+        compact: bool,
+        /// The number of spaces to indent JSON output by. Default = 2.
+        #[arg(long, value_name = "N")]
+        indent: Option<usize>,
+        /// The most values of each enum, bitmask and the like to list in the human-readable
+        /// description, followed by how many more there are. 0 lists all of them. Default = 10
+        #[arg(long, value_name = "N")]
+        max_list: Option<usize>,
+        /// When to highlight the field names, types and keywords of the human-readable
+        /// description with colors: when writing to a terminal (auto), always or never.
+        /// Default = auto
+        #[arg(long, value_enum, value_name = "WHEN")]
+        color: Option<drivel::ColorChoice>,
+        /// Summarise the objects nested N or more levels deep in the human-readable description
+        /// by their numbers of fields, rather than listing their fields, where the fields of an
+        /// object at the root are 1 level deep
+        #[arg(long, value_name = "N")]
+        max_display_depth: Option<usize>,
+        /// Align the descriptions of the fields of each object in the human-readable description
+        /// in a column, after the longest field name
+        #[arg(long)]
+        align: bool,
+        /// The `title` of the JSON Schema document. Default = "Inferred Schema".
+        #[arg(long)]
+        title: Option<String>,
+        /// The `$id` of the JSON Schema document
+        #[arg(long)]
+        id: Option<String>,
+        /// Don't add descriptions derived from field names to JSON Schema properties
+        #[arg(long)]
+        no_field_descriptions: bool,
+        /// Add the range of observed numbers to JSON Schema output, as `minimum`/`maximum` or
+        /// `exclusiveMinimum`/`exclusiveMaximum`
+        #[arg(long, value_enum, value_name = "STYLE")]
+        bounds: Option<NumericBounds>,
+        /// Label floats whose values all lie within 0 to 1 as ratios, and those within 0 to 100
+        /// with some above 10 as percentages, such as `float (0.05-0.95, ratio 0..1)`. JSON
+        /// Schema output marks them with `x-drivel-numeric-range` and bounds them by the observed
+        /// values
+        #[arg(long)]
+        numeric_ranges: bool,
+        /// Add the lengths of observed strings, arrays and maps to JSON Schema output, as
+        /// `minLength`/`maxLength`, `minItems`/`maxItems` and `minProperties`/`maxProperties`
+        #[arg(long)]
+        lengths: bool,
+        /// The draft of JSON Schema to write JSON Schema output in: 2020-12, 07 or 04.
+        /// Default = 2020-12.
+        #[arg(long, value_enum, value_name = "DRAFT")]
+        schema_draft: Option<drivel::SchemaDraft>,
+        /// Define object schemas that occur more than once in JSON Schema output in `$defs`, and
+        /// refer to them with `$ref`
+        #[arg(long)]
+        dedupe_defs: bool,
+        /// Add how often each field was present, and null, in the input to JSON Schema output, as
+        /// `x-drivel-presence` and `x-drivel-null-ratio`, and mark the fields whose values were
+        /// all different as `x-drivel-unique`, and as unique in the human-readable description,
+        /// where optional fields are followed by how often they were present
+        #[arg(long)]
+        with_stats: bool,
+        /// List how often each value occurred under the fields with few distinct values in the
+        /// human-readable description, and add them to JSON Schema output as
+        /// `x-drivel-value-counts`
+        #[arg(long)]
+        value_counts: bool,
+        /// With --value-counts, the most distinct values a field may have for its values to be
+        /// counted. Default = 20
+        #[arg(long, value_name = "N", requires = "value_counts")]
+        max_distinct: Option<usize>,
+        /// Infer the schema from line-based input one line at a time, without holding all of the
+        /// records in memory, as is done anyway when the input looks line-based, such as JSON
+        /// lines. With `--progress`, the schema inferred so far is also written to stderr
+        /// periodically
+        #[arg(long)]
+        stream: bool,
+        /// Save the inferred schema to a file in drivel's native format, for use with `--schema`
+        #[arg(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+        /// Keep watching the input files, and the files in input directories, and infer and
+        /// describe the schema again whenever they change, followed by the changes from the
+        /// schema that was described before
+        #[arg(long)]
+        watch: bool,
+        /// The files to read input from, like `--input`, where `-` is stdin. A pattern in the
+        /// name of a file, such as `data/*.json`, is expanded to the files that match it, and a
+        /// directory to the files in it
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
     },
     /// Produce synthetic data adhering to the inferred schema
     Produce {
         #[arg(short, long)]
         /// Produce `n` elements. Default = 1.
         n_repeat: Option<usize>,
+        /// Produce as many elements as the array at the root of the schema may have, picked
+        /// within its inferred bounds, rather than one. `--n-repeat` overrides it.
+        #[arg(long, conflicts_with = "minimal")]
+        n_from_schema: bool,
+        /// Write the produced data as minified JSON
+        #[arg(long, conflicts_with = "indent")]
+        compact: bool,
+        /// The number of spaces to indent pretty-printed JSON by. Default = 2.
+        #[arg(long, value_name = "N")]
+        indent: Option<usize>,
+        /// The format to write the produced data in: a single JSON value (`json`), or each
+        /// element of an array at the root of the schema, or each of the `n` values otherwise,
+        /// as a line of compact JSON (`ndjson`), written as soon as it is produced, or as a row of
+        /// comma-separated values under a header row (`csv`), with the fields of nested objects
+        /// as columns such as `address.city` and arrays as JSON. Default = json.
+        #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "indent")]
+        format: Option<drivel::OutputFormat>,
+        /// Load per-field overrides for the produced values from a JSON or YAML config file
+        #[arg(long, value_name = "FILE")]
+        produce_config: Option<PathBuf>,
+        /// Produce the smallest valid instance of the schema, with only required fields, instead
+        /// of random data
+        #[arg(long, conflicts_with_all = ["n_repeat", "produce_config", "charset"])]
+        minimal: bool,
+        /// The characters to produce strings without a recognised format from: alphabetic,
+        /// alphanumeric, ascii, hex, or custom:<chars>. Default = the characters seen in the input.
+        #[arg(long, value_name = "CHARSET")]
+        charset: Option<drivel::StringCharset>,
+        /// Produce every string without a recognised format as lorem ipsum text, rather than only
+        /// those that looked like free text in the input
+        #[arg(long, conflicts_with_all = ["minimal", "charset"])]
+        lorem: bool,
+        /// Produce realistic names, email addresses, phone numbers, addresses, companies and job
+        /// titles for the strings of fields whose names say that they are one, such as
+        /// `first_name`, `phone` or `address.city`
+        #[arg(long, conflicts_with = "minimal")]
+        realistic: bool,
+        /// The locale of the values of `--realistic`. Default = en_US.
+        #[arg(long, value_enum, value_name = "LOCALE")]
+        locale: Option<drivel::Locale>,
+        /// Make the values at this path unique across all of the produced data, such as `[].id`.
+        /// Can be repeated.
+        #[arg(long, value_name = "PATH", conflicts_with = "minimal")]
+        unique: Vec<String>,
+        /// Produce fields that look like they refer to the IDs of other records, such as the
+        /// `user_id` of `orders[]` for `users[].id`, as independent values rather than as the
+        /// IDs of the produced records
+        #[arg(long)]
+        no_references: bool,
+        /// Produce only the values at these paths, separated by commas, such as
+        /// `id,address.city,orders[].total`, leaving out all other fields
+        #[arg(long, value_name = "PATHS", value_delimiter = ',')]
+        fields: Vec<String>,
+        /// Leave out fields that the schema marks as `readOnly`, even if they are required, such
+        /// as when producing request bodies for an API
+        #[arg(long = "omit-readonly", conflicts_with = "minimal")]
+        omit_read_only: bool,
+        /// Seed the random number generators, so that the same input and options always produce
+        /// the same data, such as for snapshot tests and fixtures. Overrides a `seed` in the
+        /// produce config
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        /// The probability that an optional field is present in a produced object, whether or
+        /// not it is `null` then. Default = how often it was present in the input, or 0.5 for a
+        /// schema
+        #[arg(long, value_name = "P", value_parser = parse_probability, conflicts_with = "minimal")]
+        optional_include_probability: Option<f64>,
+        /// The probability that a nullable value is `null`, for all values without a
+        /// `null_probability` in the produce config. Default = 0.5
+        #[arg(long, value_name = "P", value_parser = parse_probability, conflicts_with = "minimal")]
+        null_probability: Option<f64>,
+        /// Stop producing more data once it reaches about this many bytes, cutting arrays short
+        /// and leaving out optional fields, with a warning, rather than producing runaway output
+        #[arg(long, value_name = "N", conflicts_with = "minimal")]
+        max_bytes: Option<usize>,
+        /// How many levels of a recursive definition of the schema, such as the nodes of a tree,
+        /// to nest within it at most, leaving out what would go deeper where the schema allows
+        /// it. Default = 3
+        #[arg(long, value_name = "N", conflicts_with = "minimal")]
+        max_recursion_depth: Option<usize>,
+        /// Instead of producing any data, write an estimate of the number of records and fields
+        /// and the size of the data that would be produced to stderr
+        #[arg(long, conflicts_with = "minimal")]
+        estimate: bool,
+        /// Produce records until the data reaches about this size, such as 100MB or 1.5GiB,
+        /// rather than a number of them, leaving out the record that would take it past the
+        /// size. The records are written as a JSON array, or as lines or rows with `--format`
+        #[arg(long, value_name = "SIZE", value_parser = drivel::parse_size, conflicts_with_all = ["n_repeat", "minimal", "estimate"])]
+        target_size: Option<u64>,
+        /// Write the produced records into numbered files in this directory, `part-00001.json`,
+        /// `part-00002.json` and so on, each a whole JSON array, or JSON lines or CSV with
+        /// `--format`, rather than to stdout. The directory is created if it doesn't exist
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["minimal", "estimate"])]
+        out_dir: Option<PathBuf>,
+        /// With --out-dir, start the next file rather than let a file grow past this size, such
+        /// as 10MB
+        #[arg(long, value_name = "SIZE", value_parser = drivel::parse_size, requires = "out_dir")]
+        file_size: Option<u64>,
+        /// With --out-dir, write at most this many records to each file
+        #[arg(long, value_name = "N", requires = "out_dir")]
+        file_records: Option<usize>,
+        /// Compress the produced data with gzip or zstd. With
+        /// --out-dir, each file is compressed, and named with the extension of the format as
+        /// well, such as `part-00001.json.gz`
+        #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "estimate")]
+        compress: Option<drivel::Compression>,
+        /// What to produce in place of `NaN`, `Infinity` and `-Infinity`, seen with
+        /// `--allow-nonfinite`. Default = null
+        #[arg(long, value_enum, value_name = "FALLBACK")]
+        non_finite_fallback: Option<drivel::NonFiniteFallback>,
+        /// Write floats in fixed-point notation, such as 0.0000001 instead of 1e-7, unless they
+        /// are too large or small for it
+        #[arg(long)]
+        no_sci_notation: bool,
+        /// Write the produced data as canonical JSON, with sorted fields and the same rendering
+        /// of numbers on every platform, ending in a line feed, such as for golden fixtures.
+        /// Implies `--no-sci-notation`
+        #[arg(long)]
+        canonical: bool,
+        /// With --tabular-array, write the produced rows as objects with a field for each column,
+        /// instead of a header and rows
+        #[arg(long)]
+        objects: bool,
+        /// Also describe the inferred schema, writing the description to stderr, so that the
+        /// input is only read and inferred once for both
+        #[arg(long)]
+        describe: bool,
+        /// Write the description of the inferred schema to this file instead of stderr. Implies
+        /// `--describe`.
+        #[arg(long, value_name = "FILE")]
+        describe_out: Option<PathBuf>,
+        /// The format of the description written with `--describe`. Default = human.
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        describe_format: Option<DescribeFormat>,
+        /// The files to read input from, like `--input`, where `-` is stdin. A pattern in the
+        /// name of a file, such as `data/*.json`, is expanded to the files that match it, and a
+        /// directory to the files in it
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
+    },
+    /// Serve data produced from the schema over HTTP, as a mock API that answers every request
+    /// with fresh data
+    Serve {
+        /// The port to listen on. Default = 8080.
+        #[arg(long, value_name = "PORT")]
+        port: Option<u16>,
+        /// The address to listen on, such as 0.0.0.0 to accept requests from other hosts.
+        /// Default = 127.0.0.1.
+        #[arg(long, value_name = "ADDRESS")]
+        host: Option<String>,
+        /// Answer each request with `n` elements, as for `produce`. Default = 1.
+        #[arg(short, long)]
+        n_repeat: Option<usize>,
+        /// Delay each response by this many milliseconds, or by a random time within a range
+        /// such as 100-500, to simulate a slow API
+        #[arg(long, value_name = "MS", value_parser = parse_latency)]
+        latency: Option<(Duration, Duration)>,
+        /// Load per-field overrides for the produced values from a JSON or YAML config file, as
+        /// for `produce`
+        #[arg(long, value_name = "FILE")]
+        produce_config: Option<PathBuf>,
+        /// Produce realistic names, email addresses and the like, as for `produce`
+        #[arg(long)]
+        realistic: bool,
+        /// Seed the random number generators, so that the responses are the same, in the order
+        /// of the requests, every time the server is started
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        /// The files to read input from, like `--input`, where `-` is stdin and patterns are
+        /// expanded as in `describe`
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
+    },
+    /// Write the input with its strings and numbers replaced by synthetic values of the same
+    /// types, formats and lengths, keeping its keys, array lengths and nesting, so that real
+    /// data can be shared as test fixtures
+    Anonymize {
+        /// Write the data as minified JSON
+        #[arg(long)]
+        compact: bool,
+        /// Seed the random number generator, so that the same input is always replaced by the
+        /// same data
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        /// The files to read input from, like `--input`, where `-` is stdin and patterns are
+        /// expanded as in `describe`
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
+    },
+    /// Validate data against the schema, inferred from the input as a reference or given with
+    /// `--from-schema`, `--schema` or `--sig`, writing where it doesn't fit and failing if it
+    /// doesn't
+    Validate {
+        /// The files of data to validate, where `-` is stdin and patterns are expanded as in
+        /// `describe`. Without them, the data is read from stdin, or the `--input` files, if
+        /// the schema is given with `--schema` or `--sig`
+        #[arg(value_name = "FILE")]
+        data: Vec<PathBuf>,
+        /// Also check the lengths of arrays, maps and strings against the bounds of the schema,
+        /// such as its `minItems` and `maxItems`
+        #[arg(long)]
+        check_lengths: bool,
+    },
+    /// Merge the schemas of several sources into one, in which the fields that not all of them
+    /// have are optional, and the types that differ between them are widened
+    Merge {
+        /// The sources to merge: schemas saved with `describe --save`, JSON Schemas, or data to
+        /// infer a schema from, where `-` is stdin and patterns are expanded as in `describe`.
+        /// A JSON or YAML document with a `$schema` keyword is a JSON Schema, as is every
+        /// source with `--from-schema`. Unless every source is of an array, those that are
+        /// are taken to be arrays of records, and their records are merged with the others
+        #[arg(value_name = "FILES", required = true)]
+        sources: Vec<PathBuf>,
+        /// The format in which to describe the merged schema. Default = json-schema.
+        #[arg(long, value_enum)]
+        format: Option<DescribeFormat>,
+        /// Save the merged schema to a file in drivel's native format, for use with `--schema`
+        #[arg(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+    },
+    /// Compare the schema of an old source with that of a new one, such as the responses of an
+    /// API before and after a release, writing the fields that were added and removed and the
+    /// values whose types, nullability or enum values changed
+    Diff {
+        /// The old source, as for `merge`: a saved schema, a JSON Schema, or data to infer a
+        /// schema from, where `-` is stdin
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+        /// The new source, as for `merge`
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+        /// Write the changes as a JSON array of objects with their `path`, the `change` and
+        /// whether it is `breaking`
+        #[arg(long)]
+        json: bool,
+        /// Fail with exit code 1 if any change is breaking, that is, if data of the new schema
+        /// may not fit the old one, such as for a removed required field or a changed type
+        #[arg(long)]
+        fail_on_breaking: bool,
     },
 }
 
@@ -29,10 +427,341 @@ struct Args {
     #[command(subcommand)]
     mode: Mode,
 
+    /// Serialize produced data and JSON Schema output as YAML instead of JSON
+    #[arg(long, global = true)]
+    yaml: bool,
+
+    /// Write the fields of objects in the order of their names, in produced data and in every
+    /// description, instead of the order in which they were first seen in the input
+    #[arg(long, global = true)]
+    sort_keys: bool,
+
+    /// The encoding of the input. Detected from the byte order mark if omitted, defaulting to UTF-8.
+    #[arg(long, global = true, value_enum)]
+    encoding: Option<drivel::InputEncoding>,
+
+    /// Decompress the input as gzip or zstd. Input that starts as gzip or zstd data does, such as a `.json.gz` file, is decompressed without it
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    decompress: Option<drivel::Compression>,
+
+    /// The format of the input: JSON, YAML or JSON lines (`json`), delimited values with a
+    /// header row of field names (`csv` or `tsv`), an XML document (`xml`), a TOML document
+    /// (`toml`), a Parquet file (`parquet`) or an Arrow IPC stream or file (`arrow`). Default = by
+    /// the extension of each file, `.csv`, `.tsv`, `.xml`, `.toml`, `.parquet` or `.arrow`, or
+    /// json otherwise, which is also read as TOML if it is neither JSON nor YAML
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    input_format: Option<drivel::InputFormat>,
+
+    /// In XML input, the prefix of the fields of attributes, which may be empty to make them
+    /// fields like those of child elements. Default = @
+    #[arg(
+        long,
+        global = true,
+        value_name = "PREFIX",
+        conflicts_with = "xml_ignore_attributes"
+    )]
+    xml_attribute_prefix: Option<String>,
+
+    /// Leave the attributes of XML input out
+    #[arg(long, global = true)]
+    xml_ignore_attributes: bool,
+
+    /// In XML input, the field of the text of elements that also have child elements or
+    /// attributes. Default = #text
+    #[arg(long, global = true, value_name = "KEY")]
+    xml_text_key: Option<String>,
+
+    /// The number of threads to infer schemas and produce data on. Default = one per CPU
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    jobs: Option<usize>,
+
+    /// Read the defaults of options, and rules for the values of fields, from a TOML config
+    /// file. Default = drivel.toml in the current directory, if there is one
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Don't read drivel.toml from the current directory
+    #[arg(long, global = true, conflicts_with = "config")]
+    no_config: bool,
+
+    /// The rules for the values of fields in the `[fields]` table of the config file
+    #[arg(skip)]
+    config_fields: BTreeMap<String, drivel::FieldOverride>,
+
+    /// Track the mean and standard deviation of the numbers of each field of the input, to
+    /// produce numbers around the same mean and to list them in the human-readable description
+    #[arg(long, global = true)]
+    track_distributions: bool,
+
+    /// Periodically report the number of records processed to stderr when inferring from line-based input
+    #[arg(long, global = true)]
+    progress: bool,
+
+    /// Once the schema has been inferred from the input, write a summary to stderr: the number of records read, how many bytes they took, the time taken, and the most nodes the schema had while being inferred
+    #[arg(long, global = true)]
+    stats: bool,
+
+    /// Leave warnings and progress out of stderr, writing only errors there
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Skip lines of line-based input that cannot be parsed, instead of failing
+    #[arg(long, global = true)]
+    skip_errors: bool,
+
+    /// Fail if more than this many lines are skipped with `--skip-errors`
+    #[arg(long, global = true, value_name = "N", requires = "skip_errors")]
+    max_errors: Option<usize>,
+
+    /// Infer the schema from at most this many records, sampled with `--sample-strategy`
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "max-docs",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_samples: Option<usize>,
+
+    /// How the records are sampled with `--max-samples`: the first ones (`head`), which stops
+    /// reading early, or a uniformly random selection of all of them (`reservoir`), which reads
+    /// the whole input. Default = head
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "STRATEGY",
+        requires = "max_samples"
+    )]
+    sample_strategy: Option<SampleStrategy>,
+
+    /// Infer the schema from each record with this probability, such as 0.01 for about 1% of
+    /// them, reading the whole input but holding none of the records that aren't sampled. With
+    /// `--max-samples`, at most that many of the sampled records are kept
+    #[arg(long, global = true, value_name = "P", value_parser = parse_probability)]
+    sample_rate: Option<f64>,
+
+    /// Seed the random sampling of `--sample-rate` and `--sample-strategy reservoir`, so that the
+    /// same input is always sampled the same
+    #[arg(long, global = true, value_name = "N")]
+    sample_seed: Option<u64>,
+
+    /// Read input from this file instead of stdin. Can be repeated, in which case the records of
+    /// all files are combined: the elements of an array at the root of a file are records, and
+    /// any other file is a single record
+    #[arg(long, global = true, value_name = "FILE")]
+    input: Vec<PathBuf>,
+
+    /// Read input from the response to a GET request of this URL instead of
+    /// stdin. Can be repeated, and combined with --input, in which case the records of all
+    /// responses and files are combined
+    #[arg(long, global = true, value_name = "URL")]
+    url: Vec<String>,
+
+    /// Read only the values at this path of each input, such as the payload of an envelope: a
+    /// JSONPath such as `$.data.items[*]`, a JSON Pointer such as `/data/items`, or a path such
+    /// as `data.items[]`. The values matched by wildcards are records of their own
+    #[arg(long, global = true, value_name = "PATH")]
+    select: Option<drivel::Selector>,
+
+    /// With --url, request each URL this many times, with `{page}` in it replaced by the number
+    /// of the page, from 1, stopping early at a page that is an empty array. Default = 1
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        requires = "url",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pages: Option<usize>,
+
+    /// With --url, a header to send with the requests, such as 'Authorization: Bearer TOKEN'.
+    /// Can be repeated
+    #[arg(long, global = true, value_name = "HEADER", requires = "url")]
+    header: Vec<String>,
+
+    /// With --url, the user name and password to authenticate the requests with, as
+    /// USER:PASSWORD
+    #[arg(long, global = true, value_name = "USER:PASSWORD", requires = "url")]
+    user: Option<String>,
+
+    /// Read the input as tabular JSON, an array of a header of column names followed by rows of
+    /// values, inferring the schema of objects with a field for each column. Produced data is
+    /// written as a header and rows too, unless `produce --objects` is given
+    #[arg(long, global = true)]
+    tabular_array: bool,
+
+    /// Write the judgment calls that inference makes to stderr as warnings, such as values of
+    /// conflicting types, or objects with many fields being treated as maps
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Like `--strict`, but also fail if inference makes any judgment calls
+    #[arg(long, global = true)]
+    strict_fail: bool,
+
     /// Treat input as JSON Schema instead of example data
     #[arg(long, global = true)]
     from_schema: bool,
 
+    /// The language of the schema read with --from-schema. Default = json-schema
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        requires = "from_schema"
+    )]
+    schema_format: Option<drivel::SchemaFormat>,
+
+    /// Load a schema saved with `describe --save` instead of inferring one from stdin
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        conflicts_with_all = ["from_schema", "input"]
+    )]
+    schema: Option<PathBuf>,
+
+    /// Use the schema of a signature, as written by `describe --format signature`, such as
+    /// '{id:int, tags:[string]}', instead of inferring one from stdin
+    #[arg(
+        long,
+        global = true,
+        value_name = "SIGNATURE",
+        conflicts_with_all = ["from_schema", "input", "schema"]
+    )]
+    sig: Option<SchemaState>,
+
+    /// Extend a schema saved with `describe --save` or `--format drivel-state` with the input,
+    /// as if it had been inferred from the input it was inferred from along with this input
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        conflicts_with_all = ["from_schema", "schema", "sig"]
+    )]
+    resume: Option<PathBuf>,
+
+    /// Treat objects with more than `N` fields as maps, with a single schema for all values
+    #[arg(long, global = true, value_name = "N")]
+    max_fields: Option<usize>,
+
+    /// Don't infer objects and arrays nested more than `N` levels deep in each input value,
+    /// where an object at the root is one level, leaving them of any value, which is produced as null
+    #[arg(long, global = true, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Don't infer objects with more than `N` keys, in one object or all of them together,
+    /// leaving them of any value, which is produced as null, instead of inferring a field for each key
+    #[arg(long, global = true, value_name = "N")]
+    max_object_keys: Option<usize>,
+
+    /// Keep objects with different sets of fields apart as variants of a union, instead of merging them
+    #[arg(long, global = true)]
+    union_objects: bool,
+
+    /// Keep values of different types apart as variants of a union, such as a field that is a string in some records and a number in others, with how often each was seen, instead of leaving them of any value
+    #[arg(long, global = true)]
+    union_types: bool,
+
+    /// With --union-objects, merge objects whose sets of fields overlap by at least this fraction, between 0 and 1, instead of keeping them apart. Default = 1, merging only objects with the same fields
+    #[arg(long, global = true, value_name = "T", value_parser = parse_probability, requires = "union_objects")]
+    merge_threshold: Option<f64>,
+
+    /// Infer objects with a string value for this field, such as `type`, as a tagged union with a shape for each of its values
+    #[arg(long, global = true, value_name = "FIELD")]
+    discriminator: Option<String>,
+
+    /// Look for a string field with few values that tells the shapes of objects apart, and infer them as a tagged union of its values
+    #[arg(long, global = true, conflicts_with = "discriminator")]
+    infer_discriminator: bool,
+
+    /// Track the distribution of array lengths, so that produced arrays follow it instead of a uniform one
+    #[arg(long, global = true)]
+    length_dist: bool,
+
+    /// Recognise MongoDB extended JSON values, such as `{"$oid": "..."}`, and produce them in the same form
+    #[arg(long, global = true)]
+    mongo_ext: bool,
+
+    /// Recognise arrays of two or three numbers as coordinates, such as `[lon, lat]` or `[x, y, z]`, and objects of a latitude and longitude, such as `{"lat": 52.37, "lng": 4.89}`, as geographic points
+    #[arg(long, global = true)]
+    infer_coords: bool,
+
+    /// Recognise arrays of two to eight values of different types, such as `["Ada", 36, true]`, as tuples with a type for each position, described by `prefixItems`
+    #[arg(long, global = true)]
+    infer_tuples: bool,
+
+    /// Recognise payment card numbers and IBANs by validating their checksums, and produce synthetic ones that pass them
+    #[arg(long, global = true)]
+    infer_financial: bool,
+
+    /// Recognise ISBN-10s, ISBN-13s and EAN-13 barcodes by validating their check digits, and produce synthetic ones that pass them
+    #[arg(long, global = true)]
+    infer_barcodes: bool,
+
+    /// Recognise objects of just an amount and an ISO 4217 currency code, such as `{"amount": 1299, "currency": "USD"}`, as money, with integer amounts in minor units, and produce realistic amounts in the currencies seen
+    #[arg(long, global = true)]
+    infer_money: bool,
+
+    /// Recognise ISO 8601 durations, such as `PT1H30M` or `P3DT12H`, and produce random ones
+    #[arg(long, global = true)]
+    infer_durations: bool,
+
+    /// Infer string fields whose values are all numbers, such as "42" or "3.14", as numeric strings with the range of the numbers. Strings with leading zeros are kept as they are
+    #[arg(long, global = true)]
+    coerce_numeric_strings: bool,
+
+    /// Accept the `NaN`, `Infinity` and `-Infinity` that some encoders, such as Python's `json` module, write in place of numbers, and infer them as floats. JSON can't represent them, so produced data has `--non-finite-fallback` in their place
+    #[arg(long, global = true)]
+    allow_nonfinite: bool,
+
+    /// Read the numbers of JSON input as strings of the digits that were written, so that none lose precision, such as IDs too large for a 64-bit integer, and produce strings of digits in their place
+    #[arg(long, global = true, conflicts_with = "coerce_numeric_strings")]
+    numbers_as_strings: bool,
+
+    /// What to do with objects of JSON input that have the same key more than once, of which only the last value is read: keep the last value silently, warn about it, or fail. Default = keep-last
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    on_duplicate_keys: Option<drivel::DuplicateKeys>,
+
+    /// A chrono-style format, such as `%m/%d/%Y`, to recognise date-times in and produce them in. Can be repeated; where several formats fit, the first wins
+    #[arg(long = "date-format", global = true, value_name = "FORMAT", value_parser = parse_date_format)]
+    date_format: Vec<String>,
+
+    /// Recognise integer fields that increase from one value to the next, such as auto-increment IDs, and produce them as increasing sequences
+    #[arg(long, global = true)]
+    infer_sequence: bool,
+
+    /// Recognise integer fields that are bitmasks of flags, such as permissions, whose values are combinations of a few powers of two, and produce them as random combinations of the flags
+    #[arg(long, global = true)]
+    infer_bitflags: bool,
+
+    /// Recognise objects whose fields are all of one schema, and whose keys look like data, such as dates or IDs, or that have hundreds of fields, as maps, with a single schema for all values
+    #[arg(long, global = true)]
+    infer_maps: bool,
+
+    /// A known set of values, as `NAME=FILE` with one value per line of the file. String fields whose values all belong to it are inferred as enums of the whole vocabulary, even if only some of its values were seen. Can be repeated; where several vocabularies fit, the first wins
+    #[arg(long, global = true, value_name = "NAME=FILE", value_parser = parse_vocabulary_arg)]
+    vocabulary: Vec<(String, PathBuf)>,
+
+    /// Force the values at a path to be of a type, whatever inference concludes, as `PATH=TYPE`, such as `users.*.id=uuid`, where `*` matches the elements of arrays and every field of objects. The type is one of email, uuid, date, date-time, uri, hostname, duration, enum (of the values seen) or string (of no particular format). Can be repeated
+    #[arg(long, global = true, value_name = "PATH=TYPE")]
+    hint: Vec<drivel::TypeHint>,
+
+    /// Classify the values of fields by the names of the fields where the values say nothing of what they are, as they were all null or empty strings: `*_at` and `timestamp` as date-times, `*_date` as dates, `id` and `*_id` as UUIDs, and `email`, `url`, `hostname` and `duration` by their formats. More names can be given in the `[name-hints]` table of the config file, as `PATTERN = TYPE`, of the types of --hint
+    #[arg(long, global = true)]
+    infer_from_names: bool,
+
+    /// The hints of the `[name-hints]` table of the config file
+    #[arg(skip)]
+    config_name_hints: Vec<drivel::NameHint>,
+
     /// Infer that some string fields are enums based on the number of unique values seen.
     #[arg(long, global = true)]
     infer_enum: bool,
@@ -41,9 +770,33 @@ struct Args {
     #[arg(long, global = true)]
     enum_max_uniq: Option<f64>,
 
+    /// The maximum number of distinct values of a field for it to be considered an enum, in addition to the ratio of --enum-max-uniq, which on large inputs allows many values. Default = no maximum.
+    #[arg(long, global = true, value_name = "N")]
+    enum_max_values: Option<usize>,
+
     /// The minimum sample size of strings before enum inference will be attempted. Default = 1.
     #[arg(long, global = true)]
     enum_min_n: Option<usize>,
+
+    /// Also consider integer fields for enum inference, using the same thresholds as strings.
+    #[arg(long, global = true)]
+    enum_numeric: bool,
+
+    /// Ignore case and surrounding whitespace when counting unique values for enum inference, so that e.g. "Active" and " ACTIVE " are one variant.
+    #[arg(long, global = true)]
+    enum_normalize: bool,
+
+    /// Also infer enums with an open tail: string fields whose most common values cover most of the values seen, such that the most common values are an enum and the rest are other strings.
+    #[arg(long, global = true, requires = "infer_enum")]
+    enum_open_tail: bool,
+
+    /// The minimum fraction of values that the enum members of an open-tail enum must cover. Default = 0.9.
+    #[arg(long, global = true, value_name = "RATIO", requires = "enum_open_tail")]
+    enum_coverage: Option<f64>,
+
+    /// The minimum number of values of a string field before its format (date, UUID, etc.) is inferred. Default = 1.
+    #[arg(long, global = true, value_name = "N")]
+    format_min_n: Option<usize>,
 }
 
 impl From<&Args> for Option<drivel::EnumInference> {
@@ -53,7 +806,13 @@ impl From<&Args> for Option<drivel::EnumInference> {
             let min_sample_size = value.enum_min_n.unwrap_or(1);
             Some(drivel::EnumInference {
                 max_unique_ratio,
+                max_values: value.enum_max_values,
                 min_sample_size,
+                include_numeric: value.enum_numeric,
+                normalize: value.enum_normalize,
+                open_tail_coverage: value
+                    .enum_open_tail
+                    .then(|| value.enum_coverage.unwrap_or(0.9)),
             })
         } else {
             None
@@ -61,106 +820,2674 @@ impl From<&Args> for Option<drivel::EnumInference> {
     }
 }
 
-fn parse_json_or_yaml(s: &str) -> Result<Value, String> {
-    match serde_json::from_str(s) {
-        Ok(v) => Ok(v),
-        Err(json_err) => match serde_yaml2::from_str::<serde_yaml2::wrapper::YamlNodeWrapper>(s) {
-            Ok(node) => serde_json::to_value(&node).map_err(|e| e.to_string()),
-            Err(yaml_err) => Err(format!(
-                "JSON error: {}. YAML error: {}",
-                json_err, yaml_err
-            )),
-        },
+/// The exit code for an error, following the conventions of the BSD `sysexits.h`: 65 for input
+/// (data or a schema) that is invalid, 74 for failing to read or write a file, and 1 for anything
+/// else, such as a schema that data can't be produced from as requested.
+fn exit_code(err: impl Into<DrivelError>) -> i32 {
+    match err.into() {
+        DrivelError::Io(_) => 74,
+        DrivelError::Decode(_)
+        | DrivelError::Parse(_)
+        | DrivelError::Schema(_)
+        | DrivelError::LoadSchema(_) => 65,
+        DrivelError::Produce(_) => 1,
     }
 }
 
-fn main() {
-    let args = Args::parse();
-    let input = match std::io::read_to_string(std::io::stdin()) {
-        Ok(s) => s,
+fn parse_date_format(format: &str) -> Result<String, String> {
+    if chrono::format::StrftimeItems::new(format).any(|item| item == chrono::format::Item::Error) {
+        Err(format!("'{}' is not a valid chrono format", format))
+    } else {
+        Ok(format.to_string())
+    }
+}
+
+fn parse_probability(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(probability) if (0.0..=1.0).contains(&probability) => Ok(probability),
+        _ => Err(format!("'{}' is not a probability between 0 and 1", arg)),
+    }
+}
+
+/// Parses a latency in milliseconds, or a range of them such as `100-500`.
+fn parse_latency(arg: &str) -> Result<(Duration, Duration), String> {
+    let millis = |ms: &str| ms.trim().parse::<u64>().map(Duration::from_millis);
+    let latency = match arg.split_once('-') {
+        Some((min, max)) => millis(min).and_then(|min| Ok((min, millis(max)?))),
+        None => millis(arg).map(|ms| (ms, ms)),
+    };
+    match latency {
+        Ok((min, max)) if min <= max => Ok((min, max)),
+        _ => Err(format!(
+            "expected milliseconds or a range of them such as 100-500, got '{}'",
+            arg
+        )),
+    }
+}
+
+fn parse_vocabulary_arg(arg: &str) -> Result<(String, PathBuf), String> {
+    match arg.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), PathBuf::from(path)))
+        }
+        _ => Err(format!("expected NAME=FILE, got '{}'", arg)),
+    }
+}
+
+/// Loads a vocabulary from a file with one value per line, ignoring surrounding whitespace and
+/// blank lines.
+fn load_vocabulary((name, path): &(String, PathBuf)) -> drivel::Vocabulary {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
         Err(err) => {
-            eprintln!("Unable to read from stdin. Error: {}", err);
-            std::process::exit(1)
+            eprintln!(
+                "Unable to read vocabulary '{}' from {}. Error: {}",
+                name,
+                path.display(),
+                err
+            );
+            std::process::exit(exit_code(err))
         }
     };
+    let values: std::collections::BTreeSet<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if values.is_empty() {
+        eprintln!(
+            "Error: vocabulary '{}' in {} has no values",
+            name,
+            path.display()
+        );
+        std::process::exit(1)
+    }
+    drivel::Vocabulary {
+        name: name.clone(),
+        values,
+    }
+}
 
-    let schema = if args.from_schema {
-        // Parse input as JSON Schema (JSON or YAML)
-        let json = match parse_json_or_yaml(&input) {
-            Ok(json) => json,
-            Err(err) => {
-                eprintln!("Error parsing input as JSON or YAML Schema: {}", err);
-                std::process::exit(1);
+/// Whether the input consists of multiple JSON documents, one per line. The YAML parser accepts
+/// such input as a single document (keeping only the first line), so this needs to be checked
+/// before attempting to parse the input as a single document.
+fn write_value(value: &Value, yaml: bool) {
+    write_json(value, yaml, Some(2), false)
+}
+
+fn serialize_with<F: serde_json::ser::Formatter>(
+    out: &mut dyn Write,
+    value: &Value,
+    formatter: F,
+) -> std::io::Result<()> {
+    let mut serializer = serde_json::Serializer::with_formatter(out, formatter);
+    Ok(value.serialize(&mut serializer)?)
+}
+
+/// Writes a value as YAML, or as JSON that is pretty-printed with the given indentation, or
+/// minified if `indent` is `None`. Floats are written in fixed-point notation where feasible if
+/// `fixed_point` is set.
+fn write_json(value: &Value, yaml: bool, indent: Option<usize>, fixed_point: bool) {
+    write_json_to(&mut std::io::stdout(), value, yaml, indent, fixed_point).unwrap();
+}
+
+/// Writes a value to `out`, as for [`write_json`].
+fn write_json_to(
+    out: &mut dyn Write,
+    value: &Value,
+    yaml: bool,
+    indent: Option<usize>,
+    fixed_point: bool,
+) -> std::io::Result<()> {
+    if yaml {
+        writeln!(out, "{}", drivel::to_yaml_string_with(value, fixed_point))
+    } else if let Some(indent) = indent {
+        let indent = " ".repeat(indent);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        if fixed_point {
+            serialize_with(out, value, drivel::FixedPointFormatter::new(formatter))
+        } else {
+            serialize_with(out, value, formatter)
+        }
+    } else if fixed_point {
+        serialize_with(
+            out,
+            value,
+            drivel::FixedPointFormatter::new(serde_json::ser::CompactFormatter),
+        )
+    } else {
+        serialize_with(out, value, serde_json::ser::CompactFormatter)
+    }
+}
+
+/// Produces data and writes it as JSON while it is produced, pretty-printed with the given
+/// indentation or minified if `indent` is `None`, as by [`write_json`].
+fn write_produced(
+    schema: &SchemaState,
+    n: usize,
+    options: &drivel::ProduceOptions,
+    mut out: impl Write,
+    indent: Option<usize>,
+    fixed_point: bool,
+) -> Result<(), DrivelError> {
+    use drivel::produce_streaming_with as produce;
+    use serde_json::ser::{CompactFormatter, PrettyFormatter};
+    use serde_json::Serializer;
+    match indent.map(|indent| " ".repeat(indent)) {
+        Some(indent) => {
+            let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+            if fixed_point {
+                let formatter = drivel::FixedPointFormatter::new(formatter);
+                produce(
+                    schema,
+                    n,
+                    options,
+                    &mut Serializer::with_formatter(&mut out, formatter),
+                )?
+            } else {
+                produce(
+                    schema,
+                    n,
+                    options,
+                    &mut Serializer::with_formatter(&mut out, formatter),
+                )?
             }
-        };
+        }
+        None if fixed_point => {
+            let formatter = drivel::FixedPointFormatter::new(CompactFormatter);
+            produce(
+                schema,
+                n,
+                options,
+                &mut Serializer::with_formatter(&mut out, formatter),
+            )?
+        }
+        None => produce(schema, n, options, &mut Serializer::new(&mut out))?,
+    }
+    Ok(out.flush()?)
+}
 
-        match drivel::parse_json_schema(&json) {
-            Ok(schema) => schema,
-            Err(err) => {
-                eprintln!("Error parsing JSON Schema: {}", err);
-                std::process::exit(1);
+/// The schema with the array at its root, if any, of exactly `n` elements, whatever its bounds.
+fn with_root_length(schema: SchemaState, n: usize) -> SchemaState {
+    match schema {
+        SchemaState::Array {
+            unique_items,
+            schema,
+            ..
+        } => SchemaState::Array {
+            min_length: n,
+            max_length: n,
+            lengths: None,
+            unique_items,
+            schema,
+        },
+        schema => schema,
+    }
+}
+
+/// Whether the records of an array of objects, or an object's nested objects, all have the same
+/// fields, as the rows of `--as-table` should.
+fn has_uniform_records(schema: &SchemaState) -> bool {
+    match schema {
+        SchemaState::Array { schema, .. } => has_uniform_records(schema),
+        SchemaState::Object {
+            required, optional, ..
+        } => optional.is_empty() && required.values().all(has_uniform_records),
+        _ => true,
+    }
+}
+
+/// The writer of the rows of `--format csv`, with the columns in the order of their names with
+/// `sort_keys`, or exits if the schema's values can't be rows.
+fn csv_writer(schema: &SchemaState, sort_keys: bool) -> drivel::DelimitedWriter {
+    let mut schema = schema.clone();
+    if sort_keys {
+        schema.sort_keys();
+    }
+    drivel::DelimitedWriter::new(&schema, ',').unwrap_or_else(|| {
+        eprintln!("Error: --format csv writes objects as rows, but the schema's values aren't objects or arrays of them");
+        std::process::exit(1)
+    })
+}
+
+/// How [`write_split`] writes each record.
+enum RecordFormat {
+    /// As an element of a JSON array, pretty-printed with the given indentation, and indented
+    /// by it as well, or minified if `indent` is `None`.
+    Json {
+        indent: Option<usize>,
+        canonical: bool,
+        fixed_point: bool,
+    },
+    /// As a line of compact JSON, as by [`write_line`].
+    Lines { canonical: bool, fixed_point: bool },
+    /// As a row of comma-separated values.
+    Csv(drivel::DelimitedWriter),
+}
+
+impl RecordFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            RecordFormat::Json { .. } => "json",
+            RecordFormat::Lines { .. } => "ndjson",
+            RecordFormat::Csv(_) => "csv",
+        }
+    }
+
+    /// What surrounds and separates the records of each file.
+    fn framing(&self) -> drivel::Framing {
+        match self {
+            RecordFormat::Json { indent, .. } => drivel::Framing::json_array(*indent),
+            RecordFormat::Lines { .. } => drivel::Framing::lines(vec![]),
+            RecordFormat::Csv(csv) => {
+                let mut header = vec![];
+                csv.write_header(&mut header)
+                    .expect("writing to memory doesn't fail");
+                drivel::Framing::lines(header)
             }
         }
-    } else {
-        // Existing inference workflow
-        let opts = drivel::InferenceOptions {
-            enum_inference: (&args).into(),
-        };
+    }
 
-        if let Ok(json) = parse_json_or_yaml(&input) {
-            drivel::infer_schema(json, &opts)
-        } else {
-            // unable to parse input as single document; try line-based format
-            let values = input
-                .lines()
-                .map(|line| match parse_json_or_yaml(line) {
-                    Ok(v) => v,
-                    Err(err) => {
-                        eprintln!(
-                            "Error parsing input; are you sure it is valid JSON or YAML? Error: {}",
-                            err
-                        );
-                        std::process::exit(1);
+    fn serialize(&self, value: &Value) -> Vec<u8> {
+        use serde_json::ser::{CompactFormatter, PrettyFormatter};
+        fn to_vec(value: &Value, formatter: impl serde_json::ser::Formatter) -> Vec<u8> {
+            let mut out = vec![];
+            value
+                .serialize(&mut serde_json::Serializer::with_formatter(
+                    &mut out, formatter,
+                ))
+                .expect("writing to memory doesn't fail");
+            out
+        }
+        let mut out = vec![];
+        match self {
+            RecordFormat::Json {
+                indent,
+                canonical,
+                fixed_point,
+            } => {
+                let json = match indent.map(|indent| " ".repeat(indent)) {
+                    _ if *canonical => drivel::to_canonical_json(value, *indent)
+                        .trim_end()
+                        .as_bytes()
+                        .to_vec(),
+                    Some(indent) if *fixed_point => to_vec(
+                        value,
+                        drivel::FixedPointFormatter::new(PrettyFormatter::with_indent(
+                            indent.as_bytes(),
+                        )),
+                    ),
+                    Some(indent) => to_vec(value, PrettyFormatter::with_indent(indent.as_bytes())),
+                    None if *fixed_point => {
+                        to_vec(value, drivel::FixedPointFormatter::new(CompactFormatter))
                     }
-                })
-                .collect();
-            drivel::infer_schema_from_iter(values, &opts)
+                    None => to_vec(value, CompactFormatter),
+                };
+                match indent {
+                    // strings can't span lines in JSON, so each line is part of the structure
+                    Some(indent) => {
+                        let json = String::from_utf8(json).expect("JSON is UTF-8");
+                        let lines: Vec<_> = json
+                            .lines()
+                            .map(|line| format!("{}{}", " ".repeat(*indent), line))
+                            .collect();
+                        out = lines.join("\n").into_bytes();
+                    }
+                    None => out = json,
+                }
+            }
+            RecordFormat::Lines {
+                canonical,
+                fixed_point,
+            } => write_line(&mut out, value, *canonical, *fixed_point)
+                .expect("writing to memory doesn't fail"),
+            RecordFormat::Csv(csv) => csv
+                .write_record(&mut out, value)
+                .expect("writing to memory doesn't fail"),
+        }
+        out
+    }
+}
+
+/// Produces records and writes them under `limits`, into numbered files in `out_dir`, or to
+/// stdout if there is none, compressed with `compress`: `n` of them, as for `--format ndjson`, or as many as fit within the
+/// total size of the limits if it has one.
+fn write_split(
+    schema: &SchemaState,
+    n: usize,
+    options: &drivel::ProduceOptions,
+    format: RecordFormat,
+    limits: drivel::SplitLimits,
+    out_dir: Option<&Path>,
+    compress: Option<drivel::Compression>,
+) -> Result<drivel::SplitSummary, DrivelError> {
+    let framing = format.framing();
+    let extension = format.extension();
+    let mut writer = match out_dir {
+        Some(dir) => drivel::SplitWriter::files(dir, "part", extension, compress, framing, limits)?,
+        None => drivel::SplitWriter::new(produced_output(compress), framing, limits),
+    };
+    let mut error = None;
+    let mut write = |value: Value| {
+        if error.is_some() {
+            return false;
         }
+        writer
+            .write_record(&format.serialize(&value))
+            .unwrap_or_else(|err| {
+                error = Some(err);
+                false
+            })
+    };
+    let result = if limits.total_bytes.is_some() {
+        drivel::try_produce_while(schema, options, write)
+    } else {
+        drivel::try_produce_each(schema, n, options, |value| {
+            write(value);
+        })
     };
+    if let Some(err) = error {
+        return Err(err.into());
+    }
+    let (_, summary) = writer.finish()?;
+    result?;
+    Ok(summary)
+}
 
-    match &args.mode {
-        Mode::Produce { n_repeat } => {
-            let n_repeat = n_repeat.unwrap_or(1);
-            let schema = match schema {
-                SchemaState::Array { .. } => schema,
-                _ => {
-                    // if the user wants to repeat the data more than once and we aren't dealing
-                    // with an array at the root, then we wrap the state in an array before we
-                    // produce our values
-                    if n_repeat > 1 {
-                        SchemaState::Array {
-                            min_length: 1,
-                            max_length: 1,
-                            schema: Box::new(schema),
-                        }
-                    } else {
-                        schema
-                    }
-                }
-            };
+/// The output of produced data: stdout, compressed with `--compress`.
+fn produced_output(compress: Option<drivel::Compression>) -> Box<dyn Write> {
+    let Some(compression) = compress else {
+        return Box::new(std::io::BufWriter::new(std::io::stdout().lock()));
+    };
+    match compression.compress(std::io::stdout()) {
+        Ok(out) => Box::new(std::io::BufWriter::new(out)),
+        Err(err) => {
+            eprintln!("Unable to compress output. Error: {}", err);
+            std::process::exit(exit_code(err))
+        }
+    }
+}
+
+/// Writes a value as a line of compact JSON, which is canonical JSON if `canonical` is set, or
+/// has floats in fixed-point notation where feasible if `fixed_point` is set.
+fn write_line(
+    out: &mut impl Write,
+    value: &Value,
+    canonical: bool,
+    fixed_point: bool,
+) -> std::io::Result<()> {
+    if canonical {
+        // which already ends in a line feed
+        return out.write_all(drivel::to_canonical_json(value, None).as_bytes());
+    }
+    if fixed_point {
+        let formatter = drivel::FixedPointFormatter::new(serde_json::ser::CompactFormatter);
+        value.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut *out, formatter,
+        ))?;
+    } else {
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    out.write_all(b"\n")
+}
+
+/// Exits after output couldn't be written, quietly if whatever reads it has stopped reading, such
+/// as `head`.
+fn exit_on_write_error(err: std::io::Error) -> ! {
+    if err.kind() == std::io::ErrorKind::BrokenPipe {
+        std::process::exit(0);
+    }
+    eprintln!("Error writing output: {}", err);
+    std::process::exit(exit_code(err))
+}
 
-            let result = drivel::produce(&schema, n_repeat);
-            let stdout = std::io::stdout();
-            serde_json::to_writer_pretty(stdout, &result).unwrap();
+/// Writes a description to a file, or to stderr if there is none, so that it can be emitted
+/// alongside produced data on stdout.
+fn write_description(description: Description, yaml: bool, path: Option<&PathBuf>) {
+    let description = match description {
+        Description::Text(text) => text,
+        Description::Document(document) if yaml => drivel::to_yaml_string(&document),
+        Description::Document(document) => serde_json::to_string_pretty(&document).unwrap(),
+    };
+    match path {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, description + "\n") {
+                eprintln!(
+                    "Unable to write description to {}. Error: {}",
+                    path.display(),
+                    err
+                );
+                std::process::exit(exit_code(err))
+            }
         }
-        Mode::Describe { json_schema } => {
-            if *json_schema {
-                let json_schema = schema.to_json_schema_document();
-                let stdout = std::io::stdout();
-                serde_json::to_writer_pretty(stdout, &json_schema).unwrap();
-            } else {
-                println!("{}", schema.to_string_pretty());
+        None => eprintln!("{}", description),
+    }
+}
+
+fn load_saved_schema(path: &PathBuf) -> SchemaState {
+    let saved = match std::fs::read_to_string(path) {
+        Ok(saved) => saved,
+        Err(err) => {
+            eprintln!(
+                "Unable to read schema from {}. Error: {}",
+                path.display(),
+                err
+            );
+            std::process::exit(exit_code(err))
+        }
+    };
+    match drivel::load_schema(&saved) {
+        Ok(schema) => schema,
+        Err(err) => {
+            eprintln!("Error loading schema from {}: {}", path.display(), err);
+            std::process::exit(exit_code(err))
+        }
+    }
+}
+
+fn save_schema_file(path: &PathBuf, schema: &SchemaState) {
+    if let Err(err) = std::fs::write(path, drivel::save_schema(schema)) {
+        eprintln!(
+            "Unable to save schema to {}. Error: {}",
+            path.display(),
+            err
+        );
+        std::process::exit(exit_code(err))
+    }
+}
+
+fn load_produce_config(path: &PathBuf) -> drivel::ProduceOptions {
+    let config = match std::fs::read_to_string(path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!(
+                "Unable to read produce config from {}. Error: {}",
+                path.display(),
+                err
+            );
+            std::process::exit(exit_code(err))
+        }
+    };
+    let options = drivel::parse_document(&config)
+        .and_then(|value| serde_json::from_value(value).map_err(DrivelError::from));
+    match options {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!(
+                "Error loading produce config from {}: {}",
+                path.display(),
+                err
+            );
+            std::process::exit(exit_code(err))
+        }
+    }
+}
+
+/// The config file that is read from the current directory, unless another is given with
+/// `--config`.
+const DEFAULT_CONFIG_FILE: &str = "drivel.toml";
+
+/// Parses the command line, along with the defaults of options in the config file, if there is
+/// one. Options given on the command line win over those of the config file.
+fn parse_args() -> Args {
+    let mut argv: Vec<OsString> = std::env::args_os().collect();
+    let mut command = Args::command();
+    command.build();
+    let matches = command.clone().get_matches_from(&argv);
+    let path = match matches.get_one::<PathBuf>("config") {
+        Some(path) => path.clone(),
+        None if !matches.get_flag("no_config") && Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+            PathBuf::from(DEFAULT_CONFIG_FILE)
+        }
+        None => return Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()),
+    };
+    let (options, (fields, name_hints)) =
+        match load_config(&path).and_then(|config| config_args(config, &command, &matches)) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Error loading config from {}: {}", path.display(), err);
+                std::process::exit(exit_code(err))
+            }
+        };
+    // options after `--` would be read as files
+    let end = argv
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(argv.len());
+    argv.splice(end..end, options);
+    let mut args = Args::parse_from(argv);
+    args.config_fields = fields;
+    args.config_name_hints = name_hints;
+    args
+}
+
+fn load_config(path: &Path) -> Result<Value, DrivelError> {
+    drivel::parse_toml(&std::fs::read_to_string(path)?)
+}
+
+/// The tables of a config file besides its options: the rules of its `[fields]` and the hints
+/// of its `[name-hints]`.
+type ConfigTables = (
+    BTreeMap<String, drivel::FieldOverride>,
+    Vec<drivel::NameHint>,
+);
+
+/// The command-line options of the defaults in a config file, for the options that aren't on
+/// the command line already, along with the rules of its `[fields]` and the hints of its
+/// `[name-hints]`. The options of the mode being run may be set in a table of their own, such as
+/// `[produce]`.
+fn config_args(
+    config: Value,
+    command: &clap::Command,
+    matches: &clap::ArgMatches,
+) -> Result<(Vec<OsString>, ConfigTables), DrivelError> {
+    let Value::Object(config) = config else {
+        unreachable!("a TOML document is a table")
+    };
+    let mut options = vec![];
+    let mut fields = BTreeMap::new();
+    let mut name_hints = vec![];
+    for (key, value) in config {
+        if key == "fields" {
+            fields = serde_json::from_value(value)?;
+            continue;
+        }
+        if key == "name-hints" {
+            let hints: BTreeMap<String, String> = serde_json::from_value(value)?;
+            name_hints = hints
+                .into_iter()
+                .map(|(pattern, kind)| {
+                    format!("{}={}", pattern, kind)
+                        .parse()
+                        .map_err(|err| DrivelError::Parse(format!("[name-hints]: {}", err)))
+                })
+                .collect::<Result<_, _>>()?;
+            continue;
+        }
+        let Some(mode) = command.find_subcommand(&key) else {
+            options.extend(option_args(&key, &value, command, Some(matches))?);
+            continue;
+        };
+        let Value::Object(mode_options) = value else {
+            return Err(DrivelError::Parse(format!(
+                "[{}] must be a table of options",
+                key
+            )));
+        };
+        for (key, value) in mode_options {
+            match matches.subcommand() {
+                Some((name, mode_matches)) if name == mode.get_name() => {
+                    options.extend(option_args(&key, &value, mode, Some(mode_matches))?)
+                }
+                // the options of other modes are only checked
+                _ => drop(option_args(&key, &value, mode, None)?),
+            }
+        }
+    }
+    Ok((options, (fields, name_hints)))
+}
+
+/// The command-line arguments of an option of a config file, such as `["--seed", "42"]` for
+/// `seed = 42`, `["--infer-enum"]` for `infer-enum = true`, or the option once for each value of
+/// an array. Options that were given on the command line, as in `matches`, are left out.
+fn option_args(
+    key: &str,
+    value: &Value,
+    command: &clap::Command,
+    matches: Option<&clap::ArgMatches>,
+) -> Result<Vec<OsString>, DrivelError> {
+    let arg = command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(key) && !matches!(key, "config" | "no-config"))
+        .ok_or_else(|| {
+            DrivelError::Parse(format!(
+                "unknown option `{}` of {}",
+                key,
+                command.get_name()
+            ))
+        })?;
+    if matches.is_some_and(|matches| {
+        matches.value_source(arg.get_id().as_str()) == Some(clap::parser::ValueSource::CommandLine)
+    }) {
+        return Ok(vec![]);
+    }
+    let flag = OsString::from(format!("--{}", key));
+    let values = match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    let mut args = vec![];
+    for value in values {
+        match (arg.get_action().takes_values(), value) {
+            (false, Value::Bool(true)) => args.push(flag.clone()),
+            (false, Value::Bool(false)) => {}
+            (false, _) => {
+                return Err(DrivelError::Parse(format!(
+                    "option `{}` is a flag, which is true or false",
+                    key
+                )))
+            }
+            (true, Value::String(value)) => args.extend([flag.clone(), value.into()]),
+            (true, value @ (Value::Number(_) | Value::Bool(_))) => {
+                args.extend([flag.clone(), value.to_string().into()])
+            }
+            (true, _) => {
+                return Err(DrivelError::Parse(format!(
+                    "option `{}` must be a string, a number or a boolean",
+                    key
+                )))
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Opens a file, or stdin if there is none, to read input from, which is decompressed as it is
+/// read if it starts as gzip or zstd data does, or if `--decompress` is given.
+fn open_input(path: Option<&PathBuf>, args: &Args) -> Box<dyn BufRead> {
+    let unreadable = |err: std::io::Error| -> ! {
+        match path {
+            Some(path) => eprintln!("Unable to read from {}. Error: {}", path.display(), err),
+            None => eprintln!("Unable to read from stdin. Error: {}", err),
+        }
+        std::process::exit(exit_code(err))
+    };
+    let mut reader: Box<dyn BufRead + Send> = match path {
+        Some(path) => Box::new(BufReader::new(
+            std::fs::File::open(path).unwrap_or_else(|err| unreadable(err)),
+        )),
+        None => Box::new(BufReader::new(std::io::stdin())),
+    };
+    let compression = args.decompress.or_else(|| {
+        drivel::Compression::detect(reader.fill_buf().unwrap_or_else(|err| unreadable(err)))
+    });
+    match compression {
+        Some(compression) => Box::new(
+            compression
+                .decompress(reader)
+                .unwrap_or_else(|err| unreadable(err)),
+        ),
+        None => reader,
+    }
+}
+
+/// How many redirects a request of a `--url` follows before it fails.
+#[cfg(feature = "fetch")]
+const MAX_REDIRECTS: u32 = 10;
+
+/// Makes a GET request of a URL, following redirects, returning the body of the response, or
+/// why the request failed, such as the status of a response that isn't a success. Credentials
+/// are only sent on to redirects to the same host.
+#[cfg(feature = "fetch")]
+fn fetch(url: &str, args: &Args) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .max_redirects(MAX_REDIRECTS)
+        .redirect_auth_headers(ureq::config::RedirectAuthHeaders::SameHost)
+        .build()
+        .into();
+    let mut request = agent.get(url);
+    for header in &args.header {
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(format!(
+                "the header '{}' isn't of the form 'Name: value'",
+                header
+            ));
+        };
+        request = request.header(name.trim(), value.trim());
+    }
+    if let Some(user) = &args.user {
+        let credentials = base64::engine::general_purpose::STANDARD.encode(user);
+        request = request.header("Authorization", format!("Basic {}", credentials));
+    }
+    let response = request.call().map_err(|err| match err {
+        ureq::Error::TooManyRedirects => {
+            format!("it redirected more than {} times", MAX_REDIRECTS)
+        }
+        ureq::Error::RedirectFailed => {
+            "it redirected to a location that couldn't be followed".to_string()
+        }
+        err => err.to_string(),
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("the server responded with {}", status));
+    }
+    let mut body = vec![];
+    response
+        .into_body()
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|err| err.to_string())?;
+    Ok(body)
+}
+
+#[cfg(not(feature = "fetch"))]
+fn fetch(_url: &str, _args: &Args) -> Result<Vec<u8>, String> {
+    Err("fetching URLs needs the `fetch` feature, which drivel was built without".to_string())
+}
+
+/// The URLs requested for the `--url`s, each of them for each of the `--pages`, along with
+/// their decoded responses. The pages of a URL stop at the first one that is an empty array, as
+/// after the last page of an API.
+fn fetch_urls(args: &Args) -> Vec<(String, String)> {
+    let mut responses = vec![];
+    for url in &args.url {
+        for page in 1..=args.pages.unwrap_or(1) {
+            let page_url = url.replace("{page}", &page.to_string());
+            let body = fetch(&page_url, args).unwrap_or_else(|err| {
+                eprintln!("Unable to fetch {}. Error: {}", page_url, err);
+                std::process::exit(74)
+            });
+            let body = match drivel::decode_input(&body, args.encoding) {
+                Ok(body) => body,
+                Err(err) => {
+                    eprintln!(
+                        "Unable to decode the response of {}. Error: {}",
+                        page_url, err
+                    );
+                    std::process::exit(exit_code(err))
+                }
+            };
+            if serde_json::from_str::<Vec<Value>>(&body).is_ok_and(|page| page.is_empty()) {
+                break;
+            }
+            responses.push((page_url, body));
+        }
+    }
+    responses
+}
+
+/// An input that has been read: text, decoded from its encoding, or the bytes of a binary
+/// format, such as Parquet.
+#[derive(Clone)]
+enum Input {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Input {
+    fn len(&self) -> usize {
+        match self {
+            Input::Text(text) => text.len(),
+            Input::Binary(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// Reads and decodes the input from a file, or from stdin if there is none.
+fn read_input(path: Option<&PathBuf>, args: &Args) -> String {
+    read_rest(path, vec![], open_input(path, args), args)
+}
+
+/// Reads the input from a file, or from stdin if there is none, decoding it unless it is of a
+/// binary format.
+fn read_data(path: Option<&PathBuf>, args: &Args) -> Input {
+    read_rest_of(path, vec![], open_input(path, args), args)
+}
+
+/// Reads the rest of an input, whose first `bytes` have already been read, decoding all of it
+/// unless it is of a binary format.
+fn read_rest_of(
+    path: Option<&PathBuf>,
+    bytes: Vec<u8>,
+    reader: Box<dyn BufRead>,
+    args: &Args,
+) -> Input {
+    if input_format(path, args).is_binary() {
+        Input::Binary(read_bytes(path, bytes, reader))
+    } else {
+        Input::Text(read_rest(path, bytes, reader, args))
+    }
+}
+
+/// Reads the rest of an input, whose first `bytes` have already been read.
+fn read_bytes(path: Option<&PathBuf>, mut bytes: Vec<u8>, mut reader: Box<dyn BufRead>) -> Vec<u8> {
+    if let Err(err) = reader.read_to_end(&mut bytes) {
+        match path {
+            Some(path) => eprintln!("Unable to read from {}. Error: {}", path.display(), err),
+            None => eprintln!("Unable to read from stdin. Error: {}", err),
+        }
+        std::process::exit(exit_code(err))
+    }
+    bytes
+}
+
+/// Reads the rest of an input, whose first `bytes` have already been read, and decodes all of
+/// it.
+fn read_rest(
+    path: Option<&PathBuf>,
+    bytes: Vec<u8>,
+    reader: Box<dyn BufRead>,
+    args: &Args,
+) -> String {
+    let bytes = read_bytes(path, bytes, reader);
+    match drivel::decode_input(&bytes, args.encoding) {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Unable to decode input. Error: {}", err);
+            std::process::exit(exit_code(err))
+        }
+    }
+}
+
+/// Expands a path whose file name is a pattern, with `*` for any characters and `?` for any
+/// one, to the files that match it, in the order of their names, for shells that don't expand
+/// patterns themselves. As in shells, `*` and `?` don't match a leading `.`. A directory is
+/// expanded to the files in it, as if it were followed by `/*`.
+fn expand_pattern(path: &Path) -> Vec<PathBuf> {
+    let matches = pattern_matches(path);
+    if matches.is_empty() {
+        eprintln!(
+            "Unable to read from {}. Error: no files match it",
+            path.display()
+        );
+        std::process::exit(exit_code(std::io::Error::from(
+            std::io::ErrorKind::NotFound,
+        )));
+    }
+    matches
+}
+
+/// The files that a path expands to, as for [`expand_pattern`], which are none if it is a
+/// pattern that no file matches or an empty directory.
+fn pattern_matches(path: &Path) -> Vec<PathBuf> {
+    if path.is_dir() {
+        return pattern_matches(&path.join("*"));
+    }
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if path.exists() || !name.contains(['*', '?']) {
+        return vec![path.to_path_buf()];
+    }
+    let pattern = name
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect::<String>();
+    let pattern = regex::Regex::new(&format!("^{}$", pattern)).expect("an escaped pattern");
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file| !file.starts_with('.') && pattern.is_match(file))
+        .map(|file| path.with_file_name(file))
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Set in the environment of the runs of `describe` started by `--watch`, so that they don't
+/// watch as well when `watch` is set in a config file.
+const WATCHED_ENV: &str = "DRIVEL_WATCHED";
+
+/// How often `--watch` checks whether the input has changed.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The files that `--watch` watches, with when they were last modified and their sizes, which
+/// change when a file is written to, or when a file is added to or removed from a directory or
+/// the files matching a pattern.
+fn watched_files(paths: &[PathBuf]) -> Vec<(PathBuf, Option<std::time::SystemTime>, u64)> {
+    paths
+        .iter()
+        .flat_map(|path| pattern_matches(path))
+        .map(|path| {
+            let metadata = std::fs::metadata(&path).ok();
+            let modified = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.modified().ok());
+            let len = metadata.map_or(0, |metadata| metadata.len());
+            (path, modified, len)
+        })
+        .collect()
+}
+
+/// Describes the schema of the input files each time they change, by running drivel again
+/// without `--watch`, so that input that can't be parsed while it is being edited is reported
+/// without ending the watch. After the first description, the changes from the schema that was
+/// described before are written to stderr.
+fn watch(args: &Args) -> ! {
+    if args.schema.is_some() || args.sig.is_some() || args.from_schema {
+        eprintln!("Error: --watch infers the schema from input files, not --schema or --sig");
+        std::process::exit(2);
+    }
+    let paths: Vec<PathBuf> = args
+        .input
+        .iter()
+        .chain(input_files(args))
+        .cloned()
+        .collect();
+    if paths.is_empty()
+        || paths.iter().any(|path| path.to_str() == Some("-"))
+        || !args.url.is_empty()
+    {
+        eprintln!(
+            "Error: --watch watches files, given as FILES or with --input, not stdin or --url"
+        );
+        std::process::exit(2);
+    }
+    let exe = std::env::current_exe().unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to run drivel again to watch the input. Error: {}",
+            err
+        );
+        std::process::exit(exit_code(err))
+    });
+    let Mode::Describe { save, .. } = &args.mode else {
+        unreachable!("only describe watches its input")
+    };
+    let saved = save.clone().unwrap_or_else(|| {
+        std::env::temp_dir().join(format!("drivel-watch-{}.json", std::process::id()))
+    });
+    let mut argv: Vec<OsString> = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--watch")
+        .collect();
+    if save.is_none() {
+        // before any `--`, after which options would be read as files
+        let end = argv
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(argv.len());
+        argv.splice(end..end, [OsString::from("--save"), saved.clone().into()]);
+    }
+    let mut previous: Option<SchemaState> = None;
+    let mut seen = None;
+    loop {
+        let files = watched_files(&paths);
+        if seen.as_ref() != Some(&files) {
+            if seen.is_some() {
+                drivel::info("The input changed; describing it again");
+            }
+            seen = Some(files);
+            let status = std::process::Command::new(&exe)
+                .args(&argv)
+                .env(WATCHED_ENV, "1")
+                .status();
+            match status {
+                Ok(status) if status.success() => {
+                    let schema = std::fs::read_to_string(&saved)
+                        .ok()
+                        .and_then(|saved| drivel::load_schema(&saved).ok());
+                    if save.is_none() {
+                        let _ = std::fs::remove_file(&saved);
+                    }
+                    if let (Some(previous), Some(schema)) = (&previous, &schema) {
+                        report_changes(previous, schema);
+                    }
+                    previous = schema.or(previous);
+                }
+                Ok(_) => drivel::info("Waiting for the input to change"),
+                Err(err) => {
+                    eprintln!(
+                        "Unable to run drivel again to watch the input. Error: {}",
+                        err
+                    );
+                    std::process::exit(exit_code(err))
+                }
+            }
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// Writes the changes between the schema described before by `--watch` and the new one to
+/// stderr, as `diff` does.
+fn report_changes(previous: &SchemaState, schema: &SchemaState) {
+    let changes = previous.diff(schema);
+    if changes.is_empty() {
+        drivel::info("The schema is unchanged");
+        return;
+    }
+    drivel::info(format!("{} change(s) from the last schema:", changes.len()));
+    for change in &changes {
+        if change.breaking {
+            drivel::info(format!("  {} (breaking)", change));
+        } else {
+            drivel::info(format!("  {}", change));
+        }
+    }
+}
+
+/// Writes each input with its values replaced by synthetic ones, by the schema given with
+/// `--schema` or `--sig`, or otherwise inferred from all of the inputs. Documents are written as
+/// JSON or YAML, and the records of line-based input as JSON lines.
+fn anonymize(args: &Args, compact: bool, seed: Option<u64>) {
+    if args.from_schema || args.tabular_array {
+        eprintln!("Error: anonymize reads data, not a JSON Schema or tabular JSON");
+        std::process::exit(1);
+    }
+    let inputs: Vec<(drivel::InputFormat, Input)> = input_paths(args)
+        .iter()
+        .map(|path| {
+            (
+                input_format(path.as_ref(), args),
+                read_data(path.as_ref(), args),
+            )
+        })
+        .chain(
+            fetch_urls(args)
+                .into_iter()
+                .map(|(_, body)| (drivel::InputFormat::Json, Input::Text(body))),
+        )
+        .collect();
+    let schema = match (&args.schema, &args.sig) {
+        (Some(path), _) => load_saved_schema(path),
+        (None, Some(schema)) => schema.clone(),
+        (None, None) => {
+            let opts = inference_options(args);
+            with_hints(
+                infer_inputs(inputs.clone(), args, &opts, &mut Collected::default()),
+                args,
+            )
+        }
+    };
+
+    let mut anonymizer = drivel::Anonymizer::new(&schema, seed);
+    let write_document = |document: &Value| {
+        write_json(document, args.yaml, (!compact).then_some(2), false);
+        if !args.yaml {
+            // which YAML already ends in, and separates documents with
+            println!();
+        }
+    };
+    for (format, input) in &inputs {
+        match parse_input(input, *format, args) {
+            // the elements of an array are records, unless the schema is of the whole array
+            drivel::ParsedInput::Document(Value::Array(values))
+                if !matches!(schema, SchemaState::Array { .. }) =>
+            {
+                let values = values.iter().map(|value| anonymizer.anonymize(value));
+                write_document(&Value::Array(values.collect()));
+            }
+            drivel::ParsedInput::Document(value) => {
+                write_document(&anonymizer.anonymize(&value));
+            }
+            drivel::ParsedInput::Records { values, .. } => {
+                let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+                for value in &values {
+                    write_line(&mut out, &anonymizer.anonymize(value), false, false)
+                        .unwrap_or_else(|err| exit_on_write_error(err));
+                }
+                out.flush().unwrap_or_else(|err| exit_on_write_error(err));
+            }
+        }
+    }
+}
+
+/// The paths of files to read input from, with their patterns expanded, where `None` is stdin,
+/// given as `-`.
+fn expand_paths<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<Option<PathBuf>> {
+    paths
+        .into_iter()
+        .flat_map(|path| match path.to_str() {
+            Some("-") => vec![None],
+            _ => expand_pattern(path).into_iter().map(Some).collect(),
+        })
+        .collect()
+}
+
+/// The files given as arguments of `describe`, `produce` or `anonymize`.
+fn input_files(args: &Args) -> &[PathBuf] {
+    match &args.mode {
+        Mode::Describe { files, .. }
+        | Mode::Produce { files, .. }
+        | Mode::Serve { files, .. }
+        | Mode::Anonymize { files, .. } => files,
+        Mode::Validate { .. } | Mode::Merge { .. } | Mode::Diff { .. } => &[],
+    }
+}
+
+/// The paths of the `--input` files and the files given as arguments, or `None` for stdin if
+/// there are none and no `--url`s either.
+fn input_paths(args: &Args) -> Vec<Option<PathBuf>> {
+    let paths = expand_paths(args.input.iter().chain(input_files(args)));
+    if paths.is_empty() && args.url.is_empty() {
+        vec![None]
+    } else {
+        paths
+    }
+}
+
+/// The options for reading input that are given on the command line.
+fn read_options(args: &Args) -> drivel::ReadOptions {
+    drivel::ReadOptions {
+        encoding: args.encoding,
+        allow_non_finite: args.allow_nonfinite,
+        numbers_as_strings: args.numbers_as_strings,
+        on_duplicate_keys: args.on_duplicate_keys.unwrap_or_default(),
+        skip_errors: args.skip_errors,
+        max_errors: args.max_errors,
+        // the records sampled at a rate are only known once they have been read
+        max_values: head_samples(args).filter(|_| args.sample_rate.is_none()),
+        xml: {
+            let defaults = drivel::XmlOptions::default();
+            drivel::XmlOptions {
+                attribute_prefix: match &args.xml_attribute_prefix {
+                    _ if args.xml_ignore_attributes => None,
+                    Some(prefix) => Some(prefix.clone()),
+                    None => defaults.attribute_prefix,
+                },
+                text_key: args.xml_text_key.clone().unwrap_or(defaults.text_key),
+            }
+        },
+    }
+}
+
+/// Reports the lines that were skipped with `--skip-errors`, or exits if input couldn't be
+/// parsed.
+fn check_parsed<T>(parsed: Result<T, DrivelError>, counts: impl Fn(&T) -> drivel::LineCounts) -> T {
+    match parsed {
+        Ok(parsed) => {
+            let counts = counts(&parsed);
+            if counts.skipped > 0 {
+                drivel::warn(format!(
+                    "Skipped {} of {} lines that could not be parsed, the first being {}",
+                    counts.skipped,
+                    counts.lines,
+                    counts.first_error.unwrap_or_default()
+                ));
+            }
+            parsed
+        }
+        Err(DrivelError::Parse(err)) => {
+            eprintln!(
+                "Error parsing input; are you sure it is valid JSON or YAML? Error: {}",
+                err
+            );
+            std::process::exit(exit_code(DrivelError::Parse(err)));
+        }
+        Err(err) => {
+            eprintln!("Unable to read input. Error: {}", err);
+            std::process::exit(exit_code(err));
+        }
+    }
+}
+
+/// The format of an input from a file, or stdin if there is none: that of `--input-format`, or
+/// of the extension of the file.
+fn input_format(path: Option<&PathBuf>, args: &Args) -> drivel::InputFormat {
+    args.input_format.unwrap_or_else(|| {
+        path.map_or_else(Default::default, |path| drivel::InputFormat::of_path(path))
+    })
+}
+
+/// Parses input as a single JSON or YAML document, or as a stream of YAML documents that are
+/// each a record, or failing that, as one document per line, or as a TOML document, unless it
+/// is XML or TOML, which are a single document, or of a delimited format, in which case each
+/// row is a record. With `--select`, only the values at its path are kept, and input without
+/// any is an error.
+fn parse_input(input: &Input, format: drivel::InputFormat, args: &Args) -> drivel::ParsedInput {
+    let parsed = parse_all_input(input, format, args);
+    match &args.select {
+        Some(selector) => selector.select_input(parsed).unwrap_or_else(|| {
+            eprintln!("Error: nothing in the input is at --select '{}'", selector);
+            std::process::exit(1)
+        }),
+        None => parsed,
+    }
+}
+
+/// Parses input as for [`parse_input`], but all of it, whatever `--select` selects.
+fn parse_all_input(input: &Input, format: drivel::InputFormat, args: &Args) -> drivel::ParsedInput {
+    let what = match format {
+        drivel::InputFormat::Json => None,
+        drivel::InputFormat::Csv | drivel::InputFormat::Tsv => Some("delimited values"),
+        drivel::InputFormat::Xml => Some("XML"),
+        drivel::InputFormat::Toml => Some("TOML"),
+        drivel::InputFormat::Parquet => Some("Parquet"),
+        drivel::InputFormat::Arrow => Some("Arrow"),
+    };
+    if let Some(what) = what {
+        let parsed = match input {
+            Input::Text(input) => format.parse(input, &read_options(args)),
+            Input::Binary(bytes) => format.parse_bytes(bytes, &read_options(args)),
+        };
+        return match parsed {
+            Ok(parsed) => parsed,
+            Err(DrivelError::Parse(err)) => {
+                eprintln!("Error parsing input as {}: {}", what, err);
+                std::process::exit(exit_code(DrivelError::Parse(err)));
+            }
+            Err(err) => {
+                eprintln!("Unable to read input. Error: {}", err);
+                std::process::exit(exit_code(err));
+            }
+        };
+    }
+    let input = match input {
+        Input::Text(input) => input,
+        Input::Binary(_) => unreachable!("only binary formats are read as bytes"),
+    };
+    let progress = new_progress(args);
+    let parsed = format.parse_with(input, &read_options(args), |_| {
+        if let Some(progress) = &progress {
+            progress.tick();
+        }
+    });
+    let parsed = check_parsed(parsed, |parsed| match parsed {
+        drivel::ParsedInput::Records { counts, .. } => counts.clone(),
+        drivel::ParsedInput::Document(_) => drivel::LineCounts::default(),
+    });
+    if let (Some(progress), drivel::ParsedInput::Records { counts, .. }) = (&progress, &parsed) {
+        if counts.lines > 0 {
+            progress.finish();
+        }
+    }
+    parsed
+}
+
+/// The number of records to infer from if they are sampled from the head of the input, in which
+/// case reading can stop once that many have been read.
+fn head_samples(args: &Args) -> Option<usize> {
+    args.max_samples
+        .filter(|_| args.sample_strategy.unwrap_or_default() == SampleStrategy::Head)
+}
+
+/// Whether the records to infer from are sampled, with `--max-samples` or `--sample-rate`.
+fn is_sampled(args: &Args) -> bool {
+    args.max_samples.is_some() || args.sample_rate.is_some()
+}
+
+/// A sampler of the records to infer from at `--sample-rate`, if it is given.
+fn sample_at_rate(args: &Args) -> Option<drivel::RateSampler> {
+    args.sample_rate
+        .map(|rate| drivel::RateSampler::new(rate, args.sample_seed))
+}
+
+/// A sampler of `max_samples` records, seeded with `--sample-seed`.
+fn sampler<T>(strategy: SampleStrategy, max_samples: usize, args: &Args) -> Sampler<T> {
+    let sampler = Sampler::new(strategy, max_samples);
+    match args.sample_seed {
+        Some(seed) => sampler.with_seed(seed),
+        None => sampler,
+    }
+}
+
+/// Samples the records to infer from with `--sample-rate` and then `--max-samples`, or keeps all
+/// of them, noting how many were sampled in `collected`.
+fn sample(records: Vec<Value>, args: &Args, collected: &mut Collected) -> Vec<Value> {
+    if !is_sampled(args) {
+        return records;
+    }
+    let read = records.len();
+    let mut rate = sample_at_rate(args);
+    let mut sampler = sampler(
+        args.sample_strategy.unwrap_or_default(),
+        args.max_samples.unwrap_or(usize::MAX),
+        args,
+    );
+    for record in records {
+        if rate.as_mut().is_some_and(|rate| !rate.keep()) {
+            continue;
+        }
+        if !sampler.offer(record) {
+            break;
+        }
+    }
+    let samples = sampler.into_samples();
+    collected.sample = Some(drivel::SampleCounts {
+        sampled: samples.len(),
+        read,
+    });
+    samples
+}
+
+fn new_progress(args: &Args) -> Option<drivel::Progress> {
+    args.progress
+        .then(|| drivel::Progress::new(Duration::from_millis(500)))
+}
+
+/// What is collected from the input values, besides their schema, for the output.
+#[derive(Default)]
+struct Collected {
+    stats: Option<drivel::FieldStats>,
+    value_counts: Option<drivel::ValueCounts>,
+    /// The distributions of the numbers of the input, with `--track-distributions`.
+    number_stats: Option<drivel::NumberStats>,
+    report: Option<drivel::InferenceReport>,
+    /// The columns of the header of tabular input, with `--tabular-array`.
+    columns: Option<Vec<String>>,
+    /// What inference took, with `--stats`.
+    summary: Option<InputSummary>,
+    /// How many of the records read were sampled, with `--max-samples` or `--sample-rate`.
+    sample: Option<drivel::SampleCounts>,
+}
+
+/// How much input inference read, and what it took, to be written to stderr with `--stats`.
+struct InputSummary {
+    started: Instant,
+    records: usize,
+    bytes: u64,
+    /// The most nodes the schema had while it was being inferred.
+    peak_nodes: usize,
+}
+
+impl InputSummary {
+    fn new() -> Self {
+        InputSummary {
+            started: Instant::now(),
+            records: 0,
+            bytes: 0,
+            peak_nodes: 0,
+        }
+    }
+
+    /// Writes the summary, once `schema` has been inferred.
+    fn report(&self, schema: &SchemaState) {
+        drivel::info(format!(
+            "Read {} records ({} bytes) in {:.2}s; the schema had at most {} nodes",
+            self.records,
+            self.bytes,
+            self.started.elapsed().as_secs_f64(),
+            self.peak_nodes.max(schema.node_count())
+        ));
+    }
+}
+
+/// A reader that counts the bytes read through it, for `--stats`.
+struct CountingReader {
+    inner: Box<dyn BufRead>,
+    bytes: Rc<Cell<u64>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.set(self.bytes.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl BufRead for CountingReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.bytes.set(self.bytes.get() + amt as u64);
+        self.inner.consume(amt)
+    }
+}
+
+impl Collected {
+    /// Adds a value that is observed by inference, at the root of the data.
+    fn add(&mut self, value: &Value) {
+        if let Some(stats) = &mut self.stats {
+            stats.add(value);
+        }
+        if let Some(value_counts) = &mut self.value_counts {
+            value_counts.add(value);
+        }
+        if let Some(number_stats) = &mut self.number_stats {
+            number_stats.add(value);
+        }
+        if let Some(report) = &mut self.report {
+            report.add(value);
+        }
+    }
+}
+
+/// The number of records of line-based input that are read before their schemas are inferred,
+/// in parallel, so that only so many are held in memory at once.
+const STREAM_BATCH_SIZE: usize = 64 * 1024;
+
+/// Infers the schema of line-based input, reading one line at a time and observing the lines in
+/// batches. With `--progress`, the number of records processed is written, along with the
+/// schema inferred so far if `snapshots` is set.
+fn stream_schema(
+    readers: Vec<Box<dyn BufRead>>,
+    args: &Args,
+    opts: &drivel::InferenceOptions,
+    collected: &mut Collected,
+    snapshots: bool,
+) -> SchemaState {
+    let progress = new_progress(args);
+    let head = head_samples(args);
+    // a reservoir holds the sampled records until the whole input has been read
+    let mut reservoir = args
+        .max_samples
+        .filter(|_| head.is_none())
+        .map(|max_samples| sampler(SampleStrategy::Reservoir, max_samples, args));
+    let mut rate = sample_at_rate(args);
+    let mut read = 0;
+    let observed = Cell::new(0);
+    let bytes = Rc::new(Cell::new(0));
+    let mut inferer = drivel::SchemaInferer::new(opts);
+    // records are observed in batches, whose chunks are inferred in parallel
+    let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+    let mut observe = |value: Value| {
+        if let Some(summary) = &mut collected.summary {
+            summary.records += 1;
+        }
+        read += 1;
+        if rate.as_mut().is_some_and(|rate| !rate.keep()) {
+            return true;
+        }
+        if let Some(reservoir) = &mut reservoir {
+            if let Some(progress) = &progress {
+                progress.tick();
+            }
+            return reservoir.offer(value);
+        }
+        collected.add(&value);
+        batch.push(value);
+        if batch.len() == STREAM_BATCH_SIZE {
+            inferer.observe_all(&batch);
+            batch.clear();
+            if let Some(summary) = &mut collected.summary {
+                summary.peak_nodes = summary.peak_nodes.max(inferer.node_count());
+            }
+        }
+        if let Some(progress) = &progress {
+            if progress.tick() && snapshots {
+                inferer.observe_all(&batch);
+                batch.clear();
+                drivel::info(format!(
+                    "Schema so far:\n{}",
+                    inferer.snapshot().to_string_pretty()
+                ));
+            }
+        }
+        observed.set(observed.get() + 1);
+        head.is_none_or(|max| observed.get() < max)
+    };
+    for reader in readers {
+        if head.is_some_and(|max| observed.get() >= max) {
+            break;
+        }
+        let reader = CountingReader {
+            inner: reader,
+            bytes: bytes.clone(),
+        };
+        let parsed = drivel::read_lines(reader, &read_options(args), |value| match &args.select {
+            // records without the path are left out, as for input that is read all at once
+            Some(selector) => selector.select(value).into_iter().all(&mut observe),
+            None => observe(value),
+        });
+        check_parsed(parsed, |counts| counts.clone());
+    }
+    inferer.observe_all(&batch);
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    let samples = reservoir.map(Sampler::into_samples).unwrap_or_default();
+    if is_sampled(args) {
+        collected.sample = Some(drivel::SampleCounts {
+            sampled: observed.get() + samples.len(),
+            read,
+        });
+    }
+    samples.iter().for_each(|value| collected.add(value));
+    inferer.observe_all(&samples);
+    if let Some(summary) = &mut collected.summary {
+        summary.bytes = bytes.get();
+        summary.peak_nodes = summary.peak_nodes.max(inferer.node_count());
+    }
+    inferer.finish()
+}
+
+/// Infers the schema of the input, read from stdin or the `--input` files. If all of them are
+/// line-based, such as JSON lines, they are read one line at a time rather than all at once, so
+/// that they needn't fit in memory.
+fn infer_input(
+    args: &Args,
+    opts: &drivel::InferenceOptions,
+    collected: &mut Collected,
+) -> SchemaState {
+    let mut inputs: Vec<_> = input_paths(args)
+        .into_iter()
+        .map(|path| {
+            let reader = open_input(path.as_ref(), args);
+            (path, vec![], reader)
+        })
+        .collect();
+    // the responses of URLs are documents rather than lines
+    let line_based = args.url.is_empty()
+        && !args.tabular_array
+        && inputs.iter_mut().all(|(path, start, reader)| {
+            if input_format(path.as_ref(), args) != drivel::InputFormat::Json {
+                return false;
+            }
+            match drivel::sniff_line_based(reader, &read_options(args)) {
+                Ok((bytes, line_based)) => {
+                    *start = bytes;
+                    line_based
+                }
+                Err(err) => {
+                    match path {
+                        Some(path) => {
+                            eprintln!("Unable to read from {}. Error: {}", path.display(), err)
+                        }
+                        None => eprintln!("Unable to read from stdin. Error: {}", err),
+                    }
+                    std::process::exit(exit_code(err))
+                }
+            }
+        });
+    if line_based {
+        let readers = inputs
+            .into_iter()
+            .map(|(_, start, reader)| {
+                Box::new(std::io::Cursor::new(start).chain(reader)) as Box<dyn BufRead>
+            })
+            .collect();
+        stream_schema(readers, args, opts, collected, false)
+    } else {
+        let inputs = inputs
+            .into_iter()
+            .map(|(path, start, reader)| {
+                let format = input_format(path.as_ref(), args);
+                (format, read_rest_of(path.as_ref(), start, reader, args))
+            })
+            .chain(
+                fetch_urls(args)
+                    .into_iter()
+                    .map(|(_, body)| (drivel::InputFormat::Json, Input::Text(body))),
+            )
+            .collect::<Vec<_>>();
+        if let Some(summary) = &mut collected.summary {
+            summary.bytes = inputs.iter().map(|(_, input)| input.len() as u64).sum();
+        }
+        infer_inputs(inputs, args, opts, collected)
+    }
+}
+
+/// Reads input from stdin, or the `--input` files, and infers its schema, along with statistics
+/// about its fields and counts of its values if they are needed for the output.
+fn read_schema(args: &Args) -> (SchemaState, Collected) {
+    let stream = matches!(args.mode, Mode::Describe { stream: true, .. });
+    if stream && args.from_schema {
+        eprintln!("Error: --stream infers a schema from example data, not from --from-schema");
+        std::process::exit(1);
+    }
+    if stream
+        && input_paths(args)
+            .iter()
+            .any(|path| input_format(path.as_ref(), args) != drivel::InputFormat::Json)
+    {
+        eprintln!(
+            "Error: --stream reads JSON lines, not delimited values, XML, TOML, Parquet or Arrow"
+        );
+        std::process::exit(1);
+    }
+    if stream && !args.url.is_empty() {
+        eprintln!("Error: --stream reads JSON lines from files or stdin, not from --url");
+        std::process::exit(1);
+    }
+    if stream && args.tabular_array {
+        eprintln!("Error: --stream reads JSON lines, not the single document of --tabular-array");
+        std::process::exit(1);
+    }
+
+    if args.from_schema {
+        let inputs: Vec<String> = input_paths(args)
+            .iter()
+            .map(|path| read_input(path.as_ref(), args))
+            .chain(fetch_urls(args).into_iter().map(|(_, body)| body))
+            .collect();
+        let [input] = inputs.as_slice() else {
+            eprintln!("Error: --from-schema reads a single JSON Schema, not several --input files or --urls");
+            std::process::exit(1);
+        };
+        // Parse input as a schema written in JSON or YAML
+        let schema_format = args.schema_format.unwrap_or_default();
+        let json = match drivel::parse_document(input) {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!(
+                    "Error parsing input as JSON or YAML {}: {}",
+                    schema_format, err
+                );
+                std::process::exit(exit_code(err));
+            }
+        };
+
+        match schema_format.parse(&json) {
+            Ok(schema) => (schema, Collected::default()),
+            Err(err) => {
+                eprintln!("Error parsing {}: {}", schema_format, err);
+                std::process::exit(exit_code(err));
+            }
+        }
+    } else {
+        let mut collected = Collected {
+            // produced optional fields are present as often as they were in the input
+            stats: matches!(
+                args.mode,
+                Mode::Describe {
+                    with_stats: true,
+                    ..
+                } | Mode::Describe {
+                    format: Some(DescribeFormat::Json),
+                    ..
+                } | Mode::Produce { .. }
+                    | Mode::Serve { .. }
+            )
+            .then(drivel::FieldStats::default),
+            columns: None,
+            value_counts: match args.mode {
+                Mode::Describe {
+                    value_counts: true,
+                    max_distinct,
+                    ..
+                } => Some(drivel::ValueCounts::new(
+                    max_distinct.unwrap_or(drivel::DEFAULT_MAX_DISTINCT_VALUES),
+                )),
+                _ => None,
+            },
+            number_stats: args.track_distributions.then(drivel::NumberStats::default),
+            report: (args.strict || args.strict_fail).then(drivel::InferenceReport::default),
+            summary: args.stats.then(InputSummary::new),
+            sample: None,
+        };
+
+        let opts = inference_options(args);
+
+        let schema = if stream {
+            if matches!(
+                args.encoding,
+                Some(drivel::InputEncoding::Utf16Le | drivel::InputEncoding::Utf16Be)
+            ) {
+                eprintln!("Error: --stream only reads UTF-8 input");
+                std::process::exit(1);
+            }
+            let readers = input_paths(args)
+                .iter()
+                .map(|path| open_input(path.as_ref(), args))
+                .collect();
+            stream_schema(readers, args, &opts, &mut collected, true)
+        } else {
+            infer_input(args, &opts, &mut collected)
+        };
+        let schema = with_hints(schema, args);
+        if let Some(summary) = &collected.summary {
+            summary.report(&schema);
+        }
+        if let Some(report) = &collected.report {
+            report_warnings(&report.warnings(&schema), args);
+        }
+        (schema, collected)
+    }
+}
+
+/// Applies the hints of `--infer-from-names`, those of the config file before the defaults, and
+/// then those of `--hint`, which win over them, to an inferred schema.
+fn with_hints(schema: SchemaState, args: &Args) -> SchemaState {
+    let schema = if args.infer_from_names {
+        let mut name_hints = args.config_name_hints.clone();
+        name_hints.extend(drivel::NameHint::defaults());
+        schema.with_name_hints(&name_hints)
+    } else {
+        schema
+    };
+    schema.with_hints(&args.hint)
+}
+
+/// The options for inferring a schema from input data that are given on the command line.
+fn inference_options(args: &Args) -> drivel::InferenceOptions {
+    let mut options = drivel::InferenceOptions::default();
+    options.enum_inference = args.into();
+    options.format_inference = args
+        .format_min_n
+        .map(|min_sample_size| drivel::FormatInferenceOptions { min_sample_size });
+    options.max_object_fields = args.max_fields;
+    options.union_objects = args.union_objects;
+    options.union_types = args.union_types;
+    options.merge_threshold = args.merge_threshold;
+    options.discriminator = match &args.discriminator {
+        Some(tag) => Some(drivel::Discriminator::Field(tag.clone())),
+        None => args
+            .infer_discriminator
+            .then_some(drivel::Discriminator::Auto),
+    };
+    options.length_distribution = args.length_dist;
+    options.extended_json = args.mongo_ext;
+    options.coordinates = args.infer_coords;
+    options.tuples = args.infer_tuples;
+    options.financial_identifiers = args.infer_financial;
+    options.barcodes = args.infer_barcodes;
+    options.money = args.infer_money;
+    options.durations = args.infer_durations;
+    options.numeric_strings = args.coerce_numeric_strings;
+    options.non_finite = args.allow_nonfinite;
+    options.date_formats = args.date_format.clone();
+    options.sequences = args.infer_sequence;
+    options.bitflags = args.infer_bitflags;
+    options.maps = args.infer_maps;
+    options.vocabularies = args.vocabulary.iter().map(load_vocabulary).collect();
+    options.max_depth = args.max_depth;
+    options.max_object_keys = args.max_object_keys;
+    options
+}
+
+/// Infers the schema of the inputs, read from stdin or the `--input` files.
+fn infer_inputs(
+    inputs: Vec<(drivel::InputFormat, Input)>,
+    args: &Args,
+    opts: &drivel::InferenceOptions,
+    collected: &mut Collected,
+) -> SchemaState {
+    let mut parsed: Vec<drivel::ParsedInput> = inputs
+        .iter()
+        .map(|(format, input)| parse_input(input, *format, args))
+        .collect();
+    let values = match parsed.pop() {
+        Some(drivel::ParsedInput::Document(json)) if parsed.is_empty() => {
+            let json = if args.tabular_array {
+                let table = drivel::records_from_table(json).unwrap_or_else(|err| {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(exit_code(err));
+                });
+                collected.columns = Some(table.columns);
+                Value::Array(table.records)
+            } else {
+                json
+            };
+            // the elements of an array at the root are records
+            let json = match json {
+                Value::Array(records) => Value::Array(sample(records, args, collected)),
+                json => json,
+            };
+            collected.add(&json);
+            if let Some(summary) = &mut collected.summary {
+                summary.records = json.as_array().map_or(1, Vec::len);
+            }
+            return drivel::infer_schema(json, opts);
+        }
+        _ if args.tabular_array => {
+            eprintln!("Error: --tabular-array reads a single JSON document, not several inputs or JSON lines");
+            std::process::exit(1);
+        }
+        Some(drivel::ParsedInput::Records { values, .. }) if parsed.is_empty() => values,
+        last => {
+            // several inputs are combined by their records, where the lines of line-based
+            // input are records just like the elements of an array
+            let documents = parsed
+                .into_iter()
+                .chain(last)
+                .map(|input| match input {
+                    drivel::ParsedInput::Document(json) => json,
+                    drivel::ParsedInput::Records { values, .. } => Value::Array(values),
+                })
+                .collect();
+            drivel::records_from_documents(documents)
+        }
+    };
+    let values = sample(values, args, collected);
+    values.iter().for_each(|value| collected.add(value));
+    if let Some(summary) = &mut collected.summary {
+        summary.records = values.len();
+    }
+    drivel::infer_schema_from_iter(values, opts)
+}
+
+/// The schema of a source of `merge` or `diff`: a schema saved with `describe --save`, a JSON Schema, or
+/// otherwise the schema inferred from it as data.
+fn source_schema(
+    path: Option<&PathBuf>,
+    args: &Args,
+    opts: &drivel::InferenceOptions,
+) -> SchemaState {
+    let name = path.map_or("stdin".to_string(), |path| path.display().to_string());
+    let input = read_data(path, args);
+    let text = match &input {
+        Input::Text(text) => Some(text.as_str()),
+        Input::Binary(_) => None,
+    };
+    match text.map(drivel::load_schema) {
+        None => {}
+        Some(Ok(schema)) => return schema,
+        // a saved schema, but not one this version of drivel can load
+        Some(Err(err @ drivel::LoadSchemaError::UnsupportedVersion { .. })) => {
+            eprintln!("Error loading schema from {}: {}", name, err);
+            std::process::exit(exit_code(err))
+        }
+        Some(Err(drivel::LoadSchemaError::InvalidFormat(_))) => {}
+    }
+
+    let format = input_format(path, args);
+    let document = match text {
+        Some(text) if format == drivel::InputFormat::Json => drivel::parse_document(text).ok(),
+        _ => None,
+    };
+    match document {
+        Some(document) if args.from_schema || document.get("$schema").is_some() => {
+            let schema_format = args.schema_format.unwrap_or_default();
+            match schema_format.parse(&document) {
+                Ok(schema) => schema,
+                Err(err) => {
+                    eprintln!("Error parsing {} from {}: {}", schema_format, name, err);
+                    std::process::exit(exit_code(err));
+                }
+            }
+        }
+        None if args.from_schema => {
+            let schema_format = args.schema_format.unwrap_or_default();
+            eprintln!("Error parsing {} as JSON or YAML {}", name, schema_format);
+            std::process::exit(exit_code(DrivelError::Parse(name)));
+        }
+        _ => with_hints(
+            infer_inputs(vec![(format, input)], args, opts, &mut Collected::default()),
+            args,
+        ),
+    }
+}
+
+/// The schemas of the sources of `merge` or `diff`, where, unless every source is of an array,
+/// the arrays are taken to be arrays of records and stand for the schema of their items, so that
+/// an array of objects and JSON lines of them compare by their records.
+fn record_schemas(schemas: Vec<SchemaState>) -> Vec<SchemaState> {
+    let records = !schemas
+        .iter()
+        .all(|schema| matches!(schema, SchemaState::Array { .. }));
+    schemas
+        .into_iter()
+        .map(|schema| match schema {
+            SchemaState::Array { schema, .. } if records => *schema,
+            schema => schema,
+        })
+        .collect()
+}
+
+/// Extends a saved schema with the schema of more input, by their records, as if the saved
+/// schema had been inferred from its input along with the new one: arrays at the root of both
+/// are records whose numbers add up.
+fn resume_schema(saved: SchemaState, schema: SchemaState) -> SchemaState {
+    let records = match (&saved, &schema) {
+        (
+            SchemaState::Array {
+                max_length: saved, ..
+            },
+            SchemaState::Array {
+                max_length: new, ..
+            },
+        ) => Some(saved + new),
+        _ => None,
+    };
+    let schema = record_schemas(vec![saved, schema])
+        .into_iter()
+        .fold(SchemaState::Initial, SchemaState::merge);
+    match (schema, records) {
+        (
+            SchemaState::Array {
+                lengths,
+                unique_items,
+                schema,
+                ..
+            },
+            Some(records),
+        ) => SchemaState::Array {
+            min_length: records,
+            max_length: records,
+            lengths: lengths.map(|_| [(records, 1)].into()),
+            unique_items,
+            schema,
+        },
+        (schema, _) => schema,
+    }
+}
+
+/// Writes the judgment calls made by inference to stderr, and with `--strict-fail`, exits if
+/// there were any.
+fn report_warnings(warnings: &[drivel::InferenceWarning], args: &Args) {
+    for warning in warnings {
+        drivel::warn(warning.to_string());
+    }
+    if args.strict_fail && !warnings.is_empty() {
+        eprintln!(
+            "Error: inference had to make {} judgment call(s); failing because of --strict-fail",
+            warnings.len()
+        );
+        // like invalid input, see `exit_code`
+        std::process::exit(65);
+    }
+}
+
+fn main() {
+    let args = parse_args();
+    drivel::set_quiet(args.quiet);
+    if let Some(jobs) = args.jobs {
+        // schemas are inferred on the global thread pool; if its threads can't be spawned, it
+        // is left to its default size
+        let _ = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global();
+    }
+    if (args.strict || args.strict_fail)
+        && (args.schema.is_some() || args.sig.is_some() || args.from_schema)
+    {
+        drivel::warn("--strict reports on inference from input data, not a schema; ignoring");
+    }
+    if (args.schema.is_some() || args.sig.is_some()) && !input_files(&args).is_empty() {
+        drivel::warn(
+            "the schema is given by --schema or --sig, not read from FILES; ignoring them",
+        );
+    }
+    if let Mode::Merge {
+        sources,
+        format,
+        save,
+    } = &args.mode
+    {
+        if args.schema.is_some() || args.sig.is_some() {
+            drivel::warn("merge merges the schemas of FILES, not --schema or --sig; ignoring them");
+        }
+        let opts = inference_options(&args);
+        let schemas: Vec<SchemaState> = expand_paths(sources)
+            .iter()
+            .map(|path| source_schema(path.as_ref(), &args, &opts))
+            .collect();
+        let schema = record_schemas(schemas)
+            .into_iter()
+            .fold(SchemaState::Initial, SchemaState::merge);
+        if let Some(path) = save {
+            save_schema_file(path, &schema);
+        }
+        let options = DescribeOptions {
+            json_schema: JsonSchemaOptions {
+                field_descriptions: true,
+                ..Default::default()
+            },
+            sort_keys: args.sort_keys,
+            ..Default::default()
+        };
+        let format = format.unwrap_or(DescribeFormat::JsonSchema);
+        match format.render(&schema, &options) {
+            Description::Text(text) => println!("{}", text),
+            Description::Document(document) => write_value(&document, args.yaml),
+        }
+        return;
+    }
+    if let Mode::Diff {
+        old,
+        new,
+        json,
+        fail_on_breaking,
+    } = &args.mode
+    {
+        if args.schema.is_some() || args.sig.is_some() {
+            drivel::warn(
+                "diff compares the schemas of OLD and NEW, not --schema or --sig; ignoring them",
+            );
+        }
+        let opts = inference_options(&args);
+        let schemas: Vec<SchemaState> = [old, new]
+            .into_iter()
+            .map(|path| {
+                let path = (path.to_str() != Some("-")).then_some(path);
+                source_schema(path, &args, &opts)
+            })
+            .collect();
+        let [old, new]: [SchemaState; 2] = record_schemas(schemas).try_into().unwrap();
+        let changes = old.diff(&new);
+        let breaking = changes.iter().filter(|change| change.breaking).count();
+        if *json {
+            let changes: Vec<serde_json::Value> = changes
+                .iter()
+                .map(|change| {
+                    serde_json::json!({
+                        "path": change.path,
+                        "change": change.kind.to_string(),
+                        "breaking": change.breaking,
+                    })
+                })
+                .collect();
+            write_value(&serde_json::Value::Array(changes), args.yaml);
+        } else if changes.is_empty() {
+            drivel::info("The schemas are the same");
+        } else {
+            for change in &changes {
+                if change.breaking {
+                    println!("{} (breaking)", change);
+                } else {
+                    println!("{}", change);
+                }
+            }
+        }
+        if *fail_on_breaking && breaking > 0 {
+            eprintln!(
+                "Error: {} of the {} change(s) are breaking; failing because of --fail-on-breaking",
+                breaking,
+                changes.len()
+            );
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Mode::Anonymize { compact, seed, .. } = &args.mode {
+        anonymize(&args, *compact, *seed);
+        return;
+    }
+    if let Mode::Describe { watch: true, .. } = &args.mode {
+        if std::env::var_os(WATCHED_ENV).is_none() {
+            watch(&args);
+        }
+    }
+    let (schema, collected) = match (&args.schema, &args.sig) {
+        (Some(path), _) => (load_saved_schema(path), Collected::default()),
+        (None, Some(schema)) => (schema.clone(), Collected::default()),
+        (None, None) => {
+            let (schema, collected) = read_schema(&args);
+            match &args.resume {
+                Some(path) => (resume_schema(load_saved_schema(path), schema), collected),
+                None => (schema, collected),
+            }
+        }
+    };
+    let field_stats = collected.stats;
+    if args.track_distributions && collected.number_stats.is_none() {
+        drivel::warn(
+            "--track-distributions needs input data to track numbers in, not a schema; ignoring",
+        );
+    }
+
+    match &args.mode {
+        Mode::Produce {
+            n_repeat,
+            n_from_schema,
+            compact,
+            indent,
+            format,
+            produce_config,
+            minimal,
+            charset,
+            lorem,
+            realistic,
+            locale,
+            unique,
+            no_references,
+            fields,
+            omit_read_only,
+            seed,
+            optional_include_probability,
+            null_probability,
+            max_bytes,
+            max_recursion_depth,
+            estimate,
+            target_size,
+            out_dir,
+            file_size,
+            file_records,
+            compress,
+            non_finite_fallback,
+            no_sci_notation,
+            canonical,
+            objects,
+            describe,
+            describe_out,
+            describe_format,
+            files: _,
+        } => {
+            let schema = if fields.is_empty() {
+                schema
+            } else {
+                let paths = schema.paths();
+                for path in fields.iter().filter(|path| !paths.contains(path.as_str())) {
+                    drivel::warn(format!(
+                        "--fields refers to unknown field '{}', ignoring",
+                        path
+                    ));
+                }
+                let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+                schema.project(&fields)
+            };
+            if *describe || describe_out.is_some() || describe_format.is_some() {
+                let format = describe_format.unwrap_or(DescribeFormat::Human);
+                let options = DescribeOptions {
+                    sort_keys: args.sort_keys,
+                    ..Default::default()
+                };
+                let description = format.render(&schema, &options);
+                write_description(description, args.yaml, describe_out.as_ref());
+            }
+
+            if *n_from_schema && n_repeat.is_none() && !matches!(schema, SchemaState::Array { .. })
+            {
+                drivel::warn("--n-from-schema only applies to schemas with an array at the root; producing a single value");
+            }
+            // an array at the root has exactly `n` elements, one unless `n` is given, or as many
+            // as its bounds allow with `--n-from-schema`, and as few as they allow with `--minimal`
+            let (schema, n_repeat) = match n_repeat {
+                None if *n_from_schema || *minimal => (schema, 1),
+                n => {
+                    let n = n.unwrap_or(1);
+                    (with_root_length(schema, n), n)
+                }
+            };
+            let mut options = drivel::ProduceOptions {
+                fields: args.config_fields.clone(),
+                ..Default::default()
+            };
+            if let Some(path) = produce_config {
+                options.merge(load_produce_config(path));
+            }
+            if charset.is_some() {
+                options.string_charset = charset.clone();
+            }
+            options.merge(drivel::ProduceOptions {
+                unique_fields: unique.clone(),
+                lorem: *lorem,
+                realistic: *realistic,
+                locale: *locale,
+                omit_read_only: *omit_read_only,
+                seed: *seed,
+                jobs: args.jobs,
+                non_finite: *non_finite_fallback,
+                optional_include_probability: *optional_include_probability,
+                null_probability: *null_probability,
+                field_stats: field_stats.clone(),
+                number_stats: collected.number_stats,
+                max_output_bytes: *max_bytes,
+                max_recursion_depth: *max_recursion_depth,
+                sort_keys: args.sort_keys,
+                ..Default::default()
+            });
+            if !*no_references {
+                options.infer_references(&schema);
+            }
+            if options.locale.is_some() && !options.realistic {
+                drivel::warn("--locale only applies to --realistic; ignoring");
+            }
+            for path in options.unknown_paths(&schema) {
+                drivel::warn(format!(
+                    "produce config refers to unknown field '{}', ignoring",
+                    path
+                ));
+            }
+
+            if *estimate {
+                let estimate = drivel::estimate_produce(&schema, n_repeat, &options);
+                eprintln!("Estimated output: {}", estimate);
+                return;
+            }
+
+            if *objects && !args.tabular_array {
+                drivel::warn("--objects only applies to --tabular-array; ignoring");
+            }
+            if target_size.is_some() || out_dir.is_some() {
+                if args.yaml || args.tabular_array {
+                    eprintln!("Error: --target-size and --out-dir write JSON, JSON lines or comma-separated values, not --yaml or --tabular-array");
+                    std::process::exit(1);
+                }
+                let record_format = match format {
+                    Some(drivel::OutputFormat::Csv) if *canonical => {
+                        eprintln!(
+                            "Error: --canonical only applies to JSON output, not --format csv"
+                        );
+                        std::process::exit(1);
+                    }
+                    Some(drivel::OutputFormat::Csv) => {
+                        RecordFormat::Csv(csv_writer(&schema, args.sort_keys))
+                    }
+                    Some(drivel::OutputFormat::Ndjson) => RecordFormat::Lines {
+                        canonical: *canonical,
+                        fixed_point: *no_sci_notation,
+                    },
+                    _ => RecordFormat::Json {
+                        indent: if *compact {
+                            None
+                        } else {
+                            Some(indent.unwrap_or(2))
+                        },
+                        canonical: *canonical,
+                        fixed_point: *no_sci_notation,
+                    },
+                };
+                let limits = drivel::SplitLimits {
+                    total_bytes: *target_size,
+                    file_bytes: *file_size,
+                    file_records: *file_records,
+                };
+                let out_dir = out_dir.as_deref();
+                let written = write_split(
+                    &schema,
+                    n_repeat,
+                    &options,
+                    record_format,
+                    limits,
+                    out_dir,
+                    *compress,
+                );
+                match written {
+                    Ok(summary) => {
+                        if summary.records == 0 {
+                            drivel::warn("no record fits within --target-size; wrote none");
+                        }
+                        if let Some(dir) = out_dir {
+                            drivel::info(format!("Wrote {} to {}", summary, dir.display()));
+                        }
+                    }
+                    Err(DrivelError::Io(err)) => exit_on_write_error(err),
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        std::process::exit(exit_code(err));
+                    }
+                }
+                return;
+            }
+            if let Some(format @ (drivel::OutputFormat::Ndjson | drivel::OutputFormat::Csv)) =
+                format
+            {
+                let csv = *format == drivel::OutputFormat::Csv;
+                if args.yaml {
+                    eprintln!(
+                        "Error: --format {} writes {}, not --yaml",
+                        if csv { "csv" } else { "ndjson" },
+                        if csv {
+                            "comma-separated values"
+                        } else {
+                            "JSON lines"
+                        }
+                    );
+                    std::process::exit(1);
+                }
+                if csv && *canonical {
+                    eprintln!("Error: --canonical only applies to JSON output, not --format csv");
+                    std::process::exit(1);
+                }
+                if args.tabular_array && !csv && !objects {
+                    eprintln!("Error: --format ndjson writes the rows of --tabular-array as objects only; add --objects");
+                    std::process::exit(1);
+                }
+                let csv = csv.then(|| csv_writer(&schema, args.sort_keys));
+                let mut out = produced_output(*compress);
+                if let Some(csv) = &csv {
+                    csv.write_header(&mut out)
+                        .unwrap_or_else(|err| exit_on_write_error(err));
+                }
+                let mut write = |value: Value| {
+                    match &csv {
+                        Some(csv) => csv.write_record(&mut out, &value),
+                        None => write_line(&mut out, &value, *canonical, *no_sci_notation),
+                    }
+                    .unwrap_or_else(|err| exit_on_write_error(err))
+                };
+                let result = if *minimal {
+                    drivel::try_produce_minimal(&schema).map(|value| match value {
+                        Value::Array(elements) => elements.into_iter().for_each(&mut write),
+                        value => write(value),
+                    })
+                } else {
+                    drivel::try_produce_each(&schema, n_repeat, &options, &mut write)
+                };
+                out.flush().unwrap_or_else(|err| exit_on_write_error(err));
+                if let Err(err) = result {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(exit_code(err));
+                }
+                return;
+            }
+
+            if args.yaml && (*compact || indent.is_some()) {
+                eprintln!("Error: --compact and --indent only apply to JSON output, not --yaml");
+                std::process::exit(1);
+            }
+            let indent = if *compact {
+                None
+            } else {
+                Some(indent.unwrap_or(2))
+            };
+            // JSON is written while it is produced, so that the data never has to fit in memory
+            if !*minimal && !args.tabular_array && !*canonical && !args.yaml {
+                let out = produced_output(*compress);
+                let result =
+                    write_produced(&schema, n_repeat, &options, out, indent, *no_sci_notation);
+                match result {
+                    Ok(()) => {}
+                    Err(DrivelError::Io(err)) => exit_on_write_error(err),
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        std::process::exit(exit_code(err));
+                    }
+                }
+                return;
+            }
+
+            let result = match schema {
+                _ if *minimal => drivel::try_produce_minimal(&schema),
+                SchemaState::Array { .. } => drivel::try_produce_with(&schema, n_repeat, &options),
+                // if the user wants to repeat the data more than once and we aren't dealing with
+                // an array at the root, then we produce each value separately and collect them
+                // in an array, so that paths in the produce config stay relative to the values
+                _ if n_repeat > 1 => {
+                    drivel::try_produce_records(&schema, n_repeat, &options).map(Value::Array)
+                }
+                _ => drivel::try_produce_with(&schema, 1, &options),
+            };
+            let result = result.unwrap_or_else(|err| {
+                eprintln!("Error: {}", err);
+                std::process::exit(exit_code(err));
+            });
+            let result = match result {
+                Value::Array(records) if args.tabular_array && !objects => {
+                    let columns = collected.columns.unwrap_or_default();
+                    drivel::table_from_records(records, &columns)
+                }
+                result if args.tabular_array && !objects => {
+                    drivel::warn("--tabular-array writes rows of an array at the root of the schema only; writing the produced value as it is");
+                    result
+                }
+                result => result,
+            };
+            if *canonical && args.yaml {
+                eprintln!("Error: --canonical only applies to JSON output, not --yaml");
+                std::process::exit(1);
+            }
+            let mut out = produced_output(*compress);
+            if *canonical {
+                out.write_all(drivel::to_canonical_json(&result, indent).as_bytes())
+            } else {
+                write_json_to(&mut out, &result, args.yaml, indent, *no_sci_notation)
+            }
+            .and_then(|()| out.flush())
+            .unwrap_or_else(|err| exit_on_write_error(err));
+        }
+        Mode::Describe {
+            format,
+            json_schema,
+            openapi,
+            dot,
+            typescript,
+            rust,
+            sql,
+            avro,
+            proto,
+            pydantic,
+            zod,
+            graphql,
+            openapi_version,
+            openapi_name,
+            openapi_split,
+            type_name,
+            as_table,
+            compact,
+            indent,
+            max_list,
+            color,
+            max_display_depth,
+            align,
+            title,
+            id,
+            no_field_descriptions,
+            bounds,
+            numeric_ranges,
+            lengths,
+            schema_draft,
+            dedupe_defs,
+            with_stats,
+            value_counts,
+            stream,
+            save,
+            ..
+        } => {
+            if *with_stats && field_stats.is_none() {
+                drivel::warn(
+                    "--with-stats needs input data to count fields in, not a schema; ignoring",
+                );
+            }
+            if *value_counts && collected.value_counts.is_none() {
+                drivel::warn(
+                    "--value-counts needs input data to count values in, not a schema; ignoring",
+                );
+            }
+            if *stream && (args.schema.is_some() || args.sig.is_some()) {
+                drivel::warn("--stream needs input data to infer from, not a schema; ignoring");
+            }
+
+            if let Some(path) = save {
+                save_schema_file(path, &schema);
+            }
+
+            if args.yaml && (*compact || indent.is_some()) {
+                eprintln!("Error: --compact and --indent only apply to JSON output, not --yaml");
+                std::process::exit(1);
+            }
+            let json_indent = if *compact {
+                None
+            } else {
+                Some(indent.unwrap_or(2))
+            };
+
+            let format = if *json_schema {
+                drivel::warn("--json-schema is deprecated; use --format json-schema");
+                DescribeFormat::JsonSchema
+            } else if *openapi {
+                DescribeFormat::OpenApi
+            } else if *dot {
+                DescribeFormat::Dot
+            } else if *typescript {
+                DescribeFormat::TypeScript
+            } else if *rust {
+                DescribeFormat::Rust
+            } else if sql.is_some() {
+                DescribeFormat::Sql
+            } else if *avro {
+                DescribeFormat::Avro
+            } else if *proto {
+                DescribeFormat::Proto
+            } else if *pydantic {
+                DescribeFormat::Pydantic
+            } else if *zod {
+                DescribeFormat::Zod
+            } else if *graphql {
+                DescribeFormat::GraphQl
+            } else {
+                format.unwrap_or(DescribeFormat::Human)
+            };
+
+            if *as_table && format == DescribeFormat::Human {
+                let mut schema = schema.clone();
+                if args.sort_keys {
+                    schema.sort_keys();
+                }
+                match schema.to_table_string() {
+                    Some(_) if !has_uniform_records(&schema) => drivel::warn(
+                        "the records don't all have the same fields; unable to describe them as a table",
+                    ),
+                    Some(table) => {
+                        println!("{}", table);
+                        return;
+                    }
+                    None => drivel::warn(
+                        "input is not an array of objects; unable to describe it as a table",
+                    ),
+                }
+            }
+
+            let options = DescribeOptions {
+                json_schema: JsonSchemaOptions {
+                    title: title.clone(),
+                    id: id.clone(),
+                    field_descriptions: !no_field_descriptions,
+                    numeric_bounds: *bounds,
+                    numeric_ranges: *numeric_ranges,
+                    dedupe_definitions: *dedupe_defs,
+                    field_stats,
+                    value_counts: collected.value_counts,
+                    lengths: *lengths,
+                    draft: schema_draft.unwrap_or_default(),
+                },
+                openapi: OpenApiOptions {
+                    version: openapi_version.unwrap_or_default(),
+                    name: openapi_name.clone(),
+                    split_components: *openapi_split,
+                },
+                max_list_items: match max_list {
+                    Some(0) => None,
+                    Some(n) => Some(*n),
+                    None => Some(DEFAULT_MAX_LIST_ITEMS),
+                },
+                type_name: type_name.clone(),
+                sql_dialect: sql.unwrap_or_default(),
+                number_stats: collected.number_stats,
+                sort_keys: args.sort_keys,
+                sample: collected.sample,
+                color: color
+                    .unwrap_or_default()
+                    .enabled(std::io::stdout().is_terminal()),
+                max_depth: *max_display_depth,
+                align_fields: *align,
+            };
+
+            match format.render(&schema, &options) {
+                Description::Text(text) => {
+                    if *compact || indent.is_some() {
+                        drivel::warn("--compact and --indent only apply to JSON output; ignoring");
+                    }
+                    println!("{}", text)
+                }
+                Description::Document(document) => {
+                    write_json(&document, args.yaml, json_indent, false)
+                }
+            }
+        }
+        Mode::Serve {
+            port,
+            host,
+            n_repeat,
+            latency,
+            produce_config,
+            realistic,
+            seed,
+            files: _,
+        } => {
+            let mut options = drivel::ProduceOptions {
+                fields: args.config_fields.clone(),
+                ..Default::default()
+            };
+            if let Some(path) = produce_config {
+                options.merge(load_produce_config(path));
+            }
+            options.merge(drivel::ProduceOptions {
+                realistic: *realistic,
+                seed: *seed,
+                jobs: args.jobs,
+                field_stats,
+                number_stats: collected.number_stats,
+                sort_keys: args.sort_keys,
+                ..Default::default()
+            });
+            options.infer_references(&schema);
+            for path in options.unknown_paths(&schema) {
+                drivel::warn(format!(
+                    "produce config refers to unknown field '{}', ignoring",
+                    path
+                ));
+            }
+            let address = format!(
+                "{}:{}",
+                host.as_deref().unwrap_or("127.0.0.1"),
+                port.unwrap_or(8080)
+            );
+            let listener = std::net::TcpListener::bind(&address).unwrap_or_else(|err| {
+                eprintln!("Unable to listen on {}. Error: {}", address, err);
+                std::process::exit(exit_code(err))
+            });
+            let mut server = drivel::MockServer::new(schema, n_repeat.unwrap_or(1), options);
+            if let Some((min, max)) = latency {
+                server = server.with_latency(*min, *max);
+            }
+            drivel::info(format!(
+                "Serving data produced from the schema at http://{}/",
+                listener
+                    .local_addr()
+                    .map_or(address, |addr| addr.to_string())
+            ));
+            if let Err(err) = server.serve(listener) {
+                eprintln!("Error: {}", err);
+                std::process::exit(exit_code(err));
+            }
+        }
+        Mode::Validate {
+            data,
+            check_lengths,
+        } => {
+            let paths = if !data.is_empty() {
+                expand_paths(data)
+            } else if args.schema.is_none() && args.sig.is_none() {
+                eprintln!("Error: validate needs the files of data to validate, as the input is read for the schema");
+                std::process::exit(1);
+            } else {
+                input_paths(&args)
+            };
+            let inputs: Vec<_> = paths
+                .iter()
+                .map(|path| {
+                    let name = path
+                        .as_ref()
+                        .map_or("stdin".to_string(), |path| path.display().to_string());
+                    let input = read_data(path.as_ref(), &args);
+                    let format = input_format(path.as_ref(), &args);
+                    (name, parse_input(&input, format, &args))
+                })
+                .chain(
+                    // the responses of the URLs are the data unless files of it are given
+                    if data.is_empty() {
+                        fetch_urls(&args)
+                    } else {
+                        vec![]
+                    }
+                    .into_iter()
+                    .map(|(url, body)| {
+                        let parsed =
+                            parse_input(&Input::Text(body), drivel::InputFormat::Json, &args);
+                        (url, parsed)
+                    }),
+                )
+                .collect();
+
+            let options = drivel::ValidationOptions {
+                lengths: *check_lengths,
+            };
+            let (mut records, mut invalid, mut mismatches) = (0, 0, 0);
+            for (name, parsed) in inputs {
+                // the elements of an array are records, unless the schema is of the whole array
+                let values = match parsed {
+                    drivel::ParsedInput::Document(Value::Array(values))
+                        if !matches!(schema, SchemaState::Array { .. }) =>
+                    {
+                        values
+                    }
+                    drivel::ParsedInput::Document(value) => vec![value],
+                    drivel::ParsedInput::Records { values, .. } => values,
+                };
+                records += values.len();
+                let errors = drivel::validate_records_with(&schema, values, &options);
+                let mut indices: Vec<usize> = errors.iter().map(|error| error.index).collect();
+                indices.dedup();
+                invalid += indices.len();
+                mismatches += errors.len();
+                for error in errors {
+                    println!("{}: {}", name, error);
+                }
+            }
+
+            if invalid > 0 {
+                eprintln!(
+                    "Error: {} of {} records don't fit the schema, with {} mismatches",
+                    invalid, records, mismatches
+                );
+                std::process::exit(1);
             }
+            drivel::info(format!("All {} records fit the schema", records));
         }
+        Mode::Anonymize { .. } => unreachable!("input is anonymized before reading a schema"),
+        Mode::Merge { .. } => unreachable!("sources are merged before reading a schema"),
+        Mode::Diff { .. } => unreachable!("sources are compared before reading a schema"),
     }
 }
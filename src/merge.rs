@@ -0,0 +1,544 @@
+use indexmap::IndexMap;
+
+use crate::infer::{json_type, merge as merge_observed, merge_unique_items, shape_signature};
+use crate::{NumberType, SchemaState, StringType};
+
+/// Merges the fields of two objects, keeping those required by both required, and making
+/// the others optional, in the order in which they were first seen.
+fn merge_fields(
+    (mut required, mut optional): (IndexMap<String, SchemaState>, IndexMap<String, SchemaState>),
+    (second_required, second_optional): (
+        IndexMap<String, SchemaState>,
+        IndexMap<String, SchemaState>,
+    ),
+) -> (IndexMap<String, SchemaState>, IndexMap<String, SchemaState>) {
+    let mut second: IndexMap<String, (SchemaState, bool)> = second_required
+        .into_iter()
+        .map(|(key, schema)| (key, (schema, true)))
+        .chain(
+            second_optional
+                .into_iter()
+                .map(|(key, schema)| (key, (schema, false))),
+        )
+        .collect();
+    let mut merged_optional = IndexMap::new();
+    for (key, schema) in std::mem::take(&mut required) {
+        match second.shift_remove(&key) {
+            Some((second, true)) => {
+                required.insert(key, merge_schemas(schema, second));
+            }
+            Some((second, false)) => {
+                merged_optional.insert(key, merge_schemas(schema, second));
+            }
+            None => {
+                merged_optional.insert(key, schema);
+            }
+        }
+    }
+    for (key, schema) in optional.drain(..) {
+        let schema = match second.shift_remove(&key) {
+            Some((second, _)) => merge_schemas(schema, second),
+            None => schema,
+        };
+        merged_optional.insert(key, schema);
+    }
+    merged_optional.extend(second.into_iter().map(|(key, (schema, _))| (key, schema)));
+    (required, merged_optional)
+}
+
+/// Merges the variants of a union into those of another, by the fields of their objects.
+fn merge_variants(mut variants: Vec<SchemaState>, second: Vec<SchemaState>) -> Vec<SchemaState> {
+    for variant in second {
+        let signature = shape_signature(&variant);
+        match variants
+            .iter()
+            .position(|v| signature.is_some() && shape_signature(v) == signature)
+        {
+            Some(idx) => {
+                let existing = std::mem::replace(&mut variants[idx], SchemaState::Initial);
+                variants[idx] = merge_schemas(existing, variant);
+            }
+            None => variants.push(variant),
+        }
+    }
+    variants
+}
+
+/// Merges the variants of a mixed schema into those of another, by their types, adding up how
+/// many values of each type were seen.
+fn merge_mixed(
+    mut variants: Vec<(SchemaState, usize)>,
+    second: Vec<(SchemaState, usize)>,
+) -> Vec<(SchemaState, usize)> {
+    for (variant, count) in second {
+        match variants
+            .iter()
+            .position(|(v, _)| json_type(v) == json_type(&variant))
+        {
+            Some(idx) => {
+                let (existing, seen) =
+                    std::mem::replace(&mut variants[idx], (SchemaState::Initial, 0));
+                variants[idx] = (merge_schemas(existing, variant), seen + count);
+            }
+            None => variants.push((variant, count)),
+        }
+    }
+    variants
+}
+
+/// Merges two schemas that have each been inferred in full, widening where they differ. Unlike
+/// merging the schemas of values while they are observed, this keeps the enums and other
+/// conclusions that are only drawn once all values have been seen.
+fn merge_schemas(first: SchemaState, second: SchemaState) -> SchemaState {
+    match (first, second) {
+        (SchemaState::Initial, s) | (s, SchemaState::Initial) => s,
+        // values of conflicting types in either are of any type in both
+        (SchemaState::Indefinite, _) | (_, SchemaState::Indefinite) => SchemaState::Indefinite,
+
+        (SchemaState::Null, SchemaState::Null) => SchemaState::Null,
+        (SchemaState::Nullable(first), SchemaState::Nullable(second)) => {
+            SchemaState::Nullable(Box::new(merge_schemas(*first, *second)))
+        }
+        (SchemaState::Nullable(inner), SchemaState::Null)
+        | (SchemaState::Null, SchemaState::Nullable(inner)) => SchemaState::Nullable(inner),
+        (SchemaState::Nullable(inner), other) | (other, SchemaState::Nullable(inner)) => {
+            SchemaState::Nullable(Box::new(merge_schemas(*inner, other)))
+        }
+        (SchemaState::Null, other) | (other, SchemaState::Null) => {
+            SchemaState::Nullable(Box::new(other))
+        }
+
+        (
+            SchemaState::String(StringType::Enum { mut variants }),
+            SchemaState::String(StringType::Enum {
+                variants: second_variants,
+            }),
+        ) => {
+            variants.extend(second_variants);
+            SchemaState::String(StringType::Enum { variants })
+        }
+        // strings of unknown length, as in JSON Schemas without `minLength` or `maxLength`, may
+        // be of any length, rather than of the lengths of the others
+        (
+            first @ SchemaState::String(StringType::Unknown { .. }),
+            second @ SchemaState::String(StringType::Unknown { .. }),
+        ) => {
+            let bounded = |schema: &SchemaState| match schema {
+                SchemaState::String(StringType::Unknown {
+                    min_length,
+                    max_length,
+                    ..
+                }) => (min_length.is_some(), max_length.is_some()),
+                _ => unreachable!(),
+            };
+            let ((first_min, first_max), (second_min, second_max)) =
+                (bounded(&first), bounded(&second));
+            match merge_observed(first, second) {
+                SchemaState::String(StringType::Unknown {
+                    strings_seen,
+                    chars_seen,
+                    min_length,
+                    max_length,
+                }) => SchemaState::String(StringType::Unknown {
+                    strings_seen,
+                    chars_seen,
+                    min_length: min_length.filter(|_| first_min && second_min),
+                    max_length: max_length.filter(|_| first_max && second_max),
+                }),
+                merged => merged,
+            }
+        }
+        (
+            SchemaState::Number(NumberType::Enum { mut variants }),
+            SchemaState::Number(NumberType::Enum {
+                variants: second_variants,
+            }),
+        ) => {
+            for (value, count) in second_variants {
+                *variants.entry(value).or_insert(0) += count;
+            }
+            SchemaState::Number(NumberType::Enum { variants })
+        }
+
+        (
+            SchemaState::Array {
+                min_length,
+                max_length,
+                lengths,
+                unique_items,
+                schema,
+            },
+            SchemaState::Array {
+                min_length: second_min_length,
+                max_length: second_max_length,
+                lengths: second_lengths,
+                unique_items: second_unique_items,
+                schema: second_schema,
+            },
+        ) => SchemaState::Array {
+            min_length: min_length.min(second_min_length),
+            max_length: max_length.max(second_max_length),
+            lengths: match (lengths, second_lengths) {
+                (Some(mut lengths), Some(second_lengths)) => {
+                    for (length, count) in second_lengths {
+                        *lengths.entry(length).or_insert(0) += count;
+                    }
+                    Some(lengths)
+                }
+                _ => None,
+            },
+            unique_items: merge_unique_items(
+                (unique_items, max_length),
+                (second_unique_items, second_max_length),
+            ),
+            schema: Box::new(merge_schemas(*schema, *second_schema)),
+        },
+
+        (
+            SchemaState::Object {
+                required,
+                optional,
+                mut access,
+                mut annotations,
+            },
+            SchemaState::Object {
+                required: second_required,
+                optional: second_optional,
+                access: second_access,
+                annotations: second_annotations,
+            },
+        ) => {
+            let (required, optional) =
+                merge_fields((required, optional), (second_required, second_optional));
+            for (key, field_access) in second_access {
+                access.entry(key).or_insert(field_access);
+            }
+            for (key, keywords) in second_annotations {
+                annotations.entry(key).or_insert(keywords);
+            }
+            SchemaState::Object {
+                required,
+                optional,
+                access,
+                annotations,
+            }
+        }
+
+        (SchemaState::Tuple(positions), SchemaState::Tuple(second_positions))
+            if positions.len() == second_positions.len() =>
+        {
+            SchemaState::Tuple(
+                positions
+                    .into_iter()
+                    .zip(second_positions)
+                    .map(|(position, second)| merge_schemas(position, second))
+                    .collect(),
+            )
+        }
+
+        (
+            SchemaState::Map {
+                min_length,
+                max_length,
+                keys,
+                schema,
+            },
+            SchemaState::Map {
+                min_length: second_min_length,
+                max_length: second_max_length,
+                keys: second_keys,
+                schema: second_schema,
+            },
+        ) => SchemaState::Map {
+            min_length: min_length.min(second_min_length),
+            max_length: max_length.max(second_max_length),
+            keys: match merge_schemas(SchemaState::String(keys), SchemaState::String(second_keys)) {
+                SchemaState::String(keys) => keys,
+                _ => unreachable!(),
+            },
+            schema: Box::new(merge_schemas(*schema, *second_schema)),
+        },
+        // an object with few fields in one source is a map with many in another
+        (
+            SchemaState::Map {
+                min_length,
+                max_length,
+                schema,
+                ..
+            },
+            SchemaState::Object {
+                required, optional, ..
+            },
+        )
+        | (
+            SchemaState::Object {
+                required, optional, ..
+            },
+            SchemaState::Map {
+                min_length,
+                max_length,
+                schema,
+                ..
+            },
+        ) => SchemaState::Map {
+            min_length: min_length.min(required.len()),
+            max_length: max_length.max(required.len() + optional.len()),
+            keys: StringType::Unknown {
+                strings_seen: vec![],
+                chars_seen: vec![],
+                min_length: None,
+                max_length: None,
+            },
+            schema: Box::new(
+                required
+                    .into_values()
+                    .chain(optional.into_values())
+                    .fold(*schema, merge_schemas),
+            ),
+        },
+
+        (SchemaState::Union(variants), SchemaState::Union(second_variants)) => {
+            SchemaState::Union(merge_variants(variants, second_variants))
+        }
+        (SchemaState::Union(variants), object @ SchemaState::Object { .. }) => {
+            SchemaState::Union(merge_variants(variants, vec![object]))
+        }
+        (object @ SchemaState::Object { .. }, SchemaState::Union(variants)) => {
+            SchemaState::Union(merge_variants(vec![object], variants))
+        }
+
+        (SchemaState::Mixed(variants), SchemaState::Mixed(second_variants)) => {
+            SchemaState::Mixed(merge_mixed(variants, second_variants))
+        }
+        // values of only one of the types of a mixed schema widen its variant of that type,
+        // without being counted, as how many of them there were isn't known
+        (SchemaState::Mixed(variants), other) | (other, SchemaState::Mixed(variants))
+            if variants
+                .iter()
+                .any(|(variant, _)| json_type(variant) == json_type(&other)) =>
+        {
+            SchemaState::Mixed(merge_mixed(variants, vec![(other, 0)]))
+        }
+
+        (
+            SchemaState::TaggedUnion { tag, mut variants },
+            SchemaState::TaggedUnion {
+                tag: second_tag,
+                variants: second_variants,
+            },
+        ) if tag == second_tag => {
+            for (value, variant) in second_variants {
+                let existing = variants.remove(&value).unwrap_or(SchemaState::Initial);
+                variants.insert(value, merge_schemas(existing, variant));
+            }
+            SchemaState::TaggedUnion { tag, variants }
+        }
+        // objects without the tag, or with another one, merge with the objects of every variant
+        (
+            SchemaState::TaggedUnion { variants, .. },
+            other @ (SchemaState::Object { .. }
+            | SchemaState::TaggedUnion { .. }
+            | SchemaState::Union(_)),
+        )
+        | (
+            other @ (SchemaState::Object { .. } | SchemaState::Union(_)),
+            SchemaState::TaggedUnion { variants, .. },
+        ) => merge_schemas(
+            variants
+                .into_values()
+                .fold(SchemaState::Initial, merge_schemas),
+            other,
+        ),
+
+        // other values merge as they do during inference, such as the ranges of numbers
+        (first, second) => merge_observed(first, second),
+    }
+}
+
+impl SchemaState {
+    /// Merges this schema with another, such as those inferred from the data of different
+    /// environments, into a single schema of the values of both. Fields that aren't required in
+    /// both are optional, enums have the values of both, and ranges and lengths are widened to
+    /// cover both. Values whose types conflict, such as strings in one and numbers in the
+    /// other, are of any type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions, SchemaState};
+    /// use serde_json::json;
+    ///
+    /// let options = InferenceOptions::default();
+    /// let staging = infer_schema(json!({"id": 1, "debug": true}), &options);
+    /// let production = infer_schema(json!({"id": 1000, "region": "eu"}), &options);
+    ///
+    /// let merged = staging.merge(production);
+    /// assert_eq!(merged.signature(), "{debug:bool?, id:int, region:string?}");
+    /// ```
+    pub fn merge(self, other: SchemaState) -> SchemaState {
+        merge_schemas(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+    use crate::{infer_schema_from_iter, InferenceOptions};
+    use serde_json::json;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    fn int(min: i64, max: i64) -> SchemaState {
+        SchemaState::Number(NumberType::Integer { min, max })
+    }
+
+    #[test]
+    fn makes_fields_optional_unless_both_require_them() {
+        let first = object_schema(
+            vec![
+                ("id", int(1, 5)),
+                ("status", strings(&["open"])),
+                ("a", SchemaState::Boolean),
+            ],
+            vec![("b", SchemaState::Boolean)],
+        );
+        let second = object_schema(
+            vec![
+                ("id", int(3, 9)),
+                ("status", strings(&["closed"])),
+                ("b", SchemaState::Boolean),
+            ],
+            vec![("a", SchemaState::Null), ("c", SchemaState::Boolean)],
+        );
+        assert_eq!(
+            first.merge(second),
+            object_schema(
+                vec![("id", int(1, 9)), ("status", strings(&["closed", "open"]))],
+                vec![
+                    ("a", SchemaState::Nullable(Box::new(SchemaState::Boolean))),
+                    ("b", SchemaState::Boolean),
+                    ("c", SchemaState::Boolean),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn merges_arrays_as_they_are_inferred_in_one_pass() {
+        let options = InferenceOptions::default();
+        let records = vec![
+            json!({"tags": []}),
+            json!({"tags": [1]}),
+            json!({"tags": [1, 2]}),
+            json!({"tags": [3]}),
+        ];
+        let merged = records
+            .iter()
+            .map(|record| infer_schema_from_iter(vec![record.clone()], &options))
+            .reduce(SchemaState::merge)
+            .unwrap();
+        let inferred = infer_schema_from_iter(records, &options);
+        assert_eq!(merged, inferred);
+        let SchemaState::Object { required, .. } = merged else {
+            panic!("expected an object, got {:?}", merged);
+        };
+        assert!(matches!(
+            required["tags"],
+            SchemaState::Array {
+                unique_items: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn widens_values_that_differ() {
+        let array = |min_length, max_length, schema| SchemaState::Array {
+            min_length,
+            max_length,
+            lengths: None,
+            unique_items: true,
+            schema: Box::new(schema),
+        };
+        assert_eq!(
+            array(1, 2, int(0, 1)).merge(array(
+                0,
+                5,
+                SchemaState::Number(NumberType::Float {
+                    min: 0.5,
+                    max: 2.5,
+                    non_finite: BTreeSet::new(),
+                })
+            )),
+            array(
+                0,
+                5,
+                SchemaState::Number(NumberType::Float {
+                    min: 0.0,
+                    max: 2.5,
+                    non_finite: BTreeSet::new(),
+                })
+            )
+        );
+        assert_eq!(
+            SchemaState::String(StringType::UUID).merge(int(1, 2)),
+            SchemaState::Indefinite
+        );
+        assert_eq!(
+            SchemaState::Indefinite.merge(SchemaState::Boolean),
+            SchemaState::Indefinite
+        );
+        let string = |min_length, max_length| {
+            SchemaState::String(StringType::Unknown {
+                strings_seen: vec![],
+                chars_seen: vec![],
+                min_length,
+                max_length,
+            })
+        };
+        assert_eq!(
+            string(Some(2), Some(4)).merge(string(Some(3), Some(8))),
+            string(Some(2), Some(8))
+        );
+        assert_eq!(
+            string(Some(2), Some(4)).merge(string(None, None)),
+            string(None, None)
+        );
+        assert_eq!(
+            SchemaState::Number(NumberType::Enum {
+                variants: BTreeMap::from_iter([(1, 2), (2, 1)])
+            })
+            .merge(SchemaState::Number(NumberType::Enum {
+                variants: BTreeMap::from_iter([(2, 1), (3, 1)])
+            })),
+            SchemaState::Number(NumberType::Enum {
+                variants: BTreeMap::from_iter([(1, 2), (2, 2), (3, 1)])
+            })
+        );
+
+        // an object in one and a map in the other is a map of the values of both
+        let map = SchemaState::Map {
+            min_length: 5,
+            max_length: 8,
+            keys: StringType::UUID,
+            schema: Box::new(int(1, 2)),
+        };
+        let SchemaState::Map {
+            min_length,
+            max_length,
+            schema,
+            ..
+        } = object_schema(vec![("a", int(0, 1))], vec![("b", int(5, 6))]).merge(map)
+        else {
+            panic!("not a map");
+        };
+        assert_eq!((min_length, max_length), (1, 8));
+        assert_eq!(*schema, int(0, 6));
+    }
+}
@@ -0,0 +1,261 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{NumberType, SchemaState, StringType};
+
+/// Measures of the size and complexity of a schema, as a quick fingerprint of the shape of the
+/// data it was inferred from.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaMetrics {
+    /// The number of fields of all objects in the schema, including those of nested objects.
+    /// The fields of the elements of an array are counted once, not once per element, and those
+    /// of a recursive definition once, not once per level.
+    pub fields: usize,
+    /// How deeply objects, arrays and maps are nested, where a value at the root that isn't one
+    /// of them has a depth of zero.
+    pub max_depth: usize,
+    /// The number of unions in the schema, including tagged unions.
+    pub unions: usize,
+    /// The number of string and integer enums in the schema, including open-tail enums.
+    pub enums: usize,
+    /// The number of values of each type that aren't objects, arrays, maps or unions, by their
+    /// JSON Schema type name (`string`, `integer`, `number`, `boolean` or `null`), or
+    /// `extended-json`, `coordinates`, `geo-point` or `unknown` for the others.
+    pub leaf_types: BTreeMap<String, usize>,
+}
+
+impl SchemaMetrics {
+    fn add(&mut self, schema: &SchemaState, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        let leaf_type = match schema {
+            SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+                return self.add(inner, depth)
+            }
+            // what is within a recursive definition is counted once, where it is defined
+            SchemaState::Reference(_) => return,
+            SchemaState::Literal(values) => {
+                return self.add(&SchemaState::literal_type(values), depth)
+            }
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                self.max_depth = self.max_depth.max(depth + 1);
+                self.fields += required.len() + optional.len();
+                for field in required.values().chain(optional.values()) {
+                    self.add(field, depth + 1);
+                }
+                return;
+            }
+            SchemaState::Array { schema, .. } | SchemaState::Map { schema, .. } => {
+                return self.add(schema, depth + 1)
+            }
+            SchemaState::Tuple(positions) => {
+                for position in positions {
+                    self.add(position, depth + 1);
+                }
+                return;
+            }
+            SchemaState::Union(variants) => {
+                self.unions += 1;
+                for variant in variants {
+                    self.add(variant, depth);
+                }
+                return;
+            }
+            SchemaState::Mixed(variants) => {
+                self.unions += 1;
+                for (variant, _) in variants {
+                    self.add(variant, depth);
+                }
+                return;
+            }
+            SchemaState::TaggedUnion { variants, .. } => {
+                self.unions += 1;
+                for variant in variants.values() {
+                    self.add(variant, depth);
+                }
+                return;
+            }
+            SchemaState::String(string_type) => {
+                if matches!(
+                    string_type,
+                    StringType::Enum { .. } | StringType::OpenEnum { .. }
+                ) {
+                    self.enums += 1;
+                }
+                "string"
+            }
+            SchemaState::Number(
+                NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+            ) => "number",
+            SchemaState::Number(number_type) => {
+                if matches!(number_type, NumberType::Enum { .. }) {
+                    self.enums += 1;
+                }
+                "integer"
+            }
+            SchemaState::Boolean => "boolean",
+            SchemaState::Null => "null",
+            SchemaState::ExtendedJson(_) => "extended-json",
+            SchemaState::Coordinates(_) => "coordinates",
+            SchemaState::GeoPoint { .. } => "geo-point",
+            SchemaState::Money { .. } => "money",
+            SchemaState::Initial | SchemaState::Indefinite => "unknown",
+        };
+        *self.leaf_types.entry(leaf_type.to_string()).or_default() += 1;
+    }
+}
+
+impl SchemaState {
+    /// Measures the size and complexity of this schema, in a single traversal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{NumberType, SchemaState};
+    ///
+    /// let schema = SchemaState::Array {
+    ///     min_length: 1,
+    ///     max_length: 3,
+    ///     lengths: None,
+    ///     unique_items: false,
+    ///     schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 10 })),
+    /// };
+    /// let metrics = schema.complexity();
+    /// assert_eq!(metrics.max_depth, 1);
+    /// assert_eq!(metrics.leaf_types["integer"], 1);
+    /// ```
+    pub fn complexity(&self) -> SchemaMetrics {
+        let mut metrics = SchemaMetrics::default();
+        metrics.add(self, 0);
+        metrics
+    }
+
+    /// The number of schemas in this schema, itself included, such as the schema of each field
+    /// of an object and of the elements of an array, as a measure of how much memory it takes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = infer_schema(json!({"id": 1, "tags": ["a"]}), &InferenceOptions::default());
+    /// assert_eq!(schema.node_count(), 4);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            SchemaState::Nullable(inner) => inner.node_count(),
+            SchemaState::Array { schema, .. } | SchemaState::Map { schema, .. } => {
+                schema.node_count()
+            }
+            SchemaState::Tuple(positions) => positions.iter().map(SchemaState::node_count).sum(),
+            SchemaState::Object {
+                required, optional, ..
+            } => required
+                .values()
+                .chain(optional.values())
+                .map(SchemaState::node_count)
+                .sum(),
+            SchemaState::Union(variants) => variants.iter().map(SchemaState::node_count).sum(),
+            SchemaState::Mixed(variants) => variants
+                .iter()
+                .map(|(variant, _)| variant.node_count())
+                .sum(),
+            SchemaState::TaggedUnion { variants, .. } => {
+                variants.values().map(SchemaState::node_count).sum()
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, EnumInference, InferenceOptions};
+    use serde_json::json;
+
+    #[test]
+    fn measures_a_known_fixture() {
+        let input = json!([
+            {
+                "id": 1,
+                "status": "active",
+                "score": 1.5,
+                "tags": ["a", "b"],
+                "address": {"city": "Amsterdam", "geo": {"lat": 52.4, "lon": 4.9}},
+                "note": null
+            },
+            {
+                "id": 2,
+                "status": "active",
+                "score": 2.5,
+                "tags": [],
+                "address": {"city": "Utrecht", "geo": {"lat": 52.1, "lon": 5.1}},
+                "note": "late"
+            }
+        ]);
+        let options = InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.5,
+                max_values: None,
+                min_sample_size: 1,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let metrics = infer_schema(input, &options).complexity();
+
+        assert_eq!(
+            metrics,
+            SchemaMetrics {
+                fields: 10,
+                max_depth: 4,
+                unions: 0,
+                enums: 1,
+                leaf_types: BTreeMap::from([
+                    ("integer".to_string(), 1),
+                    ("number".to_string(), 3),
+                    ("string".to_string(), 4),
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn counts_unions_and_their_variants() {
+        let schema = SchemaState::Nullable(Box::new(SchemaState::Union(vec![
+            SchemaState::Boolean,
+            SchemaState::Null,
+        ])));
+        let metrics = schema.complexity();
+        assert_eq!(metrics.unions, 1);
+        assert_eq!(metrics.max_depth, 0);
+        assert_eq!(metrics.leaf_types.values().sum::<usize>(), 2);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeSet;
+
+use rand::Rng;
+
+use crate::{NumberType, SchemaState};
+
+/// The active currency codes of ISO 4217, in order.
+const CURRENCIES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD",
+    "CAD", "CDF", "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE",
+    "CZK", "DJF", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL",
+    "GHS", "GIP", "GMD", "GNF", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR",
+    "IQD", "IRR", "ISK", "JMD", "JOD", "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD",
+    "KYD", "KZT", "LAK", "LBP", "LKR", "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK",
+    "MNT", "MOP", "MRU", "MUR", "MVR", "MWK", "MXN", "MXV", "MYR", "MZN", "NAD", "NGN", "NIO",
+    "NOK", "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON",
+    "RSD", "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD",
+    "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD",
+    "TZS", "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES", "VND", "VUV",
+    "WST", "XAF", "XCD", "XCG", "XOF", "XPF", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// The currencies that amounts are produced in when a schema doesn't say which, such as one
+/// read from a JSON Schema that only has a pattern for them.
+const COMMON_CURRENCIES: &[&str] = &["USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD"];
+
+/// Whether `s` is an active ISO 4217 currency code, such as `USD`.
+pub(crate) fn is_currency(s: &str) -> bool {
+    CURRENCIES.binary_search(&s).is_ok()
+}
+
+/// The number of digits after the decimal point of amounts in a currency: 2 for most, such as
+/// cents of the US dollar, but none for the yen and 3 for the dinars of Bahrain and others.
+fn decimal_places(currency: &str) -> i32 {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    }
+}
+
+/// The amount and currency fields of an object with just those two, such as `amount` and
+/// `currency`, or `amount_cents` and `currencyCode`, whose names are matched case-insensitively
+/// and regardless of underscores and dashes.
+pub(crate) fn money_keys<'a>(
+    mut keys: impl Iterator<Item = &'a String>,
+) -> Option<(&'a String, &'a String)> {
+    let normalized = |key: &str| key.to_lowercase().replace(['_', '-'], "");
+    let is_amount = |key: &str| {
+        matches!(
+            normalized(key).as_str(),
+            "amount"
+                | "value"
+                | "amountcents"
+                | "amountminor"
+                | "minorunits"
+                | "cents"
+                | "price"
+                | "total"
+        )
+    };
+    let is_currency_key = |key: &str| {
+        matches!(
+            normalized(key).as_str(),
+            "currency" | "currencycode" | "currencyiso" | "isocurrencycode" | "ccy"
+        )
+    };
+    match (keys.next()?, keys.next()?, keys.next()) {
+        (first, second, None) if is_amount(first) && is_currency_key(second) => {
+            Some((first, second))
+        }
+        (first, second, None) if is_currency_key(first) && is_amount(second) => {
+            Some((second, first))
+        }
+        _ => None,
+    }
+}
+
+/// Recognises an object of a numeric amount and an ISO 4217 currency code, such as
+/// `{"amount": 1299, "currency": "USD"}`, as money.
+pub(crate) fn infer_money(
+    object: &serde_json::Map<String, serde_json::Value>,
+) -> Option<SchemaState> {
+    let (amount_key, currency_key) = money_keys(object.keys())?;
+    let currency = object[currency_key].as_str().filter(|s| is_currency(s))?;
+    let amount = match object[amount_key].as_i64() {
+        Some(n) => NumberType::Integer { min: n, max: n },
+        None => {
+            let value = object[amount_key].as_f64()?;
+            NumberType::Float {
+                min: value,
+                max: value,
+                non_finite: BTreeSet::new(),
+            }
+        }
+    };
+    Some(SchemaState::Money {
+        amount_key: amount_key.clone(),
+        currency_key: currency_key.clone(),
+        amount,
+        currencies: BTreeSet::from([currency.to_string()]),
+    })
+}
+
+/// The currency of produced money: one of those seen, or a common one if none were.
+pub(crate) fn produce_currency<R: Rng + ?Sized>(
+    currencies: &BTreeSet<String>,
+    rng: &mut R,
+) -> String {
+    if currencies.is_empty() {
+        COMMON_CURRENCIES[rng.gen_range(0..COMMON_CURRENCIES.len())].to_string()
+    } else {
+        currencies
+            .iter()
+            .nth(rng.gen_range(0..currencies.len()))
+            .unwrap()
+            .clone()
+    }
+}
+
+/// How far above their minimum amounts are produced, in major units such as dollars, when a
+/// schema doesn't bound them, such as one read from a JSON Schema.
+const UNBOUNDED_AMOUNTS: f64 = 1000.0;
+
+/// Produces an amount in the range of `amount`: a whole number of minor units if the amounts
+/// seen were integers, or a decimal amount rounded to the minor unit of the currency otherwise,
+/// such as `12.99` for US dollars and `1299.0` for yen. Amounts without a minimum are at least 0,
+/// and those without a maximum at most a thousand major units more than their minimum.
+pub(crate) fn produce_amount<R: Rng + ?Sized>(
+    amount: &NumberType,
+    currency: &str,
+    rng: &mut R,
+) -> serde_json::Value {
+    let scale = 10f64.powi(decimal_places(currency));
+    match amount {
+        NumberType::Integer { min, max } => {
+            let min = if *min == i64::MIN { 0 } else { *min };
+            let max = if *max == i64::MAX {
+                min.saturating_add((UNBOUNDED_AMOUNTS * scale) as i64)
+            } else {
+                *max
+            };
+            serde_json::json!(rng.gen_range(min..=max.max(min)))
+        }
+        _ => {
+            let (min, max) = amount.bounds();
+            let min = if min.is_finite() { min } else { 0.0 };
+            let max = if max.is_finite() {
+                max
+            } else {
+                min + UNBOUNDED_AMOUNTS
+            };
+            let (min, max) = ((min * scale).ceil(), (max * scale).floor());
+            let minor = if min < max {
+                rng.gen_range(min..=max).round()
+            } else {
+                min
+            };
+            serde_json::json!(minor / scale)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn currencies_are_in_order() {
+        assert!(CURRENCIES.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(COMMON_CURRENCIES
+            .iter()
+            .all(|currency| is_currency(currency)));
+        assert!(is_currency("EUR"));
+        assert!(!is_currency("usd"));
+        assert!(!is_currency("ABC"));
+    }
+
+    #[test]
+    fn produces_amounts_in_the_minor_unit_of_the_currency() {
+        let decimal = NumberType::Float {
+            min: 1.0,
+            max: 500.0,
+            non_finite: BTreeSet::new(),
+        };
+        for _ in 0..50 {
+            let dollars = produce_amount(&decimal, "USD", &mut thread_rng());
+            let cents = dollars.as_f64().unwrap() * 100.0;
+            assert!((cents - cents.round()).abs() < 1e-6, "{}", dollars);
+            let yen = produce_amount(&decimal, "JPY", &mut thread_rng());
+            assert_eq!(yen.as_f64().unwrap().fract(), 0.0, "{}", yen);
+            assert!((1.0..=500.0).contains(&yen.as_f64().unwrap()), "{}", yen);
+        }
+        let minor_units = NumberType::Integer { min: 100, max: 900 };
+        let amount = produce_amount(&minor_units, "USD", &mut thread_rng());
+        assert!(amount.is_i64() && (100..=900).contains(&amount.as_i64().unwrap()));
+
+        let currencies = BTreeSet::from(["CHF".to_string()]);
+        assert_eq!(produce_currency(&currencies, &mut thread_rng()), "CHF");
+        assert!(is_currency(&produce_currency(
+            &BTreeSet::new(),
+            &mut thread_rng()
+        )));
+    }
+}
@@ -0,0 +1,122 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::produce_options::{field_path, items_path};
+
+/// The distribution of the numbers seen at a path, by their mean and standard deviation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    /// The number of numbers seen.
+    pub count: usize,
+    /// Their mean.
+    pub mean: f64,
+    /// Their (population) standard deviation, which is 0 if they were all the same.
+    pub std_dev: f64,
+}
+
+/// The running moments of the numbers at a path, as in Welford's algorithm, so that the mean
+/// and standard deviation are accurate without holding on to the numbers.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Moments {
+    count: usize,
+    mean: f64,
+    /// The sum of the squares of the differences from the mean.
+    m2: f64,
+}
+
+/// The distributions of the numbers at the paths of some data, such as `orders[].total`, as
+/// used by [`ProduceOptions`](crate::ProduceOptions), so that numbers can be produced
+/// around the same mean rather than spread evenly over their range.
+///
+/// # Example
+///
+/// ```
+/// use drivel::NumberStats;
+/// use serde_json::json;
+///
+/// let mut stats = NumberStats::default();
+/// for score in [2, 4, 4, 4, 5, 5, 7, 9] {
+///     stats.add(&json!({"score": score}));
+/// }
+///
+/// let score = stats.get("score").unwrap();
+/// assert_eq!(score.count, 8);
+/// assert_eq!(score.mean, 5.0);
+/// assert_eq!(score.std_dev, 2.0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NumberStats {
+    paths: HashMap<String, Moments>,
+}
+
+impl NumberStats {
+    /// Adds the numbers in a value, at the root of the data, to the statistics. Values that are
+    /// all inferred as one schema, such as JSON lines, are added one by one.
+    pub fn add(&mut self, value: &Value) {
+        self.add_at(value, "");
+    }
+
+    fn add_at(&mut self, value: &Value, path: &str) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    self.add_at(field, &field_path(path, key));
+                }
+            }
+            Value::Array(items) => {
+                let path = items_path(path);
+                for item in items {
+                    self.add_at(item, &path);
+                }
+            }
+            Value::Number(number) => {
+                let Some(number) = number.as_f64() else {
+                    return;
+                };
+                let moments = self.paths.entry(path.to_string()).or_default();
+                moments.count += 1;
+                let delta = number - moments.mean;
+                moments.mean += delta / moments.count as f64;
+                moments.m2 += delta * (number - moments.mean);
+            }
+            _ => {}
+        }
+    }
+
+    /// The distribution of the numbers at `path`, if any were seen there.
+    pub fn get(&self, path: &str) -> Option<Distribution> {
+        let moments = self.paths.get(path)?;
+        Some(Distribution {
+            count: moments.count,
+            mean: moments.mean,
+            std_dev: (moments.m2 / moments.count as f64).sqrt(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tracks_the_numbers_of_nested_fields_and_array_elements() {
+        let mut stats = NumberStats::default();
+        stats.add(&json!({"prices": [1.5, 2.5], "address": {"floor": 3}, "name": "a"}));
+        stats.add(&json!({"prices": [], "address": {"floor": null}}));
+        stats.add(&json!({"prices": [3.5], "address": {"floor": 5}}));
+
+        assert_eq!(
+            stats.get("prices[]"),
+            Some(Distribution {
+                count: 3,
+                mean: 2.5,
+                std_dev: (2.0f64 / 3.0).sqrt()
+            })
+        );
+        let floor = stats.get("address.floor").unwrap();
+        assert_eq!((floor.count, floor.mean, floor.std_dev), (2, 4.0, 1.0));
+        assert_eq!(stats.get("name"), None);
+        assert_eq!(stats.get("address"), None);
+    }
+}
@@ -0,0 +1,375 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    definitions::factor_objects, schema::to_json_schema_with, JsonSchemaOptions, SchemaState,
+};
+
+/// The versions of OpenAPI that a schema can be described for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OpenApiVersion {
+    /// OpenAPI 3.0, whose schemas are an extended subset of JSON Schema draft 4. Nullable
+    /// values are marked with `nullable: true`.
+    #[value(name = "3.0")]
+    V3_0,
+    /// OpenAPI 3.1, whose schemas are JSON Schema draft 2020-12. Nullable values have a
+    /// `null` type.
+    #[default]
+    #[value(name = "3.1")]
+    V3_1,
+}
+
+/// Options for customising the OpenAPI documents generated for a schema.
+#[derive(Debug, Default, Clone)]
+pub struct OpenApiOptions {
+    /// The version of OpenAPI to generate a document for.
+    pub version: OpenApiVersion,
+    /// The name of the schema under `components/schemas`. Defaults to "InferredSchema".
+    pub name: Option<String>,
+    /// Whether every nested object with properties becomes a component schema of its own,
+    /// referred to with a `$ref`, instead of being nested in the schema of its parent. Objects
+    /// of the same shape share a component then, as with
+    /// [`JsonSchemaOptions::dedupe_definitions`].
+    pub split_components: bool,
+}
+
+const DEFS_PREFIX: &str = "#/$defs/";
+const COMPONENTS_PREFIX: &str = "#/components/schemas/";
+
+/// Points `$ref`s to definitions in `$defs` at the component schemas they are moved to.
+fn rewrite_refs(schema: &mut Value) {
+    match schema {
+        Value::Object(schema_obj) => {
+            for (key, value) in schema_obj.iter_mut() {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => {
+                        if let Some(name) = reference.strip_prefix(DEFS_PREFIX) {
+                            *reference = format!("{}{}", COMPONENTS_PREFIX, name);
+                        }
+                    }
+                    (_, value) => rewrite_refs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+fn is_null_schema(schema: &Value) -> bool {
+    schema.as_object().is_some_and(|s| s.len() == 1) && schema["type"] == "null"
+}
+
+/// Rewrites the JSON Schema draft 2020-12 keywords that OpenAPI 3.0 schemas don't support into
+/// their OpenAPI 3.0 equivalents.
+fn downgrade_to_3_0(schema: &mut Value) {
+    let Some(schema_obj) = schema.as_object_mut() else {
+        if let Value::Array(values) = schema {
+            values.iter_mut().for_each(downgrade_to_3_0);
+        }
+        return;
+    };
+
+    // nullable values have a type of ["...", "null"], or a null variant, which become
+    // `nullable: true`
+    let mut nullable = false;
+    if let Some(Value::Array(types)) = schema_obj.get("type") {
+        let non_null: Vec<Value> = types.iter().filter(|t| *t != "null").cloned().collect();
+        nullable = non_null.len() < types.len();
+        let type_value = match non_null.len() {
+            1 => non_null.into_iter().next().unwrap(),
+            _ => Value::Array(non_null),
+        };
+        schema_obj.insert("type".to_string(), type_value);
+    }
+    if let Some(Value::Array(variants)) = schema_obj.get_mut("oneOf") {
+        let before = variants.len();
+        variants.retain(|variant| !is_null_schema(variant));
+        nullable |= variants.len() < before;
+    }
+    if schema_obj.get("type").is_some_and(|t| t == "null") {
+        // there is no null type, so only allow the null value
+        schema_obj.remove("type");
+        schema_obj.insert("enum".to_string(), Value::Array(vec![Value::Null]));
+        nullable = true;
+    }
+    if nullable {
+        schema_obj.insert("nullable".to_string(), Value::Bool(true));
+    }
+
+    // tuples (such as coordinates) are described by a single schema for all of their items
+    if let Some(Value::Array(positions)) = schema_obj.remove("prefixItems") {
+        let mut distinct: Vec<Value> = vec![];
+        for position in positions {
+            if !distinct.contains(&position) {
+                distinct.push(position);
+            }
+        }
+        let items = match distinct.len() {
+            1 => distinct.pop().unwrap(),
+            _ => serde_json::json!({ "oneOf": distinct }),
+        };
+        schema_obj.insert("items".to_string(), items);
+    }
+
+    // exclusive bounds are booleans modifying `minimum` and `maximum`
+    for (exclusive, inclusive) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        if let Some(bound @ Value::Number(_)) = schema_obj.remove(exclusive) {
+            schema_obj.insert(inclusive.to_string(), bound);
+            schema_obj.insert(exclusive.to_string(), Value::Bool(true));
+        }
+    }
+
+    for value in schema_obj.values_mut() {
+        downgrade_to_3_0(value);
+    }
+}
+
+/// Generates an OpenAPI document with the JSON Schema of `schema` as a component schema, reusing
+/// the JSON Schema options where they apply. Definitions in `$defs` become component schemas
+/// of their own, as do all nested objects with [`OpenApiOptions::split_components`].
+pub(crate) fn to_openapi_document(
+    schema: &SchemaState,
+    json_schema_options: &JsonSchemaOptions,
+    options: &OpenApiOptions,
+) -> Value {
+    let title = json_schema_options
+        .title
+        .as_deref()
+        .unwrap_or("Inferred Schema");
+    let name = options.name.as_deref().unwrap_or("InferredSchema");
+
+    let mut component = if options.split_components {
+        // the objects that are reused are among those factored out anyway
+        let json_schema_options = JsonSchemaOptions {
+            dedupe_definitions: false,
+            ..json_schema_options.clone()
+        };
+        let mut component = to_json_schema_with(schema, &json_schema_options);
+        factor_objects(&mut component, name);
+        component
+    } else {
+        to_json_schema_with(schema, json_schema_options)
+    };
+    let mut schemas = Map::new();
+    if let Some(Value::Object(defs)) = component.as_object_mut().and_then(|c| c.remove("$defs")) {
+        schemas.extend(defs);
+    }
+    component["description"] = serde_json::json!("Schema inferred by drivel from sample data");
+    schemas.insert(name.to_string(), component);
+
+    let mut schemas = Value::Object(schemas);
+    rewrite_refs(&mut schemas);
+    let openapi = match options.version {
+        OpenApiVersion::V3_0 => {
+            downgrade_to_3_0(&mut schemas);
+            "3.0.3"
+        }
+        OpenApiVersion::V3_1 => "3.1.0",
+    };
+
+    serde_json::json!({
+        "openapi": openapi,
+        "info": { "title": title, "version": "1.0.0" },
+        "paths": {},
+        "components": { "schemas": schemas }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NumberType, NumericBounds, StringType};
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    fn options(version: OpenApiVersion) -> OpenApiOptions {
+        OpenApiOptions {
+            version,
+            name: Some("User".to_string()),
+            split_components: false,
+        }
+    }
+
+    fn user_schema() -> SchemaState {
+        SchemaState::Object {
+            required: IndexMap::from([
+                (
+                    "age".to_string(),
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 99 }),
+                ),
+                (
+                    "location".to_string(),
+                    SchemaState::Coordinates(vec![
+                        NumberType::Float {
+                            min: 4.0,
+                            max: 5.0,
+                            non_finite: Default::default(),
+                        },
+                        NumberType::Float {
+                            min: 52.0,
+                            max: 53.0,
+                            non_finite: Default::default(),
+                        },
+                    ]),
+                ),
+            ]),
+            optional: IndexMap::from([(
+                "email".to_string(),
+                SchemaState::Nullable(Box::new(SchemaState::String(StringType::Email))),
+            )]),
+            access: Default::default(),
+            annotations: Default::default(),
+        }
+    }
+
+    #[test]
+    fn wraps_schema_in_components() {
+        let document = to_openapi_document(
+            &user_schema(),
+            &JsonSchemaOptions::default(),
+            &options(OpenApiVersion::V3_1),
+        );
+
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["info"]["title"], "Inferred Schema");
+        let user = &document["components"]["schemas"]["User"];
+        assert_eq!(user["type"], "object");
+        assert_eq!(
+            user["properties"]["email"],
+            json!({"type": ["string", "null"], "format": "email"})
+        );
+        assert!(user.get("$schema").is_none());
+    }
+
+    #[test]
+    fn marks_nullable_values_for_3_0() {
+        let schema = SchemaState::Union(vec![
+            SchemaState::Null,
+            SchemaState::Object {
+                required: IndexMap::new(),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            },
+        ]);
+        let json_options = JsonSchemaOptions {
+            numeric_bounds: Some(NumericBounds::Exclusive),
+            ..Default::default()
+        };
+
+        let document = to_openapi_document(
+            &user_schema(),
+            &json_options,
+            &options(OpenApiVersion::V3_0),
+        );
+        assert_eq!(document["openapi"], "3.0.3");
+        let user = &document["components"]["schemas"]["User"];
+        assert_eq!(
+            user["properties"]["email"],
+            json!({"type": "string", "format": "email", "nullable": true})
+        );
+        assert_eq!(
+            user["properties"]["age"],
+            json!({
+                "type": "integer",
+                "minimum": 0,
+                "exclusiveMinimum": true,
+                "maximum": 100,
+                "exclusiveMaximum": true
+            })
+        );
+        let location = &user["properties"]["location"];
+        assert!(location.get("prefixItems").is_none());
+        assert_eq!(location["items"]["oneOf"].as_array().unwrap().len(), 2);
+
+        let document = to_openapi_document(
+            &SchemaState::Nullable(Box::new(schema)),
+            &JsonSchemaOptions::default(),
+            &options(OpenApiVersion::V3_0),
+        );
+        let union = &document["components"]["schemas"]["User"];
+        assert_eq!(union["nullable"], true);
+        assert!(union["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|variant| variant["type"] == "object"));
+    }
+
+    #[test]
+    fn moves_definitions_into_components() {
+        let address = || SchemaState::Object {
+            required: IndexMap::from([(
+                "city".to_string(),
+                SchemaState::String(StringType::Hostname),
+            )]),
+            optional: IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let schema = SchemaState::Object {
+            required: IndexMap::from([
+                ("billing".to_string(), address()),
+                ("shipping".to_string(), address()),
+            ]),
+            optional: IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let json_options = JsonSchemaOptions {
+            dedupe_definitions: true,
+            ..Default::default()
+        };
+
+        let document = to_openapi_document(&schema, &json_options, &options(OpenApiVersion::V3_1));
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(schemas["billing"]["type"], "object");
+        assert_eq!(
+            schemas["User"]["properties"]["shipping"]["$ref"],
+            "#/components/schemas/billing"
+        );
+        assert!(schemas["User"].get("$defs").is_none());
+    }
+
+    #[test]
+    fn splits_nested_objects_into_components() {
+        let schema = SchemaState::Array {
+            min_length: 1,
+            max_length: 1,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: IndexMap::from([
+                    ("id".to_string(), SchemaState::Boolean),
+                    ("profile".to_string(), user_schema()),
+                ]),
+                optional: IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        };
+        let options = OpenApiOptions {
+            split_components: true,
+            ..options(OpenApiVersion::V3_1)
+        };
+
+        let document = to_openapi_document(&schema, &JsonSchemaOptions::default(), &options);
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(
+            schemas["User"]["items"]["$ref"],
+            "#/components/schemas/User_item"
+        );
+        assert_eq!(
+            schemas["User_item"]["properties"]["profile"]["$ref"],
+            "#/components/schemas/profile"
+        );
+        assert_eq!(
+            schemas["profile"]["properties"]["email"],
+            json!({"type": ["string", "null"], "format": "email"})
+        );
+        assert_eq!(schemas.as_object().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,128 @@
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde_json::Value;
+
+use crate::columnar::{invalid, read_batches};
+use crate::DrivelError;
+
+/// Reads the rows of a Parquet file as records, each an object of its columns, with nested
+/// groups as objects, and lists and maps as arrays and objects. Dates are read as `YYYY-MM-DD`
+/// strings and timestamps as RFC 3339 date-times, as drivel infers them from JSON; binary
+/// values that aren't UTF-8 are read as base64. With `max_rows`, reading stops after that many
+/// rows.
+///
+/// Pages may be uncompressed, or compressed with snappy, gzip, LZ4 or zstd. Columns may be of
+/// any of the encodings of the format, but encrypted files aren't supported.
+///
+/// # Example
+///
+/// ```no_run
+/// let file = std::fs::read("events.parquet").unwrap();
+/// let records = drivel::read_parquet(&file, Some(1000)).unwrap();
+/// let schema = drivel::infer_schema_from_iter(records, &drivel::InferenceOptions::default());
+/// ```
+pub fn read_parquet(file: &[u8], max_rows: Option<usize>) -> Result<Vec<Value>, DrivelError> {
+    let mut builder =
+        ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(file)).map_err(invalid)?;
+    if let Some(max_rows) = max_rows {
+        builder = builder.with_limit(max_rows);
+    }
+    read_batches(builder.build().map_err(invalid)?, max_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::types::Date32Type;
+    use arrow_array::{
+        Array, ArrayRef, FixedSizeBinaryArray, Int64Array, ListArray, RecordBatch, StringArray,
+    };
+    use arrow_schema::extension::Uuid;
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+    use parquet::file::properties::WriterProperties;
+    use serde_json::json;
+
+    use super::*;
+
+    /// A file of the Parquet writer, of rows in row groups of two, with pages compressed by
+    /// `compression`.
+    fn file(compression: Compression) -> Vec<u8> {
+        let days = ListArray::from_iter_primitive::<Date32Type, _, _>([
+            Some(vec![Some(0), Some(1)]),
+            None,
+            Some(vec![]),
+        ]);
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("days", days.data_type().clone(), true),
+            Field::new("uuid", DataType::FixedSizeBinary(16), false).with_extension_type(Uuid),
+        ]);
+        let uuids = [[0x55; 16], [0; 16], [0xff; 16]];
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+                Arc::new(StringArray::from(vec![Some("ab"), None, Some("ab")])),
+                Arc::new(days),
+                Arc::new(FixedSizeBinaryArray::try_from_iter(uuids.iter()).unwrap()),
+            ],
+        )
+        .unwrap();
+        let properties = WriterProperties::builder()
+            .set_compression(compression)
+            .set_max_row_group_row_count(Some(2))
+            .build();
+        let mut file = vec![];
+        let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), Some(properties)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        file
+    }
+
+    #[test]
+    fn reads_the_rows_of_files_of_the_parquet_writer() {
+        let records = [
+            json!({
+                "id": 1,
+                "name": "ab",
+                "days": ["1970-01-01", "1970-01-02"],
+                "uuid": "55555555-5555-5555-5555-555555555555",
+            }),
+            json!({
+                "id": 2,
+                "name": null,
+                "days": null,
+                "uuid": "00000000-0000-0000-0000-000000000000",
+            }),
+            json!({
+                "id": 3,
+                "name": "ab",
+                "days": [],
+                "uuid": "ffffffff-ffff-ffff-ffff-ffffffffffff",
+            }),
+        ];
+        for compression in [
+            Compression::UNCOMPRESSED,
+            Compression::SNAPPY,
+            Compression::GZIP(GzipLevel::default()),
+            Compression::LZ4_RAW,
+            Compression::ZSTD(ZstdLevel::default()),
+        ] {
+            let file = file(compression);
+            assert_eq!(
+                read_parquet(&file, None).unwrap(),
+                records,
+                "{}",
+                compression
+            );
+            assert_eq!(read_parquet(&file, Some(1)).unwrap(), records[..1]);
+            assert_eq!(read_parquet(&file, Some(3)).unwrap(), records);
+            assert!(read_parquet(&file[..file.len() - 1], None).is_err());
+        }
+        assert!(read_parquet(b"PAR1 not quite parquet PAR1", None).is_err());
+    }
+}
@@ -1,17 +1,25 @@
-use crate::schema::{NumberType, SchemaState, StringType};
+use crate::all_of::flatten_all_of;
+use crate::date_format::is_valid_date_format;
+use crate::diagnostics::warn;
+use crate::money::{is_currency, money_keys};
+use crate::pattern::is_supported_pattern;
+use crate::refs::{has_refs, refers_to, resolve_refs};
+use crate::schema::{
+    geo_point_keys, CharacterClass, FieldAccess, NonFiniteNumber, NumberType, SchemaState,
+    StringType,
+};
+use indexmap::IndexMap;
 use serde_json::{Map, Value};
 use std::fmt;
 
-type ObjectProperties = (
-    std::collections::HashMap<String, SchemaState>,
-    std::collections::HashMap<String, SchemaState>,
-);
+type ObjectProperties = (IndexMap<String, SchemaState>, IndexMap<String, SchemaState>);
 
 #[derive(Debug)]
 pub enum ParseSchemaError {
     InvalidSchema(String),
     UnsupportedFeature(String),
     ValidationFailed(String),
+    InvalidAvroSchema(String),
 }
 
 impl fmt::Display for ParseSchemaError {
@@ -22,33 +30,122 @@ impl fmt::Display for ParseSchemaError {
                 write!(f, "Unsupported JSON Schema feature: {}", msg)
             }
             ParseSchemaError::ValidationFailed(msg) => write!(f, "Validation failed: {}", msg),
+            ParseSchemaError::InvalidAvroSchema(msg) => write!(f, "Invalid Avro schema: {}", msg),
         }
     }
 }
 
 impl std::error::Error for ParseSchemaError {}
 
+/// The languages that a schema read with `--from-schema` can be written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    /// JSON Schema, or the schema of an OpenAPI document.
+    #[default]
+    JsonSchema,
+    /// Apache Avro schema, such as that of the messages of a Kafka topic.
+    Avro,
+}
+
+impl SchemaFormat {
+    /// Parses a schema in this language into the schema it describes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{SchemaFormat, SchemaState};
+    /// use serde_json::json;
+    ///
+    /// let schema = SchemaFormat::Avro.parse(&json!("boolean")).unwrap();
+    /// assert_eq!(schema, SchemaState::Boolean);
+    /// ```
+    pub fn parse(self, schema: &Value) -> Result<SchemaState, ParseSchemaError> {
+        match self {
+            SchemaFormat::JsonSchema => parse_json_schema(schema),
+            SchemaFormat::Avro => crate::parse_avro_schema(schema),
+        }
+    }
+}
+
+impl fmt::Display for SchemaFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaFormat::JsonSchema => write!(f, "JSON Schema"),
+            SchemaFormat::Avro => write!(f, "Avro schema"),
+        }
+    }
+}
+
+/// Parses a JSON Schema into the schema it describes, such as to produce data from it. Local
+/// `$ref`s to definitions in `$defs` or `definitions` are resolved first, with recursive ones
+/// expanded a few levels deep.
 pub fn parse_json_schema(schema_json: &Value) -> Result<SchemaState, ParseSchemaError> {
+    if has_refs(schema_json) {
+        return parse_schema(&resolve_refs(schema_json)?);
+    }
+    parse_schema(schema_json)
+}
+
+fn parse_schema(schema_json: &Value) -> Result<SchemaState, ParseSchemaError> {
     let schema_obj = schema_json
         .as_object()
         .ok_or_else(|| ParseSchemaError::InvalidSchema("Schema must be an object".to_string()))?;
 
-    if let Some(any_of) = schema_obj.get("anyOf") {
-        if let Some(nullable_schema) = try_parse_nullable_anyof_oneof(any_of)? {
-            return Ok(nullable_schema);
+    // OpenAPI 3.0 has no `null` type, and marks schemas that allow null with `nullable: true`
+    // instead, which means the same as a `null` type alongside the others
+    match parse_schema_object(schema_obj)? {
+        schema @ (SchemaState::Null | SchemaState::Nullable(_)) => Ok(schema),
+        schema if schema_obj.get("nullable") == Some(&Value::Bool(true)) => {
+            Ok(SchemaState::Nullable(Box::new(schema)))
         }
-        return Err(ParseSchemaError::UnsupportedFeature(
-            "anyOf patterns other than nullable not supported yet".to_string(),
-        ));
+        schema => Ok(schema),
     }
+}
 
-    if let Some(one_of) = schema_obj.get("oneOf") {
-        if let Some(nullable_schema) = try_parse_nullable_anyof_oneof(one_of)? {
-            return Ok(nullable_schema);
+fn parse_schema_object(schema_obj: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
+    // resolving `$ref`s leaves only those within the recursive definitions they point at, by
+    // the anchors of the definitions
+    if let Some(reference) = schema_obj.get("$ref") {
+        return match reference.as_str().and_then(|r| r.strip_prefix('#')) {
+            Some(name) if !name.is_empty() && !name.starts_with('/') => {
+                Ok(SchemaState::Reference(name.to_string()))
+            }
+            _ => Err(ParseSchemaError::InvalidSchema(format!(
+                "$ref {} must point at an anchor",
+                reference
+            ))),
+        };
+    }
+    if let Some(Value::String(name)) = schema_obj.get("$anchor") {
+        let reference = Value::String(format!("#{}", name));
+        if schema_obj
+            .values()
+            .any(|value| refers_to(value, &reference))
+        {
+            return parse_recursive(name, schema_obj);
+        }
+    }
+    if schema_obj.contains_key("allOf") {
+        return parse_schema_object(&flatten_all_of(schema_obj)?);
+    }
+    // values are the `const` whatever else they could be, and one of an `enum` of strings or
+    // integers is parsed along with the other keywords of its type
+    if let Some(value) = schema_obj.get("const") {
+        return parse_values(std::slice::from_ref(value));
+    }
+    if let Some(values) = schema_obj.get("enum") {
+        let typed = matches!(
+            schema_obj.get("type").and_then(Value::as_str),
+            Some("string" | "integer")
+        );
+        if !typed {
+            return parse_values(require_array(values, "Enum field")?);
+        }
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(variants) = schema_obj.get(keyword) {
+            return parse_union(schema_obj, keyword, variants);
         }
-        return Err(ParseSchemaError::UnsupportedFeature(
-            "oneOf patterns other than nullable not supported yet".to_string(),
-        ));
     }
 
     let type_field = schema_obj.get("type").ok_or_else(|| {
@@ -135,57 +232,147 @@ fn parse_nullable_type(
     Ok(SchemaState::Nullable(Box::new(inner_schema)))
 }
 
-fn try_parse_nullable_anyof_oneof(
-    schema_array: &Value,
-) -> Result<Option<SchemaState>, ParseSchemaError> {
-    let array = require_array(schema_array, "anyOf/oneOf")?;
-
-    if array.len() != 2 {
-        return Ok(None); // Not a simple nullable pattern
+/// Parses the variants of an `anyOf` or `oneOf` as a union of their schemas, each combined with
+/// the other keywords of the schema, such as a `type` or `properties` that all of them share. A
+/// `null` variant makes the union nullable, and a single other variant is the schema itself.
+/// With the `propertyName` of a `discriminator`, a union of objects is a tagged union if each
+/// variant requires that field to be one or more strings of its own.
+fn parse_union(
+    schema_obj: &Map<String, Value>,
+    keyword: &str,
+    variants: &Value,
+) -> Result<SchemaState, ParseSchemaError> {
+    let variants = require_array(variants, keyword)?;
+    if variants.is_empty() {
+        return Err(ParseSchemaError::InvalidSchema(format!(
+            "{} must have at least one schema",
+            keyword
+        )));
     }
-
-    let mut null_schema = None;
-    let mut type_schema = None;
-
-    for item in array {
-        let item_obj = require_object(item, "anyOf/oneOf items")?;
-
-        if let Some(type_field) = item_obj.get("type") {
-            if let Some(type_str) = type_field.as_str() {
-                if type_str == "null" {
-                    null_schema = Some(item);
-                } else if type_schema.is_none() {
-                    type_schema = Some(item);
-                } else {
-                    return Ok(None); // Multiple non-null types, not a simple nullable pattern
-                }
-            } else {
-                return Ok(None); // Complex type, not a simple nullable pattern
-            }
+    let discriminator = schema_obj
+        .get("discriminator")
+        .and_then(|discriminator| discriminator.get("propertyName"))
+        .and_then(Value::as_str);
+    let shared: Map<String, Value> = schema_obj
+        .iter()
+        .filter(|(key, _)| {
+            *key != keyword
+                && !ANNOTATION_KEYWORDS.contains(&key.as_str())
+                && !["discriminator", "nullable", "$schema", "$id"].contains(&key.as_str())
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    let mut nullable = false;
+    let mut schemas = vec![];
+    let mut counts = vec![];
+    for variant in variants {
+        let count = variant.get("x-drivel-count").and_then(Value::as_u64);
+        let variant = if shared.is_empty() {
+            parse_schema(variant)?
         } else {
-            return Ok(None); // No type field, not a simple nullable pattern
+            require_object(variant, &format!("{} items", keyword))?;
+            parse_schema(&serde_json::json!({ "allOf": [shared, variant] }))?
+        };
+        match variant {
+            SchemaState::Null => nullable = true,
+            SchemaState::Nullable(inner) => {
+                nullable = true;
+                schemas.push(*inner);
+                counts.push(count);
+            }
+            variant => {
+                schemas.push(variant);
+                counts.push(count);
+            }
         }
     }
-
-    if null_schema.is_some() && type_schema.is_some() {
-        let type_obj = type_schema.unwrap().as_object().unwrap();
-        let type_field = type_obj.get("type").unwrap();
-        let type_str = type_field.as_str().unwrap();
-
-        let inner_schema = parse_single_type(type_obj, type_str)?;
-        Ok(Some(SchemaState::Nullable(Box::new(inner_schema))))
+    // the variants of a mixed schema written by drivel carry how many values of each were seen
+    let counts: Option<Vec<_>> = counts.into_iter().collect();
+
+    let union = match (schemas.len(), discriminator) {
+        (0, _) => return Ok(SchemaState::Null),
+        (1, _) => schemas.pop().unwrap(),
+        (_, None) if keyword == "anyOf" && counts.is_some() => SchemaState::Mixed(
+            schemas
+                .into_iter()
+                .zip(counts.unwrap_or_default())
+                .map(|(schema, count)| (schema, count as usize))
+                .collect(),
+        ),
+        (_, Some(tag)) => tagged_union(tag, &schemas).unwrap_or(SchemaState::Union(schemas)),
+        (_, None) => SchemaState::Union(schemas),
+    };
+    if nullable {
+        Ok(SchemaState::Nullable(Box::new(union)))
     } else {
-        Ok(None) // Not a nullable pattern
+        Ok(union)
     }
 }
 
+/// The object variants of a union as a tagged union, if each of them requires `tag` to be one
+/// of an enum of strings, and no two have a value in common.
+fn tagged_union(tag: &str, variants: &[SchemaState]) -> Option<SchemaState> {
+    let mut tagged = std::collections::BTreeMap::new();
+    for variant in variants {
+        let SchemaState::Object { required, .. } = variant else {
+            return None;
+        };
+        let SchemaState::String(StringType::Enum { variants: values }) = required.get(tag)? else {
+            return None;
+        };
+        for value in values {
+            if tagged.insert(value.clone(), variant.clone()).is_some() {
+                return None;
+            }
+        }
+    }
+    Some(SchemaState::TaggedUnion {
+        tag: tag.to_string(),
+        variants: tagged,
+    })
+}
+
 fn parse_string_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
     let (min_length, max_length) = parse_string_length_constraints(schema_obj)?;
 
     if let Some(enum_value) = schema_obj.get("enum") {
         parse_string_enum(enum_value)
+    } else if let Some(string_type) = parse_drivel_string_type(schema_obj) {
+        Ok(SchemaState::String(string_type))
     } else if let Some(format_value) = schema_obj.get("format") {
         parse_string_with_format(format_value, min_length, max_length)
+    } else if let Some(width) = parse_fixed_width_numeric_pattern(schema_obj) {
+        Ok(SchemaState::String(StringType::FixedWidthNumeric { width }))
+    } else if let Some(class) = schema_obj
+        .get("pattern")
+        .and_then(Value::as_str)
+        .and_then(CharacterClass::from_pattern)
+    {
+        // the pattern needs at least one character, and strings of no particular length are
+        // produced up to 32 characters long, like those of no particular format
+        let min_length = min_length.unwrap_or(1).max(1);
+        Ok(SchemaState::String(StringType::Charset {
+            class,
+            min_length,
+            max_length: max_length.unwrap_or(min_length.max(32)),
+        }))
+    } else if let Some(pattern) = schema_obj.get("pattern").and_then(Value::as_str) {
+        if is_supported_pattern(pattern) {
+            Ok(SchemaState::String(StringType::Pattern {
+                pattern: pattern.to_string(),
+                min_length,
+                max_length,
+            }))
+        } else {
+            warn(format!(
+                "pattern '{}' is not supported for producing strings, ignoring",
+                pattern
+            ));
+            Ok(SchemaState::String(create_unknown_string_type(
+                min_length, max_length,
+            )))
+        }
     } else {
         Ok(SchemaState::String(create_unknown_string_type(
             min_length, max_length,
@@ -193,6 +380,78 @@ fn parse_string_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, Par
     }
 }
 
+/// Recognises the `x-drivel-type` extensions emitted for string types that JSON Schema has no
+/// format for.
+fn parse_drivel_string_type(schema_obj: &Map<String, Value>) -> Option<StringType> {
+    match schema_obj.get("x-drivel-type")?.as_str()? {
+        "credit-card" => Some(StringType::CreditCard),
+        "iban" => Some(StringType::Iban),
+        "isbn-10" => Some(StringType::Isbn10),
+        "isbn-13" => Some(StringType::Isbn13),
+        "ean-13" => Some(StringType::Ean13),
+        "datetime-rfc2822" => Some(StringType::DateTimeRFC2822),
+        "datetime-custom" => {
+            let format = schema_obj.get("x-drivel-date-format")?.as_str()?;
+            is_valid_date_format(format).then(|| StringType::CustomDateTime {
+                formats: vec![format.to_string()],
+            })
+        }
+        "open-enum" => {
+            let variants = schema_obj
+                .get("x-drivel-enum")?
+                .as_array()?
+                .iter()
+                .map(|variant| variant.as_str().map(str::to_string))
+                .collect::<Option<std::collections::BTreeSet<_>>>()?;
+            let other_ratio = schema_obj.get("x-drivel-other-ratio")?.as_f64()?;
+            (!variants.is_empty() && (0.0..=1.0).contains(&other_ratio)).then(|| {
+                StringType::OpenEnum {
+                    variants,
+                    other_ratio,
+                    other: Box::new(create_unknown_string_type(None, None)),
+                }
+            })
+        }
+        "numeric-string" => {
+            let minimum = schema_obj.get("x-drivel-minimum");
+            let maximum = schema_obj.get("x-drivel-maximum");
+            if let (Some(min), Some(max)) = (
+                minimum.and_then(Value::as_f64),
+                maximum.and_then(Value::as_f64),
+            ) {
+                if min > max {
+                    return None;
+                }
+            }
+            let number = match schema_obj.get("x-drivel-number-type")?.as_str()? {
+                "integer" => NumberType::Integer {
+                    min: minimum.and_then(Value::as_i64).unwrap_or(i64::MIN),
+                    max: maximum.and_then(Value::as_i64).unwrap_or(i64::MAX),
+                },
+                "number" => NumberType::Float {
+                    min: minimum.and_then(Value::as_f64).unwrap_or(f64::NEG_INFINITY),
+                    max: maximum.and_then(Value::as_f64).unwrap_or(f64::INFINITY),
+                    non_finite: Default::default(),
+                },
+                _ => return None,
+            };
+            Some(StringType::Numeric { number })
+        }
+        _ => None,
+    }
+}
+
+/// Recognises the `^[0-9]{n}$` pattern emitted for fixed-width numeric strings.
+fn parse_fixed_width_numeric_pattern(schema_obj: &Map<String, Value>) -> Option<usize> {
+    schema_obj
+        .get("pattern")?
+        .as_str()?
+        .strip_prefix("^[0-9]{")?
+        .strip_suffix("}$")?
+        .parse()
+        .ok()
+}
+
 fn validate_min_max_constraint<T: PartialOrd>(
     min: Option<T>,
     max: Option<T>,
@@ -230,24 +489,35 @@ fn parse_string_with_format(
 ) -> Result<SchemaState, ParseSchemaError> {
     let format_str = require_string(format_value, "Format field")?;
 
-    match format_str {
-        "email" => Ok(SchemaState::String(StringType::Email)),
-        "uuid" => Ok(SchemaState::String(StringType::UUID)),
-        "date" => Ok(SchemaState::String(StringType::IsoDate)),
-        "date-time" => Ok(SchemaState::String(StringType::DateTimeISO8601)),
-        "uri" => Ok(SchemaState::String(StringType::Url)),
-        "hostname" => Ok(SchemaState::String(StringType::Hostname)),
+    let string_type = match format_str {
+        "email" => StringType::Email,
+        "uuid" => StringType::UUID,
+        "date" => StringType::IsoDate,
+        "date-time" => StringType::DateTimeISO8601,
+        "uri" => StringType::Url,
+        "hostname" => StringType::Hostname,
+        "ipv4" => StringType::Ipv4,
+        "ipv6" => StringType::Ipv6,
+        "duration" => StringType::Duration,
         _ => {
             // Warn about unsupported format but continue with constraints to avoid breaking parsing
-            eprintln!(
-                "Warning: Unsupported string format '{}', using basic string type",
+            warn(format!(
+                "Unsupported string format '{}', using basic string type",
                 format_str
-            );
-            Ok(SchemaState::String(create_unknown_string_type(
+            ));
+            return Ok(SchemaState::String(create_unknown_string_type(
                 min_length, max_length,
-            )))
-        }
+            )));
+        }
+    };
+    // strings of a format are produced as its values are, of whatever length they are
+    if min_length.is_some() || max_length.is_some() {
+        warn(format!(
+            "minLength and maxLength are not applied to strings of format '{}', ignoring",
+            format_str
+        ));
     }
+    Ok(SchemaState::String(string_type))
 }
 
 fn create_unknown_string_type(min_length: Option<usize>, max_length: Option<usize>) -> StringType {
@@ -259,6 +529,59 @@ fn create_unknown_string_type(min_length: Option<usize>, max_length: Option<usiz
     }
 }
 
+/// Parses a definition that contains values of itself, the `$ref`s to its anchor `name`
+/// within it, which must be avoidable for its values not to be endless.
+fn parse_recursive(
+    name: &str,
+    schema_obj: &Map<String, Value>,
+) -> Result<SchemaState, ParseSchemaError> {
+    let mut definition = schema_obj.clone();
+    definition.remove("$anchor");
+    let schema = parse_schema(&Value::Object(definition))?;
+    if !schema.can_avoid(&|n| n == name) {
+        return Err(ParseSchemaError::InvalidSchema(format!(
+            "the recursive definition {} requires a value of itself, so no value fits it",
+            name
+        )));
+    }
+    Ok(SchemaState::Recursive {
+        name: name.to_string(),
+        schema: Box::new(schema),
+    })
+}
+
+/// The schema of a value that is one of `values`, as of a `const` or an `enum`: an enum of
+/// strings or of integers if they all are, besides any `null`, which makes it nullable, and a
+/// literal of the values otherwise.
+fn parse_values(values: &[Value]) -> Result<SchemaState, ParseSchemaError> {
+    if values.is_empty() {
+        return Err(ParseSchemaError::ValidationFailed(
+            "enum array cannot be empty".to_string(),
+        ));
+    }
+    let nullable = values.iter().any(Value::is_null);
+    let mut non_null: Vec<Value> = vec![];
+    for value in values.iter().filter(|value| !value.is_null()) {
+        if !non_null.contains(value) {
+            non_null.push(value.clone());
+        }
+    }
+    let schema = if non_null.is_empty() {
+        return Ok(SchemaState::Null);
+    } else if non_null.iter().all(Value::is_string) {
+        parse_string_enum(&Value::Array(non_null))?
+    } else if non_null.iter().all(|value| value.as_i64().is_some()) {
+        parse_integer_enum(&Value::Array(non_null))?
+    } else {
+        SchemaState::Literal(non_null)
+    };
+    if nullable {
+        Ok(SchemaState::Nullable(Box::new(schema)))
+    } else {
+        Ok(schema)
+    }
+}
+
 fn parse_string_enum(enum_value: &Value) -> Result<SchemaState, ParseSchemaError> {
     let enum_array = require_array(enum_value, "Enum field")?;
 
@@ -269,7 +592,7 @@ fn parse_string_enum(enum_value: &Value) -> Result<SchemaState, ParseSchemaError
         ));
     }
 
-    let mut variants = std::collections::HashSet::new();
+    let mut variants = std::collections::BTreeSet::new();
 
     for item in enum_array {
         let string_value = require_string(item, "All enum values")?;
@@ -279,20 +602,99 @@ fn parse_string_enum(enum_value: &Value) -> Result<SchemaState, ParseSchemaError
     Ok(SchemaState::String(StringType::Enum { variants }))
 }
 
+fn parse_integer_enum(enum_value: &Value) -> Result<SchemaState, ParseSchemaError> {
+    let enum_array = require_array(enum_value, "Enum field")?;
+
+    if enum_array.is_empty() {
+        return Err(ParseSchemaError::ValidationFailed(
+            "enum array cannot be empty".to_string(),
+        ));
+    }
+
+    let mut variants = std::collections::BTreeMap::new();
+
+    for item in enum_array {
+        let int_value = item.as_i64().ok_or_else(|| {
+            ParseSchemaError::InvalidSchema("All enum values must be integers".to_string())
+        })?;
+        variants.insert(int_value, 1);
+    }
+
+    Ok(SchemaState::Number(NumberType::Enum { variants }))
+}
+
 fn parse_number_type(
     schema_obj: &Map<String, Value>,
     is_integer: bool,
 ) -> Result<SchemaState, ParseSchemaError> {
-    let (min_value, max_value) = parse_number_constraints(schema_obj)?;
-    warn_about_unsupported_number_features(schema_obj);
+    if is_integer {
+        if let Some(enum_value) = schema_obj.get("enum") {
+            return parse_integer_enum(enum_value);
+        }
+    }
+
+    let (min_bound, max_bound) = parse_number_constraints(schema_obj)?;
+    let step = parse_multiple_of(schema_obj, is_integer)?;
 
     if is_integer {
-        let min = min_value.map(|v| v as i64).unwrap_or(i64::MIN);
-        let max = max_value.map(|v| v as i64).unwrap_or(i64::MAX);
+        // exclusive bounds are narrowed to the nearest integer inside the interval
+        let min = match min_bound {
+            Some(NumericBound::Inclusive(v)) => v as i64,
+            Some(NumericBound::Exclusive(v)) => (v.floor() as i64).saturating_add(1),
+            None => i64::MIN,
+        };
+        let max = match max_bound {
+            Some(NumericBound::Inclusive(v)) => v as i64,
+            Some(NumericBound::Exclusive(v)) => (v.ceil() as i64).saturating_sub(1),
+            None => i64::MAX,
+        };
+        validate_min_max_constraint(
+            Some(min),
+            Some(max),
+            "exclusive bounds leave no integers in range",
+        )?;
+        if let Some((start, step)) = parse_sequence(schema_obj) {
+            // a sequence of two values is enough to describe its start and step
+            return Ok(SchemaState::Number(NumberType::Sequence {
+                min,
+                max,
+                start,
+                end: start.saturating_add(step),
+                count: 2,
+                increases: 1,
+            }));
+        }
+        if let Some(flags) = parse_flags(schema_obj) {
+            return Ok(SchemaState::Number(NumberType::Flags {
+                values: flags.into_iter().map(|flag| (flag, 1)).collect(),
+            }));
+        }
+        if let Some(step) = step {
+            let bound = |bound| match bound {
+                i64::MIN => f64::NEG_INFINITY,
+                i64::MAX => f64::INFINITY,
+                bound => bound as f64,
+            };
+            return multiples(step, bound(min), bound(max), true);
+        }
         Ok(SchemaState::Number(NumberType::Integer { min, max }))
     } else {
-        let min = min_value.unwrap_or(f64::NEG_INFINITY);
-        let max = max_value.unwrap_or(f64::INFINITY);
+        // exclusive bounds are narrowed to the nearest representable float inside the
+        // interval, so that values produced from the inclusive range can never equal them
+        let min = match min_bound {
+            Some(NumericBound::Inclusive(v)) => v,
+            Some(NumericBound::Exclusive(v)) => v.next_up(),
+            None => f64::NEG_INFINITY,
+        };
+        let max = match max_bound {
+            Some(NumericBound::Inclusive(v)) => v,
+            Some(NumericBound::Exclusive(v)) => v.next_down(),
+            None => f64::INFINITY,
+        };
+
+        if let Some(step) = step {
+            return multiples(step, min, max, false);
+        }
 
         // Validate that finite ranges don't cause overflow in random generation
         if min.is_finite() && max.is_finite() {
@@ -304,44 +706,102 @@ fn parse_number_type(
             }
         }
 
-        Ok(SchemaState::Number(NumberType::Float { min, max }))
+        Ok(SchemaState::Number(NumberType::Float {
+            min,
+            max,
+            non_finite: parse_non_finite(schema_obj),
+        }))
+    }
+}
+
+/// The tokens listed by the `x-drivel-non-finite` extension emitted for floats that had values
+/// such as `NaN`, ignoring any that aren't known.
+fn parse_non_finite(
+    schema_obj: &Map<String, Value>,
+) -> std::collections::BTreeSet<NonFiniteNumber> {
+    schema_obj
+        .get("x-drivel-non-finite")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|token| NonFiniteNumber::from_token(token.as_str()?))
+        .collect()
+}
+
+/// Recognises the `x-drivel-type: "sequence"` extension emitted for sequences, returning their
+/// start and step.
+fn parse_sequence(schema_obj: &Map<String, Value>) -> Option<(i64, i64)> {
+    if schema_obj.get("x-drivel-type")?.as_str()? != "sequence" {
+        return None;
+    }
+    let start = schema_obj.get("x-drivel-sequence-start")?.as_i64()?;
+    let step = schema_obj.get("x-drivel-sequence-step")?.as_i64()?;
+    (step > 0).then_some((start, step))
+}
+
+/// Recognises the `x-drivel-type: "flags"` extension emitted for bit flags, returning the flags,
+/// which must be powers of two.
+fn parse_flags(schema_obj: &Map<String, Value>) -> Option<Vec<i64>> {
+    if schema_obj.get("x-drivel-type")?.as_str()? != "flags" {
+        return None;
+    }
+    let flags: Vec<i64> = schema_obj
+        .get("x-drivel-flags")?
+        .as_array()?
+        .iter()
+        .map(Value::as_i64)
+        .collect::<Option<_>>()?;
+    (!flags.is_empty() && flags.iter().all(|flag| *flag > 0 && flag.count_ones() == 1))
+        .then_some(flags)
+}
+
+/// A lower or upper bound on a number, as given by `minimum`/`maximum` or
+/// `exclusiveMinimum`/`exclusiveMaximum`.
+#[derive(Clone, Copy)]
+enum NumericBound {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+impl NumericBound {
+    fn value(self) -> f64 {
+        match self {
+            NumericBound::Inclusive(v) | NumericBound::Exclusive(v) => v,
+        }
     }
 }
 
 fn parse_number_constraints(
     schema_obj: &Map<String, Value>,
-) -> Result<(Option<f64>, Option<f64>), ParseSchemaError> {
-    let mut min_value = parse_numeric_field(schema_obj, "minimum")?;
-    let mut max_value = parse_numeric_field(schema_obj, "maximum")?;
+) -> Result<(Option<NumericBound>, Option<NumericBound>), ParseSchemaError> {
+    let mut min_bound = parse_numeric_field(schema_obj, "minimum")?.map(NumericBound::Inclusive);
+    let mut max_bound = parse_numeric_field(schema_obj, "maximum")?.map(NumericBound::Inclusive);
 
-    // Handle exclusive bounds by treating them as inclusive bounds (with warning)
     if let Some(exclusive_min) = parse_numeric_field(schema_obj, "exclusiveMinimum")? {
-        if min_value.is_some() {
+        if min_bound.is_some() {
             return Err(ParseSchemaError::InvalidSchema(
                 "Cannot specify both minimum and exclusiveMinimum".to_string(),
             ));
         }
-        // Treat exclusive minimum as inclusive minimum (as indicated by warning)
-        min_value = Some(exclusive_min);
+        min_bound = Some(NumericBound::Exclusive(exclusive_min));
     }
 
     if let Some(exclusive_max) = parse_numeric_field(schema_obj, "exclusiveMaximum")? {
-        if max_value.is_some() {
+        if max_bound.is_some() {
             return Err(ParseSchemaError::InvalidSchema(
                 "Cannot specify both maximum and exclusiveMaximum".to_string(),
             ));
         }
-        // Treat exclusive maximum as inclusive maximum (as indicated by warning)
-        max_value = Some(exclusive_max);
+        max_bound = Some(NumericBound::Exclusive(exclusive_max));
     }
 
     validate_min_max_constraint(
-        min_value,
-        max_value,
+        min_bound.map(NumericBound::value),
+        max_bound.map(NumericBound::value),
         "minimum cannot be greater than maximum",
     )?;
 
-    Ok((min_value, max_value))
+    Ok((min_bound, max_bound))
 }
 
 fn parse_numeric_field(
@@ -358,18 +818,63 @@ fn parse_numeric_field(
     }
 }
 
-fn warn_about_unsupported_number_features(schema_obj: &Map<String, Value>) {
-    if schema_obj.contains_key("exclusiveMinimum") {
-        eprintln!("Warning: exclusiveMinimum not supported, treating as inclusive minimum");
+/// The `multipleOf` of a number, if it has one. The step of integers is the smallest integer
+/// that is a multiple of it, such as 3 for a `multipleOf` of 1.5.
+fn parse_multiple_of(
+    schema_obj: &Map<String, Value>,
+    is_integer: bool,
+) -> Result<Option<f64>, ParseSchemaError> {
+    let Some(step) = parse_numeric_field(schema_obj, "multipleOf")? else {
+        return Ok(None);
+    };
+    if !(step > 0.0 && step.is_finite()) {
+        return Err(ParseSchemaError::ValidationFailed(
+            "multipleOf must be greater than 0".to_string(),
+        ));
     }
-
-    if schema_obj.contains_key("exclusiveMaximum") {
-        eprintln!("Warning: exclusiveMaximum not supported, treating as inclusive maximum");
+    if !is_integer || step.fract() == 0.0 {
+        return Ok(Some(step));
     }
+    // a step of `digits` decimals is `scaled / 10^digits`, whose smallest integer multiple is
+    // `scaled` divided by what it has in common with `10^digits`
+    let digits = step.to_string().split_once('.').map_or(0, |(_, d)| d.len());
+    let scale = (digits <= 15).then(|| 10_u64.pow(digits as u32));
+    let Some(scale) = scale else {
+        return Err(ParseSchemaError::UnsupportedFeature(format!(
+            "multipleOf {} of integers has too many decimals",
+            step
+        )));
+    };
+    let scaled = (step * scale as f64).round() as u64;
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    Ok(Some((scaled / gcd(scaled, scale)) as f64))
+}
 
-    if schema_obj.contains_key("multipleOf") {
-        eprintln!("Warning: multipleOf constraint not supported, ignoring");
-    }
+/// Numbers that are multiples of `step` within `min` and `max`, failing if there are none.
+fn multiples(
+    step: f64,
+    min: f64,
+    max: f64,
+    integer: bool,
+) -> Result<SchemaState, ParseSchemaError> {
+    let number_type = NumberType::Multiple {
+        step,
+        min,
+        max,
+        integer,
+    };
+    let (lowest, highest) = number_type.multiples();
+    validate_min_max_constraint(
+        Some(lowest),
+        Some(highest),
+        "no multiple of multipleOf is within the range",
+    )?;
+    Ok(SchemaState::Number(number_type))
 }
 
 fn parse_object_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
@@ -379,14 +884,151 @@ fn parse_object_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, Par
     let required_names = parse_required_field_names(schema_obj)?;
     let (required_fields, optional_fields) = parse_object_properties(properties, &required_names)?;
 
+    match schema_obj.get("x-drivel-type").and_then(Value::as_str) {
+        Some("geo-point") => {
+            if let Some(geo_point) = parse_geo_point(&required_fields, &optional_fields) {
+                return Ok(geo_point);
+            }
+        }
+        Some("money") => {
+            if let Some(money) = parse_money(&required_fields, &optional_fields) {
+                return Ok(money);
+            }
+        }
+        _ => {}
+    }
+
     warn_about_unsupported_object_features(schema_obj);
 
+    let annotations = parse_annotations(properties, |name| {
+        required_fields
+            .get(name)
+            .or_else(|| optional_fields.get(name))
+    });
     Ok(SchemaState::Object {
         required: required_fields,
         optional: optional_fields,
+        access: parse_field_access(properties),
+        annotations,
+    })
+}
+
+/// Recognises the fields of an object marked with `x-drivel-type: "geo-point"`, which must be a
+/// required latitude and longitude.
+fn parse_geo_point(
+    required: &IndexMap<String, SchemaState>,
+    optional: &IndexMap<String, SchemaState>,
+) -> Option<SchemaState> {
+    if !optional.is_empty() {
+        return None;
+    }
+    let (latitude_key, longitude_key) = geo_point_keys(required.keys())?;
+    let number = |key: &String| match &required[key] {
+        SchemaState::Number(
+            number_type @ (NumberType::Integer { .. } | NumberType::Float { .. }),
+        ) => Some(number_type.clone()),
+        _ => None,
+    };
+    Some(SchemaState::GeoPoint {
+        latitude: number(latitude_key)?,
+        longitude: number(longitude_key)?,
+        latitude_key: latitude_key.clone(),
+        longitude_key: longitude_key.clone(),
     })
 }
 
+/// Recognises the fields of an object marked with `x-drivel-type: "money"`, which must be a
+/// required amount and currency. The currencies are those of an enum of currency codes, or any
+/// if the currency is just a string, such as with a `pattern`.
+fn parse_money(
+    required: &IndexMap<String, SchemaState>,
+    optional: &IndexMap<String, SchemaState>,
+) -> Option<SchemaState> {
+    if !optional.is_empty() {
+        return None;
+    }
+    let (amount_key, currency_key) = money_keys(required.keys())?;
+    let amount = match &required[amount_key] {
+        SchemaState::Number(
+            number_type @ (NumberType::Integer { .. } | NumberType::Float { .. }),
+        ) => number_type.clone(),
+        _ => return None,
+    };
+    let currencies = match &required[currency_key] {
+        SchemaState::String(StringType::Enum { variants }) => {
+            if !variants.iter().all(|variant| is_currency(variant)) {
+                return None;
+            }
+            variants.clone()
+        }
+        SchemaState::String(_) => std::collections::BTreeSet::new(),
+        _ => return None,
+    };
+    Some(SchemaState::Money {
+        amount_key: amount_key.clone(),
+        currency_key: currency_key.clone(),
+        amount,
+        currencies,
+    })
+}
+
+/// The properties marked with either `readOnly` or `writeOnly`.
+fn parse_field_access(
+    properties: &Map<String, Value>,
+) -> std::collections::BTreeMap<String, FieldAccess> {
+    let is_set = |property: &Value, keyword| property.get(keyword) == Some(&Value::Bool(true));
+    properties
+        .iter()
+        .filter_map(|(name, property)| {
+            let access = match (is_set(property, "readOnly"), is_set(property, "writeOnly")) {
+                (true, false) => FieldAccess::ReadOnly,
+                (false, true) => FieldAccess::WriteOnly,
+                _ => return None,
+            };
+            Some((name.clone(), access))
+        })
+        .collect()
+}
+
+/// The keywords that only annotate a schema, and that drivel writes back out as they were.
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "title",
+    "description",
+    "$comment",
+    "examples",
+    "default",
+    "deprecated",
+];
+
+/// The annotations of the properties that have any: their [`ANNOTATION_KEYWORDS`] and `x-`
+/// extensions, other than drivel's own `x-drivel-` ones and those that drivel writes itself for
+/// the parsed schema of the property, such as the `description` of a ratio.
+fn parse_annotations<'a>(
+    properties: &Map<String, Value>,
+    parsed: impl Fn(&str) -> Option<&'a SchemaState>,
+) -> std::collections::BTreeMap<String, Map<String, Value>> {
+    let is_annotation = |keyword: &str| {
+        ANNOTATION_KEYWORDS.contains(&keyword)
+            || (keyword.starts_with("x-") && !keyword.starts_with("x-drivel-"))
+    };
+    properties
+        .iter()
+        .filter_map(|(name, property)| {
+            let written = parsed(name).map(crate::ToJsonSchema::to_json_schema);
+            let keywords: Map<String, Value> = property
+                .as_object()?
+                .iter()
+                .filter(|(keyword, value)| {
+                    is_annotation(keyword)
+                        && written.as_ref().and_then(|w| w.get(keyword.as_str())) != Some(*value)
+                })
+                .map(|(keyword, value)| (keyword.clone(), value.clone()))
+                .collect();
+            (!keywords.is_empty()).then(|| (name.clone(), keywords))
+        })
+        .collect()
+}
+
 fn parse_required_field_names(
     schema_obj: &Map<String, Value>,
 ) -> Result<std::collections::HashSet<String>, ParseSchemaError> {
@@ -408,11 +1050,11 @@ fn parse_object_properties(
     properties: &Map<String, Value>,
     required_names: &std::collections::HashSet<String>,
 ) -> Result<ObjectProperties, ParseSchemaError> {
-    let mut required_fields = std::collections::HashMap::new();
-    let mut optional_fields = std::collections::HashMap::new();
+    let mut required_fields = IndexMap::new();
+    let mut optional_fields = IndexMap::new();
 
     for (property_name, property_schema) in properties {
-        let parsed_schema = parse_json_schema(property_schema)?;
+        let parsed_schema = parse_schema(property_schema)?;
 
         if required_names.contains(property_name) {
             required_fields.insert(property_name.clone(), parsed_schema);
@@ -427,24 +1069,31 @@ fn parse_object_properties(
 fn warn_about_unsupported_object_features(schema_obj: &Map<String, Value>) {
     if let Some(additional_props) = schema_obj.get("additionalProperties") {
         if additional_props.as_bool() == Some(true) {
-            eprintln!("Warning: additionalProperties: true not fully supported, allowing any additional properties");
+            warn("additionalProperties: true not fully supported, allowing any additional properties");
         } else if additional_props.is_object() {
-            eprintln!("Warning: additionalProperties schema not supported, ignoring");
+            warn("additionalProperties schema not supported, ignoring");
         }
     }
 
     if schema_obj.contains_key("patternProperties") {
-        eprintln!("Warning: patternProperties not supported, ignoring");
+        warn("patternProperties not supported, ignoring");
     }
 }
 
 fn parse_array_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, ParseSchemaError> {
+    if let Some(positions) = parse_coordinates(schema_obj)? {
+        return Ok(SchemaState::Coordinates(positions));
+    }
+    if let Some(positions) = parse_tuple(schema_obj)? {
+        return Ok(SchemaState::Tuple(positions));
+    }
+
     // Parse the items schema
     let items_schema = schema_obj.get("items").ok_or_else(|| {
         ParseSchemaError::InvalidSchema("Array schema must have an 'items' field".to_string())
     })?;
 
-    let parsed_items_schema = parse_json_schema(items_schema)?;
+    let parsed_items_schema = parse_schema(items_schema)?;
 
     // Parse array constraints
     let (min_items, max_items) = parse_array_constraints(schema_obj)?;
@@ -455,10 +1104,52 @@ fn parse_array_type(schema_obj: &Map<String, Value>) -> Result<SchemaState, Pars
     Ok(SchemaState::Array {
         min_length: min_items,
         max_length: max_items,
+        lengths: None,
+        unique_items: schema_obj.get("uniqueItems") == Some(&Value::Bool(true)),
         schema: Box::new(parsed_items_schema),
     })
 }
 
+/// Recognises the `prefixItems` of two or three numbers emitted for coordinates.
+fn parse_coordinates(
+    schema_obj: &Map<String, Value>,
+) -> Result<Option<Vec<NumberType>>, ParseSchemaError> {
+    let prefix_items = match schema_obj.get("prefixItems").and_then(|v| v.as_array()) {
+        Some(prefix_items) if (2..=3).contains(&prefix_items.len()) => prefix_items,
+        _ => return Ok(None),
+    };
+
+    let mut positions = vec![];
+    for item in prefix_items {
+        match parse_schema(item)? {
+            SchemaState::Number(
+                number_type @ (NumberType::Integer { .. } | NumberType::Float { .. }),
+            ) => positions.push(number_type),
+            _ => return Ok(None),
+        }
+    }
+    Ok(Some(positions))
+}
+
+/// Recognises the `prefixItems` of a tuple, whose `items: false` allows no more elements than
+/// its positions.
+fn parse_tuple(
+    schema_obj: &Map<String, Value>,
+) -> Result<Option<Vec<SchemaState>>, ParseSchemaError> {
+    match schema_obj.get("prefixItems").and_then(|v| v.as_array()) {
+        Some(prefix_items)
+            if prefix_items.len() >= 2 && schema_obj.get("items") == Some(&Value::Bool(false)) =>
+        {
+            prefix_items
+                .iter()
+                .map(parse_schema)
+                .collect::<Result<_, _>>()
+                .map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
 fn parse_array_constraints(
     schema_obj: &Map<String, Value>,
 ) -> Result<(usize, usize), ParseSchemaError> {
@@ -472,7 +1163,7 @@ fn parse_array_constraints(
     )?;
 
     let min_items = min_items_opt.unwrap_or(0);
-    let max_items = max_items_opt.unwrap_or(/* sane default */ 16);
+    let max_items = max_items_opt.unwrap_or(/* sane default */ min_items.max(16));
 
     Ok((min_items, max_items))
 }
@@ -528,16 +1219,12 @@ fn require_string<'a>(value: &'a Value, context: &str) -> Result<&'a str, ParseS
 }
 
 fn warn_about_unsupported_array_features(schema_obj: &Map<String, Value>) {
-    if schema_obj.contains_key("uniqueItems") {
-        eprintln!("Warning: uniqueItems constraint not supported, ignoring");
-    }
-
     if schema_obj.contains_key("contains") {
-        eprintln!("Warning: contains keyword not supported, ignoring");
+        warn("contains keyword not supported, ignoring");
     }
 
     if schema_obj.contains_key("additionalItems") {
-        eprintln!("Warning: additionalItems not supported, ignoring");
+        warn("additionalItems not supported, ignoring");
     }
 }
 
@@ -545,8 +1232,51 @@ fn warn_about_unsupported_array_features(schema_obj: &Map<String, Value>) {
 mod tests {
     use super::*;
     use crate::schema::{NumberType, StringType};
+    use crate::ToJsonSchema;
     use serde_json::json;
 
+    #[test]
+    fn parses_recursive_definitions_and_writes_them_back() {
+        let tree = json!({
+            "$ref": "#/$defs/node",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                    },
+                    "required": ["children"]
+                }
+            }
+        });
+        let schema = parse_json_schema(&tree).unwrap();
+        let SchemaState::Recursive { name, schema: node } = &schema else {
+            panic!("expected a recursive definition, got {:?}", schema);
+        };
+        assert_eq!(name, "node");
+        let SchemaState::Object { required, .. } = node.as_ref() else {
+            panic!("expected an object, got {:?}", node);
+        };
+        assert!(matches!(
+            &required["children"],
+            SchemaState::Array { schema, .. } if **schema == SchemaState::Reference("node".into())
+        ));
+
+        let written = schema.to_json_schema();
+        assert_eq!(written["$anchor"], json!("node"));
+        assert_eq!(parse_json_schema(&written).unwrap(), schema);
+
+        let endless = json!({
+            "$defs": {"node": {
+                "type": "object",
+                "properties": {"next": {"$ref": "#/$defs/node"}},
+                "required": ["next"]
+            }},
+            "$ref": "#/$defs/node"
+        });
+        assert!(parse_json_schema(&endless).is_err());
+    }
+
     // Test utilities
     fn assert_string_parsing_success(result: Result<SchemaState, ParseSchemaError>) {
         match result {
@@ -614,6 +1344,7 @@ mod tests {
             Ok(SchemaState::Number(NumberType::Float {
                 min: actual_min,
                 max: actual_max,
+                ..
             })) => {
                 assert_eq!(actual_min, min, "Min value mismatch");
                 assert_eq!(actual_max, max, "Max value mismatch");
@@ -680,6 +1411,114 @@ mod tests {
             }
         }
 
+        #[test]
+        fn parse_with_fixed_width_numeric_pattern() {
+            let schema = json!({"type": "string", "pattern": "^[0-9]{5}$"});
+            let result = parse_json_schema(&schema);
+            assert!(matches!(
+                result,
+                Ok(SchemaState::String(StringType::FixedWidthNumeric {
+                    width: 5
+                }))
+            ));
+        }
+
+        #[test]
+        fn parse_character_class_patterns() {
+            let schema =
+                json!({"type": "string", "pattern": "^[A-Z]+$", "minLength": 2, "maxLength": 3});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::String(StringType::Charset {
+                    class: CharacterClass::Uppercase,
+                    min_length: 2,
+                    max_length: 3
+                })
+            );
+            let schema = json!({"type": "string", "pattern": "^[0-9a-f]+$"});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::String(StringType::Charset {
+                    class: CharacterClass::Hex,
+                    min_length: 1,
+                    max_length: 32
+                })
+            );
+        }
+
+        #[test]
+        fn parse_duration_format() {
+            let schema = json!({"type": "string", "format": "duration"});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::String(StringType::Duration)
+            );
+        }
+
+        #[test]
+        fn parse_financial_identifiers_and_barcodes() {
+            for string_type in [
+                StringType::CreditCard,
+                StringType::Iban,
+                StringType::Isbn10,
+                StringType::Isbn13,
+                StringType::Ean13,
+            ] {
+                let schema = SchemaState::String(string_type);
+                let json_schema = crate::ToJsonSchema::to_json_schema(&schema);
+                assert_eq!(parse_json_schema(&json_schema).unwrap(), schema);
+            }
+        }
+
+        #[test]
+        fn parse_custom_date_time() {
+            let schema = SchemaState::String(StringType::CustomDateTime {
+                formats: vec!["%d-%m-%Y %H:%M".to_string()],
+            });
+            let json_schema = crate::ToJsonSchema::to_json_schema(&schema);
+            assert_eq!(parse_json_schema(&json_schema).unwrap(), schema);
+
+            let invalid = json!({
+                "type": "string",
+                "x-drivel-type": "datetime-custom",
+                "x-drivel-date-format": "%Q"
+            });
+            assert_string_constraints(parse_json_schema(&invalid), None, None);
+        }
+
+        #[test]
+        fn parse_with_other_pattern() {
+            let schema = json!({"type": "string", "pattern": "^[a-z]+-[0-9]+$", "maxLength": 8});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::String(StringType::Pattern {
+                    pattern: "^[a-z]+-[0-9]+$".to_string(),
+                    min_length: None,
+                    max_length: Some(8),
+                })
+            );
+            assert_eq!(
+                crate::ToJsonSchema::to_json_schema(&parse_json_schema(&schema).unwrap()),
+                schema
+            );
+        }
+
+        #[test]
+        fn parse_with_unsupported_pattern() {
+            let schema = json!({"type": "string", "pattern": "^(?!admin)[a-z]+$"});
+            let (result, diagnostics) = crate::capture_diagnostics(|| parse_json_schema(&schema));
+            assert_string_constraints(result, None, None);
+            assert_eq!(diagnostics.len(), 1);
+        }
+
+        #[test]
+        fn parse_with_length_constraints_of_a_format() {
+            let schema = json!({"type": "string", "format": "email", "maxLength": 8});
+            let (result, diagnostics) = crate::capture_diagnostics(|| parse_json_schema(&schema));
+            assert_string_format(result, StringType::Email);
+            assert_eq!(diagnostics.len(), 1);
+        }
+
         #[test]
         fn parse_with_length_constraints() {
             let schema = json!({"type": "string", "minLength": 5, "maxLength": 20});
@@ -716,6 +1555,57 @@ mod tests {
             }
         }
 
+        #[test]
+        fn parse_open_enum_schema() {
+            let schema = json!({
+                "type": "string",
+                "x-drivel-type": "open-enum",
+                "x-drivel-enum": ["DE", "FR"],
+                "x-drivel-other-ratio": 0.1
+            });
+            match parse_json_schema(&schema) {
+                Ok(SchemaState::String(StringType::OpenEnum {
+                    variants,
+                    other_ratio,
+                    ..
+                })) => {
+                    assert_eq!(variants.into_iter().collect::<Vec<_>>(), vec!["DE", "FR"]);
+                    assert_eq!(other_ratio, 0.1);
+                }
+                other => panic!("Expected an open enum, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn parse_numeric_string_schema() {
+            for (number_type, number) in [
+                (
+                    "integer",
+                    NumberType::Integer {
+                        min: i64::MIN,
+                        max: i64::MAX,
+                    },
+                ),
+                (
+                    "number",
+                    NumberType::Float {
+                        min: f64::NEG_INFINITY,
+                        max: f64::INFINITY,
+                        non_finite: Default::default(),
+                    },
+                ),
+            ] {
+                let json_schema = crate::ToJsonSchema::to_json_schema(&StringType::Numeric {
+                    number: number.clone(),
+                });
+                assert_eq!(json_schema["x-drivel-number-type"], json!(number_type));
+                assert_eq!(
+                    parse_json_schema(&json_schema).unwrap(),
+                    SchemaState::String(StringType::Numeric { number })
+                );
+            }
+        }
+
         #[test]
         fn parse_empty_enum() {
             let schema = json!({"type": "string", "enum": []});
@@ -744,6 +1634,40 @@ mod tests {
             assert_number_parsing_success(result);
         }
 
+        #[test]
+        fn parse_sequence_extension() {
+            let schema = json!({
+                "type": "integer",
+                "x-drivel-type": "sequence",
+                "x-drivel-sequence-start": 100,
+                "x-drivel-sequence-step": 5
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            assert_eq!(crate::ToJsonSchema::to_json_schema(&parsed), schema);
+        }
+
+        #[test]
+        fn parse_flags_extension() {
+            let schema = json!({
+                "type": "integer",
+                "minimum": 0,
+                "maximum": 13,
+                "description": "bit flags 1 | 4 | 8",
+                "x-drivel-type": "flags",
+                "x-drivel-flags": [1, 4, 8]
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            assert_eq!(parsed.to_string_pretty(), "int (flags: 1 | 4 | 8)");
+            assert_eq!(crate::ToJsonSchema::to_json_schema(&parsed), schema);
+
+            let not_flags =
+                json!({"type": "integer", "x-drivel-type": "flags", "x-drivel-flags": [3]});
+            assert!(matches!(
+                parse_json_schema(&not_flags).unwrap(),
+                SchemaState::Number(NumberType::Integer { .. })
+            ));
+        }
+
         #[test]
         fn parse_number_with_constraints() {
             let schema = json!({"type": "number", "minimum": 1.5, "maximum": 99.9});
@@ -773,24 +1697,77 @@ mod tests {
         }
 
         #[test]
-        fn parse_number_with_unsupported_constraints() {
+        fn parse_integer_enum() {
+            let schema = json!({"type": "integer", "enum": [200, 404, 500]});
+            match parse_json_schema(&schema) {
+                Ok(SchemaState::Number(NumberType::Enum { variants })) => {
+                    assert_eq!(
+                        variants.keys().copied().collect::<Vec<_>>(),
+                        [200, 404, 500]
+                    );
+                }
+                other => panic!("Expected integer enum, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn parse_integer_enum_with_non_integer_values() {
+            let schema = json!({"type": "integer", "enum": [1, "two"]});
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Err(ParseSchemaError::InvalidSchema(_))
+            ));
+        }
+
+        #[test]
+        fn parse_number_with_multiple_of() {
             let schema = json!({"type": "number", "minimum": 5.0, "exclusiveMaximum": 10.0, "multipleOf": 2.5});
-            let result = parse_json_schema(&schema);
-            assert_float_constraints(result, 5.0, 10.0);
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Number(NumberType::Multiple {
+                    step: 2.5,
+                    min: 5.0,
+                    max: 10.0_f64.next_down(),
+                    integer: false,
+                })
+            );
+
+            // the step of integers is the smallest integer multiple of it
+            let schema = json!({"type": "integer", "minimum": 1, "multipleOf": 1.5});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Number(NumberType::Multiple {
+                    step: 3.0,
+                    min: 1.0,
+                    max: f64::INFINITY,
+                    integer: true,
+                })
+            );
+
+            let schema = json!({"type": "integer", "minimum": 1, "maximum": 2, "multipleOf": 5});
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Err(ParseSchemaError::ValidationFailed(_))
+            ));
+            let schema = json!({"type": "number", "multipleOf": 0});
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Err(ParseSchemaError::ValidationFailed(_))
+            ));
         }
 
         #[test]
         fn parse_number_with_exclusive_minimum() {
             let schema = json!({"type": "number", "exclusiveMinimum": 1.5});
             let result = parse_json_schema(&schema);
-            assert_float_constraints(result, 1.5, f64::INFINITY);
+            assert_float_constraints(result, 1.5_f64.next_up(), f64::INFINITY);
         }
 
         #[test]
         fn parse_number_with_exclusive_maximum() {
             let schema = json!({"type": "number", "exclusiveMaximum": 99.9});
             let result = parse_json_schema(&schema);
-            assert_float_constraints(result, f64::NEG_INFINITY, 99.9);
+            assert_float_constraints(result, f64::NEG_INFINITY, 99.9_f64.next_down());
         }
 
         #[test]
@@ -798,14 +1775,31 @@ mod tests {
             let schema =
                 json!({"type": "number", "exclusiveMinimum": 0.0, "exclusiveMaximum": 100.0});
             let result = parse_json_schema(&schema);
-            assert_float_constraints(result, 0.0, 100.0);
+            assert_float_constraints(result, 0.0_f64.next_up(), 100.0_f64.next_down());
         }
 
         #[test]
         fn parse_integer_with_exclusive_bounds() {
             let schema = json!({"type": "integer", "exclusiveMinimum": 5, "exclusiveMaximum": 20});
             let result = parse_json_schema(&schema);
-            assert_integer_constraints(result, 5, 20);
+            assert_integer_constraints(result, 6, 19);
+        }
+
+        #[test]
+        fn parse_integer_with_fractional_exclusive_bounds() {
+            let schema =
+                json!({"type": "integer", "exclusiveMinimum": 1.5, "exclusiveMaximum": 4.5});
+            let result = parse_json_schema(&schema);
+            assert_integer_constraints(result, 2, 4);
+        }
+
+        #[test]
+        fn parse_integer_with_empty_exclusive_range() {
+            let schema = json!({"type": "integer", "exclusiveMinimum": 1, "exclusiveMaximum": 2});
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Err(ParseSchemaError::ValidationFailed(_))
+            ));
         }
 
         #[test]
@@ -837,7 +1831,7 @@ mod tests {
             let schema = json!({"type": "number", "exclusiveMaximum": f64::MAX});
             let result = parse_json_schema(&schema);
             // Should parse successfully without crashing
-            assert_float_constraints(result, f64::NEG_INFINITY, f64::MAX);
+            assert_float_constraints(result, f64::NEG_INFINITY, f64::MAX.next_down());
         }
 
         #[test]
@@ -845,7 +1839,7 @@ mod tests {
             let schema = json!({"type": "number", "exclusiveMinimum": f64::MIN});
             let result = parse_json_schema(&schema);
             // Should parse successfully without crashing
-            assert_float_constraints(result, f64::MIN, f64::INFINITY);
+            assert_float_constraints(result, f64::MIN.next_up(), f64::INFINITY);
         }
 
         #[test]
@@ -853,7 +1847,7 @@ mod tests {
             let schema = json!({"type": "integer", "exclusiveMaximum": i64::MAX});
             let result = parse_json_schema(&schema);
             // Should parse successfully without crashing
-            assert_integer_constraints(result, i64::MIN, i64::MAX);
+            assert_integer_constraints(result, i64::MIN, i64::MAX - 1);
         }
 
         #[test]
@@ -861,7 +1855,110 @@ mod tests {
             let schema = json!({"type": "integer", "exclusiveMinimum": i64::MIN});
             let result = parse_json_schema(&schema);
             // Should parse successfully without crashing
-            assert_integer_constraints(result, i64::MIN, i64::MAX);
+            assert_integer_constraints(result, i64::MIN + 1, i64::MAX);
+        }
+    }
+
+    mod coordinates {
+        use super::*;
+
+        #[test]
+        fn parse_coordinates_from_prefix_items() {
+            let schema = json!({
+                "type": "array",
+                "prefixItems": [
+                    {"type": "number", "minimum": -180, "maximum": 180},
+                    {"type": "number", "minimum": -90, "maximum": 90}
+                ],
+                "items": false
+            });
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Coordinates(vec![
+                    NumberType::Float {
+                        min: -180.0,
+                        max: 180.0,
+                        non_finite: Default::default(),
+                    },
+                    NumberType::Float {
+                        min: -90.0,
+                        max: 90.0,
+                        non_finite: Default::default(),
+                    },
+                ])
+            );
+        }
+
+        #[test]
+        fn parse_geo_point_objects() {
+            let schema = json!({
+                "type": "object",
+                "x-drivel-type": "geo-point",
+                "properties": {
+                    "Lat": {"type": "number", "minimum": -90, "maximum": 90},
+                    "Lng": {"type": "number", "minimum": -180, "maximum": 180}
+                },
+                "required": ["Lat", "Lng"],
+                "additionalProperties": false
+            });
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::GeoPoint {
+                    latitude_key: "Lat".to_string(),
+                    longitude_key: "Lng".to_string(),
+                    latitude: NumberType::Float {
+                        min: -90.0,
+                        max: 90.0,
+                        non_finite: Default::default(),
+                    },
+                    longitude: NumberType::Float {
+                        min: -180.0,
+                        max: 180.0,
+                        non_finite: Default::default(),
+                    },
+                }
+            );
+
+            // without the extension, or with other fields, it stays an object
+            let mut object = schema.clone();
+            object.as_object_mut().unwrap().remove("x-drivel-type");
+            assert!(matches!(
+                parse_json_schema(&object).unwrap(),
+                SchemaState::Object { .. }
+            ));
+            let mut object = schema;
+            object["properties"]["name"] = json!({"type": "string"});
+            assert!(matches!(
+                parse_json_schema(&object).unwrap(),
+                SchemaState::Object { .. }
+            ));
+        }
+
+        #[test]
+        fn parse_closed_prefix_items_of_other_types_as_tuple() {
+            let schema = json!({
+                "type": "array",
+                "prefixItems": [{"type": "string"}, {"type": "boolean"}],
+                "items": false
+            });
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Ok(SchemaState::Tuple(positions))
+                    if matches!(positions[..], [SchemaState::String(_), SchemaState::Boolean])
+            ));
+        }
+
+        #[test]
+        fn parse_prefix_items_of_other_types_as_array() {
+            let schema = json!({
+                "type": "array",
+                "prefixItems": [{"type": "string"}, {"type": "number"}],
+                "items": {"type": "number"}
+            });
+            assert!(matches!(
+                parse_json_schema(&schema),
+                Ok(SchemaState::Array { .. })
+            ));
         }
     }
 
@@ -881,6 +1978,46 @@ mod tests {
             let result = parse_json_schema(&schema);
             assert_null_parsing_success(result);
         }
+
+        #[test]
+        fn parse_const_and_enum_of_values_of_any_type() {
+            let parse = |schema| parse_json_schema(&schema).unwrap();
+            assert_eq!(
+                parse(json!({"const": "v1"})),
+                SchemaState::String(StringType::Enum {
+                    variants: ["v1".to_string()].into()
+                })
+            );
+            assert_eq!(
+                parse(json!({"type": "boolean", "const": true})),
+                SchemaState::Literal(vec![json!(true)])
+            );
+            assert_eq!(
+                parse(json!({"type": "number", "enum": [1.5, 2.5, 1.5]})),
+                SchemaState::Literal(vec![json!(1.5), json!(2.5)])
+            );
+            assert_eq!(
+                parse(json!({"enum": [1, null]})),
+                SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Enum {
+                    variants: [(1, 1)].into()
+                })))
+            );
+            assert_eq!(
+                parse(json!({"type": ["string", "null"], "enum": ["a", null]})),
+                SchemaState::Nullable(Box::new(SchemaState::String(StringType::Enum {
+                    variants: ["a".to_string()].into()
+                })))
+            );
+            assert_eq!(parse(json!({"enum": [null]})), SchemaState::Null);
+
+            // the JSON Schema of a nullable enum has null among its values
+            let schema = json!({"enum": ["a", [1], null]});
+            assert_eq!(
+                parse(schema.clone()).to_json_schema(),
+                json!({"enum": ["a", [1], null]})
+            );
+            assert_eq!(parse(parse(schema.clone()).to_json_schema()), parse(schema));
+        }
     }
 
     mod complex_types {
@@ -898,7 +2035,9 @@ mod tests {
             });
             let result = parse_json_schema(&schema);
             match result {
-                Ok(SchemaState::Object { required, optional }) => {
+                Ok(SchemaState::Object {
+                    required, optional, ..
+                }) => {
                     assert!(required.contains_key("name"));
                     assert!(matches!(required.get("name"), Some(SchemaState::String(_))));
                     assert!(optional.contains_key("age"));
@@ -927,12 +2066,15 @@ mod tests {
             });
             let result = parse_json_schema(&schema);
             match result {
-                Ok(SchemaState::Object { required, optional }) => {
+                Ok(SchemaState::Object {
+                    required, optional, ..
+                }) => {
                     assert!(required.contains_key("user"));
                     match required.get("user") {
                         Some(SchemaState::Object {
                             required: user_required,
                             optional: user_optional,
+                            ..
                         }) => {
                             assert!(user_required.contains_key("id"));
                             assert!(matches!(
@@ -968,6 +2110,7 @@ mod tests {
                     min_length,
                     max_length,
                     schema: item_schema,
+                    ..
                 }) => {
                     assert_eq!(min_length, 1);
                     assert_eq!(max_length, 10);
@@ -989,6 +2132,7 @@ mod tests {
                     min_length,
                     max_length,
                     schema: item_schema,
+                    ..
                 }) => {
                     assert_eq!(min_length, 0);
                     assert_eq!(max_length, 16);
@@ -1017,6 +2161,7 @@ mod tests {
                     min_length,
                     max_length,
                     schema: item_schema,
+                    ..
                 }) => {
                     assert_eq!(min_length, 1);
                     assert_eq!(max_length, 3);
@@ -1024,6 +2169,8 @@ mod tests {
                         SchemaState::Array {
                             min_length: inner_min,
                             max_length: inner_max,
+                            lengths: None,
+                            unique_items: false,
                             schema: inner_schema,
                         } => {
                             assert_eq!(*inner_min, 2);
@@ -1037,6 +2184,37 @@ mod tests {
             }
         }
 
+        #[test]
+        fn parse_unique_items() {
+            let schema = json!({
+                "type": "array",
+                "items": {"type": "string"},
+                "uniqueItems": true
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            assert!(matches!(
+                parsed,
+                SchemaState::Array {
+                    unique_items: true,
+                    ..
+                }
+            ));
+            assert_eq!(
+                crate::ToJsonSchema::to_json_schema(&parsed)["uniqueItems"],
+                json!(true)
+            );
+
+            let schema =
+                json!({"type": "array", "items": {"type": "string"}, "uniqueItems": false});
+            assert!(matches!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Array {
+                    unique_items: false,
+                    ..
+                }
+            ));
+        }
+
         #[test]
         fn parse_array_of_objects() {
             let schema = json!({
@@ -1056,7 +2234,9 @@ mod tests {
                     schema: item_schema,
                     ..
                 }) => match item_schema.as_ref() {
-                    SchemaState::Object { required, optional } => {
+                    SchemaState::Object {
+                        required, optional, ..
+                    } => {
                         assert!(required.contains_key("id"));
                         assert!(optional.contains_key("name"));
                     }
@@ -1065,6 +2245,175 @@ mod tests {
                 _ => panic!("Expected array of objects to parse correctly"),
             }
         }
+
+        #[test]
+        fn parse_read_only_and_write_only_fields() {
+            let schema = json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer", "readOnly": true},
+                    "password": {"type": "string", "writeOnly": true},
+                    "name": {"type": "string", "readOnly": false}
+                },
+                "required": ["id", "name"]
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            match &parsed {
+                SchemaState::Object { access, .. } => assert_eq!(
+                    access,
+                    &std::collections::BTreeMap::from([
+                        ("id".to_string(), FieldAccess::ReadOnly),
+                        ("password".to_string(), FieldAccess::WriteOnly),
+                    ])
+                ),
+                _ => panic!("Expected an object"),
+            }
+
+            let json_schema = crate::ToJsonSchema::to_json_schema(&parsed);
+            assert_eq!(json_schema["properties"]["id"]["readOnly"], json!(true));
+            assert_eq!(
+                json_schema["properties"]["password"]["writeOnly"],
+                json!(true)
+            );
+            assert!(json_schema["properties"]["name"].get("readOnly").is_none());
+        }
+
+        #[test]
+        fn round_trips_annotations_of_fields() {
+            let schema = json!({
+                "type": "object",
+                "properties": {
+                    "sku": {
+                        "type": "string",
+                        "title": "Stock keeping unit",
+                        "x-internal-owner": {"team": "inventory"}
+                    },
+                    "price": {
+                        "type": "number",
+                        "description": "The price in euros",
+                        "x-drivel-number-type": "float"
+                    }
+                },
+                "required": ["sku"]
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            let document = crate::ToJsonSchema::to_json_schema_document_with(
+                &parsed,
+                &crate::JsonSchemaOptions {
+                    field_descriptions: true,
+                    ..Default::default()
+                },
+            );
+
+            let sku = &document["properties"]["sku"];
+            assert_eq!(sku["title"], json!("Stock keeping unit"));
+            assert_eq!(sku["x-internal-owner"], json!({"team": "inventory"}));
+            assert_eq!(sku["description"], json!("Sku"));
+            let price = &document["properties"]["price"];
+            assert_eq!(price["description"], json!("The price in euros"));
+            let json_schema = crate::ToJsonSchema::to_json_schema(&parsed);
+            assert_eq!(parse_json_schema(&json_schema).unwrap(), parsed);
+        }
+
+        #[test]
+        fn parse_schema_with_definitions() {
+            let schema = json!({
+                "type": "object",
+                "properties": {
+                    "billing": {"type": "object", "properties": {"city": {"type": "string"}}},
+                    "shipping": {"type": "object", "properties": {"city": {"type": "string"}}}
+                },
+                "required": ["billing", "shipping"]
+            });
+            let parsed = parse_json_schema(&schema).unwrap();
+            let deduped = crate::ToJsonSchema::to_json_schema_document_with(
+                &parsed,
+                &crate::JsonSchemaOptions {
+                    dedupe_definitions: true,
+                    ..Default::default()
+                },
+            );
+            assert!(deduped["properties"]["shipping"].get("$ref").is_some());
+            assert_eq!(parse_json_schema(&deduped).unwrap(), parsed);
+        }
+    }
+
+    mod composition {
+        use super::*;
+
+        #[test]
+        fn parse_anyof_of_any_schemas_as_union() {
+            let schema = json!({
+                "anyOf": [
+                    {"type": "integer", "minimum": 1, "maximum": 9},
+                    {"type": "string", "format": "uuid"},
+                    {"type": "null"}
+                ]
+            });
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Nullable(Box::new(SchemaState::Union(vec![
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+                    SchemaState::String(StringType::UUID),
+                ])))
+            );
+        }
+
+        #[test]
+        fn parse_oneof_variants_with_the_keywords_they_share() {
+            let schema = json!({
+                "type": "object",
+                "properties": {"email": {"type": "string"}, "phone": {"type": "string"}},
+                "oneOf": [{"required": ["email"]}, {"required": ["phone"]}]
+            });
+            let Ok(SchemaState::Union(variants)) = parse_json_schema(&schema) else {
+                panic!("Expected a union of the variants");
+            };
+            let required: Vec<Vec<&String>> = variants
+                .iter()
+                .map(|variant| match variant {
+                    SchemaState::Object { required, .. } => required.keys().collect(),
+                    other => panic!("Expected an object, got {:?}", other),
+                })
+                .collect();
+            assert_eq!(required, vec![vec!["email"], vec!["phone"]]);
+        }
+
+        #[test]
+        fn parse_allof_as_the_combination_of_its_schemas() {
+            let schema = json!({
+                "allOf": [
+                    {"$ref": "#/$defs/named"},
+                    {
+                        "properties": {"age": {"type": "integer", "minimum": 0}},
+                        "required": ["age"]
+                    }
+                ],
+                "$defs": {
+                    "named": {
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}},
+                        "required": ["name"]
+                    }
+                }
+            });
+            match parse_json_schema(&schema).unwrap() {
+                SchemaState::Object {
+                    required, optional, ..
+                } => {
+                    assert!(optional.is_empty());
+                    assert!(required.contains_key("name"));
+                    assert_eq!(
+                        required["age"],
+                        SchemaState::Number(NumberType::Integer {
+                            min: 0,
+                            max: i64::MAX
+                        })
+                    );
+                }
+                other => panic!("Expected an object, got {:?}", other),
+            }
+        }
     }
 
     mod nullable_types {
@@ -1128,6 +2477,58 @@ mod tests {
             }
         }
 
+        #[test]
+        fn nullable_keyword_means_the_same_as_a_null_type() {
+            let openapi_3_0 = json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "format": "email", "nullable": true},
+                    "tags": {
+                        "type": "array",
+                        "items": {"type": "integer", "minimum": 1, "maximum": 9},
+                        "nullable": true
+                    },
+                    "id": {"type": ["integer", "null"], "nullable": true}
+                },
+                "required": ["name", "tags", "id"]
+            });
+            let openapi_3_1 = json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": ["string", "null"], "format": "email"},
+                    "tags": {
+                        "type": ["array", "null"],
+                        "items": {"type": "integer", "minimum": 1, "maximum": 9}
+                    },
+                    "id": {"type": ["integer", "null"]}
+                },
+                "required": ["name", "tags", "id"]
+            });
+            let nullable = parse_json_schema(&openapi_3_0).unwrap();
+            assert_eq!(nullable, parse_json_schema(&openapi_3_1).unwrap());
+
+            let options = crate::ProduceOptions {
+                seed: Some(7),
+                ..Default::default()
+            };
+            // the root arrays are produced with as many records as asked for
+            let records = |items| {
+                let schema = parse_json_schema(&json!({"type": "array", "items": items})).unwrap();
+                crate::produce_with(&schema, 50, &options)
+            };
+            let produced = records(openapi_3_0);
+            assert_eq!(produced, records(openapi_3_1));
+            let values = produced.as_array().unwrap();
+            assert!(values.iter().any(|value| value["name"].is_null()));
+            assert!(values.iter().any(|value| value["name"].is_string()));
+
+            let not_nullable = json!({"type": "string", "nullable": false});
+            assert!(matches!(
+                parse_json_schema(&not_nullable),
+                Ok(SchemaState::String(_))
+            ));
+        }
+
         #[test]
         fn parse_nullable_reversed_order() {
             let schema = json!({"type": ["null", "string"]});
@@ -1219,6 +2620,29 @@ mod tests {
                 _ => panic!("Expected oneOf nullable boolean to parse correctly"),
             }
         }
+
+        #[test]
+        fn parse_oneof_objects_as_union() {
+            let schema = json!({
+                "oneOf": [
+                    {"type": "object", "properties": {"a": {"type": "boolean"}}, "required": ["a"]},
+                    {"type": "object", "properties": {"b": {"type": "null"}}, "required": ["b"]},
+                    {"type": "null"}
+                ]
+            });
+            match parse_json_schema(&schema) {
+                Ok(SchemaState::Nullable(inner)) => match inner.as_ref() {
+                    SchemaState::Union(variants) => {
+                        assert_eq!(variants.len(), 2);
+                        assert!(variants
+                            .iter()
+                            .all(|v| matches!(v, SchemaState::Object { .. })));
+                    }
+                    other => panic!("Expected union, got {:?}", other),
+                },
+                other => panic!("Expected nullable union, got {:?}", other),
+            }
+        }
     }
 
     mod error_handling {
@@ -1306,5 +2730,40 @@ mod tests {
                     .contains("minItems cannot be greater than maxItems"));
             }
         }
+
+        #[test]
+        fn malformed_schemas_return_errors() {
+            let schemas = [
+                json!(5),
+                json!([{"type": "string"}]),
+                json!({"type": 5}),
+                json!({"type": "strnig"}),
+                json!({"type": "string", "enum": "a"}),
+                json!({"type": "string", "minLength": -1}),
+                json!({"type": "integer", "minimum": "0"}),
+                json!({"type": "array", "items": 7}),
+                json!({"type": "object", "properties": {"a": []}}),
+                json!({"anyOf": {"type": "null"}}),
+                json!({"oneOf": [5, {"type": "null"}]}),
+            ];
+            for schema in schemas {
+                assert!(parse_json_schema(&schema).is_err(), "{}", schema);
+            }
+        }
+
+        #[test]
+        fn array_with_only_min_items_allows_that_many() {
+            let schema = json!({"type": "array", "items": {"type": "boolean"}, "minItems": 40});
+            assert_eq!(
+                parse_json_schema(&schema).unwrap(),
+                SchemaState::Array {
+                    min_length: 40,
+                    max_length: 40,
+                    lengths: None,
+                    unique_items: false,
+                    schema: Box::new(SchemaState::Boolean)
+                }
+            );
+        }
     }
 }
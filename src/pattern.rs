@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rand::Rng;
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+
+/// How many times more than its minimum a quantifier without a maximum, such as `+` or `*`,
+/// repeats at most, if the strings have no length bounds either.
+const UNBOUNDED_REPEATS: usize = 8;
+
+/// How many times more than its minimum a quantifier without a maximum repeats at most, however
+/// long the strings may be, as nested quantifiers such as `(a+)+` multiply their repeats.
+const MAX_UNBOUNDED_REPEATS: usize = 256;
+
+/// How many strings are generated from a pattern to find one that matches, such as because of
+/// where its anchors are, and that has a length within bounds.
+const ATTEMPTS: usize = 100;
+
+/// A `pattern` of a JSON Schema, that strings matching it can be generated from.
+struct Pattern {
+    hir: Hir,
+    regex: Regex,
+}
+
+thread_local! {
+    /// The patterns compiled on this thread, or `None` for those that strings can't be produced
+    /// from, so that each is only compiled once however many values are produced.
+    static COMPILED: RefCell<HashMap<String, Option<Rc<Pattern>>>> = RefCell::new(HashMap::new());
+}
+
+/// Whether strings matching `hir` can be generated by choosing from its classes, alternatives
+/// and repetitions. Word boundaries depend on the characters around them, so they can't be.
+fn is_generable(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Look(look) => matches!(
+            look,
+            Look::Start | Look::End | Look::StartLF | Look::EndLF | Look::StartCRLF | Look::EndCRLF
+        ),
+        HirKind::Class(Class::Bytes(class)) => class.is_ascii(),
+        kind => kind.subs().iter().all(is_generable),
+    }
+}
+
+fn compile(pattern: &str) -> Option<Rc<Pattern>> {
+    let hir = regex_syntax::parse(pattern).ok()?;
+    if !is_generable(&hir) {
+        return None;
+    }
+    let regex = Regex::new(pattern).ok()?;
+    let pattern = Pattern { hir, regex };
+    // patterns like `a^b` have nothing to generate from, as nothing matches them
+    let mut rng = rand::thread_rng();
+    (0..ATTEMPTS)
+        .any(|_| pattern.matches(&pattern.generate(UNBOUNDED_REPEATS as u32, &mut rng)))
+        .then(|| Rc::new(pattern))
+}
+
+fn compiled(pattern: &str) -> Option<Rc<Pattern>> {
+    COMPILED.with(|compiled| {
+        compiled
+            .borrow_mut()
+            .entry(pattern.to_string())
+            .or_insert_with(|| compile(pattern))
+            .clone()
+    })
+}
+
+/// Pushes a character of a class, from its printable ASCII characters if it has any, so that a
+/// class such as `.` doesn't produce control characters and other oddities.
+fn push_class_char<R: Rng + ?Sized>(class: &Class, out: &mut Vec<u8>, rng: &mut R) {
+    let ranges: Vec<(u32, u32)> = match class {
+        Class::Unicode(class) => class
+            .ranges()
+            .iter()
+            .map(|range| (u32::from(range.start()), u32::from(range.end())))
+            .collect(),
+        Class::Bytes(class) => class
+            .ranges()
+            .iter()
+            .map(|range| (u32::from(range.start()), u32::from(range.end())))
+            .collect(),
+    };
+    let printable: Vec<(u32, u32)> = ranges
+        .iter()
+        .map(|&(start, end)| (start.max(0x20), end.min(0x7E)))
+        .filter(|(start, end)| start <= end)
+        .collect();
+    let ranges = if printable.is_empty() {
+        ranges
+    } else {
+        printable
+    };
+    let total: u32 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+    if total == 0 {
+        return;
+    }
+    let mut index = rng.gen_range(0..total);
+    for (start, end) in ranges {
+        let size = end - start + 1;
+        if index < size {
+            let c = char::from_u32(start + index).unwrap_or('?');
+            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            return;
+        }
+        index -= size;
+    }
+}
+
+fn generate_into<R: Rng + ?Sized>(hir: &Hir, unbounded: u32, out: &mut Vec<u8>, rng: &mut R) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.extend_from_slice(&literal.0),
+        HirKind::Class(class) => push_class_char(class, out, rng),
+        HirKind::Repetition(repetition) => {
+            let max = repetition
+                .max
+                .unwrap_or(repetition.min.saturating_add(unbounded));
+            for _ in 0..rng.gen_range(repetition.min..=max) {
+                generate_into(&repetition.sub, unbounded, out, rng);
+            }
+        }
+        HirKind::Capture(capture) => generate_into(&capture.sub, unbounded, out, rng),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate_into(hir, unbounded, out, rng);
+            }
+        }
+        HirKind::Alternation(hirs) => {
+            let hir = &hirs[rng.gen_range(0..hirs.len())];
+            generate_into(hir, unbounded, out, rng);
+        }
+    }
+}
+
+/// Pushes the shortest string that matches `hir`, taking the first character of each class, the
+/// first alternative and the minimum of each quantifier.
+fn minimal_into(hir: &Hir, out: &mut Vec<u8>) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.extend_from_slice(&literal.0),
+        HirKind::Class(class) => {
+            let first = match class {
+                Class::Unicode(class) => class.ranges().first().map(|range| range.start()),
+                Class::Bytes(class) => class
+                    .ranges()
+                    .first()
+                    .map(|range| char::from(range.start())),
+            };
+            if let Some(c) = first {
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            for _ in 0..repetition.min {
+                minimal_into(&repetition.sub, out);
+            }
+        }
+        HirKind::Capture(capture) => minimal_into(&capture.sub, out),
+        HirKind::Concat(hirs) => hirs.iter().for_each(|hir| minimal_into(hir, out)),
+        HirKind::Alternation(hirs) => minimal_into(&hirs[0], out),
+    }
+}
+
+impl Pattern {
+    fn generate<R: Rng + ?Sized>(&self, unbounded: u32, rng: &mut R) -> String {
+        let mut out = vec![];
+        generate_into(&self.hir, unbounded, &mut out, rng);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    fn matches(&self, s: &str) -> bool {
+        self.regex.is_match(s)
+    }
+}
+
+/// Whether strings matching `pattern` can be produced. Patterns that the `regex` crate doesn't
+/// support, such as those with lookarounds, or that have word boundaries, can't be.
+pub(crate) fn is_supported_pattern(pattern: &str) -> bool {
+    compiled(pattern).is_some()
+}
+
+/// Whether `s` matches `pattern`, which need not be [supported](is_supported_pattern) for
+/// producing strings. A pattern that isn't a valid regular expression matches nothing.
+pub(crate) fn matches_pattern(pattern: &str, s: &str) -> bool {
+    match compiled(pattern) {
+        Some(compiled) => compiled.matches(s),
+        None => Regex::new(pattern).is_ok_and(|regex| regex.is_match(s)),
+    }
+}
+
+/// Produces a string that matches `pattern`, with a length within the bounds if it can, by
+/// choosing at random from its classes and alternatives, and repeating its quantifiers a random
+/// number of times. Quantifiers without a maximum repeat at most up to the maximum length, or a
+/// few times more than the minimum length if there is none. Returns `None` if the pattern isn't
+/// [supported](is_supported_pattern).
+pub(crate) fn produce_pattern<R: Rng + ?Sized>(
+    pattern: &str,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    rng: &mut R,
+) -> Option<String> {
+    let pattern = compiled(pattern)?;
+    let unbounded = max_length
+        .unwrap_or(min_length.unwrap_or(0) + UNBOUNDED_REPEATS)
+        .min(MAX_UNBOUNDED_REPEATS) as u32;
+    let within_bounds = |s: &str| {
+        let length = s.chars().count();
+        min_length.is_none_or(|min| length >= min) && max_length.is_none_or(|max| length <= max)
+    };
+    let mut matching = None;
+    for _ in 0..ATTEMPTS {
+        let s = pattern.generate(unbounded, rng);
+        if pattern.matches(&s) {
+            if within_bounds(&s) {
+                return Some(s);
+            }
+            matching.get_or_insert(s);
+        }
+    }
+    // the pattern matters more than the length, if no string was found within bounds
+    Some(matching.unwrap_or_else(|| pattern.generate(unbounded, rng)))
+}
+
+/// The shortest string that matches `pattern`, or a string produced from it if that is shorter
+/// than the minimum length. Returns `None` if the pattern isn't
+/// [supported](is_supported_pattern).
+pub(crate) fn minimal_pattern(pattern: &str, min_length: Option<usize>) -> Option<String> {
+    let compiled = compiled(pattern)?;
+    let mut out = vec![];
+    minimal_into(&compiled.hir, &mut out);
+    let minimal = String::from_utf8_lossy(&out).into_owned();
+    if compiled.matches(&minimal) && min_length.is_none_or(|min| minimal.chars().count() >= min) {
+        Some(minimal)
+    } else {
+        produce_pattern(pattern, min_length, None, &mut rand::thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn produces_strings_matching_patterns() {
+        for pattern in [
+            "^[a-z]+-[0-9]+$",
+            "^[A-Z]{3}[0-9]{2,4}$",
+            "^(GET|POST|PUT|DELETE) /[a-z/]*$",
+            "^\\d{3}-\\d{4}$",
+            "^[^@\\s]+@[^@\\s]+\\.(com|org)$",
+            "^(?i)sku_[a-f0-9]{8}$",
+            "v[0-9]+\\.[0-9]+",
+            "^\\p{Lu}\\w*$",
+            "^$",
+        ] {
+            let regex = Regex::new(pattern).unwrap();
+            assert!(is_supported_pattern(pattern), "{}", pattern);
+            for _ in 0..50 {
+                let s = produce_pattern(pattern, None, None, &mut thread_rng()).unwrap();
+                assert!(regex.is_match(&s), "{:?} doesn't match {}", s, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_unbounded_quantifiers_to_the_length() {
+        let regex = Regex::new("^[a-z]+$").unwrap();
+        for _ in 0..50 {
+            let s = produce_pattern("^[a-z]+$", Some(5), Some(10), &mut thread_rng()).unwrap();
+            assert!(regex.is_match(&s) && (5..=10).contains(&s.len()), "{:?}", s);
+        }
+        let s = produce_pattern("^x*$", None, Some(40), &mut thread_rng()).unwrap();
+        assert!(s.len() <= 40, "{:?}", s);
+    }
+
+    #[test]
+    fn produces_the_shortest_string_matching_a_pattern() {
+        assert_eq!(
+            minimal_pattern("^[A-Z]{3}-(x|yy)[0-9]*$", None).as_deref(),
+            Some("AAA-x")
+        );
+        let longer = minimal_pattern("^[a-z]+$", Some(4)).unwrap();
+        assert!(longer.len() >= 4, "{:?}", longer);
+    }
+
+    #[test]
+    fn rejects_patterns_that_cant_be_produced() {
+        for pattern in ["\\bword\\b", "^(?=a)a$", "(a", "a^b"] {
+            assert!(!is_supported_pattern(pattern), "{}", pattern);
+            assert_eq!(
+                produce_pattern(pattern, None, None, &mut thread_rng()),
+                None
+            );
+        }
+    }
+}
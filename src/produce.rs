@@ -7,203 +7,4313 @@ use fake::{
     },
     Fake, Faker,
 };
-use rand::{random, thread_rng, Rng};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use rayon::prelude::*;
+use serde::{Serialize, Serializer};
 use serde_json::Number;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-use crate::{NumberType, SchemaState, StringType};
+use crate::{
+    barcode::{produce_ean13, produce_isbn10, produce_isbn13},
+    date_format::{is_valid_date_format, minimal_date_format, produce_date_format},
+    diagnostics::warn,
+    duration::produce_duration,
+    extended_json::produce_extended_json,
+    financial::{produce_credit_card, produce_iban},
+    generators::CommandGenerator,
+    money::{produce_amount, produce_currency},
+    pattern::{minimal_pattern, produce_pattern},
+    produce_options::{field_path, items_path},
+    realistic::Semantic,
+    schema::is_geographic,
+    DerivedField, Distribution, DrivelError, ExtendedJsonType, FieldAccess, FieldOverride,
+    FieldStats, NonFiniteFallback, NumberType, ProduceOptions, SchemaState, StringCharset,
+    StringType, ValueFormat, ValueGenerator,
+};
 
-fn produce_inner(schema: &SchemaState, repeat_n: usize, current_depth: usize) -> serde_json::Value {
-    match schema {
-        SchemaState::Initial | SchemaState::Null => serde_json::Value::Null,
-        SchemaState::Nullable(inner) => {
-            let should_return_null: bool = random();
-            if should_return_null {
-                serde_json::Value::Null
+fn pick_variant<R: Rng + ?Sized>(variants: &BTreeSet<String>, rng: &mut R) -> String {
+    // variants are kept in sorted order, so for a given rng state the pick is stable
+    let idx = rng.gen_range(0..variants.len());
+    variants.iter().nth(idx).unwrap().clone()
+}
+
+/// A random buzzword, such as "face to face" or "24/7", made safe for use as a hostname label.
+fn host_label<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let buzzword: String = Buzzword().fake_with_rng(rng);
+    buzzword
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// The length of an array: sampled from its distribution of lengths if it has one, or picked
+/// within its bounds otherwise.
+fn array_length<R: Rng + ?Sized>(
+    min_length: usize,
+    max_length: usize,
+    lengths: Option<&BTreeMap<usize, usize>>,
+    rng: &mut R,
+) -> usize {
+    match lengths.filter(|lengths| !lengths.is_empty()) {
+        Some(lengths) => sample_length(lengths, rng),
+        None if min_length != max_length => rng.gen_range(min_length..=max_length),
+        None => min_length,
+    }
+}
+
+/// Picks an array length with a probability proportional to how often it was observed.
+fn sample_length<R: Rng + ?Sized>(lengths: &BTreeMap<usize, usize>, rng: &mut R) -> usize {
+    let total = lengths.values().sum::<usize>();
+    let mut remaining = rng.gen_range(0..total);
+    for (&length, &count) in lengths {
+        if remaining < count {
+            return length;
+        }
+        remaining -= count;
+    }
+    unreachable!()
+}
+
+/// Picks a variant of a mixed schema with a probability proportional to how many of its values
+/// were seen, or any of them alike if none were counted.
+fn pick_weighted<'a, R: Rng + ?Sized>(
+    variants: &'a [(SchemaState, usize)],
+    rng: &mut R,
+) -> &'a SchemaState {
+    let total = variants.iter().map(|(_, count)| count).sum::<usize>();
+    if total == 0 {
+        return &variants[rng.gen_range(0..variants.len())].0;
+    }
+    let mut remaining = rng.gen_range(0..total);
+    for (variant, count) in variants {
+        if remaining < *count {
+            return variant;
+        }
+        remaining -= count;
+    }
+    unreachable!()
+}
+
+/// Picks the length of a string of no particular format, between the shortest and longest seen.
+fn unknown_string_length<R: Rng + ?Sized>(
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    rng: &mut R,
+) -> usize {
+    let min = min_length.unwrap_or(0);
+    let max = max_length.unwrap_or(min.max(32));
+    if min != max {
+        rng.gen_range(min..=max)
+    } else {
+        min
+    }
+}
+
+/// The length from which strings of no particular format may be free text.
+const FREE_TEXT_MIN_LENGTH: usize = 40;
+
+/// Whether the strings seen look like free text, such as descriptions or comments, rather than
+/// codes or identifiers: long, mostly letters and punctuation, with words separated by spaces.
+fn is_free_text(chars_seen: &[char], max_length: Option<usize>) -> bool {
+    if chars_seen.is_empty() || max_length.is_none_or(|max| max < FREE_TEXT_MIN_LENGTH) {
+        return false;
+    }
+    let total = chars_seen.len() as f64;
+    let spaces = chars_seen.iter().filter(|c| **c == ' ').count() as f64;
+    let prose = chars_seen
+        .iter()
+        .filter(|c| c.is_alphabetic() || c.is_whitespace() || ".,;:!?'\"-()".contains(**c))
+        .count() as f64;
+    (0.05..=0.3).contains(&(spaces / total)) && prose >= 0.9 * total
+}
+
+/// Lorem ipsum sentences of 4 to 12 words, cut off at `length` characters.
+fn produce_lorem<R: Rng + ?Sized>(length: usize, rng: &mut R) -> String {
+    let mut text = String::new();
+    while text.len() < length {
+        let words = rng.gen_range(4..=12);
+        for i in 0..words {
+            let word: String = Word().fake_with_rng(rng);
+            if i == 0 {
+                let mut chars = word.chars();
+                text.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                text.push_str(chars.as_str());
             } else {
-                produce_inner(inner, repeat_n, current_depth + 1)
+                text.push_str(&word);
             }
+            text.push_str(if i + 1 == words { ". " } else { " " });
         }
-        SchemaState::String(string_type) => {
-            let value = match string_type {
-                StringType::IsoDate => {
-                    let date: NaiveDate = Faker.fake();
-                    date.to_string()
-                }
-                StringType::DateTimeISO8601 => {
-                    let date_time: DateTime<Utc> = Faker.fake();
-                    let date_time = date_time.round_subsecs(3);
-                    date_time.to_rfc3339()
-                }
-                StringType::DateTimeRFC2822 => {
-                    let date_time: DateTime<Utc> = Faker.fake();
-                    let date_time = date_time.round_subsecs(3);
-                    date_time.to_rfc2822()
-                }
-                StringType::UUID => {
-                    let uuid = uuid::Uuid::new_v4();
-                    uuid.to_string()
-                }
-                StringType::Email => FreeEmail().fake(),
-                StringType::Hostname => {
-                    let name: String = Buzzword().fake();
-                    let suffix: String = DomainSuffix().fake();
-                    format!("{}.{}", name.to_lowercase(), suffix)
-                }
-                StringType::Url => {
-                    let host: String = Buzzword().fake();
-                    let suffix: String = DomainSuffix().fake();
-                    let path: String = Word().fake();
-                    format!(
-                        "https://{}.{}/{}",
-                        host.to_lowercase(),
-                        suffix,
-                        path.to_lowercase()
-                    )
-                }
-                StringType::Unknown {
-                    chars_seen,
-                    min_length,
-                    max_length,
-                    ..
-                } => {
-                    let min = min_length.unwrap_or(0);
-                    let max = max_length.unwrap_or(32);
-                    let take_n = if min != max {
-                        thread_rng().gen_range(min..=max)
-                    } else {
-                        min
-                    };
+    }
+    let mut text: String = text.chars().take(length).collect();
+    if text.ends_with(' ') {
+        text.pop();
+        text.push('.');
+    }
+    text
+}
 
-                    if chars_seen.is_empty() {
-                        // we have no data at all to go by; generate a totally random string
-                        take_n.fake()
-                    } else {
-                        // otherwise we use the fact that we have collected all characters seen
-                        // to generate a random string with a similar character distribution to the
-                        // input data.
-                        (0..take_n)
-                            .map(|_| {
-                                let idx = thread_rng().gen_range(0..chars_seen.len());
-                                chars_seen[idx]
-                            })
-                            .collect()
-                    }
-                }
-                StringType::Enum { variants } => {
-                    let variants_vec = variants.iter().cloned().collect::<Vec<_>>();
-                    let idx = thread_rng().gen_range(0..variants_vec.len());
-                    variants_vec[idx].clone()
-                }
+/// Whether the strings seen look like numbers written as text, such as those read with
+/// [`ReadOptions::numbers_as_strings`](crate::ReadOptions::numbers_as_strings): digits, with
+/// signs or decimal points among them.
+fn is_numeric_text(chars_seen: &[char]) -> bool {
+    chars_seen.iter().any(char::is_ascii_digit)
+        && chars_seen.iter().any(|c| !c.is_ascii_digit())
+        && chars_seen
+            .iter()
+            .all(|c| c.is_ascii_digit() || *c == '-' || *c == '.')
+}
+
+/// A number written as text, of about `length` characters, with a sign and a decimal point
+/// about as often as among the characters seen.
+fn produce_numeric_text<R: Rng + ?Sized>(
+    length: usize,
+    chars_seen: &[char],
+    rng: &mut R,
+) -> String {
+    // the share of the strings seen that had a character, if they were about `length` long
+    let share = |c: char| {
+        let count = chars_seen.iter().filter(|seen| **seen == c).count();
+        (count as f64 * length as f64 / chars_seen.len() as f64).min(1.0)
+    };
+    let negative = rng.gen_bool(share('-'));
+    let digits = length.saturating_sub(usize::from(negative)).max(1);
+    let point = digits > 2 && rng.gen_bool(share('.'));
+    let digits = digits - usize::from(point);
+    let integer_digits = if point {
+        rng.gen_range(1..digits)
+    } else {
+        digits
+    };
+
+    let mut text = String::with_capacity(length);
+    if negative {
+        text.push('-');
+    }
+    for i in 0..digits {
+        if point && i == integer_digits {
+            text.push('.');
+        }
+        // no leading zeros
+        let min = u8::from(i == 0 && integer_digits > 1);
+        text.push(char::from(b'0' + rng.gen_range(min..10)));
+    }
+    text
+}
+
+pub(crate) fn produce_string<R: Rng + ?Sized>(
+    string_type: &StringType,
+    charset: Option<&StringCharset>,
+    rng: &mut R,
+) -> String {
+    match string_type {
+        StringType::IsoDate => {
+            let date: NaiveDate = Faker.fake_with_rng(rng);
+            date.to_string()
+        }
+        StringType::DateTimeISO8601 => {
+            let date_time: DateTime<Utc> = Faker.fake_with_rng(rng);
+            let date_time = date_time.round_subsecs(3);
+            date_time.to_rfc3339()
+        }
+        StringType::DateTimeRFC2822 => {
+            let date_time: DateTime<Utc> = Faker.fake_with_rng(rng);
+            let date_time = date_time.round_subsecs(3);
+            date_time.to_rfc2822()
+        }
+        StringType::UUID => {
+            let uuid = uuid::Builder::from_random_bytes(rng.gen()).into_uuid();
+            uuid.to_string()
+        }
+        StringType::Email => FreeEmail().fake_with_rng(rng),
+        StringType::Hostname => {
+            let suffix: String = DomainSuffix().fake_with_rng(rng);
+            format!("{}.{}", host_label(rng), suffix)
+        }
+        StringType::Ipv4 => std::net::Ipv4Addr::from(rng.gen::<u32>()).to_string(),
+        StringType::Ipv6 => std::net::Ipv6Addr::from(rng.gen::<u128>()).to_string(),
+        StringType::Url => {
+            let suffix: String = DomainSuffix().fake_with_rng(rng);
+            let path: String = Word().fake_with_rng(rng);
+            format!(
+                "https://{}.{}/{}",
+                host_label(rng),
+                suffix,
+                path.to_lowercase()
+            )
+        }
+        StringType::Unknown {
+            chars_seen,
+            min_length,
+            max_length,
+            ..
+        } => {
+            let take_n = unknown_string_length(*min_length, *max_length, rng);
+
+            let charset = charset.map(StringCharset::chars).filter(|c| !c.is_empty());
+            if let Some(chars) = charset {
+                (0..take_n)
+                    .map(|_| chars[rng.gen_range(0..chars.len())])
+                    .collect()
+            } else if is_free_text(chars_seen, *max_length) {
+                produce_lorem(take_n, rng)
+            } else if is_numeric_text(chars_seen) {
+                produce_numeric_text(take_n, chars_seen, rng)
+            } else if chars_seen.is_empty() {
+                // we have no data at all to go by; generate a totally random string
+                take_n.fake_with_rng(rng)
+            } else {
+                // otherwise we use the fact that we have collected all characters seen
+                // to generate a random string with a similar character distribution to the
+                // input data.
+                (0..take_n)
+                    .map(|_| {
+                        let idx = rng.gen_range(0..chars_seen.len());
+                        chars_seen[idx]
+                    })
+                    .collect()
+            }
+        }
+        StringType::CustomDateTime { formats } => produce_date_format(&formats[0], rng),
+        StringType::CreditCard => produce_credit_card(rng),
+        StringType::Iban => produce_iban(rng),
+        StringType::Isbn10 => produce_isbn10(rng),
+        StringType::Isbn13 => produce_isbn13(rng),
+        StringType::Ean13 => produce_ean13(rng),
+        StringType::Duration => produce_duration(rng),
+        StringType::Numeric { number } => produce_number(number, rng).to_string(),
+        StringType::FixedWidthNumeric { width } => (0..*width)
+            .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+            .collect(),
+        StringType::Charset {
+            class,
+            min_length,
+            max_length,
+        } => {
+            let chars = class.chars();
+            let length = rng.gen_range(*min_length..=*max_length);
+            (0..length)
+                .map(|_| chars[rng.gen_range(0..chars.len())])
+                .collect()
+        }
+        StringType::Pattern {
+            pattern,
+            min_length,
+            max_length,
+        } => produce_pattern(pattern, *min_length, *max_length, rng).unwrap_or_else(|| {
+            let unknown = StringType::Unknown {
+                strings_seen: vec![],
+                chars_seen: vec![],
+                min_length: *min_length,
+                max_length: *max_length,
+            };
+            produce_string(&unknown, charset, rng)
+        }),
+        StringType::Enum { variants } => pick_variant(variants, rng),
+        StringType::OpenEnum {
+            variants,
+            other_ratio,
+            other,
+        } => {
+            if rng.gen_bool(other_ratio.clamp(0.0, 1.0)) {
+                produce_string(other, charset, rng)
+            } else {
+                pick_variant(variants, rng)
+            }
+        }
+    }
+}
+
+/// How many numbers are drawn from a normal distribution for one within the range of its
+/// schema, before the last one is clamped to the range instead.
+const MAX_DISTRIBUTION_ATTEMPTS: usize = 8;
+
+/// Produces an integer or float from a normal distribution with the mean and standard deviation
+/// of the numbers that its schema was inferred from, within the range of the schema. Other
+/// numbers, and floats without finite bounds, are produced as by [`produce_number`].
+fn produce_distributed_number<R: Rng + ?Sized>(
+    number_type: &NumberType,
+    distribution: &Distribution,
+    rng: &mut R,
+) -> serde_json::Value {
+    let (min, max) = match *number_type {
+        NumberType::Integer { min, max } => (min as f64, max as f64),
+        NumberType::Float { min, max, .. } if min.is_finite() && max.is_finite() => (min, max),
+        _ => return produce_number(number_type, rng),
+    };
+    let mut number = distribution.mean;
+    for _ in 0..MAX_DISTRIBUTION_ATTEMPTS {
+        // the Box-Muller transform, with the first uniform value in (0, 1] for its logarithm
+        let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+        let angle = std::f64::consts::TAU * rng.gen::<f64>();
+        number = distribution.mean + distribution.std_dev * radius * angle.cos();
+        if (min..=max).contains(&number) {
+            break;
+        }
+    }
+    match *number_type {
+        NumberType::Integer { min, max } => {
+            serde_json::Value::Number(Number::from((number.round() as i64).clamp(min, max)))
+        }
+        _ => serde_json::Value::Number(Number::from_f64(number.clamp(min, max)).unwrap()),
+    }
+}
+
+fn produce_number<R: Rng + ?Sized>(number_type: &NumberType, rng: &mut R) -> serde_json::Value {
+    match *number_type {
+        NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. } => {
+            let number = if min != max {
+                rng.gen_range(min..=max)
+            } else {
+                min
             };
-            serde_json::Value::String(value)
+            serde_json::Value::Number(Number::from(number))
         }
-        SchemaState::Number(number_type) => match *number_type {
-            NumberType::Integer { min, max } => {
-                let number = if min != max {
-                    thread_rng().gen_range(min..=max)
+        // a range of NaN to NaN has no values besides the non-finite ones, which aren't
+        // produced here
+        NumberType::Float { min, .. } if min.is_nan() => serde_json::json!(0.0),
+        NumberType::Float { min, max, .. } => {
+            let number = if min != max {
+                // Handle infinite bounds and very large ranges safely
+                let safe_min = if min.is_infinite() {
+                    max.min(0.0) - 1e10
                 } else {
                     min
                 };
-                serde_json::Value::Number(Number::from(number))
-            }
-            NumberType::Float { min, max } => {
-                let number = if min != max {
-                    // Handle infinite bounds and very large ranges safely
-                    let safe_min = if min.is_infinite() { -1e10 } else { min };
-                    let safe_max = if max.is_infinite() { 1e10 } else { max };
-
-                    // Check if the range would cause overflow
-                    let range_size = safe_max - safe_min;
-                    if !range_size.is_finite() || range_size > 1e15 {
-                        // Use a reasonable fallback range
-                        thread_rng().gen_range(0.0..=1000.0)
-                    } else {
-                        thread_rng().gen_range(safe_min..=safe_max)
-                    }
+                let safe_max = if max.is_infinite() {
+                    safe_min.max(0.0) + 1e10
                 } else {
-                    min
+                    max
                 };
-                serde_json::Value::Number(Number::from_f64(number).unwrap())
+
+                // Check if the range would cause overflow
+                let range_size = safe_max - safe_min;
+                let number = if !range_size.is_finite() || range_size > 1e15 {
+                    // Use a reasonable fallback range
+                    rng.gen_range(0.0..=1000.0)
+                } else {
+                    rng.gen_range(safe_min..=safe_max)
+                };
+                // the bounds may be the closest floats to an exclusive bound from a JSON
+                // Schema, so guard against the fallback range or rounding crossing them
+                number.clamp(min, max)
+            } else {
+                min
+            };
+            serde_json::Value::Number(Number::from_f64(number).unwrap())
+        }
+        NumberType::Enum { ref variants } => {
+            let idx = rng.gen_range(0..variants.len());
+            serde_json::Value::Number(Number::from(*variants.keys().nth(idx).unwrap()))
+        }
+        // each flag is set or not at random
+        NumberType::Flags { .. } => {
+            let flags: Vec<i64> = number_type
+                .flags()
+                .into_iter()
+                .filter(|_| rng.gen_bool(0.5))
+                .collect();
+            serde_json::Value::Number(Number::from(NumberType::flag_mask(&flags)))
+        }
+        NumberType::Multiple { .. } => {
+            let (lowest, highest) = number_type.multiples();
+            number_type.multiple(rng.gen_range(lowest..=highest))
+        }
+    }
+}
+
+/// Produces a number within the range of a [`FieldOverride`], of the same kind (integer or
+/// float) as the inferred schema.
+fn produce_number_in_range<R: Rng + ?Sized>(
+    schema: &SchemaState,
+    min: Option<f64>,
+    max: Option<f64>,
+    rng: &mut R,
+) -> serde_json::Value {
+    let number_type = match schema {
+        SchemaState::Nullable(inner) => return produce_number_in_range(inner, min, max, rng),
+        SchemaState::Number(
+            NumberType::Integer {
+                min: inferred_min,
+                max: inferred_max,
             }
-        },
-        SchemaState::Boolean => serde_json::Value::Bool(random()),
-        SchemaState::Array {
-            min_length,
-            max_length,
+            | NumberType::Sequence {
+                min: inferred_min,
+                max: inferred_max,
+                ..
+            },
+        ) => {
+            let min = min.map(|v| v.ceil() as i64).unwrap_or(*inferred_min);
+            let max = max.map(|v| v.floor() as i64).unwrap_or(*inferred_max);
+            NumberType::Integer {
+                min,
+                max: max.max(min),
+            }
+        }
+        SchemaState::Number(NumberType::Float {
+            min: inferred_min,
+            max: inferred_max,
+            ..
+        }) => {
+            let min = min.unwrap_or(*inferred_min);
+            let max = max.unwrap_or(*inferred_max);
+            NumberType::Float {
+                min,
+                max: max.max(min),
+                non_finite: BTreeSet::new(),
+            }
+        }
+        _ => {
+            let min = min.unwrap_or(f64::NEG_INFINITY);
+            let max = max.unwrap_or(f64::INFINITY);
+            NumberType::Float {
+                min,
+                max: max.max(min),
+                non_finite: BTreeSet::new(),
+            }
+        }
+    };
+    produce_number(&number_type, rng)
+}
+
+/// Produces a value according to an override, or returns `None` if the override does not
+/// replace the inferred schema for this particular value.
+fn produce_override(
+    schema: &SchemaState,
+    field_override: &FieldOverride,
+    ctx: &Context,
+    rng: &mut StdRng,
+) -> Option<serde_json::Value> {
+    if let Some(probability) = field_override.null_probability {
+        if rng.gen_bool(probability.clamp(0.0, 1.0)) {
+            return Some(serde_json::Value::Null);
+        }
+    }
+
+    if let Some(value) = &field_override.value {
+        Some(value.clone())
+    } else if let Some(pool) = field_override.pool.as_ref().filter(|p| !p.is_empty()) {
+        Some(pool[rng.gen_range(0..pool.len())].clone())
+    } else if let Some(value) = field_override
+        .references
+        .as_ref()
+        .and_then(|path| ctx.pick_referenced(path, rng))
+    {
+        Some(value)
+    } else if let Some(format) = field_override.format {
+        let string_type = format.string_type();
+        Some(
+            ctx.options
+                .generators
+                .for_format(format)
+                .and_then(|generator| {
+                    generator.try_generate(&SchemaState::String(string_type.clone()), rng)
+                })
+                .unwrap_or_else(|| {
+                    serde_json::Value::String(produce_string(&string_type, None, rng))
+                }),
+        )
+    } else if field_override.min.is_some() || field_override.max.is_some() {
+        Some(produce_number_in_range(
             schema,
-        } => {
-            if schema.as_ref() == &SchemaState::Indefinite
-                || schema.as_ref() == &SchemaState::Initial
-            {
-                return serde_json::Value::Array(vec![]);
+            field_override.min,
+            field_override.max,
+            rng,
+        ))
+    } else {
+        None
+    }
+}
+
+/// An error that prevents data from being produced as requested.
+#[derive(Debug, PartialEq)]
+pub enum ProduceError {
+    /// No more distinct values could be produced for a field that must be unique.
+    UniquenessUnsatisfiable { path: String, produced: usize },
+    /// The schema can't have any values at a path, such as an enum without variants or a range
+    /// whose minimum is greater than its maximum. Inferred schemas never are, but hand-written or
+    /// edited ones can be.
+    InvalidSchema { path: String, reason: String },
+    /// Derived fields of the same object depend on each other in a cycle, so none of them can
+    /// be computed first. They are computed in the order they were given instead.
+    CyclicDerivedFields { paths: Vec<String> },
+}
+
+impl fmt::Display for ProduceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProduceError::UniquenessUnsatisfiable { path, produced } => write!(
+                f,
+                "Unable to produce more than {} unique values for '{}'",
+                produced, path
+            ),
+            ProduceError::InvalidSchema { path, reason } if path.is_empty() => {
+                write!(f, "Unable to produce values: {}", reason)
+            }
+            ProduceError::InvalidSchema { path, reason } => {
+                write!(f, "Unable to produce values for '{}': {}", path, reason)
+            }
+            ProduceError::CyclicDerivedFields { paths } => write!(
+                f,
+                "The derived fields '{}' depend on each other in a cycle",
+                paths.join("', '")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProduceError {}
+
+/// How many times a value is produced again when it collides with one produced before, for a
+/// field that must be unique.
+const MAX_UNIQUE_ATTEMPTS: usize = 1000;
+
+/// How many levels of a recursive definition are nested within it at most, by default.
+pub(crate) const DEFAULT_MAX_RECURSION_DEPTH: usize = 3;
+
+/// How many values [`try_produce_each`] produces in parallel before handing them over.
+const STREAM_CHUNK_SIZE: usize = 1024;
+
+/// The probability of producing a non-finite value for a float that had some, unless it had
+/// nothing else.
+const NON_FINITE_PROBABILITY: f64 = 0.1;
+
+/// Where a value is produced: its path, which it shares with e.g. the other elements of the same
+/// array and which options refer to, and its JSON pointer, which is unique to the value. The
+/// pointer is only needed to derive seeded random values, so it is only kept track of then.
+struct Location<'s> {
+    path: String,
+    pointer: Option<String>,
+    /// The recursive definitions that the value is within, innermost last, which the
+    /// [`SchemaState::Reference`]s within it are values of.
+    recursion: Vec<(&'s str, &'s SchemaState)>,
+}
+
+impl<'s> Location<'s> {
+    fn field(&self, name: &str) -> Self {
+        Location {
+            path: field_path(&self.path, name),
+            pointer: self
+                .pointer
+                .as_ref()
+                .map(|pointer| pointer_to(pointer, name)),
+            recursion: self.recursion.clone(),
+        }
+    }
+
+    fn item(&self, index: usize) -> Self {
+        Location {
+            path: items_path(&self.path),
+            pointer: self
+                .pointer
+                .as_ref()
+                .map(|pointer| format!("{}/{}", pointer, index)),
+            recursion: self.recursion.clone(),
+        }
+    }
+
+    fn entry(&self, key: &str) -> Self {
+        Location {
+            path: field_path(&self.path, "*"),
+            pointer: self
+                .pointer
+                .as_ref()
+                .map(|pointer| pointer_to(pointer, key)),
+            recursion: self.recursion.clone(),
+        }
+    }
+
+    /// The same location, within another level of the recursive definition `name`.
+    fn within(&self, name: &'s str, definition: &'s SchemaState) -> Self {
+        let mut recursion = self.recursion.clone();
+        recursion.push((name, definition));
+        Location {
+            path: self.path.clone(),
+            pointer: self.pointer.clone(),
+            recursion,
+        }
+    }
+
+    /// How many levels of the recursive definition `name` the location is within.
+    fn levels(&self, name: &str) -> usize {
+        self.recursion.iter().filter(|(n, _)| *n == name).count()
+    }
+}
+
+/// Appends a reference token to a JSON pointer, escaping it as described in RFC 6901.
+fn pointer_to(pointer: &str, token: &str) -> String {
+    format!(
+        "{}/{}",
+        pointer,
+        token.replace('~', "~0").replace('/', "~1")
+    )
+}
+
+/// Derives the seed for the random values at a JSON pointer from the seed of the whole run, by
+/// taking the 64-bit FNV-1a hash of the seed, the pointer and the name of the stream of values
+/// (to tell apart e.g. whether an optional field is present from its value). Unlike the hashers
+/// of the standard library, this is guaranteed to stay the same across platforms and releases.
+fn derive_seed(seed: u64, pointer: &str, stream: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    // 0xff never occurs in UTF-8, so it separates the pointer from the stream unambiguously
+    seed.to_le_bytes()
+        .iter()
+        .chain(pointer.as_bytes())
+        .chain(&[0xff])
+        .chain(stream.as_bytes())
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// Runs `produce` on a thread pool of [`ProduceOptions::jobs`] threads if it is set, or on the
+/// global thread pool otherwise. Values produced in parallel are collected in index order either
+/// way, so that the number of threads only affects how fast they are produced.
+fn with_jobs<T: Send>(options: &ProduceOptions, produce: impl FnOnce() -> T + Send) -> T {
+    with_pool(thread_pool(options).as_ref(), produce)
+}
+
+/// The thread pool of [`ProduceOptions::jobs`], if there is one and its threads could be
+/// spawned.
+fn thread_pool(options: &ProduceOptions) -> Option<rayon::ThreadPool> {
+    let jobs = options.jobs?;
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .ok()
+}
+
+fn with_pool<T: Send>(pool: Option<&rayon::ThreadPool>, produce: impl FnOnce() -> T + Send) -> T {
+    match pool {
+        Some(pool) => pool.install(produce),
+        // the global pool is still there if no threads could be spawned for a pool of our own
+        None => produce(),
+    }
+}
+
+/// The distinct values produced at a path that other fields refer to, in the order they were
+/// first produced.
+#[derive(Default)]
+struct ReferencedValues {
+    seen: HashSet<String>,
+    values: Vec<serde_json::Value>,
+}
+
+impl ReferencedValues {
+    fn insert(&mut self, value: &serde_json::Value) {
+        if self.seen.insert(value.to_string()) {
+            self.values.push(value.clone());
+        }
+    }
+}
+
+struct Context<'a> {
+    repeat_n: usize,
+    options: &'a ProduceOptions,
+    /// The serialized values produced so far at each path that must be unique, of the
+    /// [`ProduceOptions::unique_fields`] and the unique fields of the
+    /// [`ProduceOptions::field_stats`].
+    unique: HashMap<&'a str, Mutex<HashSet<String>>>,
+    /// The unique fields of the field stats whose values were repeated, as they couldn't all
+    /// be different, which is warned about rather than an error.
+    repeated: Mutex<BTreeSet<String>>,
+    /// The first error encountered, if any.
+    error: Mutex<Option<ProduceError>>,
+    /// The number of values produced so far at each path with a sequence.
+    sequences: Mutex<HashMap<String, i64>>,
+    /// The values produced so far at each path that a [`FieldOverride::references`] refers to.
+    referenced: HashMap<&'a str, Mutex<ReferencedValues>>,
+    /// The approximate size in bytes of the data produced so far.
+    bytes: AtomicUsize,
+    /// Whether any data was left out for exceeding [`ProduceOptions::max_output_bytes`].
+    truncated: AtomicBool,
+    /// The rules of [`ProduceOptions::derived`] for the fields of the objects at each path, in
+    /// the order they are applied.
+    derived: HashMap<&'a str, Vec<&'a DerivedField>>,
+    /// The generators of the [`ProduceOptions::commands`], by their paths, with the values
+    /// that each command wrote that haven't been used yet.
+    commands: HashMap<&'a str, CommandGenerator<'a>>,
+}
+
+impl<'a> Context<'a> {
+    fn new(repeat_n: usize, options: &'a ProduceOptions) -> Self {
+        let (derived, cycle) = derived_order(&options.derived);
+        Context {
+            repeat_n,
+            options,
+            unique: options
+                .unique_fields
+                .iter()
+                .map(String::as_str)
+                .chain(inferred_unique_fields(options))
+                .map(|path| (path, Mutex::new(HashSet::new())))
+                .collect(),
+            repeated: Mutex::new(BTreeSet::new()),
+            error: Mutex::new(cycle),
+            sequences: Mutex::new(HashMap::new()),
+            referenced: options
+                .fields
+                .values()
+                .filter_map(|o| o.references.as_deref())
+                .map(|path| (path, Mutex::default()))
+                .collect(),
+            bytes: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+            derived,
+            commands: options
+                .commands
+                .iter()
+                .map(|(path, command)| (path.as_str(), CommandGenerator::new(path, command)))
+                .collect(),
+        }
+    }
+
+    fn root<'s>(&self) -> Location<'s> {
+        Location {
+            path: String::new(),
+            pointer: self.options.seed.map(|_| String::new()),
+            recursion: vec![],
+        }
+    }
+
+    /// The root of the `index`th of several separately produced values.
+    fn record<'s>(&self, index: usize) -> Location<'s> {
+        Location {
+            path: String::new(),
+            pointer: self.options.seed.map(|_| format!("/{}", index)),
+            recursion: vec![],
+        }
+    }
+
+    /// The random number generator for a stream of values at a location, which is derived from
+    /// the seed and the location's JSON pointer if there is a seed, so that it doesn't depend on
+    /// the order in which values are produced.
+    fn rng(&self, location: &Location, stream: &str) -> StdRng {
+        match self.options.seed {
+            Some(seed) => {
+                let pointer = location.pointer.as_deref().unwrap_or_default();
+                StdRng::seed_from_u64(derive_seed(seed, pointer, stream))
             }
+            None => StdRng::from_rng(thread_rng()).unwrap(),
+        }
+    }
 
-            let n_elements = if current_depth == 0 && repeat_n > 1 {
-                // if we are dealing with an array at the root and user requested multiple repetitions,
-                // we produce the requested `n` elements
-                repeat_n
-            } else if min_length != max_length {
-                thread_rng().gen_range(*min_length..=*max_length)
-            } else {
-                *min_length
-            };
+    /// Whether the values of a schema must be produced one after the other, rather than in
+    /// parallel: a sequence must increase from one value to the next, and with a seed, which of
+    /// several colliding values of a unique field is kept must not depend on timing.
+    fn in_order(&self, schema: &SchemaState) -> bool {
+        contains_sequence(schema)
+            || (self.options.seed.is_some()
+                && !(self.unique.is_empty() && self.referenced.is_empty()))
+    }
 
-            let data: Vec<_> = (0..n_elements)
-                .into_par_iter()
-                .map(|_| produce_inner(schema, repeat_n, current_depth + 1))
-                .collect();
-            serde_json::Value::Array(data)
+    /// Whether the value at a path is, or contains, a value that another field refers to, and
+    /// so must be produced before the fields of the same object that don't.
+    fn leads_to_referenced(&self, path: &str) -> bool {
+        self.referenced.keys().any(|referenced| {
+            referenced
+                .strip_prefix(path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        })
+    }
+
+    /// Keeps a value produced at a path, if another field refers to it.
+    fn keep_referenced(&self, path: &str, value: &serde_json::Value) {
+        if let (Some(referenced), false) = (self.referenced.get(path), value.is_null()) {
+            referenced.lock().unwrap().insert(value);
         }
-        SchemaState::Object { required, optional } => {
-            let mut map = serde_json::Map::new();
-            for (k, v) in required.iter() {
-                let value = produce_inner(v, repeat_n, current_depth + 1);
-                map.insert(k.clone(), value);
-            }
-            for (k, v) in optional.iter() {
-                let should_include: bool = random();
-                if should_include {
-                    let value = produce_inner(v, repeat_n, current_depth + 1);
-                    map.insert(k.clone(), value);
+    }
+
+    /// One of the values produced so far at a referenced path, if any were.
+    fn pick_referenced(&self, path: &str, rng: &mut StdRng) -> Option<serde_json::Value> {
+        let referenced = self.referenced.get(path)?.lock().unwrap();
+        let values = &referenced.values;
+        (!values.is_empty()).then(|| values[rng.gen_range(0..values.len())].clone())
+    }
+
+    /// The probability that the optional field at a location is present, if it isn't the
+    /// default of one half.
+    fn include_probability(&self, field: &Location) -> Option<f64> {
+        self.options.optional_include_probability.or_else(|| {
+            let counts = self.options.field_stats.as_ref()?.get(&field.path)?;
+            Some(counts.presence())
+        })
+    }
+
+    /// The position of the next value of the sequence at `path`.
+    fn next_in_sequence(&self, path: &str) -> i64 {
+        let mut sequences = self.sequences.lock().unwrap();
+        let position = sequences.entry(path.to_string()).or_insert(0);
+        *position += 1;
+        *position - 1
+    }
+
+    /// Whether values at `location` can't be produced for a schema without going deeper into a
+    /// recursive definition than [`ProduceOptions::max_recursion_depth`] allows, because it
+    /// requires a [`SchemaState::Reference`] to one that the location is within that many
+    /// times already. Such values are left out where the schema allows it.
+    fn too_deep(&self, schema: &SchemaState, location: &Location) -> bool {
+        let max_depth = self
+            .options
+            .max_recursion_depth
+            .unwrap_or(DEFAULT_MAX_RECURSION_DEPTH);
+        let cut = |name: &str| location.levels(name) > max_depth;
+        // most values aren't within any definition that deep, and needn't be looked into
+        location.recursion.iter().any(|(name, _)| cut(name)) && !schema.can_avoid(&cut)
+    }
+
+    /// Adds the size of a produced value to the size of the data.
+    fn count(&self, bytes: usize) {
+        if self.options.max_output_bytes.is_some() {
+            self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the data has reached [`ProduceOptions::max_output_bytes`], in which case no more
+    /// elements or optional fields are produced. Asking this is what leaves them out, so it must
+    /// only be asked right before producing them.
+    fn over_budget(&self) -> bool {
+        let over = self
+            .options
+            .max_output_bytes
+            .is_some_and(|max| self.bytes.load(Ordering::Relaxed) >= max);
+        if over {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        over
+    }
+
+    /// Warns of the fields whose values were all different in the input but had to be repeated.
+    fn warn_if_repeated(&self) {
+        for path in self.repeated.lock().unwrap().iter() {
+            warn(format!(
+                "the values of '{}' were all different in the input, but can't all be different in the produced data; some are repeated",
+                path
+            ));
+        }
+    }
+
+    /// Warns if any data was left out for exceeding [`ProduceOptions::max_output_bytes`].
+    fn warn_if_truncated(&self) {
+        if let (Some(max), true) = (
+            self.options.max_output_bytes,
+            self.truncated.load(Ordering::Relaxed),
+        ) {
+            warn(format!("stopped producing data after reaching the maximum of {} bytes; arrays and maps were cut short and optional fields left out",
+                max
+            ));
+        }
+    }
+
+    fn warn_if_commands_failed(&self) {
+        let mut paths: Vec<_> = self.commands.keys().collect();
+        paths.sort();
+        for path in paths {
+            if let Some(reason) = self.commands[path].failure() {
+                warn(format!(
+                    "the command generating the values of '{}' failed, as {}; they were produced as usual",
+                    path, reason
+                ));
+            }
+        }
+    }
+
+    fn into_result<T>(self, value: T) -> Result<T, ProduceError> {
+        self.warn_if_truncated();
+        self.warn_if_repeated();
+        self.warn_if_commands_failed();
+        match self.error.into_inner().unwrap() {
+            Some(err) => Err(err),
+            None => Ok(value),
+        }
+    }
+}
+
+/// Groups derived fields by the path of their object and orders each group so that every rule
+/// comes after those for the fields it depends on, keeping the given order otherwise. Rules that
+/// depend on each other in a cycle are left in the given order, and returned as an error.
+fn derived_order(
+    rules: &[DerivedField],
+) -> (HashMap<&str, Vec<&DerivedField>>, Option<ProduceError>) {
+    let mut groups: HashMap<&str, Vec<&DerivedField>> = HashMap::new();
+    for rule in rules {
+        groups.entry(rule.location().0).or_default().push(rule);
+    }
+    let mut cycle = vec![];
+    for group in groups.values_mut() {
+        let mut pending = std::mem::take(group);
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|rule| {
+                rule.depends_on.iter().all(|dependency| {
+                    !pending
+                        .iter()
+                        .any(|other| other.location().1 == dependency && other.path != rule.path)
+                })
+            });
+            match ready {
+                Some(index) => group.push(pending.remove(index)),
+                None => {
+                    cycle.extend(pending.iter().map(|rule| rule.path.clone()));
+                    group.append(&mut pending);
                 }
             }
-            serde_json::Value::Object(map)
         }
-        SchemaState::Indefinite => serde_json::Value::Null,
     }
+    cycle.sort();
+    let error = (!cycle.is_empty()).then_some(ProduceError::CyclicDerivedFields { paths: cycle });
+    (groups, error)
 }
 
-/// Produces a JSON value based on the given schema.
-///
-/// This function generates a JSON value based on the provided schema state.
-///
-/// # Arguments
-///
-/// * `schema` - The schema state to produce JSON values for.
-/// * `repeat_n` - The number of times to repeat generation (used for arrays at the JSON root).
-///
-/// # Returns
-///
-/// # Examples
-///
-/// ```
-/// use drivel::{SchemaState, NumberType, produce};
-///
-/// // The inferred schema consists of an array with length = 1
-/// let schema = SchemaState::Array {
-///     min_length: 1,
-///     max_length: 1,
-///     schema: Box::new(SchemaState::Number(NumberType::Integer { min: 0, max: 100 })),
-/// };
-///
-/// // Generate three values based on the schema
-/// let json_data = produce(&schema, 3);
-///
-/// // Do something with the generated JSON data
-/// println!("{}", json_data);
+/// Applies the derived fields of the objects at a location to a produced object.
+fn apply_derived(
+    ctx: &Context,
+    location: &Location,
+    mut fields: serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    for rule in ctx
+        .derived
+        .get(location.path.as_str())
+        .into_iter()
+        .flatten()
+    {
+        let field = rule.location().1;
+        if fields.contains_key(field) {
+            let value = rule.compute(&fields);
+            fields.insert(field.to_string(), value);
+        }
+    }
+    fields
+}
+
+/// The number of distinct values that can be produced for a schema, if it is finite and small
+/// enough to count.
+fn distinct_values(schema: &SchemaState) -> Option<usize> {
+    match schema {
+        SchemaState::Initial | SchemaState::Null => Some(1),
+        SchemaState::Boolean => Some(2),
+        SchemaState::Nullable(inner) => distinct_values(inner)?.checked_add(1),
+        SchemaState::String(StringType::Enum { variants }) => Some(variants.len()),
+        SchemaState::String(StringType::FixedWidthNumeric { width }) => {
+            10_usize.checked_pow(u32::try_from(*width).ok()?)
+        }
+        SchemaState::Number(NumberType::Integer { min, max }) => {
+            usize::try_from(i128::from(*max) - i128::from(*min) + 1).ok()
+        }
+        SchemaState::Number(NumberType::Enum { variants }) => Some(variants.len()),
+        SchemaState::Literal(values) => Some(values.len()),
+        SchemaState::Number(number_type @ NumberType::Flags { .. }) => {
+            1_usize.checked_shl(u32::try_from(number_type.flags().len()).ok()?)
+        }
+        SchemaState::Union(variants) => variants.iter().try_fold(0_usize, |total, variant| {
+            total.checked_add(distinct_values(variant)?)
+        }),
+        SchemaState::Mixed(variants) => variants.iter().try_fold(0_usize, |total, (variant, _)| {
+            total.checked_add(distinct_values(variant)?)
+        }),
+        _ => None,
+    }
+}
+
+/// Whether a schema has a sequence anywhere within it, in which case its values must be produced
+/// in order.
+fn contains_sequence(schema: &SchemaState) -> bool {
+    match schema {
+        SchemaState::Number(NumberType::Sequence { .. }) => true,
+        SchemaState::Nullable(inner) => contains_sequence(inner),
+        SchemaState::Array { schema, .. } | SchemaState::Map { schema, .. } => {
+            contains_sequence(schema)
+        }
+        SchemaState::Tuple(positions) => positions.iter().any(contains_sequence),
+        SchemaState::Object {
+            required, optional, ..
+        } => required
+            .values()
+            .chain(optional.values())
+            .any(contains_sequence),
+        SchemaState::Union(variants) => variants.iter().any(contains_sequence),
+        SchemaState::Mixed(variants) => variants
+            .iter()
+            .any(|(variant, _)| contains_sequence(variant)),
+        SchemaState::TaggedUnion { variants, .. } => variants.values().any(contains_sequence),
+        SchemaState::Recursive { schema, .. } => contains_sequence(schema),
+        _ => false,
+    }
+}
+
+/// The paths of the unique fields of the [`ProduceOptions::field_stats`], except for those
+/// whose values are given by an override, transform or generator, which needn't be anything
+/// like those of the input.
+fn inferred_unique_fields(options: &ProduceOptions) -> impl Iterator<Item = &str> {
+    let unique = options
+        .field_stats
+        .as_ref()
+        .map(FieldStats::unique_fields)
+        .unwrap_or_default();
+    unique.into_iter().filter(|path| {
+        !options.unique_fields.iter().any(|unique| unique == path)
+            && !options.fields.contains_key(*path)
+            && !options.transforms.contains_key(*path)
+            && options.generators.for_field(path).is_none()
+            && !options.commands.contains_key(*path)
+    })
+}
+
+fn produce_inner<'s>(
+    schema: &'s SchemaState,
+    ctx: &Context,
+    location: &Location<'s>,
+    current_depth: usize,
+) -> serde_json::Value {
+    let value = match ctx.unique.get(location.path.as_str()) {
+        Some(seen) => produce_unique(schema, ctx, location, current_depth, seen),
+        None => {
+            let mut rng = ctx.rng(location, "value");
+            produce_value(schema, ctx, location, current_depth, &mut rng)
+        }
+    };
+    ctx.keep_referenced(&location.path, &value);
+    value
+}
+
+/// Produces a value that differs from all the values produced before at the same path, by
+/// producing values until one hasn't been seen yet. If that fails, the error is recorded and
+/// the last value is returned regardless.
+fn produce_unique<'s>(
+    schema: &'s SchemaState,
+    ctx: &Context,
+    location: &Location<'s>,
+    current_depth: usize,
+    seen: &Mutex<HashSet<String>>,
+) -> serde_json::Value {
+    // overrides, transforms and generators may produce other values than the schema describes
+    let capacity = distinct_values(schema).filter(|_| {
+        !ctx.options.fields.contains_key(&location.path)
+            && !ctx.options.transforms.contains_key(&location.path)
+            && ctx.options.generators.for_field(&location.path).is_none()
+            && !ctx.options.commands.contains_key(&location.path)
+    });
+    let mut value = serde_json::Value::Null;
+    for attempt in 0..MAX_UNIQUE_ATTEMPTS {
+        // each attempt needs a stream of its own, or a seeded attempt would repeat the last one
+        let stream = match attempt {
+            0 => "value".to_string(),
+            _ => format!("value-retry-{}", attempt),
+        };
+        let mut rng = ctx.rng(location, &stream);
+        value = produce_value(schema, ctx, location, current_depth, &mut rng);
+        let mut seen = seen.lock().unwrap();
+        if seen.insert(value.to_string()) {
+            return value;
+        }
+        if capacity.is_some_and(|capacity| seen.len() >= capacity) {
+            break;
+        }
+    }
+
+    if !ctx.options.unique_fields.contains(&location.path) {
+        ctx.repeated.lock().unwrap().insert(location.path.clone());
+        return value;
+    }
+    let produced = seen.lock().unwrap().len();
+    ctx.error
+        .lock()
+        .unwrap()
+        .get_or_insert(ProduceError::UniquenessUnsatisfiable {
+            path: location.path.clone(),
+            produced,
+        });
+    value
+}
+
+/// The size of a value as minified JSON.
+fn serialized_size(value: &serde_json::Value) -> usize {
+    value.to_string().len()
+}
+
+/// The size of a value as minified JSON, without that of its elements or the values of its
+/// fields: the brackets and commas of an array, the braces, names and punctuation of an object,
+/// and the whole of anything else.
+fn shallow_size(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Array(items) => 2 + items.len().saturating_sub(1),
+        serde_json::Value::Object(fields) => {
+            2 + fields.keys().map(|key| key.len() + 4).sum::<usize>()
+        }
+        _ => serialized_size(value),
+    }
+}
+
+/// Produces a value for the schema at `location`, applying any override and transform for its
+/// path.
+fn produce_value<'s>(
+    schema: &'s SchemaState,
+    ctx: &Context,
+    location: &Location<'s>,
+    current_depth: usize,
+    rng: &mut StdRng,
+) -> serde_json::Value {
+    let field_override = ctx.options.fields.get(&location.path);
+    let generator = ctx
+        .options
+        .generators
+        .for_field(&location.path)
+        .or_else(|| {
+            let command = ctx.commands.get(location.path.as_str())?;
+            Some(command as &dyn ValueGenerator)
+        });
+    let value = match field_override.and_then(|o| produce_override(schema, o, ctx, rng)) {
+        Some(value) => {
+            ctx.count(serialized_size(&value));
+            value
+        }
+        None => match generator.and_then(|generator| generator.try_generate(schema, rng)) {
+            Some(value) => {
+                ctx.count(serialized_size(&value));
+                value
+            }
+            None => {
+                let null_overridden = field_override.is_some_and(|o| o.null_probability.is_some());
+                let value =
+                    produce_schema(schema, ctx, location, current_depth, null_overridden, rng);
+                // the elements and fields have been counted as they were produced
+                ctx.count(shallow_size(&value));
+                value
+            }
+        },
+    };
+    match ctx.options.transforms.get(&location.path) {
+        Some(transform) => transform.apply(value),
+        None => value,
+    }
+}
+
+/// Produces a value for the schema at `location`, after any override for its path has been
+/// considered. Nullable and union schemas describe the same value as their inner schemas, so
+/// they recurse into this directly, rather than applying the override again.
+fn produce_schema<'s>(
+    schema: &'s SchemaState,
+    ctx: &Context,
+    location: &Location<'s>,
+    current_depth: usize,
+    null_overridden: bool,
+    rng: &mut StdRng,
+) -> serde_json::Value {
+    match schema {
+        SchemaState::Initial | SchemaState::Null => serde_json::Value::Null,
+        SchemaState::Literal(values) => values[rng.gen_range(0..values.len())].clone(),
+        SchemaState::Nullable(inner) => {
+            // an override's null probability replaces the default one
+            let should_return_null = (!null_overridden
+                && match ctx.options.null_probability {
+                    Some(probability) => rng.gen_bool(probability.clamp(0.0, 1.0)),
+                    None => rng.gen(),
+                })
+                || ctx.too_deep(inner, location);
+            if should_return_null {
+                serde_json::Value::Null
+            } else {
+                produce_schema(
+                    inner,
+                    ctx,
+                    location,
+                    current_depth + 1,
+                    null_overridden,
+                    rng,
+                )
+            }
+        }
+        SchemaState::String(string_type) => {
+            let generated = ValueFormat::of(string_type)
+                .and_then(|format| ctx.options.generators.for_format(format))
+                .and_then(|generator| generator.try_generate(schema, rng));
+            let semantic = ctx
+                .options
+                .realistic
+                .then(|| Semantic::of(&location.path, string_type))
+                .flatten();
+            match (generated, semantic) {
+                (Some(value), _) => value,
+                (None, Some(semantic)) => serde_json::Value::String(
+                    semantic.produce(ctx.options.locale.unwrap_or_default(), rng),
+                ),
+                (None, None) => serde_json::Value::String(match string_type {
+                    StringType::Unknown {
+                        min_length,
+                        max_length,
+                        ..
+                    } if ctx.options.lorem && ctx.options.string_charset.is_none() => {
+                        produce_lorem(unknown_string_length(*min_length, *max_length, rng), rng)
+                    }
+                    _ => produce_string(string_type, ctx.options.string_charset.as_ref(), rng),
+                }),
+            }
+        }
+        SchemaState::Number(NumberType::Sequence {
+            start, end, count, ..
+        }) => {
+            let step = NumberType::sequence_step(*start, *end, *count);
+            let position = ctx.next_in_sequence(&location.path);
+            serde_json::json!(start.saturating_add(position.saturating_mul(step)))
+        }
+        SchemaState::Number(NumberType::Float {
+            min, non_finite, ..
+        }) if !non_finite.is_empty() && (min.is_nan() || rng.gen_bool(NON_FINITE_PROBABILITY)) => {
+            let value = non_finite
+                .iter()
+                .nth(rng.gen_range(0..non_finite.len()))
+                .unwrap();
+            match ctx.options.non_finite.unwrap_or_default() {
+                NonFiniteFallback::Null => serde_json::Value::Null,
+                NonFiniteFallback::Zero => serde_json::json!(0),
+                NonFiniteFallback::Token => serde_json::Value::String(value.token().to_string()),
+            }
+        }
+        SchemaState::Number(number_type) => match ctx
+            .options
+            .number_stats
+            .as_ref()
+            .and_then(|stats| stats.get(&location.path))
+        {
+            Some(distribution) => produce_distributed_number(number_type, &distribution, rng),
+            None => produce_number(number_type, rng),
+        },
+        SchemaState::ExtendedJson(extended_type) => produce_extended_json(extended_type, rng),
+        SchemaState::Coordinates(positions) if is_geographic(positions) => {
+            // values are rounded to six decimal places, the precision of typical GPS readings
+            let lon = (rng.gen_range(-180.0..=180.0_f64) * 1e6).round() / 1e6;
+            let lat = (rng.gen_range(-90.0..=90.0_f64) * 1e6).round() / 1e6;
+            serde_json::json!([lon, lat])
+        }
+        SchemaState::GeoPoint {
+            latitude_key,
+            longitude_key,
+            ..
+        } => {
+            let lat = (rng.gen_range(-90.0..=90.0_f64) * 1e6).round() / 1e6;
+            let lon = (rng.gen_range(-180.0..=180.0_f64) * 1e6).round() / 1e6;
+            let mut point = serde_json::Map::new();
+            point.insert(latitude_key.clone(), serde_json::json!(lat));
+            point.insert(longitude_key.clone(), serde_json::json!(lon));
+            serde_json::Value::Object(point)
+        }
+        SchemaState::Money {
+            amount_key,
+            currency_key,
+            amount,
+            currencies,
+        } => {
+            let currency = produce_currency(currencies, rng);
+            let mut money = serde_json::Map::new();
+            money.insert(amount_key.clone(), produce_amount(amount, &currency, rng));
+            money.insert(currency_key.clone(), serde_json::Value::String(currency));
+            serde_json::Value::Object(money)
+        }
+        SchemaState::Coordinates(positions) => serde_json::Value::Array(
+            positions
+                .iter()
+                .map(|position| produce_number(position, rng))
+                .collect(),
+        ),
+        SchemaState::Tuple(positions) => serde_json::Value::Array(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(index, position)| {
+                    produce_inner(position, ctx, &location.item(index), current_depth + 1)
+                })
+                .collect(),
+        ),
+        SchemaState::Boolean => serde_json::Value::Bool(rng.gen()),
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            unique_items,
+            schema,
+        } => {
+            if schema.as_ref() == &SchemaState::Indefinite
+                || schema.as_ref() == &SchemaState::Initial
+            {
+                return serde_json::Value::Array(vec![]);
+            }
+
+            let n_elements = if ctx.too_deep(schema, location) {
+                0
+            } else if current_depth == 0 && ctx.repeat_n > 1 {
+                // if we are dealing with an array at the root and user requested multiple repetitions,
+                // we produce the requested `n` elements
+                ctx.repeat_n
+            } else {
+                array_length(*min_length, *max_length, lengths.as_ref(), rng)
+            };
+
+            let produce_item =
+                |index| produce_inner(schema, ctx, &location.item(index), current_depth + 1);
+            // elements are left out, from the first one that would exceed the maximum size on
+            let within_budget = |index| (!ctx.over_budget()).then(|| produce_item(index));
+            let mut data: Vec<_> = if ctx.in_order(schema) {
+                (0..n_elements).map_while(within_budget).collect()
+            } else {
+                let items: Vec<_> = (0..n_elements).into_par_iter().map(within_budget).collect();
+                items.into_iter().map_while(|item| item).collect()
+            };
+            if *unique_items {
+                // duplicates are replaced by more elements, for as long as they keep turning up
+                // new values, which they may stop doing if the elements only have a few values
+                let mut seen = HashSet::new();
+                data.retain(|value| seen.insert(value.to_string()));
+                let mut index = n_elements;
+                while data.len() < n_elements
+                    && index < n_elements * MAX_UNIQUE_ATTEMPTS
+                    && !ctx.over_budget()
+                {
+                    let value = produce_item(index);
+                    if seen.insert(value.to_string()) {
+                        data.push(value);
+                    }
+                    index += 1;
+                }
+            }
+            serde_json::Value::Array(data)
+        }
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            ..
+        } => {
+            let omitted = |k: &String| {
+                ctx.options.omit_read_only && access.get(k) == Some(&FieldAccess::ReadOnly)
+            };
+            // the fields that others refer to come first, so that their values can be picked
+            let (referenced, others): (Vec<_>, Vec<_>) = required
+                .iter()
+                .map(|(k, v)| (k, v, true))
+                .chain(optional.iter().map(|(k, v)| (k, v, false)))
+                .filter(|(k, _, _)| !omitted(k))
+                .partition(|(k, _, _)| ctx.leads_to_referenced(&location.field(k).path));
+            let mut produced = serde_json::Map::new();
+            for (k, v, is_required) in referenced.into_iter().chain(others) {
+                let field = location.field(k);
+                if !is_required {
+                    if ctx.too_deep(v, &field) {
+                        continue;
+                    }
+                    // each field decides on its own whether it is present, so that adding a
+                    // field doesn't change which of the others are
+                    let mut rng = ctx.rng(&field, "presence");
+                    let should_include = match ctx.include_probability(&field) {
+                        Some(probability) => rng.gen_bool(probability.clamp(0.0, 1.0)),
+                        None => rng.gen(),
+                    } && !ctx.over_budget();
+                    if !should_include {
+                        continue;
+                    }
+                }
+                let value = produce_inner(v, ctx, &field, current_depth + 1);
+                produced.insert(k.clone(), value);
+            }
+            // the fields are written in the order of the schema, not the order of production
+            let map = required
+                .keys()
+                .chain(optional.keys())
+                .filter_map(|k| produced.remove(k).map(|value| (k.clone(), value)))
+                .collect();
+            let mut map = apply_derived(ctx, location, map);
+            if ctx.options.sort_keys {
+                map.sort_keys();
+            }
+            serde_json::Value::Object(map)
+        }
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => {
+            let n_entries = if ctx.too_deep(schema, location) {
+                0
+            } else if min_length != max_length {
+                rng.gen_range(*min_length..=*max_length)
+            } else {
+                *min_length
+            };
+            // keys that happen to be produced more than once are only included once
+            let keys: BTreeSet<String> = (0..n_entries)
+                .map(|_| produce_string(keys, ctx.options.string_charset.as_ref(), rng))
+                .collect();
+            let map = keys
+                .into_iter()
+                .map_while(|key| {
+                    (!ctx.over_budget()).then(|| {
+                        let value =
+                            produce_inner(schema, ctx, &location.entry(&key), current_depth + 1);
+                        (key, value)
+                    })
+                })
+                .collect();
+            serde_json::Value::Object(map)
+        }
+        SchemaState::Union(variants) if variants.is_empty() => serde_json::Value::Null,
+        SchemaState::Union(variants) => {
+            let variants = shallow_enough(variants, |variant| variant, ctx, location);
+            let idx = rng.gen_range(0..variants.len());
+            produce_schema(
+                variants[idx],
+                ctx,
+                location,
+                current_depth + 1,
+                null_overridden,
+                rng,
+            )
+        }
+        SchemaState::Mixed(variants) if variants.is_empty() => serde_json::Value::Null,
+        SchemaState::Mixed(variants) if variants.iter().any(|(v, _)| ctx.too_deep(v, location)) => {
+            let variants: Vec<_> = shallow_enough(variants, |(variant, _)| variant, ctx, location)
+                .into_iter()
+                .cloned()
+                .collect();
+            produce_schema(
+                pick_weighted(&variants, rng),
+                ctx,
+                location,
+                current_depth + 1,
+                null_overridden,
+                rng,
+            )
+        }
+        SchemaState::Mixed(variants) => produce_schema(
+            pick_weighted(variants, rng),
+            ctx,
+            location,
+            current_depth + 1,
+            null_overridden,
+            rng,
+        ),
+        SchemaState::TaggedUnion { variants, .. } if variants.is_empty() => serde_json::Value::Null,
+        SchemaState::TaggedUnion { tag, variants } => {
+            let variants: Vec<_> = variants.iter().collect();
+            let variants = shallow_enough(&variants, |(_, variant)| variant, ctx, location);
+            let (value, variant) = variants[rng.gen_range(0..variants.len())];
+            let object = produce_schema(
+                variant,
+                ctx,
+                location,
+                current_depth + 1,
+                null_overridden,
+                rng,
+            );
+            with_tag(object, tag, value)
+        }
+        SchemaState::Recursive { name, schema } => produce_schema(
+            schema,
+            ctx,
+            &location.within(name, schema),
+            current_depth,
+            null_overridden,
+            rng,
+        ),
+        SchemaState::Reference(name) => {
+            match location.recursion.iter().rev().find(|(n, _)| n == name) {
+                Some(&(name, definition)) if !ctx.too_deep(schema, location) => produce_schema(
+                    definition,
+                    ctx,
+                    &location.within(name, definition),
+                    current_depth,
+                    null_overridden,
+                    rng,
+                ),
+                // only a definition that always contains itself gets here, which can't be
+                // produced anyway
+                _ => serde_json::Value::Null,
+            }
+        }
+        SchemaState::Indefinite => serde_json::Value::Null,
+    }
+}
+
+/// The variants of a union that can be produced at `location` without going too deep into a
+/// recursive definition, or all of them if none can.
+fn shallow_enough<'v, T>(
+    variants: &'v [T],
+    schema: impl Fn(&'v T) -> &'v SchemaState,
+    ctx: &Context,
+    location: &Location,
+) -> Vec<&'v T> {
+    let shallow: Vec<_> = variants
+        .iter()
+        .filter(|variant| !ctx.too_deep(schema(variant), location))
+        .collect();
+    match shallow.is_empty() {
+        true => variants.iter().collect(),
+        false => shallow,
+    }
+}
+
+/// Sets the tag field of an object produced for a variant of a tagged union to the variant's
+/// value, which the schema of the field may also have had other values for.
+fn with_tag(mut object: serde_json::Value, tag: &str, value: &str) -> serde_json::Value {
+    if let Some(fields) = object.as_object_mut() {
+        fields.insert(tag.to_string(), serde_json::json!(value));
+    }
+    object
+}
+
+fn invalid_schema(path: &str, reason: impl Into<String>) -> Result<(), ProduceError> {
+    Err(ProduceError::InvalidSchema {
+        path: path.to_string(),
+        reason: reason.into(),
+    })
+}
+
+fn check_range<T: PartialOrd>(path: &str, min: T, max: T, what: &str) -> Result<(), ProduceError> {
+    // written so that NaN bounds fail the check too
+    if min <= max {
+        Ok(())
+    } else {
+        invalid_schema(
+            path,
+            format!("the minimum {} is greater than the maximum", what),
+        )
+    }
+}
+
+fn check_number(number_type: &NumberType, path: &str) -> Result<(), ProduceError> {
+    match number_type {
+        NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. } => {
+            check_range(path, min, max, "value")
+        }
+        // only non-finite values were seen, which are produced as their fallback
+        NumberType::Float {
+            min, non_finite, ..
+        } if min.is_nan() && !non_finite.is_empty() => Ok(()),
+        NumberType::Float { min, max, .. } => check_range(path, min, max, "value"),
+        NumberType::Enum { variants } if variants.is_empty() => {
+            invalid_schema(path, "the enum has no variants")
+        }
+        NumberType::Enum { .. } => Ok(()),
+        NumberType::Flags { values } if values.is_empty() => {
+            invalid_schema(path, "the flags have no values")
+        }
+        NumberType::Flags { .. } => Ok(()),
+        NumberType::Multiple { step, .. } if !(step.is_finite() && *step > 0.0) => {
+            invalid_schema(path, "the step of the multiples isn't a positive number")
+        }
+        NumberType::Multiple { .. } => {
+            let (lowest, highest) = number_type.multiples();
+            if lowest > highest {
+                invalid_schema(path, "no multiple of the step is within the range")
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+fn check_string(string_type: &StringType, path: &str) -> Result<(), ProduceError> {
+    match string_type {
+        StringType::Unknown {
+            min_length: Some(min),
+            max_length: Some(max),
+            ..
+        } => check_range(path, min, max, "length"),
+        StringType::Charset {
+            min_length,
+            max_length,
+            ..
+        } => check_range(path, min_length, max_length, "length"),
+        StringType::Enum { variants } | StringType::OpenEnum { variants, .. }
+            if variants.is_empty() =>
+        {
+            invalid_schema(path, "the enum has no variants")
+        }
+        StringType::OpenEnum { other_ratio, .. } if !(0.0..=1.0).contains(other_ratio) => {
+            invalid_schema(path, "the ratio of other values is not between 0 and 1")
+        }
+        StringType::OpenEnum { other, .. } => check_string(other, path),
+        StringType::Numeric { number } => check_number(number, path),
+        StringType::CustomDateTime { formats } => match formats.first() {
+            None => invalid_schema(path, "no date format is given"),
+            Some(format) if !is_valid_date_format(format) => {
+                invalid_schema(path, format!("'{}' is not a valid date format", format))
+            }
+            Some(_) => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Checks that values can be produced for every part of a schema, so that producing from a
+/// hand-written or edited schema fails with an error rather than a panic.
+fn check_schema(schema: &SchemaState, path: &str) -> Result<(), ProduceError> {
+    match schema {
+        SchemaState::String(string_type) => check_string(string_type, path),
+        SchemaState::Number(number_type) => check_number(number_type, path),
+        SchemaState::Literal(values) if values.is_empty() => {
+            invalid_schema(path, "the literal has no values")
+        }
+        SchemaState::Coordinates(positions) => positions
+            .iter()
+            .try_for_each(|position| check_number(position, path)),
+        SchemaState::Tuple(positions) => positions
+            .iter()
+            .try_for_each(|position| check_schema(position, &items_path(path))),
+        SchemaState::GeoPoint {
+            latitude,
+            longitude,
+            ..
+        } => check_number(latitude, path).and_then(|_| check_number(longitude, path)),
+        SchemaState::Money { amount, .. } => check_number(amount, path),
+        SchemaState::Nullable(inner) => check_schema(inner, path),
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            schema,
+            ..
+        } => {
+            check_range(path, min_length, max_length, "length")?;
+            if lengths
+                .as_ref()
+                .is_some_and(|lengths| lengths.values().sum::<usize>() == 0 && !lengths.is_empty())
+            {
+                return invalid_schema(path, "the distribution of lengths has no observations");
+            }
+            check_schema(schema, &items_path(path))
+        }
+        SchemaState::Object {
+            required, optional, ..
+        } => required
+            .iter()
+            .chain(optional)
+            .try_for_each(|(name, field)| check_schema(field, &field_path(path, name))),
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => {
+            check_range(path, min_length, max_length, "number of entries")?;
+            check_string(keys, path)?;
+            check_schema(schema, &field_path(path, "*"))
+        }
+        SchemaState::Union(variants) => variants
+            .iter()
+            .try_for_each(|variant| check_schema(variant, path)),
+        SchemaState::Mixed(variants) => variants
+            .iter()
+            .try_for_each(|(variant, _)| check_schema(variant, path)),
+        SchemaState::TaggedUnion { variants, .. } => variants
+            .values()
+            .try_for_each(|variant| check_schema(variant, path)),
+        SchemaState::Recursive { name, schema } if !schema.can_avoid(&|n| n == name) => {
+            invalid_schema(
+                path,
+                format!("the recursive definition {} always contains itself", name),
+            )
+        }
+        SchemaState::Recursive { schema, .. } => check_schema(schema, path),
+        SchemaState::Initial
+        | SchemaState::Null
+        | SchemaState::Boolean
+        | SchemaState::Literal(_)
+        | SchemaState::ExtendedJson(_)
+        | SchemaState::Reference(_)
+        | SchemaState::Indefinite => Ok(()),
+    }
+}
+
+/// Produces a JSON value based on the given schema.
+///
+/// This function generates a JSON value based on the provided schema state.
+///
+/// # Arguments
+///
+/// * `schema` - The schema state to produce JSON values for.
+/// * `repeat_n` - The number of times to repeat generation (used for arrays at the JSON root).
+///
+/// # Panics
+///
+/// Panics if the schema can't have any values, such as an enum without variants; use
+/// [`try_produce_with`] to get an error instead.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{SchemaState, NumberType, produce};
+///
+/// // The inferred schema consists of an array with length = 1
+/// let schema = SchemaState::Array {
+///     min_length: 1,
+///     max_length: 1,
+///     lengths: None,
+///     unique_items: false,
+///     schema: Box::new(SchemaState::Number(NumberType::Integer { min: 0, max: 100 })),
+/// };
+///
+/// // Generate three values based on the schema
+/// let json_data = produce(&schema, 3);
+///
+/// // Do something with the generated JSON data
+/// println!("{}", json_data);
 /// // Output: [23, 58, 12]
 /// ```
 pub fn produce(schema: &SchemaState, repeat_n: usize) -> serde_json::Value {
-    produce_inner(schema, repeat_n, 0)
+    produce_with(schema, repeat_n, &ProduceOptions::default())
+}
+
+/// Produces a JSON value based on the given schema, like [`produce`], customised by the given
+/// options.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{produce_with, FieldOverride, ProduceOptions, SchemaState, StringType};
+/// use serde_json::json;
+/// use indexmap::IndexMap;
+///
+/// let schema = SchemaState::Object {
+///     required: IndexMap::from([("country".to_string(), SchemaState::String(StringType::UUID))]),
+///     optional: IndexMap::new(),
+///     access: Default::default(),
+///     annotations: Default::default(),
+/// };
+/// let mut options = ProduceOptions::default();
+/// options.fields.insert(
+///     "country".to_string(),
+///     FieldOverride { value: Some(json!("NL")), ..Default::default() },
+/// );
+///
+/// assert_eq!(produce_with(&schema, 1, &options), json!({"country": "NL"}));
+/// ```
+///
+/// If the values of a field in [`ProduceOptions::unique_fields`] can't all be different, some
+/// are repeated, and if the schema can't have any values, such as an enum without variants,
+/// this panics; use [`try_produce_with`] to get an error instead.
+pub fn produce_with(
+    schema: &SchemaState,
+    repeat_n: usize,
+    options: &ProduceOptions,
+) -> serde_json::Value {
+    let ctx = Context::new(repeat_n, options);
+    let value = with_jobs(options, || produce_inner(schema, &ctx, &ctx.root(), 0));
+    ctx.warn_if_truncated();
+    ctx.warn_if_repeated();
+    ctx.warn_if_commands_failed();
+    value
+}
+
+/// Produces a JSON value based on the given schema, like [`produce_with`], but fails if the
+/// values of a field in [`ProduceOptions::unique_fields`] can't all be different, or if the
+/// schema can't have any values.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{try_produce_with, ProduceOptions, SchemaState};
+///
+/// let schema = SchemaState::Array {
+///     min_length: 3,
+///     max_length: 3,
+///     lengths: None,
+///     unique_items: false,
+///     schema: Box::new(SchemaState::Boolean),
+/// };
+/// let options = ProduceOptions {
+///     unique_fields: vec!["[]".to_string()],
+///     ..Default::default()
+/// };
+///
+/// // there are only two distinct booleans
+/// assert!(try_produce_with(&schema, 1, &options).is_err());
+/// ```
+pub fn try_produce_with(
+    schema: &SchemaState,
+    repeat_n: usize,
+    options: &ProduceOptions,
+) -> Result<serde_json::Value, ProduceError> {
+    check_schema(schema, "")?;
+    let ctx = Context::new(repeat_n, options);
+    let value = with_jobs(options, || produce_inner(schema, &ctx, &ctx.root(), 0));
+    ctx.into_result(value)
+}
+
+/// Produces `n` separate values based on the given schema, with the paths of the options
+/// relative to each of them. Fields in [`ProduceOptions::unique_fields`] are unique across all
+/// of the values. Fails like [`try_produce_with`]. Fewer than `n` values are produced if they
+/// reach [`ProduceOptions::max_output_bytes`].
+pub fn try_produce_records(
+    schema: &SchemaState,
+    n: usize,
+    options: &ProduceOptions,
+) -> Result<Vec<serde_json::Value>, ProduceError> {
+    check_schema(schema, "")?;
+    let ctx = Context::new(1, options);
+    let produce_record =
+        |index| (!ctx.over_budget()).then(|| produce_inner(schema, &ctx, &ctx.record(index), 0));
+    let values = with_jobs(options, || {
+        if ctx.in_order(schema) {
+            (0..n).map_while(produce_record).collect()
+        } else {
+            let records: Vec<_> = (0..n).into_par_iter().map(produce_record).collect();
+            records.into_iter().map_while(|record| record).collect()
+        }
+    });
+    ctx.into_result(values)
+}
+
+/// Values produced a chunk at a time, for [`try_produce_each`] and [`produce_streaming_with`]:
+/// the elements of an array at the root of the schema, or `n` separate values otherwise.
+struct Stream<'a> {
+    ctx: Context<'a>,
+    pool: Option<rayon::ThreadPool>,
+    /// The schema of each value.
+    schema: &'a SchemaState,
+    /// The location of the array at the root whose elements are produced, if there is one.
+    array: Option<Location<'a>>,
+    n: usize,
+    /// The serialized values produced so far, if they must be unique as the elements of an
+    /// array with unique items.
+    seen: Option<HashSet<String>>,
+    /// The values that have been produced but not handed over yet.
+    pending: std::collections::VecDeque<serde_json::Value>,
+    /// The number of values that have been kept, as opposed to being left out as duplicates.
+    kept: usize,
+    /// The index of the next value to produce.
+    index: usize,
+    /// Whether production stopped early, for exceeding [`ProduceOptions::max_output_bytes`].
+    stopped: bool,
+}
+
+impl<'a> Stream<'a> {
+    fn new(
+        schema: &'a SchemaState,
+        n: usize,
+        options: &'a ProduceOptions,
+    ) -> Result<Self, ProduceError> {
+        check_schema(schema, "")?;
+        let ctx = Context::new(1, options);
+        let (schema, n, seen, array) = match schema {
+            SchemaState::Array {
+                min_length,
+                max_length,
+                lengths,
+                unique_items,
+                schema,
+            } => {
+                let root = ctx.root();
+                let n = if matches!(
+                    schema.as_ref(),
+                    SchemaState::Indefinite | SchemaState::Initial
+                ) {
+                    0
+                } else if n > 1 {
+                    n
+                } else {
+                    let mut rng = ctx.rng(&root, "value");
+                    array_length(*min_length, *max_length, lengths.as_ref(), &mut rng)
+                };
+                (
+                    schema.as_ref(),
+                    n,
+                    unique_items.then(HashSet::new),
+                    Some(root),
+                )
+            }
+            schema => (schema, n, None, None),
+        };
+        Ok(Stream {
+            ctx,
+            pool: thread_pool(options),
+            schema,
+            array,
+            n,
+            seen,
+            pending: Default::default(),
+            kept: 0,
+            index: 0,
+            stopped: false,
+        })
+    }
+
+    /// The location of the `index`th value: an element of the array at the root, the root itself
+    /// for a single value, or the root of one of several separate values.
+    fn location(&self, index: usize) -> Location<'a> {
+        match &self.array {
+            Some(array) => array.item(index),
+            None if self.n == 1 => self.ctx.root(),
+            None => self.ctx.record(index),
+        }
+    }
+
+    /// Produces the next chunk of values, or returns `false` if there are no more.
+    fn produce_chunk(&mut self) -> bool {
+        let (index, n) = (self.index, self.n);
+        // as for the elements of an array, duplicates are replaced by more values, for as long as
+        // they keep turning up new ones
+        let attempts = match self.seen {
+            Some(_) => n.saturating_mul(MAX_UNIQUE_ATTEMPTS),
+            None => n,
+        };
+        if self.stopped
+            || self.kept >= n
+            || index >= attempts
+            || self.ctx.error.lock().unwrap().is_some()
+        {
+            return false;
+        }
+        // replacements for duplicates are produced one at a time, since few may be needed
+        let end = if index < n {
+            n.min(index + STREAM_CHUNK_SIZE)
+        } else {
+            index + 1
+        };
+        let depth = usize::from(self.array.is_some());
+        let produce = |index| {
+            (!self.ctx.over_budget())
+                .then(|| produce_inner(self.schema, &self.ctx, &self.location(index), depth))
+        };
+        let values: Vec<_> = with_pool(self.pool.as_ref(), || {
+            if self.ctx.in_order(self.schema) || index >= n {
+                (index..end).map_while(produce).collect()
+            } else {
+                let values: Vec<_> = (index..end).into_par_iter().map(produce).collect();
+                values.into_iter().map_while(|value| value).collect()
+            }
+        });
+        self.stopped = values.len() < end - index;
+        self.index = end;
+        let kept = self.kept;
+        for value in values {
+            let new = match &mut self.seen {
+                Some(seen) => seen.insert(value.to_string()),
+                None => true,
+            };
+            if new {
+                self.pending.push_back(value);
+                self.kept += 1;
+            }
+        }
+        // an unbounded stream of unique values ends once a whole chunk turns up no new ones
+        if self.n == usize::MAX && self.seen.is_some() && self.kept == kept {
+            self.n = self.kept;
+        }
+        true
+    }
+
+    fn finish(self) -> Result<(), ProduceError> {
+        self.ctx.into_result(())
+    }
+}
+
+impl Iterator for Stream<'_> {
+    type Item = serde_json::Value;
+
+    fn next(&mut self) -> Option<serde_json::Value> {
+        loop {
+            if let Some(value) = self.pending.pop_front() {
+                return Some(value);
+            }
+            if !self.produce_chunk() {
+                return None;
+            }
+        }
+    }
+}
+
+/// Produces values like [`try_produce_records`], but hands each of them to `emit` as soon as it
+/// is produced rather than collecting them, so that any number of them can be written out, such
+/// as one JSON line each, without holding them all in memory. With an array at the root of the
+/// schema, its elements are produced instead: `n` of them if `n` is more than 1, or as many as
+/// the array may have otherwise, the same as with [`try_produce_with`] and the same seed.
+///
+/// Values are produced in parallel a chunk at a time, and handed over in order. Fails like
+/// [`try_produce_with`], after handing over the values produced before the error.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{try_produce_each, ProduceOptions, SchemaState};
+///
+/// let mut lines = vec![];
+/// try_produce_each(&SchemaState::Boolean, 3, &ProduceOptions::default(), |value| {
+///     lines.push(value.to_string())
+/// })
+/// .unwrap();
+/// assert_eq!(lines.len(), 3);
+/// ```
+pub fn try_produce_each(
+    schema: &SchemaState,
+    n: usize,
+    options: &ProduceOptions,
+    emit: impl FnMut(serde_json::Value),
+) -> Result<(), ProduceError> {
+    let mut stream = Stream::new(schema, n, options)?;
+    stream.by_ref().for_each(emit);
+    stream.finish()
+}
+
+/// Produces values like [`try_produce_each`], handing each of them to `emit` for as long as it
+/// returns `true`, rather than a given number of them, such as to produce data of a given size.
+/// With an array at the root of the schema, its elements are produced, until they run out if
+/// they must be unique.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{try_produce_while, ProduceOptions, SchemaState};
+///
+/// let mut bytes = 0;
+/// try_produce_while(&SchemaState::Null, &ProduceOptions::default(), |value| {
+///     bytes += value.to_string().len();
+///     bytes < 10
+/// })
+/// .unwrap();
+/// assert_eq!(bytes, 12);
+/// ```
+pub fn try_produce_while(
+    schema: &SchemaState,
+    options: &ProduceOptions,
+    mut emit: impl FnMut(serde_json::Value) -> bool,
+) -> Result<(), ProduceError> {
+    let mut stream = Stream::new(schema, usize::MAX, options)?;
+    for value in stream.by_ref() {
+        if !emit(value) {
+            break;
+        }
+    }
+    stream.finish()
+}
+
+/// Produces data like [`produce`], writing it to `writer` as compact JSON while it is produced,
+/// rather than producing all of it first, so that the memory used doesn't grow with `n`. Fails
+/// if the data can't be written, or like [`try_produce_with`].
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{produce_streaming, SchemaState};
+///
+/// let mut out = vec![];
+/// produce_streaming(&SchemaState::Null, 3, &mut out).unwrap();
+/// assert_eq!(out, b"[null,null,null]");
+/// ```
+pub fn produce_streaming(
+    schema: &SchemaState,
+    n: usize,
+    writer: impl std::io::Write,
+) -> Result<(), DrivelError> {
+    let mut serializer = serde_json::Serializer::new(writer);
+    produce_streaming_with(schema, n, &ProduceOptions::default(), &mut serializer)
+}
+
+/// Produces data like [`try_produce_with`], customised by the given options, serializing it with
+/// `serializer` while it is produced, such as with a
+/// [`PrettyFormatter`](serde_json::ser::PrettyFormatter) or a
+/// [`FixedPointFormatter`](crate::FixedPointFormatter). With an array at the root of the schema,
+/// its elements are produced and written a chunk at a time, and for another schema, `n` values
+/// are written as an array if `n` is more than 1, as with [`try_produce_records`], so that only
+/// a chunk of the data is held in memory at once. With the same seed, the data is the same as
+/// that returned by [`try_produce_with`] or [`try_produce_records`].
+///
+/// Fails if the data can't be written, or like [`try_produce_with`], after writing the data
+/// produced before the error, which is then left incomplete.
+pub fn produce_streaming_with<W: std::io::Write, F: serde_json::ser::Formatter>(
+    schema: &SchemaState,
+    n: usize,
+    options: &ProduceOptions,
+    serializer: &mut serde_json::Serializer<W, F>,
+) -> Result<(), DrivelError> {
+    let mut stream = Stream::new(schema, n, options)?;
+    // serializing a JSON value can only fail to write it
+    let written = if stream.array.is_some() || n > 1 {
+        serializer.collect_seq(&mut stream)
+    } else {
+        stream.next().unwrap_or_default().serialize(serializer)
+    };
+    written.map_err(std::io::Error::from)?;
+    Ok(stream.finish()?)
+}
+
+/// The smallest valid string of the given type: the shortest string that can be produced, or a
+/// fixed, well-known value of the string's format.
+fn minimal_string(string_type: &StringType) -> String {
+    match string_type {
+        StringType::Unknown {
+            chars_seen,
+            min_length,
+            ..
+        } => {
+            let c = chars_seen.first().copied().unwrap_or('a');
+            c.to_string().repeat(min_length.unwrap_or(0))
+        }
+        StringType::IsoDate => "1970-01-01".to_string(),
+        StringType::DateTimeISO8601 => "1970-01-01T00:00:00Z".to_string(),
+        StringType::DateTimeRFC2822 => "Thu, 1 Jan 1970 00:00:00 +0000".to_string(),
+        StringType::UUID => uuid::Uuid::nil().to_string(),
+        StringType::Email => "user@example.com".to_string(),
+        StringType::Hostname => "example.com".to_string(),
+        StringType::Ipv4 => "0.0.0.0".to_string(),
+        StringType::Ipv6 => "::".to_string(),
+        StringType::Url => "https://example.com/".to_string(),
+        StringType::FixedWidthNumeric { width } => "0".repeat(*width),
+        StringType::Charset {
+            class, min_length, ..
+        } => class.chars()[0].to_string().repeat(*min_length),
+        StringType::Pattern {
+            pattern,
+            min_length,
+            ..
+        } => minimal_pattern(pattern, *min_length)
+            .unwrap_or_else(|| "a".repeat(min_length.unwrap_or(0))),
+        StringType::CustomDateTime { formats } => minimal_date_format(&formats[0]),
+        StringType::CreditCard => "4111111111111111".to_string(),
+        StringType::Iban => "NL91ABNA0417164300".to_string(),
+        StringType::Isbn10 => "0306406152".to_string(),
+        StringType::Isbn13 => "9780306406157".to_string(),
+        StringType::Ean13 => "4006381333931".to_string(),
+        StringType::Duration => "PT1H".to_string(),
+        StringType::Numeric { number } => minimal_number(number).to_string(),
+        StringType::Enum { variants } | StringType::OpenEnum { variants, .. } => {
+            variants.iter().next().cloned().unwrap_or_default()
+        }
+    }
+}
+
+/// The smallest valid number of the given type: its minimum, or zero if it is unbounded below.
+fn minimal_number(number_type: &NumberType) -> serde_json::Value {
+    match number_type {
+        NumberType::Integer { min, max } if *min == i64::MIN => serde_json::json!(0.min(*max)),
+        NumberType::Integer { min, .. } => serde_json::json!(min),
+        NumberType::Float { min, max, .. } if !min.is_finite() => {
+            serde_json::json!(0.0_f64.min(*max))
+        }
+        NumberType::Float { min, .. } => serde_json::json!(min),
+        NumberType::Enum { variants } => serde_json::json!(variants.keys().next()),
+        NumberType::Sequence { start, .. } => serde_json::json!(start),
+        NumberType::Flags { values } => serde_json::json!(values.keys().next()),
+        NumberType::Multiple { min, max, .. } => {
+            let (lowest, highest) = number_type.multiples();
+            let steps = match (min.is_finite(), max.is_finite()) {
+                (false, true) => highest.min(0),
+                (false, false) => 0,
+                _ => lowest,
+            };
+            number_type.multiple(steps)
+        }
+    }
+}
+
+/// Produces the smallest valid instance of the given schema, rather than random data.
+///
+/// Only required fields are included, nullable values are `null`, arrays and maps have their
+/// minimum length, strings have their minimum length (or a fixed value of their format) and
+/// numbers are at their minimum. Since the keys of a map must be distinct, they are the only
+/// values that are still produced at random.
+///
+/// Panics if the schema can't have any values, such as an enum without variants; use
+/// [`try_produce_minimal`] to get an error instead.
+///
+/// # Examples
+///
+/// ```
+/// use drivel::{produce_minimal, NumberType, SchemaState};
+/// use serde_json::json;
+/// use indexmap::IndexMap;
+///
+/// let schema = SchemaState::Object {
+///     required: IndexMap::from([(
+///         "age".to_string(),
+///         SchemaState::Number(NumberType::Integer { min: 18, max: 65 }),
+///     )]),
+///     optional: IndexMap::from([("active".to_string(), SchemaState::Boolean)]),
+///     access: Default::default(),
+///     annotations: Default::default(),
+/// };
+///
+/// assert_eq!(produce_minimal(&schema), json!({"age": 18}));
+/// ```
+pub fn produce_minimal(schema: &SchemaState) -> serde_json::Value {
+    try_produce_minimal(schema).unwrap()
+}
+
+/// Produces the smallest valid instance of the given schema, like [`produce_minimal`], but fails
+/// instead of panicking if the schema can't have any values.
+pub fn try_produce_minimal(schema: &SchemaState) -> Result<serde_json::Value, ProduceError> {
+    check_schema(schema, "")?;
+    Ok(minimal_value(schema))
+}
+
+/// The smallest valid instance of a schema, which doesn't recurse into any definition where it
+/// can be avoided.
+fn minimal_value(schema: &SchemaState) -> serde_json::Value {
+    let shallowest = |variant: &&SchemaState| variant.can_avoid(&|_| true);
+    match schema {
+        SchemaState::Initial
+        | SchemaState::Null
+        | SchemaState::Nullable(_)
+        | SchemaState::Reference(_)
+        | SchemaState::Indefinite => serde_json::Value::Null,
+        SchemaState::Recursive { schema, .. } => minimal_value(schema),
+        SchemaState::String(string_type) => serde_json::Value::String(minimal_string(string_type)),
+        SchemaState::Number(number_type) => minimal_number(number_type),
+        SchemaState::Literal(values) => values.first().cloned().unwrap_or_default(),
+        SchemaState::ExtendedJson(ExtendedJsonType::ObjectId) => {
+            serde_json::json!({ "$oid": "0".repeat(24) })
+        }
+        SchemaState::ExtendedJson(ExtendedJsonType::Date) => {
+            serde_json::json!({ "$date": "1970-01-01T00:00:00.000Z" })
+        }
+        SchemaState::ExtendedJson(ExtendedJsonType::Long { min, .. }) => {
+            serde_json::json!({ "$numberLong": min.to_string() })
+        }
+        SchemaState::Coordinates(positions) => {
+            serde_json::Value::Array(positions.iter().map(minimal_number).collect())
+        }
+        SchemaState::Tuple(positions) => {
+            serde_json::Value::Array(positions.iter().map(minimal_value).collect())
+        }
+        SchemaState::GeoPoint {
+            latitude_key,
+            longitude_key,
+            latitude,
+            longitude,
+        } => serde_json::json!({
+            latitude_key.as_str(): minimal_number(latitude),
+            longitude_key.as_str(): minimal_number(longitude),
+        }),
+        SchemaState::Money {
+            amount_key,
+            currency_key,
+            amount,
+            currencies,
+        } => serde_json::json!({
+            amount_key.as_str(): minimal_number(amount),
+            currency_key.as_str(): currencies.first().map_or("USD", String::as_str),
+        }),
+        SchemaState::Boolean => serde_json::Value::Bool(false),
+        SchemaState::Array {
+            min_length, schema, ..
+        } => match schema.as_ref() {
+            SchemaState::Initial | SchemaState::Indefinite => serde_json::Value::Array(vec![]),
+            schema => serde_json::Value::Array(vec![minimal_value(schema); *min_length]),
+        },
+        SchemaState::Object { required, .. } => serde_json::Value::Object(
+            required
+                .iter()
+                .map(|(k, v)| (k.clone(), minimal_value(v)))
+                .collect(),
+        ),
+        SchemaState::Map {
+            min_length,
+            keys,
+            schema,
+            ..
+        } => {
+            let value = minimal_value(schema);
+            let mut map = serde_json::Map::new();
+            // keys can't be repeated, so keep producing them until there are enough
+            for _ in 0..(*min_length * 100) {
+                if map.len() >= *min_length {
+                    break;
+                }
+                map.insert(produce_string(keys, None, &mut thread_rng()), value.clone());
+            }
+            serde_json::Value::Object(map)
+        }
+        SchemaState::Union(variants) => variants
+            .iter()
+            .find(shallowest)
+            .or(variants.first())
+            .map_or(serde_json::Value::Null, minimal_value),
+        SchemaState::Mixed(variants) => variants
+            .iter()
+            .map(|(variant, _)| variant)
+            .find(shallowest)
+            .or(variants.first().map(|(variant, _)| variant))
+            .map_or(serde_json::Value::Null, minimal_value),
+        SchemaState::TaggedUnion { tag, variants } => variants
+            .iter()
+            .find(|(_, variant)| shallowest(variant))
+            .or(variants.iter().next())
+            .map_or(serde_json::Value::Null, |(value, variant)| {
+                with_tag(minimal_value(variant), tag, value)
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        infer_schema, infer_string::infer_string_type, CharacterClass, InferenceOptions,
+        ValueTransform,
+    };
+    use indexmap::IndexMap;
+    use rand::{rngs::StdRng, SeedableRng};
+    use serde_json::json;
+
+    fn colours() -> BTreeSet<String> {
+        ["red", "green", "blue", "yellow", "purple"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn picks(seed: u64, variants: &BTreeSet<String>) -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..8).map(|_| pick_variant(variants, &mut rng)).collect()
+    }
+
+    #[test]
+    fn enum_picks_are_stable_for_a_seed() {
+        assert_eq!(picks(42, &colours()), picks(42, &colours()));
+    }
+
+    fn seeded(seed: u64) -> ProduceOptions {
+        ProduceOptions {
+            seed: Some(seed),
+            ..Default::default()
+        }
+    }
+
+    fn customers(extra_fields: &[(&str, SchemaState)]) -> SchemaState {
+        let address = SchemaState::Object {
+            required: indexmap::IndexMap::from([(
+                "city".to_string(),
+                SchemaState::String(StringType::Hostname),
+            )]),
+            optional: indexmap::IndexMap::from([(
+                "zip".to_string(),
+                SchemaState::String(StringType::FixedWidthNumeric { width: 5 }),
+            )]),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let mut required = indexmap::IndexMap::from([
+            ("id".to_string(), SchemaState::String(StringType::UUID)),
+            (
+                "age".to_string(),
+                SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Integer {
+                    min: 18,
+                    max: 99,
+                }))),
+            ),
+            (
+                "addresses".to_string(),
+                SchemaState::Array {
+                    min_length: 0,
+                    max_length: 4,
+                    lengths: None,
+                    unique_items: false,
+                    schema: Box::new(address),
+                },
+            ),
+        ]);
+        required.extend(
+            extra_fields
+                .iter()
+                .map(|(name, schema)| (name.to_string(), schema.clone())),
+        );
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 1,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required,
+                optional: indexmap::IndexMap::from([(
+                    "email".to_string(),
+                    SchemaState::String(StringType::Email),
+                )]),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        }
+    }
+
+    #[test]
+    fn seeded_production_is_reproducible() {
+        let schema = customers(&[]);
+        let produced = produce_with(&schema, 20, &seeded(42));
+        assert_eq!(produced, produce_with(&schema, 20, &seeded(42)));
+        assert_ne!(produced, produce_with(&schema, 20, &seeded(43)));
+    }
+
+    #[test]
+    fn seeded_production_is_the_same_in_parallel() {
+        let options = |jobs| ProduceOptions {
+            jobs: Some(jobs),
+            ..seeded(42)
+        };
+        let schema = customers(&[]);
+        let sequential = try_produce_records(&schema, 200, &options(1)).unwrap();
+        assert_eq!(
+            try_produce_records(&schema, 200, &options(4)).unwrap(),
+            sequential
+        );
+        assert_eq!(
+            produce_with(&schema, 200, &options(4)),
+            produce_with(&schema, 200, &options(1))
+        );
+    }
+
+    #[test]
+    fn streamed_values_are_those_produced_at_once() {
+        let each = |schema: &SchemaState, n, options: &ProduceOptions| {
+            let mut values = vec![];
+            try_produce_each(schema, n, options, |value| values.push(value)).map(|_| values)
+        };
+        // more than one chunk of the elements of an array at the root
+        let schema = customers(&[]);
+        let options = ProduceOptions {
+            jobs: Some(4),
+            ..seeded(42)
+        };
+        assert_eq!(
+            serde_json::Value::Array(each(&schema, 2500, &options).unwrap()),
+            produce_with(&schema, 2500, &options)
+        );
+        assert_eq!(each(&schema, 1, &options).unwrap().len(), 1);
+
+        let schema = SchemaState::Number(NumberType::Integer { min: 1, max: 20 });
+        let records = each(&schema, 20, &unique("")).unwrap();
+        assert_eq!(distinct(records.into_iter()), 20);
+        // the values up to the error are still handed over
+        let schema = SchemaState::Boolean;
+        let mut values = 0;
+        let result = try_produce_each(&schema, 3, &unique(""), |_| values += 1);
+        assert!(result.is_err());
+        assert_eq!(values, 3);
+    }
+
+    #[test]
+    fn produces_values_while_they_are_wanted() {
+        let taken = |schema: &SchemaState, n, options: &ProduceOptions| {
+            let mut values = vec![];
+            try_produce_while(schema, options, |value| {
+                values.push(value);
+                values.len() < n
+            })
+            .map(|_| values)
+        };
+        let schema = customers(&[]);
+        let SchemaState::Array { schema: record, .. } = &schema else {
+            unreachable!()
+        };
+        assert_eq!(
+            taken(record, 1500, &seeded(42)).unwrap(),
+            try_produce_records(record, 1500, &seeded(42)).unwrap()
+        );
+        assert_eq!(
+            serde_json::Value::Array(taken(&schema, 1500, &seeded(42)).unwrap()),
+            produce_with(&schema, 1500, &seeded(42))
+        );
+
+        // unique elements end once they run out
+        let schema = SchemaState::Array {
+            min_length: 1,
+            max_length: 1,
+            lengths: None,
+            unique_items: true,
+            schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 20 })),
+        };
+        let values = taken(&schema, usize::MAX, &seeded(42)).unwrap();
+        assert_eq!(values.len(), 20);
+        assert_eq!(distinct(values.into_iter()), 20);
+        let schema = SchemaState::Number(NumberType::Integer { min: 1, max: 20 });
+        assert!(taken(&schema, usize::MAX, &unique("")).is_err());
+    }
+
+    #[test]
+    fn streamed_json_is_the_data_produced_at_once() {
+        let streamed = |schema: &SchemaState, n| {
+            let mut out = vec![];
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(b"  ");
+            let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+            produce_streaming_with(schema, n, &seeded(42), &mut serializer).unwrap();
+            serde_json::from_slice::<serde_json::Value>(&out).unwrap()
+        };
+        let schema = customers(&[]);
+        assert_eq!(streamed(&schema, 1), produce_with(&schema, 1, &seeded(42)));
+        assert_eq!(
+            streamed(&schema, 1500),
+            produce_with(&schema, 1500, &seeded(42))
+        );
+        let SchemaState::Array { schema, .. } = schema else {
+            unreachable!()
+        };
+        assert_eq!(streamed(&schema, 1), produce_with(&schema, 1, &seeded(42)));
+        assert_eq!(
+            streamed(&schema, 3),
+            serde_json::Value::Array(try_produce_records(&schema, 3, &seeded(42)).unwrap())
+        );
+    }
+
+    #[test]
+    fn seeded_values_survive_new_fields() {
+        let produced = produce_with(&customers(&[]), 20, &seeded(42));
+        let schema = customers(&[
+            ("active", SchemaState::Boolean),
+            ("name", SchemaState::String(StringType::Email)),
+        ]);
+        let mut grown = produce_with(&schema, 20, &seeded(42));
+        for record in grown.as_array_mut().unwrap() {
+            let record = record.as_object_mut().unwrap();
+            record.remove("active");
+            record.remove("name");
+        }
+
+        assert_eq!(produced, grown);
+    }
+
+    #[test]
+    fn derived_seeds_are_stable() {
+        // the derivation is part of the seed's guarantees, so it must never change
+        assert_eq!(derive_seed(0, "", ""), 0xe603_f73a_248f_3d8e);
+        assert_eq!(
+            derive_seed(42, "/3/address/city", "value"),
+            derive_seed(42, "/3/address/city", "value")
+        );
+        assert_ne!(
+            derive_seed(42, "/3/address/city", "value"),
+            derive_seed(42, "/3/address/cit", "yvalue")
+        );
+        assert_eq!(pointer_to("/0", "a/b~c"), "/0/a~1b~0c");
+    }
+
+    #[test]
+    fn enum_picks_do_not_depend_on_insertion_order() {
+        let reversed = ["purple", "yellow", "blue", "green", "red"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(picks(7, &colours()), picks(7, &reversed));
+    }
+
+    #[test]
+    fn enum_picks_match_golden_fixture() {
+        assert_eq!(
+            picks(42, &colours()),
+            vec!["red", "purple", "blue", "purple", "yellow", "blue", "purple", "purple"]
+        );
+    }
+
+    #[test]
+    fn produces_fixed_width_numeric_strings() {
+        let schema = SchemaState::String(StringType::FixedWidthNumeric { width: 5 });
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let s = value.as_str().unwrap();
+            assert_eq!(s.len(), 5);
+            assert!(s.bytes().all(|b| b.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn produces_members_and_other_values_of_open_enums() {
+        let schema = SchemaState::String(StringType::OpenEnum {
+            variants: ["DE", "FR"].iter().map(|s| s.to_string()).collect(),
+            other_ratio: 0.5,
+            other: Box::new(StringType::Unknown {
+                strings_seen: vec![],
+                chars_seen: vec!['x'],
+                min_length: Some(5),
+                max_length: Some(5),
+            }),
+        });
+        let values: Vec<serde_json::Value> = (0..200).map(|_| produce(&schema, 1)).collect();
+        assert!(values.iter().any(|v| v == "DE" || v == "FR"));
+        assert!(values.iter().any(|v| v == "xxxxx"));
+        assert!(values
+            .iter()
+            .all(|v| v == "DE" || v == "FR" || v == "xxxxx"));
+    }
+
+    #[test]
+    fn produces_numeric_strings_in_range() {
+        let schema = SchemaState::String(StringType::Numeric {
+            number: NumberType::Integer { min: 10, max: 20 },
+        });
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let number: i64 = value.as_str().unwrap().parse().unwrap();
+            assert!((10..=20).contains(&number), "{}", number);
+        }
+        assert_eq!(produce_minimal(&schema), json!("10"));
+    }
+
+    #[test]
+    fn produces_distinct_elements_of_unique_arrays() {
+        let schema = SchemaState::Array {
+            min_length: 5,
+            max_length: 5,
+            lengths: None,
+            unique_items: true,
+            schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 6 })),
+        };
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let elements = value.as_array().unwrap();
+            assert_eq!(elements.len(), 5);
+            let distinct: std::collections::HashSet<_> =
+                elements.iter().map(|e| e.to_string()).collect();
+            assert_eq!(distinct.len(), 5, "{}", value);
+        }
+
+        // there are only two booleans to pick from
+        let schema = SchemaState::Array {
+            min_length: 3,
+            max_length: 3,
+            lengths: None,
+            unique_items: true,
+            schema: Box::new(SchemaState::Boolean),
+        };
+        let value = produce(&schema, 1);
+        assert_eq!(value.as_array().unwrap().len(), 2, "{}", value);
+    }
+
+    #[test]
+    fn produces_fallbacks_for_non_finite_values() {
+        let schema = SchemaState::Number(NumberType::Float {
+            min: f64::NAN,
+            max: f64::NAN,
+            non_finite: BTreeSet::from([crate::NonFiniteNumber::NaN]),
+        });
+        for (fallback, expected) in [
+            (None, json!(null)),
+            (Some(NonFiniteFallback::Null), json!(null)),
+            (Some(NonFiniteFallback::Zero), json!(0)),
+            (Some(NonFiniteFallback::Token), json!("NaN")),
+        ] {
+            let options = ProduceOptions {
+                non_finite: fallback,
+                ..Default::default()
+            };
+            assert_eq!(
+                produce_with(&schema, 1, &options),
+                expected,
+                "{:?}",
+                fallback
+            );
+        }
+
+        // floats that were also finite are only sometimes not
+        let schema = SchemaState::Number(NumberType::Float {
+            min: 1.0,
+            max: 2.0,
+            non_finite: BTreeSet::from([
+                crate::NonFiniteNumber::Infinity,
+                crate::NonFiniteNumber::NegativeInfinity,
+            ]),
+        });
+        let options = ProduceOptions {
+            non_finite: Some(NonFiniteFallback::Token),
+            ..Default::default()
+        };
+        let values: Vec<_> = (0..500)
+            .map(|_| produce_with(&schema, 1, &options))
+            .collect();
+        assert!(values.iter().any(|v| v == &json!("Infinity")));
+        assert!(values.iter().any(|v| v == &json!("-Infinity")));
+        assert!(values
+            .iter()
+            .filter(|v| v.is_number())
+            .all(|v| (1.0..=2.0).contains(&v.as_f64().unwrap())));
+        assert!(values.iter().filter(|v| v.is_number()).count() > 350);
+    }
+
+    fn tree() -> SchemaState {
+        crate::parse_json_schema(&json!({
+            "$ref": "#/$defs/node",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "children": {
+                            "type": "array",
+                            "items": {"$ref": "#/$defs/node"},
+                            "maxItems": 3
+                        }
+                    },
+                    "required": ["name", "children"]
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn nesting(value: &serde_json::Value) -> usize {
+        value["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|child| 1 + nesting(child))
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn nests_recursive_definitions_up_to_the_maximum_depth() {
+        let schema = tree();
+        for depth in [None, Some(0), Some(1), Some(5)] {
+            let nested: Vec<_> = (0..40)
+                .map(|seed| {
+                    let options = ProduceOptions {
+                        max_recursion_depth: depth,
+                        ..seeded(seed)
+                    };
+                    let value = produce_with(&schema, 1, &options);
+                    assert!(schema.validate(&value).is_empty());
+                    nesting(&value)
+                })
+                .collect();
+            let depth = depth.unwrap_or(DEFAULT_MAX_RECURSION_DEPTH);
+            assert_eq!(nested.iter().max(), Some(&depth), "{:?}", nested);
+        }
+
+        assert_eq!(
+            try_produce_minimal(&schema).unwrap(),
+            json!({"name": "", "children": []})
+        );
+    }
+
+    #[test]
+    fn produces_integer_enum_values() {
+        let schema = SchemaState::Number(NumberType::Enum {
+            variants: [(200, 3), (404, 1), (500, 1)].into_iter().collect(),
+        });
+        for _ in 0..100 {
+            let value = produce(&schema, 1).as_i64().unwrap();
+            assert!([200, 404, 500].contains(&value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn produces_values_strictly_inside_exclusive_bounds() {
+        let schema = crate::parse_json_schema(&serde_json::json!({
+            "type": "integer",
+            "exclusiveMinimum": 1,
+            "exclusiveMaximum": 3
+        }))
+        .unwrap();
+        for _ in 0..100 {
+            assert_eq!(produce(&schema, 1).as_i64(), Some(2));
+        }
+
+        for (exclusive_min, exclusive_max) in [(0.0, 1.0), (1.0, 1.0 + 4.0 * f64::EPSILON)] {
+            let schema = crate::parse_json_schema(&serde_json::json!({
+                "type": "number",
+                "exclusiveMinimum": exclusive_min,
+                "exclusiveMaximum": exclusive_max
+            }))
+            .unwrap();
+            for _ in 0..1000 {
+                let value = produce(&schema, 1).as_f64().unwrap();
+                assert!(
+                    exclusive_min < value && value < exclusive_max,
+                    "{} is not inside ({}, {})",
+                    value,
+                    exclusive_min,
+                    exclusive_max
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn produces_multiples_and_constants_of_a_json_schema() {
+        let schema = crate::parse_json_schema(&serde_json::json!({
+            "type": "object",
+            "properties": {
+                "price": {"type": "number", "exclusiveMinimum": 0, "maximum": 10, "multipleOf": 0.05},
+                "dozens": {"type": "integer", "minimum": 1, "maximum": 100, "multipleOf": 12},
+                "halves": {"type": "integer", "multipleOf": 0.5},
+                "version": {"const": 2.5},
+                "active": {"type": "boolean", "const": true},
+                "code": {"enum": ["none", 0, [1, 2], null]}
+            },
+            "required": ["price", "dozens", "halves", "version", "active", "code"]
+        }))
+        .unwrap();
+        for _ in 0..200 {
+            let value = produce(&schema, 1);
+            // the price has no more decimals than its step
+            let price = value["price"].as_f64().unwrap();
+            assert!(price > 0.0 && price <= 10.0, "{}", value);
+            assert_eq!((price * 100.0).round() % 5.0, 0.0, "{}", value);
+            assert!(value["price"].to_string().len() <= 4, "{}", value);
+            let dozens = value["dozens"].as_i64().unwrap();
+            assert!(dozens % 12 == 0 && (12..=96).contains(&dozens), "{}", value);
+            assert!(value["halves"].is_i64(), "{}", value);
+            assert_eq!(value["version"], json!(2.5));
+            assert_eq!(value["active"], json!(true));
+            assert!(
+                [json!("none"), json!(0), json!([1, 2]), json!(null)].contains(&value["code"]),
+                "{}",
+                value
+            );
+            assert!(schema.validate(&value).is_empty(), "{}", value);
+        }
+        assert_eq!(
+            minimal_value(&schema)["dozens"],
+            json!(12),
+            "the smallest multiple within the range"
+        );
+    }
+
+    #[test]
+    fn produces_values_inside_half_bounded_float_ranges() {
+        for (min, max) in [(f64::NEG_INFINITY, -1e12), (5e11, f64::INFINITY)] {
+            let schema = SchemaState::Number(NumberType::Float {
+                min,
+                max,
+                non_finite: Default::default(),
+            });
+            for _ in 0..100 {
+                let value = produce(&schema, 1).as_f64().unwrap();
+                assert!(min <= value && value <= max, "{}", value);
+            }
+        }
+    }
+
+    fn record_schema() -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 5,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: indexmap::IndexMap::from([
+                    (
+                        "id".to_string(),
+                        SchemaState::String(StringType::Unknown {
+                            strings_seen: vec![],
+                            chars_seen: vec!['a'],
+                            min_length: Some(3),
+                            max_length: Some(3),
+                        }),
+                    ),
+                    (
+                        "count".to_string(),
+                        SchemaState::Number(NumberType::Integer { min: 0, max: 1000 }),
+                    ),
+                    (
+                        "status".to_string(),
+                        SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+                    ),
+                    ("note".to_string(), SchemaState::Boolean),
+                ]),
+                optional: indexmap::IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        }
+    }
+
+    #[test]
+    fn field_overrides_win_over_inferred_schema() {
+        let options: ProduceOptions = serde_json::from_value(serde_json::json!({
+            "fields": {
+                "[].id": {"format": "uuid"},
+                "[].count": {"min": 10.5, "max": 12},
+                "[].status": {"pool": ["open", "closed"]},
+                "[].note": {"value": "fixed"}
+            }
+        }))
+        .unwrap();
+
+        let produced = produce_with(&record_schema(), 20, &options);
+        for record in produced.as_array().unwrap() {
+            let id = record["id"].as_str().unwrap();
+            assert_eq!(infer_string_type(id), StringType::UUID, "{}", id);
+            let count = record["count"].as_i64().unwrap();
+            assert!((11..=12).contains(&count), "{}", count);
+            let status = record["status"].as_str().unwrap();
+            assert!(["open", "closed"].contains(&status), "{}", status);
+            assert_eq!(record["note"], "fixed");
+        }
+    }
+
+    #[test]
+    fn produces_ip_addresses() {
+        for string_type in [StringType::Ipv4, StringType::Ipv6] {
+            let schema = SchemaState::String(string_type.clone());
+            for _ in 0..20 {
+                let value = produce_with(&schema, 1, &ProduceOptions::default());
+                let ip = value.as_str().unwrap();
+                assert_eq!(infer_string_type(ip), string_type, "{}", ip);
+            }
+            let minimal = produce_minimal(&schema);
+            assert_eq!(infer_string_type(minimal.as_str().unwrap()), string_type);
+        }
+    }
+
+    #[test]
+    fn produces_unformatted_strings_from_charset() {
+        let options = ProduceOptions {
+            string_charset: Some(StringCharset::Hex),
+            ..Default::default()
+        };
+        let produced = produce_with(&record_schema(), 20, &options);
+        for record in produced.as_array().unwrap() {
+            let id = record["id"].as_str().unwrap();
+            assert_eq!(id.len(), 3);
+            assert!(id.bytes().all(|b| b.is_ascii_hexdigit()), "{}", id);
+        }
+
+        let schema = SchemaState::String(StringType::UUID);
+        let value = produce_with(&schema, 1, &options);
+        assert_eq!(infer_string_type(value.as_str().unwrap()), StringType::UUID);
+    }
+
+    fn records_of(field: &str, schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 1,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: indexmap::IndexMap::from([(field.to_string(), schema)]),
+                optional: indexmap::IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        }
+    }
+
+    fn unique(path: &str) -> ProduceOptions {
+        ProduceOptions {
+            unique_fields: vec![path.to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn distinct(values: impl Iterator<Item = serde_json::Value>) -> usize {
+        values.map(|v| v.to_string()).collect::<HashSet<_>>().len()
+    }
+
+    #[test]
+    fn unique_fields_are_unique_across_records() {
+        let schema = records_of("email", SchemaState::String(StringType::Email));
+        let produced = try_produce_with(&schema, 200, &unique("[].email")).unwrap();
+        let records = produced.as_array().unwrap();
+        assert_eq!(distinct(records.iter().map(|r| r["email"].clone())), 200);
+
+        // sequential ids use up every value in the range
+        let schema = records_of(
+            "id",
+            SchemaState::Number(NumberType::Integer { min: 1, max: 50 }),
+        );
+        let produced = try_produce_with(&schema, 50, &unique("[].id")).unwrap();
+        let records = produced.as_array().unwrap();
+        assert_eq!(distinct(records.iter().map(|r| r["id"].clone())), 50);
+
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([(
+                "id".to_string(),
+                SchemaState::Number(NumberType::Integer { min: 1, max: 20 }),
+            )]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let records = try_produce_records(&schema, 20, &unique("id")).unwrap();
+        assert_eq!(distinct(records.into_iter().map(|r| r["id"].clone())), 20);
+    }
+
+    #[test]
+    fn fields_that_were_unique_in_the_input_are_unique() {
+        let mut stats = crate::FieldStats::default();
+        stats.add(&json!((1..=30)
+            .map(|id| json!({"id": id}))
+            .collect::<Vec<_>>()));
+        let options = ProduceOptions {
+            field_stats: Some(stats),
+            ..Default::default()
+        };
+        let schema = records_of(
+            "id",
+            SchemaState::Number(NumberType::Integer { min: 1, max: 30 }),
+        );
+        let produced = try_produce_with(&schema, 30, &options).unwrap();
+        let records = produced.as_array().unwrap();
+        assert_eq!(distinct(records.iter().map(|r| r["id"].clone())), 30);
+
+        // which, unlike unique_fields, are repeated if they must be
+        let (produced, diagnostics) =
+            crate::capture_diagnostics(|| try_produce_with(&schema, 40, &options));
+        assert_eq!(produced.unwrap().as_array().unwrap().len(), 40);
+        assert!(diagnostics[0]
+            .message
+            .contains("'[].id' were all different"));
+    }
+
+    #[test]
+    fn referencing_fields_are_values_of_the_fields_they_refer_to() {
+        let input = json!({
+            // orders come first in sorted order, but are produced after the users they refer to
+            "orders": [{"id": 1, "user_id": 500}],
+            "users": [{"id": 500, "name": "a"}, {"id": 900, "name": "b"}],
+        });
+        let schema = crate::infer_schema(input, &crate::InferenceOptions::default());
+        let mut options = seeded(7);
+        options.infer_references(&schema);
+
+        let produced = produce_with(&schema, 1, &options);
+        let ids: HashSet<_> = produced["users"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["id"].clone())
+            .collect();
+        let orders = produced["orders"].as_array().unwrap();
+        assert!(!orders.is_empty());
+        assert!(orders.iter().all(|order| ids.contains(&order["user_id"])));
+        assert_eq!(produce_with(&schema, 1, &options), produced);
+    }
+
+    #[test]
+    fn produces_sequences_in_order() {
+        let schema = records_of(
+            "id",
+            SchemaState::Number(NumberType::Sequence {
+                min: 100,
+                max: 190,
+                start: 100,
+                end: 190,
+                count: 10,
+                increases: 9,
+            }),
+        );
+        let produced = produce(&schema, 5);
+        let ids: Vec<_> = produced
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![100, 110, 120, 130, 140]);
+    }
+
+    #[test]
+    fn produces_combinations_of_flags() {
+        let schema = SchemaState::Number(NumberType::Flags {
+            values: BTreeMap::from([(1, 3), (4, 2), (16, 1), (5, 1)]),
+        });
+        let values: BTreeSet<i64> = (0..200)
+            .map(|_| produce(&schema, 1).as_i64().unwrap())
+            .collect();
+        assert!(values.iter().all(|value| value & !(1 | 4 | 16) == 0));
+        assert_eq!(values.len(), 8);
+        assert_eq!(produce_minimal(&schema), serde_json::json!(1));
+    }
+
+    #[test]
+    fn transforms_post_process_values_at_their_path() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                (
+                    "code".to_string(),
+                    SchemaState::String(StringType::FixedWidthNumeric { width: 4 }),
+                ),
+                (
+                    "note".to_string(),
+                    SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+                ),
+            ]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let mut options = ProduceOptions::default();
+        options.transforms.insert(
+            "code".to_string(),
+            ValueTransform::new(|value| json!(format!("C-{}", value.as_str().unwrap()))),
+        );
+        options.transforms.insert(
+            "note".to_string(),
+            ValueTransform::new(|value| json!(value.is_null())),
+        );
+        options.fields.insert(
+            "note".to_string(),
+            FieldOverride {
+                null_probability: Some(1.0),
+                ..Default::default()
+            },
+        );
+
+        let value = produce_with(&schema, 1, &options);
+        assert!(value["code"].as_str().unwrap().starts_with("C-"));
+        assert_eq!(value["note"], json!(true));
+    }
+
+    fn people(fields: &[(&str, SchemaState)]) -> SchemaState {
+        SchemaState::Array {
+            min_length: 50,
+            max_length: 50,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Object {
+                required: fields
+                    .iter()
+                    .map(|(name, schema)| (name.to_string(), schema.clone()))
+                    .collect(),
+                optional: indexmap::IndexMap::new(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        }
+    }
+
+    #[test]
+    fn derived_fields_are_computed_in_dependency_order() {
+        let name = SchemaState::String(StringType::Enum {
+            variants: ["Ada", "Grace", "Alan"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        });
+        let schema = people(&[
+            ("first_name", name.clone()),
+            ("last_name", name.clone()),
+            ("full_name", name.clone()),
+            ("greeting", name),
+        ]);
+        let mut options = ProduceOptions::default();
+        // the greeting is listed first, but needs the full name to be derived before it
+        options
+            .derived
+            .push(DerivedField::new("[].greeting", &["full_name"], |fields| {
+                json!(format!("Hello, {}!", fields["full_name"].as_str().unwrap()))
+            }));
+        options.derived.push(DerivedField::new(
+            "[].full_name",
+            &["first_name", "last_name"],
+            |fields| {
+                json!(format!(
+                    "{} {}",
+                    fields["first_name"].as_str().unwrap(),
+                    fields["last_name"].as_str().unwrap()
+                ))
+            },
+        ));
+
+        let value = try_produce_with(&schema, 1, &options).unwrap();
+        for person in value.as_array().unwrap() {
+            let full_name = format!(
+                "{} {}",
+                person["first_name"].as_str().unwrap(),
+                person["last_name"].as_str().unwrap()
+            );
+            assert_eq!(person["full_name"], json!(full_name));
+            assert_eq!(person["greeting"], json!(format!("Hello, {}!", full_name)));
+        }
+    }
+
+    #[test]
+    fn derived_fields_can_order_dates() {
+        let schema = people(&[
+            ("start_date", SchemaState::String(StringType::IsoDate)),
+            ("end_date", SchemaState::String(StringType::IsoDate)),
+        ]);
+        let date = |value: &serde_json::Value| {
+            NaiveDate::parse_from_str(value.as_str().unwrap(), "%Y-%m-%d").unwrap()
+        };
+        let mut options = ProduceOptions::default();
+        // the produced end date is only used for how long after the start it is
+        options.derived.push(DerivedField::new(
+            "[].end_date",
+            &["start_date"],
+            move |fields| {
+                let start = date(&fields["start_date"]);
+                let days = (date(&fields["end_date"]) - start).num_days().abs() % 365;
+                json!((start + chrono::Duration::days(days)).to_string())
+            },
+        ));
+
+        let value = try_produce_with(&schema, 1, &options).unwrap();
+        let records = value.as_array().unwrap();
+        assert!(records
+            .iter()
+            .all(|record| date(&record["end_date"]) >= date(&record["start_date"])));
+        assert!(records
+            .iter()
+            .any(|record| record["end_date"] != record["start_date"]));
+    }
+
+    #[test]
+    fn cyclic_derived_fields_are_an_error() {
+        let schema = people(&[("a", SchemaState::Boolean), ("b", SchemaState::Boolean)]);
+        let mut options = ProduceOptions::default();
+        options
+            .derived
+            .push(DerivedField::new("[].a", &["b"], |fields| {
+                fields["b"].clone()
+            }));
+        options
+            .derived
+            .push(DerivedField::new("[].b", &["a"], |fields| {
+                fields["a"].clone()
+            }));
+        assert_eq!(
+            try_produce_with(&schema, 1, &options),
+            Err(ProduceError::CyclicDerivedFields {
+                paths: vec!["[].a".to_string(), "[].b".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn generators_take_the_place_of_built_in_values() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                ("id".to_string(), SchemaState::String(StringType::UUID)),
+                ("email".to_string(), SchemaState::String(StringType::Email)),
+                (
+                    "backup".to_string(),
+                    SchemaState::Array {
+                        min_length: 2,
+                        max_length: 2,
+                        lengths: None,
+                        unique_items: false,
+                        schema: Box::new(SchemaState::String(StringType::Email)),
+                    },
+                ),
+                ("pinned".to_string(), SchemaState::String(StringType::Email)),
+                ("alias".to_string(), SchemaState::Boolean),
+            ]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let mut options = seeded(42);
+        options
+            .generators
+            .register_field("id", |_: &SchemaState, rng: &mut dyn rand::RngCore| {
+                json!(format!("ID-{}", rng.gen_range(0..10)))
+            });
+        options.generators.register_format(
+            ValueFormat::Email,
+            |_: &SchemaState, _: &mut dyn rand::RngCore| json!("someone@example.com"),
+        );
+        // an override wins over the field's generator
+        options
+            .generators
+            .register_field("pinned", |_: &SchemaState, _: &mut dyn rand::RngCore| {
+                json!("generated")
+            });
+        options.fields.insert(
+            "pinned".to_string(),
+            FieldOverride {
+                value: Some(json!("pinned@example.com")),
+                ..Default::default()
+            },
+        );
+        // which also produces the strings of an override's format
+        options.fields.insert(
+            "alias".to_string(),
+            FieldOverride {
+                format: Some(ValueFormat::Email),
+                ..Default::default()
+            },
+        );
+
+        let value = produce_with(&schema, 1, &options);
+        assert!(value["id"].as_str().unwrap().starts_with("ID-"));
+        assert_eq!(value["email"], json!("someone@example.com"));
+        assert_eq!(
+            value["backup"],
+            json!(["someone@example.com", "someone@example.com"])
+        );
+        assert_eq!(value["pinned"], json!("pinned@example.com"));
+        assert_eq!(value["alias"], json!("someone@example.com"));
+        assert_eq!(produce_with(&schema, 1, &options), value);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_generate_values_a_batch_at_a_time() {
+        let SchemaState::Array { schema, .. } =
+            records_of("sku", SchemaState::String(StringType::UUID))
+        else {
+            unreachable!()
+        };
+        let command = |script: &str| crate::GeneratorCommand {
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            batch_size: Some(2),
+        };
+        let mut options = ProduceOptions {
+            jobs: Some(1),
+            ..seeded(3)
+        };
+        options.commands.insert(
+            "sku".to_string(),
+            command(r#"i=0; while [ $i -lt $DRIVEL_COUNT ]; do echo "SKU-$i"; i=$((i+1)); done"#),
+        );
+        let (records, diagnostics) =
+            crate::capture_diagnostics(|| try_produce_records(&schema, 5, &options).unwrap());
+        let skus: Vec<_> = records.iter().map(|record| record["sku"].clone()).collect();
+        assert_eq!(
+            skus,
+            vec![
+                json!("SKU-0"),
+                json!("SKU-1"),
+                json!("SKU-0"),
+                json!("SKU-1"),
+                json!("SKU-0")
+            ]
+        );
+        assert!(diagnostics.is_empty());
+
+        // lines that are JSON are values of their own
+        options.commands.insert(
+            "sku".to_string(),
+            command(r#"echo '{"code": 7}'; echo null"#),
+        );
+        let records = try_produce_records(&schema, 2, &options).unwrap();
+        assert_eq!(records[0]["sku"], json!({"code": 7}));
+        assert_eq!(records[1]["sku"], json!(null));
+
+        // a command that fails is warned about once, and the values are produced as usual
+        options
+            .commands
+            .insert("sku".to_string(), command("exit 3"));
+        let (records, diagnostics) =
+            crate::capture_diagnostics(|| try_produce_records(&schema, 3, &options).unwrap());
+        assert!(records
+            .iter()
+            .all(|record| uuid::Uuid::parse_str(record["sku"].as_str().unwrap()).is_ok()));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'sku'"));
+    }
+
+    #[test]
+    fn uniqueness_applies_to_transformed_values() {
+        let schema = records_of("id", SchemaState::String(StringType::UUID));
+        let mut options = unique("[].id");
+        options.transforms.insert(
+            "[].id".to_string(),
+            ValueTransform::new(|value| json!(&value.as_str().unwrap()[..1])),
+        );
+
+        // there are only 16 distinct hex digits to start a UUID with
+        let err = try_produce_with(&schema, 20, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ProduceError::UniquenessUnsatisfiable { produced: 16, .. }
+        ));
+    }
+
+    #[test]
+    fn impossible_uniqueness_is_an_error() {
+        let schema = records_of(
+            "status",
+            SchemaState::String(StringType::Enum {
+                variants: ["open".to_string(), "closed".to_string()].into(),
+            }),
+        );
+
+        assert!(try_produce_with(&schema, 2, &unique("[].status")).is_ok());
+        assert_eq!(
+            try_produce_with(&schema, 3, &unique("[].status")),
+            Err(ProduceError::UniquenessUnsatisfiable {
+                path: "[].status".to_string(),
+                produced: 2
+            })
+        );
+        // without the error, values are repeated
+        let produced = produce_with(&schema, 3, &unique("[].status"));
+        assert_eq!(produced.as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn schemas_without_values_are_errors() {
+        let invalid = |schema: SchemaState| {
+            let options = ProduceOptions::default();
+            let err = try_produce_with(&schema, 1, &options).unwrap_err();
+            assert_eq!(try_produce_records(&schema, 2, &options), Err(err));
+            let err = try_produce_minimal(&schema).unwrap_err();
+            match err {
+                ProduceError::InvalidSchema { path, .. } => path,
+                other => panic!("Expected an invalid schema, got {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            invalid(records_of(
+                "status",
+                SchemaState::String(StringType::Enum {
+                    variants: BTreeSet::new()
+                })
+            )),
+            "[].status"
+        );
+        assert_eq!(
+            invalid(SchemaState::Number(NumberType::Integer { min: 5, max: 1 })),
+            ""
+        );
+        assert_eq!(
+            invalid(SchemaState::Number(NumberType::Float {
+                min: f64::NAN,
+                max: 1.0,
+                non_finite: Default::default(),
+            })),
+            ""
+        );
+        assert_eq!(
+            invalid(records_of(
+                "at",
+                SchemaState::Nullable(Box::new(SchemaState::String(StringType::CustomDateTime {
+                    formats: vec![]
+                })))
+            )),
+            "[].at"
+        );
+        assert_eq!(
+            invalid(SchemaState::Array {
+                min_length: 0,
+                max_length: 2,
+                lengths: Some(BTreeMap::from([(1, 0)])),
+                unique_items: false,
+                schema: Box::new(SchemaState::Boolean),
+            }),
+            ""
+        );
+        assert_eq!(
+            invalid(SchemaState::Map {
+                min_length: 3,
+                max_length: 1,
+                keys: StringType::UUID,
+                schema: Box::new(SchemaState::Boolean),
+            }),
+            ""
+        );
+    }
+
+    #[test]
+    fn strings_with_only_a_minimum_length_are_at_least_that_long() {
+        let schema = SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: Some(40),
+            max_length: None,
+        });
+        let produced = try_produce_with(&schema, 1, &ProduceOptions::default()).unwrap();
+        assert!(produced.as_str().unwrap().chars().count() >= 40);
+    }
+
+    #[test]
+    fn null_probability_overrides_nullability() {
+        let never_null: ProduceOptions = serde_json::from_value(serde_json::json!({
+            "fields": {"[].status": {"null_probability": 0.0}}
+        }))
+        .unwrap();
+        let produced = produce_with(&record_schema(), 50, &never_null);
+        assert!(produced
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|record| record["status"].is_boolean()));
+
+        let always_null: ProduceOptions = serde_json::from_value(serde_json::json!({
+            "fields": {"[].count": {"null_probability": 1.0}}
+        }))
+        .unwrap();
+        let produced = produce_with(&record_schema(), 50, &always_null);
+        assert!(produced
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|record| record["count"].is_null()));
+    }
+
+    #[test]
+    fn null_probability_applies_to_every_nullable_value() {
+        let nulls = |options: &ProduceOptions| {
+            let produced = produce_with(&record_schema(), 1000, options);
+            let records = produced.as_array().unwrap().clone();
+            records
+                .iter()
+                .filter(|record| record["status"].is_null())
+                .count()
+        };
+        let options: ProduceOptions = serde_json::from_value(serde_json::json!({
+            "null_probability": 0.1
+        }))
+        .unwrap();
+        assert!((50..=150).contains(&nulls(&options)));
+        let never_null = ProduceOptions {
+            null_probability: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(nulls(&never_null), 0);
+
+        // an override of the field wins
+        let mut always_null = never_null.clone();
+        always_null.fields.insert(
+            "[].status".to_string(),
+            FieldOverride {
+                null_probability: Some(1.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(nulls(&always_null), 1000);
+    }
+
+    #[test]
+    fn produces_the_shape_of_each_tag() {
+        // both variants allow either tag, which is set to the one that was picked
+        let variant = |field: &str| SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                (
+                    "type".to_string(),
+                    SchemaState::String(StringType::Enum {
+                        variants: BTreeSet::from(["click".to_string(), "key".to_string()]),
+                    }),
+                ),
+                (field.to_string(), SchemaState::Boolean),
+            ]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let schema = SchemaState::TaggedUnion {
+            tag: "type".to_string(),
+            variants: BTreeMap::from([
+                ("click".to_string(), variant("x")),
+                ("key".to_string(), variant("code")),
+            ]),
+        };
+        let mut tags = HashSet::new();
+        for _ in 0..50 {
+            let value = produce(&schema, 1);
+            let tag = value["type"].as_str().unwrap().to_string();
+            let field = if tag == "click" { "x" } else { "code" };
+            assert!(value[field].is_boolean(), "{}", value);
+            assert_eq!(value.as_object().unwrap().len(), 2, "{}", value);
+            tags.insert(tag);
+        }
+        assert_eq!(tags.len(), 2);
+        assert_eq!(produce_minimal(&schema)["type"], "click");
+    }
+
+    #[test]
+    fn produces_geo_points_within_their_ranges() {
+        let schema = SchemaState::GeoPoint {
+            latitude_key: "lat".to_string(),
+            longitude_key: "Lng".to_string(),
+            latitude: NumberType::Float {
+                min: 52.3,
+                max: 52.4,
+                non_finite: Default::default(),
+            },
+            longitude: NumberType::Integer { min: 4, max: 5 },
+        };
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let point = value.as_object().unwrap();
+            assert_eq!(point.len(), 2, "{}", value);
+            assert!(
+                (-90.0..=90.0).contains(&point["lat"].as_f64().unwrap()),
+                "{}",
+                value
+            );
+            assert!(
+                (-180.0..=180.0).contains(&point["Lng"].as_f64().unwrap()),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn produces_money_in_the_currencies_seen() {
+        let schema = SchemaState::Money {
+            amount_key: "price".to_string(),
+            currency_key: "currency".to_string(),
+            amount: NumberType::Float {
+                min: 5.0,
+                max: 80.0,
+                non_finite: Default::default(),
+            },
+            currencies: ["EUR", "JPY"].into_iter().map(String::from).collect(),
+        };
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            assert_eq!(value.as_object().unwrap().len(), 2, "{}", value);
+            let price = value["price"].as_f64().unwrap();
+            assert!((5.0..=80.0).contains(&price), "{}", value);
+            match value["currency"].as_str().unwrap() {
+                "EUR" => assert!((price * 100.0 - (price * 100.0).round()).abs() < 1e-6),
+                "JPY" => assert_eq!(price.fract(), 0.0, "{}", value),
+                other => panic!("unexpected currency {}", other),
+            }
+        }
+        assert_eq!(
+            produce_minimal(&schema),
+            serde_json::json!({"price": 5.0, "currency": "EUR"})
+        );
+    }
+
+    #[test]
+    fn produces_strings_of_their_character_class() {
+        for class in [
+            CharacterClass::Uppercase,
+            CharacterClass::Lowercase,
+            CharacterClass::Hex,
+            CharacterClass::Digits,
+        ] {
+            let schema = SchemaState::String(StringType::Charset {
+                class,
+                min_length: 2,
+                max_length: 4,
+            });
+            for _ in 0..50 {
+                let value = produce(&schema, 1);
+                let value = value.as_str().unwrap();
+                assert!((2..=4).contains(&value.len()), "{}", value);
+                assert!(value.chars().all(|c| class.contains(c)), "{}", value);
+            }
+            assert_eq!(produce_minimal(&schema).as_str().unwrap().len(), 2);
+        }
+    }
+
+    #[test]
+    fn produces_lorem_text_for_free_text_strings() {
+        let unknown = |seen: &[&str]| {
+            SchemaState::String(StringType::Unknown {
+                strings_seen: seen.iter().map(|s| s.to_string()).collect(),
+                chars_seen: seen.iter().flat_map(|s| s.chars()).collect(),
+                min_length: seen.iter().map(|s| s.chars().count()).min(),
+                max_length: seen.iter().map(|s| s.chars().count()).max(),
+            })
+        };
+        let is_lorem = |value: &str| {
+            value.contains(' ')
+                && value.chars().next().unwrap().is_ascii_uppercase()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || " .".contains(c))
+        };
+
+        let description = unknown(&[
+            "A sturdy chair that fits any room, with a padded seat.",
+            "Handmade from oak and finished by hand, it will last for years!",
+        ]);
+        for _ in 0..20 {
+            let value = produce(&description, 1);
+            let value = value.as_str().unwrap();
+            assert!((54..=64).contains(&value.len()), "{}", value);
+            assert!(is_lorem(value), "{}", value);
+        }
+
+        // short strings and long strings without spaces keep their characters
+        let code = unknown(&["ab cd", "ef gh"]);
+        let token = unknown(&["dGhpcyBpcyBhIGxvbmcgYmFzZTY0IGVuY29kZWQgdG9rZW4gdmFsdWU="]);
+        for schema in [&code, &token] {
+            let value = produce(schema, 1);
+            assert!(!is_lorem(value.as_str().unwrap()), "{}", value);
+        }
+
+        let options = ProduceOptions {
+            lorem: true,
+            ..Default::default()
+        };
+        let value = produce_with(&unknown(&["Tiny words and more of them"]), 1, &options);
+        assert!(is_lorem(value.as_str().unwrap()), "{}", value);
+    }
+
+    #[test]
+    fn produces_strings_matching_patterns_of_a_json_schema() {
+        let schema = crate::parse_json_schema(&serde_json::json!({
+            "type": "object",
+            "properties": {
+                "sku": {"type": "string", "pattern": "^[A-Z]{3}-[0-9]{4}$"},
+                "slug": {"type": "string", "pattern": "^[a-z]+(-[a-z]+)*$", "maxLength": 12},
+                "phone": {"type": "string", "pattern": "^\\+?[0-9]{2} ?\\([0-9]{3}\\) [0-9]+$"}
+            },
+            "required": ["sku", "slug", "phone"]
+        }))
+        .unwrap();
+        let sku = regex::Regex::new("^[A-Z]{3}-[0-9]{4}$").unwrap();
+        let slug = regex::Regex::new("^[a-z]+(-[a-z]+)*$").unwrap();
+        let phone = regex::Regex::new("^\\+?[0-9]{2} ?\\([0-9]{3}\\) [0-9]+$").unwrap();
+        for _ in 0..50 {
+            let value = produce(&schema, 1);
+            assert!(sku.is_match(value["sku"].as_str().unwrap()), "{}", value);
+            let produced_slug = value["slug"].as_str().unwrap();
+            assert!(
+                slug.is_match(produced_slug) && produced_slug.len() <= 12,
+                "{}",
+                value
+            );
+            assert!(
+                phone.is_match(value["phone"].as_str().unwrap()),
+                "{}",
+                value
+            );
+        }
+        let minimal = produce_minimal(&schema);
+        assert!(
+            sku.is_match(minimal["sku"].as_str().unwrap()),
+            "{}",
+            minimal
+        );
+    }
+
+    #[test]
+    fn produces_numeric_text_for_numbers_read_as_strings() {
+        let options = crate::ReadOptions {
+            numbers_as_strings: true,
+            ..Default::default()
+        };
+        let input = "[{\"id\": 123456789012345678901, \"price\": -12.50}, {\"id\": 223456789012345678902, \"price\": 3.75}]";
+        let values = crate::parse_values(input, &options).unwrap().into_values();
+        let schema = crate::infer_schema(
+            values[0].clone(),
+            &crate::InferenceOptions {
+                enum_inference: None,
+                format_inference: None,
+                max_object_fields: None,
+                union_objects: false,
+                merge_threshold: None,
+                discriminator: None,
+                length_distribution: false,
+                extended_json: false,
+                coordinates: false,
+                tuples: false,
+                financial_identifiers: false,
+                barcodes: false,
+                money: false,
+                durations: false,
+                numeric_strings: false,
+                non_finite: false,
+                date_formats: vec![],
+                sequences: false,
+                bitflags: false,
+                vocabularies: vec![],
+                max_depth: None,
+                max_object_keys: None,
+                maps: false,
+                union_types: false,
+            },
+        );
+        for record in produce(&schema, 20).as_array().unwrap() {
+            let id = record["id"].as_str().unwrap();
+            assert!(
+                id.len() == 21 && id.bytes().all(|b| b.is_ascii_digit()),
+                "{}",
+                id
+            );
+            let price = record["price"].as_str().unwrap();
+            assert!(price.parse::<f64>().is_ok(), "{}", price);
+            assert!(
+                !price.starts_with("00") && !price.starts_with("-00"),
+                "{}",
+                price
+            );
+        }
+    }
+
+    #[test]
+    fn huge_arrays_are_cut_short_at_the_maximum_size() {
+        let huge_array = |schema| SchemaState::Array {
+            min_length: 1_000_000,
+            max_length: 1_000_000,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        };
+        let schema = huge_array(huge_array(SchemaState::Object {
+            required: IndexMap::from([(
+                "id".to_string(),
+                SchemaState::Number(NumberType::Integer { min: 0, max: 1000 }),
+            )]),
+            optional: IndexMap::from([("name".to_string(), SchemaState::String(StringType::UUID))]),
+            access: Default::default(),
+            annotations: Default::default(),
+        }));
+        let options = ProduceOptions {
+            max_output_bytes: Some(10_000),
+            ..Default::default()
+        };
+        let value = try_produce_with(&schema, 1, &options).unwrap();
+        let size = value.to_string().len();
+        assert!((10_000..20_000).contains(&size), "{}", size);
+        assert!(value[0][0]["id"].is_number());
+
+        let records = try_produce_records(&schema, 1000, &options).unwrap();
+        assert_eq!(records.len(), 1);
+        let records = try_produce_records(&SchemaState::Boolean, 100_000, &options).unwrap();
+        assert!(records.len() < 100_000);
+    }
+
+    #[test]
+    fn warnings_about_cut_short_data_are_diagnostics_rather_than_data() {
+        let options = ProduceOptions {
+            max_output_bytes: Some(100),
+            ..Default::default()
+        };
+        let (records, diagnostics) = crate::capture_diagnostics(|| {
+            try_produce_records(&SchemaState::Boolean, 1000, &options).unwrap()
+        });
+        let payload = serde_json::to_string(&records).unwrap();
+        assert!(payload
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .all(|value| value == "true" || value == "false"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::Severity::Warning);
+        assert!(diagnostics[0].message.contains("100 bytes"));
+
+        // nothing is left to warn about without a maximum
+        let (_, diagnostics) = crate::capture_diagnostics(|| {
+            try_produce_records(&SchemaState::Boolean, 1000, &Default::default()).unwrap()
+        });
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn ratios_produced_from_their_json_schema_stay_within_their_observed_range() {
+        let ratio = SchemaState::Number(NumberType::Float {
+            min: 0.25,
+            max: 0.75,
+            non_finite: Default::default(),
+        });
+        let options = crate::JsonSchemaOptions {
+            numeric_ranges: true,
+            ..Default::default()
+        };
+        let parsed =
+            crate::parse_json_schema(&crate::schema::to_json_schema_with(&ratio, &options))
+                .unwrap();
+        assert_eq!(parsed.to_string_pretty(), "float (0.25-0.75)");
+        for schema in [ratio, parsed] {
+            for _ in 0..100 {
+                let value = produce(&schema, 1).as_f64().unwrap();
+                assert!((0.25..=0.75).contains(&value), "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn produces_coordinates_within_their_ranges() {
+        let geographic = SchemaState::Coordinates(vec![
+            NumberType::Float {
+                min: 4.8,
+                max: 4.9,
+                non_finite: Default::default(),
+            },
+            NumberType::Float {
+                min: 52.3,
+                max: 52.4,
+                non_finite: Default::default(),
+            },
+        ]);
+        let points = SchemaState::Coordinates(vec![
+            NumberType::Integer { min: 200, max: 500 },
+            NumberType::Integer { min: 200, max: 500 },
+            NumberType::Integer { min: 0, max: 5 },
+        ]);
+        for _ in 0..100 {
+            let value = produce(&geographic, 1);
+            let [lon, lat] = value.as_array().unwrap().as_slice() else {
+                panic!("Expected a pair, got {}", value);
+            };
+            assert!(
+                (-180.0..=180.0).contains(&lon.as_f64().unwrap()),
+                "{}",
+                value
+            );
+            assert!((-90.0..=90.0).contains(&lat.as_f64().unwrap()), "{}", value);
+
+            let value = produce(&points, 1);
+            let [x, y, z] = value.as_array().unwrap().as_slice() else {
+                panic!("Expected a triple, got {}", value);
+            };
+            assert!((200..=500).contains(&x.as_i64().unwrap()), "{}", value);
+            assert!((200..=500).contains(&y.as_i64().unwrap()), "{}", value);
+            assert!((0..=5).contains(&z.as_i64().unwrap()), "{}", value);
+        }
+    }
+
+    #[test]
+    fn produces_each_position_of_tuples() {
+        let schema = SchemaState::Tuple(vec![
+            SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+            SchemaState::Boolean,
+        ]);
+        for _ in 0..20 {
+            let value = produce(&schema, 1);
+            let [count, flag] = value.as_array().unwrap().as_slice() else {
+                panic!("Expected a pair, got {}", value);
+            };
+            assert!((1..=9).contains(&count.as_i64().unwrap()), "{}", value);
+            assert!(flag.is_boolean(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn produces_minimal_instances() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                (
+                    "name".to_string(),
+                    SchemaState::String(StringType::Unknown {
+                        strings_seen: vec![],
+                        chars_seen: vec!['x', 'y'],
+                        min_length: Some(2),
+                        max_length: Some(8),
+                    }),
+                ),
+                (
+                    "tags".to_string(),
+                    SchemaState::Array {
+                        min_length: 1,
+                        max_length: 4,
+                        lengths: None,
+                        unique_items: false,
+                        schema: Box::new(SchemaState::String(StringType::UUID)),
+                    },
+                ),
+                (
+                    "score".to_string(),
+                    SchemaState::Number(NumberType::Float {
+                        min: f64::NEG_INFINITY,
+                        max: -2.5,
+                        non_finite: Default::default(),
+                    }),
+                ),
+                (
+                    "parent".to_string(),
+                    SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+                ),
+                ("active".to_string(), SchemaState::Boolean),
+            ]),
+            optional: indexmap::IndexMap::from([("notes".to_string(), SchemaState::Boolean)]),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+
+        assert_eq!(
+            produce_minimal(&schema),
+            serde_json::json!({
+                "name": "xx",
+                "tags": ["00000000-0000-0000-0000-000000000000"],
+                "score": -2.5,
+                "parent": null,
+                "active": false
+            })
+        );
+    }
+
+    #[test]
+    fn minimal_values_satisfy_their_formats() {
+        for string_type in [
+            StringType::UUID,
+            StringType::Email,
+            StringType::Url,
+            StringType::Hostname,
+            StringType::IsoDate,
+            StringType::DateTimeISO8601,
+            StringType::DateTimeRFC2822,
+        ] {
+            let value = minimal_string(&string_type);
+            assert_eq!(infer_string_type(&value), string_type, "{}", value);
+        }
+    }
+
+    #[test]
+    fn produces_maps_with_keys_of_the_key_type() {
+        let schema = SchemaState::Map {
+            min_length: 2,
+            max_length: 5,
+            keys: StringType::UUID,
+            schema: Box::new(SchemaState::Boolean),
+        };
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let map = value.as_object().unwrap();
+            assert!((2..=5).contains(&map.len()));
+            for (key, value) in map {
+                assert_eq!(infer_string_type(key), StringType::UUID);
+                assert!(value.is_boolean());
+            }
+        }
+    }
+
+    #[test]
+    fn produces_coherent_union_variants() {
+        let variant = |field: &str| SchemaState::Object {
+            required: indexmap::IndexMap::from([(field.to_string(), SchemaState::Boolean)]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let schema = SchemaState::Union(vec![variant("a"), variant("b")]);
+        for _ in 0..100 {
+            let value = produce(&schema, 1);
+            let keys: Vec<_> = value.as_object().unwrap().keys().collect();
+            assert!(keys == ["a"] || keys == ["b"], "{:?}", keys);
+        }
+    }
+
+    #[test]
+    fn produces_mixed_variants_as_often_as_they_were_seen() {
+        let schema = SchemaState::Array {
+            min_length: 0,
+            max_length: 0,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(SchemaState::Mixed(vec![
+                (SchemaState::Boolean, 3),
+                (
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+                    1,
+                ),
+            ])),
+        };
+
+        let value = produce(&schema, 4000);
+
+        let values = value.as_array().unwrap();
+        let booleans = values.iter().filter(|value| value.is_boolean()).count();
+        let integers = values.iter().filter(|value| value.is_i64()).count();
+        assert_eq!(booleans + integers, 4000);
+        assert!((2700..3300).contains(&booleans), "{booleans} booleans");
+    }
+
+    #[test]
+    fn optional_fields_are_absent_and_null_at_separate_rates() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::new(),
+            optional: indexmap::IndexMap::from([(
+                "nickname".to_string(),
+                SchemaState::Nullable(Box::new(SchemaState::Boolean)),
+            )]),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let rates = |options: &ProduceOptions| {
+            let records = try_produce_records(&schema, 4000, options).unwrap();
+            let present: Vec<_> = records.iter().filter_map(|r| r.get("nickname")).collect();
+            let null = present.iter().filter(|v| v.is_null()).count();
+            (
+                present.len() as f64 / records.len() as f64,
+                null as f64 / present.len() as f64,
+            )
+        };
+
+        let mut options = ProduceOptions {
+            optional_include_probability: Some(0.8),
+            ..Default::default()
+        };
+        options.fields.insert(
+            "nickname".to_string(),
+            FieldOverride {
+                null_probability: Some(0.25),
+                ..Default::default()
+            },
+        );
+        let (presence, nulls) = rates(&options);
+        assert!((presence - 0.8).abs() < 0.05, "{}", presence);
+        assert!((nulls - 0.25).abs() < 0.05, "{}", nulls);
+
+        // without a probability, fields are present as often as they were in the data
+        let mut stats = crate::FieldStats::default();
+        for i in 0..10 {
+            stats.add(&if i < 3 {
+                json!({"nickname": null})
+            } else {
+                json!({})
+            });
+        }
+        let options = ProduceOptions {
+            field_stats: Some(stats),
+            ..Default::default()
+        };
+        let (presence, nulls) = rates(&options);
+        assert!((presence - 0.3).abs() < 0.05, "{}", presence);
+        assert!((nulls - 0.5).abs() < 0.1, "{}", nulls);
+    }
+
+    #[test]
+    fn numbers_follow_the_tracked_distributions() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                (
+                    "score".to_string(),
+                    SchemaState::Number(NumberType::Integer { min: 0, max: 100 }),
+                ),
+                (
+                    "ratio".to_string(),
+                    SchemaState::Number(NumberType::Float {
+                        min: 0.0,
+                        max: 1000.0,
+                        non_finite: Default::default(),
+                    }),
+                ),
+            ]),
+            optional: indexmap::IndexMap::new(),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+        let mut stats = crate::NumberStats::default();
+        for score in [75, 80, 85, 90, 95, 100] {
+            stats.add(&json!({"score": score, "ratio": 3.5}));
+        }
+        let options = ProduceOptions {
+            number_stats: Some(stats),
+            ..Default::default()
+        };
+
+        let records = try_produce_records(&schema, 4000, &options).unwrap();
+        let scores: Vec<f64> = records
+            .iter()
+            .map(|r| r["score"].as_f64().unwrap())
+            .collect();
+        assert!(records.iter().all(|r| r["score"].is_i64()));
+        assert!(scores.iter().all(|score| (0.0..=100.0).contains(score)));
+        let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+        // the tail above the maximum is drawn again, which pulls the mean down a little
+        assert!((mean - 87.5).abs() < 2.5, "{}", mean);
+        assert!(scores.iter().filter(|score| **score < 60.0).count() < 40);
+        assert!(records.iter().all(|r| r["ratio"] == 3.5));
+    }
+
+    #[test]
+    fn omits_read_only_fields() {
+        let schema = SchemaState::Object {
+            required: indexmap::IndexMap::from([
+                (
+                    "id".to_string(),
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 10 }),
+                ),
+                (
+                    "name".to_string(),
+                    SchemaState::String(StringType::Unknown {
+                        strings_seen: vec!["a".to_string()],
+                        chars_seen: vec!['a'],
+                        min_length: Some(1),
+                        max_length: Some(1),
+                    }),
+                ),
+            ]),
+            optional: indexmap::IndexMap::new(),
+            access: BTreeMap::from([("id".to_string(), FieldAccess::ReadOnly)]),
+            annotations: Default::default(),
+        };
+
+        let value = produce_with(&schema, 1, &ProduceOptions::default());
+        assert!(value.get("id").is_some());
+
+        let options = ProduceOptions {
+            omit_read_only: true,
+            ..Default::default()
+        };
+        let value = produce_with(&schema, 1, &options);
+        assert_eq!(value, json!({"name": "a"}));
+    }
+
+    #[test]
+    fn samples_lengths_from_distribution() {
+        let lengths = BTreeMap::from([(0, 90), (50, 10)]);
+        let mut rng = StdRng::seed_from_u64(42);
+        let samples: Vec<_> = (0..1000)
+            .map(|_| sample_length(&lengths, &mut rng))
+            .collect();
+        assert!(samples.iter().all(|&l| l == 0 || l == 50));
+        let long = samples.iter().filter(|&&l| l == 50).count();
+        assert!((50..150).contains(&long), "{}", long);
+    }
+
+    /// For every format that inference can detect, values produced from the inferred schema must
+    /// be detected as that same format again.
+    #[test]
+    fn produced_values_satisfy_detected_formats() {
+        let cases = [
+            (
+                vec![
+                    "48f41410-2d97-4d54-8bfa-aa4e22acca01",
+                    "0e3a99a5-0201-4444-9ab1-8343fac56233",
+                ],
+                StringType::UUID,
+            ),
+            (
+                vec!["john@example.com", "jane@example.org"],
+                StringType::Email,
+            ),
+            (
+                vec!["https://example.com/foo", "https://drivel.dev/bar"],
+                StringType::Url,
+            ),
+            (vec!["example.com", "drivel.dev"], StringType::Hostname),
+            (vec!["2024-01-01", "1999-12-31"], StringType::IsoDate),
+            (
+                vec!["2024-01-01T12:00:00Z", "1999-12-31T23:59:59+01:00"],
+                StringType::DateTimeISO8601,
+            ),
+            (
+                vec![
+                    "Tue, 1 Jul 2003 10:52:37 +0200",
+                    "Wed, 2 Jul 2003 10:52:37 +0000",
+                ],
+                StringType::DateTimeRFC2822,
+            ),
+        ];
+
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        for (samples, expected) in cases {
+            let schema = infer_schema(serde_json::json!(samples), &options);
+            let item_schema = match &schema {
+                SchemaState::Array { schema, .. } => schema.as_ref(),
+                other => panic!("Expected an array schema, got {:?}", other),
+            };
+            let expected = SchemaState::String(expected);
+            assert_eq!(item_schema, &expected);
+
+            for _ in 0..500 {
+                let value = produce(item_schema, 1);
+                let s = value.as_str().unwrap();
+                assert_eq!(
+                    SchemaState::String(infer_string_type(s)),
+                    expected,
+                    "{:?} produced for {:?}",
+                    s,
+                    expected
+                );
+            }
+        }
+    }
 }
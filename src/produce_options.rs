@@ -0,0 +1,722 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::{FieldStats, GeneratorRegistry, Locale, NumberStats, SchemaState, StringType};
+
+/// Options that customise how data is produced by [`produce_with`](crate::produce_with).
+///
+/// These can be deserialized from a config file, such as:
+///
+/// ```json
+/// {
+///   "fields": {
+///     "id": { "format": "uuid" },
+///     "status": { "pool": ["active", "suspended"] },
+///     "orders[].total": { "min": 0, "max": 500 },
+///     "address.country": { "value": "NL" },
+///     "nickname": { "null_probability": 0.9 }
+///   },
+///   "unique_fields": ["id"]
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProduceOptions {
+    /// Overrides for the values produced at particular paths.
+    ///
+    /// A path is the dot-separated list of field names leading to a value from the root of the
+    /// document, where `[]` refers to the elements of an array and `*` to the values of a map.
+    /// For example, `orders[].total` is the `total` field of every element of the `orders`
+    /// array, and `[].id` is the `id` of every record in an array at the root.
+    pub fields: BTreeMap<String, FieldOverride>,
+    /// The characters to produce strings of no particular format from. By default, these are
+    /// the characters seen in the input data.
+    pub string_charset: Option<StringCharset>,
+    /// Whether to produce every string of no particular format as lorem ipsum text, as is done
+    /// by default only for strings that looked like free text, such as long descriptions. A
+    /// [`string_charset`](Self::string_charset) takes precedence.
+    pub lorem: bool,
+    /// Whether to produce realistic values, such as names, email addresses, phone numbers,
+    /// addresses and companies, for the strings of fields whose names say that they are one,
+    /// such as `first_name` or `address.city`, in place of strings of no particular format.
+    /// These win over [`lorem`](Self::lorem) and [`string_charset`](Self::string_charset).
+    pub realistic: bool,
+    /// The locale of the values of [`realistic`](Self::realistic). By default, this is `en_US`.
+    pub locale: Option<Locale>,
+    /// The paths of values that must all be different within the data produced in one go, such
+    /// as `[].id` for the `id` of every record in an array at the root.
+    pub unique_fields: Vec<String>,
+    /// Seeds the random number generators, so that the same options and schema always produce
+    /// the same data.
+    ///
+    /// The random values at every location are derived from the seed and the JSON pointer of
+    /// that location (such as `/3/address/city`), not drawn from one stream in the order the
+    /// values are produced. As a result, adding, removing or changing a field leaves the values
+    /// produced for the other fields as they were, and records are the same whether they are
+    /// produced one at a time or in parallel.
+    pub seed: Option<u64>,
+    /// Functions that post-process the values produced at particular paths, such as to
+    /// uppercase a code or prefix an id. These can only be set programmatically, not from a
+    /// config file.
+    ///
+    /// A transform receives every value produced at its path, including `null`s, after any
+    /// [`FieldOverride`] has been applied. Values are checked for uniqueness after they have
+    /// been transformed, so a transform that maps different values to the same one can make a
+    /// unique field unsatisfiable.
+    #[serde(skip)]
+    pub transforms: BTreeMap<String, ValueTransform>,
+    /// Custom generators for the values of particular fields or the strings of particular
+    /// formats, which take the place of drivel's own. These can only be set programmatically,
+    /// not from a config file; see [`GeneratorRegistry`] for which generator produces a value.
+    #[serde(skip)]
+    pub generators: GeneratorRegistry,
+    /// External commands that generate the values at particular paths, such as valid VAT
+    /// numbers or internal SKUs, a batch at a time; see [`GeneratorCommand`]. A generator of
+    /// [`generators`](Self::generators) for the same path wins over its command.
+    pub commands: BTreeMap<String, GeneratorCommand>,
+    /// Rules that compute the values of fields from those of their siblings, such as a
+    /// `full_name` from a `first_name` and a `last_name`, applied in order of their
+    /// dependencies. These can only be set programmatically, not from a config file; see
+    /// [`DerivedField`].
+    #[serde(skip)]
+    pub derived: Vec<DerivedField>,
+    /// Whether to leave out the fields that are marked as read-only, even if they are required,
+    /// such as when producing request bodies for an API.
+    pub omit_read_only: bool,
+    /// The number of threads to produce values on, with one thread producing them all in order.
+    /// By default, there is one per CPU. With a [`seed`](Self::seed), the produced data is the
+    /// same for any number of threads.
+    pub jobs: Option<usize>,
+    /// What to produce in place of the values of a float that weren't finite numbers, such as
+    /// `NaN`, since JSON can't represent them. By default, these are `null`.
+    pub non_finite: Option<NonFiniteFallback>,
+    /// The probability, between 0 and 1, that an optional field is present in an object. Whether
+    /// a present field is `null` is decided separately, by a `null_probability` override or, if
+    /// it is nullable, at random. By default, optional fields are present as often as they were
+    /// in the [`field_stats`](Self::field_stats), or half of the time without them.
+    pub optional_include_probability: Option<f64>,
+    /// The probability, between 0 and 1, that a nullable value is `null`, for datasets as sparse
+    /// as the real ones. A `null_probability` override of a field wins over it. By default,
+    /// nullable values are `null` half of the time.
+    pub null_probability: Option<f64>,
+    /// The statistics of the data that the schema was inferred from, if there was any, for
+    /// optional fields to be present as often as they were in it, and for the values of
+    /// [unique](FieldStats::is_unique) fields to be unique as well, like those of
+    /// [`unique_fields`](Self::unique_fields), except that some are repeated, with a warning,
+    /// if they can't all be different. These can only be set programmatically, not from a
+    /// config file.
+    #[serde(skip)]
+    pub field_stats: Option<FieldStats>,
+    /// The distributions of the numbers of the data that the schema was inferred from, if they
+    /// were tracked, for integers and floats to be produced around the same mean, with the same
+    /// standard deviation, rather than spread evenly over their range. These can only be set
+    /// programmatically, not from a config file.
+    #[serde(skip)]
+    pub number_stats: Option<NumberStats>,
+    /// The approximate size, in bytes of minified JSON, at which to stop producing more data,
+    /// such as for schemas with large array bounds. Once it is reached, arrays and maps get no
+    /// more elements and optional fields are left out, and a warning is written to stderr. The
+    /// data may still exceed it by about the size of the values being produced at the time. By
+    /// default, there is no limit.
+    pub max_output_bytes: Option<usize>,
+    /// How many levels of a recursive definition, such as the nodes of a tree, are nested
+    /// within it at most. At the deepest level, what would be of the definition again is left
+    /// out where the schema allows it: an optional field or nullable value is left out or
+    /// `null`, an array is empty, and a union picks another variant. By default, 3.
+    pub max_recursion_depth: Option<usize>,
+    /// Whether the fields of objects are produced in the order of their names, rather than the
+    /// order in which they were first seen in the input.
+    pub sort_keys: bool,
+}
+
+/// A function that post-processes produced values, for use in [`ProduceOptions::transforms`].
+///
+/// # Example
+///
+/// ```
+/// use drivel::{produce_with, ProduceOptions, SchemaState, StringType, ValueTransform};
+/// use serde_json::{json, Value};
+///
+/// let schema = SchemaState::String(StringType::Email);
+/// let mut options = ProduceOptions::default();
+/// options.transforms.insert(
+///     "".to_string(),
+///     ValueTransform::new(|value| match value {
+///         Value::String(s) => Value::String(s.to_uppercase()),
+///         other => other,
+///     }),
+/// );
+///
+/// let email = produce_with(&schema, 1, &options);
+/// assert_eq!(email.as_str().unwrap(), email.as_str().unwrap().to_uppercase());
+/// ```
+#[derive(Clone)]
+pub struct ValueTransform(Arc<dyn Fn(Value) -> Value + Send + Sync>);
+
+impl ValueTransform {
+    pub fn new(transform: impl Fn(Value) -> Value + Send + Sync + 'static) -> Self {
+        ValueTransform(Arc::new(transform))
+    }
+
+    pub(crate) fn apply(&self, value: Value) -> Value {
+        (self.0)(value)
+    }
+}
+
+impl fmt::Debug for ValueTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ValueTransform")
+    }
+}
+
+impl PartialEq for ValueTransform {
+    /// Transforms are only equal if they are the same function, since closures can't be compared.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A rule that computes the value of a field from the other fields of the same object, for use
+/// in [`ProduceOptions::derived`], such as to keep a `full_name` consistent with a `first_name`
+/// and a `last_name`, or an `end_date` after a `start_date`.
+///
+/// Once all the fields of an object have been produced, the rules for its fields are applied
+/// one after the other, each after the rules for the fields it depends on, so that one derived
+/// field can depend on another. A rule receives the fields of the object so far, including the
+/// value that was produced for its own field, which it can use as a source of randomness, and
+/// returns the field's value. It is only applied to objects that have its field, so an optional
+/// field stays optional. Derived values aren't checked for uniqueness.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{produce_with, DerivedField, ProduceOptions, SchemaState, StringType};
+/// use serde_json::{json, Map, Value};
+/// use indexmap::IndexMap;
+///
+/// let name = || SchemaState::String(StringType::Enum {
+///     variants: ["Ada", "Grace"].into_iter().map(String::from).collect(),
+/// });
+/// let schema = SchemaState::Object {
+///     required: IndexMap::from([
+///         ("first_name".to_string(), name()),
+///         ("last_name".to_string(), name()),
+///         ("full_name".to_string(), name()),
+///     ]),
+///     optional: IndexMap::new(),
+///     access: Default::default(),
+///     annotations: Default::default(),
+/// };
+/// let mut options = ProduceOptions::default();
+/// let full_name = |fields: &Map<String, Value>| {
+///     format!("{} {}", fields["first_name"].as_str().unwrap(), fields["last_name"].as_str().unwrap())
+/// };
+/// options.derived.push(DerivedField::new(
+///     "full_name",
+///     &["first_name", "last_name"],
+///     move |fields| json!(full_name(fields)),
+/// ));
+///
+/// let person = produce_with(&schema, 1, &options);
+/// assert_eq!(person["full_name"], json!(full_name(person.as_object().unwrap())));
+/// ```
+#[derive(Clone)]
+pub struct DerivedField {
+    /// The path of the derived field, such as `[].full_name`.
+    pub path: String,
+    /// The names of the fields of the same object that the value is computed from, which are
+    /// derived first if they are derived fields themselves.
+    pub depends_on: Vec<String>,
+    compute: Arc<ComputeFn>,
+}
+
+type ComputeFn = dyn Fn(&Map<String, Value>) -> Value + Send + Sync;
+
+impl DerivedField {
+    pub fn new(
+        path: impl Into<String>,
+        depends_on: &[&str],
+        compute: impl Fn(&Map<String, Value>) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        DerivedField {
+            path: path.into(),
+            depends_on: depends_on.iter().map(|field| field.to_string()).collect(),
+            compute: Arc::new(compute),
+        }
+    }
+
+    /// The path of the object that has the derived field, and the name of the field.
+    pub(crate) fn location(&self) -> (&str, &str) {
+        self.path.rsplit_once('.').unwrap_or(("", &self.path))
+    }
+
+    pub(crate) fn compute(&self, fields: &Map<String, Value>) -> Value {
+        (self.compute)(fields)
+    }
+}
+
+impl fmt::Debug for DerivedField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DerivedField")
+            .field("path", &self.path)
+            .field("depends_on", &self.depends_on)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for DerivedField {
+    /// Rules are only equal if they compute their values with the same function, since closures
+    /// can't be compared.
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.depends_on == other.depends_on
+            && Arc::ptr_eq(&self.compute, &other.compute)
+    }
+}
+
+/// Overrides how the value at a particular path is produced. Overrides win over the inferred
+/// schema; if more than one is set, the first in the order below applies.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldOverride {
+    /// The probability, between 0 and 1, of producing `null` instead of a value.
+    pub null_probability: Option<f64>,
+    /// Always produce this value.
+    pub value: Option<Value>,
+    /// Produce one of these values, picked at random.
+    pub pool: Option<Vec<Value>>,
+    /// Produce one of the values produced at another path, such as `users[].id`, picked at
+    /// random, so that the records of relational fixtures join. Until any value has been
+    /// produced there, the other overrides and the inferred schema apply as if it weren't set.
+    pub references: Option<String>,
+    /// Produce strings of this format.
+    pub format: Option<ValueFormat>,
+    /// The inclusive lower bound of the numbers to produce.
+    pub min: Option<f64>,
+    /// The inclusive upper bound of the numbers to produce.
+    pub max: Option<f64>,
+}
+
+/// An external command that generates the values at a path, for use in
+/// [`ProduceOptions::commands`], such as:
+///
+/// ```json
+/// {
+///   "commands": {
+///     "[].vat_number": { "command": ["python3", "vat.py"], "batch_size": 500 }
+///   }
+/// }
+/// ```
+///
+/// The command is run whenever the values it wrote last have all been used, with the
+/// environment variables `DRIVEL_COUNT`, the number of values wanted, `DRIVEL_PATH`, the path,
+/// `DRIVEL_SCHEMA`, the JSON Schema of the values, and `DRIVEL_SEED`, a random number that is
+/// derived from [`ProduceOptions::seed`] if there is one. It writes a value to stdout on each
+/// line, as JSON, or as a string if the line isn't JSON, and may write fewer or more values than
+/// were wanted. If it can't be run, fails or writes no values, a warning is written and drivel
+/// produces the values at the path as usual.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeneratorCommand {
+    /// The program to run, followed by its arguments.
+    pub command: Vec<String>,
+    /// How many values to ask for at a time. By default,
+    /// [`DEFAULT_COMMAND_BATCH_SIZE`](crate::DEFAULT_COMMAND_BATCH_SIZE).
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+}
+
+/// A string format that can be requested by a [`FieldOverride`], or have a generator registered
+/// for it in a [`GeneratorRegistry`], named as in JSON Schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValueFormat {
+    Email,
+    Uuid,
+    Date,
+    DateTime,
+    Uri,
+    Hostname,
+    Duration,
+}
+
+impl ValueFormat {
+    pub(crate) fn string_type(self) -> StringType {
+        match self {
+            ValueFormat::Email => StringType::Email,
+            ValueFormat::Uuid => StringType::UUID,
+            ValueFormat::Date => StringType::IsoDate,
+            ValueFormat::DateTime => StringType::DateTimeISO8601,
+            ValueFormat::Uri => StringType::Url,
+            ValueFormat::Hostname => StringType::Hostname,
+            ValueFormat::Duration => StringType::Duration,
+        }
+    }
+
+    /// The format of strings of a type, if it is one of these.
+    pub(crate) fn of(string_type: &StringType) -> Option<Self> {
+        match string_type {
+            StringType::Email => Some(ValueFormat::Email),
+            StringType::UUID => Some(ValueFormat::Uuid),
+            StringType::IsoDate => Some(ValueFormat::Date),
+            StringType::DateTimeISO8601 => Some(ValueFormat::DateTime),
+            StringType::Url => Some(ValueFormat::Uri),
+            StringType::Hostname => Some(ValueFormat::Hostname),
+            StringType::Duration => Some(ValueFormat::Duration),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ValueFormat {
+    /// Writes the name of the format, as in JSON Schema, such as `date-time`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueFormat::Email => "email",
+            ValueFormat::Uuid => "uuid",
+            ValueFormat::Date => "date",
+            ValueFormat::DateTime => "date-time",
+            ValueFormat::Uri => "uri",
+            ValueFormat::Hostname => "hostname",
+            ValueFormat::Duration => "duration",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What is produced in place of a float that isn't a finite number, such as `NaN` or
+/// `Infinity`, which JSON has no representation for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum NonFiniteFallback {
+    /// `null`.
+    #[default]
+    Null,
+    /// The number `0`.
+    Zero,
+    /// The token as a string, such as `"NaN"`.
+    Token,
+}
+
+/// The formats that produced data can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// A single JSON value, such as an array of all of the produced elements.
+    #[default]
+    Json,
+    /// JSON lines: each produced element as compact JSON on a line of its own, written as soon
+    /// as it is produced.
+    Ndjson,
+    /// Comma-separated values: a header row of the columns of the produced objects, with the
+    /// fields of nested objects flattened into columns such as `address.city`, and a row for
+    /// each produced element, written as soon as it is produced.
+    Csv,
+}
+
+/// A set of characters to produce strings from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StringCharset {
+    /// The ASCII letters, `a-z` and `A-Z`.
+    Alphabetic,
+    /// The ASCII letters and digits.
+    Alphanumeric,
+    /// The printable ASCII characters, including the space.
+    Ascii,
+    /// The lowercase hexadecimal digits, `0-9` and `a-f`.
+    Hex,
+    /// Exactly the characters of this string.
+    Custom(String),
+}
+
+impl StringCharset {
+    pub(crate) fn chars(&self) -> Vec<char> {
+        match self {
+            StringCharset::Alphabetic => ('a'..='z').chain('A'..='Z').collect(),
+            StringCharset::Alphanumeric => ('a'..='z').chain('A'..='Z').chain('0'..='9').collect(),
+            StringCharset::Ascii => (' '..='~').collect(),
+            StringCharset::Hex => ('0'..='9').chain('a'..='f').collect(),
+            StringCharset::Custom(chars) => chars.chars().collect(),
+        }
+    }
+}
+
+impl FromStr for StringCharset {
+    type Err = String;
+
+    /// Parses the name of a character set, or `custom:` followed by the characters to use.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alphabetic" => Ok(StringCharset::Alphabetic),
+            "alphanumeric" => Ok(StringCharset::Alphanumeric),
+            "ascii" => Ok(StringCharset::Ascii),
+            "hex" => Ok(StringCharset::Hex),
+            _ => match s.strip_prefix("custom:") {
+                Some("") => Err("a custom character set needs at least one character".to_string()),
+                Some(chars) => Ok(StringCharset::Custom(chars.to_string())),
+                None => Err(format!(
+                    "unknown character set '{}', expected one of alphabetic, alphanumeric, ascii, \
+                     hex or custom:<chars>",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl ProduceOptions {
+    /// Merges `other` into these options. Where both set an override, a transform or a generator
+    /// for the same path, a character set or a seed, the one from `other` wins. The derived fields of
+    /// `other` are added after these.
+    pub fn merge(&mut self, other: ProduceOptions) {
+        self.fields.extend(other.fields);
+        self.transforms.extend(other.transforms);
+        self.generators.extend(other.generators);
+        self.commands.extend(other.commands);
+        self.derived.extend(other.derived);
+        if other.string_charset.is_some() {
+            self.string_charset = other.string_charset;
+        }
+        if other.seed.is_some() {
+            self.seed = other.seed;
+        }
+        self.lorem |= other.lorem;
+        self.realistic |= other.realistic;
+        if other.locale.is_some() {
+            self.locale = other.locale;
+        }
+        self.omit_read_only |= other.omit_read_only;
+        if other.jobs.is_some() {
+            self.jobs = other.jobs;
+        }
+        if other.non_finite.is_some() {
+            self.non_finite = other.non_finite;
+        }
+        if other.optional_include_probability.is_some() {
+            self.optional_include_probability = other.optional_include_probability;
+        }
+        if other.null_probability.is_some() {
+            self.null_probability = other.null_probability;
+        }
+        if other.field_stats.is_some() {
+            self.field_stats = other.field_stats;
+        }
+        if other.number_stats.is_some() {
+            self.number_stats = other.number_stats;
+        }
+        if other.max_output_bytes.is_some() {
+            self.max_output_bytes = other.max_output_bytes;
+        }
+        if other.max_recursion_depth.is_some() {
+            self.max_recursion_depth = other.max_recursion_depth;
+        }
+        self.sort_keys |= other.sort_keys;
+        for path in other.unique_fields {
+            if !self.unique_fields.contains(&path) {
+                self.unique_fields.push(path);
+            }
+        }
+    }
+
+    /// Returns the paths of the overrides, unique fields, transforms, generators, derived fields
+    /// and referenced fields that do not refer to any value in `schema`, and so will never be applied.
+    pub fn unknown_paths(&self, schema: &SchemaState) -> Vec<&str> {
+        let paths = schema.paths();
+        self.fields
+            .keys()
+            .chain(&self.unique_fields)
+            .chain(self.transforms.keys())
+            .chain(self.generators.paths())
+            .chain(self.commands.keys())
+            .chain(self.derived.iter().map(|rule| &rule.path))
+            .chain(self.fields.values().filter_map(|o| o.references.as_ref()))
+            .filter(|path| !paths.contains(path.as_str()))
+            .map(|path| path.as_str())
+            .collect()
+    }
+}
+
+pub(crate) fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+pub(crate) fn items_path(path: &str) -> String {
+    format!("{}[]", path)
+}
+
+impl SchemaState {
+    /// Returns the paths of all values in this schema, as used by [`ProduceOptions`], such as
+    /// `orders[].total`, where the root is the empty path.
+    pub fn paths(&self) -> BTreeSet<String> {
+        let mut paths = BTreeSet::new();
+        collect_paths(self, "", &mut paths);
+        paths
+    }
+}
+
+fn collect_paths(schema: &SchemaState, path: &str, paths: &mut BTreeSet<String>) {
+    paths.insert(path.to_string());
+    match schema {
+        SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+            collect_paths(inner, path, paths)
+        }
+        SchemaState::Array { schema, .. } => collect_paths(schema, &items_path(path), paths),
+        SchemaState::Tuple(positions) => {
+            for position in positions {
+                collect_paths(position, &items_path(path), paths);
+            }
+        }
+        SchemaState::Map { schema, .. } => collect_paths(schema, &field_path(path, "*"), paths),
+        SchemaState::Union(variants) => {
+            for variant in variants {
+                collect_paths(variant, path, paths);
+            }
+        }
+        SchemaState::Mixed(variants) => {
+            for (variant, _) in variants {
+                collect_paths(variant, path, paths);
+            }
+        }
+        SchemaState::TaggedUnion { variants, .. } => {
+            for variant in variants.values() {
+                collect_paths(variant, path, paths);
+            }
+        }
+        SchemaState::Object {
+            required, optional, ..
+        } => {
+            for (key, field) in required.iter().chain(optional) {
+                collect_paths(field, &field_path(path, key), paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NumberType;
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    fn schema() -> SchemaState {
+        SchemaState::Object {
+            required: IndexMap::from([(
+                "orders".to_string(),
+                SchemaState::Array {
+                    min_length: 1,
+                    max_length: 3,
+                    lengths: None,
+                    unique_items: false,
+                    schema: Box::new(SchemaState::Object {
+                        required: IndexMap::from([(
+                            "total".to_string(),
+                            SchemaState::Number(NumberType::Float {
+                                min: 1.0,
+                                max: 9.0,
+                                non_finite: Default::default(),
+                            }),
+                        )]),
+                        optional: IndexMap::new(),
+                        access: Default::default(),
+                        annotations: Default::default(),
+                    }),
+                },
+            )]),
+            optional: IndexMap::from([("id".to_string(), SchemaState::Boolean)]),
+            access: Default::default(),
+            annotations: Default::default(),
+        }
+    }
+
+    #[test]
+    fn deserializes_config() {
+        let options: ProduceOptions = serde_json::from_value(json!({
+            "fields": {
+                "id": {"format": "uuid", "null_probability": 0.5},
+                "orders[].total": {"min": 0, "max": 5}
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(options.fields["id"].format, Some(ValueFormat::Uuid));
+        assert_eq!(options.fields["id"].null_probability, Some(0.5));
+        assert_eq!(options.fields["orders[].total"].max, Some(5.0));
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        for config in [
+            json!({"fields": {"id": {"colour": "red"}}}),
+            json!({"fields": {"id": {"format": "ipv4"}}}),
+            json!({"feilds": {}}),
+        ] {
+            assert!(serde_json::from_value::<ProduceOptions>(config).is_err());
+        }
+    }
+
+    #[test]
+    fn merged_overrides_win() {
+        let mut options = ProduceOptions::default();
+        options.fields.insert(
+            "id".to_string(),
+            FieldOverride {
+                value: Some(json!(1)),
+                ..Default::default()
+            },
+        );
+        let mut other = ProduceOptions::default();
+        other.fields.insert(
+            "id".to_string(),
+            FieldOverride {
+                value: Some(json!(2)),
+                ..Default::default()
+            },
+        );
+        options.merge(other);
+
+        assert_eq!(options.fields["id"].value, Some(json!(2)));
+    }
+
+    #[test]
+    fn parses_charsets() {
+        assert_eq!("hex".parse(), Ok(StringCharset::Hex));
+        assert_eq!(
+            "custom:ACGT".parse(),
+            Ok(StringCharset::Custom("ACGT".to_string()))
+        );
+        assert!("custom:".parse::<StringCharset>().is_err());
+        assert!("base64".parse::<StringCharset>().is_err());
+
+        let options: ProduceOptions =
+            serde_json::from_value(json!({"string_charset": {"custom": "01"}})).unwrap();
+        assert_eq!(
+            options.string_charset,
+            Some(StringCharset::Custom("01".to_string()))
+        );
+    }
+
+    #[test]
+    fn finds_unknown_paths() {
+        let mut options = ProduceOptions::default();
+        for path in ["id", "orders[].total", "orders.total", "name"] {
+            options
+                .fields
+                .insert(path.to_string(), FieldOverride::default());
+        }
+
+        assert_eq!(
+            options.unknown_paths(&schema()),
+            vec!["name", "orders.total"]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many records to process between checks of the clock, to keep `tick` cheap.
+const CHECK_EVERY: usize = 1024;
+
+/// A throttled progress reporter that periodically writes the number of records processed to
+/// stderr, as [information](crate::Severity::Info).
+///
+/// `tick` can be called concurrently from multiple threads, in which case the reported count is
+/// the aggregate over all threads.
+pub struct Progress {
+    processed: AtomicUsize,
+    interval: Duration,
+    last_report: Mutex<Instant>,
+}
+
+impl Progress {
+    /// Creates a reporter that writes at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Progress {
+            processed: AtomicUsize::new(0),
+            interval,
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Records that a single record has been processed, reporting progress if due. Returns
+    /// whether progress was reported, so that callers can report more alongside it.
+    pub fn tick(&self) -> bool {
+        let processed = self.processed.fetch_add(1, Ordering::Relaxed) + 1;
+        let due = processed.is_multiple_of(CHECK_EVERY) && self.due();
+        if due {
+            crate::diagnostics::info(format!("Processed {} records...", processed));
+        }
+        due
+    }
+
+    /// The number of records processed so far.
+    pub fn processed(&self) -> usize {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// Writes the final count of records processed.
+    pub fn finish(&self) {
+        crate::diagnostics::info(format!("Processed {} records", self.processed()));
+    }
+
+    fn due(&self) -> bool {
+        let mut last_report = self.last_report.lock().unwrap();
+        if last_report.elapsed() >= self.interval {
+            *last_report = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn counts_ticks() {
+        let progress = Progress::new(Duration::from_secs(60));
+        for _ in 0..10 {
+            progress.tick();
+        }
+        assert_eq!(progress.processed(), 10);
+    }
+
+    #[test]
+    fn counts_ticks_across_threads() {
+        let progress = Progress::new(Duration::ZERO);
+        (0..10_000).into_par_iter().for_each(|_| {
+            progress.tick();
+        });
+        assert_eq!(progress.processed(), 10_000);
+    }
+
+    #[test]
+    fn tells_whether_progress_was_reported() {
+        let progress = Progress::new(Duration::ZERO);
+        let reported = (0..CHECK_EVERY * 2).filter(|_| progress.tick()).count();
+        assert_eq!(reported, 2);
+    }
+
+    #[test]
+    fn throttles_reports() {
+        let progress = Progress::new(Duration::from_secs(60));
+        assert!(!progress.due());
+
+        let progress = Progress::new(Duration::ZERO);
+        assert!(progress.due());
+    }
+}
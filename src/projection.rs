@@ -0,0 +1,276 @@
+use crate::produce_options::{field_path, items_path};
+use crate::SchemaState;
+use indexmap::IndexMap;
+
+/// Whether the value at `path` is, or contains, the value at `selected`, where both are paths
+/// as used by [`ProduceOptions`](crate::ProduceOptions), such as `orders[].total`.
+fn is_within(selected: &str, path: &str) -> bool {
+    path.is_empty()
+        || selected == path
+        || selected
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+/// The projection of the schema of the value at `path`, or `None` if none of the selected paths
+/// is within it.
+fn project_at(schema: &SchemaState, path: &str, selected: &[&str]) -> Option<SchemaState> {
+    if selected.contains(&path) {
+        return Some(schema.clone());
+    }
+    // the root is kept even if nothing is selected in it, as an object without its fields
+    if !path.is_empty() && !selected.iter().any(|s| is_within(s, path)) {
+        return None;
+    }
+    Some(match schema {
+        SchemaState::Nullable(inner) => {
+            SchemaState::Nullable(Box::new(project_at(inner, path, selected)?))
+        }
+        SchemaState::Recursive { name, schema } => SchemaState::Recursive {
+            name: name.clone(),
+            schema: Box::new(project_at(schema, path, selected)?),
+        },
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths,
+            unique_items,
+            schema,
+        } => SchemaState::Array {
+            min_length: *min_length,
+            max_length: *max_length,
+            lengths: lengths.clone(),
+            unique_items: *unique_items,
+            schema: Box::new(project_at(schema, &items_path(path), selected)?),
+        },
+        SchemaState::Tuple(positions) => SchemaState::Tuple(
+            positions
+                .iter()
+                .map(|position| project_at(position, &items_path(path), selected))
+                .collect::<Option<_>>()?,
+        ),
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => SchemaState::Map {
+            min_length: *min_length,
+            max_length: *max_length,
+            keys: keys.clone(),
+            schema: Box::new(project_at(schema, &field_path(path, "*"), selected)?),
+        },
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            annotations,
+        } => {
+            let project_fields = |fields: &IndexMap<String, SchemaState>| {
+                fields
+                    .iter()
+                    .filter_map(|(key, field)| {
+                        let field = project_at(field, &field_path(path, key), selected)?;
+                        Some((key.clone(), field))
+                    })
+                    .collect::<IndexMap<_, _>>()
+            };
+            let required = project_fields(required);
+            let optional = project_fields(optional);
+            let kept = |key: &String| required.contains_key(key) || optional.contains_key(key);
+            SchemaState::Object {
+                access: access
+                    .iter()
+                    .filter(|(key, _)| kept(key))
+                    .map(|(key, access)| (key.clone(), *access))
+                    .collect(),
+                annotations: annotations
+                    .iter()
+                    .filter(|(key, _)| kept(key))
+                    .map(|(key, keywords)| (key.clone(), keywords.clone()))
+                    .collect(),
+                required,
+                optional,
+            }
+        }
+        SchemaState::Union(variants) => SchemaState::Union(
+            variants
+                .iter()
+                .filter_map(|variant| project_at(variant, path, selected))
+                .collect(),
+        ),
+        SchemaState::Mixed(variants) => SchemaState::Mixed(
+            variants
+                .iter()
+                .filter_map(|(variant, count)| Some((project_at(variant, path, selected)?, *count)))
+                .collect(),
+        ),
+        // the tag is set on every object produced for a variant, so it is only kept if selected
+        SchemaState::TaggedUnion { tag, variants }
+            if selected.contains(&field_path(path, tag).as_str()) =>
+        {
+            SchemaState::TaggedUnion {
+                tag: tag.clone(),
+                variants: variants
+                    .iter()
+                    .filter_map(|(value, variant)| {
+                        Some((value.clone(), project_at(variant, path, selected)?))
+                    })
+                    .collect(),
+            }
+        }
+        SchemaState::TaggedUnion { variants, .. } => SchemaState::Union(
+            variants
+                .values()
+                .filter_map(|variant| project_at(variant, path, selected))
+                .collect(),
+        ),
+        // values without fields of their own are kept whole
+        _ => schema.clone(),
+    })
+}
+
+impl SchemaState {
+    /// Projects this schema onto the values at the given paths, as used by
+    /// [`ProduceOptions`](crate::ProduceOptions), such as `id` or `orders[].total`, leaving out
+    /// all other fields of objects. The objects that contain a selected value keep only the
+    /// fields leading to it, and a selected object keeps all of its fields. Paths that don't
+    /// refer to any value are ignored; see [`SchemaState::paths`] to check for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions, SchemaState};
+    /// use serde_json::json;
+    ///
+    /// let input = json!({"id": 1, "name": "Ann", "address": {"city": "Oslo", "zip": "0150"}});
+    /// let options = InferenceOptions::default();
+    /// let schema = infer_schema(input, &options).project(&["id", "address.city"]);
+    /// let SchemaState::Object { required, .. } = &schema else { panic!() };
+    /// assert_eq!(required.len(), 2);
+    /// assert!(schema.paths().contains("address.city"));
+    /// assert!(!schema.paths().contains("address.zip"));
+    /// ```
+    pub fn project(&self, paths: &[&str]) -> SchemaState {
+        project_at(self, "", paths).unwrap_or_else(|| self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+    use crate::{NumberType, StringType};
+
+    fn integer() -> SchemaState {
+        SchemaState::Number(NumberType::Integer { min: 1, max: 9 })
+    }
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 3,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn customer() -> SchemaState {
+        object_schema(
+            vec![
+                ("id", integer()),
+                ("name", SchemaState::String(StringType::Email)),
+                (
+                    "address",
+                    object_schema(
+                        vec![("city", SchemaState::String(StringType::UUID))],
+                        vec![("zip", integer())],
+                    ),
+                ),
+                (
+                    "orders",
+                    array(object_schema(
+                        vec![("total", integer()), ("sku", integer())],
+                        vec![],
+                    )),
+                ),
+            ],
+            vec![("note", integer())],
+        )
+    }
+
+    #[test]
+    fn projects_nested_fields() {
+        assert_eq!(
+            customer().project(&["id", "address.zip", "note"]),
+            object_schema(
+                vec![
+                    ("id", integer()),
+                    ("address", object_schema(vec![], vec![("zip", integer())])),
+                ],
+                vec![("note", integer())],
+            )
+        );
+        // a selected object keeps all of its fields
+        let SchemaState::Object { required, .. } = customer().project(&["address"]) else {
+            panic!("Expected an object");
+        };
+        assert_eq!(required.len(), 1);
+        assert_eq!(
+            required["address"],
+            object_schema(
+                vec![("city", SchemaState::String(StringType::UUID))],
+                vec![("zip", integer())],
+            )
+        );
+    }
+
+    #[test]
+    fn projects_fields_of_array_elements() {
+        assert_eq!(
+            customer().project(&["orders[].total"]),
+            object_schema(
+                vec![(
+                    "orders",
+                    array(object_schema(vec![("total", integer())], vec![]))
+                )],
+                vec![],
+            )
+        );
+        assert_eq!(
+            array(customer()).project(&["[].id", "[].nothing", "id"]),
+            array(object_schema(vec![("id", integer())], vec![]))
+        );
+    }
+
+    #[test]
+    fn keeps_tags_of_tagged_unions_only_if_selected() {
+        let schema = SchemaState::TaggedUnion {
+            tag: "type".to_string(),
+            variants: [
+                (
+                    "a".to_string(),
+                    object_schema(vec![("type", integer()), ("x", integer())], vec![]),
+                ),
+                (
+                    "b".to_string(),
+                    object_schema(vec![("type", integer()), ("y", integer())], vec![]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert!(matches!(
+            schema.project(&["type", "x"]),
+            SchemaState::TaggedUnion { .. }
+        ));
+        assert_eq!(
+            schema.project(&["x"]),
+            SchemaState::Union(vec![
+                object_schema(vec![("x", integer())], vec![]),
+                object_schema(vec![], vec![]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use std::collections::BTreeSet;
+
+use crate::codegen::{pascal_case, snake_case_words, unique};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+const TIMESTAMP: &str = "google/protobuf/timestamp.proto";
+const STRUCT: &str = "google/protobuf/struct.proto";
+
+/// How a field holds its values.
+enum Label {
+    Singular,
+    Repeated,
+    Map,
+}
+
+/// The declarations of the types nested in a message, which only have to be named uniquely
+/// among themselves.
+#[derive(Default)]
+struct Scope {
+    names: Vec<String>,
+    /// The lines of each declaration.
+    declarations: Vec<Vec<String>>,
+}
+
+impl Scope {
+    fn name(&mut self, name: &str) -> String {
+        let name = unique(self.names.iter().cloned().chain([pascal_case(name)]))
+            .pop()
+            .unwrap();
+        self.names.push(name.clone());
+        name
+    }
+}
+
+/// The name of a field in JSON, as protobuf derives it from the name of the field, e.g.
+/// `user_id` is `userId`.
+fn json_name(name: &str) -> String {
+    let mut json = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            json.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            json.push(c);
+        }
+    }
+    json
+}
+
+/// The name of an enum value, in upper snake case and prefixed with the name of the enum, as
+/// enum values share the scope of their enum, e.g. `STATUS_IN_PROGRESS` for `inProgress`.
+fn enum_value_name(prefix: &str, value: &str) -> String {
+    let words = snake_case_words(value);
+    // a value that starts with a digit is valid after the prefix
+    let words = (words.strip_prefix("field_"))
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(&words);
+    format!("{}_{}", prefix, words.to_ascii_uppercase())
+}
+
+fn number_type(number: &NumberType) -> &'static str {
+    let (min, max) = match number {
+        NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } => return "double",
+        NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. } => (*min, *max),
+        NumberType::Multiple { min, max, .. } => (*min as i64, *max as i64),
+        NumberType::Enum { variants: values } | NumberType::Flags { values } => (
+            values.keys().next().copied().unwrap_or_default(),
+            values.keys().next_back().copied().unwrap_or_default(),
+        ),
+    };
+    if i32::try_from(min).is_ok() && i32::try_from(max).is_ok() {
+        "int32"
+    } else {
+        "int64"
+    }
+}
+
+/// The fields of an object, with whether they are optional, the required ones first.
+fn object_fields(schema: &SchemaState) -> Vec<(&str, &SchemaState, bool)> {
+    let SchemaState::Object {
+        required, optional, ..
+    } = schema
+    else {
+        unreachable!("only objects are messages")
+    };
+    required
+        .iter()
+        .map(|(key, field)| (key.as_str(), field, false))
+        .chain(
+            optional
+                .iter()
+                .map(|(key, field)| (key.as_str(), field, true)),
+        )
+        .collect()
+}
+
+/// The messages of protobuf output, and the well-known types they import.
+#[derive(Default)]
+struct Messages {
+    imports: BTreeSet<&'static str>,
+    /// The names of the recursive definitions whose messages are being declared, with the
+    /// names of the messages, which the values of them within them refer to.
+    recursive: Vec<(String, String)>,
+}
+
+impl Messages {
+    fn import(&mut self, file: &'static str, name: &str) -> String {
+        self.imports.insert(file);
+        name.to_string()
+    }
+
+    /// Declares a message with a field for each of `fields`, with any types that are only used
+    /// by its fields nested in it.
+    fn message(&mut self, name: &str, fields: Vec<(&str, &SchemaState, bool)>) -> Vec<String> {
+        let mut scope = Scope::default();
+        let names = unique(fields.iter().map(|(key, _, _)| snake_case_words(key)));
+        let mut lines = vec![];
+        for (number, ((key, field, optional), name)) in fields.into_iter().zip(names).enumerate() {
+            let (field, nullable) = match field {
+                SchemaState::Nullable(inner) => (inner.as_ref(), true),
+                field => (field, optional),
+            };
+            let (label, field_type) = self.field_type(field, key, &mut scope);
+            let label = match label {
+                Label::Repeated => "repeated ",
+                Label::Singular if nullable => "optional ",
+                Label::Singular | Label::Map => "",
+            };
+            let mut line = format!("{}{} {} = {}", label, field_type, name, number + 1);
+            if key != name && key != json_name(&name) {
+                let quoted = key.replace('\\', "\\\\").replace('"', "\\\"");
+                line.push_str(&format!(" [json_name = \"{}\"]", quoted));
+            }
+            line.push(';');
+            lines.push(line);
+        }
+
+        let mut body: Vec<String> = vec![];
+        for declaration in scope.declarations {
+            body.extend(declaration);
+            body.push(String::new());
+        }
+        body.extend(lines);
+        let mut message = vec![format!("message {} {{", name)];
+        message.extend(body.into_iter().map(|line| match line.is_empty() {
+            true => line,
+            false => format!("  {}", line),
+        }));
+        message.push("}".to_string());
+        message
+    }
+
+    fn field_type(
+        &mut self,
+        schema: &SchemaState,
+        key: &str,
+        scope: &mut Scope,
+    ) -> (Label, String) {
+        match schema {
+            SchemaState::Array { schema, .. } => {
+                let items = match schema.as_ref() {
+                    SchemaState::Nullable(inner) => inner,
+                    items => items,
+                };
+                let item = self.singular_type(items, &format!("{}_item", key), scope);
+                (Label::Repeated, item)
+            }
+            SchemaState::Coordinates(_) => (Label::Repeated, "double".to_string()),
+            // the positions of tuples are of different types
+            SchemaState::Tuple(_) => (
+                Label::Repeated,
+                self.import(STRUCT, "google.protobuf.Value"),
+            ),
+            SchemaState::Map { schema, .. } => {
+                let value = self.singular_type(schema, &format!("{}_value", key), scope);
+                (Label::Map, format!("map<string, {}>", value))
+            }
+            schema => (Label::Singular, self.singular_type(schema, key, scope)),
+        }
+    }
+
+    /// The type of a value that is not repeated, where `key` is the field it is of, after which
+    /// the messages and enums it declares in `scope` are named. Arrays and maps in arrays and
+    /// maps are lists and structs, as fields can't be repeated twice.
+    fn singular_type(&mut self, schema: &SchemaState, key: &str, scope: &mut Scope) -> String {
+        match schema {
+            SchemaState::Nullable(inner) => self.singular_type(inner, key, scope),
+            SchemaState::Literal(values) => {
+                self.singular_type(&SchemaState::literal_type(values), key, scope)
+            }
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                let name = scope.name(key);
+                self.recursive.push((definition.clone(), name.clone()));
+                let message = self.message(&name, object_fields(schema));
+                self.recursive.pop();
+                scope.declarations.push(message);
+                name
+            }
+            SchemaState::Recursive { schema, .. } => self.singular_type(schema, key, scope),
+            // only objects are messages, so another recursive definition can't be referred to
+            SchemaState::Reference(definition) => {
+                match self
+                    .recursive
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| name == definition)
+                {
+                    Some((_, name)) => name.clone(),
+                    None => self.import(STRUCT, "google.protobuf.Value"),
+                }
+            }
+            SchemaState::Boolean => "bool".to_string(),
+            SchemaState::Number(number) => number_type(number).to_string(),
+            SchemaState::String(StringType::DateTimeISO8601)
+            | SchemaState::ExtendedJson(ExtendedJsonType::Date) => {
+                self.import(TIMESTAMP, "google.protobuf.Timestamp")
+            }
+            SchemaState::String(StringType::Enum { variants })
+                if variants.iter().all(|variant| !variant.is_empty()) =>
+            {
+                let name = scope.name(key);
+                let prefix = snake_case_words(&name).to_ascii_uppercase();
+                let values = unique(
+                    [format!("{}_UNSPECIFIED", prefix)].into_iter().chain(
+                        variants
+                            .iter()
+                            .map(|variant| enum_value_name(&prefix, variant)),
+                    ),
+                );
+                let mut lines = vec![format!("enum {} {{", name)];
+                lines.extend(
+                    (values.iter().enumerate())
+                        .map(|(number, value)| format!("  {} = {};", value, number)),
+                );
+                lines.push("}".to_string());
+                scope.declarations.push(lines);
+                name
+            }
+            SchemaState::String(_) | SchemaState::ExtendedJson(ExtendedJsonType::ObjectId) => {
+                "string".to_string()
+            }
+            SchemaState::ExtendedJson(ExtendedJsonType::Long { .. }) => "int64".to_string(),
+            SchemaState::Object { .. } => self.nested(key, object_fields(schema), scope),
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                ..
+            } => {
+                let double = SchemaState::Number(NumberType::Float {
+                    min: f64::NEG_INFINITY,
+                    max: f64::INFINITY,
+                    non_finite: Default::default(),
+                });
+                let fields = vec![
+                    (latitude_key.as_str(), &double, false),
+                    (longitude_key.as_str(), &double, false),
+                ];
+                self.nested(key, fields, scope)
+            }
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                ..
+            } => {
+                let amount = SchemaState::Number(amount.clone());
+                let currency = SchemaState::String(StringType::Unknown {
+                    strings_seen: vec![],
+                    chars_seen: vec![],
+                    min_length: None,
+                    max_length: None,
+                });
+                let fields = vec![
+                    (amount_key.as_str(), &amount, false),
+                    (currency_key.as_str(), &currency, false),
+                ];
+                self.nested(key, fields, scope)
+            }
+            SchemaState::Array { .. } | SchemaState::Coordinates(_) | SchemaState::Tuple(_) => {
+                self.import(STRUCT, "google.protobuf.ListValue")
+            }
+            SchemaState::Map { .. } | SchemaState::TaggedUnion { .. } => {
+                self.import(STRUCT, "google.protobuf.Struct")
+            }
+            // values of no single type are JSON values
+            SchemaState::Initial
+            | SchemaState::Null
+            | SchemaState::Union(_)
+            | SchemaState::Mixed(_)
+            | SchemaState::Indefinite => self.import(STRUCT, "google.protobuf.Value"),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        key: &str,
+        fields: Vec<(&str, &SchemaState, bool)>,
+        scope: &mut Scope,
+    ) -> String {
+        let name = scope.name(key);
+        let message = self.message(&name, fields);
+        scope.declarations.push(message);
+        name
+    }
+}
+
+/// Renders a schema as the proto3 message definitions of a `.proto` file, such as to bootstrap
+/// a gRPC contract. The root is a message named `name`, of the fields of the records, or of
+/// those of the elements of an array at the root, or of a single `value` field otherwise. Each
+/// nested object is a message nested in that of its parent, named after its field. Fields are
+/// numbered in the order of their names, arrays are `repeated`, optional and nullable fields
+/// are `optional`, and enums of strings are enums, whose values are prefixed with the name of
+/// the enum after a zero `UNSPECIFIED` value. Date-times are `google.protobuf.Timestamp`s, and
+/// values of no single type, such as unions, are `google.protobuf.Value`s. Fields whose names
+/// in JSON aren't the protobuf JSON names of their fields get a `json_name`. The values of a
+/// recursive definition within it are of its message.
+pub(crate) fn to_proto(schema: &SchemaState, name: &str) -> String {
+    let mut messages = Messages::default();
+    let mut records: &SchemaState = match schema {
+        SchemaState::Array { schema, .. } => schema,
+        schema => schema,
+    };
+    if let SchemaState::Recursive {
+        name: definition,
+        schema,
+    } = records
+    {
+        if matches!(schema.as_ref(), SchemaState::Object { .. }) {
+            messages
+                .recursive
+                .push((definition.clone(), pascal_case(name)));
+            records = schema;
+        }
+    }
+    let fields: Vec<(&str, &SchemaState, bool)> = match records {
+        SchemaState::Object { .. } => object_fields(records),
+        records => vec![("value", records, false)],
+    };
+    let message = messages.message(&pascal_case(name), fields);
+
+    let mut output = vec!["syntax = \"proto3\";".to_string(), String::new()];
+    if !messages.imports.is_empty() {
+        output.extend((messages.imports.iter()).map(|import| format!("import \"{}\";", import)));
+        output.push(String::new());
+    }
+    output.extend(message);
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, EnumInference, InferenceOptions};
+    use serde_json::json;
+
+    #[test]
+    fn describes_records_as_messages() {
+        let input = json!([
+            {
+                "id": 1,
+                "status": "in-progress",
+                "placedAt": "2023-12-25T10:30:00Z",
+                "address": {"city": "Delft", "zip_code": null},
+                "tags": ["a"],
+                "first-name": "Ann",
+            },
+            {
+                "id": 3000000000i64,
+                "status": "done",
+                "placedAt": "2024-01-02T08:00:00Z",
+                "address": {"city": "Leiden", "zip_code": "2311"},
+                "tags": [],
+                "first-name": "Bob",
+                "note": "x",
+            },
+            {
+                "id": 3,
+                "status": "done",
+                "placedAt": "2024-01-03T08:00:00Z",
+                "address": {"city": "Gouda", "zip_code": "2611"},
+                "tags": ["b"],
+                "first-name": "Cy",
+            },
+        ]);
+        let options = InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.7,
+                max_values: None,
+                min_sample_size: 3,
+                include_numeric: false,
+                normalize: false,
+                open_tail_coverage: None,
+            }),
+            ..Default::default()
+        };
+        let schema = infer_schema(input, &options);
+
+        assert_eq!(
+            to_proto(&schema, "order"),
+            r#"syntax = "proto3";
+
+import "google/protobuf/timestamp.proto";
+
+message Order {
+  enum Status {
+    STATUS_UNSPECIFIED = 0;
+    STATUS_DONE = 1;
+    STATUS_IN_PROGRESS = 2;
+  }
+
+  message Address {
+    string city = 1;
+    optional string zip_code = 2;
+  }
+
+  int64 id = 1;
+  Status status = 2;
+  google.protobuf.Timestamp placed_at = 3;
+  Address address = 4;
+  repeated string tags = 5;
+  string first_name = 6 [json_name = "first-name"];
+  optional string note = 7;
+}"#
+        );
+    }
+
+    #[test]
+    fn values_without_a_message_field_type_are_well_known_types() {
+        let input = json!([
+            {"matrix": [[1]], "mixed": [1, "a"], "counts": {"a": 1, "b": 2, "c": 3, "d": 4}},
+            {"matrix": [], "mixed": [true], "counts": {}},
+        ]);
+        let options = InferenceOptions {
+            max_object_fields: Some(3),
+            ..Default::default()
+        };
+        let schema = infer_schema(input, &options);
+
+        let proto = to_proto(&schema, "row");
+
+        assert!(proto.contains("import \"google/protobuf/struct.proto\";"));
+        assert!(proto.contains("repeated google.protobuf.ListValue matrix = 1;"));
+        assert!(proto.contains("repeated google.protobuf.Value mixed = 2;"));
+        assert!(proto.contains("map<string, int32> counts = 3;"));
+    }
+
+    #[test]
+    fn wraps_values_that_are_not_records_in_a_message() {
+        let proto = to_proto(&SchemaState::Boolean, "flag");
+
+        assert_eq!(
+            proto,
+            "syntax = \"proto3\";\n\nmessage Flag {\n  bool value = 1;\n}"
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_messages() {
+        let node = |children| SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(SchemaState::Object {
+                required: [("children".to_string(), children)].into_iter().collect(),
+                optional: Default::default(),
+                access: Default::default(),
+                annotations: Default::default(),
+            }),
+        };
+        let children = |item| SchemaState::Array {
+            min_length: 0,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(item),
+        };
+        let tree = node(children(SchemaState::Reference("node".to_string())));
+
+        assert_eq!(
+            to_proto(&tree, "tree"),
+            "syntax = \"proto3\";\n\nmessage Tree {\n  repeated Tree children = 1;\n}"
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::codegen::{self, any_string, object, pascal_case, snake_case_words, unique};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// Names that the generated code imports, which its own classes mustn't shadow.
+const RESERVED_NAMES: &[&str] = &[
+    "Annotated",
+    "Any",
+    "BaseModel",
+    "False",
+    "Field",
+    "IPv4Address",
+    "IPv6Address",
+    "Literal",
+    "None",
+    "Optional",
+    "True",
+    "UUID",
+    "Union",
+];
+
+/// Keywords, which can't be used as field names, and the methods of `BaseModel` that fields
+/// mustn't shadow.
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "copy", "def", "del",
+    "dict", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+    "is", "json", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "schema", "try",
+    "validate", "while", "with", "yield",
+];
+
+/// Turns a field name into the name of a class attribute, in snake case, with keywords suffixed
+/// with `_`.
+fn attribute_name(name: &str) -> String {
+    let mut snake = snake_case_words(name);
+    if KEYWORDS.contains(&snake.as_str()) {
+        snake.push('_');
+    }
+    snake
+}
+
+/// A Python string literal of a string.
+fn literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/// The declarations of Pydantic output, a model class for each object shape, and the names
+/// that they import.
+struct Declarations {
+    /// The lines of the fields of each class.
+    classes: codegen::Declarations<Vec<String>>,
+    /// The names imported from each module of the standard library.
+    imports: BTreeMap<&'static str, BTreeSet<&'static str>>,
+    /// Whether any field is declared with `Field`, for an alias or a discriminator.
+    fields: bool,
+}
+
+impl Declarations {
+    fn import(&mut self, module: &'static str, name: &'static str) -> String {
+        self.imports.entry(module).or_default().insert(name);
+        name.to_string()
+    }
+
+    /// The type `Optional[inner]`.
+    fn optional(&mut self, inner: String) -> String {
+        format!("{}[{}]", self.import("typing", "Optional"), inner)
+    }
+
+    /// The type of a union of types, `Union[...]`, or the type itself if there is just one.
+    fn union(&mut self, types: Vec<String>) -> String {
+        let mut distinct: Vec<String> = vec![];
+        for t in types {
+            if !distinct.contains(&t) {
+                distinct.push(t);
+            }
+        }
+        if distinct.len() == 1 {
+            return distinct.pop().unwrap();
+        }
+        format!(
+            "{}[{}]",
+            self.import("typing", "Union"),
+            distinct.join(", ")
+        )
+    }
+
+    /// The type `Literal[...]` of the given values.
+    fn literal(&mut self, values: Vec<String>) -> String {
+        format!(
+            "{}[{}]",
+            self.import("typing", "Literal"),
+            values.join(", ")
+        )
+    }
+
+    /// Declares a model class for an object, with an attribute for each of its fields.
+    /// Optional fields, like nullable ones, are `Optional`, and default to `None`. Fields whose
+    /// names aren't snake case have an alias of their name. The class of a recursive definition,
+    /// `recursive`, is named before its fields are rendered, so that the values of it within it
+    /// can refer to it.
+    fn object(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> String {
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            unreachable!("only objects are declared as classes")
+        };
+        let index = self.classes.start();
+        if let Some(definition) = recursive {
+            self.classes.name(index, definition, name, parent);
+        }
+        let keys: Vec<(&String, bool)> = required
+            .keys()
+            .map(|key| (key, false))
+            .chain(optional.keys().map(|key| (key, true)))
+            .collect();
+        let attribute_names = unique(keys.iter().map(|(key, _)| attribute_name(key)));
+        let mut lines = vec![];
+        for ((key, is_optional), attribute) in keys.into_iter().zip(attribute_names) {
+            let field = if is_optional {
+                &optional[key]
+            } else {
+                &required[key]
+            };
+            let mut field_type = self.type_of(field, &pascal_case(key), name);
+            if is_optional && !matches!(field, SchemaState::Nullable(_)) {
+                field_type = self.optional(field_type);
+            }
+            let mut arguments = vec![];
+            if is_optional {
+                arguments.push("default=None".to_string());
+            }
+            if attribute != *key {
+                arguments.push(format!("alias={}", literal(key)));
+            }
+            let default = match arguments.as_slice() {
+                [] => String::new(),
+                [default] if default == "default=None" => " = None".to_string(),
+                _ => {
+                    self.fields = true;
+                    format!(" = Field({})", arguments.join(", "))
+                }
+            };
+            lines.push(format!("{}: {}{}", attribute, field_type, default));
+        }
+        self.classes.finish(index, lines, name, parent)
+    }
+
+    /// The Python type of the values of a schema, declaring model classes for the objects among
+    /// them, named after `name`, the name of the field they are in as a class.
+    fn type_of(&mut self, schema: &SchemaState, name: &str, parent: &str) -> String {
+        match schema {
+            SchemaState::Initial | SchemaState::Indefinite => self.import("typing", "Any"),
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                self.object(schema, name, parent, Some(definition))
+            }
+            SchemaState::Recursive { schema, .. } => self.type_of(schema, name, parent),
+            // a forward reference to the class, which isn't defined yet; only objects are
+            // declared, so another recursive definition can't be referred to
+            SchemaState::Reference(definition) => match self.classes.recursive(definition) {
+                Some(declared) => literal(declared),
+                None => self.import("typing", "Any"),
+            },
+            SchemaState::Null => "None".to_string(),
+            SchemaState::Nullable(inner) => {
+                let inner = self.type_of(inner, name, parent);
+                self.optional(inner)
+            }
+            // a `Literal` can't be of floats, so values are of the type they are inferred as
+            SchemaState::Literal(values) => {
+                self.type_of(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::String(StringType::Enum { variants }) => {
+                self.literal(variants.iter().map(|variant| literal(variant)).collect())
+            }
+            SchemaState::String(StringType::IsoDate) => self.import("datetime", "date"),
+            SchemaState::String(StringType::DateTimeISO8601) => self.import("datetime", "datetime"),
+            SchemaState::String(StringType::UUID) => self.import("uuid", "UUID"),
+            SchemaState::String(StringType::Ipv4) => self.import("ipaddress", "IPv4Address"),
+            SchemaState::String(StringType::Ipv6) => self.import("ipaddress", "IPv6Address"),
+            SchemaState::String(_) => "str".to_string(),
+            SchemaState::Number(NumberType::Enum { variants }) => {
+                self.literal(variants.keys().map(|value| value.to_string()).collect())
+            }
+            SchemaState::Number(
+                NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+            ) => "float".to_string(),
+            SchemaState::Number(_) => "int".to_string(),
+            SchemaState::Boolean => "bool".to_string(),
+            SchemaState::Array { schema, .. } => format!(
+                "list[{}]",
+                self.type_of(schema, &format!("{}Item", name), parent)
+            ),
+            SchemaState::Object { .. } => self.object(schema, name, parent, None),
+            SchemaState::Map { schema, .. } => format!(
+                "dict[str, {}]",
+                self.type_of(schema, &format!("{}Value", name), parent)
+            ),
+            SchemaState::ExtendedJson(extended) => {
+                let key = match extended {
+                    ExtendedJsonType::ObjectId => "$oid",
+                    ExtendedJsonType::Date => "$date",
+                    ExtendedJsonType::Long { .. } => "$numberLong",
+                };
+                self.object(&object(vec![(key, any_string())]), name, parent, None)
+            }
+            SchemaState::Coordinates(positions) => format!(
+                "tuple[{}]",
+                positions
+                    .iter()
+                    .map(|position| match position {
+                        NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } =>
+                            "float",
+                        _ => "int",
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SchemaState::Tuple(positions) => {
+                let positions: Vec<String> = positions
+                    .iter()
+                    .map(|position| self.type_of(position, &format!("{}Item", name), parent))
+                    .collect();
+                format!("tuple[{}]", positions.join(", "))
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            } => self.object(
+                &object(vec![
+                    (latitude_key, SchemaState::Number(latitude.clone())),
+                    (longitude_key, SchemaState::Number(longitude.clone())),
+                ]),
+                name,
+                parent,
+                None,
+            ),
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => self.object(
+                &object(vec![
+                    (amount_key, SchemaState::Number(amount.clone())),
+                    (
+                        currency_key,
+                        SchemaState::String(StringType::Enum {
+                            variants: currencies.clone(),
+                        }),
+                    ),
+                ]),
+                name,
+                parent,
+                None,
+            ),
+            SchemaState::Union(variants) => {
+                let types = variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variant)| {
+                        self.type_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect();
+                self.union(types)
+            }
+            SchemaState::Mixed(variants) => {
+                let types = variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (variant, _))| {
+                        self.type_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect();
+                self.union(types)
+            }
+            SchemaState::TaggedUnion { tag, variants } => {
+                let types = variants
+                    .iter()
+                    .map(|(value, variant)| {
+                        // the tag field of each variant has just its value, which tells them apart
+                        let mut variant = variant.clone();
+                        if let SchemaState::Object {
+                            required, optional, ..
+                        } = &mut variant
+                        {
+                            optional.shift_remove(tag);
+                            required.insert(
+                                tag.clone(),
+                                SchemaState::String(StringType::Enum {
+                                    variants: std::iter::once(value.clone()).collect(),
+                                }),
+                            );
+                        }
+                        self.type_of(&variant, &format!("{}{}", name, pascal_case(value)), parent)
+                    })
+                    .collect();
+                let union = self.union(types);
+                self.fields = true;
+                format!(
+                    "{}[{}, Field(discriminator={})]",
+                    self.import("typing", "Annotated"),
+                    union,
+                    literal(&attribute_name(tag))
+                )
+            }
+        }
+    }
+}
+
+/// Renders a schema as Python classes of Pydantic models: a subclass of `BaseModel` for each
+/// object shape, named after the field it is in, and a type alias for the root, named `name`,
+/// unless it is an object itself. Classes come before the classes that refer to them. Optional
+/// and nullable fields are `Optional`, optional ones defaulting to `None`, and fields whose
+/// names aren't snake case have an alias of their name. Enums are `Literal`s of their values,
+/// and tagged unions are unions discriminated by their tag field. The values of a recursive
+/// definition within it are of its class, by a forward reference.
+pub(crate) fn to_pydantic(schema: &SchemaState, name: &str) -> String {
+    let mut declarations = Declarations {
+        classes: codegen::Declarations::new(RESERVED_NAMES),
+        imports: BTreeMap::new(),
+        fields: false,
+    };
+    let root = match schema {
+        SchemaState::Object { .. } => {
+            declarations.object(schema, name, "", None);
+            None
+        }
+        SchemaState::Recursive {
+            name: definition,
+            schema,
+        } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+            declarations.object(schema, name, "", Some(definition));
+            None
+        }
+        _ => Some(declarations.type_of(schema, name, "")),
+    };
+
+    let mut imports: Vec<String> = declarations
+        .imports
+        .iter()
+        .map(|(module, names)| {
+            let names: Vec<&str> = names.iter().copied().collect();
+            format!("from {} import {}", module, names.join(", "))
+        })
+        .collect();
+    if !imports.is_empty() {
+        imports.push(String::new());
+    }
+    imports.push(if declarations.fields {
+        "from pydantic import BaseModel, Field".to_string()
+    } else {
+        "from pydantic import BaseModel".to_string()
+    });
+    let mut output = vec![imports.join("\n")];
+    for (name, lines) in declarations.classes.into_finished() {
+        let body = if lines.is_empty() {
+            "    pass".to_string()
+        } else {
+            lines
+                .iter()
+                .map(|line| format!("    {}", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        output.push(format!("class {}(BaseModel):\n{}", name, body));
+    }
+    if let Some(root) = root.filter(|root| root != name) {
+        output.push(format!("{} = {}", name, root));
+    }
+    output.join("\n\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn declares_a_model_for_each_object_shape() {
+        let address = object(vec![("city", any_string())]);
+        let mut schema = object(vec![
+            (
+                "id",
+                SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+            ),
+            ("orderId", SchemaState::String(StringType::UUID)),
+            ("status", strings(&["open", "closed"])),
+            ("shipping_address", address.clone()),
+            ("from", SchemaState::Nullable(Box::new(address))),
+        ]);
+        if let SchemaState::Object { optional, .. } = &mut schema {
+            optional.insert(
+                "tags".to_string(),
+                array(SchemaState::String(StringType::IsoDate)),
+            );
+            optional.insert(
+                "line-items".to_string(),
+                array(object(vec![("sku", any_string())])),
+            );
+        }
+        assert_eq!(
+            to_pydantic(&array(schema), "Orders"),
+            r#"from datetime import date
+from typing import Literal, Optional
+from uuid import UUID
+
+from pydantic import BaseModel, Field
+
+
+class ShippingAddress(BaseModel):
+    city: str
+
+
+class LineItemsItem(BaseModel):
+    sku: str
+
+
+class OrdersItem(BaseModel):
+    id: int
+    order_id: UUID = Field(alias="orderId")
+    status: Literal["closed", "open"]
+    shipping_address: ShippingAddress
+    from_: Optional[ShippingAddress] = Field(alias="from")
+    tags: Optional[list[date]] = None
+    line_items: Optional[list[LineItemsItem]] = Field(default=None, alias="line-items")
+
+
+Orders = list[OrdersItem]"#
+        );
+    }
+
+    #[test]
+    fn discriminates_tagged_unions_by_their_tag() {
+        let click = object(vec![
+            ("type", strings(&["click"])),
+            (
+                "x",
+                SchemaState::Number(NumberType::Float {
+                    min: 0.0,
+                    max: 1.0,
+                    non_finite: Default::default(),
+                }),
+            ),
+        ]);
+        let schema = object(vec![
+            (
+                "event",
+                SchemaState::TaggedUnion {
+                    tag: "type".to_string(),
+                    variants: BTreeMap::from_iter([
+                        ("click".to_string(), click),
+                        ("key".to_string(), object(vec![("type", strings(&["key"]))])),
+                    ]),
+                },
+            ),
+            (
+                "value",
+                SchemaState::Union(vec![SchemaState::Boolean, SchemaState::Indefinite]),
+            ),
+            ("empty", object(vec![])),
+        ]);
+        assert_eq!(
+            to_pydantic(&schema, "Log"),
+            r#"from typing import Annotated, Any, Literal, Union
+
+from pydantic import BaseModel, Field
+
+
+class EventClick(BaseModel):
+    type: Literal["click"]
+    x: float
+
+
+class EventKey(BaseModel):
+    type: Literal["key"]
+
+
+class Empty(BaseModel):
+    pass
+
+
+class Log(BaseModel):
+    event: Annotated[Union[EventClick, EventKey], Field(discriminator="type")]
+    value: Union[bool, Any]
+    empty: Empty"#
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_classes_ahead() {
+        let tree = SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(object(vec![(
+                "next",
+                SchemaState::Nullable(Box::new(SchemaState::Reference("node".to_string()))),
+            )])),
+        };
+        assert_eq!(
+            to_pydantic(&tree, "Node"),
+            r#"from typing import Optional
+
+from pydantic import BaseModel
+
+
+class Node(BaseModel):
+    next: Optional["Node"]"#
+        );
+    }
+}
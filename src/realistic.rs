@@ -0,0 +1,271 @@
+use fake::faker::{address::raw::*, company::raw::*, internet::raw::*, job::raw::Title};
+use fake::faker::{name::raw::*, phone_number::raw::PhoneNumber};
+use fake::locales::{Data, AR_SA, EN, FR_FR, JA_JP, PT_BR, ZH_CN, ZH_TW};
+use fake::Fake;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::StringType;
+
+/// The locales that realistic values can be produced for, with
+/// [`ProduceOptions::realistic`](crate::ProduceOptions::realistic).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+pub enum Locale {
+    /// English, as in the United States.
+    #[default]
+    #[value(name = "en_US")]
+    #[serde(rename = "en_US")]
+    EnUs,
+    /// French, as in France.
+    #[value(name = "fr_FR")]
+    #[serde(rename = "fr_FR")]
+    FrFr,
+    /// Portuguese, as in Brazil.
+    #[value(name = "pt_BR")]
+    #[serde(rename = "pt_BR")]
+    PtBr,
+    /// Japanese, as in Japan.
+    #[value(name = "ja_JP")]
+    #[serde(rename = "ja_JP")]
+    JaJp,
+    /// Chinese, as in mainland China.
+    #[value(name = "zh_CN")]
+    #[serde(rename = "zh_CN")]
+    ZhCn,
+    /// Chinese, as in Taiwan.
+    #[value(name = "zh_TW")]
+    #[serde(rename = "zh_TW")]
+    ZhTw,
+    /// Arabic, as in Saudi Arabia.
+    #[value(name = "ar_SA")]
+    #[serde(rename = "ar_SA")]
+    ArSa,
+}
+
+/// What the name of a field says its strings are, for realistic values in their place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Semantic {
+    FirstName,
+    LastName,
+    FullName,
+    Username,
+    Email,
+    Phone,
+    Address,
+    Street,
+    City,
+    State,
+    Country,
+    CountryCode,
+    PostCode,
+    Company,
+    JobTitle,
+}
+
+/// A name without its case and separators, so that `firstName`, `first_name` and `First-Name`
+/// are all `firstname`.
+fn compact(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// The names of the fields that hold people, whose `name` is a person's name.
+const PEOPLE: &[&str] = &[
+    "",
+    "user",
+    "users",
+    "customer",
+    "customers",
+    "person",
+    "people",
+    "author",
+    "authors",
+    "owner",
+    "contact",
+    "contacts",
+    "employee",
+    "employees",
+    "member",
+    "members",
+    "patient",
+    "student",
+    "account",
+    "profile",
+    "sender",
+    "recipient",
+];
+
+/// The names of the fields that hold organisations, whose `name` is a company's name.
+const ORGANISATIONS: &[&str] = &[
+    "company",
+    "companies",
+    "organization",
+    "organisation",
+    "employer",
+    "business",
+    "vendor",
+    "supplier",
+];
+
+impl Semantic {
+    /// What the strings at a path are, by the name of the field and of its parent for names such
+    /// as `name` and `state` that mean different things in different places, if they are
+    /// strings without a format that says otherwise.
+    pub(crate) fn of(path: &str, string_type: &StringType) -> Option<Semantic> {
+        match string_type {
+            StringType::Email => return Some(Semantic::Email),
+            StringType::Unknown { .. } | StringType::Charset { .. } => {}
+            _ => return None,
+        }
+        let mut segments = path
+            .rsplit('.')
+            .map(|segment| compact(segment.trim_end_matches("[]")));
+        let field = segments.next()?;
+        let parent = segments.next().unwrap_or_default();
+        let semantic = match field.as_str() {
+            "firstname" | "givenname" | "forename" | "fname" => Semantic::FirstName,
+            "lastname" | "surname" | "familyname" | "lname" => Semantic::LastName,
+            "fullname" | "displayname" | "customername" | "contactname" | "authorname" => {
+                Semantic::FullName
+            }
+            "name" if ORGANISATIONS.contains(&parent.as_str()) => Semantic::Company,
+            "name" if PEOPLE.contains(&parent.as_str()) => Semantic::FullName,
+            "username" | "login" | "handle" | "screenname" | "nickname" => Semantic::Username,
+            "email" | "emailaddress" | "mail" | "email1" | "contactemail" => Semantic::Email,
+            "phone" | "phonenumber" | "telephone" | "tel" | "mobile" | "mobilephone" | "cell"
+            | "cellphone" | "fax" => Semantic::Phone,
+            "address" | "fulladdress" | "postaladdress" | "mailingaddress" => Semantic::Address,
+            "street" | "streetaddress" | "streetname" | "addressline1" | "address1" | "line1" => {
+                Semantic::Street
+            }
+            "city" | "town" | "cityname" => Semantic::City,
+            "province" | "statename" => Semantic::State,
+            "state" if parent.contains("address") || parent == "location" => Semantic::State,
+            "country" | "countryname" => Semantic::Country,
+            "countrycode" => Semantic::CountryCode,
+            "zip" | "zipcode" | "postcode" | "postalcode" => Semantic::PostCode,
+            "company" | "companyname" | "employer" | "organization" | "organisation" => {
+                Semantic::Company
+            }
+            "jobtitle" | "position" | "occupation" | "profession" => Semantic::JobTitle,
+            _ => return None,
+        };
+        Some(semantic)
+    }
+
+    /// A realistic value of this kind in a locale.
+    pub(crate) fn produce<R: Rng + ?Sized>(self, locale: Locale, rng: &mut R) -> String {
+        match locale {
+            Locale::EnUs => self.produce_in(EN, rng),
+            Locale::FrFr => self.produce_in(FR_FR, rng),
+            Locale::PtBr => self.produce_in(PT_BR, rng),
+            Locale::JaJp => self.produce_in(JA_JP, rng),
+            Locale::ZhCn => self.produce_in(ZH_CN, rng),
+            Locale::ZhTw => self.produce_in(ZH_TW, rng),
+            Locale::ArSa => self.produce_in(AR_SA, rng),
+        }
+    }
+
+    fn produce_in<L: Data + Copy, R: Rng + ?Sized>(self, l: L, rng: &mut R) -> String {
+        let street = |rng: &mut R| {
+            let number: String = BuildingNumber(l).fake_with_rng(rng);
+            let street: String = StreetName(l).fake_with_rng(rng);
+            format!("{} {}", number, street)
+        };
+        match self {
+            Semantic::FirstName => FirstName(l).fake_with_rng(rng),
+            Semantic::LastName => LastName(l).fake_with_rng(rng),
+            Semantic::FullName => Name(l).fake_with_rng(rng),
+            Semantic::Username => Username(l).fake_with_rng(rng),
+            Semantic::Email => SafeEmail(l).fake_with_rng(rng),
+            Semantic::Phone => PhoneNumber(l).fake_with_rng(rng),
+            Semantic::Address => {
+                let street = street(rng);
+                let city: String = CityName(l).fake_with_rng(rng);
+                let post_code: String = PostCode(l).fake_with_rng(rng);
+                format!("{}, {} {}", street, post_code, city)
+            }
+            Semantic::Street => street(rng),
+            Semantic::City => CityName(l).fake_with_rng(rng),
+            Semantic::State => StateName(l).fake_with_rng(rng),
+            Semantic::Country => CountryName(l).fake_with_rng(rng),
+            Semantic::CountryCode => CountryCode(l).fake_with_rng(rng),
+            Semantic::PostCode => PostCode(l).fake_with_rng(rng),
+            Semantic::Company => CompanyName(l).fake_with_rng(rng),
+            Semantic::JobTitle => Title(l).fake_with_rng(rng),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    fn unknown() -> StringType {
+        StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: Some(1),
+            max_length: Some(10),
+        }
+    }
+
+    #[test]
+    fn recognises_fields_by_their_names() {
+        let of = |path: &str| Semantic::of(path, &unknown());
+        assert_eq!(of("[].firstName"), Some(Semantic::FirstName));
+        assert_eq!(of("users[].last_name"), Some(Semantic::LastName));
+        assert_eq!(of("billing_address.Zip-Code"), Some(Semantic::PostCode));
+        assert_eq!(of("shippingAddress.state"), Some(Semantic::State));
+        assert_eq!(of("[].name"), Some(Semantic::FullName));
+        assert_eq!(of("author.name"), Some(Semantic::FullName));
+        assert_eq!(of("company.name"), Some(Semantic::Company));
+        assert_eq!(of("phones[]"), None);
+        assert_eq!(of("contact.phone"), Some(Semantic::Phone));
+        // names that mean something else elsewhere
+        assert_eq!(of("products[].name"), None);
+        assert_eq!(of("[].state"), None);
+        assert_eq!(of("[].id"), None);
+
+        // strings of another format are left alone, but emails are realistic wherever they are
+        assert_eq!(Semantic::of("[].city", &StringType::UUID), None);
+        assert_eq!(
+            Semantic::of("[].primary", &StringType::Email),
+            Some(Semantic::Email)
+        );
+    }
+
+    #[test]
+    fn produces_values_in_every_locale() {
+        let mut rng = rand::thread_rng();
+        for locale in Locale::value_variants() {
+            for semantic in [
+                Semantic::FirstName,
+                Semantic::LastName,
+                Semantic::FullName,
+                Semantic::Username,
+                Semantic::Email,
+                Semantic::Phone,
+                Semantic::Address,
+                Semantic::Street,
+                Semantic::City,
+                Semantic::State,
+                Semantic::Country,
+                Semantic::CountryCode,
+                Semantic::PostCode,
+                Semantic::Company,
+                Semantic::JobTitle,
+            ] {
+                assert!(!semantic.produce(*locale, &mut rng).is_empty());
+            }
+        }
+        assert!(Semantic::Email
+            .produce(Locale::EnUs, &mut rng)
+            .contains('@'));
+        let name = Semantic::FullName.produce(Locale::JaJp, &mut rng);
+        assert!(!name.is_ascii());
+    }
+}
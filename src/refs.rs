@@ -0,0 +1,369 @@
+use serde_json::{Map, Value};
+
+use crate::ParseSchemaError;
+
+/// Whether a JSON Schema refers to a definition anywhere with `$ref`.
+pub(crate) fn has_refs(schema: &Value) -> bool {
+    match schema {
+        Value::Object(schema_obj) => schema_obj
+            .iter()
+            .any(|(key, value)| key == "$ref" || has_refs(value)),
+        Value::Array(values) => values.iter().any(has_refs),
+        _ => false,
+    }
+}
+
+/// Whether a JSON Schema has a `$ref` to `reference` anywhere within it.
+pub(crate) fn refers_to(schema: &Value, reference: &Value) -> bool {
+    match schema {
+        Value::Object(schema_obj) => schema_obj.iter().any(|(key, value)| {
+            (key == "$ref" && value == reference) || refers_to(value, reference)
+        }),
+        Value::Array(values) => values.iter().any(|value| refers_to(value, reference)),
+        _ => false,
+    }
+}
+
+/// Replaces every local `$ref` of a JSON Schema, such as `#/$defs/address`,
+/// `#/definitions/address` or the anchor `#address`, with the schema it points at, so that the
+/// schema can be parsed as if it had been written inline. The keywords alongside a `$ref`, such
+/// as its `description`, are kept, overriding those of the definition.
+///
+/// A recursive reference, which is within the definition it points at, isn't expanded again:
+/// the definition gets an `$anchor` of a name unique within the schema, and the reference
+/// becomes a `$ref` to it, such as `#node`, which are parsed as a [`SchemaState::Recursive`]
+/// definition and a [`SchemaState::Reference`] to it.
+///
+/// [`SchemaState::Recursive`]: crate::SchemaState::Recursive
+/// [`SchemaState::Reference`]: crate::SchemaState::Reference
+pub(crate) fn resolve_refs(schema: &Value) -> Result<Value, ParseSchemaError> {
+    let mut resolver = Resolver {
+        root: schema,
+        expanding: vec![],
+        names: vec![],
+    };
+    resolver.resolve(schema)
+}
+
+/// A definition being expanded.
+struct Expansion<'a> {
+    /// The schema of the definition, within the document.
+    target: &'a Value,
+    /// What to name the definition after if it turns out to be recursive, such as the last
+    /// part of the `$ref` that pointed at it.
+    label: String,
+    /// The name of the definition, once a reference within it has pointed at it again.
+    name: Option<String>,
+}
+
+struct Resolver<'a> {
+    /// The document that `$ref`s point into.
+    root: &'a Value,
+    /// The definitions being expanded, innermost last.
+    expanding: Vec<Expansion<'a>>,
+    /// The names given to recursive definitions so far.
+    names: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    /// The schema with its `$ref`s resolved.
+    fn resolve(&mut self, schema: &'a Value) -> Result<Value, ParseSchemaError> {
+        let Value::Object(schema_obj) = schema else {
+            return Ok(schema.clone());
+        };
+        // a definition with an anchor may be pointed at from within itself without a `$ref`
+        // having led to it
+        let anchor = anchor_of(schema_obj).filter(|_| !self.is_expanding(schema));
+        if let Some(anchor) = anchor {
+            self.expanding.push(Expansion {
+                target: schema,
+                label: anchor.to_string(),
+                name: None,
+            });
+        }
+        let mut resolved = match schema_obj.get("$ref") {
+            Some(reference) => match self.resolve_ref(reference)? {
+                Value::Object(target) => target,
+                _ => {
+                    return Err(ParseSchemaError::InvalidSchema(format!(
+                        "$ref {} must point at a schema object",
+                        reference
+                    )))
+                }
+            },
+            None => Map::new(),
+        };
+
+        for (key, value) in schema_obj {
+            let value = match (key.as_str(), value) {
+                // definitions are only needed where they are referred to, and anchors are only
+                // kept for recursive definitions
+                ("$ref" | "$defs" | "definitions" | "$anchor", _) => continue,
+                ("$id", Value::String(id)) if id.starts_with('#') => continue,
+                ("properties" | "patternProperties", Value::Object(properties)) => {
+                    let mut resolved_properties = Map::new();
+                    for (name, property) in properties {
+                        resolved_properties.insert(name.clone(), self.resolve(property)?);
+                    }
+                    Value::Object(resolved_properties)
+                }
+                (
+                    "items" | "additionalProperties" | "not" | "contains",
+                    value @ Value::Object(_),
+                ) => self.resolve(value)?,
+                ("anyOf" | "oneOf" | "allOf" | "prefixItems", Value::Array(schemas)) => {
+                    let mut resolved_schemas = vec![];
+                    for schema in schemas {
+                        resolved_schemas.push(self.resolve(schema)?);
+                    }
+                    Value::Array(resolved_schemas)
+                }
+                (_, value) => value.clone(),
+            };
+            resolved.insert(key.clone(), value);
+        }
+        if anchor.is_some() {
+            self.finish(&mut resolved);
+        }
+        Ok(Value::Object(resolved))
+    }
+
+    fn is_expanding(&self, target: &Value) -> bool {
+        self.expanding
+            .iter()
+            .any(|expansion| std::ptr::eq(expansion.target, target))
+    }
+
+    /// Ends the innermost expansion, anchoring the definition if it turned out to be recursive.
+    fn finish(&mut self, resolved: &mut Map<String, Value>) {
+        let expansion = self.expanding.pop().expect("a definition being expanded");
+        if let Some(name) = expansion.name {
+            resolved.insert("$anchor".to_string(), Value::String(name));
+        }
+    }
+
+    /// The schema that a `$ref` points at, resolved in turn, or a `$ref` to the anchor of the
+    /// definition it points at if that is being expanded already.
+    fn resolve_ref(&mut self, reference: &Value) -> Result<Value, ParseSchemaError> {
+        let reference = reference
+            .as_str()
+            .ok_or_else(|| ParseSchemaError::InvalidSchema("$ref must be a string".to_string()))?;
+        let fragment = reference.strip_prefix('#').ok_or_else(|| {
+            ParseSchemaError::UnsupportedFeature(format!(
+                "$ref {} points outside of the schema; only local references such as \
+                 #/$defs/name are supported",
+                reference
+            ))
+        })?;
+        let target = match fragment {
+            "" => Some(self.root),
+            pointer if pointer.starts_with('/') => self.root.pointer(pointer),
+            anchor => find_anchor(self.root, anchor),
+        }
+        .ok_or_else(|| {
+            ParseSchemaError::InvalidSchema(format!("$ref {} points at nothing", reference))
+        })?;
+
+        if let Some(index) = self
+            .expanding
+            .iter()
+            .position(|expansion| std::ptr::eq(expansion.target, target))
+        {
+            let name = match &self.expanding[index].name {
+                Some(name) => name.clone(),
+                None => {
+                    let label = self.expanding[index].label.clone();
+                    let name = self.unique_name(&label);
+                    self.expanding[index].name = Some(name.clone());
+                    name
+                }
+            };
+            return Ok(serde_json::json!({ "$ref": format!("#{}", name) }));
+        }
+        let Value::Object(target_obj) = target else {
+            return Ok(target.clone());
+        };
+        let label = match anchor_of(target_obj) {
+            Some(anchor) => anchor.to_string(),
+            None => fragment.rsplit('/').next().unwrap_or_default().to_string(),
+        };
+        self.expanding.push(Expansion {
+            target,
+            label,
+            name: None,
+        });
+        // the target pushed above is recognised as being expanded, so it isn't pushed again
+        match self.resolve(target)? {
+            Value::Object(mut resolved) => {
+                self.finish(&mut resolved);
+                Ok(Value::Object(resolved))
+            }
+            resolved => {
+                self.expanding.pop();
+                Ok(resolved)
+            }
+        }
+    }
+
+    /// A name for a recursive definition, after `label`, that is a valid anchor and that no
+    /// other definition of the schema has.
+    fn unique_name(&mut self, label: &str) -> String {
+        let mut base: String = label
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect();
+        if !base.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            base = format!("_{}", base);
+        }
+        if base == "_" {
+            base = "root".to_string();
+        }
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.names.contains(&name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        self.names.push(name.clone());
+        name
+    }
+}
+
+/// The anchor of a schema, given by `$anchor`, or by an `$id` of just a fragment, such as
+/// `#node`, as drafts before 2019-09 spell it.
+fn anchor_of(schema_obj: &Map<String, Value>) -> Option<&str> {
+    match (schema_obj.get("$anchor"), schema_obj.get("$id")) {
+        (Some(Value::String(anchor)), _) => Some(anchor),
+        (_, Some(Value::String(id))) => id.strip_prefix('#').filter(|id| !id.is_empty()),
+        _ => None,
+    }
+}
+
+/// The schema within a document that has the given anchor, if any.
+fn find_anchor<'a>(schema: &'a Value, anchor: &str) -> Option<&'a Value> {
+    match schema {
+        Value::Object(schema_obj) if anchor_of(schema_obj) == Some(anchor) => Some(schema),
+        Value::Object(schema_obj) => schema_obj
+            .values()
+            .find_map(|value| find_anchor(value, anchor)),
+        Value::Array(values) => values.iter().find_map(|value| find_anchor(value, anchor)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn inlines_definitions() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "billing": {"$ref": "#/$defs/address", "description": "Where invoices go"},
+                "shipping": {"$ref": "#/definitions/address"}
+            },
+            "$defs": {"address": {"type": "object", "properties": {"city": {"type": "string"}}}},
+            "definitions": {"address": {"$ref": "#/$defs/address"}}
+        });
+
+        let address = json!({"type": "object", "properties": {"city": {"type": "string"}}});
+        let mut billing = address.clone();
+        billing["description"] = json!("Where invoices go");
+        assert_eq!(
+            resolve_refs(&schema).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {"billing": billing, "shipping": address}
+            })
+        );
+    }
+
+    #[test]
+    fn anchors_recursive_references() {
+        let schema = json!({
+            "$ref": "#/$defs/node",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "value": {"type": "integer"},
+                        "parent": {"$ref": "#/$defs/node"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}},
+                        "next": {"anyOf": [{"$ref": "#/$defs/node"}, {"type": "null"}]}
+                    },
+                    "required": ["value", "children", "next"]
+                }
+            }
+        });
+
+        let resolved = resolve_refs(&schema).unwrap();
+        assert_eq!(resolved["$anchor"], "node");
+        assert_eq!(resolved["properties"]["parent"], json!({"$ref": "#node"}));
+        assert_eq!(
+            resolved["properties"]["children"]["items"],
+            json!({"$ref": "#node"})
+        );
+        assert_eq!(
+            resolved["properties"]["next"]["anyOf"][0],
+            json!({"$ref": "#node"})
+        );
+        assert!(resolved.get("$defs").is_none());
+    }
+
+    #[test]
+    fn resolves_anchors_and_names_definitions_apart() {
+        // as drivel writes recursive definitions, with an anchor rather than in `$defs`
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "tree": {
+                    "$anchor": "node",
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#node"}}}
+                },
+                "copy": {"$ref": "#node"},
+                "unused": {"$anchor": "leaf", "type": "string"}
+            }
+        });
+
+        // each expansion of the definition is anchored by a name of its own, in the order in
+        // which they are reached
+        let resolved = resolve_refs(&schema).unwrap();
+        for (property, name) in [("tree", "node"), ("copy", "node2")] {
+            let tree = &resolved["properties"][property];
+            assert_eq!(tree["$anchor"], name);
+            assert_eq!(
+                tree["properties"]["children"]["items"],
+                json!({ "$ref": format!("#{}", name) })
+            );
+        }
+        assert_eq!(resolved["properties"]["unused"], json!({"type": "string"}));
+    }
+
+    #[test]
+    fn rejects_references_it_cannot_resolve() {
+        assert!(matches!(
+            resolve_refs(&json!({"$ref": "#/$defs/missing"})),
+            Err(ParseSchemaError::InvalidSchema(_))
+        ));
+        assert!(matches!(
+            resolve_refs(&json!({"$ref": "https://example.com/user.json"})),
+            Err(ParseSchemaError::UnsupportedFeature(_))
+        ));
+        // a schema that always contains itself has no values
+        let endless = json!({
+            "type": "object",
+            "properties": {"self": {"$ref": "#"}},
+            "required": ["self"]
+        });
+        assert!(matches!(
+            crate::parse_json_schema(&endless),
+            Err(ParseSchemaError::InvalidSchema(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+use crate::codegen::snake_case_words;
+use crate::produce_options::{field_path, items_path};
+use crate::{FieldOverride, NumberType, ProduceOptions, SchemaState};
+
+/// A field with a string or integer value, as the IDs of records and the fields that refer to
+/// them are.
+struct KeyField {
+    path: String,
+    /// The name of the field, in snake case.
+    name: String,
+    /// The name of the field of the object that has this field, such as `users` for
+    /// `users[].id`, in snake case.
+    parent: Option<String>,
+    integer: bool,
+}
+
+fn collect_key_fields(
+    schema: &SchemaState,
+    path: &str,
+    name: Option<&str>,
+    parent: Option<&str>,
+    fields: &mut Vec<KeyField>,
+) {
+    match schema {
+        SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+            collect_key_fields(inner, path, name, parent, fields)
+        }
+        SchemaState::Array { schema, .. } => {
+            collect_key_fields(schema, &items_path(path), name, parent, fields)
+        }
+        SchemaState::Object {
+            required, optional, ..
+        } => {
+            for (key, field) in required.iter().chain(optional) {
+                collect_key_fields(field, &field_path(path, key), Some(key), name, fields);
+            }
+        }
+        SchemaState::String(_) | SchemaState::Number(NumberType::Integer { .. }) => {
+            if let Some(name) = name {
+                fields.push(KeyField {
+                    path: path.to_string(),
+                    name: snake_case_words(name),
+                    parent: parent.map(snake_case_words),
+                    integer: matches!(schema, SchemaState::Number(_)),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether the records under a field of this name, such as `users` or `user`, are of an entity,
+/// such as `user`.
+fn names_entity(records: &str, entity: &str) -> bool {
+    let stem = entity.strip_suffix('y').unwrap_or(entity);
+    records == entity
+        || records.strip_suffix('s') == Some(entity)
+        || records.strip_suffix("es") == Some(entity)
+        || (entity.ends_with('y') && records.strip_suffix("ies") == Some(stem))
+}
+
+impl SchemaState {
+    /// Returns the fields that look like they refer to the IDs of other records, by the paths of
+    /// the fields and those they refer to. A field named after an entity and `id`, such as
+    /// `user_id` or `userId`, refers to the `id` of the objects under a field named after the
+    /// entity, such as `users[].id` or `user.id`, if both are strings or both integers. Where
+    /// several fields fit, the one with the shortest path wins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions};
+    /// use serde_json::json;
+    ///
+    /// let input = json!({
+    ///     "users": [{"id": 1}, {"id": 2}],
+    ///     "orders": [{"id": 10, "userId": 2}],
+    /// });
+    /// let schema = infer_schema(input, &InferenceOptions::default());
+    ///
+    /// let references = schema.references();
+    /// assert_eq!(references["orders[].userId"], "users[].id");
+    /// assert_eq!(references.len(), 1);
+    /// ```
+    pub fn references(&self) -> BTreeMap<String, String> {
+        let mut fields = vec![];
+        collect_key_fields(self, "", None, None, &mut fields);
+        let mut ids: Vec<&KeyField> = fields
+            .iter()
+            .filter(|field| field.name == "id" && field.parent.is_some())
+            .collect();
+        ids.sort_by(|a, b| (a.path.len(), &a.path).cmp(&(b.path.len(), &b.path)));
+
+        fields
+            .iter()
+            .filter_map(|field| {
+                let entity = field.name.strip_suffix("_id").filter(|e| !e.is_empty())?;
+                let id = ids.iter().find(|id| {
+                    id.integer == field.integer
+                        && id.path != field.path
+                        && names_entity(id.parent.as_deref().unwrap_or_default(), entity)
+                })?;
+                Some((field.path.clone(), id.path.clone()))
+            })
+            .collect()
+    }
+}
+
+impl ProduceOptions {
+    /// Makes the fields of a schema that look like they refer to the IDs of other records, as
+    /// found by [`SchemaState::references`], produce the values produced for those IDs, unless
+    /// they already have an override, transform or generator. Returns the references added, by
+    /// the paths of the fields and those they refer to.
+    pub fn infer_references(&mut self, schema: &SchemaState) -> BTreeMap<String, String> {
+        let mut references = schema.references();
+        references.retain(|path, _| {
+            !self.fields.contains_key(path)
+                && !self.transforms.contains_key(path)
+                && self.generators.for_field(path).is_none()
+        });
+        for (path, referenced) in &references {
+            self.fields.insert(
+                path.clone(),
+                FieldOverride {
+                    references: Some(referenced.clone()),
+                    ..Default::default()
+                },
+            );
+        }
+        references
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{infer_schema, InferenceOptions, ProduceOptions};
+    use serde_json::json;
+
+    #[test]
+    fn finds_fields_named_after_the_records_they_refer_to() {
+        let input = json!({
+            "users": [{"id": 1, "name": "a"}],
+            "categories": [{"id": "c1"}],
+            "author": {"id": 7},
+            "posts": [{
+                "id": 3,
+                "user_id": 1,
+                "CategoryID": "c1",
+                "authorId": 7,
+                "session_id": 4,
+                "reviewer": {"user_id": "u1"},
+            }],
+        });
+        let schema = infer_schema(input, &InferenceOptions::default());
+
+        let references = schema.references();
+        let references: Vec<(&str, &str)> = references
+            .iter()
+            .map(|(path, referenced)| (path.as_str(), referenced.as_str()))
+            .collect();
+        // there are no sessions, and the reviewer's user ID is a string while those of users
+        // are integers
+        assert_eq!(
+            references,
+            vec![
+                ("posts[].CategoryID", "categories[].id"),
+                ("posts[].authorId", "author.id"),
+                ("posts[].user_id", "users[].id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn inferred_references_leave_overridden_fields_alone() {
+        let input = json!({
+            "users": [{"id": 1}],
+            "teams": [{"id": 2}],
+            "orders": [{"user_id": 1, "team_id": 2}],
+        });
+        let schema = infer_schema(input, &InferenceOptions::default());
+        let mut options: ProduceOptions =
+            serde_json::from_value(json!({"fields": {"orders[].team_id": {"value": 5}}})).unwrap();
+
+        let added = options.infer_references(&schema);
+
+        assert_eq!(added.len(), 1);
+        assert_eq!(
+            options.fields["orders[].user_id"].references.as_deref(),
+            Some("users[].id")
+        );
+        assert_eq!(options.fields["orders[].team_id"].references, None);
+    }
+}
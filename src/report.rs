@@ -0,0 +1,425 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::{
+    infer_schema,
+    produce_options::{field_path, items_path},
+    InferenceOptions, SchemaState, StringType,
+};
+
+/// A judgment call made while inferring a schema, where the values seen didn't fit a schema
+/// exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferenceWarning {
+    /// The path of the value the warning is about, as used by
+    /// [`ProduceOptions`](crate::ProduceOptions), such as `orders[].total`. The root is the empty
+    /// path.
+    pub path: String,
+    pub kind: InferenceWarningKind,
+}
+
+/// The kinds of judgment calls that inference makes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferenceWarningKind {
+    /// Values of incompatible types were seen, such as numbers and strings, so the schema doesn't
+    /// describe all of them. The types are named as in JSON Schema, with integers and floats both
+    /// being numbers.
+    ConflictingTypes { types: Vec<String> },
+    /// Objects with too many fields were treated as a map, with a single schema for the values of
+    /// all fields. `fields` is the number of distinct fields seen in the objects.
+    TreatedAsMap { fields: usize },
+    /// Values that only differ in case or surrounding whitespace were collapsed into the same
+    /// variants of an enum.
+    NormalizedEnum { spellings: usize, variants: usize },
+    /// Values outside of the most common ones were treated as other strings of an open enum.
+    OpenEnum { variants: usize, other_ratio: f64 },
+    /// Objects or arrays were nested too deeply, or had too many keys, to be looked into, and
+    /// are of any value. `type_name` is `object` or `array`.
+    Opaque { type_name: String },
+}
+
+impl fmt::Display for InferenceWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "at the root: ")?;
+        } else {
+            write!(f, "'{}': ", self.path)?;
+        }
+        match &self.kind {
+            InferenceWarningKind::ConflictingTypes { types } => write!(
+                f,
+                "values of several types were seen ({}), which the schema doesn't all describe",
+                types.join(", ")
+            ),
+            InferenceWarningKind::TreatedAsMap { fields } => write!(
+                f,
+                "objects with {} distinct fields were treated as a map",
+                fields
+            ),
+            InferenceWarningKind::NormalizedEnum {
+                spellings,
+                variants,
+            } => write!(
+                f,
+                "{} distinct values were collapsed into {} enum variant(s)",
+                spellings, variants
+            ),
+            InferenceWarningKind::OpenEnum {
+                variants,
+                other_ratio,
+            } => write!(
+                f,
+                "{:.1}% of values were outside of the {} enum variants, and treated as other strings",
+                other_ratio * 100.0,
+                variants
+            ),
+            InferenceWarningKind::Opaque { type_name } => write!(
+                f,
+                "{}s were nested too deeply or had too many keys to be inferred, and are of any value",
+                type_name
+            ),
+        }
+    }
+}
+
+fn type_name(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some("boolean"),
+        Value::Number(_) => Some("number"),
+        Value::String(_) => Some("string"),
+        Value::Array(_) => Some("array"),
+        Value::Object(_) => Some("object"),
+    }
+}
+
+/// Collects what is needed to report the judgment calls made while inferring a schema from some
+/// values, which are added as they are observed by inference.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_schema_from_iter, InferenceOptions, InferenceReport, InferenceWarningKind};
+/// use serde_json::json;
+///
+/// # let options = InferenceOptions::default();
+/// let records = vec![json!({"id": 1}), json!({"id": "2"})];
+/// let mut report = InferenceReport::default();
+/// records.iter().for_each(|record| report.add(record));
+/// let schema = infer_schema_from_iter(records, &options);
+///
+/// let warnings = report.warnings(&schema);
+/// assert_eq!(warnings[0].path, "id");
+/// assert!(matches!(warnings[0].kind, InferenceWarningKind::ConflictingTypes { .. }));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct InferenceReport {
+    /// The types of the non-null values seen at each path.
+    types: HashMap<String, BTreeSet<&'static str>>,
+    /// The distinct strings seen at each path.
+    strings: HashMap<String, HashSet<String>>,
+}
+
+impl InferenceReport {
+    /// Adds a value, at the root of the data. Values that are all inferred as one schema, such as
+    /// JSON lines, are added one by one.
+    pub fn add(&mut self, value: &Value) {
+        self.add_at(value, "");
+    }
+
+    fn add_at(&mut self, value: &Value, path: &str) {
+        if let Some(type_name) = type_name(value) {
+            self.types
+                .entry(path.to_string())
+                .or_default()
+                .insert(type_name);
+        }
+        match value {
+            Value::String(s) => {
+                self.strings
+                    .entry(path.to_string())
+                    .or_default()
+                    .insert(s.clone());
+            }
+            Value::Array(items) => {
+                let path = items_path(path);
+                for item in items {
+                    self.add_at(item, &path);
+                }
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    self.add_at(field, &field_path(path, key));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The judgment calls made in inferring `schema` from the values added, ordered by path.
+    pub fn warnings(&self, schema: &SchemaState) -> Vec<InferenceWarning> {
+        let mut warnings: BTreeMap<String, Vec<InferenceWarningKind>> = BTreeMap::new();
+        for (path, types) in &self.types {
+            if types.len() > 1 {
+                warnings.entry(path.clone()).or_default().push(
+                    InferenceWarningKind::ConflictingTypes {
+                        types: types.iter().map(|t| t.to_string()).collect(),
+                    },
+                );
+            }
+        }
+        self.collect_schema_warnings(schema, "", &mut warnings);
+        warnings
+            .into_iter()
+            .flat_map(|(path, kinds)| {
+                kinds.into_iter().map(move |kind| InferenceWarning {
+                    path: path.clone(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    fn collect_schema_warnings(
+        &self,
+        schema: &SchemaState,
+        path: &str,
+        warnings: &mut BTreeMap<String, Vec<InferenceWarningKind>>,
+    ) {
+        let mut warn = |kind| warnings.entry(path.to_string()).or_default().push(kind);
+        match schema {
+            SchemaState::String(StringType::Enum { variants }) => {
+                let spellings = self.strings.get(path).map_or(0, HashSet::len);
+                // an enum of a vocabulary has more variants than values seen, not fewer
+                if spellings > variants.len() {
+                    warn(InferenceWarningKind::NormalizedEnum {
+                        spellings,
+                        variants: variants.len(),
+                    });
+                }
+            }
+            SchemaState::String(StringType::OpenEnum {
+                variants,
+                other_ratio,
+                ..
+            }) => warn(InferenceWarningKind::OpenEnum {
+                variants: variants.len(),
+                other_ratio: *other_ratio,
+            }),
+            SchemaState::Indefinite => {
+                // values of several types are already reported as conflicting
+                if let Some([type_name @ ("object" | "array")]) = self
+                    .types
+                    .get(path)
+                    .map(|types| types.iter().copied().collect::<Vec<_>>())
+                    .as_deref()
+                {
+                    warn(InferenceWarningKind::Opaque {
+                        type_name: type_name.to_string(),
+                    });
+                }
+            }
+            SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+                self.collect_schema_warnings(inner, path, warnings)
+            }
+            SchemaState::Array { schema, .. } => {
+                self.collect_schema_warnings(schema, &items_path(path), warnings)
+            }
+            SchemaState::Tuple(positions) => {
+                for position in positions {
+                    self.collect_schema_warnings(position, &items_path(path), warnings);
+                }
+            }
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                for (key, field) in required.iter().chain(optional) {
+                    self.collect_schema_warnings(field, &field_path(path, key), warnings);
+                }
+            }
+            SchemaState::Map {
+                max_length, schema, ..
+            } => {
+                warn(InferenceWarningKind::TreatedAsMap {
+                    fields: *max_length,
+                });
+                self.collect_schema_warnings(schema, &field_path(path, "*"), warnings);
+            }
+            SchemaState::Union(variants) => {
+                for variant in variants {
+                    self.collect_schema_warnings(variant, path, warnings);
+                }
+            }
+            SchemaState::Mixed(variants) => {
+                // the schema describes values of each type seen, so they don't conflict
+                if let Some(kinds) = warnings.get_mut(path) {
+                    kinds.retain(|kind| {
+                        !matches!(kind, InferenceWarningKind::ConflictingTypes { .. })
+                    });
+                }
+                for (variant, _) in variants {
+                    self.collect_schema_warnings(variant, path, warnings);
+                }
+            }
+            SchemaState::TaggedUnion { variants, .. } => {
+                for variant in variants.values() {
+                    self.collect_schema_warnings(variant, path, warnings);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Infers the schema of a JSON value like [`infer_schema`], along with the judgment calls made
+/// in doing so, such as treating objects with many fields as maps.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_schema_with_report, InferenceOptions};
+/// use serde_json::json;
+///
+/// # let options = InferenceOptions::default();
+/// let (_, warnings) = infer_schema_with_report(json!([{"id": 1}, {"id": [1]}]), &options);
+/// assert_eq!(
+///     warnings[0].to_string(),
+///     "'[].id': values of several types were seen (array, number), which the schema doesn't all describe"
+/// );
+/// ```
+pub fn infer_schema_with_report(
+    json: Value,
+    options: &InferenceOptions,
+) -> (SchemaState, Vec<InferenceWarning>) {
+    let mut report = InferenceReport::default();
+    report.add(&json);
+    let schema = infer_schema(json, options);
+    let warnings = report.warnings(&schema);
+    (schema, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EnumInference;
+    use serde_json::json;
+
+    fn options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    #[test]
+    fn reports_nothing_for_consistent_values() {
+        let input = json!([{"id": 1, "score": 1.5, "name": null}, {"id": 2, "score": 2}]);
+        let (_, warnings) = infer_schema_with_report(input, &options());
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn reports_conflicting_types_and_maps() {
+        let input = json!([
+            {"id": 1, "tags": {"a": 1, "b": 2, "c": 3}},
+            {"id": "2", "tags": {"d": 4}}
+        ]);
+        let options = InferenceOptions {
+            max_object_fields: Some(2),
+            ..options()
+        };
+        let (_, warnings) = infer_schema_with_report(input, &options);
+        assert_eq!(
+            warnings,
+            vec![
+                InferenceWarning {
+                    path: "[].id".to_string(),
+                    kind: InferenceWarningKind::ConflictingTypes {
+                        types: vec!["number".to_string(), "string".to_string()]
+                    },
+                },
+                InferenceWarning {
+                    path: "[].tags".to_string(),
+                    kind: InferenceWarningKind::TreatedAsMap { fields: 4 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_opaque_values() {
+        let input = json!([{"id": 1, "a": {"b": [1]}}, {"id": 2, "a": {"b": []}}]);
+        let options = InferenceOptions {
+            max_depth: Some(2),
+            ..options()
+        };
+        let (_, warnings) = infer_schema_with_report(input, &options);
+        assert_eq!(
+            warnings,
+            vec![InferenceWarning {
+                path: "[].a".to_string(),
+                kind: InferenceWarningKind::Opaque {
+                    type_name: "object".to_string()
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_collapsed_enums() {
+        let options = |normalize, open_tail_coverage| InferenceOptions {
+            enum_inference: Some(EnumInference {
+                max_unique_ratio: 0.1,
+                max_values: None,
+                min_sample_size: 1,
+                include_numeric: false,
+                normalize,
+                open_tail_coverage,
+            }),
+            ..options()
+        };
+
+        let mut statuses = vec!["active"; 8];
+        statuses.extend(["Active", " ACTIVE"]);
+        let (_, warnings) = infer_schema_with_report(json!(statuses), &options(true, None));
+        assert_eq!(
+            warnings[0].kind,
+            InferenceWarningKind::NormalizedEnum {
+                spellings: 3,
+                variants: 1
+            }
+        );
+
+        let mut countries = vec!["NL"; 19];
+        countries.extend(["BE", "DE", "FR", "LU", "AT", "CH"]);
+        let (_, warnings) = infer_schema_with_report(json!(countries), &options(false, Some(0.7)));
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0].kind,
+            InferenceWarningKind::OpenEnum { variants: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::BTreeSet;
+
+use crate::codegen::{self, any_string, object, pascal_case, snake_case_words, unique};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// The traits that every generated type derives.
+const DERIVE: &str = "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]";
+
+/// Names of types that the generated code uses, which its own types mustn't shadow.
+const RESERVED_NAMES: &[&str] = &[
+    "Box",
+    "Deserialize",
+    "HashMap",
+    "Option",
+    "Result",
+    "Serialize",
+    "Self",
+    "String",
+    "Value",
+    "Vec",
+];
+
+/// Keywords, which can't be used as field names, including those reserved for future use.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "union", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Turns a field name into the name of a struct field, in snake case, with keywords suffixed
+/// with `_`.
+fn snake_case(name: &str) -> String {
+    let mut snake = snake_case_words(name);
+    if KEYWORDS.contains(&snake.as_str()) {
+        snake.push('_');
+    }
+    snake
+}
+
+fn rename(key: &str) -> String {
+    format!("#[serde(rename = {})]", serde_json::to_string(key).unwrap())
+}
+
+fn number_type(number: &NumberType) -> &'static str {
+    match number {
+        NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } => "f64",
+        _ => "i64",
+    }
+}
+
+/// The name of a variant of an untagged union, after the kind of its values.
+fn variant_name(schema: &SchemaState) -> &'static str {
+    match schema {
+        SchemaState::Nullable(inner) => variant_name(inner),
+        SchemaState::String(_) => "String",
+        SchemaState::Number(
+            NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+        ) => "Float",
+        SchemaState::Number(_) => "Integer",
+        SchemaState::Boolean => "Boolean",
+        SchemaState::Array { .. } | SchemaState::Coordinates(_) => "Array",
+        SchemaState::Map { .. } => "Map",
+        SchemaState::Null => "Null",
+        _ => "Object",
+    }
+}
+
+/// A struct or enum declaration, without its name.
+#[derive(PartialEq)]
+struct Declaration {
+    /// The attributes of the declaration, besides the derived traits.
+    attributes: Vec<String>,
+    /// `struct` or `enum`.
+    keyword: &'static str,
+    /// The lines of its fields or variants, with their attributes.
+    lines: Vec<String>,
+}
+
+/// The declarations of Rust output, a struct for each object shape and an enum for each enum
+/// and union.
+struct Declarations {
+    /// The declaration of each type.
+    types: codegen::Declarations<Declaration>,
+    /// Whether any map was reached, for which `HashMap` is imported.
+    maps: bool,
+}
+
+impl Declarations {
+    /// Declares a struct for an object, with a field for each of its fields. Optional fields,
+    /// like nullable ones, are `Option`s, which are left out when serialized if they are `None`.
+    /// The struct of a recursive definition, `recursive`, is named before its fields are
+    /// rendered, so that the values of it within it can refer to it.
+    fn object(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> String {
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            unreachable!("only objects are declared as structs")
+        };
+        let index = self.types.start();
+        if let Some(definition) = recursive {
+            self.types.name(index, definition, name, parent);
+        }
+        let keys: Vec<(&String, bool)> = required
+            .keys()
+            .map(|key| (key, false))
+            .chain(optional.keys().map(|key| (key, true)))
+            .collect();
+        let field_names = unique(keys.iter().map(|(key, _)| snake_case(key)));
+        let mut lines = vec![];
+        for ((key, is_optional), field_name) in keys.into_iter().zip(field_names) {
+            let field = if is_optional {
+                &optional[key]
+            } else {
+                &required[key]
+            };
+            let mut field_type = self.type_of(field, &pascal_case(key), name);
+            if field_name != *key {
+                lines.push(rename(key));
+            }
+            if is_optional {
+                if !matches!(field, SchemaState::Nullable(_)) {
+                    field_type = format!("Option<{}>", field_type);
+                }
+                lines.push(
+                    "#[serde(default, skip_serializing_if = \"Option::is_none\")]".to_string(),
+                );
+            }
+            lines.push(format!("pub {}: {},", field_name, field_type));
+        }
+        let declaration = Declaration {
+            attributes: vec![],
+            keyword: "struct",
+            lines,
+        };
+        self.types.finish(index, declaration, name, parent)
+    }
+
+    /// Declares an enum of the given strings, with a variant for each.
+    fn string_enum(&mut self, variants: &BTreeSet<String>, name: &str, parent: &str) -> String {
+        let index = self.types.start();
+        let names = unique(variants.iter().map(|variant| pascal_case(variant)));
+        let mut lines = vec![];
+        for (variant, variant_name) in variants.iter().zip(names) {
+            if variant_name != *variant {
+                lines.push(rename(variant));
+            }
+            lines.push(format!("{},", variant_name));
+        }
+        let declaration = Declaration {
+            attributes: vec![],
+            keyword: "enum",
+            lines,
+        };
+        self.types.finish(index, declaration, name, parent)
+    }
+
+    /// Declares an untagged enum for a union, with a variant for each of its variants, named
+    /// after the kind of its values.
+    fn union(&mut self, variants: &[SchemaState], name: &str, parent: &str) -> String {
+        let index = self.types.start();
+        let names = unique(
+            variants
+                .iter()
+                .map(|variant| variant_name(variant).to_string()),
+        );
+        let mut lines = vec![];
+        for (variant, variant_name) in variants.iter().zip(names) {
+            let variant_type = self.type_of(variant, &format!("{}{}", name, variant_name), name);
+            lines.push(format!("{}({}),", variant_name, variant_type));
+        }
+        let declaration = Declaration {
+            attributes: vec!["#[serde(untagged)]".to_string()],
+            keyword: "enum",
+            lines,
+        };
+        self.types.finish(index, declaration, name, parent)
+    }
+
+    /// Declares an internally tagged enum for a tagged union, with a variant for each value of
+    /// the tag, holding a struct of the other fields of the objects with that value.
+    fn tagged_union(
+        &mut self,
+        tag: &str,
+        variants: &std::collections::BTreeMap<String, SchemaState>,
+        name: &str,
+        parent: &str,
+    ) -> String {
+        let index = self.types.start();
+        let names = unique(variants.keys().map(|value| pascal_case(value)));
+        let mut lines = vec![];
+        for ((value, variant), variant_name) in variants.iter().zip(names) {
+            if variant_name != *value {
+                lines.push(rename(value));
+            }
+            let mut variant = variant.clone();
+            if let SchemaState::Object {
+                required, optional, ..
+            } = &mut variant
+            {
+                required.shift_remove(tag);
+                optional.shift_remove(tag);
+                if required.is_empty() && optional.is_empty() {
+                    lines.push(format!("{},", variant_name));
+                    continue;
+                }
+            }
+            let variant_type = self.type_of(&variant, &format!("{}{}", name, variant_name), name);
+            lines.push(format!("{}({}),", variant_name, variant_type));
+        }
+        let declaration = Declaration {
+            attributes: vec![format!(
+                "#[serde(tag = {})]",
+                serde_json::to_string(tag).unwrap()
+            )],
+            keyword: "enum",
+            lines,
+        };
+        self.types.finish(index, declaration, name, parent)
+    }
+
+    /// The Rust type of the values of a schema, declaring structs and enums for the objects,
+    /// enums and unions among them, named after `name`, the name of the field they are in as a
+    /// type.
+    fn type_of(&mut self, schema: &SchemaState, name: &str, parent: &str) -> String {
+        match schema {
+            SchemaState::Initial | SchemaState::Indefinite => "serde_json::Value".to_string(),
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                self.object(schema, name, parent, Some(definition))
+            }
+            SchemaState::Recursive { schema, .. } => self.type_of(schema, name, parent),
+            // boxed, as a struct can't hold itself; only objects are declared, so another
+            // recursive definition can't be referred to
+            SchemaState::Reference(definition) => self.types.recursive(definition).map_or_else(
+                || "serde_json::Value".to_string(),
+                |declared| format!("Box<{}>", declared),
+            ),
+            SchemaState::Null => "()".to_string(),
+            SchemaState::Nullable(inner) => {
+                format!("Option<{}>", self.type_of(inner, name, parent))
+            }
+            SchemaState::Literal(values) => {
+                self.type_of(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::String(StringType::Enum { variants }) => {
+                self.string_enum(variants, name, parent)
+            }
+            SchemaState::String(_) => "String".to_string(),
+            SchemaState::Number(number) => number_type(number).to_string(),
+            SchemaState::Boolean => "bool".to_string(),
+            SchemaState::Array { schema, .. } => format!(
+                "Vec<{}>",
+                self.type_of(schema, &format!("{}Item", name), parent)
+            ),
+            SchemaState::Object { .. } => self.object(schema, name, parent, None),
+            SchemaState::Map { schema, .. } => {
+                self.maps = true;
+                format!(
+                    "HashMap<String, {}>",
+                    self.type_of(schema, &format!("{}Value", name), parent)
+                )
+            }
+            SchemaState::ExtendedJson(extended) => {
+                let key = match extended {
+                    ExtendedJsonType::ObjectId => "$oid",
+                    ExtendedJsonType::Date => "$date",
+                    ExtendedJsonType::Long { .. } => "$numberLong",
+                };
+                self.object(&object(vec![(key, any_string())]), name, parent, None)
+            }
+            SchemaState::Coordinates(positions) => format!(
+                "({})",
+                positions
+                    .iter()
+                    .map(number_type)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            // serde reads and writes tuples as arrays
+            SchemaState::Tuple(positions) => {
+                let positions: Vec<String> = positions
+                    .iter()
+                    .map(|position| self.type_of(position, &format!("{}Item", name), parent))
+                    .collect();
+                format!("({})", positions.join(", "))
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            } => self.object(
+                &object(vec![
+                    (latitude_key, SchemaState::Number(latitude.clone())),
+                    (longitude_key, SchemaState::Number(longitude.clone())),
+                ]),
+                name,
+                parent,
+                None,
+            ),
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => self.object(
+                &object(vec![
+                    (amount_key, SchemaState::Number(amount.clone())),
+                    (
+                        currency_key,
+                        SchemaState::String(StringType::Enum {
+                            variants: currencies.clone(),
+                        }),
+                    ),
+                ]),
+                name,
+                parent,
+                None,
+            ),
+            SchemaState::Union(variants) => self.union(variants, name, parent),
+            SchemaState::Mixed(variants) => {
+                let variants: Vec<_> = variants
+                    .iter()
+                    .map(|(variant, _)| variant.clone())
+                    .collect();
+                self.union(&variants, name, parent)
+            }
+            SchemaState::TaggedUnion { tag, variants } => {
+                self.tagged_union(tag, variants, name, parent)
+            }
+        }
+    }
+}
+
+/// Renders a schema as Rust type definitions that derive serde's `Serialize` and
+/// `Deserialize`: a struct for each object shape, named after the field it is in, an enum for
+/// each enum of strings, and a type alias for the root, named `name`, unless it is an object
+/// itself. Optional and nullable fields are `Option`s, and fields whose names aren't snake case
+/// are renamed. Unions are untagged enums, and tagged unions are enums tagged by their tag
+/// field, with a variant for each of its values. The values of a recursive definition within
+/// it are boxed values of its struct.
+pub(crate) fn to_rust(schema: &SchemaState, name: &str) -> String {
+    let mut declarations = Declarations {
+        types: codegen::Declarations::new(RESERVED_NAMES),
+        maps: false,
+    };
+    let root = match schema {
+        SchemaState::Object { .. } => {
+            declarations.object(schema, name, "", None);
+            None
+        }
+        SchemaState::Recursive {
+            name: definition,
+            schema,
+        } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+            declarations.object(schema, name, "", Some(definition));
+            None
+        }
+        _ => Some(declarations.type_of(schema, name, "")),
+    };
+    let mut output = vec!["use serde::{Deserialize, Serialize};".to_string()];
+    if declarations.maps {
+        output[0].push_str("\nuse std::collections::HashMap;");
+    }
+    if let Some(root) = root.filter(|root| root != name) {
+        output.push(format!("pub type {} = {};", name, root));
+    }
+    for (name, declaration) in declarations.types.into_reached() {
+        let mut lines = vec![DERIVE.to_string()];
+        lines.extend(declaration.attributes);
+        lines.push(format!("pub {} {} {{", declaration.keyword, name));
+        lines.extend(declaration.lines.iter().map(|line| format!("    {}", line)));
+        lines.push("}".to_string());
+        output.push(lines.join("\n"));
+    }
+    output.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn names_fields_and_types_after_json_keys() {
+        assert_eq!(snake_case("userId"), "user_id");
+        assert_eq!(snake_case("HTTPStatus"), "http_status");
+        assert_eq!(snake_case("billing-address"), "billing_address");
+        assert_eq!(snake_case("$oid"), "oid");
+        assert_eq!(snake_case("1st"), "field_1st");
+        assert_eq!(snake_case("type"), "type_");
+
+        let mut schema = object(vec![
+            (
+                "id",
+                SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+            ),
+            ("orderStatus", strings(&["open", "on-hold"])),
+            (
+                "lines",
+                array(object(vec![(
+                    "price",
+                    SchemaState::Number(NumberType::Float {
+                        min: 0.5,
+                        max: 1.5,
+                        non_finite: Default::default(),
+                    }),
+                )])),
+            ),
+            ("string", object(vec![("type", any_string())])),
+        ]);
+        if let SchemaState::Object { optional, .. } = &mut schema {
+            optional.insert(
+                "note".to_string(),
+                SchemaState::Nullable(Box::new(any_string())),
+            );
+            optional.insert(
+                "counts".to_string(),
+                SchemaState::Map {
+                    min_length: 1,
+                    max_length: 1,
+                    keys: StringType::UUID,
+                    schema: Box::new(SchemaState::Boolean),
+                },
+            );
+        }
+        assert_eq!(
+            to_rust(&schema, "Order"),
+            r#"use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: i64,
+    #[serde(rename = "orderStatus")]
+    pub order_status: OrderStatus,
+    pub lines: Vec<LinesItem>,
+    pub string: OrderString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counts: Option<HashMap<String, bool>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    #[serde(rename = "on-hold")]
+    OnHold,
+    #[serde(rename = "open")]
+    Open,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinesItem {
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderString {
+    #[serde(rename = "type")]
+    pub type_: String,
+}"#
+        );
+    }
+
+    #[test]
+    fn declares_unions_as_enums() {
+        let click = object(vec![
+            ("type", strings(&["click"])),
+            (
+                "x",
+                SchemaState::Number(NumberType::Integer { min: 0, max: 1 }),
+            ),
+        ]);
+        let schema = array(object(vec![
+            (
+                "event",
+                SchemaState::TaggedUnion {
+                    tag: "type".to_string(),
+                    variants: BTreeMap::from_iter([
+                        ("click".to_string(), click),
+                        (
+                            "key-press".to_string(),
+                            object(vec![("type", strings(&["key-press"]))]),
+                        ),
+                    ]),
+                },
+            ),
+            (
+                "value",
+                SchemaState::Union(vec![
+                    SchemaState::Boolean,
+                    object(vec![("x", SchemaState::Boolean)]),
+                ]),
+            ),
+            (
+                "at",
+                SchemaState::Coordinates(vec![
+                    NumberType::Integer { min: 0, max: 1 },
+                    NumberType::Integer { min: 0, max: 1 },
+                ]),
+            ),
+        ]));
+        assert_eq!(
+            to_rust(&schema, "Log"),
+            r#"use serde::{Deserialize, Serialize};
+
+pub type Log = Vec<LogItem>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogItem {
+    pub event: Event,
+    pub value: LogItemValue,
+    pub at: (i64, i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    #[serde(rename = "click")]
+    Click(EventClick),
+    #[serde(rename = "key-press")]
+    KeyPress,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventClick {
+    pub x: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LogItemValue {
+    Boolean(bool),
+    Object(ValueObject),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueObject {
+    pub x: bool,
+}"#
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_box_their_structs() {
+        let tree = SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(object(vec![(
+                "children",
+                array(SchemaState::Reference("node".to_string())),
+            )])),
+        };
+        assert_eq!(
+            to_rust(&tree, "Tree"),
+            r#"use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tree {
+    pub children: Vec<Box<Tree>>,
+}"#
+        );
+    }
+}
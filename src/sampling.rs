@@ -0,0 +1,195 @@
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+/// How records are sampled when a schema is inferred from at most a given number of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SampleStrategy {
+    /// The first records. Reading can stop as soon as enough records have been seen, but the
+    /// sample is biased if the input is sorted.
+    #[default]
+    Head,
+    /// A uniformly random selection of all records, by reservoir sampling. The whole input has to
+    /// be read, but only the sampled records are held in memory.
+    Reservoir,
+}
+
+/// Samples at most a given number of values, offered one at a time, with a [`SampleStrategy`].
+///
+/// # Example
+///
+/// ```
+/// use drivel::{SampleStrategy, Sampler};
+///
+/// let mut sampler = Sampler::new(SampleStrategy::Reservoir, 10);
+/// for i in 0..1000 {
+///     sampler.offer(i);
+/// }
+/// assert_eq!(sampler.seen(), 1000);
+/// assert_eq!(sampler.into_samples().len(), 10);
+/// ```
+#[derive(Debug)]
+pub struct Sampler<T> {
+    strategy: SampleStrategy,
+    max_samples: usize,
+    seen: usize,
+    samples: Vec<T>,
+    rng: StdRng,
+}
+
+impl<T> Sampler<T> {
+    pub fn new(strategy: SampleStrategy, max_samples: usize) -> Self {
+        Sampler {
+            strategy,
+            max_samples,
+            seen: 0,
+            samples: Vec::with_capacity(max_samples.min(1024)),
+            rng: StdRng::from_rng(thread_rng()).unwrap(),
+        }
+    }
+
+    /// Seeds the random choices of reservoir sampling, so that the same values are always
+    /// sampled the same.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Offers a value to the sample. Returns whether later values can still be sampled, which is
+    /// not the case once the head of the input has been taken.
+    pub fn offer(&mut self, value: T) -> bool {
+        self.seen += 1;
+        if self.samples.len() < self.max_samples {
+            self.samples.push(value);
+        } else if self.strategy == SampleStrategy::Reservoir {
+            // keep each of the values seen so far with the same probability
+            let idx = self.rng.gen_range(0..self.seen);
+            if idx < self.max_samples {
+                self.samples[idx] = value;
+            }
+        }
+        self.wants_more()
+    }
+
+    /// Whether later values can still be sampled.
+    pub fn wants_more(&self) -> bool {
+        self.strategy == SampleStrategy::Reservoir || self.samples.len() < self.max_samples
+    }
+
+    /// The number of values offered so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// The sampled values, in the order in which they were offered for the head strategy, and in
+    /// no particular order for reservoir sampling.
+    pub fn into_samples(self) -> Vec<T> {
+        self.samples
+    }
+}
+
+/// Keeps each of the values offered with a probability, the rate, such as 0.01 to infer from
+/// about 1% of a large input, deciding for each value as it is offered, so that none of them
+/// need to be held.
+///
+/// # Example
+///
+/// ```
+/// use drivel::RateSampler;
+///
+/// let mut sampler = RateSampler::new(0.1, Some(42));
+/// let kept = (0..10_000).filter(|_| sampler.keep()).count();
+/// assert!((900..1100).contains(&kept));
+/// ```
+#[derive(Debug)]
+pub struct RateSampler {
+    rate: f64,
+    rng: StdRng,
+}
+
+impl RateSampler {
+    /// Samples at `rate`, between 0 and 1, with random choices seeded by `seed` if it is given.
+    pub fn new(rate: f64, seed: Option<u64>) -> Self {
+        RateSampler {
+            rate: rate.clamp(0.0, 1.0),
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_rng(thread_rng()).unwrap(),
+            },
+        }
+    }
+
+    /// Whether to keep the next value.
+    pub fn keep(&mut self) -> bool {
+        self.rng.gen_bool(self.rate)
+    }
+}
+
+/// How many records a schema was inferred from, when they were sampled, out of how many were
+/// read, which the human-readable and JSON Schema descriptions note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct SampleCounts {
+    /// The number of records sampled, which the schema was inferred from.
+    pub sampled: usize,
+    /// The number of records read.
+    pub read: usize,
+}
+
+impl std::fmt::Display for SampleCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sampled {} of {} records", self.sampled, self.read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn head_takes_the_first_values_and_stops() {
+        let mut sampler = Sampler::new(SampleStrategy::Head, 3);
+        let offered: Vec<bool> = (0..5).map(|i| sampler.offer(i)).collect();
+        assert_eq!(offered, vec![true, true, false, false, false]);
+        assert_eq!(sampler.into_samples(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn reservoir_samples_from_the_whole_input() {
+        let mut sampler = Sampler::new(SampleStrategy::Reservoir, 10);
+        assert!((0..10_000).all(|i| sampler.offer(i)));
+        let samples = sampler.into_samples();
+        assert_eq!(samples.len(), 10);
+        // the chance that all ten samples are among the first thousand values is 1e-10
+        assert!(samples.iter().any(|&i| i >= 1000), "{:?}", samples);
+        let unique: std::collections::HashSet<_> = samples.iter().collect();
+        assert_eq!(unique.len(), 10);
+    }
+
+    #[test]
+    fn seeded_samples_are_the_same() {
+        let sample = |seed| {
+            let mut sampler = Sampler::new(SampleStrategy::Reservoir, 5).with_seed(seed);
+            (0..1000).for_each(|i| {
+                sampler.offer(i);
+            });
+            sampler.into_samples()
+        };
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+
+        let kept = |rate, seed| {
+            let mut sampler = RateSampler::new(rate, Some(seed));
+            (0..1000).filter(|_| sampler.keep()).collect::<Vec<_>>()
+        };
+        assert_eq!(kept(0.5, 7), kept(0.5, 7));
+        assert_eq!(kept(1.0, 7).len(), 1000);
+        assert!(kept(0.0, 7).is_empty());
+    }
+
+    #[test]
+    fn keeps_every_value_of_short_input() {
+        let mut sampler = Sampler::new(SampleStrategy::Reservoir, 10);
+        (0..4).for_each(|i| {
+            sampler.offer(i);
+        });
+        assert_eq!(sampler.into_samples(), vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::SchemaState;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use std::fmt;
+
+/// The version of drivel's native schema format. This must be bumped whenever a change to
+/// `SchemaState` (or its inner types) changes how it is serialized.
+pub const SAVED_SCHEMA_VERSION: u32 = 1;
+
+/// The version of drivel itself, which is saved along with schemas to tell which drivel saved one
+/// that can't be loaded.
+const DRIVEL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The envelope that saved schemas are wrapped in: the version of the format, the version of
+/// drivel that saved it, which schemas saved before it was recorded don't have, and the schema,
+/// which is only deserialized once the version of the format is known to be supported.
+#[derive(Serialize, Deserialize)]
+struct SavedSchema<S> {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    drivel_version: Option<String>,
+    schema: S,
+}
+
+#[derive(Debug)]
+pub enum LoadSchemaError {
+    InvalidFormat(String),
+    /// The schema was saved in another version of the format, by the given version of drivel if
+    /// it was recorded.
+    UnsupportedVersion {
+        version: u32,
+        drivel_version: Option<String>,
+    },
+}
+
+impl fmt::Display for LoadSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadSchemaError::InvalidFormat(msg) => {
+                write!(f, "Not a valid saved drivel schema: {}", msg)
+            }
+            LoadSchemaError::UnsupportedVersion {
+                version,
+                drivel_version,
+            } => {
+                write!(f, "Schema file format v{}", version)?;
+                if let Some(drivel_version) = drivel_version {
+                    write!(f, " (saved by drivel {})", drivel_version)?;
+                }
+                write!(f, ", this binary expects v{}; ", SAVED_SCHEMA_VERSION)?;
+                if *version > SAVED_SCHEMA_VERSION {
+                    write!(f, "load it with a newer version of drivel")
+                } else {
+                    write!(f, "save it again with this version of drivel")
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadSchemaError {}
+
+/// Serializes a schema into drivel's native format.
+///
+/// Unlike JSON Schema, this format is lossless: everything drivel has inferred about the data,
+/// such as the strings seen for fields of unknown type, is kept, so that data produced from a
+/// loaded schema is the same as data produced from the original one.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{load_schema, save_schema, NumberType, SchemaState};
+///
+/// let schema = SchemaState::Number(NumberType::Float {
+///     min: 0.5,
+///     max: f64::INFINITY,
+///     non_finite: Default::default(),
+/// });
+/// let saved = save_schema(&schema);
+///
+/// assert_eq!(load_schema(&saved).unwrap(), schema);
+/// ```
+pub fn save_schema(schema: &SchemaState) -> String {
+    serde_json::to_string(&SavedSchema {
+        version: SAVED_SCHEMA_VERSION,
+        drivel_version: Some(DRIVEL_VERSION.to_string()),
+        schema,
+    })
+    .unwrap()
+}
+
+/// Loads a schema that was serialized with [`save_schema`].
+///
+/// Returns an error if the input is not a saved schema, or if it was saved in a version of the
+/// format that this version of drivel does not support. If a schema in a supported version of
+/// the format is invalid, such as one saved by a newer drivel with types this one doesn't know
+/// of, the error says which drivel saved it.
+pub fn load_schema(saved: &str) -> Result<SchemaState, LoadSchemaError> {
+    let header: SavedSchema<IgnoredAny> =
+        serde_json::from_str(saved).map_err(|e| LoadSchemaError::InvalidFormat(e.to_string()))?;
+    if header.version != SAVED_SCHEMA_VERSION {
+        return Err(LoadSchemaError::UnsupportedVersion {
+            version: header.version,
+            drivel_version: header.drivel_version,
+        });
+    }
+
+    let saved: SavedSchema<SchemaState> = serde_json::from_str(saved).map_err(|e| {
+        LoadSchemaError::InvalidFormat(match header.drivel_version {
+            Some(version) if version != DRIVEL_VERSION => format!(
+                "{} (saved by drivel {}, this is drivel {})",
+                e, version, DRIVEL_VERSION
+            ),
+            _ => e.to_string(),
+        })
+    })?;
+    Ok(saved.schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, InferenceOptions, NumberType, StringType};
+    use indexmap::IndexMap;
+    use serde_json::json;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn round_trips_inferred_schema() {
+        let input = json!([
+            {"id": "48f41410-2d97-4d54-8bfa-aa4e22acca01", "name": "foo", "tags": ["a"], "score": 1.5},
+            {"id": "48f41410-2d97-4d54-8bfa-aa4e22acca02", "name": null, "tags": [], "age": 42}
+        ]);
+        let schema = infer_schema(
+            input,
+            &InferenceOptions {
+                enum_inference: None,
+                format_inference: None,
+                max_object_fields: None,
+                union_objects: false,
+                merge_threshold: None,
+                discriminator: None,
+                length_distribution: false,
+                extended_json: false,
+                coordinates: false,
+                tuples: false,
+                financial_identifiers: false,
+                barcodes: false,
+                money: false,
+                durations: false,
+                numeric_strings: false,
+                non_finite: false,
+                date_formats: vec![],
+                sequences: false,
+                bitflags: false,
+                vocabularies: vec![],
+                max_depth: None,
+                max_object_keys: None,
+                maps: false,
+                union_types: false,
+            },
+        );
+
+        assert_eq!(load_schema(&save_schema(&schema)).unwrap(), schema);
+    }
+
+    #[test]
+    fn round_trips_enums_and_unbounded_ranges() {
+        let schema = SchemaState::Object {
+            required: IndexMap::from([
+                (
+                    "status".to_string(),
+                    SchemaState::Number(NumberType::Enum {
+                        variants: BTreeMap::from([(200, 3), (404, 1)]),
+                    }),
+                ),
+                (
+                    "colour".to_string(),
+                    SchemaState::String(StringType::Enum {
+                        variants: BTreeSet::from(["red".to_string(), "blue".to_string()]),
+                    }),
+                ),
+            ]),
+            optional: IndexMap::from([(
+                "weight".to_string(),
+                SchemaState::Number(NumberType::Float {
+                    min: f64::NEG_INFINITY,
+                    max: f64::INFINITY,
+                    non_finite: Default::default(),
+                }),
+            )]),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+
+        assert_eq!(load_schema(&save_schema(&schema)).unwrap(), schema);
+    }
+
+    #[test]
+    fn keeps_the_order_of_fields() {
+        let fields = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| (name.to_string(), SchemaState::Boolean))
+                .collect::<IndexMap<_, _>>()
+        };
+        let schema = SchemaState::Object {
+            required: fields(&["delta", "alpha", "echo"]),
+            optional: fields(&["zulu", "xray"]),
+            access: Default::default(),
+            annotations: Default::default(),
+        };
+
+        let saved = save_schema(&schema);
+        let positions = ["delta", "alpha", "echo", "zulu", "xray"]
+            .map(|name| saved.find(&format!("\"{}\"", name)).unwrap());
+        assert!(
+            positions.windows(2).all(|pair| pair[0] < pair[1]),
+            "{}",
+            saved
+        );
+        let SchemaState::Object { required, .. } = load_schema(&saved).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(
+            required.keys().collect::<Vec<_>>(),
+            ["delta", "alpha", "echo"]
+        );
+    }
+
+    #[test]
+    fn saves_the_version_of_drivel() {
+        let saved: serde_json::Value =
+            serde_json::from_str(&save_schema(&SchemaState::Boolean)).unwrap();
+        assert_eq!(saved["version"], SAVED_SCHEMA_VERSION);
+        assert_eq!(saved["drivel_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn loads_schemas_saved_without_the_version_of_drivel() {
+        let saved = json!({"version": SAVED_SCHEMA_VERSION, "schema": "Boolean"}).to_string();
+        assert_eq!(load_schema(&saved).unwrap(), SchemaState::Boolean);
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let newer = json!({
+            "version": SAVED_SCHEMA_VERSION + 1,
+            "drivel_version": "99.0.0",
+            "schema": {"SomethingNew": {}}
+        })
+        .to_string();
+        let err = load_schema(&newer).unwrap_err();
+        assert!(matches!(
+            &err,
+            LoadSchemaError::UnsupportedVersion { version, drivel_version: Some(drivel) }
+                if *version == SAVED_SCHEMA_VERSION + 1 && drivel == "99.0.0"
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Schema file format v{} (saved by drivel 99.0.0), this binary expects v{}; load it with a newer version of drivel",
+                SAVED_SCHEMA_VERSION + 1,
+                SAVED_SCHEMA_VERSION
+            )
+        );
+
+        let older = json!({"version": 0, "schema": "Boolean"}).to_string();
+        assert_eq!(
+            load_schema(&older).unwrap_err().to_string(),
+            format!(
+                "Schema file format v0, this binary expects v{}; save it again with this version of drivel",
+                SAVED_SCHEMA_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn names_the_drivel_that_saved_an_invalid_schema() {
+        let saved = json!({
+            "version": SAVED_SCHEMA_VERSION,
+            "drivel_version": "99.0.0",
+            "schema": "SomethingNew"
+        })
+        .to_string();
+        let err = load_schema(&saved).unwrap_err().to_string();
+        assert!(err.contains("saved by drivel 99.0.0"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(matches!(
+            load_schema("{\"type\": \"object\"}"),
+            Err(LoadSchemaError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            load_schema(&json!({"version": SAVED_SCHEMA_VERSION, "schema": "Nope"}).to_string()),
+            Err(LoadSchemaError::InvalidFormat(_))
+        ));
+    }
+}
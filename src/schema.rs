@@ -1,5 +1,14 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
+use crate::{
+    definitions::factor_definitions,
+    draft::convert as convert_draft,
+    produce_options::{field_path, items_path},
+    FieldStats, NumberStats, SchemaDraft, ValueCounts,
+};
+
 /// A trait for converting schema types to JSON Schema format.
 ///
 /// This trait provides methods to convert drivel's internal schema representation
@@ -59,10 +68,10 @@ use std::fmt::Display;
 ///
 /// ```
 /// use drivel::{ToJsonSchema, SchemaState, StringType, NumberType};
-/// use std::collections::HashMap;
+/// use indexmap::IndexMap;
 /// use serde_json::json;
 ///
-/// let mut required = HashMap::new();
+/// let mut required = IndexMap::new();
 /// required.insert("id".to_string(), SchemaState::Number(NumberType::Integer { min: 1, max: 1000 }));
 /// required.insert("name".to_string(), SchemaState::String(StringType::Unknown {
 ///     strings_seen: vec!["test".to_string()],
@@ -73,7 +82,9 @@ use std::fmt::Display;
 ///
 /// let object_schema = SchemaState::Object {
 ///     required,
-///     optional: HashMap::new(),
+///     optional: IndexMap::new(),
+///     access: Default::default(),
+///     annotations: Default::default(),
 /// };
 ///
 /// let json_schema = object_schema.to_json_schema();
@@ -111,6 +122,29 @@ pub trait ToJsonSchema {
     /// ```
     fn to_json_schema(&self) -> serde_json::Value;
 
+    /// Adds the range of numbers covered by this schema to `schema`, which must have been
+    /// generated by `to_json_schema()`, as keywords of the given style.
+    ///
+    /// This is used by `to_json_schema_document_with()` when
+    /// [`JsonSchemaOptions::numeric_bounds`] is set. By default it does nothing.
+    fn add_numeric_bounds(&self, _schema: &mut serde_json::Value, _style: NumericBounds) {}
+
+    /// Adds the lengths of the strings, arrays and maps covered by this schema to `schema`,
+    /// which must have been generated by `to_json_schema()`.
+    ///
+    /// This is used by `to_json_schema_document_with()` when [`JsonSchemaOptions::lengths`] is
+    /// set. By default it does nothing.
+    fn add_lengths(&self, _schema: &mut serde_json::Value) {}
+
+    /// Marks the floats covered by this schema that look like ratios or percentages, as
+    /// [`NumberType::numeric_range`] classifies them, with `x-drivel-numeric-range`, such as
+    /// `"ratio 0..1"`, in `schema`, which must have been generated by `to_json_schema()`. They
+    /// are bounded by the values observed, unless they are bounded already.
+    ///
+    /// This is used by `to_json_schema_document_with()` when
+    /// [`JsonSchemaOptions::numeric_ranges`] is set. By default it does nothing.
+    fn add_numeric_ranges(&self, _schema: &mut serde_json::Value) {}
+
     /// Converts the schema to a complete JSON Schema document.
     ///
     /// This method wraps the core schema from `to_json_schema()` in a complete
@@ -147,22 +181,315 @@ pub trait ToJsonSchema {
     /// assert_eq!(document["format"], "uuid");
     /// ```
     fn to_json_schema_document(&self) -> serde_json::Value {
+        self.to_json_schema_document_with(&JsonSchemaOptions::default())
+    }
+
+    /// Converts the schema to a complete JSON Schema document, customised by the given options.
+    ///
+    /// This behaves like `to_json_schema_document()`, but allows setting the document's `title`
+    /// and `$id`, and adding a `description` to every object property, derived from the
+    /// property's name.
+    /// The document can be of an older [draft](JsonSchemaOptions::draft) of JSON Schema than
+    /// 2020-12.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drivel::{JsonSchemaOptions, ToJsonSchema, SchemaState};
+    /// use indexmap::IndexMap;
+    ///
+    /// let schema = SchemaState::Object {
+    ///     required: IndexMap::from_iter([("user_id".to_string(), SchemaState::Boolean)]),
+    ///     optional: IndexMap::new(),
+    ///     access: Default::default(),
+    ///     annotations: Default::default(),
+    /// };
+    /// let options = JsonSchemaOptions {
+    ///     title: Some("User".to_string()),
+    ///     id: Some("https://example.com/user.schema.json".to_string()),
+    ///     field_descriptions: true,
+    ///     numeric_bounds: None,
+    ///     numeric_ranges: false,
+    ///     dedupe_definitions: false,
+    ///     field_stats: None,
+    ///     value_counts: None,
+    ///     lengths: false,
+    ///     draft: Default::default(),
+    /// };
+    /// let document = schema.to_json_schema_document_with(&options);
+    ///
+    /// assert_eq!(document["title"], "User");
+    /// assert_eq!(document["$id"], "https://example.com/user.schema.json");
+    /// assert_eq!(document["properties"]["user_id"]["description"], "User id");
+    /// ```
+    fn to_json_schema_document_with(&self, options: &JsonSchemaOptions) -> serde_json::Value {
+        let title = options.title.as_deref().unwrap_or("Inferred Schema");
         let mut doc = serde_json::json!({
-            "$schema": "https://json-schema.org/draft/2020-12/schema",
-            "title": "Inferred Schema",
+            "$schema": SchemaDraft::Draft2020_12.uri(),
+            "title": title,
             "description": "Schema inferred by drivel from sample data"
         });
 
-        if let serde_json::Value::Object(schema_obj) = self.to_json_schema() {
+        if let Some(id) = &options.id {
+            doc["$id"] = serde_json::json!(id);
+        }
+
+        if let serde_json::Value::Object(schema_obj) = to_json_schema_with(self, options) {
             if let serde_json::Value::Object(doc_obj) = &mut doc {
                 doc_obj.extend(schema_obj);
             }
         }
 
+        convert_draft(&mut doc, options.draft);
         doc
     }
 }
 
+/// Converts a schema to JSON Schema, with the keywords enabled by the given options, but without
+/// the document-level `$schema`, `title`, `description` and `$id`.
+pub(crate) fn to_json_schema_with<S: ToJsonSchema + ?Sized>(
+    schema_state: &S,
+    options: &JsonSchemaOptions,
+) -> serde_json::Value {
+    let mut schema = schema_state.to_json_schema();
+    if let Some(style) = options.numeric_bounds {
+        schema_state.add_numeric_bounds(&mut schema, style);
+    }
+    if options.numeric_ranges {
+        schema_state.add_numeric_ranges(&mut schema);
+    }
+    if options.lengths {
+        schema_state.add_lengths(&mut schema);
+    }
+    if let Some(stats) = &options.field_stats {
+        // before factoring out definitions, since stats can differ between their occurrences
+        add_field_stats(&mut schema, stats, "");
+    }
+    if let Some(counts) = &options.value_counts {
+        add_value_counts(&mut schema, counts, "");
+    }
+    if options.dedupe_definitions {
+        factor_definitions(&mut schema);
+    }
+    if options.field_descriptions {
+        add_field_descriptions(&mut schema);
+    }
+    schema
+}
+
+/// Options for customising the JSON Schema documents generated by
+/// [`ToJsonSchema::to_json_schema_document_with`].
+#[derive(Debug, Default, Clone)]
+pub struct JsonSchemaOptions {
+    /// The document's `title`. Defaults to "Inferred Schema".
+    pub title: Option<String>,
+    /// The document's `$id`. Omitted if not set.
+    pub id: Option<String>,
+    /// Whether to add a `description` to every object property, derived from its name.
+    pub field_descriptions: bool,
+    /// How to add the range of observed numbers to integer and number schemas. Omitted if
+    /// not set.
+    pub numeric_bounds: Option<NumericBounds>,
+    /// Whether to mark floats that look like ratios or percentages as such, as
+    /// `x-drivel-numeric-range`, bounded by the values observed, and to label them in the
+    /// human-readable format too, such as `float (0.05-0.95, ratio 0..1)`.
+    pub numeric_ranges: bool,
+    /// Whether to define object schemas that occur more than once in `$defs`, and refer to them
+    /// with `$ref`, instead of repeating them inline.
+    pub dedupe_definitions: bool,
+    /// Statistics about the fields of the data the schema was inferred from, to add to every
+    /// object property as `x-drivel-presence` (the fraction of objects that had the field) and
+    /// `x-drivel-null-ratio` (the fraction of its values that were `null`), and as
+    /// `x-drivel-unique: true` to the properties whose values were all different. The
+    /// human-readable format marks the latter as `unique` too. Omitted if not set.
+    pub field_stats: Option<FieldStats>,
+    /// How often each value occurred at the paths of the data the schema was inferred from that
+    /// had few distinct values, to add to their schemas as `x-drivel-value-counts`, a list of
+    /// each `value` with its `count` and `ratio` of all values at the path. These are also
+    /// listed under the fields in the human-readable format. Omitted if not set.
+    pub value_counts: Option<ValueCounts>,
+    /// Whether to add the lengths observed to the schemas of strings of no particular format,
+    /// as `minLength` and `maxLength`, of arrays, as `minItems` and `maxItems`, and of maps, as
+    /// `minProperties` and `maxProperties`.
+    pub lengths: bool,
+    /// The draft of JSON Schema to generate documents for. Defaults to 2020-12.
+    pub draft: SchemaDraft,
+}
+
+/// The keywords used to describe the range of a number in JSON Schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum NumericBounds {
+    /// `minimum` and `maximum`, set to the smallest and largest values observed.
+    Inclusive,
+    /// `exclusiveMinimum` and `exclusiveMaximum`, set just outside the values observed: one
+    /// past them for integers, and the next representable value for floats.
+    Exclusive,
+}
+
+/// Turns a field name into a human-readable phrase, e.g. `user_id` or `userId` into "User id".
+fn humanize(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words: Vec<String> = vec![];
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        // split camelCase ("userId") and acronyms followed by a word ("HTTPStatus")
+        let previous = i.checked_sub(1).map(|j| chars[j]);
+        let next = chars.get(i + 1);
+        let starts_word = c.is_uppercase()
+            && previous.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_numeric()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if starts_word && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    let phrase = words.join(" ");
+    let mut chars = phrase.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => phrase,
+    }
+}
+
+fn add_field_descriptions(schema: &mut serde_json::Value) {
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        for (key, property) in properties.iter_mut() {
+            if let Some(property_obj) = property.as_object_mut() {
+                property_obj
+                    .entry("description")
+                    .or_insert_with(|| serde_json::json!(humanize(key)));
+            }
+            add_field_descriptions(property);
+        }
+    }
+
+    if let Some(items) = schema.get_mut("items") {
+        add_field_descriptions(items);
+    }
+
+    if let Some(positions) = schema.get_mut("prefixItems").and_then(|p| p.as_array_mut()) {
+        positions.iter_mut().for_each(add_field_descriptions);
+    }
+
+    if let Some(values) = schema.get_mut("additionalProperties") {
+        add_field_descriptions(values);
+    }
+
+    if let Some(definitions) = schema.get_mut("$defs").and_then(|d| d.as_object_mut()) {
+        for definition in definitions.values_mut() {
+            add_field_descriptions(definition);
+        }
+    }
+
+    if let Some(variants) = schema.get_mut("oneOf").and_then(|v| v.as_array_mut()) {
+        variants.iter_mut().for_each(add_field_descriptions);
+    }
+}
+
+/// Rounds a ratio to four decimal places, which is plenty to tell apart rare and common fields.
+fn rounded_ratio(ratio: f64) -> serde_json::Value {
+    serde_json::json!((ratio * 1e4).round() / 1e4)
+}
+
+fn add_field_stats(schema: &mut serde_json::Value, stats: &FieldStats, path: &str) {
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        for (key, property) in properties.iter_mut() {
+            let property_path = field_path(path, key);
+            if let (Some(counts), Some(property_obj)) =
+                (stats.get(&property_path), property.as_object_mut())
+            {
+                property_obj.insert(
+                    "x-drivel-presence".to_string(),
+                    rounded_ratio(counts.presence()),
+                );
+                property_obj.insert(
+                    "x-drivel-null-ratio".to_string(),
+                    rounded_ratio(counts.null_ratio()),
+                );
+                if stats.is_unique(&property_path) {
+                    property_obj
+                        .insert("x-drivel-unique".to_string(), serde_json::Value::Bool(true));
+                }
+            }
+            add_field_stats(property, stats, &property_path);
+        }
+    }
+
+    if let Some(items) = schema.get_mut("items") {
+        add_field_stats(items, stats, &items_path(path));
+    }
+
+    // the positions of tuples are elements of the array, as in the paths of `FieldStats`
+    if let Some(positions) = schema.get_mut("prefixItems").and_then(|p| p.as_array_mut()) {
+        for position in positions {
+            add_field_stats(position, stats, &items_path(path));
+        }
+    }
+
+    if let Some(variants) = schema.get_mut("oneOf").and_then(|v| v.as_array_mut()) {
+        for variant in variants {
+            add_field_stats(variant, stats, path);
+        }
+    }
+}
+
+fn add_value_counts(schema: &mut serde_json::Value, counts: &ValueCounts, path: &str) {
+    if let (Some(values), Some(schema_obj)) = (counts.get(path), schema.as_object_mut()) {
+        let total: usize = values.iter().map(|(_, count)| count).sum();
+        let values = values
+            .into_iter()
+            .map(|(value, count)| {
+                serde_json::json!({
+                    "value": value,
+                    "count": count,
+                    "ratio": rounded_ratio(count as f64 / total as f64),
+                })
+            })
+            .collect();
+        schema_obj.insert(
+            "x-drivel-value-counts".to_string(),
+            serde_json::Value::Array(values),
+        );
+    }
+
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        for (key, property) in properties.iter_mut() {
+            add_value_counts(property, counts, &field_path(path, key));
+        }
+    }
+
+    if let Some(items) = schema.get_mut("items") {
+        add_value_counts(items, counts, &items_path(path));
+    }
+
+    if let Some(positions) = schema.get_mut("prefixItems").and_then(|p| p.as_array_mut()) {
+        for position in positions {
+            add_value_counts(position, counts, &items_path(path));
+        }
+    }
+
+    if let Some(variants) = schema.get_mut("oneOf").and_then(|v| v.as_array_mut()) {
+        for variant in variants {
+            add_value_counts(variant, counts, path);
+        }
+    }
+}
+
 /// Represents specialized string types that can be inferred from sample data.
 ///
 /// This enum captures semantic information about strings beyond the basic string type,
@@ -182,7 +509,7 @@ pub trait ToJsonSchema {
 /// ```
 /// use drivel::{StringType, ToJsonSchema};
 /// use serde_json::json;
-/// use std::collections::HashSet;
+/// use std::collections::BTreeSet;
 ///
 /// // UUID detection
 /// let uuid_type = StringType::UUID;
@@ -202,13 +529,13 @@ pub trait ToJsonSchema {
 /// let enum_variants = ["red", "green", "blue"]
 ///     .iter()
 ///     .map(|s| s.to_string())
-///     .collect::<HashSet<_>>();
+///     .collect::<BTreeSet<_>>();
 /// let enum_type = StringType::Enum { variants: enum_variants };
 /// let schema = enum_type.to_json_schema();
 /// assert_eq!(schema["type"], "string");
 /// assert!(schema["enum"].is_array());
 /// ```
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum StringType {
     /// General string type with length constraints and character analysis.
     ///
@@ -264,6 +591,22 @@ pub enum StringType {
     /// - "1995-01-01T12:00:00+02:00"
     DateTimeISO8601,
 
+    /// Date-time in one of the chrono-style formats supplied by the user for inference.
+    ///
+    /// Detected, if formats are supplied, when strings parse with a format. Values are produced
+    /// in the first format.
+    /// Uses custom extensions `x-drivel-type: "datetime-custom"` and `x-drivel-date-format`.
+    ///
+    /// # Fields
+    ///
+    /// * `formats` - The supplied formats that every string seen parses with, in the order they
+    ///   were supplied
+    ///
+    /// # Examples
+    /// - "12/25/2023", with format `%m/%d/%Y`
+    /// - "25-12-2023 10:30", with format `%d-%m-%Y %H:%M`
+    CustomDateTime { formats: Vec<String> },
+
     /// Universally Unique Identifier format.
     ///
     /// Detected when strings match UUID patterns (with or without hyphens).
@@ -305,6 +648,163 @@ pub enum StringType {
     /// - "api.service.internal"
     Hostname,
 
+    /// IPv4 address format.
+    ///
+    /// Detected when strings are IPv4 addresses in dotted-decimal notation.
+    /// Maps to JSON Schema `format: "ipv4"`.
+    ///
+    /// # Examples
+    /// - "192.168.0.1"
+    /// - "10.0.0.255"
+    Ipv4,
+
+    /// IPv6 address format.
+    ///
+    /// Detected when strings are IPv6 addresses, in full or with `::` for groups of zeros.
+    /// Maps to JSON Schema `format: "ipv6"`.
+    ///
+    /// # Examples
+    /// - "2001:db8::8a2e:370:7334"
+    /// - "::1"
+    Ipv6,
+
+    /// Fixed-width numeric string with significant leading zeros.
+    ///
+    /// Detected when strings consist only of ASCII digits and start with a zero, such as zip
+    /// codes or account numbers. These are kept as strings rather than being treated as
+    /// numbers, which would drop the leading zeros.
+    /// Maps to JSON Schema `pattern: "^[0-9]{width}$"`.
+    ///
+    /// # Fields
+    ///
+    /// * `width` - The number of digits in each string
+    ///
+    /// # Examples
+    /// - "00042"
+    /// - "007"
+    FixedWidthNumeric { width: usize },
+
+    /// Numbers written as strings, by sources that quote every scalar.
+    ///
+    /// Detected, if enabled, when every string is a JSON number without leading zeros, which
+    /// would be dropped by a number (as in [`StringType::FixedWidthNumeric`]). The range of the
+    /// numbers is kept, and values are produced as strings of numbers in that range.
+    /// Uses custom extensions `x-drivel-type: "numeric-string"` and `x-drivel-number-type`,
+    /// which is `integer` or `number`.
+    ///
+    /// # Fields
+    ///
+    /// * `number` - The type of the numbers, with their range
+    ///
+    /// Uses custom extensions `x-drivel-type: "numeric-string"`, `x-drivel-number-type` and, for
+    /// finite ranges, `x-drivel-minimum` and `x-drivel-maximum`.
+    ///
+    /// # Examples
+    /// - "42"
+    /// - "-3.14"
+    Numeric { number: NumberType },
+
+    /// Payment card number.
+    ///
+    /// Detected, if enabled, when strings of 13 to 19 digits pass the Luhn checksum.
+    /// Uses custom extension `x-drivel-type: "credit-card"`.
+    ///
+    /// # Examples
+    /// - "4111111111111111"
+    CreditCard,
+
+    /// International Bank Account Number, in its electronic format without spaces.
+    ///
+    /// Detected, if enabled, when strings pass the IBAN mod-97 checksum.
+    /// Uses custom extension `x-drivel-type: "iban"`.
+    ///
+    /// # Examples
+    /// - "NL91ABNA0417164300"
+    Iban,
+
+    /// ISBN-10, the 10-character International Standard Book Number used before 2007.
+    ///
+    /// Detected, if enabled, when strings of 9 digits and a check digit or `X` pass the ISBN-10
+    /// mod-11 checksum. Uses custom extension `x-drivel-type: "isbn-10"`.
+    ///
+    /// # Examples
+    /// - "0306406152"
+    Isbn10,
+
+    /// ISBN-13, the EAN-13 barcodes of books, starting with 978 or 979.
+    ///
+    /// Detected, if enabled, when strings of 13 digits with a book prefix pass the EAN-13
+    /// checksum. Uses custom extension `x-drivel-type: "isbn-13"`.
+    ///
+    /// # Examples
+    /// - "9780306406157"
+    Isbn13,
+
+    /// EAN-13 barcode, the 13-digit European (or International) Article Number.
+    ///
+    /// Detected, if enabled, when strings of 13 digits pass the EAN-13 checksum. Uses custom
+    /// extension `x-drivel-type: "ean-13"`.
+    ///
+    /// # Examples
+    /// - "4006381333931"
+    Ean13,
+
+    /// ISO 8601 duration.
+    ///
+    /// Detected, if enabled, when strings are durations of years, months, weeks, days, hours,
+    /// minutes and seconds.
+    /// Maps to JSON Schema `format: "duration"`.
+    ///
+    /// # Examples
+    /// - "PT1H30M"
+    /// - "P3DT12H"
+    Duration,
+
+    /// Strings of no particular format, but all made of one class of characters, such as
+    /// uppercase codes or hexadecimal hashes.
+    ///
+    /// Detected, if formats are inferred from a minimum number of values, when every string
+    /// seen belongs to the class, and the strings are no enum.
+    /// Maps to JSON Schema `pattern` (such as `^[A-Z]+$`) with `minLength` and `maxLength`.
+    ///
+    /// # Fields
+    ///
+    /// * `class` - The class of characters of the strings
+    /// * `min_length` - Minimum string length observed
+    /// * `max_length` - Maximum string length observed
+    ///
+    /// # Examples
+    /// - "NL", "USA"
+    /// - "9f86d081"
+    Charset {
+        class: CharacterClass,
+        min_length: usize,
+        max_length: usize,
+    },
+
+    /// Strings that match a regular expression, read from the `pattern` of a JSON Schema that
+    /// is not the pattern of another string type, such as `^[a-z]+-[0-9]+$`.
+    ///
+    /// Detected, if formats are inferred from a minimum number of values, when every string
+    /// seen has the same structure of runs of digits and letters between other characters, such
+    /// as ids like `ORD-2024-00173`, which is inferred as `^ORD-[0-9]{4}-[0-9]{5}$`.
+    ///
+    /// Produced by generating strings from the pattern, which must be one that strings matching
+    /// it can be generated from: patterns with lookarounds or word boundaries are read as
+    /// strings of no particular format instead.
+    /// Maps to JSON Schema `pattern`, with `minLength` and `maxLength` if they were given.
+    ///
+    /// # Fields
+    ///
+    /// * `pattern` - The regular expression that the strings match
+    /// * `min_length` - Minimum string length, if any
+    /// * `max_length` - Maximum string length, if any
+    Pattern {
+        pattern: String,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+    },
+
     /// Enumerated string values with a finite set of variants.
     ///
     /// Detected when all observed string values belong to a small,
@@ -314,7 +814,8 @@ pub enum StringType {
     ///
     /// # Fields
     ///
-    /// * `variants` - Set of all distinct string values observed
+    /// * `variants` - Set of all distinct string values observed, kept in sorted order so that
+    ///   output and value selection are deterministic
     ///
     /// # JSON Schema Output
     ///
@@ -328,13 +829,107 @@ pub enum StringType {
     /// - The uniqueness ratio suggests a closed set rather than open text
     /// - Sample size is sufficient to make a confident determination
     Enum {
-        variants: std::collections::HashSet<String>,
+        variants: std::collections::BTreeSet<String>,
+    },
+
+    /// Mostly enumerated string values, with an open tail of values outside the enum.
+    ///
+    /// Detected, if open-tail enum inference is enabled, when a field is not an enum as a whole,
+    /// but its most common values cover most of the values seen, such as a `country` field that
+    /// is nearly always one of a few countries.
+    ///
+    /// # Fields
+    ///
+    /// * `variants` - The most common values, kept in sorted order
+    /// * `other_ratio` - The fraction of values seen that were not one of the variants
+    /// * `other` - The string type of the values that were not one of the variants
+    ///
+    /// # JSON Schema Output
+    ///
+    /// Generates a JSON Schema with `type: "string"`, as any string is allowed, and custom
+    /// extensions `x-drivel-type: "open-enum"`, `x-drivel-enum` and `x-drivel-other-ratio`.
+    OpenEnum {
+        variants: std::collections::BTreeSet<String>,
+        other_ratio: f64,
+        other: Box<StringType>,
     },
 }
 
-impl Display for StringType {
+/// A class of ASCII characters that all the strings of a [`StringType::Charset`] are made of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum CharacterClass {
+    /// The uppercase letters, `A-Z`.
+    Uppercase,
+    /// The lowercase letters, `a-z`.
+    Lowercase,
+    /// The lowercase hexadecimal digits, `0-9` and `a-f`.
+    Hex,
+    /// The digits, `0-9`.
+    Digits,
+}
+
+impl CharacterClass {
+    const ALL: [CharacterClass; 4] = [
+        CharacterClass::Uppercase,
+        CharacterClass::Lowercase,
+        CharacterClass::Hex,
+        CharacterClass::Digits,
+    ];
+
+    /// The JSON Schema `pattern` of strings of the class.
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            CharacterClass::Uppercase => "^[A-Z]+$",
+            CharacterClass::Lowercase => "^[a-z]+$",
+            CharacterClass::Hex => "^[0-9a-f]+$",
+            CharacterClass::Digits => "^[0-9]+$",
+        }
+    }
+
+    /// The class whose [`pattern`](Self::pattern) this is, if any.
+    pub fn from_pattern(pattern: &str) -> Option<CharacterClass> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.pattern() == pattern)
+    }
+
+    /// The characters of the class.
+    pub fn chars(&self) -> Vec<char> {
+        match self {
+            CharacterClass::Uppercase => ('A'..='Z').collect(),
+            CharacterClass::Lowercase => ('a'..='z').collect(),
+            CharacterClass::Hex => ('0'..='9').chain('a'..='f').collect(),
+            CharacterClass::Digits => ('0'..='9').collect(),
+        }
+    }
+
+    /// Whether a character belongs to the class.
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharacterClass::Uppercase => c.is_ascii_uppercase(),
+            CharacterClass::Lowercase => c.is_ascii_lowercase(),
+            CharacterClass::Hex => c.is_ascii_digit() || ('a'..='f').contains(&c),
+            CharacterClass::Digits => c.is_ascii_digit(),
+        }
+    }
+}
+
+impl Display for CharacterClass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = match self {
+        match self {
+            CharacterClass::Uppercase => write!(f, "uppercase"),
+            CharacterClass::Lowercase => write!(f, "lowercase"),
+            CharacterClass::Hex => write!(f, "hex"),
+            CharacterClass::Digits => write!(f, "digit"),
+        }
+    }
+}
+
+impl StringType {
+    /// Describes this type as [`SchemaState::to_string_pretty_with`] does, listing at most
+    /// `max_list_items` of the variants of an enum.
+    fn describe(&self, max_list_items: Option<usize>) -> String {
+        match self {
             StringType::Unknown {
                 strings_seen: _,
                 chars_seen: _,
@@ -358,17 +953,53 @@ impl Display for StringType {
             StringType::IsoDate => "string (date - ISO 8601)".to_owned(),
             StringType::DateTimeRFC2822 => "string (datetime - RFC 2822)".to_owned(),
             StringType::DateTimeISO8601 => "string (datetime - ISO 8601)".to_owned(),
+            StringType::CustomDateTime { formats } => {
+                format!("string (datetime - {})", formats[0])
+            }
             StringType::UUID => "string (uuid)".to_owned(),
             StringType::Email => "string (email)".to_owned(),
             StringType::Hostname => "string (hostname)".to_owned(),
+            StringType::Ipv4 => "string (ipv4)".to_owned(),
+            StringType::Ipv6 => "string (ipv6)".to_owned(),
             StringType::Url => "string (url)".to_owned(),
+            StringType::FixedWidthNumeric { width } => {
+                format!("string (numeric, width {})", width)
+            }
+            StringType::Numeric { number } => format!("string (numeric: {})", number),
+            StringType::CreditCard => "string (credit card)".to_owned(),
+            StringType::Iban => "string (iban)".to_owned(),
+            StringType::Isbn10 => "string (isbn-10)".to_owned(),
+            StringType::Isbn13 => "string (isbn-13)".to_owned(),
+            StringType::Ean13 => "string (ean-13)".to_owned(),
+            StringType::Duration => "string (duration - ISO 8601)".to_owned(),
+            StringType::Charset {
+                class,
+                min_length,
+                max_length,
+            } if min_length != max_length => {
+                format!("{} string ({}-{})", class, min_length, max_length)
+            }
+            StringType::Charset {
+                class, min_length, ..
+            } => format!("{} string ({})", class, min_length),
+            StringType::Pattern { pattern, .. } => format!("string (pattern {})", pattern),
             StringType::Enum { variants } => {
                 let variants_vec = variants.iter().cloned().collect::<Vec<_>>();
-                let formatted = variants_vec.join(", ");
+                let formatted = join_list(variants_vec, ", ", max_list_items);
                 format!("string (enum: {})", formatted)
             }
-        };
-        write!(f, "{}", text)
+            StringType::OpenEnum { variants, .. } => {
+                let variants_vec = variants.iter().cloned().collect::<Vec<_>>();
+                let formatted = join_list(variants_vec, ", ", max_list_items);
+                format!("string (enum: {} (open))", formatted)
+            }
+        }
+    }
+}
+
+impl Display for StringType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe(None))
     }
 }
 
@@ -392,9 +1023,11 @@ impl Display for StringType {
 ///
 /// - `Integer` maps to JSON Schema `type: "integer"`
 /// - `Float` maps to JSON Schema `type: "number"`
+/// - `Enum` maps to JSON Schema `type: "integer"` with an `enum` of the observed values
 ///
-/// Both variants track the range of observed values, but the generated JSON
-/// Schema only contains the `"type"` property.
+/// `Integer` and `Float` track the range of observed values, but the generated JSON
+/// Schema only contains the `"type"` property, unless
+/// [`JsonSchemaOptions::numeric_bounds`] asks for the range to be included.
 ///
 /// # Examples
 ///
@@ -409,12 +1042,16 @@ impl Display for StringType {
 /// }));
 ///
 /// // Float with range constraints
-/// let float_type = NumberType::Float { min: 0.5, max: 99.9 };
+/// let float_type = NumberType::Float {
+///     min: 0.5,
+///     max: 99.9,
+///     non_finite: Default::default(),
+/// };
 /// assert_eq!(float_type.to_json_schema(), json!({
 ///     "type": "number"
 /// }));
 /// ```
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum NumberType {
     /// Integer numbers with tracked range bounds.
     ///
@@ -467,40 +1104,492 @@ pub enum NumberType {
     ///
     /// Float ranges use f64 precision. Be aware of potential floating-point
     /// precision issues when working with very large numbers or high-precision decimals.
-    Float { min: f64, max: f64 },
-}
+    Float {
+        #[serde(with = "non_finite_f64")]
+        min: f64,
+        #[serde(with = "non_finite_f64")]
+        max: f64,
+        /// The values seen that aren't numbers in JSON, in input that was parsed leniently. The
+        /// range is `NaN` to `NaN` if no other values were seen.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+        non_finite: std::collections::BTreeSet<NonFiniteNumber>,
+    },
 
-impl Display for NumberType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = match self {
-            NumberType::Integer { min, max } => {
-                if min != max {
-                    format!("int ({}-{})", min, max)
-                } else {
-                    format!("int ({})", min)
-                }
-            }
-            NumberType::Float { min, max } => {
-                if min != max {
-                    format!("float ({}-{})", min, max)
-                } else {
-                    format!("float ({})", min)
-                }
-            }
-        };
-        write!(f, "{}", text)
-    }
-}
+    /// Integers drawn from a small set of known values.
+    ///
+    /// This variant is produced by enum inference when the number of distinct
+    /// integers observed is low relative to the sample size (e.g. status codes).
+    ///
+    /// # Fields
+    ///
+    /// * `variants` - The distinct values observed, with the number of times each was seen
+    ///
+    /// # JSON Schema Output
+    ///
+    /// Generates a JSON Schema with:
+    /// - `type: "integer"`
+    /// - `enum: [...]` - Array of the observed values
+    Enum {
+        variants: std::collections::BTreeMap<i64, usize>,
+    },
 
-/// The SchemaState enum is a recursive data structure that describes the schema of a given JSON structure.
-///
-/// There are a few notable differences with the data types from the JSON specification:
-/// - The SchemaState enum has Initial and Indefinite variants. These encode two possible results of the
-///   schema inference process that have no equivalents in the JSON specification.
-/// - The String and Number types have an inner type that specialises the more generic types. This is to
-///   add some further semantics to the data type, provided `drivel` is able to infer these semantics.
-#[derive(PartialEq, Debug)]
-pub enum SchemaState {
+    /// Integers that increase from one value to the next, such as auto-increment primary keys.
+    ///
+    /// Detected, if enabled, when nearly every integer seen is greater than the one before it.
+    /// Produced as an increasing sequence from `start`, by the average step between the values
+    /// seen. Uses custom extensions `x-drivel-type: "sequence"`, `x-drivel-sequence-start` and
+    /// `x-drivel-sequence-step`.
+    ///
+    /// # Fields
+    ///
+    /// * `min` - Minimum integer value observed in the sample data
+    /// * `max` - Maximum integer value observed in the sample data
+    /// * `start` - The first value observed
+    /// * `end` - The last value observed
+    /// * `count` - The number of values observed
+    /// * `increases` - The number of values observed that were greater than the one before them
+    ///
+    /// # Examples
+    /// - 1, 2, 3, 5, 6
+    Sequence {
+        min: i64,
+        max: i64,
+        start: i64,
+        end: i64,
+        count: usize,
+        increases: usize,
+    },
+
+    /// Integers that are bitmasks of flags, such as permissions, i.e. each a combination of the
+    /// same few powers of two.
+    ///
+    /// Detected, if enabled, when enough integers are seen, every flag is seen on its own, and
+    /// most values are a single flag. Produced as random combinations of the flags. Uses custom
+    /// extensions `x-drivel-type: "flags"` and `x-drivel-flags`, with the flags in the
+    /// `description`.
+    ///
+    /// # Fields
+    ///
+    /// * `values` - The distinct values observed, with the number of times each was seen
+    ///
+    /// # Examples
+    /// - 1, 4, 2, 5, 8, 1
+    Flags {
+        values: std::collections::BTreeMap<i64, usize>,
+    },
+
+    /// Numbers that are multiples of a step, such as prices in steps of 0.05 or quantities
+    /// sold by the dozen.
+    ///
+    /// Never inferred: only parsed from the `multipleOf` of a JSON Schema, so that values
+    /// produced from it validate. Produced as a random multiple within the range, written with
+    /// no more decimals than the step.
+    ///
+    /// # Fields
+    ///
+    /// * `step` - The positive number that every value is a multiple of
+    /// * `min` - The smallest value allowed, which may be infinite
+    /// * `max` - The largest value allowed, which may be infinite
+    /// * `integer` - Whether the values are integers, in which case the step is one too
+    ///
+    /// # JSON Schema Output
+    ///
+    /// Generates a JSON Schema with:
+    /// - `type: "integer"` or `type: "number"`
+    /// - `multipleOf` - The step
+    Multiple {
+        step: f64,
+        #[serde(with = "non_finite_f64")]
+        min: f64,
+        #[serde(with = "non_finite_f64")]
+        max: f64,
+        integer: bool,
+    },
+}
+
+/// The values of a float that some JSON encoders write, such as Python's `json` module and
+/// pandas, but that aren't valid JSON, nor representable in [`serde_json::Value`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum NonFiniteNumber {
+    #[serde(rename = "NaN")]
+    NaN,
+    #[serde(rename = "Infinity")]
+    Infinity,
+    #[serde(rename = "-Infinity")]
+    NegativeInfinity,
+}
+
+impl NonFiniteNumber {
+    /// The token for the value, as written by the encoders that write them.
+    pub fn token(&self) -> &'static str {
+        match self {
+            NonFiniteNumber::NaN => "NaN",
+            NonFiniteNumber::Infinity => "Infinity",
+            NonFiniteNumber::NegativeInfinity => "-Infinity",
+        }
+    }
+
+    /// The value of a token, if it is one of `NaN`, `Infinity` and `-Infinity`.
+    pub fn from_token(token: &str) -> Option<NonFiniteNumber> {
+        match token {
+            "NaN" => Some(NonFiniteNumber::NaN),
+            "Infinity" => Some(NonFiniteNumber::Infinity),
+            "-Infinity" => Some(NonFiniteNumber::NegativeInfinity),
+            _ => None,
+        }
+    }
+}
+
+/// The scale of a float whose values all lie in a window that numbers measuring a share of
+/// something typically lie in, such as a conversion rate or a battery level.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NumericRange {
+    /// A ratio between 0 and 1.
+    Ratio,
+    /// A percentage between 0 and 100.
+    Percentage,
+}
+
+impl NumericRange {
+    /// The smallest and largest values of the window.
+    pub fn window(&self) -> (f64, f64) {
+        match self {
+            NumericRange::Ratio => (0.0, 1.0),
+            NumericRange::Percentage => (0.0, 100.0),
+        }
+    }
+}
+
+impl Display for NumericRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumericRange::Ratio => write!(f, "ratio 0..1"),
+            NumericRange::Percentage => write!(f, "percentage 0..100"),
+        }
+    }
+}
+
+/// Serializes floats such that unbounded ranges survive a round trip through JSON, which has no
+/// representation for infinity.
+mod non_finite_f64 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else {
+            serializer.serialize_str(&value.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(value) => Ok(value),
+            Repr::Text(text) => text.parse().map_err(D::Error::custom),
+        }
+    }
+}
+
+impl NumberType {
+    /// The smallest and largest values of this type, as floats.
+    pub(crate) fn bounds(&self) -> (f64, f64) {
+        match self {
+            NumberType::Integer { min, max } => (*min as f64, *max as f64),
+            NumberType::Float { min, max, .. } | NumberType::Multiple { min, max, .. } => {
+                (*min, *max)
+            }
+            NumberType::Enum { variants } => (
+                variants.keys().next().map_or(0.0, |&v| v as f64),
+                variants.keys().next_back().map_or(0.0, |&v| v as f64),
+            ),
+            NumberType::Sequence { min, max, .. } => (*min as f64, *max as f64),
+            NumberType::Flags { .. } => (0.0, NumberType::flag_mask(&self.flags()) as f64),
+        }
+    }
+
+    /// The flags of a bitmask, as the powers of two that its values are combinations of, in
+    /// increasing order. Empty for other number types.
+    pub fn flags(&self) -> Vec<i64> {
+        match self {
+            NumberType::Flags { values } => {
+                let mask = values.keys().fold(0, |mask, value| mask | value);
+                (0..63)
+                    .map(|bit| 1_i64 << bit)
+                    .filter(|flag| mask & flag != 0)
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// The value with all of the given flags set.
+    pub(crate) fn flag_mask(flags: &[i64]) -> i64 {
+        flags.iter().fold(0, |mask, flag| mask | flag)
+    }
+
+    /// The range of the values of `Multiple`, as the numbers of steps of its smallest and
+    /// largest values. Ends that are unbounded are 1e10 from zero, as for floats of no
+    /// particular range; `(0, -1)` for other number types.
+    pub(crate) fn multiples(&self) -> (i64, i64) {
+        let NumberType::Multiple { step, min, max, .. } = self else {
+            return (0, -1);
+        };
+        let min = if min.is_finite() {
+            *min
+        } else {
+            max.min(0.0) - 1e10
+        };
+        let max = if max.is_finite() {
+            *max
+        } else {
+            min.max(0.0) + 1e10
+        };
+        ((min / step).ceil() as i64, (max / step).floor() as i64)
+    }
+
+    /// The value of `Multiple` that is the given number of steps, rounded to the decimals of the
+    /// step so that it isn't written as `0.30000000000000004`.
+    pub(crate) fn multiple(&self, steps: i64) -> serde_json::Value {
+        let NumberType::Multiple { step, integer, .. } = self else {
+            return serde_json::Value::Null;
+        };
+        let value = steps as f64 * step;
+        if *integer {
+            return serde_json::json!(value as i64);
+        }
+        // the shortest representation of the step has as many decimals as it needs
+        let decimals = step.to_string().split_once('.').map_or(0, |(_, d)| d.len());
+        let rounded: f64 = format!("{:.*}", decimals, value).parse().unwrap_or(value);
+        serde_json::json!(rounded)
+    }
+
+    /// The average step between the values of a sequence, rounded to a positive integer.
+    pub(crate) fn sequence_step(start: i64, end: i64, count: usize) -> i64 {
+        if count < 2 {
+            return 1;
+        }
+        let step = (end as f64 - start as f64) / (count - 1) as f64;
+        (step.round() as i64).max(1)
+    }
+
+    /// Whether this is a float that looks like a ratio or a percentage, i.e. whose values vary
+    /// within 0 to 1, or within 0 to 100 with some above 10. Floats up to 10 are as often
+    /// ratings or measurements, and integers up to 100 counts or ages, so neither is classified.
+    pub fn numeric_range(&self) -> Option<NumericRange> {
+        match self {
+            NumberType::Float {
+                min,
+                max,
+                non_finite,
+            } if non_finite.is_empty() && *min >= 0.0 && min < max => {
+                if *max <= 1.0 {
+                    Some(NumericRange::Ratio)
+                } else if *max > 10.0 && *max <= 100.0 {
+                    Some(NumericRange::Percentage)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_within(&self, min: f64, max: f64) -> bool {
+        let (lower, upper) = self.bounds();
+        min <= lower && upper <= max
+    }
+}
+
+/// Whether coordinates are pairs of longitude and latitude, i.e. pairs whose values all fit the
+/// ranges of geographic coordinates.
+pub(crate) fn is_geographic(positions: &[NumberType]) -> bool {
+    matches!(positions, [lon, lat] if lon.is_within(-180.0, 180.0) && lat.is_within(-90.0, 90.0))
+}
+
+/// The latitude and longitude fields of an object with just those two, such as `lat` and `lng`,
+/// whose names are matched case-insensitively.
+pub(crate) fn geo_point_keys<'a>(
+    mut keys: impl Iterator<Item = &'a String>,
+) -> Option<(&'a String, &'a String)> {
+    let is_latitude = |key: &str| matches!(key.to_lowercase().as_str(), "lat" | "latitude");
+    let is_longitude = |key: &str| {
+        matches!(
+            key.to_lowercase().as_str(),
+            "lon" | "lng" | "long" | "longitude"
+        )
+    };
+    match (keys.next()?, keys.next()?, keys.next()) {
+        (first, second, None) if is_latitude(first) && is_longitude(second) => {
+            Some((first, second))
+        }
+        (first, second, None) if is_longitude(first) && is_latitude(second) => {
+            Some((second, first))
+        }
+        _ => None,
+    }
+}
+
+/// Joins the items of a list, such as the variants of an enum, with `separator`, leaving out
+/// those after the first `max_items` with a count of how many more there are, such as
+/// `a, b, c, … (+4 more)`.
+fn join_list(items: Vec<String>, separator: &str, max_items: Option<usize>) -> String {
+    match max_items {
+        Some(max) if items.len() > max => format!(
+            "{}{}… (+{} more)",
+            items[..max].join(separator),
+            if max == 0 { "" } else { separator },
+            items.len() - max
+        ),
+        _ => items.join(separator),
+    }
+}
+
+/// The flags of a bitmask as they are described, such as `1 | 2 | 4`.
+fn join_flags(flags: &[i64]) -> String {
+    flags
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+impl NumberType {
+    /// Describes this type as [`SchemaState::to_string_pretty_with`] does, listing at most
+    /// `max_list_items` of the variants of an enum or flags of a bitmask.
+    fn describe(&self, max_list_items: Option<usize>) -> String {
+        match self {
+            NumberType::Integer { min, max } => {
+                if min != max {
+                    format!("int ({}-{})", min, max)
+                } else {
+                    format!("int ({})", min)
+                }
+            }
+            NumberType::Float {
+                min,
+                max,
+                non_finite,
+            } => {
+                let mut values = if min.is_nan() {
+                    vec![]
+                } else if min != max {
+                    vec![format!("{}-{}", min, max)]
+                } else {
+                    vec![min.to_string()]
+                };
+                values.extend(non_finite.iter().map(|value| value.token().to_string()));
+                format!("float ({})", values.join(", "))
+            }
+            NumberType::Enum { variants } => {
+                let values = variants.keys().map(|v| v.to_string()).collect::<Vec<_>>();
+                format!("int (enum: {})", join_list(values, ", ", max_list_items))
+            }
+            NumberType::Sequence {
+                start, end, count, ..
+            } => format!(
+                "int (sequence from {}, step {})",
+                start,
+                NumberType::sequence_step(*start, *end, *count)
+            ),
+            NumberType::Flags { .. } => {
+                let flags = self.flags().iter().map(|flag| flag.to_string()).collect();
+                format!("int (flags: {})", join_list(flags, " | ", max_list_items))
+            }
+            NumberType::Multiple {
+                step,
+                min,
+                max,
+                integer,
+            } => {
+                let kind = if *integer { "int" } else { "float" };
+                // the ends are those of the multiples, not of the range, which may be exclusive
+                let (lowest, highest) = self.multiples();
+                let (lowest, highest) = (self.multiple(lowest), self.multiple(highest));
+                match (min.is_finite(), max.is_finite()) {
+                    (true, true) => {
+                        format!("{} (multiples of {}, {}-{})", kind, step, lowest, highest)
+                    }
+                    (true, false) => format!("{} (multiples of {}, from {})", kind, step, lowest),
+                    (false, true) => format!("{} (multiples of {}, up to {})", kind, step, highest),
+                    (false, false) => format!("{} (multiples of {})", kind, step),
+                }
+            }
+        }
+    }
+}
+
+impl Display for NumberType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe(None))
+    }
+}
+
+/// Values in MongoDB extended JSON, as written by e.g. `mongoexport`, which wraps values that have
+/// no JSON equivalent in a single-field object such as `{"$oid": "..."}`.
+///
+/// These are only inferred when recognition of extended JSON is enabled. Data is produced in the
+/// same wrapped form, so that it can be imported with `mongoimport`.
+///
+/// # JSON Schema Output
+///
+/// The wrapper object is emitted as-is, with a single required property describing the wrapped
+/// value.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ExtendedJsonType {
+    /// An ObjectId, `{"$oid": "<24 hex digits>"}`.
+    ObjectId,
+    /// A date, `{"$date": "<ISO 8601 date-time>"}`.
+    Date,
+    /// A 64-bit integer encoded as a string, `{"$numberLong": "<integer>"}`, with the range of
+    /// observed values.
+    Long { min: i64, max: i64 },
+}
+
+impl Display for ExtendedJsonType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtendedJsonType::ObjectId => write!(f, "objectid"),
+            ExtendedJsonType::Date => write!(f, "date (extended json)"),
+            ExtendedJsonType::Long { min, max } => {
+                if min != max {
+                    write!(f, "long ({}-{})", min, max)
+                } else {
+                    write!(f, "long ({})", min)
+                }
+            }
+        }
+    }
+}
+
+/// Whether a field of an object is only read or only written, as marked with `readOnly` or
+/// `writeOnly` in JSON Schema.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldAccess {
+    /// The field is managed by the owner of the data, such as a server-generated `id`, and is
+    /// left out of data that is sent to it, such as a request body.
+    ReadOnly,
+    /// The field is only ever sent, such as a `password`, and is left out of data that is
+    /// retrieved.
+    WriteOnly,
+}
+
+/// The SchemaState enum is a recursive data structure that describes the schema of a given JSON structure.
+///
+/// There are a few notable differences with the data types from the JSON specification:
+/// - The SchemaState enum has Initial and Indefinite variants. These encode two possible results of the
+///   schema inference process that have no equivalents in the JSON specification.
+/// - The String and Number types have an inner type that specialises the more generic types. This is to
+///   add some further semantics to the data type, provided `drivel` is able to infer these semantics.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SchemaState {
     /// Initial state.
     Initial,
     /// Represents a null value.
@@ -519,79 +1608,616 @@ pub enum SchemaState {
         min_length: usize,
         /// Maximum length of the array.
         max_length: usize,
+        /// How many times each array length was observed, if the length distribution is tracked.
+        #[serde(default)]
+        lengths: Option<std::collections::BTreeMap<usize, usize>>,
+        /// Whether no array had the same element twice, such as a set of tags. Only inferred for
+        /// arrays of strings, numbers and booleans, and only once some array had more than one
+        /// element.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        unique_items: bool,
         /// Schema for the elements of the array.
         schema: Box<SchemaState>,
     },
     /// Represents an object with required and optional fields and their corresponding schemas.
     Object {
-        /// Required fields and their schemas.
-        required: std::collections::HashMap<String, SchemaState>,
-        /// Optional fields and their schemas.
-        optional: std::collections::HashMap<String, SchemaState>,
+        /// Required fields and their schemas, in the order in which they were first seen.
+        required: IndexMap<String, SchemaState>,
+        /// Optional fields and their schemas, in the order in which they were first seen.
+        optional: IndexMap<String, SchemaState>,
+        /// The fields that are only read or only written, such as a server-generated `id` or a
+        /// `password`. Fields that are both read and written are not listed.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        access: std::collections::BTreeMap<String, FieldAccess>,
+        /// The keywords of the schemas of fields that drivel has no use for, such as `title`,
+        /// `description` or `x-` extensions, as they were in the JSON Schema this was parsed
+        /// from, to be written back out unchanged.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        annotations: std::collections::BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
     },
+    /// Represents an object used as a map, whose keys are data rather than field names, with a
+    /// single schema for all of its values.
+    Map {
+        /// Minimum number of entries in the map.
+        min_length: usize,
+        /// Maximum number of entries in the map.
+        max_length: usize,
+        /// The type of the keys of the map.
+        keys: StringType,
+        /// Schema for the values of the map.
+        schema: Box<SchemaState>,
+    },
+    /// Represents a value in MongoDB extended JSON.
+    ExtendedJson(ExtendedJsonType),
+    /// Represents a fixed-length array of numbers that are the positions of a coordinate, such
+    /// as `[lon, lat]` or `[x, y, z]`, with a number type for each position.
+    Coordinates(Vec<NumberType>),
+    /// Represents a fixed-length array whose positions are of different types, such as
+    /// `[name, count, flag]`, with a schema for each position.
+    Tuple(Vec<SchemaState>),
+    /// Represents a geographic point as an object of a latitude and a longitude, such as
+    /// `{"lat": 52.37, "lng": 4.89}`, with the names of the two fields as they were seen.
+    GeoPoint {
+        /// The name of the latitude field, such as `lat` or `Latitude`.
+        latitude_key: String,
+        /// The name of the longitude field, such as `lng`, `lon` or `longitude`.
+        longitude_key: String,
+        /// The latitudes seen.
+        latitude: NumberType,
+        /// The longitudes seen.
+        longitude: NumberType,
+    },
+    /// Represents an amount of money as an object of an amount and an ISO 4217 currency code,
+    /// such as `{"amount": 1299, "currency": "USD"}`, with the names of the two fields as they
+    /// were seen.
+    Money {
+        /// The name of the amount field, such as `amount`, `value` or `amount_cents`.
+        amount_key: String,
+        /// The name of the currency field, such as `currency` or `currencyCode`.
+        currency_key: String,
+        /// The amounts seen: integers if they are in minor units, such as cents, or floats if
+        /// they are decimal.
+        amount: NumberType,
+        /// The currency codes seen.
+        currencies: std::collections::BTreeSet<String>,
+    },
+    /// Represents a value that is one of several distinct schemas, such as the object shapes of
+    /// the records of a heterogeneous event log, or the variants of an `anyOf` or `oneOf` in a
+    /// JSON Schema.
+    Union(Vec<SchemaState>),
+    /// Represents a value of several types, such as a field that is a string in some records and
+    /// a number in others, with the schema of the values of each type and how many of them were
+    /// seen.
+    Mixed(Vec<(SchemaState, usize)>),
+    /// Represents a value that is one of several object shapes, told apart by the value of a tag
+    /// field, such as the `type` of the events of an event log.
+    TaggedUnion {
+        /// The name of the field whose value selects the shape, such as `type` or `kind`.
+        tag: String,
+        /// The schema of the objects with each value of the tag field, which includes the tag
+        /// field itself.
+        variants: std::collections::BTreeMap<String, SchemaState>,
+    },
+    /// Represents a value that is one of a few given values, such as the `const` or `enum` of a
+    /// JSON Schema, that aren't all strings or all integers, which are enums of their own types.
+    /// Never inferred: the values are only produced as they are.
+    Literal(Vec<serde_json::Value>),
+    /// Represents a value of a definition that contains values of itself, such as a node of a
+    /// tree whose children are nodes, from a recursive `$ref` of a JSON Schema. The values
+    /// within it that are of the definition again are [`SchemaState::Reference`]s to its name.
+    /// Never inferred.
+    Recursive {
+        /// The name of the definition, which is unique within a schema.
+        name: String,
+        schema: Box<SchemaState>,
+    },
+    /// Represents a value of the [`SchemaState::Recursive`] definition of the given name, which
+    /// this is within.
+    Reference(String),
     /// Represents an indefinite state.
     Indefinite,
 }
 
-fn to_string_pretty_inner(schema_state: &SchemaState, depth: usize) -> String {
+/// Options that customise the human-readable description of a schema, as
+/// [`SchemaState::to_string_pretty_with`] writes it. All of them are off by default, as in
+/// [`PrettyOptions::default`], describing the schema as [`SchemaState::to_string_pretty`] does.
+///
+/// More options are added over time, so the struct can't be built with a struct expression
+/// outside of this crate: start from the defaults with [`PrettyOptions::builder`] instead, or
+/// set the fields of [`PrettyOptions::default`].
+///
+/// # Example
+///
+/// ```
+/// use drivel::PrettyOptions;
+///
+/// let options = PrettyOptions::builder().max_list_items(5).sort_keys(true).build();
+/// assert_eq!(options.max_list_items, Some(5));
+/// assert!(!options.color);
+/// ```
+#[derive(Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct PrettyOptions<'a> {
+    /// The most values of each enum, bitmask and the like to list, followed by how many more
+    /// there are, such as `string (enum: a, b, … (+8 more))`, or `None` to list all of them.
+    pub max_list_items: Option<usize>,
+    /// The counts of values to list under the fields they occurred in, from the most to the
+    /// least common.
+    pub value_counts: Option<&'a ValueCounts>,
+    /// The distributions of the numbers, whose mean and standard deviation are listed after
+    /// their ranges.
+    pub number_stats: Option<&'a NumberStats>,
+    /// The statistics of the fields, by which those whose values were all different are
+    /// marked as `unique`, and optional fields are followed by how often they were present.
+    pub field_stats: Option<&'a FieldStats>,
+    /// Whether to list the fields of objects in the order of their names, rather than the
+    /// required fields before the optional ones.
+    pub sort_keys: bool,
+    /// Whether to highlight field names, types and keywords with ANSI colors, for a terminal.
+    pub color: bool,
+    /// The depth of nesting from which objects and tuples are summarised rather than described
+    /// field by field, if any.
+    pub max_depth: Option<usize>,
+    /// Whether to align the descriptions of the fields of each object in a column.
+    pub align_fields: bool,
+    /// Whether to label floats that look like ratios or percentages as such.
+    pub numeric_ranges: bool,
+}
+
+impl<'a> PrettyOptions<'a> {
+    /// A builder of options, starting from the defaults.
+    pub fn builder() -> PrettyOptionsBuilder<'a> {
+        PrettyOptionsBuilder::default()
+    }
+}
+
+/// Builds [`PrettyOptions`] one option at a time, starting from the defaults. Each method sets
+/// the option of the same name; see there for what it does.
+#[derive(Default)]
+pub struct PrettyOptionsBuilder<'a> {
+    options: PrettyOptions<'a>,
+}
+
+impl<'a> PrettyOptionsBuilder<'a> {
+    /// Sets [`max_list_items`](PrettyOptions::max_list_items).
+    pub fn max_list_items(mut self, max_list_items: usize) -> Self {
+        self.options.max_list_items = Some(max_list_items);
+        self
+    }
+
+    /// Sets [`value_counts`](PrettyOptions::value_counts).
+    pub fn value_counts(mut self, value_counts: &'a ValueCounts) -> Self {
+        self.options.value_counts = Some(value_counts);
+        self
+    }
+
+    /// Sets [`number_stats`](PrettyOptions::number_stats).
+    pub fn number_stats(mut self, number_stats: &'a NumberStats) -> Self {
+        self.options.number_stats = Some(number_stats);
+        self
+    }
+
+    /// Sets [`field_stats`](PrettyOptions::field_stats).
+    pub fn field_stats(mut self, field_stats: &'a FieldStats) -> Self {
+        self.options.field_stats = Some(field_stats);
+        self
+    }
+
+    /// Sets [`sort_keys`](PrettyOptions::sort_keys).
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.options.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets [`color`](PrettyOptions::color).
+    pub fn color(mut self, color: bool) -> Self {
+        self.options.color = color;
+        self
+    }
+
+    /// Sets [`max_depth`](PrettyOptions::max_depth).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets [`align_fields`](PrettyOptions::align_fields).
+    pub fn align_fields(mut self, align_fields: bool) -> Self {
+        self.options.align_fields = align_fields;
+        self
+    }
+
+    /// Sets [`numeric_ranges`](PrettyOptions::numeric_ranges).
+    pub fn numeric_ranges(mut self, numeric_ranges: bool) -> Self {
+        self.options.numeric_ranges = numeric_ranges;
+        self
+    }
+
+    /// The options built.
+    pub fn build(self) -> PrettyOptions<'a> {
+        self.options
+    }
+}
+
+/// The colors of the parts of a colored description.
+#[derive(Clone, Copy)]
+enum Highlight {
+    Field,
+    Keyword,
+    Text,
+    Number,
+    Boolean,
+    Other,
+    Elided,
+}
+
+impl Highlight {
+    /// The ANSI escape code of the color.
+    fn code(self) -> &'static str {
+        match self {
+            Highlight::Field => "1",
+            Highlight::Keyword => "33",
+            Highlight::Text => "32",
+            Highlight::Number => "36",
+            Highlight::Boolean => "35",
+            Highlight::Other => "34",
+            Highlight::Elided => "2",
+        }
+    }
+}
+
+impl PrettyOptions<'_> {
+    /// `text` in the color of `highlight`, if descriptions are colored.
+    fn paint(&self, highlight: Highlight, text: impl Display) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", highlight.code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Whether the objects and tuples at `depth` are too deeply nested to be described in full.
+    fn elides(&self, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max| depth >= max)
+    }
+
+    /// Describes a number at `path`, with the mean and standard deviation of the integers and
+    /// floats at it if they were tracked, such as `int (1-9, mean 4.2, std dev 1.3)`.
+    fn number(&self, number_type: &NumberType, path: &str) -> String {
+        let mut description = number_type.describe(self.max_list_items);
+        let range = number_type.numeric_range().filter(|_| self.numeric_ranges);
+        if let (Some(range), Some(start)) = (range, description.strip_suffix(')')) {
+            description = format!("{}, {})", start, range);
+        }
+        let distribution = match number_type {
+            NumberType::Integer { .. } | NumberType::Float { .. } => {
+                self.number_stats.and_then(|stats| stats.get(path))
+            }
+            _ => None,
+        };
+        let description = match (distribution, description.strip_suffix(')')) {
+            (Some(distribution), Some(description)) => format!(
+                "{}, mean {:.1}, std dev {:.1})",
+                description, distribution.mean, distribution.std_dev
+            ),
+            _ => description,
+        };
+        self.paint(Highlight::Number, description)
+    }
+
+    /// How often the optional field at `path` was present in the objects it could occur in,
+    /// such as ` (present in 94%)`, or nothing if that wasn't counted. The percentage is only
+    /// 0% or 100% if the field was never or always present.
+    fn presence(&self, path: &str) -> String {
+        let Some(counts) = self.field_stats.and_then(|stats| stats.get(path)) else {
+            return String::new();
+        };
+        let percentage = match (100.0 * counts.presence()).round() {
+            0.0 if counts.present > 0 => 1.0,
+            100.0 if counts.present < counts.objects => 99.0,
+            percentage => percentage,
+        };
+        format!(" (present in {}%)", percentage)
+    }
+
+    /// The lines listing how often each value occurred at `path`, such as `"open": 2 (66.7%)`,
+    /// each starting with a newline, or nothing if they weren't counted there.
+    fn value_counts(&self, path: &str, indent: &str) -> String {
+        let Some(values) = self.value_counts.and_then(|counts| counts.get(path)) else {
+            return String::new();
+        };
+        let total: usize = values.iter().map(|(_, count)| count).sum();
+        let lines = values
+            .iter()
+            .map(|(value, count)| {
+                let percentage = 100.0 * *count as f64 / total as f64;
+                format!("{}: {} ({:.1}%)", value, count, percentage)
+            })
+            .collect();
+        let separator = format!("\n{}", indent);
+        format!(
+            "{}{}",
+            separator,
+            join_list(lines, &separator, self.max_list_items)
+        )
+    }
+}
+
+/// Describes a schema at `path`, which is at the given depth of nesting.
+fn to_string_pretty_inner(
+    schema_state: &SchemaState,
+    depth: usize,
+    path: &str,
+    pretty: &PrettyOptions,
+) -> String {
+    let max_list_items = pretty.max_list_items;
     match schema_state {
-        SchemaState::Initial | SchemaState::Indefinite => "unknown".to_string(),
-        SchemaState::Null => "null".to_string(),
+        SchemaState::Initial | SchemaState::Indefinite => pretty.paint(Highlight::Other, "unknown"),
+        // a field can only have this schema if every value seen was null
+        SchemaState::Null => pretty.paint(Highlight::Other, "always null"),
         SchemaState::Nullable(state) => {
-            format!("nullable {}", to_string_pretty_inner(state, depth))
+            format!(
+                "{} {}",
+                pretty.paint(Highlight::Keyword, "nullable"),
+                to_string_pretty_inner(state, depth, path, pretty)
+            )
+        }
+        SchemaState::String(string_type) => {
+            pretty.paint(Highlight::Text, string_type.describe(max_list_items))
+        }
+        SchemaState::Number(number_type) => pretty.number(number_type, path),
+        SchemaState::ExtendedJson(extended_type) => pretty.paint(Highlight::Other, extended_type),
+        SchemaState::Coordinates(positions) if is_geographic(positions) => {
+            pretty.paint(Highlight::Other, "coordinates (lon, lat)")
+        }
+        SchemaState::Coordinates(positions) => {
+            let positions = positions
+                .iter()
+                .map(|position| position.describe(max_list_items))
+                .collect::<Vec<_>>()
+                .join(", ");
+            pretty.paint(Highlight::Other, format!("coordinates ({})", positions))
+        }
+        SchemaState::GeoPoint {
+            latitude_key,
+            longitude_key,
+            ..
+        } => pretty.paint(
+            Highlight::Other,
+            format!("geo point ({}, {})", latitude_key, longitude_key),
+        ),
+        SchemaState::Money {
+            amount_key,
+            currency_key,
+            amount,
+            currencies,
+        } => {
+            let unit = match amount {
+                NumberType::Integer { .. } => "in minor units",
+                _ => "decimal",
+            };
+            let currencies = currencies.iter().cloned().collect();
+            let currencies = join_list(currencies, ", ", max_list_items);
+            pretty.paint(
+                Highlight::Other,
+                format!(
+                    "money ({} {}, {}: {})",
+                    amount_key, unit, currency_key, currencies
+                ),
+            )
+        }
+        SchemaState::Boolean => pretty.paint(Highlight::Boolean, "boolean"),
+        SchemaState::Literal(values) if values.len() == 1 => {
+            pretty.paint(Highlight::Other, format!("always {}", values[0]))
+        }
+        SchemaState::Literal(values) => {
+            let values = values.iter().map(ToString::to_string).collect();
+            pretty.paint(
+                Highlight::Other,
+                format!("one of {}", join_list(values, ", ", max_list_items)),
+            )
+        }
+        SchemaState::Recursive { name, schema } => format!(
+            "{} {}",
+            to_string_pretty_inner(schema, depth, path, pretty),
+            pretty.paint(Highlight::Keyword, format!("(recursive {})", name))
+        ),
+        SchemaState::Reference(name) => {
+            pretty.paint(Highlight::Keyword, format!("recursive {}", name))
         }
-        SchemaState::String(string_type) => format!("{}", string_type),
-        SchemaState::Number(number_type) => format!("{}", number_type),
-        SchemaState::Boolean => "boolean".to_string(),
         SchemaState::Array {
             min_length,
             max_length,
+            unique_items,
             schema,
+            ..
         } => {
             let indent = 2 + 2 * depth;
             let indent_str = " ".repeat(indent);
             let indent_str_close = " ".repeat(indent - 2);
-            let length = if min_length != max_length {
-                format!("({}-{})", min_length, max_length)
-            } else {
-                format!("({})", min_length)
-            };
+            let length = array_length_string(*min_length, *max_length, *unique_items);
+            if pretty.elides(depth) {
+                let items = to_string_pretty_inner(schema, depth + 1, &items_path(path), pretty);
+                return format!("[{}] {}", items, length);
+            }
             format!(
                 "[\n{}{}\n{}] {}",
                 indent_str,
-                to_string_pretty_inner(schema, depth + 1),
+                to_string_pretty_inner(schema, depth + 1, &items_path(path), pretty),
                 indent_str_close,
                 length
             )
         }
-        SchemaState::Object { required, optional } => {
+        SchemaState::Tuple(positions) if pretty.elides(depth) => pretty.paint(
+            Highlight::Elided,
+            format!("[…] (tuple of {})", positions.len()),
+        ),
+        SchemaState::Tuple(positions) => {
             let indent = 2 + 2 * depth;
             let indent_str = " ".repeat(indent);
             let indent_str_close = " ".repeat(indent - 2);
+            let positions = positions
+                .iter()
+                .map(|position| {
+                    let position =
+                        to_string_pretty_inner(position, depth + 1, &items_path(path), pretty);
+                    format!("{}{}", indent_str, position)
+                })
+                .collect::<Vec<_>>()
+                .join(",\n");
+            format!("[\n{}\n{}] (tuple)", positions, indent_str_close)
+        }
+        SchemaState::Object {
+            required, optional, ..
+        } if pretty.elides(depth) => {
+            let fields = required.len() + optional.len();
+            let nested = schema_state.complexity().fields - fields;
+            let summary = match nested {
+                0 => format!("{{…}} ({} fields)", fields),
+                nested => format!("{{…}} ({} fields, {} nested)", fields, nested),
+            };
+            pretty.paint(Highlight::Elided, summary)
+        }
+        SchemaState::Object {
+            required,
+            optional,
+            access,
+            ..
+        } => {
+            let indent = 2 + 2 * depth;
+            let indent_str = " ".repeat(indent);
+            let indent_str_close = " ".repeat(indent - 2);
+            let keyword = |keyword: &str| match keyword {
+                "" => String::new(),
+                keyword => format!("{} ", pretty.paint(Highlight::Keyword, keyword)),
+            };
+            let access_str = |k: &String| match access.get(k) {
+                Some(FieldAccess::ReadOnly) => keyword("read-only"),
+                Some(FieldAccess::WriteOnly) => keyword("write-only"),
+                None => String::new(),
+            };
+            // with alignment, the descriptions of the fields start in the same column
+            let width = match pretty.align_fields {
+                true => required
+                    .keys()
+                    .chain(optional.keys())
+                    .map(|k| k.chars().count())
+                    .max()
+                    .unwrap_or(0),
+                false => 0,
+            };
 
-            let required_fields = required.iter().map(|(k, v)| {
-                format!(
-                    "{}\"{}\": {}",
-                    indent_str,
-                    k,
-                    to_string_pretty_inner(v, depth + 1)
-                )
-            });
-
-            let optional_fields = optional.iter().map(|(k, v)| {
-                format!(
-                    "{}\"{}\": optional {}",
+            // the counts of a field's values are listed under it, after the comma
+            let field = |k: &String, v: &SchemaState, presence: &str| {
+                let field_path = field_path(path, k);
+                let unique = pretty
+                    .field_stats
+                    .is_some_and(|stats| stats.is_unique(&field_path));
+                let padding = " ".repeat(width.saturating_sub(k.chars().count()));
+                let description = format!(
+                    "{}{}: {}{}{}{}{}{}",
                     indent_str,
-                    k,
-                    to_string_pretty_inner(v, depth + 1)
-                )
-            });
-
-            let all_fields: Vec<String> = required_fields.chain(optional_fields).collect();
-            let combined = all_fields.join(",\n");
+                    pretty.paint(Highlight::Field, format!("\"{}\"", k)),
+                    padding,
+                    keyword(presence),
+                    access_str(k),
+                    keyword(if unique { "unique" } else { "" }),
+                    to_string_pretty_inner(v, depth + 1, &field_path, pretty),
+                    if presence.is_empty() {
+                        String::new()
+                    } else {
+                        pretty.presence(&field_path)
+                    }
+                );
+                let counts = pretty.value_counts(&field_path, &format!("{}  ", indent_str));
+                (description, counts)
+            };
+            let mut fields: Vec<(&String, &SchemaState, &str)> = required
+                .iter()
+                .map(|(k, v)| (k, v, ""))
+                .chain(optional.iter().map(|(k, v)| (k, v, "optional")))
+                .collect();
+            // the required fields come first, each in the order in which they were first seen,
+            // unless the fields are sorted by name
+            if pretty.sort_keys {
+                fields.sort_by_key(|(key, _, _)| *key);
+            }
+            let all_fields: Vec<(String, String)> = fields
+                .into_iter()
+                .map(|(k, v, presence)| field(k, v, presence))
+                .collect();
+            let last = all_fields.len().saturating_sub(1);
+            let combined = all_fields
+                .into_iter()
+                .enumerate()
+                .map(|(i, (description, counts))| {
+                    let comma = if i < last { "," } else { "" };
+                    format!("{}{}{}", description, comma, counts)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
 
             format!("{{\n{}\n{}}}", combined, indent_str_close)
         }
+        SchemaState::Map {
+            min_length,
+            max_length,
+            keys,
+            schema,
+        } => {
+            let length = if min_length != max_length {
+                format!("({}-{})", min_length, max_length)
+            } else {
+                format!("({})", min_length)
+            };
+            format!(
+                "map<{}, {}> {}",
+                pretty.paint(Highlight::Text, keys.describe(max_list_items)),
+                to_string_pretty_inner(schema, depth, &field_path(path, "*"), pretty),
+                length
+            )
+        }
+        SchemaState::Union(variants) => variants
+            .iter()
+            .map(|variant| match variant {
+                // as one of several variants, a null is not always null
+                SchemaState::Null => pretty.paint(Highlight::Other, "null"),
+                variant => to_string_pretty_inner(variant, depth, path, pretty),
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+        SchemaState::Mixed(variants) => {
+            let total = variants
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>()
+                .max(1);
+            variants
+                .iter()
+                .map(|(variant, count)| {
+                    format!(
+                        "{} ({}%)",
+                        to_string_pretty_inner(variant, depth, path, pretty),
+                        (*count as f64 * 100.0 / total as f64).round()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        }
+        SchemaState::TaggedUnion { tag, variants } => {
+            let variants = variants
+                .iter()
+                .map(|(value, variant)| {
+                    format!(
+                        "\"{}\" => {}",
+                        value,
+                        to_string_pretty_inner(variant, depth, path, pretty)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" | ");
+            format!("tagged by '{}': {}", tag, variants)
+        }
     }
 }
 
@@ -605,10 +2231,11 @@ impl SchemaState {
     ///
     /// ```
     /// use drivel::{SchemaState, StringType, NumberType};
-    /// use std::collections::{HashMap, HashSet};
+    /// use indexmap::IndexMap;
+    /// use std::collections::HashSet;
     /// use std::iter::FromIterator;
     ///
-    /// let required = HashMap::from_iter(vec![
+    /// let required = IndexMap::from_iter(vec![
     ///     ("name".to_string(), SchemaState::String(StringType::Unknown {
     ///         strings_seen: vec!["abc".to_string()],
     ///         chars_seen: vec!['a', 'b', 'c'],
@@ -617,13 +2244,15 @@ impl SchemaState {
     ///     }))
     /// ]);
     ///
-    /// let optional = HashMap::from_iter(vec![
+    /// let optional = IndexMap::from_iter(vec![
     ///     ("age".to_string(), SchemaState::Number(NumberType::Integer { min: 0, max: 120 }))
     /// ]);
     ///
     /// let schema = SchemaState::Object {
     ///     required,
     ///     optional,
+    ///     access: Default::default(),
+    ///     annotations: Default::default(),
     /// };
     ///
     /// println!("{}", schema.to_string_pretty());
@@ -638,7 +2267,565 @@ impl SchemaState {
     /// }
     /// ```
     pub fn to_string_pretty(&self) -> String {
-        to_string_pretty_inner(self, 0)
+        to_string_pretty_inner(self, 0, "", &PrettyOptions::default())
+    }
+
+    /// Like [`SchemaState::to_string_pretty`], but with the statistics, lists, order of fields,
+    /// colors, depth and alignment of `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{NumberType, PrettyOptions, SchemaState, ValueCounts};
+    /// use serde_json::json;
+    /// use indexmap::IndexMap;
+    ///
+    /// let schema = SchemaState::Number(NumberType::Enum {
+    ///     variants: (1..=5).map(|n| (n, 1)).collect(),
+    /// });
+    /// let options = PrettyOptions::builder().max_list_items(2).build();
+    /// assert_eq!(schema.to_string_pretty_with(&options), "int (enum: 1, 2, … (+3 more))");
+    ///
+    /// let schema = SchemaState::Object {
+    ///     required: IndexMap::from([("active".to_string(), SchemaState::Boolean)]),
+    ///     optional: IndexMap::new(),
+    ///     access: Default::default(),
+    ///     annotations: Default::default(),
+    /// };
+    /// let mut counts = ValueCounts::default();
+    /// for active in [true, true, false, true] {
+    ///     counts.add(&json!({"active": active}));
+    /// }
+    /// let options = PrettyOptions::builder().value_counts(&counts).build();
+    /// assert_eq!(
+    ///     schema.to_string_pretty_with(&options),
+    ///     "{\n  \"active\": boolean\n    true: 3 (75.0%)\n    false: 1 (25.0%)\n}"
+    /// );
+    /// ```
+    pub fn to_string_pretty_with(&self, options: &PrettyOptions) -> String {
+        to_string_pretty_inner(self, 0, "", options)
+    }
+
+    /// Describes the schema as [`DescribeFormat::Human`](crate::DescribeFormat::Human) does,
+    /// with the statistics, order of fields, colors, depth and alignment of `options`.
+    pub(crate) fn to_string_pretty_with_options(&self, options: &crate::DescribeOptions) -> String {
+        let pretty = PrettyOptions {
+            max_list_items: options.max_list_items,
+            value_counts: options.json_schema.value_counts.as_ref(),
+            number_stats: options.number_stats.as_ref(),
+            field_stats: options.json_schema.field_stats.as_ref(),
+            sort_keys: options.sort_keys,
+            color: options.color,
+            max_depth: options.max_depth,
+            align_fields: options.align_fields,
+            numeric_ranges: options.json_schema.numeric_ranges,
+        };
+        self.to_string_pretty_with(&pretty)
+    }
+
+    /// Returns a tabular representation of the schema if it describes an array of objects.
+    ///
+    /// The array wrapper is collapsed and the element object is described as a record, with one
+    /// row per field. Fields of nested objects are flattened into dotted paths (e.g. `address.city`),
+    /// and a field is only listed as required if it is required at every level of nesting. The
+    /// rows are in the order of the fields of the schema.
+    ///
+    /// Returns `None` if the schema is not an array whose elements are all objects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drivel::{SchemaState, NumberType};
+    /// use indexmap::IndexMap;
+    ///
+    /// let schema = SchemaState::Array {
+    ///     min_length: 2,
+    ///     max_length: 2,
+    ///     lengths: None,
+    ///     unique_items: false,
+    ///     schema: Box::new(SchemaState::Object {
+    ///         required: IndexMap::from_iter([
+    ///             ("id".to_string(), SchemaState::Number(NumberType::Integer { min: 1, max: 2 })),
+    ///         ]),
+    ///         optional: IndexMap::from_iter([("active".to_string(), SchemaState::Boolean)]),
+    ///         access: Default::default(),
+    ///         annotations: Default::default(),
+    ///     }),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     schema.to_table_string().unwrap(),
+    ///     "record (2 rows)\n\nfield   type      required\nid      int (1-2) yes\nactive  boolean   no"
+    /// );
+    /// ```
+    pub fn to_table_string(&self) -> Option<String> {
+        let (min_length, max_length, element) = match self {
+            SchemaState::Array {
+                min_length,
+                max_length,
+                schema,
+                ..
+            } => (min_length, max_length, schema.as_ref()),
+            _ => return None,
+        };
+
+        if !matches!(element, SchemaState::Object { .. }) {
+            return None;
+        }
+
+        let mut rows = vec![];
+        collect_table_rows(element, "", true, &mut rows);
+
+        let header = ("field".to_string(), "type".to_string(), "required");
+        let field_width = rows
+            .iter()
+            .map(|(field, _, _)| field.len())
+            .chain([header.0.len()])
+            .max()
+            .unwrap_or(0);
+        let type_width = rows
+            .iter()
+            .map(|(_, type_, _)| type_.len())
+            .chain([header.1.len()])
+            .max()
+            .unwrap_or(0);
+
+        let lines = std::iter::once(header)
+            .chain(rows.into_iter().map(|(field, type_, required)| {
+                (field, type_, if required { "yes" } else { "no" })
+            }))
+            .map(|(field, type_, required)| {
+                format!("{:field_width$}  {:type_width$} {}", field, type_, required)
+            })
+            .collect::<Vec<_>>();
+
+        let rows = if min_length != max_length {
+            format!("{}-{}", min_length, max_length)
+        } else {
+            format!("{}", min_length)
+        };
+
+        Some(format!("record ({} rows)\n\n{}", rows, lines.join("\n")))
+    }
+
+    /// Returns an equivalent schema in a canonical form, so that schemas which only differ in how
+    /// their unions happen to be arranged compare equal.
+    ///
+    /// Normalization, applied throughout the schema:
+    /// - flattens unions nested in unions, and removes duplicate variants;
+    /// - sorts the variants of unions in a canonical order;
+    /// - replaces a union of a single variant with that variant;
+    /// - hoists `null` variants and nullable variants of a union into a nullable union, and
+    ///   collapses nullable `null`s and nullables of nullables.
+    ///
+    /// Everything else, including the statistics gathered during inference (such as the
+    /// characters seen in strings), is preserved. What is not preserved is how often each variant
+    /// of a union is picked when producing data, which depends on their number and arrangement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drivel::SchemaState;
+    ///
+    /// let nested = SchemaState::Union(vec![
+    ///     SchemaState::Boolean,
+    ///     SchemaState::Union(vec![SchemaState::Null, SchemaState::Boolean]),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     nested.normalize(),
+    ///     SchemaState::Nullable(Box::new(SchemaState::Boolean))
+    /// );
+    /// ```
+    pub fn normalize(self) -> SchemaState {
+        match self {
+            SchemaState::Nullable(inner) => match inner.normalize() {
+                inner @ (SchemaState::Null | SchemaState::Nullable(_)) => inner,
+                inner => SchemaState::Nullable(Box::new(inner)),
+            },
+            SchemaState::Array {
+                min_length,
+                max_length,
+                lengths,
+                unique_items,
+                schema,
+            } => SchemaState::Array {
+                min_length,
+                max_length,
+                lengths,
+                unique_items,
+                schema: Box::new(schema.normalize()),
+            },
+            SchemaState::Tuple(positions) => {
+                SchemaState::Tuple(positions.into_iter().map(SchemaState::normalize).collect())
+            }
+            SchemaState::Object {
+                required,
+                optional,
+                access,
+                annotations,
+            } => SchemaState::Object {
+                required: normalize_fields(required),
+                optional: normalize_fields(optional),
+                access: access.clone(),
+                annotations: annotations.clone(),
+            },
+            SchemaState::Map {
+                min_length,
+                max_length,
+                keys,
+                schema,
+            } => SchemaState::Map {
+                min_length,
+                max_length,
+                keys,
+                schema: Box::new(schema.normalize()),
+            },
+            SchemaState::Union(variants) => normalize_union(variants),
+            SchemaState::Mixed(variants) => {
+                let mut variants: Vec<_> = variants
+                    .into_iter()
+                    .map(|(variant, count)| (variant.normalize(), count))
+                    .collect();
+                variants.sort_by_cached_key(|(variant, _)| variant_sort_key(variant));
+                SchemaState::Mixed(variants)
+            }
+            SchemaState::TaggedUnion { tag, variants } => SchemaState::TaggedUnion {
+                tag,
+                variants: variants
+                    .into_iter()
+                    .map(|(value, variant)| (value, variant.normalize()))
+                    .collect(),
+            },
+            SchemaState::Recursive { name, schema } => SchemaState::Recursive {
+                name,
+                schema: Box::new(schema.normalize()),
+            },
+            other => other,
+        }
+    }
+
+    /// Whether two schemas are the same once [normalized](SchemaState::normalize).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drivel::SchemaState;
+    ///
+    /// let a = SchemaState::Union(vec![SchemaState::Boolean, SchemaState::Null]);
+    /// let b = SchemaState::Nullable(Box::new(SchemaState::Boolean));
+    ///
+    /// assert_ne!(a, b);
+    /// assert!(a.equivalent(&b));
+    /// ```
+    pub fn equivalent(&self, other: &SchemaState) -> bool {
+        self.clone().normalize() == other.clone().normalize()
+    }
+
+    /// Sorts the fields of every object in the schema by name, which are otherwise in the order
+    /// in which they were first seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions, SchemaState};
+    /// use serde_json::json;
+    ///
+    /// let mut schema = infer_schema(json!({"b": 1, "a": 2}), &InferenceOptions::default());
+    /// schema.sort_keys();
+    ///
+    /// let SchemaState::Object { required, .. } = schema else { unreachable!() };
+    /// assert_eq!(required.keys().collect::<Vec<_>>(), ["a", "b"]);
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match self {
+            SchemaState::Nullable(schema)
+            | SchemaState::Array { schema, .. }
+            | SchemaState::Map { schema, .. }
+            | SchemaState::Recursive { schema, .. } => schema.sort_keys(),
+            SchemaState::Tuple(schemas) | SchemaState::Union(schemas) => {
+                schemas.iter_mut().for_each(SchemaState::sort_keys)
+            }
+            SchemaState::Mixed(variants) => variants
+                .iter_mut()
+                .for_each(|(variant, _)| variant.sort_keys()),
+            SchemaState::TaggedUnion { variants, .. } => {
+                variants.values_mut().for_each(SchemaState::sort_keys)
+            }
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                for fields in [required, optional] {
+                    fields.sort_keys();
+                    fields.values_mut().for_each(SchemaState::sort_keys);
+                }
+            }
+            SchemaState::Literal(values) => values
+                .iter_mut()
+                .for_each(serde_json::Value::sort_all_objects),
+            _ => {}
+        }
+    }
+
+    /// The schema that the values of a [`SchemaState::Literal`] would be inferred as, for the
+    /// outputs that declare types rather than values, such as a `bool` for `true`.
+    pub(crate) fn literal_type(values: &[serde_json::Value]) -> SchemaState {
+        crate::infer_schema_from_iter(values.to_vec(), &crate::InferenceOptions::default())
+    }
+
+    /// Whether the schema has values that don't contain a [`SchemaState::Reference`] to any of
+    /// the recursive definitions that `cut` is true for, by leaving out optional fields, having
+    /// no elements or picking other variants, so that values can stop recursing into them.
+    pub(crate) fn can_avoid(&self, cut: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            SchemaState::Reference(name) => !cut(name),
+            SchemaState::Recursive { schema, .. } => schema.can_avoid(cut),
+            SchemaState::Array {
+                min_length, schema, ..
+            }
+            | SchemaState::Map {
+                min_length, schema, ..
+            } => *min_length == 0 || schema.can_avoid(cut),
+            SchemaState::Tuple(positions) => positions.iter().all(|p| p.can_avoid(cut)),
+            SchemaState::Object { required, .. } => required.values().all(|f| f.can_avoid(cut)),
+            SchemaState::Union(variants) => variants.iter().any(|v| v.can_avoid(cut)),
+            SchemaState::Mixed(variants) => variants.iter().any(|(v, _)| v.can_avoid(cut)),
+            SchemaState::TaggedUnion { variants, .. } => {
+                variants.values().any(|v| v.can_avoid(cut))
+            }
+            _ => true,
+        }
+    }
+}
+
+fn normalize_fields(fields: IndexMap<String, SchemaState>) -> IndexMap<String, SchemaState> {
+    fields
+        .into_iter()
+        .map(|(key, schema)| (key, schema.normalize()))
+        .collect()
+}
+
+/// A key to sort the variants of unions by, with the fields of objects sorted by name, so that
+/// the key doesn't depend on the order in which they were seen.
+fn variant_sort_key(schema: &SchemaState) -> String {
+    serde_json::to_value(schema)
+        .map(|mut value| {
+            value.sort_all_objects();
+            value.to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn normalize_union(variants: Vec<SchemaState>) -> SchemaState {
+    fn flatten_into(variant: SchemaState, flattened: &mut Vec<SchemaState>, nullable: &mut bool) {
+        match variant {
+            SchemaState::Union(variants) => {
+                for variant in variants {
+                    flatten_into(variant, flattened, nullable);
+                }
+            }
+            SchemaState::Null => *nullable = true,
+            SchemaState::Nullable(inner) => {
+                *nullable = true;
+                flatten_into(*inner, flattened, nullable);
+            }
+            variant => {
+                if !flattened.contains(&variant) {
+                    flattened.push(variant);
+                }
+            }
+        }
+    }
+
+    if variants.is_empty() {
+        return SchemaState::Union(variants);
+    }
+
+    let mut flattened = vec![];
+    let mut nullable = false;
+    for variant in variants {
+        flatten_into(variant.normalize(), &mut flattened, &mut nullable);
+    }
+    flattened.sort_by_cached_key(variant_sort_key);
+
+    let union = match flattened.len() {
+        0 => SchemaState::Null,
+        1 => flattened.pop().unwrap(),
+        _ => SchemaState::Union(flattened),
+    };
+    if nullable && union != SchemaState::Null {
+        SchemaState::Nullable(Box::new(union))
+    } else {
+        union
+    }
+}
+
+/// The lengths of an array, such as `(1-3)`, and whether its elements are distinct.
+fn array_length_string(min_length: usize, max_length: usize, unique_items: bool) -> String {
+    let unique = if unique_items { ", unique" } else { "" };
+    if min_length != max_length {
+        format!("({}-{}{})", min_length, max_length, unique)
+    } else {
+        format!("({}{})", min_length, unique)
+    }
+}
+
+fn to_string_inline(schema_state: &SchemaState) -> String {
+    match schema_state {
+        SchemaState::Nullable(inner) => format!("nullable {}", to_string_inline(inner)),
+        SchemaState::Recursive { name, schema } => {
+            format!("{} (recursive {})", to_string_inline(schema), name)
+        }
+        SchemaState::Object { .. } => "object".to_string(),
+        SchemaState::Array {
+            min_length,
+            max_length,
+            unique_items,
+            schema,
+            ..
+        } => {
+            let length = array_length_string(*min_length, *max_length, *unique_items);
+            format!("[{}] {}", to_string_inline(schema), length)
+        }
+        SchemaState::Tuple(positions) => {
+            let positions: Vec<_> = positions.iter().map(to_string_inline).collect();
+            format!("[{}] (tuple)", positions.join(", "))
+        }
+        _ => to_string_pretty_inner(schema_state, 0, "", &PrettyOptions::default()),
+    }
+}
+
+fn collect_table_rows(
+    schema_state: &SchemaState,
+    prefix: &str,
+    required: bool,
+    rows: &mut Vec<(String, String, bool)>,
+) {
+    if let SchemaState::Object {
+        required: required_fields,
+        optional: optional_fields,
+        ..
+    } = schema_state
+    {
+        let fields = required_fields
+            .iter()
+            .map(|(k, v)| (k, v, required))
+            .chain(optional_fields.iter().map(|(k, v)| (k, v, false)));
+
+        for (key, schema, required) in fields {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+
+            match schema {
+                SchemaState::Object {
+                    required: nested_required,
+                    optional: nested_optional,
+                    ..
+                } if !(nested_required.is_empty() && nested_optional.is_empty()) => {
+                    collect_table_rows(schema, &path, required, rows)
+                }
+                _ => rows.push((path, to_string_inline(schema), required)),
+            }
+        }
+    }
+}
+
+impl SchemaState {
+    /// Calls `f` with each number of the schema and its part of `schema`, the JSON Schema of
+    /// the schema, other than the longitudes and latitudes of geographic coordinates, which
+    /// their ranges bound already.
+    fn for_each_number(
+        &self,
+        schema: &mut serde_json::Value,
+        f: &mut dyn FnMut(&NumberType, &mut serde_json::Value),
+    ) {
+        match self {
+            SchemaState::Number(number_type) => f(number_type, schema),
+            SchemaState::Coordinates(positions) if !is_geographic(positions) => {
+                if let Some(position_schemas) =
+                    schema.get_mut("prefixItems").and_then(|v| v.as_array_mut())
+                {
+                    for (position, position_schema) in positions.iter().zip(position_schemas) {
+                        f(position, position_schema);
+                    }
+                }
+            }
+            SchemaState::Tuple(positions) => {
+                if let Some(position_schemas) =
+                    schema.get_mut("prefixItems").and_then(|v| v.as_array_mut())
+                {
+                    for (position, position_schema) in positions.iter().zip(position_schemas) {
+                        position.for_each_number(position_schema, f);
+                    }
+                }
+            }
+            SchemaState::Money {
+                amount_key, amount, ..
+            } => {
+                if let Some(amount_schema) = schema
+                    .get_mut("properties")
+                    .and_then(|properties| properties.get_mut(amount_key))
+                {
+                    f(amount, amount_schema);
+                }
+            }
+            SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+                inner.for_each_number(schema, f)
+            }
+            SchemaState::Array { schema: items, .. } => {
+                if let Some(items_schema) = schema.get_mut("items") {
+                    items.for_each_number(items_schema, f);
+                }
+            }
+            SchemaState::Map { schema: values, .. } => {
+                if let Some(values_schema) = schema.get_mut("additionalProperties") {
+                    values.for_each_number(values_schema, f);
+                }
+            }
+            SchemaState::Union(variants) => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("oneOf").and_then(|v| v.as_array_mut())
+                {
+                    for (variant, variant_schema) in variants.iter().zip(variant_schemas) {
+                        variant.for_each_number(variant_schema, f);
+                    }
+                }
+            }
+            SchemaState::Mixed(variants) => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("anyOf").and_then(|v| v.as_array_mut())
+                {
+                    for ((variant, _), variant_schema) in variants.iter().zip(variant_schemas) {
+                        variant.for_each_number(variant_schema, f);
+                    }
+                }
+            }
+            SchemaState::TaggedUnion { variants, .. } => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("oneOf").and_then(|v| v.as_array_mut())
+                {
+                    for (variant, variant_schema) in variants.values().zip(variant_schemas) {
+                        variant.for_each_number(variant_schema, f);
+                    }
+                }
+            }
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                for (key, field) in required.iter().chain(optional) {
+                    if let Some(field_schema) = schema
+                        .get_mut("properties")
+                        .and_then(|properties| properties.get_mut(key))
+                    {
+                        field.for_each_number(field_schema, f);
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -648,8 +2835,50 @@ impl ToJsonSchema for SchemaState {
             SchemaState::Boolean => serde_json::json!({ "type": "boolean" }),
             SchemaState::Null => serde_json::json!({ "type": "null" }),
             SchemaState::Initial | SchemaState::Indefinite => serde_json::json!({}),
+            SchemaState::Literal(values) if values.len() == 1 => {
+                serde_json::json!({ "const": values[0] })
+            }
+            SchemaState::Literal(values) => serde_json::json!({ "enum": values }),
+            // the values within the definition refer back to it by its name, as an anchor
+            SchemaState::Recursive { name, schema } => {
+                let mut recursive = schema.to_json_schema();
+                if let Some(recursive_obj) = recursive.as_object_mut() {
+                    recursive_obj.insert("$anchor".to_string(), serde_json::json!(name));
+                }
+                recursive
+            }
+            SchemaState::Reference(name) => serde_json::json!({ "$ref": format!("#{}", name) }),
             SchemaState::String(string_type) => string_type.to_json_schema(),
             SchemaState::Number(number_type) => number_type.to_json_schema(),
+            SchemaState::ExtendedJson(extended_type) => extended_type.to_json_schema(),
+            SchemaState::Coordinates(positions) => {
+                let mut position_schemas: Vec<_> =
+                    positions.iter().map(|p| p.to_json_schema()).collect();
+                if is_geographic(positions) {
+                    for (schema, range) in position_schemas.iter_mut().zip([180, 90]) {
+                        schema["minimum"] = serde_json::json!(-range);
+                        schema["maximum"] = serde_json::json!(range);
+                    }
+                }
+                serde_json::json!({
+                    "type": "array",
+                    "prefixItems": position_schemas,
+                    "items": false,
+                    "minItems": positions.len(),
+                    "maxItems": positions.len()
+                })
+            }
+            SchemaState::Tuple(positions) => {
+                let position_schemas: Vec<_> =
+                    positions.iter().map(|p| p.to_json_schema()).collect();
+                serde_json::json!({
+                    "type": "array",
+                    "prefixItems": position_schemas,
+                    "items": false,
+                    "minItems": positions.len(),
+                    "maxItems": positions.len()
+                })
+            }
             SchemaState::Nullable(inner) => {
                 let mut inner_schema = inner.to_json_schema();
 
@@ -658,17 +2887,145 @@ impl ToJsonSchema for SchemaState {
                     if let Some(type_str) = type_value.as_str() {
                         inner_schema["type"] = serde_json::json!([type_str, "null"]);
                     }
+                } else if let Some(variants) = ["oneOf", "anyOf"]
+                    .into_iter()
+                    .find(|keyword| inner_schema.get(keyword).is_some())
+                    .and_then(|keyword| inner_schema.get_mut(keyword))
+                    .and_then(|v| v.as_array_mut())
+                {
+                    variants.push(serde_json::json!({ "type": "null" }));
+                }
+                // the values of an enum or const are all that validate, so null must be one
+                if let Some(value) = inner_schema.as_object_mut().and_then(|s| s.remove("const")) {
+                    inner_schema["enum"] = serde_json::json!([value]);
+                }
+                if let Some(values) = inner_schema.get_mut("enum").and_then(|v| v.as_array_mut()) {
+                    values.push(serde_json::Value::Null);
+                }
+
+                inner_schema
+            }
+            SchemaState::Array {
+                unique_items,
+                schema,
+                ..
+            } => {
+                let mut array_schema = serde_json::json!({
+                    "type": "array",
+                    "items": schema.to_json_schema()
+                });
+                if *unique_items {
+                    array_schema["uniqueItems"] = serde_json::json!(true);
+                }
+                array_schema
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            } => {
+                let mut properties = serde_json::Map::new();
+                for (key, number_type, range) in [
+                    (latitude_key, latitude, 90),
+                    (longitude_key, longitude, 180),
+                ] {
+                    let mut schema = number_type.to_json_schema();
+                    schema["minimum"] = serde_json::json!(-range);
+                    schema["maximum"] = serde_json::json!(range);
+                    properties.insert(key.clone(), schema);
+                }
+                serde_json::json!({
+                    "type": "object",
+                    "x-drivel-type": "geo-point",
+                    "properties": properties,
+                    "required": [latitude_key, longitude_key],
+                    "additionalProperties": false
+                })
+            }
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => {
+                let mut amount_schema = match amount {
+                    NumberType::Integer { .. } => serde_json::json!({ "type": "integer" }),
+                    _ => serde_json::json!({ "type": "number" }),
+                };
+                // amounts that were never negative, such as prices, stay so
+                if amount.is_within(0.0, f64::INFINITY) {
+                    amount_schema["minimum"] = serde_json::json!(0);
                 }
-
-                inner_schema
+                let currency_schema = if currencies.is_empty() {
+                    serde_json::json!({ "type": "string", "pattern": "^[A-Z]{3}$" })
+                } else {
+                    serde_json::json!({ "type": "string", "enum": currencies })
+                };
+                let mut properties = serde_json::Map::new();
+                properties.insert(amount_key.clone(), amount_schema);
+                properties.insert(currency_key.clone(), currency_schema);
+                serde_json::json!({
+                    "type": "object",
+                    "x-drivel-type": "money",
+                    "properties": properties,
+                    "required": [amount_key, currency_key],
+                    "additionalProperties": false
+                })
             }
-            SchemaState::Array { schema, .. } => {
+            SchemaState::Map { schema, .. } => {
                 serde_json::json!({
-                    "type": "array",
-                    "items": schema.to_json_schema()
+                    "type": "object",
+                    "additionalProperties": schema.to_json_schema()
+                })
+            }
+            SchemaState::Union(variants) => {
+                let variants: Vec<_> = variants.iter().map(|v| v.to_json_schema()).collect();
+                serde_json::json!({ "oneOf": variants })
+            }
+            // with how many values of each variant were seen, to be read back
+            SchemaState::Mixed(variants) => {
+                let variants: Vec<_> = variants
+                    .iter()
+                    .map(|(variant, count)| {
+                        let mut schema = variant.to_json_schema();
+                        if let Some(schema) = schema.as_object_mut() {
+                            schema.insert("x-drivel-count".to_string(), serde_json::json!(count));
+                        }
+                        schema
+                    })
+                    .collect();
+                serde_json::json!({ "anyOf": variants })
+            }
+            SchemaState::TaggedUnion { tag, variants } => {
+                let variants: Vec<_> = variants
+                    .iter()
+                    .map(|(value, variant)| {
+                        let mut schema = variant.to_json_schema();
+                        if let Some(properties) = schema
+                            .get_mut("properties")
+                            .and_then(|properties| properties.as_object_mut())
+                        {
+                            // an enum of one value rather than `const`, which OpenAPI 3.0 lacks
+                            properties.insert(
+                                tag.clone(),
+                                serde_json::json!({ "type": "string", "enum": [value] }),
+                            );
+                        }
+                        schema
+                    })
+                    .collect();
+                serde_json::json!({
+                    "oneOf": variants,
+                    "discriminator": { "propertyName": tag }
                 })
             }
-            SchemaState::Object { required, optional } => {
+            SchemaState::Object {
+                required,
+                optional,
+                access,
+                annotations,
+            } => {
                 let mut properties = serde_json::Map::new();
                 let mut required_fields = Vec::new();
 
@@ -683,6 +3040,22 @@ impl ToJsonSchema for SchemaState {
                     properties.insert(key.clone(), schema.to_json_schema());
                 }
 
+                for (key, access) in access {
+                    let keyword = match access {
+                        FieldAccess::ReadOnly => "readOnly",
+                        FieldAccess::WriteOnly => "writeOnly",
+                    };
+                    if let Some(property) = properties.get_mut(key) {
+                        property[keyword] = serde_json::Value::Bool(true);
+                    }
+                }
+
+                for (key, keywords) in annotations {
+                    if let Some(serde_json::Value::Object(property)) = properties.get_mut(key) {
+                        property.extend(keywords.clone());
+                    }
+                }
+
                 serde_json::json!({
                     "type": "object",
                     "properties": properties,
@@ -692,6 +3065,108 @@ impl ToJsonSchema for SchemaState {
             }
         }
     }
+
+    fn add_numeric_bounds(&self, schema: &mut serde_json::Value, style: NumericBounds) {
+        self.for_each_number(schema, &mut |number_type, schema| {
+            number_type.add_numeric_bounds(schema, style)
+        });
+    }
+
+    fn add_numeric_ranges(&self, schema: &mut serde_json::Value) {
+        self.for_each_number(schema, &mut |number_type, schema| {
+            number_type.add_numeric_ranges(schema)
+        });
+    }
+
+    fn add_lengths(&self, schema: &mut serde_json::Value) {
+        let lengths = |schema: &mut serde_json::Value, keywords: [&str; 2], min, max| {
+            let [min_keyword, max_keyword] = keywords;
+            schema[min_keyword] = serde_json::json!(min);
+            schema[max_keyword] = serde_json::json!(max);
+        };
+        match self {
+            SchemaState::String(string_type) => string_type.add_lengths(schema),
+            SchemaState::Nullable(inner) | SchemaState::Recursive { schema: inner, .. } => {
+                inner.add_lengths(schema)
+            }
+            SchemaState::Array {
+                min_length,
+                max_length,
+                schema: items,
+                ..
+            } => {
+                lengths(schema, ["minItems", "maxItems"], min_length, max_length);
+                if let Some(items_schema) = schema.get_mut("items") {
+                    items.add_lengths(items_schema);
+                }
+            }
+            SchemaState::Map {
+                min_length,
+                max_length,
+                schema: values,
+                ..
+            } => {
+                lengths(
+                    schema,
+                    ["minProperties", "maxProperties"],
+                    min_length,
+                    max_length,
+                );
+                if let Some(values_schema) = schema.get_mut("additionalProperties") {
+                    values.add_lengths(values_schema);
+                }
+            }
+            SchemaState::Union(variants) => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("oneOf").and_then(|v| v.as_array_mut())
+                {
+                    for (variant, variant_schema) in variants.iter().zip(variant_schemas) {
+                        variant.add_lengths(variant_schema);
+                    }
+                }
+            }
+            SchemaState::Mixed(variants) => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("anyOf").and_then(|v| v.as_array_mut())
+                {
+                    for ((variant, _), variant_schema) in variants.iter().zip(variant_schemas) {
+                        variant.add_lengths(variant_schema);
+                    }
+                }
+            }
+            SchemaState::TaggedUnion { variants, .. } => {
+                if let Some(variant_schemas) =
+                    schema.get_mut("oneOf").and_then(|v| v.as_array_mut())
+                {
+                    for (variant, variant_schema) in variants.values().zip(variant_schemas) {
+                        variant.add_lengths(variant_schema);
+                    }
+                }
+            }
+            SchemaState::Tuple(positions) => {
+                if let Some(position_schemas) =
+                    schema.get_mut("prefixItems").and_then(|v| v.as_array_mut())
+                {
+                    for (position, position_schema) in positions.iter().zip(position_schemas) {
+                        position.add_lengths(position_schema);
+                    }
+                }
+            }
+            SchemaState::Object {
+                required, optional, ..
+            } => {
+                for (key, field) in required.iter().chain(optional) {
+                    if let Some(field_schema) = schema
+                        .get_mut("properties")
+                        .and_then(|properties| properties.get_mut(key))
+                    {
+                        field.add_lengths(field_schema);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl ToJsonSchema for StringType {
@@ -725,11 +3200,115 @@ impl ToJsonSchema for StringType {
                 "format": "hostname",
                 "x-drivel-type": "hostname"
             }),
+            StringType::Ipv4 => serde_json::json!({
+                "type": "string",
+                "format": "ipv4"
+            }),
+            StringType::Ipv6 => serde_json::json!({
+                "type": "string",
+                "format": "ipv6"
+            }),
             StringType::DateTimeRFC2822 => serde_json::json!({
                 "type": "string",
                 "x-drivel-type": "datetime-rfc2822",
                 "description": "RFC 2822 datetime format"
             }),
+            StringType::CustomDateTime { formats } => serde_json::json!({
+                "type": "string",
+                "x-drivel-type": "datetime-custom",
+                "x-drivel-date-format": formats[0]
+            }),
+            StringType::CreditCard => serde_json::json!({
+                "type": "string",
+                "pattern": "^[0-9]{13,19}$",
+                "x-drivel-type": "credit-card"
+            }),
+            StringType::Iban => serde_json::json!({
+                "type": "string",
+                "pattern": "^[A-Z]{2}[0-9]{2}[A-Z0-9]{11,30}$",
+                "x-drivel-type": "iban"
+            }),
+            StringType::Isbn10 => serde_json::json!({
+                "type": "string",
+                "pattern": "^[0-9]{9}[0-9X]$",
+                "x-drivel-type": "isbn-10"
+            }),
+            StringType::Isbn13 => serde_json::json!({
+                "type": "string",
+                "pattern": "^97[89][0-9]{10}$",
+                "x-drivel-type": "isbn-13"
+            }),
+            StringType::Ean13 => serde_json::json!({
+                "type": "string",
+                "pattern": "^[0-9]{13}$",
+                "x-drivel-type": "ean-13"
+            }),
+            StringType::Duration => serde_json::json!({
+                "type": "string",
+                "format": "duration"
+            }),
+            StringType::FixedWidthNumeric { width } => serde_json::json!({
+                "type": "string",
+                "pattern": format!("^[0-9]{{{}}}$", width)
+            }),
+            StringType::Charset {
+                class,
+                min_length,
+                max_length,
+            } => serde_json::json!({
+                "type": "string",
+                "pattern": class.pattern(),
+                "minLength": min_length,
+                "maxLength": max_length
+            }),
+            StringType::Pattern {
+                pattern,
+                min_length,
+                max_length,
+            } => {
+                let mut schema = serde_json::json!({
+                    "type": "string",
+                    "pattern": pattern
+                });
+                if let Some(min_length) = min_length {
+                    schema["minLength"] = serde_json::json!(min_length);
+                }
+                if let Some(max_length) = max_length {
+                    schema["maxLength"] = serde_json::json!(max_length);
+                }
+                schema
+            }
+            StringType::Numeric { number } => {
+                let (number_type, pattern) = match number {
+                    NumberType::Float { .. } => (
+                        "number",
+                        "^-?(0|[1-9][0-9]*)(\\.[0-9]+)?([eE][+-]?[0-9]+)?$",
+                    ),
+                    _ => ("integer", "^-?(0|[1-9][0-9]*)$"),
+                };
+                let mut schema = serde_json::json!({
+                    "type": "string",
+                    "pattern": pattern,
+                    "x-drivel-type": "numeric-string",
+                    "x-drivel-number-type": number_type
+                });
+                // the range of the numbers, where it can be written, so that it survives parsing
+                let (min, max) = match number {
+                    NumberType::Integer { min, max } => {
+                        (serde_json::json!(min), serde_json::json!(max))
+                    }
+                    // infinite bounds are written as `null`
+                    NumberType::Float { min, max, .. } => {
+                        (serde_json::json!(min), serde_json::json!(max))
+                    }
+                    _ => (serde_json::Value::Null, serde_json::Value::Null),
+                };
+                if !min.is_null() && !max.is_null() {
+                    schema["x-drivel-minimum"] = min;
+                    schema["x-drivel-maximum"] = max;
+                }
+                schema
+            }
             StringType::Enum { variants } => {
                 let enum_values: Vec<&String> = variants.iter().collect();
                 serde_json::json!({
@@ -737,6 +3316,30 @@ impl ToJsonSchema for StringType {
                     "enum": enum_values
                 })
             }
+            StringType::OpenEnum {
+                variants,
+                other_ratio,
+                ..
+            } => serde_json::json!({
+                "type": "string",
+                "x-drivel-type": "open-enum",
+                "x-drivel-enum": variants,
+                "x-drivel-other-ratio": rounded_ratio(*other_ratio)
+            }),
+        }
+    }
+
+    fn add_lengths(&self, schema: &mut serde_json::Value) {
+        // strings of a format have lengths of their own, and those of a charset or pattern
+        // have theirs already
+        if let StringType::Unknown {
+            min_length: Some(min_length),
+            max_length: Some(max_length),
+            ..
+        } = self
+        {
+            schema["minLength"] = serde_json::json!(min_length);
+            schema["maxLength"] = serde_json::json!(max_length);
         }
     }
 }
@@ -747,11 +3350,225 @@ impl ToJsonSchema for NumberType {
             NumberType::Integer { .. } => serde_json::json!({
                 "type": "integer"
             }),
+            NumberType::Float { non_finite, .. } if !non_finite.is_empty() => {
+                let tokens: Vec<_> = non_finite.iter().map(NonFiniteNumber::token).collect();
+                serde_json::json!({
+                    "type": "number",
+                    "x-drivel-non-finite": tokens
+                })
+            }
             NumberType::Float { .. } => serde_json::json!({
                 "type": "number"
             }),
+            NumberType::Enum { variants } => {
+                let enum_values: Vec<&i64> = variants.keys().collect();
+                serde_json::json!({
+                    "type": "integer",
+                    "enum": enum_values
+                })
+            }
+            NumberType::Sequence {
+                start, end, count, ..
+            } => serde_json::json!({
+                "type": "integer",
+                "x-drivel-type": "sequence",
+                "x-drivel-sequence-start": start,
+                "x-drivel-sequence-step": NumberType::sequence_step(*start, *end, *count)
+            }),
+            NumberType::Flags { .. } => {
+                let flags = self.flags();
+                serde_json::json!({
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": NumberType::flag_mask(&flags),
+                    "description": format!("bit flags {}", join_flags(&flags)),
+                    "x-drivel-type": "flags",
+                    "x-drivel-flags": flags
+                })
+            }
+            NumberType::Multiple { step, integer, .. } => serde_json::json!({
+                "type": if *integer { "integer" } else { "number" },
+                "multipleOf": step
+            }),
+        }
+    }
+
+    fn add_numeric_bounds(&self, schema: &mut serde_json::Value, style: NumericBounds) {
+        let (min, max) = match (self, style) {
+            (
+                NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. },
+                NumericBounds::Inclusive,
+            ) => (
+                (*min != i64::MIN).then(|| serde_json::json!(min)),
+                (*max != i64::MAX).then(|| serde_json::json!(max)),
+            ),
+            (
+                NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. },
+                NumericBounds::Exclusive,
+            ) => (
+                (*min != i64::MIN).then(|| serde_json::json!(min - 1)),
+                (*max != i64::MAX).then(|| serde_json::json!(max + 1)),
+            ),
+            (
+                NumberType::Float { min, max, .. } | NumberType::Multiple { min, max, .. },
+                NumericBounds::Inclusive,
+            ) => (
+                min.is_finite().then(|| serde_json::json!(min)),
+                max.is_finite().then(|| serde_json::json!(max)),
+            ),
+            (
+                NumberType::Float { min, max, .. } | NumberType::Multiple { min, max, .. },
+                NumericBounds::Exclusive,
+            ) => (
+                min.is_finite().then(|| serde_json::json!(min.next_down())),
+                max.is_finite().then(|| serde_json::json!(max.next_up())),
+            ),
+            (NumberType::Enum { .. }, _) => (None, None),
+            // the bits of the flags already bound them
+            (NumberType::Flags { .. }, _) => return,
+        };
+        let (min_key, max_key) = match style {
+            NumericBounds::Inclusive => ("minimum", "maximum"),
+            NumericBounds::Exclusive => ("exclusiveMinimum", "exclusiveMaximum"),
+        };
+        if let Some(schema_obj) = schema.as_object_mut() {
+            if let Some(min) = min {
+                schema_obj.insert(min_key.to_string(), min);
+            }
+            if let Some(max) = max {
+                schema_obj.insert(max_key.to_string(), max);
+            }
+        }
+    }
+
+    fn add_numeric_ranges(&self, schema: &mut serde_json::Value) {
+        let Some(range) = self.numeric_range() else {
+            return;
+        };
+        schema["x-drivel-numeric-range"] = serde_json::json!(range.to_string());
+        // the values observed lie within the range, and keep data produced from the schema in it
+        let bounded = ["minimum", "exclusiveMinimum"]
+            .iter()
+            .any(|keyword| schema.get(keyword).is_some());
+        if !bounded {
+            self.add_numeric_bounds(schema, NumericBounds::Inclusive);
+        }
+    }
+}
+
+impl ToJsonSchema for ExtendedJsonType {
+    fn to_json_schema(&self) -> serde_json::Value {
+        let (key, value_schema) = match self {
+            ExtendedJsonType::ObjectId => (
+                "$oid",
+                serde_json::json!({ "type": "string", "pattern": "^[0-9a-f]{24}$" }),
+            ),
+            ExtendedJsonType::Date => (
+                "$date",
+                serde_json::json!({ "type": "string", "format": "date-time" }),
+            ),
+            ExtendedJsonType::Long { .. } => (
+                "$numberLong",
+                serde_json::json!({ "type": "string", "pattern": "^-?[0-9]+$" }),
+            ),
+        };
+        serde_json::json!({
+            "type": "object",
+            "properties": { key: value_schema },
+            "required": [key],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Constructors of schemas for the tests of this and other modules.
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    pub fn unknown_string(min_length: Option<usize>, max_length: Option<usize>) -> StringType {
+        StringType::Unknown {
+            strings_seen: vec!["test".to_string()],
+            chars_seen: vec!['t', 'e', 's', 't'],
+            min_length,
+            max_length,
+        }
+    }
+
+    pub fn enum_string(variants: Vec<&str>) -> StringType {
+        let variant_set = variants
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<BTreeSet<_>>();
+        StringType::Enum {
+            variants: variant_set,
+        }
+    }
+
+    pub fn integer_range(min: i64, max: i64) -> NumberType {
+        NumberType::Integer { min, max }
+    }
+
+    pub fn float_range(min: f64, max: f64) -> NumberType {
+        NumberType::Float {
+            min,
+            max,
+            non_finite: Default::default(),
+        }
+    }
+
+    pub fn string_schema(string_type: StringType) -> SchemaState {
+        SchemaState::String(string_type)
+    }
+
+    pub fn number_schema(number_type: NumberType) -> SchemaState {
+        SchemaState::Number(number_type)
+    }
+
+    pub fn nullable_schema(inner: SchemaState) -> SchemaState {
+        SchemaState::Nullable(Box::new(inner))
+    }
+
+    pub fn array_schema(
+        min_length: usize,
+        max_length: usize,
+        item_schema: SchemaState,
+    ) -> SchemaState {
+        SchemaState::Array {
+            min_length,
+            max_length,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(item_schema),
+        }
+    }
+
+    pub fn object_schema(
+        required_fields: Vec<(&str, SchemaState)>,
+        optional_fields: Vec<(&str, SchemaState)>,
+    ) -> SchemaState {
+        let required = required_fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<IndexMap<_, _>>();
+
+        let optional = optional_fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<IndexMap<_, _>>();
+
+        SchemaState::Object {
+            required,
+            optional,
+            access: Default::default(),
+            annotations: Default::default(),
         }
     }
+
+    pub fn assert_schema_equals(schema: &SchemaState, expected: serde_json::Value) {
+        assert_eq!(schema.to_json_schema(), expected);
+    }
 }
 
 #[cfg(test)]
@@ -759,86 +3576,172 @@ mod tests {
     use super::*;
     use serde_json::json;
 
-    mod test_helpers {
+    mod table_tests {
         use super::*;
-        use std::collections::{HashMap, HashSet};
+        use crate::schema::test_helpers::*;
 
-        pub fn unknown_string(min_length: Option<usize>, max_length: Option<usize>) -> StringType {
-            StringType::Unknown {
-                strings_seen: vec!["test".to_string()],
-                chars_seen: vec!['t', 'e', 's', 't'],
-                min_length,
-                max_length,
-            }
+        #[test]
+        fn non_array_has_no_table() {
+            let schema = object_schema(vec![("id", SchemaState::Boolean)], vec![]);
+            assert_eq!(schema.to_table_string(), None);
         }
 
-        pub fn enum_string(variants: Vec<&str>) -> StringType {
-            let variant_set = variants
-                .iter()
-                .map(|s| s.to_string())
-                .collect::<HashSet<_>>();
-            StringType::Enum {
-                variants: variant_set,
-            }
+        #[test]
+        fn array_of_scalars_has_no_table() {
+            let schema = array_schema(1, 3, SchemaState::Boolean);
+            assert_eq!(schema.to_table_string(), None);
         }
 
-        pub fn integer_range(min: i64, max: i64) -> NumberType {
-            NumberType::Integer { min, max }
-        }
+        #[test]
+        fn array_of_objects_renders_fields_as_rows() {
+            let schema = array_schema(
+                1,
+                3,
+                object_schema(
+                    vec![
+                        ("name", string_schema(unknown_string(Some(3), Some(8)))),
+                        ("tags", array_schema(0, 2, string_schema(StringType::Email))),
+                    ],
+                    vec![("age", nullable_schema(number_schema(integer_range(1, 99))))],
+                ),
+            );
 
-        pub fn float_range(min: f64, max: f64) -> NumberType {
-            NumberType::Float { min, max }
+            assert_eq!(
+                schema.to_table_string().unwrap(),
+                [
+                    "record (1-3 rows)",
+                    "",
+                    "field  type                   required",
+                    "name   string (3-8)           yes",
+                    "tags   [string (email)] (0-2) yes",
+                    "age    nullable int (1-99)    no",
+                ]
+                .join("\n")
+            );
         }
 
-        pub fn string_schema(string_type: StringType) -> SchemaState {
-            SchemaState::String(string_type)
+        #[test]
+        fn nested_objects_are_flattened() {
+            let address = object_schema(
+                vec![("city", string_schema(unknown_string(Some(8), Some(8))))],
+                vec![("zip", string_schema(unknown_string(Some(5), Some(5))))],
+            );
+            let schema = array_schema(
+                2,
+                2,
+                object_schema(
+                    vec![("address", address)],
+                    vec![(
+                        "meta",
+                        object_schema(vec![("id", SchemaState::Boolean)], vec![]),
+                    )],
+                ),
+            );
+
+            assert_eq!(
+                schema.to_table_string().unwrap(),
+                [
+                    "record (2 rows)",
+                    "",
+                    "field         type       required",
+                    "address.city  string (8) yes",
+                    "address.zip   string (5) no",
+                    "meta.id       boolean    no",
+                ]
+                .join("\n")
+            );
         }
+    }
 
-        pub fn number_schema(number_type: NumberType) -> SchemaState {
-            SchemaState::Number(number_type)
+    mod normalize_tests {
+        use super::*;
+        use crate::schema::test_helpers::*;
+
+        fn record(field: &str) -> SchemaState {
+            object_schema(vec![(field, SchemaState::Boolean)], vec![])
         }
 
-        pub fn nullable_schema(inner: SchemaState) -> SchemaState {
-            SchemaState::Nullable(Box::new(inner))
+        #[test]
+        fn unions_compare_regardless_of_variant_order() {
+            let a = SchemaState::Union(vec![record("a"), record("b"), record("c")]);
+            let b = SchemaState::Union(vec![record("c"), record("a"), record("b")]);
+
+            assert_ne!(a, b);
+            assert!(a.equivalent(&b));
+            assert_eq!(a.normalize(), b.normalize());
         }
 
-        pub fn array_schema(
-            min_length: usize,
-            max_length: usize,
-            item_schema: SchemaState,
-        ) -> SchemaState {
-            SchemaState::Array {
-                min_length,
-                max_length,
-                schema: Box::new(item_schema),
-            }
+        #[test]
+        fn flattens_and_dedups_nested_unions() {
+            let nested = SchemaState::Union(vec![
+                record("a"),
+                SchemaState::Union(vec![record("b"), record("a")]),
+            ]);
+
+            assert!(nested.equivalent(&SchemaState::Union(vec![record("b"), record("a")])));
+            assert_eq!(
+                SchemaState::Union(vec![record("a"), record("a")]).normalize(),
+                record("a")
+            );
         }
 
-        pub fn object_schema(
-            required_fields: Vec<(&str, SchemaState)>,
-            optional_fields: Vec<(&str, SchemaState)>,
-        ) -> SchemaState {
-            let required = required_fields
-                .into_iter()
-                .map(|(k, v)| (k.to_string(), v))
-                .collect::<HashMap<_, _>>();
+        #[test]
+        fn hoists_nulls_out_of_unions() {
+            let union = SchemaState::Union(vec![
+                nullable_schema(record("a")),
+                record("b"),
+                SchemaState::Null,
+            ]);
+            let expected = nullable_schema(SchemaState::Union(vec![record("a"), record("b")]));
 
-            let optional = optional_fields
-                .into_iter()
-                .map(|(k, v)| (k.to_string(), v))
-                .collect::<HashMap<_, _>>();
+            assert!(union.equivalent(&expected));
+            assert_eq!(
+                nullable_schema(nullable_schema(record("a"))).normalize(),
+                nullable_schema(record("a"))
+            );
+            assert_eq!(
+                SchemaState::Union(vec![SchemaState::Null]).normalize(),
+                SchemaState::Null
+            );
+        }
+
+        #[test]
+        fn normalizes_inside_other_schemas() {
+            let a = array_schema(
+                1,
+                2,
+                object_schema(
+                    vec![("events", SchemaState::Union(vec![record("x"), record("y")]))],
+                    vec![],
+                ),
+            );
+            let b = array_schema(
+                1,
+                2,
+                object_schema(
+                    vec![("events", SchemaState::Union(vec![record("y"), record("x")]))],
+                    vec![],
+                ),
+            );
 
-            SchemaState::Object { required, optional }
+            assert!(a.equivalent(&b));
+            assert!(!a.equivalent(&array_schema(1, 3, SchemaState::Boolean)));
         }
 
-        pub fn assert_schema_equals(schema: &SchemaState, expected: serde_json::Value) {
-            assert_eq!(schema.to_json_schema(), expected);
+        #[test]
+        fn preserves_other_schemas() {
+            let schema = object_schema(
+                vec![("name", string_schema(unknown_string(Some(1), Some(4))))],
+                vec![("age", number_schema(integer_range(0, 9)))],
+            );
+
+            assert_eq!(schema.clone().normalize(), schema);
         }
     }
 
     mod json_schema_tests {
-        use super::test_helpers::*;
         use super::*;
+        use crate::schema::test_helpers::*;
 
         mod basic_types {
             use super::*;
@@ -979,6 +3882,18 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn duration_string_to_json_schema() {
+                let schema = string_schema(StringType::Duration);
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "string",
+                        "format": "duration"
+                    }),
+                );
+            }
+
             #[test]
             fn datetime_rfc2822_string_to_json_schema() {
                 let schema = string_schema(StringType::DateTimeRFC2822);
@@ -992,6 +3907,18 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn fixed_width_numeric_string_to_json_schema() {
+                let schema = string_schema(StringType::FixedWidthNumeric { width: 5 });
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "string",
+                        "pattern": "^[0-9]{5}$"
+                    }),
+                );
+            }
+
             #[test]
             fn enum_string_multiple_variants_to_json_schema() {
                 let schema = string_schema(enum_string(vec!["red", "green", "blue"]));
@@ -1014,6 +3941,25 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn open_enum_string_to_json_schema() {
+                let schema = string_schema(StringType::OpenEnum {
+                    variants: ["DE", "FR"].iter().map(|s| s.to_string()).collect(),
+                    other_ratio: 0.05,
+                    other: Box::new(unknown_string(None, None)),
+                });
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "string",
+                        "x-drivel-type": "open-enum",
+                        "x-drivel-enum": ["DE", "FR"],
+                        "x-drivel-other-ratio": 0.05
+                    }),
+                );
+                assert_eq!(schema.to_string_pretty(), "string (enum: DE, FR (open))");
+            }
+
             #[test]
             fn enum_string_empty_variants_to_json_schema() {
                 let schema = string_schema(enum_string(vec![]));
@@ -1041,6 +3987,21 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn integer_enum_to_json_schema() {
+                let schema = number_schema(NumberType::Enum {
+                    variants: [(404, 1), (200, 5)].into_iter().collect(),
+                });
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "integer",
+                        "enum": [200, 404]
+                    }),
+                );
+                assert_eq!(schema.to_string_pretty(), "int (enum: 200, 404)");
+            }
+
             #[test]
             fn integer_single_value_to_json_schema() {
                 let schema = number_schema(integer_range(42, 42));
@@ -1080,12 +4041,106 @@ mod tests {
                 assert_schema_equals(
                     &schema,
                     json!({
-                        "type": "number"
-                    }),
+                        "type": "number"
+                    }),
+                );
+            }
+
+            #[test]
+            fn character_classes_to_json_schema() {
+                let schema = string_schema(StringType::Charset {
+                    class: CharacterClass::Uppercase,
+                    min_length: 2,
+                    max_length: 3,
+                });
+                assert_eq!(schema.to_string_pretty(), "uppercase string (2-3)");
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "string",
+                        "pattern": "^[A-Z]+$",
+                        "minLength": 2,
+                        "maxLength": 3
+                    }),
+                );
+                assert_eq!(
+                    crate::parse_json_schema(&schema.to_json_schema()).unwrap(),
+                    schema
+                );
+
+                let hex = string_schema(StringType::Charset {
+                    class: CharacterClass::Hex,
+                    min_length: 8,
+                    max_length: 8,
+                });
+                assert_eq!(hex.to_string_pretty(), "hex string (8)");
+                assert_eq!(hex.to_json_schema()["pattern"], "^[0-9a-f]+$");
+            }
+
+            #[test]
+            fn ratios_and_percentages_to_json_schema() {
+                let ranges = JsonSchemaOptions {
+                    numeric_ranges: true,
+                    ..Default::default()
+                };
+                let describe = |schema: &SchemaState| {
+                    schema.to_string_pretty_with_options(&crate::DescribeOptions {
+                        json_schema: ranges.clone(),
+                        ..Default::default()
+                    })
+                };
+                let ratio = number_schema(float_range(0.05, 0.95));
+                assert_eq!(describe(&ratio), "float (0.05-0.95, ratio 0..1)");
+                assert_eq!(
+                    to_json_schema_with(&ratio, &ranges),
+                    json!({
+                        "type": "number",
+                        "minimum": 0.05,
+                        "maximum": 0.95,
+                        "x-drivel-numeric-range": "ratio 0..1"
+                    })
+                );
+                // only with the option
+                assert_eq!(ratio.to_string_pretty(), "float (0.05-0.95)");
+                assert_schema_equals(&ratio, json!({"type": "number"}));
+
+                let percentage = number_schema(float_range(12.5, 87.5));
+                assert_eq!(
+                    describe(&percentage),
+                    "float (12.5-87.5, percentage 0..100)"
                 );
+                let exclusive = JsonSchemaOptions {
+                    numeric_bounds: Some(NumericBounds::Exclusive),
+                    ..ranges.clone()
+                };
+                let bounded = to_json_schema_with(&percentage, &exclusive);
+                assert_eq!(bounded["x-drivel-numeric-range"], "percentage 0..100");
+                assert!(bounded.get("maximum").is_none());
+
+                // the descriptions of fields are theirs
+                let rate = object_schema(vec![("conversion_rate", ratio.clone())], vec![]);
+                let document = rate.to_json_schema_document_with(&JsonSchemaOptions {
+                    field_descriptions: true,
+                    ..ranges.clone()
+                });
+                let property = &document["properties"]["conversion_rate"];
+                assert_eq!(property["description"], "Conversion rate");
+                assert_eq!(property["x-drivel-numeric-range"], "ratio 0..1");
+
+                // integers, ratings up to 10 and negative numbers are left alone
+                for schema in [
+                    number_schema(integer_range(0, 100)),
+                    number_schema(float_range(1.0, 5.0)),
+                    number_schema(float_range(-0.5, 0.5)),
+                ] {
+                    let json_schema = to_json_schema_with(&schema, &ranges);
+                    assert!(json_schema.get("x-drivel-numeric-range").is_none());
+                    assert!(json_schema.get("maximum").is_none());
+                }
             }
 
             #[test]
+            #[allow(clippy::approx_constant)]
             fn float_single_value_to_json_schema() {
                 let schema = number_schema(float_range(3.14, 3.14));
                 assert_schema_equals(
@@ -1161,6 +4216,31 @@ mod tests {
                 assert_eq!(result["required"], json!(["id"]));
                 assert_eq!(result["additionalProperties"], false);
             }
+
+            #[test]
+            fn nullable_mixed_to_json_schema() {
+                let schema = nullable_schema(SchemaState::Mixed(vec![
+                    (string_schema(StringType::UUID), 7),
+                    (SchemaState::Boolean, 3),
+                ]));
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "anyOf": [
+                            {"type": "string", "format": "uuid", "x-drivel-count": 7},
+                            {"type": "boolean", "x-drivel-count": 3},
+                            {"type": "null"}
+                        ]
+                    }),
+                );
+                assert_eq!(
+                    schema.to_string_pretty(),
+                    "nullable string (uuid) (70%) | boolean (30%)"
+                );
+
+                let parsed = crate::parse_json_schema(&schema.to_json_schema()).unwrap();
+                assert_eq!(parsed, schema);
+            }
         }
 
         mod array_types {
@@ -1209,6 +4289,41 @@ mod tests {
                 assert_eq!(result["items"]["properties"]["name"]["type"], "string");
             }
 
+            #[test]
+            fn map_to_json_schema() {
+                let schema = SchemaState::Map {
+                    min_length: 1,
+                    max_length: 200,
+                    keys: StringType::IsoDate,
+                    schema: Box::new(number_schema(integer_range(1, 100))),
+                };
+                assert_schema_equals(
+                    &schema,
+                    json!({
+                        "type": "object",
+                        "additionalProperties": { "type": "integer" }
+                    }),
+                );
+                assert_eq!(
+                    schema.to_string_pretty(),
+                    "map<string (date - ISO 8601), int (1-100)> (1-200)"
+                );
+            }
+
+            #[test]
+            fn union_to_json_schema() {
+                let schema = nullable_schema(SchemaState::Union(vec![
+                    object_schema(vec![("a", SchemaState::Boolean)], vec![]),
+                    object_schema(vec![("b", SchemaState::Null)], vec![]),
+                ]));
+                let result = schema.to_json_schema();
+                let variants = result["oneOf"].as_array().unwrap();
+                assert_eq!(variants.len(), 3);
+                assert_eq!(variants[0]["properties"]["a"]["type"], "boolean");
+                assert_eq!(variants[1]["properties"]["b"]["type"], "null");
+                assert_eq!(variants[2], json!({ "type": "null" }));
+            }
+
             #[test]
             fn nested_array_to_json_schema() {
                 let schema = array_schema(
@@ -1293,6 +4408,33 @@ mod tests {
                 assert_eq!(result["properties"]["count"]["type"], "integer");
             }
 
+            #[test]
+            fn object_fields_in_the_order_in_which_they_were_seen() {
+                let schema = object_schema(
+                    vec![("d", SchemaState::Boolean), ("b", SchemaState::Boolean)],
+                    vec![("c", SchemaState::Boolean), ("a", SchemaState::Boolean)],
+                );
+                for _ in 0..10 {
+                    assert_eq!(schema.to_json_schema()["required"], json!(["d", "b"]));
+                }
+                let order = |description: String| {
+                    description
+                        .lines()
+                        .filter_map(|line| line.trim().strip_prefix('"'))
+                        .map(|line| line[..1].to_string())
+                        .collect::<String>()
+                };
+                assert_eq!(order(schema.to_string_pretty()), "dbca");
+                assert_eq!(
+                    order(
+                        schema.to_string_pretty_with(
+                            &PrettyOptions::builder().sort_keys(true).build()
+                        )
+                    ),
+                    "abcd"
+                );
+            }
+
             #[test]
             fn nested_object_to_json_schema() {
                 let inner_object = object_schema(
@@ -1317,6 +4459,589 @@ mod tests {
         mod document_format {
             use super::*;
 
+            #[test]
+            fn json_schema_document_with_title_and_id() {
+                let options = JsonSchemaOptions {
+                    title: Some("Thing".to_string()),
+                    id: Some("urn:thing".to_string()),
+                    field_descriptions: false,
+                    numeric_bounds: None,
+                    numeric_ranges: false,
+                    dedupe_definitions: false,
+                    field_stats: None,
+                    value_counts: None,
+                    lengths: false,
+                    draft: SchemaDraft::Draft2020_12,
+                };
+                let document = SchemaState::Boolean.to_json_schema_document_with(&options);
+
+                assert_eq!(document["title"], "Thing");
+                assert_eq!(document["$id"], "urn:thing");
+                assert_eq!(document["type"], "boolean");
+            }
+
+            #[test]
+            fn json_schema_document_with_lengths_for_a_draft() {
+                let schema = array_schema(
+                    2,
+                    5,
+                    object_schema(
+                        vec![("name", string_schema(unknown_string(Some(3), Some(8))))],
+                        vec![("tags", array_schema(0, 3, string_schema(StringType::Email)))],
+                    ),
+                );
+                let options = JsonSchemaOptions {
+                    id: Some("urn:people".to_string()),
+                    lengths: true,
+                    draft: SchemaDraft::Draft04,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+
+                assert_eq!(
+                    document["$schema"],
+                    "http://json-schema.org/draft-04/schema#"
+                );
+                assert_eq!(document["id"], "urn:people");
+                assert_eq!(
+                    (&document["minItems"], &document["maxItems"]),
+                    (&json!(2), &json!(5))
+                );
+                let properties = &document["items"]["properties"];
+                assert_eq!(properties["name"]["minLength"], 3);
+                assert_eq!(properties["name"]["maxLength"], 8);
+                assert_eq!(properties["tags"]["maxItems"], 3);
+                // emails are as long as they are
+                assert_eq!(properties["tags"]["items"].get("maxLength"), None);
+
+                let document = schema.to_json_schema_document();
+                assert_eq!(document.get("minItems"), None);
+                assert_eq!(
+                    document["items"]["properties"]["name"].get("minLength"),
+                    None
+                );
+            }
+
+            #[test]
+            fn json_schema_document_without_id() {
+                let document = SchemaState::Boolean.to_json_schema_document();
+                assert!(document.get("$id").is_none());
+            }
+
+            #[test]
+            fn json_schema_document_with_field_descriptions() {
+                let schema = array_schema(
+                    1,
+                    1,
+                    object_schema(
+                        vec![(
+                            "homeAddress",
+                            object_schema(vec![("zip_code", SchemaState::Boolean)], vec![]),
+                        )],
+                        vec![("is-active", nullable_schema(SchemaState::Boolean))],
+                    ),
+                );
+                let options = JsonSchemaOptions {
+                    field_descriptions: true,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+                let properties = &document["items"]["properties"];
+
+                assert_eq!(properties["homeAddress"]["description"], "Home address");
+                assert_eq!(
+                    properties["homeAddress"]["properties"]["zip_code"]["description"],
+                    "Zip code"
+                );
+                assert_eq!(properties["is-active"]["description"], "Is active");
+            }
+
+            #[test]
+            fn field_descriptions_do_not_override_existing_descriptions() {
+                let schema = object_schema(
+                    vec![("sent", string_schema(StringType::DateTimeRFC2822))],
+                    vec![],
+                );
+                let options = JsonSchemaOptions {
+                    field_descriptions: true,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+
+                assert_eq!(
+                    document["properties"]["sent"]["description"],
+                    "RFC 2822 datetime format"
+                );
+            }
+
+            #[test]
+            fn json_schema_document_with_inclusive_bounds() {
+                let schema = array_schema(
+                    1,
+                    1,
+                    object_schema(
+                        vec![("count", number_schema(integer_range(0, 10)))],
+                        vec![(
+                            "ratio",
+                            nullable_schema(number_schema(float_range(0.5, f64::INFINITY))),
+                        )],
+                    ),
+                );
+                let options = JsonSchemaOptions {
+                    numeric_bounds: Some(NumericBounds::Inclusive),
+                    numeric_ranges: false,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+                let properties = &document["items"]["properties"];
+
+                assert_eq!(properties["count"]["minimum"], 0);
+                assert_eq!(properties["count"]["maximum"], 10);
+                assert_eq!(properties["ratio"]["minimum"], 0.5);
+                assert!(properties["ratio"].get("maximum").is_none());
+            }
+
+            #[test]
+            fn exclusive_bounds_round_trip_through_parsing() {
+                let schema = object_schema(
+                    vec![
+                        ("count", number_schema(integer_range(0, 10))),
+                        ("ratio", number_schema(float_range(0.1, 0.9))),
+                    ],
+                    vec![],
+                );
+                let options = JsonSchemaOptions {
+                    numeric_bounds: Some(NumericBounds::Exclusive),
+                    numeric_ranges: false,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+
+                assert_eq!(document["properties"]["count"]["exclusiveMinimum"], -1);
+                assert_eq!(document["properties"]["count"]["exclusiveMaximum"], 11);
+                assert!(document["properties"]["ratio"]["exclusiveMinimum"].as_f64() < Some(0.1));
+                assert_eq!(crate::parse_json_schema(&document).unwrap(), schema);
+            }
+
+            #[test]
+            fn coordinates_to_json_schema() {
+                let geographic =
+                    SchemaState::Coordinates(vec![float_range(4.8, 4.9), float_range(52.3, 52.4)]);
+                assert_eq!(geographic.to_string_pretty(), "coordinates (lon, lat)");
+                assert_eq!(
+                    geographic.to_json_schema(),
+                    json!({
+                        "type": "array",
+                        "prefixItems": [
+                            {"type": "number", "minimum": -180, "maximum": 180},
+                            {"type": "number", "minimum": -90, "maximum": 90}
+                        ],
+                        "items": false,
+                        "minItems": 2,
+                        "maxItems": 2
+                    })
+                );
+
+                let points = SchemaState::Coordinates(vec![
+                    integer_range(0, 500),
+                    integer_range(0, 500),
+                    float_range(0.5, 1.5),
+                ]);
+                assert_eq!(
+                    points.to_string_pretty(),
+                    "coordinates (int (0-500), int (0-500), float (0.5-1.5))"
+                );
+                assert_eq!(points.to_json_schema()["prefixItems"][2]["type"], "number");
+            }
+
+            #[test]
+            fn tuples_to_json_schema() {
+                let tuple = SchemaState::Tuple(vec![
+                    number_schema(integer_range(1, 9)),
+                    SchemaState::Boolean,
+                ]);
+                assert_eq!(
+                    tuple.to_string_pretty(),
+                    "[\n  int (1-9),\n  boolean\n] (tuple)"
+                );
+                assert_eq!(
+                    tuple.to_json_schema(),
+                    json!({
+                        "type": "array",
+                        "prefixItems": [
+                            {"type": "integer"},
+                            {"type": "boolean"}
+                        ],
+                        "items": false,
+                        "minItems": 2,
+                        "maxItems": 2
+                    })
+                );
+            }
+
+            #[test]
+            fn tagged_unions_to_json_schema() {
+                let variant = |tag: &str, field: &str| {
+                    object_schema(
+                        vec![
+                            (
+                                "type",
+                                SchemaState::String(StringType::Enum {
+                                    variants: [tag.to_string()].into(),
+                                }),
+                            ),
+                            (field, SchemaState::Boolean),
+                        ],
+                        vec![],
+                    )
+                };
+                let schema = SchemaState::TaggedUnion {
+                    tag: "type".to_string(),
+                    variants: std::collections::BTreeMap::from([
+                        ("click".to_string(), variant("click", "x")),
+                        ("key".to_string(), variant("key", "code")),
+                    ]),
+                };
+                let document = schema.to_json_schema();
+                assert_eq!(document["discriminator"], json!({"propertyName": "type"}));
+                assert_eq!(
+                    document["oneOf"][1]["properties"]["type"],
+                    json!({"type": "string", "enum": ["key"]})
+                );
+                assert_eq!(crate::parse_json_schema(&document).unwrap(), schema);
+
+                // without the discriminator, it is a union of the shapes
+                let mut union = document;
+                union.as_object_mut().unwrap().remove("discriminator");
+                assert!(matches!(
+                    crate::parse_json_schema(&union).unwrap(),
+                    SchemaState::Union(_)
+                ));
+            }
+
+            #[test]
+            fn geo_points_to_json_schema() {
+                let schema = SchemaState::GeoPoint {
+                    latitude_key: "lat".to_string(),
+                    longitude_key: "lon".to_string(),
+                    latitude: float_range(52.3, 52.4),
+                    longitude: float_range(4.8, 4.9),
+                };
+                assert_eq!(schema.to_string_pretty(), "geo point (lat, lon)");
+                assert_eq!(
+                    schema.to_json_schema(),
+                    json!({
+                        "type": "object",
+                        "x-drivel-type": "geo-point",
+                        "properties": {
+                            "lat": {"type": "number", "minimum": -90, "maximum": 90},
+                            "lon": {"type": "number", "minimum": -180, "maximum": 180}
+                        },
+                        "required": ["lat", "lon"],
+                        "additionalProperties": false
+                    })
+                );
+                assert_eq!(
+                    crate::parse_json_schema(&schema.to_json_schema()).unwrap(),
+                    SchemaState::GeoPoint {
+                        latitude_key: "lat".to_string(),
+                        longitude_key: "lon".to_string(),
+                        latitude: float_range(-90.0, 90.0),
+                        longitude: float_range(-180.0, 180.0),
+                    }
+                );
+            }
+
+            #[test]
+            fn money_to_json_schema() {
+                let schema = SchemaState::Money {
+                    amount_key: "amount".to_string(),
+                    currency_key: "currency".to_string(),
+                    amount: NumberType::Integer {
+                        min: 100,
+                        max: 5000,
+                    },
+                    currencies: ["EUR", "USD"].into_iter().map(String::from).collect(),
+                };
+                assert_eq!(
+                    schema.to_string_pretty(),
+                    "money (amount in minor units, currency: EUR, USD)"
+                );
+                assert_eq!(
+                    schema.to_json_schema(),
+                    json!({
+                        "type": "object",
+                        "x-drivel-type": "money",
+                        "properties": {
+                            "amount": {"type": "integer", "minimum": 0},
+                            "currency": {"type": "string", "enum": ["EUR", "USD"]}
+                        },
+                        "required": ["amount", "currency"],
+                        "additionalProperties": false
+                    })
+                );
+                let SchemaState::Money {
+                    amount, currencies, ..
+                } = crate::parse_json_schema(&schema.to_json_schema()).unwrap()
+                else {
+                    panic!("Expected money");
+                };
+                assert!(matches!(amount, NumberType::Integer { min: 0, .. }));
+                assert_eq!(currencies.len(), 2);
+
+                // without an enum of currencies, any currency code will do
+                let decimal = SchemaState::Money {
+                    amount_key: "value".to_string(),
+                    currency_key: "ccy".to_string(),
+                    amount: float_range(-20.0, 12.99),
+                    currencies: Default::default(),
+                };
+                assert_eq!(
+                    decimal.to_json_schema()["properties"],
+                    json!({
+                        "value": {"type": "number"},
+                        "ccy": {"type": "string", "pattern": "^[A-Z]{3}$"}
+                    })
+                );
+                assert!(matches!(
+                    crate::parse_json_schema(&decimal.to_json_schema()).unwrap(),
+                    SchemaState::Money { currencies, .. } if currencies.is_empty()
+                ));
+            }
+
+            #[test]
+            fn json_schema_document_with_deduplicated_definitions() {
+                let address = || object_schema(vec![("zip_code", SchemaState::Boolean)], vec![]);
+                let schema = object_schema(
+                    vec![("billing", address()), ("shipping", address())],
+                    vec![],
+                );
+                let options = JsonSchemaOptions {
+                    dedupe_definitions: true,
+                    field_descriptions: true,
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+
+                assert_eq!(
+                    document["$schema"],
+                    "https://json-schema.org/draft/2020-12/schema"
+                );
+                assert_eq!(
+                    document["properties"]["shipping"]["$ref"],
+                    "#/$defs/billing"
+                );
+                assert_eq!(
+                    document["properties"]["shipping"]["description"],
+                    "Shipping"
+                );
+                assert_eq!(
+                    document["$defs"]["billing"]["properties"]["zip_code"]["description"],
+                    "Zip code"
+                );
+            }
+
+            #[test]
+            fn json_schema_document_with_field_stats() {
+                let data = json!([
+                    {"name": "a", "email": "a@example.com", "address": {"city": null}},
+                    {"name": "b", "email": null, "address": {"city": "Utrecht"}},
+                    {"name": "c", "address": {}},
+                    {"name": "d", "email": null}
+                ]);
+                let mut stats = FieldStats::default();
+                stats.add(&data);
+                let schema = array_schema(
+                    4,
+                    4,
+                    object_schema(
+                        vec![("name", string_schema(unknown_string(Some(1), Some(1))))],
+                        vec![
+                            ("email", nullable_schema(string_schema(StringType::Email))),
+                            (
+                                "address",
+                                object_schema(
+                                    vec![],
+                                    vec![(
+                                        "city",
+                                        nullable_schema(string_schema(StringType::Hostname)),
+                                    )],
+                                ),
+                            ),
+                        ],
+                    ),
+                );
+                let options = JsonSchemaOptions {
+                    field_stats: Some(stats),
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+                let properties = &document["items"]["properties"];
+
+                assert_eq!(properties["name"]["x-drivel-presence"], 1.0);
+                assert_eq!(properties["name"]["x-drivel-null-ratio"], 0.0);
+                assert_eq!(properties["email"]["x-drivel-presence"], 0.75);
+                assert_eq!(properties["email"]["x-drivel-null-ratio"], 0.6667);
+                assert_eq!(properties["address"]["x-drivel-presence"], 0.75);
+                let city = &properties["address"]["properties"]["city"];
+                assert_eq!(city["x-drivel-presence"], 0.6667);
+                assert_eq!(city["x-drivel-null-ratio"], 0.5);
+                assert_eq!(properties["name"].get("x-drivel-unique"), None);
+            }
+
+            #[test]
+            fn unique_fields_are_marked_with_field_stats() {
+                let mut stats = FieldStats::default();
+                stats.add(&json!((0..10)
+                    .map(|i| json!({"id": i, "kind": i % 2}))
+                    .collect::<Vec<_>>()));
+                let schema = array_schema(
+                    10,
+                    10,
+                    object_schema(
+                        vec![
+                            ("id", number_schema(NumberType::Integer { min: 0, max: 9 })),
+                            (
+                                "kind",
+                                number_schema(NumberType::Integer { min: 0, max: 1 }),
+                            ),
+                        ],
+                        vec![],
+                    ),
+                );
+                let options = JsonSchemaOptions {
+                    field_stats: Some(stats.clone()),
+                    ..Default::default()
+                };
+                let properties =
+                    &schema.to_json_schema_document_with(&options)["items"]["properties"];
+                assert_eq!(properties["id"]["x-drivel-unique"], true);
+                assert_eq!(properties["kind"].get("x-drivel-unique"), None);
+
+                let description = schema
+                    .to_string_pretty_with(&PrettyOptions::builder().field_stats(&stats).build());
+                assert!(
+                    description.contains("\"id\": unique int (0-9)"),
+                    "{}",
+                    description
+                );
+                assert!(
+                    description.contains("\"kind\": int (0-1)"),
+                    "{}",
+                    description
+                );
+            }
+
+            #[test]
+            fn optional_fields_are_described_with_their_presence() {
+                let data = json!((0..200)
+                    .map(|i| match i {
+                        0 => json!({"id": i, "rare": true, "often": 1}),
+                        199 => json!({"id": i}),
+                        _ => json!({"id": i, "often": 1}),
+                    })
+                    .collect::<Vec<_>>());
+                let mut stats = FieldStats::default();
+                stats.add(&data);
+                let schema = crate::infer_schema(data, &crate::InferenceOptions::default());
+                let description = schema
+                    .to_string_pretty_with(&PrettyOptions::builder().field_stats(&stats).build());
+                assert!(
+                    description.contains("\"often\": optional int (1) (present in 99%)"),
+                    "{}",
+                    description
+                );
+                assert!(
+                    description.contains("\"rare\": optional boolean (present in 1%)"),
+                    "{}",
+                    description
+                );
+                assert!(!description.contains("\"id\": unique int (0-199) ("));
+            }
+
+            #[test]
+            fn value_counts_in_json_schema_and_descriptions() {
+                let data = json!([
+                    {"status": "open", "tags": ["a"]},
+                    {"status": "closed", "tags": ["a", "b"]},
+                    {"status": "open", "tags": []}
+                ]);
+                let mut counts = ValueCounts::new(5);
+                counts.add(&data);
+                let schema = array_schema(
+                    3,
+                    3,
+                    object_schema(
+                        vec![
+                            ("status", string_schema(unknown_string(Some(4), Some(6)))),
+                            (
+                                "tags",
+                                array_schema(0, 2, string_schema(unknown_string(Some(1), Some(1)))),
+                            ),
+                        ],
+                        vec![],
+                    ),
+                );
+
+                let options = JsonSchemaOptions {
+                    value_counts: Some(counts.clone()),
+                    ..Default::default()
+                };
+                let document = schema.to_json_schema_document_with(&options);
+                let properties = &document["items"]["properties"];
+                assert_eq!(
+                    properties["status"]["x-drivel-value-counts"],
+                    json!([
+                        {"value": "open", "count": 2, "ratio": 0.6667},
+                        {"value": "closed", "count": 1, "ratio": 0.3333}
+                    ])
+                );
+                assert_eq!(
+                    properties["tags"]["items"]["x-drivel-value-counts"][0],
+                    json!({"value": "a", "count": 2, "ratio": 0.6667})
+                );
+
+                let description = schema.to_string_pretty_with(
+                    &PrettyOptions::builder()
+                        .max_list_items(1)
+                        .value_counts(&counts)
+                        .build(),
+                );
+                // the comma after a field is missing if it happens to be the last one
+                assert!(
+                    description.replace("),\n", ")\n").contains(
+                        "\"status\": string (4-6)\n      \"open\": 2 (66.7%)\n      … (+1 more)\n"
+                    ),
+                    "{}",
+                    description
+                );
+            }
+
+            #[test]
+            fn describes_the_distributions_of_numbers() {
+                let schema =
+                    object_schema(vec![("score", number_schema(integer_range(2, 9)))], vec![]);
+                let mut stats = NumberStats::default();
+                for score in [2, 4, 4, 4, 5, 5, 7, 9] {
+                    stats.add(&json!({"score": score}));
+                }
+                assert_eq!(
+                    schema.to_string_pretty_with(
+                        &PrettyOptions::builder().number_stats(&stats).build()
+                    ),
+                    "{\n  \"score\": int (2-9, mean 5.0, std dev 2.0)\n}"
+                );
+            }
+
+            #[test]
+            fn humanizes_field_names() {
+                assert_eq!(humanize("user_id"), "User id");
+                assert_eq!(humanize("userId"), "User id");
+                assert_eq!(humanize("HTTPStatus"), "Http status");
+                assert_eq!(humanize("__private"), "Private");
+                assert_eq!(humanize(""), "");
+            }
+
             #[test]
             fn json_schema_document_format() {
                 let schema = SchemaState::Boolean;
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::ParsedInput;
+
+/// A step of a [`Selector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// The field of an object with this name, or the element of an array at this index if it
+    /// is a number.
+    Key(String),
+    /// Every element of an array, or every field of an object.
+    Wildcard,
+}
+
+/// Selects the values at a path of the input, such as the payload of API responses wrapped in
+/// an envelope, so that only their schema is inferred. Parsed from one of:
+///
+/// - a JSONPath of names, indices and wildcards, such as `$.data.items[*]` or `$['data'][0]`;
+/// - a JSON Pointer, such as `/data/items`, with `~1` for `/` and `~0` for `~` in names;
+/// - a path as in [`ProduceOptions`](crate::ProduceOptions), such as `data.items[]`, where `[]`
+///   and `*` are wildcards.
+///
+/// A path without wildcards selects at most one value of each input value; with them, it
+/// selects each of the values they match, which are records of their own.
+///
+/// # Example
+///
+/// ```
+/// use drivel::Selector;
+/// use serde_json::json;
+///
+/// let response = json!({"status": "ok", "data": {"items": [{"id": 1}, {"id": 2}]}});
+/// let selector: Selector = "$.data.items[*]".parse().unwrap();
+/// assert_eq!(selector.select(response.clone()), vec![json!({"id": 1}), json!({"id": 2})]);
+///
+/// let selector: Selector = "/data/items/1/id".parse().unwrap();
+/// assert_eq!(selector.select(response), vec![json!(2)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    path: String,
+    steps: Vec<Step>,
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = if let Some(path) = s.strip_prefix('$') {
+            json_path_steps(path)
+        } else if s.is_empty() || s.starts_with('/') {
+            Ok(pointer_steps(s))
+        } else {
+            Ok(path_steps(s))
+        }
+        .map_err(|err| format!("invalid path '{}': {}", s, err))?;
+        Ok(Selector {
+            path: s.to_string(),
+            steps,
+        })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+/// The steps of a JSONPath after its `$`, of `.name`, `.*`, `[N]`, `['name']` and `[*]`.
+fn json_path_steps(path: &str) -> Result<Vec<Step>, String> {
+    let mut steps = vec![];
+    let mut rest = path;
+    while !rest.is_empty() {
+        if rest.starts_with("..") {
+            return Err("recursive descent (`..`) is not supported".to_string());
+        }
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let name = &after[..end];
+            if name.is_empty() {
+                return Err("expected a name after `.`".to_string());
+            }
+            steps.push(match name {
+                "*" => Step::Wildcard,
+                name => Step::Key(name.to_string()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or("expected `]`")?;
+            let inner = after[..end].trim();
+            let quoted = (inner.len() >= 2)
+                .then(|| {
+                    ['\'', '"']
+                        .into_iter()
+                        .find_map(|quote| inner.strip_prefix(quote)?.strip_suffix(quote))
+                })
+                .flatten();
+            steps.push(match (inner, quoted) {
+                (_, Some(name)) => Step::Key(name.to_string()),
+                ("*", None) => Step::Wildcard,
+                (index, None) if !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()) => {
+                    Step::Key(index.to_string())
+                }
+                (inner, None) => {
+                    return Err(format!(
+                        "expected an index, a quoted name or `*` in brackets, got `{}`",
+                        inner
+                    ))
+                }
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Err(format!("expected `.` or `[`, got `{}`", rest));
+        }
+    }
+    Ok(steps)
+}
+
+/// The steps of a JSON Pointer, whose tokens are names or indices.
+fn pointer_steps(pointer: &str) -> Vec<Step> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| Step::Key(token.replace("~1", "/").replace("~0", "~")))
+        .collect()
+}
+
+/// The steps of a dot-separated path, with `[]` for the elements of an array.
+fn path_steps(path: &str) -> Vec<Step> {
+    path.replace("[]", ".*")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| match segment {
+            "*" => Step::Wildcard,
+            name => Step::Key(name.to_string()),
+        })
+        .collect()
+}
+
+fn select_into(value: Value, steps: &[Step], selected: &mut Vec<Value>) {
+    let Some((step, rest)) = steps.split_first() else {
+        selected.push(value);
+        return;
+    };
+    match (step, value) {
+        (Step::Key(name), Value::Object(mut fields)) => {
+            if let Some(field) = fields.remove(name) {
+                select_into(field, rest, selected);
+            }
+        }
+        (Step::Key(index), Value::Array(mut elements)) => {
+            if let Some(index) = index.parse().ok().filter(|index| *index < elements.len()) {
+                select_into(elements.swap_remove(index), rest, selected);
+            }
+        }
+        (Step::Wildcard, Value::Array(elements)) => {
+            for element in elements {
+                select_into(element, rest, selected);
+            }
+        }
+        (Step::Wildcard, Value::Object(fields)) => {
+            for (_, field) in fields {
+                select_into(field, rest, selected);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Selector {
+    /// The values at the path of `value`, which are none if it doesn't have the path, and at
+    /// most one if the path has no wildcards.
+    pub fn select(&self, value: Value) -> Vec<Value> {
+        let mut selected = vec![];
+        select_into(value, &self.steps, &mut selected);
+        selected
+    }
+
+    /// Whether the path has wildcards, and so can select several values of each value.
+    pub fn is_plural(&self) -> bool {
+        self.steps.contains(&Step::Wildcard)
+    }
+
+    /// The values at the path of parsed input: of a document, the value at the path, or an
+    /// array of the values matched by a path with wildcards, whose elements are records; and of
+    /// records, those at the path of each record, leaving out the records without it. Returns
+    /// `None` if nothing in the input is at the path.
+    pub fn select_input(&self, input: ParsedInput) -> Option<ParsedInput> {
+        match input {
+            ParsedInput::Document(value) => {
+                let mut selected = self.select(value);
+                if self.is_plural() {
+                    (!selected.is_empty()).then_some(ParsedInput::Document(Value::Array(selected)))
+                } else {
+                    selected.pop().map(ParsedInput::Document)
+                }
+            }
+            ParsedInput::Records { values, counts } => {
+                let values: Vec<Value> = values
+                    .into_iter()
+                    .flat_map(|value| self.select(value))
+                    .collect();
+                (!values.is_empty()).then_some(ParsedInput::Records { values, counts })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, value: Value) -> Vec<Value> {
+        path.parse::<Selector>().unwrap().select(value)
+    }
+
+    #[test]
+    fn selects_with_json_paths_pointers_and_paths() {
+        let value = json!({"data": {"a/b": 1, "items": [{"id": 1}, {"id": 2}], "x.y": 3}});
+        for path in [
+            "$.data.items[*].id",
+            "$['data'].items.*.id",
+            "data.items[].id",
+        ] {
+            assert_eq!(
+                select(path, value.clone()),
+                vec![json!(1), json!(2)],
+                "{}",
+                path
+            );
+        }
+        for path in ["$.data.items[1]", "/data/items/1", "data.items.1"] {
+            assert_eq!(
+                select(path, value.clone()),
+                vec![json!({"id": 2})],
+                "{}",
+                path
+            );
+        }
+        assert_eq!(select("/data/a~1b", value.clone()), vec![json!(1)]);
+        assert_eq!(select("$.data[\"x.y\"]", value.clone()), vec![json!(3)]);
+        assert_eq!(select("$", value.clone()), vec![value.clone()]);
+        assert_eq!(select("", value.clone()), vec![value.clone()]);
+        assert_eq!(select("$.data.missing", value.clone()), Vec::<Value>::new());
+        assert_eq!(select("/data/items/5", value), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn rejects_unsupported_json_paths() {
+        assert!("$..id".parse::<Selector>().is_err());
+        assert!("$.data[?(@.id)]".parse::<Selector>().is_err());
+        assert!("$.data[".parse::<Selector>().is_err());
+        assert!("$data".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn selects_from_parsed_input() {
+        let documents = ParsedInput::Document(json!({"data": [{"id": 1}, {"id": 2}]}));
+        let selector: Selector = "$.data".parse().unwrap();
+        assert_eq!(
+            selector.select_input(documents.clone()),
+            Some(ParsedInput::Document(json!([{"id": 1}, {"id": 2}])))
+        );
+        let selector: Selector = "$.data[*].id".parse().unwrap();
+        assert_eq!(
+            selector.select_input(documents),
+            Some(ParsedInput::Document(json!([1, 2])))
+        );
+
+        let records = ParsedInput::Records {
+            values: vec![json!({"data": 1}), json!({"other": 2}), json!({"data": 3})],
+            counts: Default::default(),
+        };
+        let selector: Selector = "data".parse().unwrap();
+        assert_eq!(
+            selector
+                .select_input(records.clone())
+                .unwrap()
+                .into_values(),
+            vec![json!(1), json!(3)]
+        );
+        let selector: Selector = "missing".parse().unwrap();
+        assert_eq!(selector.select_input(records), None);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{try_produce_records, try_produce_with, DrivelError, ProduceOptions, SchemaState};
+use rand::{thread_rng, Rng};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// The most bytes of headers a request may have, so that a client can't make the server buffer
+/// without end.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// How long the server waits for a client to send more of its request, so that a client that
+/// connects and sends nothing doesn't hold a thread forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most connections that are answered at once, each on a thread of its own. Any more wait
+/// to be accepted until one of them is answered, so that many clients can't make the server
+/// start a thread for each of them.
+const MAX_CONNECTIONS: usize = 64;
+
+/// A mock HTTP server that answers every request with data freshly produced from a schema, as
+/// `produce` would write it, so that a schema can stand in for an API that doesn't exist yet.
+///
+/// Every method and path gets the same kind of response: a JSON body of `200 OK`, or of
+/// `500 Internal Server Error` if the data can't be produced. With a
+/// [seed](ProduceOptions::seed), the `n`th response is the same from one run to the next, but
+/// each differs from the others.
+///
+/// ```
+/// use drivel::{infer_schema, InferenceOptions, MockServer, ProduceOptions};
+/// use serde_json::json;
+///
+/// let schema = infer_schema(json!({"id": 1, "name": "Ada"}), &InferenceOptions::default());
+/// let server = MockServer::new(schema, 1, ProduceOptions::default());
+/// let body = server.respond().unwrap();
+/// assert!(body["id"].is_i64());
+/// assert!(body["name"].is_string());
+/// ```
+pub struct MockServer {
+    schema: SchemaState,
+    n: usize,
+    options: ProduceOptions,
+    latency: Option<(Duration, Duration)>,
+    responses: AtomicU64,
+}
+
+impl MockServer {
+    /// Creates a server whose responses are produced from `schema` with `options`: `n`
+    /// elements of an array at the root of the schema, or an array of `n` values otherwise if
+    /// `n` is more than 1.
+    pub fn new(schema: SchemaState, n: usize, options: ProduceOptions) -> Self {
+        MockServer {
+            schema,
+            n,
+            options,
+            latency: None,
+            responses: AtomicU64::new(0),
+        }
+    }
+
+    /// Delays each response by a random time between `min` and `max`, to simulate a slow API.
+    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
+        self.latency = Some((min, max.max(min)));
+        self
+    }
+
+    /// Produces the body of the next response.
+    pub fn respond(&self) -> Result<Value, DrivelError> {
+        let index = self.responses.fetch_add(1, Ordering::Relaxed);
+        let options = ProduceOptions {
+            seed: self.options.seed.map(|seed| seed.wrapping_add(index)),
+            ..self.options.clone()
+        };
+        let value = match &self.schema {
+            SchemaState::Array { .. } => try_produce_with(&self.schema, self.n, &options),
+            schema if self.n > 1 => try_produce_records(schema, self.n, &options).map(Value::Array),
+            schema => try_produce_with(schema, 1, &options),
+        };
+        Ok(value?)
+    }
+
+    /// Answers the requests of `listener` for as long as it listens, each connection on a
+    /// thread of its own, with at most 64 at once. Each request is written to stderr, as
+    /// [information](crate::Severity::Info), and connections that can't be accepted are
+    /// [warned](crate::Severity::Warning) about and skipped.
+    pub fn serve(&self, listener: TcpListener) -> Result<(), DrivelError> {
+        let connections = (Mutex::new(0), Condvar::new());
+        std::thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        crate::diagnostics::warn(format!("failed to accept a connection: {}", err));
+                        continue;
+                    }
+                };
+                let (open, closed) = &connections;
+                *closed
+                    .wait_while(open.lock().unwrap(), |open| *open >= MAX_CONNECTIONS)
+                    .unwrap() += 1;
+                scope.spawn(move || {
+                    // a client that goes away before it is answered is no concern of the server
+                    let _ = self.handle(stream);
+                    *open.lock().unwrap() -= 1;
+                    closed.notify_one();
+                });
+            }
+            Ok(())
+        })
+    }
+
+    fn handle(&self, stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader)? {
+            Some(request) => {
+                if let Some((min, max)) = self.latency {
+                    std::thread::sleep(thread_rng().gen_range(min..=max));
+                }
+                let (status, body) = match self.respond() {
+                    Ok(value) => ("200 OK", value),
+                    Err(err) => (
+                        "500 Internal Server Error",
+                        serde_json::json!({"error": err.to_string()}),
+                    ),
+                };
+                let body = body.to_string();
+                crate::diagnostics::info(format!(
+                    "{} {} {} ({} bytes)",
+                    request.method,
+                    request.target,
+                    &status[..3],
+                    body.len()
+                ));
+                Response {
+                    status,
+                    body,
+                    head: request.method == "HEAD",
+                }
+            }
+            None => Response {
+                status: "400 Bad Request",
+                body: serde_json::json!({"error": "malformed request"}).to_string(),
+                head: false,
+            },
+        };
+        response.write(stream)
+    }
+}
+
+/// The parts of a request that the server uses.
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    target: String,
+}
+
+/// Reads the request line and headers of a request, and discards its body, or returns `None`
+/// if it isn't an HTTP request.
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Option<Request>> {
+    let mut lines = Vec::new();
+    let mut read = 0;
+    loop {
+        let mut line = String::new();
+        // a line is only read up to the limit, so that one without an end isn't buffered
+        let n = reader
+            .by_ref()
+            .take((MAX_HEADER_BYTES - read) as u64)
+            .read_line(&mut line)?;
+        read += n;
+        if n == 0 || !line.ends_with('\n') {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']).to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let mut request_line = lines
+        .first()
+        .map(|line| line.split(' '))
+        .into_iter()
+        .flatten();
+    let (Some(method), Some(target), Some(version)) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Ok(None);
+    };
+    if !version.starts_with("HTTP/") {
+        return Ok(None);
+    }
+    let length = lines[1..]
+        .iter()
+        .filter_map(|header| header.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    std::io::copy(&mut reader.take(length), &mut std::io::sink())?;
+    Ok(Some(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+    }))
+}
+
+struct Response {
+    status: &'static str,
+    body: String,
+    /// Whether the response is to a `HEAD` request, which gets the headers of the response to
+    /// a `GET`, with the length of its body, but not the body itself.
+    head: bool,
+}
+
+impl Response {
+    fn write(&self, mut out: impl Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.body.len(),
+        )?;
+        if !self.head {
+            out.write_all(self.body.as_bytes())?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, InferenceOptions};
+    use serde_json::json;
+
+    #[test]
+    fn reads_request_line_and_skips_body() {
+        let mut input: &[u8] =
+            b"POST /users?page=2 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbodyGET";
+        let request = read_request(&mut input).unwrap();
+        assert_eq!(
+            request,
+            Some(Request {
+                method: "POST".to_string(),
+                target: "/users?page=2".to_string(),
+            })
+        );
+        assert_eq!(input, b"GET");
+
+        let mut input: &[u8] = b"hello\r\n\r\n";
+        assert_eq!(read_request(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn gives_up_on_headers_beyond_the_limit() {
+        // a line that never ends
+        let mut input = BufReader::new(std::io::repeat(b'a'));
+        assert_eq!(read_request(&mut input).unwrap(), None);
+
+        let input = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_BYTES)
+        );
+        assert_eq!(read_request(&mut input.as_bytes()).unwrap(), None);
+    }
+
+    #[test]
+    fn serves_fresh_data_for_each_request() {
+        let schema = infer_schema(
+            json!([{"id": 1, "name": "Ada"}, {"id": 2, "name": "Bo"}]),
+            &InferenceOptions::default(),
+        );
+        let options = ProduceOptions {
+            seed: Some(7),
+            ..Default::default()
+        };
+        let server = MockServer::new(schema, 3, options);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        // the server runs until the tests end
+        std::thread::spawn(move || server.serve(listener));
+        let request = |method: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(
+                stream,
+                "{} /users HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                method
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let body = |response: &str| -> Value {
+            serde_json::from_str(response.split_once("\r\n\r\n").unwrap().1).unwrap()
+        };
+        let (first, second) = (request("GET"), request("GET"));
+        assert!(first.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(first.contains("Content-Type: application/json\r\n"));
+        let records = body(&first);
+        assert_eq!(records.as_array().unwrap().len(), 3);
+        assert!(records[0]["id"].is_i64());
+        assert_ne!(records, body(&second));
+
+        // HEAD gets the length of the body that GET would, without the body
+        let head = request("HEAD");
+        let (headers, rest) = head.split_once("\r\n\r\n").unwrap();
+        let length = headers
+            .lines()
+            .find_map(|header| header.strip_prefix("Content-Length: "))
+            .unwrap();
+        assert!(length.parse::<usize>().unwrap() > 2, "{}", headers);
+        assert_eq!(rest, "");
+    }
+}
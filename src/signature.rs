@@ -0,0 +1,853 @@
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::codegen::{any_string, any_string_type};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// Whether a character can be part of a field name or enum value that isn't a JSON string.
+fn is_plain(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '@' | '/' | '$')
+}
+
+/// A field name or enum value as written in a signature: as it is if it is made of characters
+/// that have no meaning in a signature, or as a JSON string otherwise.
+fn token(s: &str) -> String {
+    let plain = !s.is_empty() && s.chars().all(is_plain);
+    if plain {
+        s.to_string()
+    } else {
+        serde_json::to_string(s).unwrap()
+    }
+}
+
+fn number_signature(number_type: &NumberType) -> String {
+    match number_type {
+        NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } => {
+            "float".to_string()
+        }
+        NumberType::Enum { variants } => {
+            let variants = variants
+                .keys()
+                .map(|variant| variant.to_string())
+                .collect::<Vec<_>>();
+            format!("enum({})", variants.join("|"))
+        }
+        _ => "int".to_string(),
+    }
+}
+
+fn string_signature(string_type: &StringType) -> String {
+    let variants = |variants: &std::collections::BTreeSet<String>| {
+        variants
+            .iter()
+            .map(|variant| token(variant))
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+    match string_type {
+        StringType::Enum { variants: values } => format!("enum({})", variants(values)),
+        StringType::OpenEnum {
+            variants: values, ..
+        } => format!("enum({})|string", variants(values)),
+        _ => "string".to_string(),
+    }
+}
+
+/// The signatures of the fields of an object, sorted by name, with `?` after optional ones.
+fn fields_signature<'a>(
+    required: impl Iterator<Item = (&'a String, String)>,
+    optional: impl Iterator<Item = (&'a String, String)>,
+) -> String {
+    let mut fields = required
+        .chain(optional.map(|(key, signature)| (key, format!("{}?", signature))))
+        .collect::<Vec<_>>();
+    fields.sort();
+    let fields = fields
+        .into_iter()
+        .map(|(key, signature)| format!("{}:{}", token(key), signature))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// The signatures of the variants of a union, sorted and without duplicates, so that they don't
+/// depend on the order in which the variants were seen.
+fn union_signature<'a>(variants: impl Iterator<Item = &'a SchemaState>) -> String {
+    let mut variants = variants.map(SchemaState::signature).collect::<Vec<_>>();
+    variants.sort();
+    variants.dedup();
+    variants.join("|")
+}
+
+impl SchemaState {
+    /// A compact, one-line signature of the types of this schema, such as
+    /// `{address:{city:string, zip:string?}, id:int, tags:[string]}`, for comparing and logging
+    /// schemas. Unlike [`SchemaState::to_string_pretty`], it leaves out the ranges, lengths and
+    /// formats seen, so that schemas of values of the same shapes have the same signature.
+    ///
+    /// Fields are sorted by name, followed by `?` if they are optional; arrays are written as
+    /// `[items]`, tuples as `tuple(a, b)`, maps as `{*:values}`, nullable values as
+    /// `type|null`, enums as `enum(a|b|c)` and unions as their sorted variants joined by `|`. Field names and enum values that
+    /// contain other characters than letters, digits and `_-.@/$` are written as JSON strings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, InferenceOptions};
+    /// use serde_json::json;
+    ///
+    /// let input = json!([
+    ///     {"id": 1, "tags": ["a"], "address": {"city": "Oslo", "zip": null}},
+    ///     {"id": 2, "tags": [], "address": {"city": "Bergen"}},
+    /// ]);
+    /// let options = InferenceOptions::default();
+    /// assert_eq!(
+    ///     infer_schema(input, &options).signature(),
+    ///     "[{address:{city:string, zip:null?}, id:int, tags:[string]}]"
+    /// );
+    /// ```
+    pub fn signature(&self) -> String {
+        match self {
+            SchemaState::Initial | SchemaState::Indefinite => "any".to_string(),
+            SchemaState::Null => "null".to_string(),
+            SchemaState::Nullable(inner) => format!("{}|null", inner.signature()),
+            SchemaState::Literal(values) => SchemaState::literal_type(values).signature(),
+            SchemaState::Recursive { schema, .. } => schema.signature(),
+            SchemaState::Reference(name) => format!("recursive {}", name),
+            SchemaState::String(string_type) => string_signature(string_type),
+            SchemaState::Number(number_type) => number_signature(number_type),
+            SchemaState::Boolean => "bool".to_string(),
+            SchemaState::ExtendedJson(extended_type) => match extended_type {
+                ExtendedJsonType::ObjectId => "objectid",
+                ExtendedJsonType::Date => "date",
+                ExtendedJsonType::Long { .. } => "long",
+            }
+            .to_string(),
+            SchemaState::Coordinates(positions) => {
+                let positions = positions.iter().map(number_signature).collect::<Vec<_>>();
+                format!("({})", positions.join(", "))
+            }
+            SchemaState::Tuple(positions) => {
+                let positions = positions
+                    .iter()
+                    .map(SchemaState::signature)
+                    .collect::<Vec<_>>();
+                format!("tuple({})", positions.join(", "))
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                latitude,
+                longitude,
+            } => fields_signature(
+                [
+                    (latitude_key, number_signature(latitude)),
+                    (longitude_key, number_signature(longitude)),
+                ]
+                .into_iter(),
+                std::iter::empty(),
+            ),
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                amount,
+                currencies,
+            } => {
+                let currency = if currencies.is_empty() {
+                    "string".to_string()
+                } else {
+                    let currencies = currencies.iter().cloned().collect::<Vec<_>>();
+                    format!("enum({})", currencies.join("|"))
+                };
+                fields_signature(
+                    [
+                        (amount_key, number_signature(amount)),
+                        (currency_key, currency),
+                    ]
+                    .into_iter(),
+                    std::iter::empty(),
+                )
+            }
+            SchemaState::Array { schema, .. } => format!("[{}]", schema.signature()),
+            SchemaState::Map { schema, .. } => format!("{{*:{}}}", schema.signature()),
+            SchemaState::Object {
+                required, optional, ..
+            } => fields_signature(
+                required.iter().map(|(key, field)| (key, field.signature())),
+                optional.iter().map(|(key, field)| (key, field.signature())),
+            ),
+            SchemaState::Union(variants) => union_signature(variants.iter()),
+            // signatures leave out how many values of each variant were seen
+            SchemaState::Mixed(variants) => {
+                union_signature(variants.iter().map(|(variant, _)| variant))
+            }
+            SchemaState::TaggedUnion { variants, .. } => union_signature(variants.values()),
+        }
+    }
+}
+
+/// An error in a signature that [`SchemaState::from_str`] can't parse: what was wrong, and the
+/// character at which it was noticed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSignatureError {
+    /// The position of the character, counting from 0.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid signature at character {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseSignatureError {}
+
+/// Integers of any size, as a signature's `int`, and as `{"type": "integer"}` is parsed.
+fn any_integer() -> NumberType {
+    NumberType::Integer {
+        min: i64::MIN,
+        max: i64::MAX,
+    }
+}
+
+/// Floats of any size, as a signature's `float`, and as `{"type": "number"}` is parsed.
+fn any_float() -> NumberType {
+    NumberType::Float {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+        non_finite: Default::default(),
+    }
+}
+
+/// The number of elements of arrays and entries of maps in a signature, which leaves them out,
+/// as for arrays parsed from a JSON Schema without `minItems` and `maxItems`.
+const LENGTHS: (usize, usize) = (0, 16);
+
+/// The schema of the variants of a union in a signature: an open enum for an enum followed by
+/// `string`, nullable for variants followed by `null`, and a union of them otherwise.
+fn union_of(mut variants: Vec<SchemaState>) -> SchemaState {
+    if variants.len() > 1 && variants.last() == Some(&SchemaState::Null) {
+        variants.pop();
+        return SchemaState::Nullable(Box::new(union_of(variants)));
+    }
+    let mut merged: Vec<SchemaState> = vec![];
+    for variant in variants {
+        match (merged.last_mut(), variant) {
+            (
+                Some(SchemaState::String(string_type @ StringType::Enum { .. })),
+                SchemaState::String(other @ StringType::Unknown { .. }),
+            ) => {
+                let StringType::Enum { variants } = string_type else {
+                    unreachable!()
+                };
+                *string_type = StringType::OpenEnum {
+                    variants: std::mem::take(variants),
+                    // signatures leave out how often other values were seen
+                    other_ratio: 0.5,
+                    other: Box::new(other),
+                };
+            }
+            (_, variant) => merged.push(variant),
+        }
+    }
+    match <[SchemaState; 1]>::try_from(merged) {
+        Ok([schema]) => schema,
+        Err(variants) => SchemaState::Union(variants),
+    }
+}
+
+/// A recursive descent parser of signatures, which allows whitespace between any two tokens.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            chars: input.char_indices().peekable(),
+            input,
+        }
+    }
+
+    /// The position of the next character, or the length of the input at its end.
+    fn position(&mut self) -> usize {
+        let offset = self
+            .chars
+            .peek()
+            .map_or(self.input.len(), |(offset, _)| *offset);
+        self.input[..offset].chars().count()
+    }
+
+    fn error<T>(&mut self, message: impl Into<String>) -> Result<T, ParseSignatureError> {
+        Err(ParseSignatureError {
+            position: self.position(),
+            message: message.into(),
+        })
+    }
+
+    /// The next character that isn't whitespace, without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    /// Consumes the next character if it is `c`, returning whether it was.
+    fn eat(&mut self, c: char) -> bool {
+        self.peek() == Some(c) && self.chars.next().is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseSignatureError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(found) => self.error(format!("expected '{}', found '{}'", c, found)),
+                None => self.error(format!("expected '{}', found the end", c)),
+            }
+        }
+    }
+
+    fn end(&mut self) -> Result<(), ParseSignatureError> {
+        match self.peek() {
+            Some(found) => self.error(format!("unexpected '{}'", found)),
+            None => Ok(()),
+        }
+    }
+
+    /// A field name or enum value, as it is or as a JSON string.
+    fn token(&mut self) -> Result<String, ParseSignatureError> {
+        if self.peek() == Some('"') {
+            let start = self.chars.next().unwrap().0;
+            let mut escaped = false;
+            for (offset, c) in self.chars.by_ref() {
+                match c {
+                    '"' if !escaped => {
+                        return serde_json::from_str(&self.input[start..=offset]).map_err(|err| {
+                            ParseSignatureError {
+                                position: self.input[..start].chars().count(),
+                                message: format!("invalid string: {}", err),
+                            }
+                        });
+                    }
+                    '\\' => escaped = !escaped,
+                    _ => escaped = false,
+                }
+            }
+            return self.error("unterminated string");
+        }
+        let mut token = String::new();
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| is_plain(*c)) {
+            token.push(c);
+        }
+        if token.is_empty() {
+            match self.peek() {
+                Some(found) => self.error(format!("expected a name, found '{}'", found)),
+                None => self.error("expected a name, found the end"),
+            }
+        } else {
+            Ok(token)
+        }
+    }
+
+    /// Types joined by `|`.
+    fn union(&mut self) -> Result<SchemaState, ParseSignatureError> {
+        let mut variants = vec![self.variant()?];
+        while self.eat('|') {
+            variants.push(self.variant()?);
+        }
+        Ok(union_of(variants))
+    }
+
+    fn variant(&mut self) -> Result<SchemaState, ParseSignatureError> {
+        match self.peek() {
+            Some('[') => {
+                self.chars.next();
+                let schema = self.union()?;
+                self.expect(']')?;
+                Ok(SchemaState::Array {
+                    min_length: LENGTHS.0,
+                    max_length: LENGTHS.1,
+                    lengths: None,
+                    unique_items: false,
+                    schema: Box::new(schema),
+                })
+            }
+            Some('{') => {
+                self.chars.next();
+                if self.eat('*') {
+                    self.expect(':')?;
+                    let schema = self.union()?;
+                    self.expect('}')?;
+                    return Ok(SchemaState::Map {
+                        min_length: LENGTHS.0,
+                        max_length: LENGTHS.1,
+                        keys: any_string_type(),
+                        schema: Box::new(schema),
+                    });
+                }
+                self.fields()
+            }
+            Some('(') => {
+                self.chars.next();
+                let mut positions = vec![self.number()?];
+                while self.eat(',') {
+                    positions.push(self.number()?);
+                }
+                self.expect(')')?;
+                if !(2..=3).contains(&positions.len()) {
+                    return self.error("coordinates have two or three positions");
+                }
+                Ok(SchemaState::Coordinates(positions))
+            }
+            _ => {
+                let position = self.position();
+                let word = self.token()?;
+                match word.as_str() {
+                    "any" => Ok(SchemaState::Indefinite),
+                    "null" => Ok(SchemaState::Null),
+                    "bool" => Ok(SchemaState::Boolean),
+                    "string" => Ok(any_string()),
+                    "int" => Ok(SchemaState::Number(any_integer())),
+                    "float" => Ok(SchemaState::Number(any_float())),
+                    "objectid" => Ok(SchemaState::ExtendedJson(ExtendedJsonType::ObjectId)),
+                    "date" => Ok(SchemaState::ExtendedJson(ExtendedJsonType::Date)),
+                    "long" => Ok(SchemaState::ExtendedJson(ExtendedJsonType::Long {
+                        min: i64::MIN,
+                        max: i64::MAX,
+                    })),
+                    "enum" => self.enumeration(),
+                    "tuple" => self.tuple(),
+                    _ => Err(ParseSignatureError {
+                        position,
+                        message: format!("unknown type '{}'", word),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// The fields of an object, after its `{`.
+    fn fields(&mut self) -> Result<SchemaState, ParseSignatureError> {
+        let mut required = IndexMap::new();
+        let mut optional = IndexMap::new();
+        if !self.eat('}') {
+            loop {
+                let position = self.position();
+                let key = self.token()?;
+                self.expect(':')?;
+                let field = self.union()?;
+                if required.contains_key(&key) || optional.contains_key(&key) {
+                    return Err(ParseSignatureError {
+                        position,
+                        message: format!("duplicate field '{}'", key),
+                    });
+                }
+                if self.eat('?') {
+                    optional.insert(key, field);
+                } else {
+                    required.insert(key, field);
+                }
+                if self.eat('}') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return match self.peek() {
+                        Some(found) => {
+                            self.error(format!("expected ',' or '}}', found '{}'", found))
+                        }
+                        None => self.error("expected ',' or '}', found the end"),
+                    };
+                }
+            }
+        }
+        Ok(SchemaState::Object {
+            required,
+            optional,
+            access: Default::default(),
+            annotations: Default::default(),
+        })
+    }
+
+    /// The values of an enum, after its `enum`, which are integers if they all are.
+    fn enumeration(&mut self) -> Result<SchemaState, ParseSignatureError> {
+        self.expect('(')?;
+        let mut values = vec![self.token()?];
+        while self.eat('|') {
+            values.push(self.token()?);
+        }
+        self.expect(')')?;
+        let integers: Option<BTreeMap<i64, usize>> = values
+            .iter()
+            .map(|value| Some((value.parse().ok()?, 1)))
+            .collect();
+        Ok(match integers {
+            Some(variants) => SchemaState::Number(NumberType::Enum { variants }),
+            None => SchemaState::String(StringType::Enum {
+                variants: values.into_iter().collect::<BTreeSet<_>>(),
+            }),
+        })
+    }
+
+    /// The positions of a tuple, after its `tuple`.
+    fn tuple(&mut self) -> Result<SchemaState, ParseSignatureError> {
+        self.expect('(')?;
+        let mut positions = vec![self.union()?];
+        while self.eat(',') {
+            positions.push(self.union()?);
+        }
+        self.expect(')')?;
+        if positions.len() < 2 {
+            return self.error("tuples have at least two positions");
+        }
+        Ok(SchemaState::Tuple(positions))
+    }
+
+    /// A position of coordinates, `int` or `float`.
+    fn number(&mut self) -> Result<NumberType, ParseSignatureError> {
+        let position = self.position();
+        match self.token()?.as_str() {
+            "int" => Ok(any_integer()),
+            "float" => Ok(any_float()),
+            word => Err(ParseSignatureError {
+                position,
+                message: format!("expected int or float, found '{}'", word),
+            }),
+        }
+    }
+}
+
+impl FromStr for SchemaState {
+    type Err = ParseSignatureError;
+
+    /// Parses a signature as written by [`SchemaState::signature`], such as
+    /// `{id:int, tags:[string], status:enum(open|closed)?}`, for hand-writing quick schemas.
+    ///
+    /// As signatures leave out ranges, lengths and formats, scalars are of any value: `string`
+    /// is of no particular format or length, `int` and `float` are of any size, and arrays and
+    /// maps have up to 16 elements, as for a JSON Schema without bounds. An enum of integers is
+    /// a numeric enum, an enum followed by `|string` an open one, and variants followed by
+    /// `|null` are nullable, so that the signature of a parsed schema is the one it was parsed
+    /// from, up to the order of fields and variants.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::SchemaState;
+    ///
+    /// let schema: SchemaState = "{id:int, tags:[string], nickname:string|null?}".parse().unwrap();
+    /// assert_eq!(schema.signature(), "{id:int, nickname:string|null?, tags:[string]}");
+    /// assert!("{id:integer}".parse::<SchemaState>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let schema = parser.union()?;
+        parser.end()?;
+        Ok(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn integer() -> SchemaState {
+        SchemaState::Number(NumberType::Integer { min: 1, max: 9 })
+    }
+
+    fn string() -> SchemaState {
+        SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: Some(1),
+            max_length: Some(5),
+        })
+    }
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 0,
+            max_length: 3,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    #[test]
+    fn signs_nested_objects_and_arrays() {
+        let schema = object_schema(
+            vec![
+                ("name", string()),
+                ("id", integer()),
+                ("tags", array(string())),
+                (
+                    "address",
+                    object_schema(
+                        vec![("city", string())],
+                        vec![("zip", SchemaState::Nullable(Box::new(string())))],
+                    ),
+                ),
+            ],
+            vec![(
+                "score",
+                SchemaState::Number(NumberType::Float {
+                    min: 0.0,
+                    max: 1.0,
+                    non_finite: Default::default(),
+                }),
+            )],
+        );
+        assert_eq!(
+            schema.signature(),
+            "{address:{city:string, zip:string|null?}, id:int, name:string, score:float?, \
+             tags:[string]}"
+        );
+        assert_eq!(
+            array(array(schema.clone())).signature(),
+            format!("[[{}]]", schema.signature())
+        );
+    }
+
+    #[test]
+    fn signs_enums_maps_and_unions() {
+        let status = SchemaState::String(StringType::Enum {
+            variants: ["open", "closed", "on hold"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        });
+        let map = SchemaState::Map {
+            min_length: 1,
+            max_length: 2,
+            keys: StringType::UUID,
+            schema: Box::new(SchemaState::Boolean),
+        };
+        let schema = object_schema(
+            vec![("status", status), ("flags", map), ("first name", string())],
+            vec![],
+        );
+        assert_eq!(
+            schema.signature(),
+            r#"{"first name":string, flags:{*:bool}, status:enum(closed|"on hold"|open)}"#
+        );
+
+        let union = |variants: Vec<SchemaState>| SchemaState::Union(variants);
+        let a = object_schema(vec![("a", integer())], vec![]);
+        let b = object_schema(vec![("b", string())], vec![]);
+        assert_eq!(
+            union(vec![a.clone(), b.clone()]).signature(),
+            union(vec![b.clone(), a.clone(), b]).signature()
+        );
+        assert_eq!(union(vec![a, integer()]).signature(), "int|{a:int}");
+    }
+
+    #[test]
+    fn equal_schemas_have_equal_signatures() {
+        // fields are kept in hash maps, whose order differs between instances
+        let fields = (0..20).map(|i| format!("field{}", i)).collect::<Vec<_>>();
+        let schema = || {
+            object_schema(
+                fields.iter().map(|key| (key.as_str(), integer())).collect(),
+                vec![],
+            )
+        };
+        assert_eq!(schema().signature(), schema().signature());
+    }
+
+    fn parse(signature: &str) -> SchemaState {
+        signature.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_hand_written_signatures() {
+        let schema = parse(r#"{ "first name" : string, id:int, tags:[enum(a|"b c")]? }"#);
+        let SchemaState::Object {
+            required, optional, ..
+        } = &schema
+        else {
+            panic!("not an object: {:?}", schema);
+        };
+        assert_eq!(required["first name"], any_string());
+        assert_eq!(required["id"], SchemaState::Number(any_integer()));
+        assert_eq!(
+            optional["tags"],
+            parsed_array(SchemaState::String(StringType::Enum {
+                variants: ["a", "b c"].into_iter().map(String::from).collect(),
+            }))
+        );
+
+        assert_eq!(
+            parse("enum(3|1)|null"),
+            SchemaState::Nullable(Box::new(SchemaState::Number(NumberType::Enum {
+                variants: BTreeMap::from([(1, 1), (3, 1)]),
+            })))
+        );
+        assert!(matches!(
+            parse("enum(x|y)|string"),
+            SchemaState::String(StringType::OpenEnum { .. })
+        ));
+        assert_eq!(
+            parse("int|{a:int}"),
+            SchemaState::Union(vec![
+                SchemaState::Number(any_integer()),
+                object_schema(vec![("a", SchemaState::Number(any_integer()))], vec![]),
+            ])
+        );
+        assert!(matches!(parse("{*:bool}"), SchemaState::Map { .. }));
+        assert_eq!(
+            parse("(int, float)"),
+            SchemaState::Coordinates(vec![any_integer(), any_float()])
+        );
+        let tuple = parse("tuple(string, int|null, [bool])");
+        assert_eq!(tuple.signature(), "tuple(string, int|null, [bool])");
+        assert!(matches!(tuple, SchemaState::Tuple(positions) if positions.len() == 3));
+    }
+
+    #[test]
+    fn reports_where_signatures_are_invalid() {
+        let error = |signature: &str| signature.parse::<SchemaState>().unwrap_err();
+        assert_eq!(error("{id:integer}").position, 4);
+        assert_eq!(
+            error("{id:int").message,
+            "expected ',' or '}', found the end"
+        );
+        assert_eq!(error("{a:int, a:bool}").message, "duplicate field 'a'");
+        assert_eq!(error("[int] x").message, "unexpected 'x'");
+        assert_eq!(
+            error("(int)").message,
+            "coordinates have two or three positions"
+        );
+        assert_eq!(
+            error("tuple(int)").message,
+            "tuples have at least two positions"
+        );
+        assert!("\"unterminated".parse::<SchemaState>().is_err());
+    }
+
+    /// An array as parsed from a signature, which leaves out its lengths.
+    fn parsed_array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: LENGTHS.0,
+            max_length: LENGTHS.1,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    /// A random name for a field or an enum value, which may need quoting.
+    fn random_name(rng: &mut StdRng) -> String {
+        let names = [
+            "id",
+            "first name",
+            "a.b",
+            "x\"y",
+            "*",
+            "enum",
+            "null",
+            "é",
+            "$ref",
+        ];
+        names[rng.gen_range(0..names.len())].to_string()
+    }
+
+    /// A random schema of those that a signature can describe exactly: without ranges, lengths
+    /// or formats, and with the variants of unions in the order of their signatures.
+    fn random_schema(rng: &mut StdRng, depth: usize) -> SchemaState {
+        let kinds = if depth == 0 { 9 } else { 14 };
+        match rng.gen_range(0..kinds) {
+            0 => SchemaState::Indefinite,
+            1 => SchemaState::Boolean,
+            2 => any_string(),
+            3 => SchemaState::Number(any_integer()),
+            4 => SchemaState::Number(any_float()),
+            5 => SchemaState::ExtendedJson(ExtendedJsonType::ObjectId),
+            6 => SchemaState::String(StringType::Enum {
+                variants: (0..rng.gen_range(1..4)).map(|_| random_name(rng)).collect(),
+            }),
+            7 => SchemaState::Number(NumberType::Enum {
+                variants: (0..rng.gen_range(1..4))
+                    .map(|_| (rng.gen_range(-5..5), 1))
+                    .collect(),
+            }),
+            8 => SchemaState::String(StringType::OpenEnum {
+                variants: BTreeSet::from([random_name(rng)]),
+                other_ratio: 0.5,
+                other: Box::new(any_string_type()),
+            }),
+            9 => parsed_array(random_schema(rng, depth - 1)),
+            10 => SchemaState::Map {
+                min_length: LENGTHS.0,
+                max_length: LENGTHS.1,
+                keys: any_string_type(),
+                schema: Box::new(random_schema(rng, depth - 1)),
+            },
+            11 => {
+                let mut required = IndexMap::new();
+                let mut optional = IndexMap::new();
+                for _ in 0..rng.gen_range(0..4) {
+                    let fields = if rng.gen() {
+                        &mut required
+                    } else {
+                        &mut optional
+                    };
+                    fields.insert(random_name(rng), random_schema(rng, depth - 1));
+                }
+                optional.retain(|key, _| !required.contains_key(key));
+                SchemaState::Object {
+                    required,
+                    optional,
+                    access: Default::default(),
+                    annotations: Default::default(),
+                }
+            }
+            12 => match random_schema(rng, depth - 1) {
+                schema @ (SchemaState::Nullable(_) | SchemaState::Indefinite) => schema,
+                schema => SchemaState::Nullable(Box::new(schema)),
+            },
+            _ => {
+                // unions of strings would be taken for open enums, and the variants of nested
+                // unions for those of this one
+                let mut variants = (0..3)
+                    .map(|_| random_schema(rng, depth - 1))
+                    .filter(|variant| {
+                        !matches!(
+                            variant,
+                            SchemaState::String(_)
+                                | SchemaState::Nullable(_)
+                                | SchemaState::Union(_)
+                                | SchemaState::Indefinite
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                variants.sort_by_key(SchemaState::signature);
+                variants.dedup_by_key(|variant| variant.signature());
+                match <[SchemaState; 1]>::try_from(variants) {
+                    Ok([variant]) => variant,
+                    Err(variants) if variants.is_empty() => SchemaState::Boolean,
+                    Err(variants) => SchemaState::Union(variants),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signatures_parse_back_into_their_schemas() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..500 {
+            let schema = random_schema(&mut rng, 3);
+            let signature = schema.signature();
+            assert_eq!(parse(&signature), schema, "{}", signature);
+            assert_eq!(parse(&signature).signature(), signature);
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Compression;
+
+/// The units of the sizes of [`parse_size`], in bytes.
+const SIZE_UNITS: [(&str, u64); 8] = [
+    ("KB", 1000),
+    ("MB", 1000 * 1000),
+    ("GB", 1000 * 1000 * 1000),
+    ("TB", 1000 * 1000 * 1000 * 1000),
+    ("KIB", 1 << 10),
+    ("MIB", 1 << 20),
+    ("GIB", 1 << 30),
+    ("TIB", 1 << 40),
+];
+
+/// Parses a number of bytes, such as `1500`, `100MB` or `1.5 GiB`, where `KB`, `MB`, `GB` and
+/// `TB` are powers of 1000, `KiB`, `MiB`, `GiB` and `TiB` powers of 1024, and `B` or no unit
+/// is bytes. Units are in any case, and the `B` may be left out, as in `100M`.
+///
+/// # Example
+///
+/// ```
+/// use drivel::parse_size;
+///
+/// assert_eq!(parse_size("100MB"), Ok(100_000_000));
+/// assert_eq!(parse_size("1.5 KiB"), Ok(1536));
+/// assert!(parse_size("lots").is_err());
+/// ```
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "'{}' is not a size, such as 5000, 500KB or 1.5GB",
+            size.trim()
+        )
+    };
+    let upper = size.trim().to_ascii_uppercase();
+    let split = upper
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(split);
+    let unit = unit.trim();
+    let multiplier = match unit {
+        "" | "B" => 1,
+        _ => SIZE_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit || name.strip_suffix('B') == Some(unit))
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(invalid)?,
+    };
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Limits on the output of a [`SplitWriter`], of which each is optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SplitLimits {
+    /// The most bytes to write in all, after which no more records are taken.
+    pub total_bytes: Option<u64>,
+    /// The most bytes of each file, after which records go into the next file.
+    pub file_bytes: Option<u64>,
+    /// The most records of each file, after which records go into the next file.
+    pub file_records: Option<usize>,
+}
+
+/// What surrounds and separates the records of each file written by a [`SplitWriter`], so
+/// that each file is a whole document of its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Framing {
+    /// The bytes at the start of each file, such as the header row of CSV.
+    pub header: Vec<u8>,
+    /// The bytes between the records of a file.
+    pub separator: Vec<u8>,
+    /// The bytes at the end of each file.
+    pub footer: Vec<u8>,
+}
+
+impl Framing {
+    /// The framing of a JSON array of records, whose elements are each on a line of their own
+    /// if the records are pretty-printed with `indent`.
+    pub fn json_array(indent: Option<usize>) -> Self {
+        match indent {
+            Some(_) => Framing {
+                header: b"[\n".to_vec(),
+                separator: b",\n".to_vec(),
+                footer: b"\n]\n".to_vec(),
+            },
+            None => Framing {
+                header: b"[".to_vec(),
+                separator: b",".to_vec(),
+                footer: b"]\n".to_vec(),
+            },
+        }
+    }
+
+    /// The framing of lines of records, such as JSON lines, or the rows of CSV under `header`.
+    pub fn lines(header: Vec<u8>) -> Self {
+        Framing {
+            header,
+            ..Default::default()
+        }
+    }
+}
+
+/// Opens the `n`th file of a [`SplitWriter`], counting from 1.
+type Open = Box<dyn FnMut(usize) -> io::Result<Box<dyn Write>>>;
+
+/// Where a [`SplitWriter`] writes to.
+enum Destination<W> {
+    /// A single writer, which all of the output goes to.
+    Single(W),
+    /// A series of files, of which the one being written is `current`.
+    Files {
+        open: Open,
+        current: Option<Box<dyn Write>>,
+    },
+}
+
+/// Writes serialized records into a series of numbered files, starting a new file whenever the
+/// current one reaches its limits, and stopping once the output reaches the limit of all files,
+/// so that data of a given size, rather than a given number of records, can be produced.
+///
+/// A record is only written if it fits within the limits, counting the bytes of the
+/// [`Framing`]: a record that would take the output past [`SplitLimits::total_bytes`] isn't
+/// written, and ends the output, while one that would take a file past
+/// [`SplitLimits::file_bytes`] goes into the next file instead, unless it is the first of its
+/// file, as it can't fit any file then.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{Framing, SplitLimits, SplitWriter};
+///
+/// let limits = SplitLimits {
+///     total_bytes: Some(20),
+///     ..Default::default()
+/// };
+/// let mut writer = SplitWriter::new(Vec::new(), Framing::json_array(None), limits);
+/// let mut records = 0;
+/// while writer.write_record(br#"{"id":1}"#).unwrap() {
+///     records += 1;
+/// }
+/// assert_eq!(records, 2);
+/// let (out, summary) = writer.finish().unwrap();
+/// assert_eq!(out.unwrap(), b"[{\"id\":1},{\"id\":1}]\n");
+/// assert_eq!(summary.bytes, 20);
+/// ```
+pub struct SplitWriter<W = Box<dyn Write>> {
+    destination: Destination<W>,
+    framing: Framing,
+    limits: SplitLimits,
+    /// The bytes and records of the file being written, if one has been started.
+    file: Option<(u64, usize)>,
+    summary: SplitSummary,
+}
+
+/// What a [`SplitWriter`] wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SplitSummary {
+    /// The number of records written.
+    pub records: usize,
+    /// The number of bytes written, including the framing.
+    pub bytes: u64,
+    /// The number of files written, or 1 for a single writer.
+    pub files: usize,
+}
+
+impl std::fmt::Display for SplitSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} record{} ({}) in {} file{}",
+            self.records,
+            if self.records == 1 { "" } else { "s" },
+            crate::estimate::human_bytes(self.bytes as usize),
+            self.files,
+            if self.files == 1 { "" } else { "s" },
+        )
+    }
+}
+
+impl<W: Write> SplitWriter<W> {
+    /// Writes all records to `out`, as a single file that is never split, stopping at
+    /// [`SplitLimits::total_bytes`].
+    pub fn new(out: W, framing: Framing, limits: SplitLimits) -> Self {
+        SplitWriter {
+            destination: Destination::Single(out),
+            framing,
+            limits,
+            file: None,
+            summary: SplitSummary::default(),
+        }
+    }
+
+    fn out(&mut self) -> &mut dyn Write {
+        match &mut self.destination {
+            Destination::Single(out) => out,
+            Destination::Files { current, .. } => current
+                .as_mut()
+                .expect("records are written to a file that has been started")
+                .as_mut(),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out().write_all(bytes)?;
+        self.summary.bytes += bytes.len() as u64;
+        if let Some((file_bytes, _)) = &mut self.file {
+            *file_bytes += bytes.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Starts the next file with the header.
+    fn start_file(&mut self) -> io::Result<()> {
+        if let Destination::Files { open, current } = &mut self.destination {
+            *current = Some(open(self.summary.files + 1)?);
+        }
+        self.file = Some((0, 0));
+        self.summary.files += 1;
+        let header = std::mem::take(&mut self.framing.header);
+        let written = self.write(&header);
+        self.framing.header = header;
+        written
+    }
+
+    /// Ends the current file with the footer.
+    fn end_file(&mut self) -> io::Result<()> {
+        let footer = std::mem::take(&mut self.framing.footer);
+        let written = self.write(&footer);
+        self.framing.footer = footer;
+        written?;
+        self.out().flush()?;
+        self.file = None;
+        if let Destination::Files { current, .. } = &mut self.destination {
+            *current = None;
+        }
+        Ok(())
+    }
+
+    /// Writes a record, in a new file if it doesn't fit in the current one. Returns `false`,
+    /// without writing it, if the record would take the output past its limit, in which case no
+    /// more records should be written.
+    pub fn write_record(&mut self, record: &[u8]) -> io::Result<bool> {
+        let header = self.framing.header.len() as u64;
+        let separator = self.framing.separator.len() as u64;
+        let footer = self.framing.footer.len() as u64;
+        let record_bytes = record.len() as u64;
+        let full = match (self.file, &self.destination) {
+            (Some((bytes, records)), Destination::Files { .. }) if records > 0 => {
+                self.limits
+                    .file_records
+                    .is_some_and(|limit| records >= limit)
+                    || self
+                        .limits
+                        .file_bytes
+                        .is_some_and(|limit| bytes + separator + record_bytes + footer > limit)
+            }
+            _ => false,
+        };
+        let starts_file = self.file.is_none() || full;
+        // the size of the output with the record, once its file is ended
+        let total = self.summary.bytes
+            + if full { footer } else { 0 }
+            + if starts_file { header } else { separator }
+            + record_bytes
+            + footer;
+        if self.limits.total_bytes.is_some_and(|limit| total > limit) {
+            return Ok(false);
+        }
+        if full {
+            self.end_file()?;
+        }
+        if starts_file {
+            self.start_file()?;
+        } else {
+            let separator = std::mem::take(&mut self.framing.separator);
+            let written = self.write(&separator);
+            self.framing.separator = separator;
+            written?;
+        }
+        self.write(record)?;
+        self.summary.records += 1;
+        if let Some((_, records)) = &mut self.file {
+            *records += 1;
+        }
+        Ok(true)
+    }
+
+    /// Ends the output, and returns the single writer, if there is one, and what was written. A
+    /// single writer always gets a whole document, even without records, but no files are
+    /// created without records.
+    pub fn finish(mut self) -> io::Result<(Option<W>, SplitSummary)> {
+        if self.file.is_none() && matches!(self.destination, Destination::Single(_)) {
+            self.start_file()?;
+        }
+        if self.file.is_some() {
+            self.end_file()?;
+        }
+        let out = match self.destination {
+            Destination::Single(out) => Some(out),
+            Destination::Files { .. } => None,
+        };
+        Ok((out, self.summary))
+    }
+}
+
+impl SplitWriter {
+    /// Writes the records into files named `{prefix}-00001.{extension}`, `{prefix}-00002...`
+    /// and so on, in the directory `dir`, which is created if it doesn't exist. With a
+    /// `compression`, each file is compressed, and its name ends in the extension of the
+    /// compression as well, such as `part-00001.json.gz`, while the limits are still of the bytes
+    /// before compression.
+    pub fn files(
+        dir: &Path,
+        prefix: &str,
+        extension: &str,
+        compression: Option<Compression>,
+        framing: Framing,
+        limits: SplitLimits,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let dir = dir.to_path_buf();
+        let prefix = prefix.to_string();
+        let extension = match compression {
+            Some(compression) => format!("{}.{}", extension, compression.extension()),
+            None => extension.to_string(),
+        };
+        let open: Open = Box::new(move |n| {
+            let path: PathBuf = dir.join(format!("{}-{:05}.{}", prefix, n, extension));
+            let file = std::fs::File::create(path)?;
+            Ok(match compression {
+                Some(compression) => Box::new(io::BufWriter::new(compression.compress(file)?)),
+                None => Box::new(io::BufWriter::new(file)) as Box<dyn Write>,
+            })
+        });
+        Ok(SplitWriter {
+            destination: Destination::Files {
+                open,
+                current: None,
+            },
+            framing,
+            limits,
+            file: None,
+            summary: SplitSummary::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("5000"), Ok(5000));
+        assert_eq!(parse_size("5000 B"), Ok(5000));
+        assert_eq!(parse_size("100MB"), Ok(100_000_000));
+        assert_eq!(parse_size("100m"), Ok(100_000_000));
+        assert_eq!(parse_size("1.5GB"), Ok(1_500_000_000));
+        assert_eq!(parse_size("2KiB"), Ok(2048));
+        assert_eq!(parse_size("1Gi"), Ok(1 << 30));
+        for size in ["", "MB", "10 XB", "1.2.3KB", "-5"] {
+            assert!(parse_size(size).is_err(), "{}", size);
+        }
+    }
+
+    fn write_all(writer: &mut SplitWriter<Vec<u8>>, records: &[&str]) -> usize {
+        records
+            .iter()
+            .take_while(|record| writer.write_record(record.as_bytes()).unwrap())
+            .count()
+    }
+
+    #[test]
+    fn stops_before_the_record_that_would_exceed_the_budget() {
+        let limits = SplitLimits {
+            total_bytes: Some(13),
+            ..Default::default()
+        };
+        let mut writer = SplitWriter::new(vec![], Framing::lines(b"id\n".to_vec()), limits);
+        assert_eq!(write_all(&mut writer, &["1\n", "22\n", "333\n", "4\n"]), 3);
+        let (out, summary) = writer.finish().unwrap();
+        assert_eq!(out.unwrap(), b"id\n1\n22\n333\n");
+        assert_eq!(
+            summary,
+            SplitSummary {
+                records: 3,
+                bytes: 12,
+                files: 1
+            }
+        );
+
+        // a single writer gets a whole document even if nothing fits
+        let limits = SplitLimits {
+            total_bytes: Some(1),
+            ..Default::default()
+        };
+        let mut writer = SplitWriter::new(vec![], Framing::json_array(Some(2)), limits);
+        assert_eq!(write_all(&mut writer, &["  1"]), 0);
+        assert_eq!(writer.finish().unwrap().0.unwrap(), b"[\n\n]\n");
+    }
+
+    #[test]
+    fn splits_records_into_numbered_files() {
+        let dir = std::env::temp_dir().join(format!("drivel-split-{}", std::process::id()));
+        let limits = SplitLimits {
+            total_bytes: None,
+            file_bytes: Some(12),
+            file_records: Some(3),
+        };
+        let mut writer = SplitWriter::files(
+            &dir,
+            "part",
+            "json",
+            None,
+            Framing::json_array(None),
+            limits,
+        )
+        .unwrap();
+        for record in ["1", "2", "3", "4", "55555", "666666666666"] {
+            assert!(writer.write_record(record.as_bytes()).unwrap());
+        }
+        let (out, summary) = writer.finish().unwrap();
+        assert!(out.is_none());
+        assert_eq!(summary.to_string(), "6 records (33 bytes) in 3 files");
+        let file = |n: usize| std::fs::read_to_string(dir.join(format!("part-{:05}.json", n)));
+        assert_eq!(file(1).unwrap(), "[1,2,3]\n");
+        assert_eq!(file(2).unwrap(), "[4,55555]\n");
+        // a record that can't fit any file has one of its own
+        assert_eq!(file(3).unwrap(), "[666666666666]\n");
+        assert!(file(4).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,597 @@
+use crate::codegen::{snake_case_words, unique};
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// The dialects of SQL that a schema can be described in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SqlDialect {
+    /// PostgreSQL, which has types for UUIDs, timestamps and JSON.
+    #[default]
+    Postgres,
+    /// MySQL, whose enums of strings are `ENUM` columns.
+    #[value(name = "mysql")]
+    MySql,
+    /// SQLite, whose columns are mostly `INTEGER`, `REAL` or `TEXT`.
+    Sqlite,
+}
+
+/// A column of a table.
+struct Column {
+    name: String,
+    /// Its type, such as `BIGINT`.
+    sql_type: String,
+    not_null: bool,
+    /// A `CHECK` constraint of its values, or what follows its type, such as
+    /// `GENERATED BY DEFAULT AS IDENTITY`.
+    suffix: Option<String>,
+}
+
+/// A `CREATE TABLE` statement.
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+    primary_key: String,
+    /// The columns that refer to the primary keys of other tables, and those keys.
+    foreign_keys: Vec<(String, Key)>,
+}
+
+/// The primary key of a table that has been declared, to which other tables refer.
+#[derive(Clone)]
+struct Key {
+    table: String,
+    column: String,
+    sql_type: String,
+}
+
+/// The tables of SQL output, a table for each object shape and array.
+struct Tables {
+    dialect: SqlDialect,
+    /// The tables, in an order in which each only refers to those before it.
+    tables: Vec<Table>,
+    /// The names of all tables, including those whose columns are still being declared.
+    names: Vec<String>,
+}
+
+impl SqlDialect {
+    fn quote(&self, identifier: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", identifier),
+            _ => format!("\"{}\"", identifier),
+        }
+    }
+
+    fn literal(&self, value: &str) -> String {
+        let mut value = value.replace('\'', "''");
+        if *self == SqlDialect::MySql {
+            // MySQL reads backslashes in strings as escapes
+            value = value.replace('\\', "\\\\");
+        }
+        format!("'{}'", value)
+    }
+
+    fn integer(&self, min: i64, max: i64) -> &'static str {
+        let fits = i32::try_from(min).is_ok() && i32::try_from(max).is_ok();
+        match self {
+            SqlDialect::Sqlite => "INTEGER",
+            SqlDialect::Postgres if fits => "INTEGER",
+            SqlDialect::MySql if fits => "INT",
+            _ => "BIGINT",
+        }
+    }
+
+    fn float(&self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "DOUBLE PRECISION",
+            SqlDialect::MySql => "DOUBLE",
+            SqlDialect::Sqlite => "REAL",
+        }
+    }
+
+    fn boolean(&self) -> &'static str {
+        match self {
+            SqlDialect::Sqlite => "INTEGER",
+            _ => "BOOLEAN",
+        }
+    }
+
+    fn json(&self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "JSONB",
+            SqlDialect::MySql => "JSON",
+            SqlDialect::Sqlite => "TEXT",
+        }
+    }
+
+    /// The type of the column of a primary key generated by the database, and what follows it.
+    fn generated_key(&self) -> (&'static str, &'static str) {
+        match self {
+            SqlDialect::Postgres => ("BIGINT", "GENERATED BY DEFAULT AS IDENTITY"),
+            SqlDialect::MySql => ("BIGINT", "AUTO_INCREMENT"),
+            // an INTEGER primary key is the rowid, which is generated
+            SqlDialect::Sqlite => ("INTEGER", ""),
+        }
+    }
+
+    fn number(&self, number: &NumberType) -> &'static str {
+        let (min, max) = match number {
+            NumberType::Float { .. } | NumberType::Multiple { integer: false, .. } => {
+                return self.float()
+            }
+            NumberType::Integer { min, max } | NumberType::Sequence { min, max, .. } => {
+                (*min, *max)
+            }
+            NumberType::Multiple { min, max, .. } => (*min as i64, *max as i64),
+            NumberType::Enum { variants: values } | NumberType::Flags { values } => (
+                values.keys().next().copied().unwrap_or_default(),
+                values.keys().next_back().copied().unwrap_or_default(),
+            ),
+        };
+        self.integer(min, max)
+    }
+
+    /// The type of a column of strings, and a constraint of its values, if any.
+    fn string(&self, string: &StringType, column: &str) -> (String, Option<String>) {
+        let sql_type = match (self, string) {
+            (SqlDialect::MySql, StringType::Enum { variants }) => {
+                let values: Vec<String> = variants.iter().map(|v| self.literal(v)).collect();
+                return (format!("ENUM({})", values.join(", ")), None);
+            }
+            (_, StringType::Enum { variants }) => {
+                let values: Vec<String> = variants.iter().map(|v| self.literal(v)).collect();
+                let check = format!("CHECK ({} IN ({}))", self.quote(column), values.join(", "));
+                return ("TEXT".to_string(), Some(check));
+            }
+            (SqlDialect::Sqlite, _) => "TEXT",
+            (SqlDialect::Postgres, StringType::UUID) => "UUID",
+            (SqlDialect::MySql, StringType::UUID) => "CHAR(36)",
+            (_, StringType::IsoDate) => "DATE",
+            (SqlDialect::Postgres, StringType::DateTimeISO8601) => "TIMESTAMPTZ",
+            (SqlDialect::MySql, StringType::DateTimeISO8601) => "DATETIME",
+            (_, StringType::FixedWidthNumeric { width }) => {
+                return (format!("CHAR({})", width), None)
+            }
+            _ => "TEXT",
+        };
+        (sql_type.to_string(), None)
+    }
+}
+
+/// The fields of an object, the required ones first, with whether each is optional.
+fn fields(schema: &SchemaState) -> Vec<(&str, &SchemaState, bool)> {
+    let SchemaState::Object {
+        required, optional, ..
+    } = schema
+    else {
+        unreachable!("only objects have fields")
+    };
+    required
+        .iter()
+        .map(|(key, field)| (key.as_str(), field, false))
+        .chain(
+            optional
+                .iter()
+                .map(|(key, field)| (key.as_str(), field, true)),
+        )
+        .collect()
+}
+
+/// Whether a field can be the primary key of its table: a required integer or string named
+/// `id`.
+fn is_key(name: &str, schema: &SchemaState, optional: bool) -> bool {
+    name == "id"
+        && !optional
+        && matches!(
+            schema,
+            SchemaState::Number(NumberType::Integer { .. } | NumberType::Sequence { .. })
+                | SchemaState::String(_)
+        )
+}
+
+impl Tables {
+    fn reserve(&mut self, name: &str) -> String {
+        let name = unique(self.names.iter().cloned().chain([name.to_string()]))
+            .pop()
+            .unwrap();
+        self.names.push(name.clone());
+        name
+    }
+
+    /// Declares a table of the values of a schema, which are the rows of the fields of an
+    /// object, or of a single `value` column otherwise, and which belong to a row of `parent`,
+    /// if they are the elements of one of its arrays. The tables of the objects in its fields
+    /// are declared before it, and those of its arrays after it.
+    fn table(&mut self, schema: &SchemaState, name: &str, parent: Option<&Key>) -> Key {
+        let (schema, nullable) = match schema {
+            SchemaState::Nullable(inner) => (inner.as_ref(), true),
+            schema => (schema, false),
+        };
+        let fields = match schema {
+            SchemaState::Object { .. } => fields(schema),
+            schema => vec![("value", schema, nullable)],
+        };
+        let name = self.reserve(name);
+
+        let natural_key = fields
+            .iter()
+            .position(|(key, field, optional)| is_key(key, field, *optional));
+        let mut column_names: Vec<String> = fields
+            .iter()
+            .map(|(key, _, _)| snake_case_words(key))
+            .collect();
+        if let Some(parent) = parent {
+            column_names.insert(0, format!("{}_id", parent.table));
+        }
+        if natural_key.is_none() {
+            column_names.insert(0, "id".to_string());
+        }
+        let mut column_names = unique(column_names).into_iter();
+
+        let mut table = Table {
+            name: name.clone(),
+            columns: vec![],
+            primary_key: String::new(),
+            foreign_keys: vec![],
+        };
+        if natural_key.is_none() {
+            let (sql_type, suffix) = self.dialect.generated_key();
+            table.primary_key = column_names.next().unwrap();
+            table.columns.push(Column {
+                name: table.primary_key.clone(),
+                sql_type: sql_type.to_string(),
+                not_null: true,
+                suffix: (!suffix.is_empty()).then(|| suffix.to_string()),
+            });
+        }
+        if let Some(parent) = parent {
+            let column = column_names.next().unwrap();
+            table.columns.push(Column {
+                name: column.clone(),
+                sql_type: parent.sql_type.clone(),
+                not_null: true,
+                suffix: None,
+            });
+            table.foreign_keys.push((column, parent.clone()));
+        }
+
+        let mut arrays = vec![];
+        for (i, ((_, field, optional), column)) in fields.into_iter().zip(column_names).enumerate()
+        {
+            let (field, nullable) = match field {
+                SchemaState::Nullable(inner) => (inner.as_ref(), true),
+                field => (field, optional),
+            };
+            if Some(i) == natural_key {
+                table.primary_key = column.clone();
+            }
+            match field {
+                SchemaState::Object { .. } => {
+                    let key = self.table(field, &format!("{}_{}", name, column), None);
+                    let column = unique(
+                        table
+                            .columns
+                            .iter()
+                            .map(|column| column.name.clone())
+                            .chain([format!("{}_id", column)]),
+                    )
+                    .pop()
+                    .unwrap();
+                    table.columns.push(Column {
+                        name: column.clone(),
+                        sql_type: key.sql_type.clone(),
+                        not_null: !nullable,
+                        suffix: None,
+                    });
+                    table.foreign_keys.push((column, key));
+                }
+                SchemaState::Array { schema, .. } => arrays.push((column, schema)),
+                SchemaState::GeoPoint {
+                    latitude_key,
+                    longitude_key,
+                    ..
+                } => {
+                    for part in [latitude_key, longitude_key] {
+                        table.columns.push(Column {
+                            name: format!("{}_{}", column, snake_case_words(part)),
+                            sql_type: self.dialect.float().to_string(),
+                            not_null: !nullable,
+                            suffix: None,
+                        });
+                    }
+                }
+                SchemaState::Money {
+                    amount_key,
+                    currency_key,
+                    amount,
+                    currencies,
+                } => {
+                    table.columns.push(Column {
+                        name: format!("{}_{}", column, snake_case_words(amount_key)),
+                        sql_type: self.dialect.number(amount).to_string(),
+                        not_null: !nullable,
+                        suffix: None,
+                    });
+                    let currency = format!("{}_{}", column, snake_case_words(currency_key));
+                    let variants = currencies.clone();
+                    let (sql_type, suffix) = self
+                        .dialect
+                        .string(&StringType::Enum { variants }, &currency);
+                    table.columns.push(Column {
+                        name: currency,
+                        sql_type,
+                        not_null: !nullable,
+                        suffix,
+                    });
+                }
+                field => {
+                    let (mut sql_type, suffix) = self.column_type(field, &column);
+                    if Some(i) == natural_key
+                        && sql_type == "TEXT"
+                        && self.dialect == SqlDialect::MySql
+                    {
+                        // MySQL can't index a TEXT column without a length
+                        sql_type = "VARCHAR(255)".to_string();
+                    }
+                    table.columns.push(Column {
+                        name: column,
+                        sql_type,
+                        not_null: !nullable && *field != SchemaState::Null,
+                        suffix,
+                    });
+                }
+            }
+        }
+        // the primary key comes first
+        let position = (table.columns.iter())
+            .position(|column| column.name == table.primary_key)
+            .unwrap();
+        let primary_key = table.columns.remove(position);
+        table.columns.insert(0, primary_key);
+
+        let key = Key {
+            table: name.clone(),
+            column: table.primary_key.clone(),
+            sql_type: table.columns[0].sql_type.clone(),
+        };
+        self.tables.push(table);
+        for (column, items) in arrays {
+            self.table(items, &format!("{}_{}", name, column), Some(&key));
+        }
+        key
+    }
+
+    /// The type of a column of values that are neither objects nor arrays, and a constraint of
+    /// them, if any. Those that aren't all of one type, such as unions and maps, are JSON.
+    fn column_type(&self, schema: &SchemaState, column: &str) -> (String, Option<String>) {
+        let sql_type = match schema {
+            SchemaState::String(string) => return self.dialect.string(string, column),
+            SchemaState::Number(number) => self.dialect.number(number),
+            SchemaState::Boolean => self.dialect.boolean(),
+            SchemaState::Literal(values) => {
+                return self.column_type(&SchemaState::literal_type(values), column)
+            }
+            SchemaState::ExtendedJson(ExtendedJsonType::ObjectId) => match self.dialect {
+                SqlDialect::MySql => "CHAR(24)",
+                _ => "TEXT",
+            },
+            SchemaState::ExtendedJson(ExtendedJsonType::Date) => {
+                return self.dialect.string(&StringType::DateTimeISO8601, column);
+            }
+            SchemaState::ExtendedJson(ExtendedJsonType::Long { .. }) => "BIGINT",
+            _ => self.dialect.json(),
+        };
+        (sql_type.to_string(), None)
+    }
+}
+
+/// Renders a schema as the SQL `CREATE TABLE` statements of a database that its records could
+/// be loaded into. The root is a table named `name`, of the fields of the records, or of those
+/// of the elements of an array at the root. Each nested object is a table of its own, which
+/// its parent refers to with a foreign key, and each array is a child table of its elements,
+/// which refers to its parent. A required field named `id` is the primary key of its table,
+/// or one is generated otherwise. Optional and nullable fields are nullable columns, enums of
+/// strings are constrained to their values, and values without a type of their own, such as
+/// unions and maps, are JSON.
+pub(crate) fn to_sql(schema: &SchemaState, name: &str, dialect: SqlDialect) -> String {
+    let mut tables = Tables {
+        dialect,
+        tables: vec![],
+        names: vec![],
+    };
+    let records = match schema {
+        SchemaState::Array { schema, .. } => schema,
+        schema => schema,
+    };
+    tables.table(records, &snake_case_words(name), None);
+
+    let quote = |identifier: &str| dialect.quote(identifier);
+    let statements: Vec<String> = tables
+        .tables
+        .iter()
+        .map(|table| {
+            let mut lines: Vec<String> = table
+                .columns
+                .iter()
+                .map(|column| {
+                    let mut line = format!("{} {}", quote(&column.name), column.sql_type);
+                    if column.not_null {
+                        line.push_str(" NOT NULL");
+                    }
+                    if let Some(suffix) = &column.suffix {
+                        line = format!("{} {}", line, suffix);
+                    }
+                    line
+                })
+                .collect();
+            lines.push(format!("PRIMARY KEY ({})", quote(&table.primary_key)));
+            for (column, key) in &table.foreign_keys {
+                lines.push(format!(
+                    "FOREIGN KEY ({}) REFERENCES {} ({})",
+                    quote(column),
+                    quote(&key.table),
+                    quote(&key.column)
+                ));
+            }
+            format!(
+                "CREATE TABLE {} (\n    {}\n);",
+                quote(&table.name),
+                lines.join(",\n    ")
+            )
+        })
+        .collect();
+    statements.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 0,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn integer(max: i64) -> SchemaState {
+        SchemaState::Number(NumberType::Integer { min: 0, max })
+    }
+
+    fn text() -> SchemaState {
+        SchemaState::String(StringType::Unknown {
+            strings_seen: vec![],
+            chars_seen: vec![],
+            min_length: None,
+            max_length: None,
+        })
+    }
+
+    fn status() -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: ["open".to_string(), "won't fix".to_string()].into(),
+        })
+    }
+
+    #[test]
+    fn declares_tables_of_nested_objects_and_arrays() {
+        let schema = array(object_schema(
+            vec![
+                ("id", SchemaState::String(StringType::UUID)),
+                ("status", status()),
+                (
+                    "createdAt",
+                    SchemaState::String(StringType::DateTimeISO8601),
+                ),
+                ("address", object_schema(vec![("city", text())], vec![])),
+                ("tags", array(text())),
+                (
+                    "lines",
+                    array(object_schema(vec![("quantity", integer(i64::MAX))], vec![])),
+                ),
+            ],
+            vec![
+                ("note", SchemaState::Nullable(Box::new(text()))),
+                ("extra", SchemaState::Union(vec![text(), integer(9)])),
+            ],
+        ));
+        assert_eq!(
+            to_sql(&schema, "Order", SqlDialect::Postgres),
+            r#"CREATE TABLE "order_address" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "city" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE "order" (
+    "id" UUID NOT NULL,
+    "status" TEXT NOT NULL CHECK ("status" IN ('open', 'won''t fix')),
+    "created_at" TIMESTAMPTZ NOT NULL,
+    "address_id" BIGINT NOT NULL,
+    "note" TEXT,
+    "extra" JSONB,
+    PRIMARY KEY ("id"),
+    FOREIGN KEY ("address_id") REFERENCES "order_address" ("id")
+);
+
+CREATE TABLE "order_tags" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "order_id" UUID NOT NULL,
+    "value" TEXT NOT NULL,
+    PRIMARY KEY ("id"),
+    FOREIGN KEY ("order_id") REFERENCES "order" ("id")
+);
+
+CREATE TABLE "order_lines" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "order_id" UUID NOT NULL,
+    "quantity" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    FOREIGN KEY ("order_id") REFERENCES "order" ("id")
+);"#
+        );
+    }
+
+    #[test]
+    fn writes_the_types_of_each_dialect() {
+        let schema = object_schema(
+            vec![
+                ("id", text()),
+                ("status", status()),
+                ("active", SchemaState::Boolean),
+                ("counts", array(integer(9))),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            to_sql(&schema, "Item", SqlDialect::MySql),
+            r#"CREATE TABLE `item` (
+    `id` VARCHAR(255) NOT NULL,
+    `status` ENUM('open', 'won''t fix') NOT NULL,
+    `active` BOOLEAN NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE TABLE `item_counts` (
+    `id` BIGINT NOT NULL AUTO_INCREMENT,
+    `item_id` VARCHAR(255) NOT NULL,
+    `value` INT NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`item_id`) REFERENCES `item` (`id`)
+);"#
+        );
+        assert_eq!(
+            to_sql(&schema, "Item", SqlDialect::Sqlite),
+            r#"CREATE TABLE "item" (
+    "id" TEXT NOT NULL,
+    "status" TEXT NOT NULL CHECK ("status" IN ('open', 'won''t fix')),
+    "active" INTEGER NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE "item_counts" (
+    "id" INTEGER NOT NULL,
+    "item_id" TEXT NOT NULL,
+    "value" INTEGER NOT NULL,
+    PRIMARY KEY ("id"),
+    FOREIGN KEY ("item_id") REFERENCES "item" ("id")
+);"#
+        );
+    }
+
+    #[test]
+    fn declares_a_table_of_values_that_are_not_objects() {
+        assert_eq!(
+            to_sql(&array(integer(9)), "Scores", SqlDialect::Postgres),
+            r#"CREATE TABLE "scores" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "value" INTEGER NOT NULL,
+    PRIMARY KEY ("id")
+);"#
+        );
+        // a field named like a generated column is numbered
+        let schema = object_schema(vec![("id", SchemaState::Boolean)], vec![]);
+        assert!(to_sql(&schema, "T", SqlDialect::Sqlite).contains("\"id2\" INTEGER NOT NULL"));
+    }
+}
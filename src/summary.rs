@@ -0,0 +1,435 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::{
+    produce_options::{field_path, items_path},
+    DescribeOptions, NumberType, NumericBounds, SampleCounts, SchemaState, StringType,
+    ToJsonSchema,
+};
+
+/// A summary of what was inferred about the values at each path of a schema, for tools that
+/// read the results of inference rather than the schema itself, such as dashboards.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaSummary {
+    /// The values at each path, in the order of their paths, starting with the root.
+    pub fields: Vec<FieldSummary>,
+    /// How many of the records read the schema was inferred from, if they were sampled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleCounts>,
+}
+
+/// What was inferred about the values at a path of a schema, such as `orders[].total`, as used
+/// by [`ProduceOptions`](crate::ProduceOptions). The values of the variants of a union at the
+/// same path are summarised together. The counts and ratios are those of the data that the
+/// schema was inferred from, and are left out if they weren't collected.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldSummary {
+    /// The path of the values, where the root is the empty path, the elements of an array are
+    /// at `[]` under it, and the values of a map at `*`.
+    pub path: String,
+    /// The JSON Schema types of the values, such as `string` and `null`, or none if nothing is
+    /// known about them.
+    pub types: Vec<String>,
+    /// The format of strings, such as `email` or `date-time`, as in JSON Schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Whether the field is in every object it could be in. The root, the elements of arrays
+    /// and the values of maps always are.
+    pub required: bool,
+    /// Whether the values may be `null`.
+    pub nullable: bool,
+    /// The smallest number seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    /// The largest number seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    /// The length of the shortest string, array or map seen, as a number of characters,
+    /// elements or entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// The length of the longest string, array or map seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// The values of an enum, or the only values seen, which are likely all there are.
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub enum_values: Vec<Value>,
+    /// The name of the recursive definition whose values are nested at this path, such as the
+    /// children of a tree node, which are summarised where it is defined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recursive: Option<String>,
+    /// The number of objects that had the field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub present: Option<usize>,
+    /// The fraction of the objects that could have had the field that did, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence: Option<f64>,
+    /// The fraction of the values of the field that were `null`, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub null_ratio: Option<f64>,
+    /// Whether the strings or integers of the field, such as IDs, were all different.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub unique: bool,
+    /// The mean of the numbers seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    /// The standard deviation of the numbers seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std_dev: Option<f64>,
+    /// How often each value occurred, from the most to the least common, if there were few
+    /// enough distinct values to count.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_counts: Option<Vec<ValueCount>>,
+}
+
+/// How often a value occurred at a path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueCount {
+    pub value: Value,
+    pub count: usize,
+}
+
+impl FieldSummary {
+    fn new(path: &str, required: bool) -> Self {
+        FieldSummary {
+            path: path.to_string(),
+            types: vec![],
+            format: None,
+            required,
+            nullable: false,
+            min: None,
+            max: None,
+            min_length: None,
+            max_length: None,
+            enum_values: vec![],
+            recursive: None,
+            present: None,
+            presence: None,
+            null_ratio: None,
+            unique: false,
+            mean: None,
+            std_dev: None,
+            value_counts: None,
+        }
+    }
+
+    fn add_type(&mut self, name: &str) {
+        if !self.types.iter().any(|t| t == name) {
+            self.types.push(name.to_string());
+        }
+    }
+
+    fn add_lengths(&mut self, min: usize, max: usize) {
+        self.min_length = Some(self.min_length.map_or(min, |m| m.min(min)));
+        self.max_length = Some(self.max_length.map_or(max, |m| m.max(max)));
+    }
+
+    fn add_bounds(&mut self, min: Option<&Value>, max: Option<&Value>) {
+        let number = |value: &Option<Value>| value.as_ref().and_then(Value::as_f64);
+        if let Some(min) = min.filter(|min| min.is_number()) {
+            if number(&self.min).is_none_or(|m| min.as_f64().is_some_and(|min| min < m)) {
+                self.min = Some(min.clone());
+            }
+        }
+        if let Some(max) = max.filter(|max| max.is_number()) {
+            if number(&self.max).is_none_or(|m| max.as_f64().is_some_and(|max| max > m)) {
+                self.max = Some(max.clone());
+            }
+        }
+    }
+
+    fn add_enum_values(&mut self, values: impl IntoIterator<Item = Value>) {
+        for value in values {
+            if !self.enum_values.contains(&value) {
+                self.enum_values.push(value);
+            }
+        }
+    }
+
+    /// Adds what is known about a schema whose values are neither containers nor unions, from
+    /// its JSON Schema.
+    fn add_leaf(&mut self, schema: &SchemaState) {
+        let mut json = schema.to_json_schema();
+        schema.add_numeric_bounds(&mut json, NumericBounds::Inclusive);
+        schema.add_lengths(&mut json);
+        match schema {
+            SchemaState::String(_) => self.add_type("string"),
+            SchemaState::Number(
+                NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+            ) => self.add_type("number"),
+            SchemaState::Number(_) => self.add_type("integer"),
+            _ => match json.get("type") {
+                Some(Value::String(name)) => self.add_type(name),
+                Some(Value::Array(names)) => names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .for_each(|name| self.add_type(name)),
+                _ => {}
+            },
+        }
+        if let Some(format) = json.get("format").and_then(Value::as_str) {
+            self.format.get_or_insert_with(|| format.to_string());
+        }
+        self.add_bounds(json.get("minimum"), json.get("maximum"));
+        if let (Some(min), Some(max)) = (
+            json.get("minLength").and_then(Value::as_u64),
+            json.get("maxLength").and_then(Value::as_u64),
+        ) {
+            self.add_lengths(min as usize, max as usize);
+        }
+        match schema {
+            SchemaState::String(
+                StringType::Enum { variants } | StringType::OpenEnum { variants, .. },
+            ) => self.add_enum_values(variants.iter().map(|v| Value::from(v.as_str()))),
+            SchemaState::Number(NumberType::Enum { variants }) => {
+                self.add_enum_values(variants.keys().map(|&v| Value::from(v)))
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Summarizer<'o> {
+    options: &'o DescribeOptions,
+    fields: BTreeMap<String, FieldSummary>,
+}
+
+impl Summarizer<'_> {
+    fn add(&mut self, schema: &SchemaState, path: &str, required: bool) {
+        let field = self
+            .fields
+            .entry(path.to_string())
+            .or_insert_with(|| FieldSummary::new(path, required));
+        field.required &= required;
+        match schema {
+            SchemaState::Nullable(inner) => {
+                field.nullable = true;
+                field.add_type("null");
+                self.add(inner, path, required)
+            }
+            SchemaState::Null => {
+                field.nullable = true;
+                field.add_type("null");
+            }
+            SchemaState::Recursive { schema, .. } => self.add(schema, path, required),
+            SchemaState::Reference(name) => {
+                field.add_type("object");
+                field.recursive = Some(name.clone());
+            }
+            SchemaState::Literal(values) => {
+                field.add_leaf(&SchemaState::literal_type(values));
+                field.add_enum_values(values.iter().cloned());
+            }
+            SchemaState::Array {
+                min_length,
+                max_length,
+                schema,
+                ..
+            } => {
+                field.add_type("array");
+                field.add_lengths(*min_length, *max_length);
+                self.add(schema, &items_path(path), true)
+            }
+            SchemaState::Tuple(positions) => {
+                field.add_type("array");
+                field.add_lengths(positions.len(), positions.len());
+                for position in positions {
+                    self.add(position, &items_path(path), true);
+                }
+            }
+            SchemaState::Map {
+                min_length,
+                max_length,
+                schema,
+                ..
+            } => {
+                field.add_type("object");
+                field.add_lengths(*min_length, *max_length);
+                self.add(schema, &field_path(path, "*"), true)
+            }
+            SchemaState::Object {
+                required: required_fields,
+                optional,
+                ..
+            } => {
+                field.add_type("object");
+                for (key, value) in required_fields {
+                    self.add(value, &field_path(path, key), true);
+                }
+                for (key, value) in optional {
+                    self.add(value, &field_path(path, key), false);
+                }
+            }
+            SchemaState::Union(variants) => {
+                for variant in variants {
+                    self.add(variant, path, required);
+                }
+            }
+            SchemaState::Mixed(variants) => {
+                for (variant, _) in variants {
+                    self.add(variant, path, required);
+                }
+            }
+            SchemaState::TaggedUnion { variants, .. } => {
+                for variant in variants.values() {
+                    self.add(variant, path, required);
+                }
+            }
+            leaf => field.add_leaf(leaf),
+        }
+    }
+
+    /// Adds the statistics of the data that the schema was inferred from to the summaries.
+    fn add_stats(&mut self) {
+        let field_stats = self.options.json_schema.field_stats.as_ref();
+        let value_counts = self.options.json_schema.value_counts.as_ref();
+        let number_stats = self.options.number_stats.as_ref();
+        for (path, field) in &mut self.fields {
+            if let Some(counts) = field_stats.and_then(|stats| stats.get(path)) {
+                field.present = Some(counts.present);
+                field.presence = Some(counts.presence());
+                field.null_ratio = Some(counts.null_ratio());
+            }
+            field.unique = field_stats.is_some_and(|stats| stats.is_unique(path));
+            if let Some(distribution) = number_stats.and_then(|stats| stats.get(path)) {
+                field.mean = Some(distribution.mean);
+                field.std_dev = Some(distribution.std_dev);
+            }
+            field.value_counts = value_counts
+                .and_then(|counts| counts.get(path))
+                .map(|counts| {
+                    counts
+                        .into_iter()
+                        .map(|(value, count)| ValueCount { value, count })
+                        .collect()
+                });
+        }
+    }
+}
+
+impl SchemaState {
+    /// Summarises what was inferred about the values at each path of this schema, along with
+    /// the statistics of the data it was inferred from that `options` has, such as how often
+    /// each field was present, as is written by `describe --format json`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{infer_schema, DescribeOptions, InferenceOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = infer_schema(
+    ///     json!([{"id": 1, "tags": ["a"]}, {"id": 7}]),
+    ///     &InferenceOptions::default(),
+    /// );
+    /// let summary = schema.summary(&DescribeOptions::default());
+    ///
+    /// let paths: Vec<_> = summary.fields.iter().map(|f| f.path.as_str()).collect();
+    /// assert_eq!(paths, vec!["", "[]", "[].id", "[].tags", "[].tags[]"]);
+    /// let id = &summary.fields[2];
+    /// assert_eq!(id.types, vec!["integer"]);
+    /// assert_eq!((id.min.clone(), id.max.clone()), (Some(json!(1)), Some(json!(7))));
+    /// assert!(!summary.fields[3].required);
+    /// ```
+    pub fn summary(&self, options: &DescribeOptions) -> SchemaSummary {
+        let mut summarizer = Summarizer {
+            options,
+            fields: BTreeMap::new(),
+        };
+        summarizer.add(self, "", true);
+        summarizer.add_stats();
+        SchemaSummary {
+            fields: summarizer.fields.into_values().collect(),
+            sample: options.sample,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, FieldStats, InferenceOptions, JsonSchemaOptions, ValueCounts};
+    use serde_json::json;
+
+    #[test]
+    fn summarises_fields_with_the_statistics_of_the_input() {
+        let records = json!([
+            {"id": 1, "email": "a@example.com", "status": "open", "score": 1.5},
+            {"id": 2, "email": null, "status": "closed", "score": 2.5},
+            {"id": 3, "status": "open"}
+        ]);
+        let schema = infer_schema(records.clone(), &InferenceOptions::default());
+        let mut field_stats = FieldStats::default();
+        field_stats.add(&records);
+        let mut value_counts = ValueCounts::new(5);
+        value_counts.add(&records);
+        let options = DescribeOptions {
+            json_schema: JsonSchemaOptions {
+                field_stats: Some(field_stats),
+                value_counts: Some(value_counts),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let summary = serde_json::to_value(schema.summary(&options)).unwrap();
+        let field = |path: &str| {
+            summary["fields"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|field| field["path"] == path)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            field("[]"),
+            json!({"path": "[]", "types": ["object"], "required": true, "nullable": false})
+        );
+        assert_eq!(field("[].id")["min"], json!(1));
+        assert_eq!(field("[].id")["max"], json!(3));
+        assert_eq!(field("[].id")["presence"], json!(1.0));
+
+        let email = field("[].email");
+        assert_eq!(email["types"], json!(["null", "string"]));
+        assert_eq!(email["format"], json!("email"));
+        assert_eq!(email["required"], json!(false));
+        assert_eq!(email["nullable"], json!(true));
+        assert_eq!(email["present"], json!(2));
+        assert_eq!(email["null_ratio"], json!(0.5));
+
+        let score = field("[].score");
+        assert_eq!(score["types"], json!(["number"]));
+        assert_eq!(score["min"], json!(1.5));
+
+        let status = field("[].status");
+        assert_eq!(
+            status["value_counts"],
+            json!([{"value": "open", "count": 2}, {"value": "closed", "count": 1}])
+        );
+        assert!(summary.get("sample").is_none());
+    }
+
+    #[test]
+    fn summarises_the_variants_of_unions_at_one_path() {
+        let schema = SchemaState::Union(vec![
+            SchemaState::Number(NumberType::Integer { min: 5, max: 9 }),
+            SchemaState::Number(NumberType::Float {
+                min: -1.5,
+                max: 2.0,
+                non_finite: Default::default(),
+            }),
+            SchemaState::String(StringType::Enum {
+                variants: ["a".to_string()].into(),
+            }),
+        ]);
+        let summary = schema.summary(&DescribeOptions::default());
+        assert_eq!(summary.fields.len(), 1);
+        let field = &summary.fields[0];
+        assert_eq!(field.types, vec!["integer", "number", "string"]);
+        assert_eq!(field.min, Some(json!(-1.5)));
+        assert_eq!(field.max, Some(json!(9)));
+        assert_eq!(field.enum_values, vec![json!("a")]);
+    }
+}
@@ -0,0 +1,207 @@
+use indexmap::IndexSet;
+use serde_json::{Map, Value};
+
+use crate::DrivelError;
+
+/// A table of column names and rows, as read from tabular JSON by [`records_from_table`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    /// The names of the columns, in the order of the header.
+    pub columns: Vec<String>,
+    /// One object per row, with a field for each of its columns.
+    pub records: Vec<Value>,
+}
+
+/// Reads "tabular" JSON, an array whose first element is a header of column names and whose
+/// other elements are rows of values, as objects with a field for each column, as with
+/// `--tabular-array`. A row shorter than the header leaves out the fields of the missing
+/// columns, so that they are inferred as optional.
+///
+/// Fails if the value isn't an array of arrays, if the header has other values than strings or
+/// the same name twice, or if a row is longer than the header.
+///
+/// # Example
+///
+/// ```
+/// use drivel::records_from_table;
+/// use serde_json::json;
+///
+/// let table = records_from_table(json!([["id", "name"], [1, "Ada"], [2]])).unwrap();
+/// assert_eq!(table.columns, vec!["id", "name"]);
+/// assert_eq!(table.records, vec![json!({"id": 1, "name": "Ada"}), json!({"id": 2})]);
+/// ```
+pub fn records_from_table(table: Value) -> Result<Table, DrivelError> {
+    let invalid = |reason: String| DrivelError::Parse(format!("not a tabular array: {}", reason));
+    let Value::Array(rows) = table else {
+        return Err(invalid(
+            "expected an array of a header and rows".to_string(),
+        ));
+    };
+    let mut rows = rows.into_iter();
+    let columns = match rows.next() {
+        Some(Value::Array(header)) => header
+            .into_iter()
+            .map(|column| match column {
+                Value::String(name) => Ok(name),
+                other => Err(invalid(format!(
+                    "expected the header to hold column names, found {}",
+                    other
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(other) => {
+            return Err(invalid(format!(
+                "expected a header of column names, found {}",
+                other
+            )))
+        }
+        None => return Err(invalid("expected a header of column names".to_string())),
+    };
+    for (index, column) in columns.iter().enumerate() {
+        if columns[..index].contains(column) {
+            return Err(invalid(format!("the header has column '{}' twice", column)));
+        }
+    }
+
+    let records = rows
+        .enumerate()
+        .map(|(index, row)| match row {
+            Value::Array(values) if values.len() <= columns.len() => Ok(Value::Object(
+                columns.iter().cloned().zip(values).collect::<Map<_, _>>(),
+            )),
+            Value::Array(values) => Err(invalid(format!(
+                "row {} has {} values, more than the {} columns of the header",
+                index + 1,
+                values.len(),
+                columns.len()
+            ))),
+            other => Err(invalid(format!(
+                "expected row {} to be an array, found {}",
+                index + 1,
+                other
+            ))),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Table { columns, records })
+}
+
+/// Writes records as tabular JSON, the reverse of [`records_from_table`]: a header of the
+/// `columns`, followed by any other fields of the records in the order they first appear in, and a
+/// row of the values of each record, with `null` for the fields it doesn't have. Values that
+/// aren't objects are rows of their own.
+///
+/// # Example
+///
+/// ```
+/// use drivel::table_from_records;
+/// use serde_json::json;
+///
+/// let records = vec![json!({"name": "Ada", "id": 1}), json!({"id": 2, "age": 36})];
+/// assert_eq!(
+///     table_from_records(records, &["id".to_string(), "name".to_string()]),
+///     json!([["id", "name", "age"], [1, "Ada", null], [2, null, 36]])
+/// );
+/// ```
+pub fn table_from_records(records: Vec<Value>, columns: &[String]) -> Value {
+    let mut columns = columns.to_vec();
+    let others: IndexSet<&String> = records
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|record| record.keys())
+        .filter(|key| !columns.contains(key))
+        .collect();
+    columns.extend(others.into_iter().cloned());
+
+    let header = Value::Array(columns.iter().cloned().map(Value::String).collect());
+    let rows = records.into_iter().map(|record| match record {
+        Value::Object(mut fields) => Value::Array(
+            columns
+                .iter()
+                .map(|column| fields.remove(column).unwrap_or(Value::Null))
+                .collect(),
+        ),
+        other => other,
+    });
+    Value::Array(std::iter::once(header).chain(rows).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{infer_schema, produce_with, InferenceOptions, ProduceOptions, SchemaState};
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        json!([
+            ["id", "name", "score", "active"],
+            [1, "Ada", 9.5, true],
+            [2, "Grace", 7.25, false],
+            [3, "Alan", 8.0]
+        ])
+    }
+
+    #[test]
+    fn infers_an_object_schema_from_a_header_and_rows() {
+        let table = records_from_table(fixture()).unwrap();
+        assert_eq!(table.columns, vec!["id", "name", "score", "active"]);
+        let options = InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        };
+        let schema = infer_schema(Value::Array(table.records), &options);
+        let SchemaState::Array { schema: items, .. } = &schema else {
+            panic!("not an array: {:?}", schema);
+        };
+        let SchemaState::Object {
+            required, optional, ..
+        } = items.as_ref()
+        else {
+            panic!("not an object: {:?}", items);
+        };
+        assert_eq!(required.len(), 3);
+        assert!(matches!(required["score"], SchemaState::Number(_)));
+        assert_eq!(optional["active"], SchemaState::Boolean);
+
+        // produced records can be written back as a header and rows
+        let produced = produce_with(&schema, 1, &ProduceOptions::default());
+        let rows = table_from_records(produced.as_array().unwrap().clone(), &table.columns);
+        let rows = rows.as_array().unwrap();
+        assert_eq!(rows[0], json!(["id", "name", "score", "active"]));
+        assert!(rows[1..]
+            .iter()
+            .all(|row| row.as_array().unwrap().len() == 4 && row[1].is_string()));
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_tabular() {
+        let reason = |table: Value| records_from_table(table).unwrap_err().to_string();
+        assert!(reason(json!({"id": 1})).contains("expected an array"));
+        assert!(reason(json!([])).contains("expected a header"));
+        assert!(reason(json!([["id", 1]])).contains("column names, found 1"));
+        assert!(reason(json!([["id", "id"]])).contains("column 'id' twice"));
+        assert!(reason(json!([["id"], [1, 2]])).contains("row 1 has 2 values"));
+        assert!(reason(json!([["id"], {"id": 1}])).contains("row 1 to be an array"));
+    }
+}
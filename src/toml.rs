@@ -0,0 +1,547 @@
+use serde_json::{Map, Value};
+
+use crate::DrivelError;
+
+/// Parses a TOML document, such as a `drivel.toml` config file, into the JSON value of its
+/// root table.
+///
+/// Tables, arrays of tables, dotted and quoted keys, inline tables, arrays, all four kinds of
+/// strings, integers, floats and booleans are supported. Dates and times are strings, as JSON
+/// has no type for them, and so are floats that aren't finite, such as `inf`, as JSON can't
+/// represent them.
+///
+/// # Example
+///
+/// ```
+/// use drivel::parse_toml;
+/// use serde_json::json;
+///
+/// let config = r#"
+/// ## options for every mode
+/// infer-enum = true
+///
+/// [fields]
+/// "users[].email" = { format = "email" }
+/// "users[].role" = { pool = ["admin", "user"] }
+/// "#;
+/// assert_eq!(
+///     parse_toml(config).unwrap(),
+///     json!({
+///         "infer-enum": true,
+///         "fields": {
+///             "users[].email": {"format": "email"},
+///             "users[].role": {"pool": ["admin", "user"]}
+///         }
+///     })
+/// );
+/// ```
+pub fn parse_toml(s: &str) -> Result<Value, DrivelError> {
+    let mut parser = Parser {
+        chars: s.chars().collect(),
+        position: 0,
+    };
+    parser.document().map_err(|message| {
+        DrivelError::Parse(format!("TOML error at line {}: {}", parser.line(), message))
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    /// The line of the current position, counting from 1.
+    fn line(&self) -> usize {
+        1 + self.chars[..self.position.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.position + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.position += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}', found the end", expected)),
+        }
+    }
+
+    /// Skips spaces and tabs.
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace, newlines and comments, as between the values of an array.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => self.position += 1,
+                Some('#') => {
+                    while !matches!(self.peek(), Some('\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skips the rest of a line, which may only have whitespace and a comment left.
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.position += 1;
+            }
+        }
+        self.eat('\r');
+        match self.next() {
+            Some('\n') | None => Ok(()),
+            Some(c) => Err(format!("expected the end of the line, found '{}'", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Value, String> {
+        let mut root = Map::new();
+        // the path of the table that keys are added to
+        let mut current: Vec<String> = vec![];
+        // the tables that have had a header, which can't have another
+        let mut defined: Vec<Vec<String>> = vec![];
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(Value::Object(root)),
+                Some('[') => {
+                    self.position += 1;
+                    let is_array = self.eat('[');
+                    self.skip_whitespace();
+                    let path = self.key()?;
+                    self.skip_whitespace();
+                    self.expect(']')?;
+                    if is_array {
+                        self.expect(']')?;
+                    }
+                    let (last, parents) = path.split_last().unwrap();
+                    let parent = table_at(&mut root, parents)?;
+                    if is_array {
+                        match parent
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Array(vec![]))
+                        {
+                            Value::Array(tables) => tables.push(Value::Object(Map::new())),
+                            _ => return Err(format!("{} is not an array of tables", last)),
+                        }
+                    } else {
+                        if defined.contains(&path) {
+                            return Err(format!("table {} is defined twice", path.join(".")));
+                        }
+                        table_at(parent, std::slice::from_ref(last))?;
+                        defined.push(path.clone());
+                    }
+                    current = path;
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let (key, value) = self.key_value()?;
+                    insert(table_at(&mut root, &current)?, &key, value)?;
+                    self.end_of_line()?;
+                }
+            }
+        }
+    }
+
+    fn key_value(&mut self) -> Result<(Vec<String>, Value), String> {
+        let key = self.key()?;
+        self.skip_whitespace();
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value = self.value()?;
+        Ok((key, value))
+    }
+
+    /// A key, which is a list of bare or quoted keys separated by dots.
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut key = vec![];
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.position;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.position += 1;
+                    }
+                    if start == self.position {
+                        return Err(match self.peek() {
+                            Some(c) => format!("expected a key, found '{}'", c),
+                            None => "expected a key, found the end".to_string(),
+                        });
+                    }
+                    self.chars[start..self.position].iter().collect()
+                }
+            };
+            key.push(part);
+            self.skip_whitespace();
+            if !self.eat('.') {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.multiline_string('"').map(Value::from),
+            Some('"') => self.basic_string().map(Value::from),
+            Some('\'') if self.starts_with("'''") => self.multiline_string('\'').map(Value::from),
+            Some('\'') => self.literal_string().map(Value::from),
+            Some('[') => {
+                self.position += 1;
+                let mut values = vec![];
+                loop {
+                    self.skip_blank();
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_blank();
+                    if !self.eat(',') {
+                        self.skip_blank();
+                        self.expect(']')?;
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut table = Map::new();
+                self.skip_whitespace();
+                if self.eat('}') {
+                    return Ok(Value::Object(table));
+                }
+                loop {
+                    let (key, value) = self.key_value()?;
+                    insert(&mut table, &key, value)?;
+                    self.skip_whitespace();
+                    if !self.eat(',') {
+                        self.expect('}')?;
+                        return Ok(Value::Object(table));
+                    }
+                }
+            }
+            Some(_) => {
+                let start = self.position;
+                while matches!(self.peek(), Some(c) if !c.is_whitespace() && !matches!(c, ',' | ']' | '}' | '#'))
+                {
+                    self.position += 1;
+                }
+                // a date may be followed by a time after a space
+                if self.peek() == Some(' ')
+                    && self
+                        .chars
+                        .get(self.position + 1)
+                        .is_some_and(char::is_ascii_digit)
+                    && self.chars[start..self.position].contains(&'-')
+                {
+                    self.position += 1;
+                    while matches!(self.peek(), Some(c) if !c.is_whitespace() && !matches!(c, ',' | ']' | '}' | '#'))
+                    {
+                        self.position += 1;
+                    }
+                }
+                if start == self.position {
+                    return Err("expected a value".to_string());
+                }
+                let token: String = self.chars[start..self.position].iter().collect();
+                scalar(&token)
+            }
+            None => Err("expected a value, found the end".to_string()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some('\n') | None => {
+                    self.position -= 1;
+                    return Err("unterminated string".to_string());
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(s),
+                Some('\n') | None => {
+                    self.position -= 1;
+                    return Err("unterminated string".to_string());
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// A string between three of `quote`, which has escapes if it is `"`.
+    fn multiline_string(&mut self, quote: char) -> Result<String, String> {
+        self.position += 3;
+        // a newline right after the opening quotes isn't part of the string
+        self.eat('\r');
+        self.eat('\n');
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote && self.starts_with(&format!("{}{}", quote, quote)) => {
+                    self.position += 2;
+                    // up to two quotes before the closing ones are part of the string
+                    for _ in 0..2 {
+                        if self.eat(quote) {
+                            s.push(quote);
+                        }
+                    }
+                    return Ok(s);
+                }
+                Some('\\') if quote == '"' => {
+                    if matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                        // a backslash at the end of a line trims the whitespace that follows
+                        self.skip_blank_space();
+                    } else {
+                        s.push(self.escape()?);
+                    }
+                }
+                Some(c) => s.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    /// Skips whitespace and newlines, but not comments, which are part of a string.
+    fn skip_blank_space(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+            self.position += 1;
+        }
+    }
+
+    /// The character of an escape, after its backslash.
+    fn escape(&mut self) -> Result<char, String> {
+        let digits = match self.next() {
+            Some('b') => return Ok('\u{8}'),
+            Some('t') => return Ok('\t'),
+            Some('n') => return Ok('\n'),
+            Some('f') => return Ok('\u{c}'),
+            Some('r') => return Ok('\r'),
+            Some('"') => return Ok('"'),
+            Some('\\') => return Ok('\\'),
+            Some('u') => 4,
+            Some('U') => 8,
+            Some(c) => return Err(format!("invalid escape '\\{}'", c)),
+            None => return Err("unterminated string".to_string()),
+        };
+        let end = (self.position + digits).min(self.chars.len());
+        let hex: String = self.chars[self.position..end].iter().collect();
+        self.position = end;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .filter(|_| hex.len() == digits)
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid unicode escape '{}'", hex))
+    }
+}
+
+/// The value of a bare token: a boolean, an integer, a float, or a date or time as a string.
+fn scalar(token: &str) -> Result<Value, String> {
+    match token {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => return Ok(Value::from(token)),
+        _ => {}
+    }
+    let invalid = || format!("invalid value '{}'", token);
+    let bytes = token.as_bytes();
+    let is_date =
+        bytes.len() >= 10 && bytes[4] == b'-' && bytes[..4].iter().all(u8::is_ascii_digit);
+    let is_time = bytes.len() >= 8 && bytes[2] == b':' && bytes[..2].iter().all(u8::is_ascii_digit);
+    if is_date || is_time {
+        return Ok(Value::from(token));
+    }
+    if token.starts_with('_') || token.ends_with('_') || token.contains("__") {
+        return Err(invalid());
+    }
+    let digits = token.replace('_', "");
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = digits.strip_prefix(prefix) {
+            return i64::from_str_radix(digits, radix)
+                .map(Value::from)
+                .map_err(|_| invalid());
+        }
+    }
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-') {
+        return Err(invalid());
+    }
+    if digits.contains(['.', 'e', 'E']) {
+        digits
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(invalid)
+    } else {
+        digits
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| invalid())
+    }
+}
+
+/// The table at a path of keys in a table, which is created if there is none, or the last
+/// table of an array of tables.
+fn table_at<'a>(
+    table: &'a mut Map<String, Value>,
+    path: &[String],
+) -> Result<&'a mut Map<String, Value>, String> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(table);
+    };
+    let value = table
+        .entry(first.clone())
+        .or_insert_with(|| Value::Object(Map::new()));
+    let table = match value {
+        Value::Object(table) => table,
+        Value::Array(tables) => match tables.last_mut() {
+            Some(Value::Object(table)) => table,
+            _ => return Err(format!("{} is not a table", first)),
+        },
+        _ => return Err(format!("{} is not a table", first)),
+    };
+    table_at(table, rest)
+}
+
+/// Inserts the value of a dotted key into a table.
+fn insert(table: &mut Map<String, Value>, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().unwrap();
+    let table = table_at(table, parents)?;
+    if table.contains_key(last) {
+        return Err(format!("{} is defined twice", key.join(".")));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_tables_and_arrays_of_tables() {
+        let document = r#"
+            title = "drivel"  # a comment
+            [produce]
+            seed = 42
+            [produce.owner]
+            name = 'Ada'
+            [[source]]
+            path = "a.json"
+            [[source]]
+            path = "b.json"
+            weight.value = 2
+        "#;
+        assert_eq!(
+            parse_toml(document).unwrap(),
+            json!({
+                "title": "drivel",
+                "produce": {"seed": 42, "owner": {"name": "Ada"}},
+                "source": [{"path": "a.json"}, {"path": "b.json", "weight": {"value": 2}}]
+            })
+        );
+    }
+
+    #[test]
+    fn parses_values() {
+        let document = r#"
+            strings = ["tab\there", 'C:\path', "\u00e9", """
+first
+second""", '''it's''']
+            numbers = [1_000, -17, 0xff, 0o17, 0b101, 3.5, 1e3, -0.25]
+            booleans = [true, false]
+            dates = [1979-05-27, 1979-05-27T07:32:00Z, 1979-05-27 07:32:00, 07:32:00]
+            nested = [ [1, 2], { a = 1, b.c = "d" }, ]
+            empty = { }
+        "#;
+        assert_eq!(
+            parse_toml(document).unwrap(),
+            json!({
+                "strings": ["tab\there", "C:\\path", "é", "first\nsecond", "it's"],
+                "numbers": [1000, -17, 255, 15, 5, 3.5, 1000.0, -0.25],
+                "booleans": [true, false],
+                "dates": ["1979-05-27", "1979-05-27T07:32:00Z", "1979-05-27 07:32:00", "07:32:00"],
+                "nested": [[1, 2], {"a": 1, "b": {"c": "d"}}],
+                "empty": {}
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_documents_with_their_line() {
+        for (document, line) in [
+            ("a = 1\na = 2\n", 2),
+            ("[t]\n[t]\n", 2),
+            ("a = \"unterminated\n", 1),
+            ("a = 1 b = 2", 1),
+            ("\n\na = 1__0", 3),
+            ("a = [1, 2", 1),
+            ("a = 1\n[a]", 2),
+            ("= 1", 1),
+            ("a = # nothing", 1),
+        ] {
+            match parse_toml(document) {
+                Err(DrivelError::Parse(message)) => assert!(
+                    message.starts_with(&format!("TOML error at line {}:", line)),
+                    "{}: {}",
+                    document,
+                    message
+                ),
+                other => panic!("{}: {:?}", document, other),
+            }
+        }
+    }
+}
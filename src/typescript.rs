@@ -0,0 +1,498 @@
+use crate::codegen::{self, pascal_case};
+use crate::{ExtendedJsonType, FieldAccess, NumberType, SchemaState, StringType};
+
+/// The name of the root type of TypeScript output, unless another is given.
+pub(crate) const DEFAULT_TYPE_NAME: &str = "InferredSchema";
+
+/// Whether a field name can be written as a property name without quotes.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+pub(crate) fn property_name(key: &str) -> String {
+    if is_identifier(key) {
+        key.to_string()
+    } else {
+        serde_json::to_string(key).unwrap()
+    }
+}
+
+/// The TypeScript type of a union of types, such as `string | null`.
+fn union(types: Vec<String>) -> String {
+    let mut unique: Vec<String> = vec![];
+    for t in types {
+        if !unique.contains(&t) {
+            unique.push(t);
+        }
+    }
+    unique.join(" | ")
+}
+
+/// The type of the elements of an array, in parentheses if it is a union.
+fn array_of(element: String) -> String {
+    if element.contains(" | ") {
+        format!("({})[]", element)
+    } else {
+        format!("{}[]", element)
+    }
+}
+
+/// An inline object type of the given fields, such as `{ lat: number; lng: number }`.
+fn inline_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, t)| format!("{}: {}", property_name(key), t))
+        .collect();
+    format!("{{ {} }}", fields.join("; "))
+}
+
+fn string_type(string_type: &StringType) -> String {
+    match string_type {
+        StringType::Enum { variants } => union(
+            variants
+                .iter()
+                .map(|variant| serde_json::to_string(variant).unwrap())
+                .collect(),
+        ),
+        // the values of the enum are kept for documentation, although any string fits
+        StringType::OpenEnum { variants, .. } => union(
+            variants
+                .iter()
+                .map(|variant| serde_json::to_string(variant).unwrap())
+                .chain(std::iter::once("string".to_string()))
+                .collect(),
+        ),
+        _ => "string".to_string(),
+    }
+}
+
+fn number_type(number_type: &NumberType) -> String {
+    match number_type {
+        NumberType::Enum { variants } => {
+            union(variants.keys().map(|value| value.to_string()).collect())
+        }
+        _ => "number".to_string(),
+    }
+}
+
+/// The declarations of TypeScript output, an interface for each object shape.
+struct Declarations {
+    /// The fields of each interface.
+    interfaces: codegen::Declarations<Vec<String>>,
+}
+
+impl Declarations {
+    /// Declares an interface for an object, named `name` unless another object has that name
+    /// already, in which case it is prefixed with the name of the type that holds it, `parent`,
+    /// or numbered. Objects with the same fields share one interface, unless they are a recursive
+    /// definition, `recursive`, whose values within it refer to its interface by name.
+    fn interface(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> String {
+        let SchemaState::Object {
+            required,
+            optional,
+            access,
+            ..
+        } = schema
+        else {
+            unreachable!("only objects are declared as interfaces")
+        };
+        let index = self.interfaces.start();
+        if let Some(definition) = recursive {
+            self.interfaces.name(index, definition, name, parent);
+        }
+
+        let keys: Vec<(&String, bool)> = required
+            .keys()
+            .map(|key| (key, false))
+            .chain(optional.keys().map(|key| (key, true)))
+            .collect();
+        let fields = keys
+            .into_iter()
+            .map(|(key, is_optional)| {
+                let field = if is_optional {
+                    &optional[key]
+                } else {
+                    &required[key]
+                };
+                let readonly = if access.get(key) == Some(&FieldAccess::ReadOnly) {
+                    "readonly "
+                } else {
+                    ""
+                };
+                format!(
+                    "{}{}{}: {};",
+                    readonly,
+                    property_name(key),
+                    if is_optional { "?" } else { "" },
+                    self.type_of(field, &pascal_case(key), name)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.interfaces.finish(index, fields, name, parent)
+    }
+
+    /// The TypeScript type of the values of a schema, declaring an interface for the objects
+    /// among them, named after `name`, the name of the field they are in as a type.
+    fn type_of(&mut self, schema: &SchemaState, name: &str, parent: &str) -> String {
+        match schema {
+            SchemaState::Initial | SchemaState::Indefinite => "unknown".to_string(),
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                self.interface(schema, name, parent, Some(definition))
+            }
+            SchemaState::Recursive { schema, .. } => self.type_of(schema, name, parent),
+            // only objects are declared, so another recursive definition can't be referred to
+            SchemaState::Reference(definition) => self
+                .interfaces
+                .recursive(definition)
+                .unwrap_or("unknown")
+                .to_string(),
+            SchemaState::Null => "null".to_string(),
+            SchemaState::Nullable(inner) => {
+                union(vec![self.type_of(inner, name, parent), "null".to_string()])
+            }
+            SchemaState::String(string) => string_type(string),
+            SchemaState::Number(number) => number_type(number),
+            SchemaState::Boolean => "boolean".to_string(),
+            // scalars are literal types as they are written in JSON
+            SchemaState::Literal(values)
+                if values.iter().all(|v| !v.is_array() && !v.is_object()) =>
+            {
+                union(values.iter().map(ToString::to_string).collect())
+            }
+            SchemaState::Literal(values) => {
+                self.type_of(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::Array { schema, .. } => {
+                array_of(self.type_of(schema, &format!("{}Item", name), parent))
+            }
+            SchemaState::Object { .. } => self.interface(schema, name, parent, None),
+            SchemaState::Map { schema, .. } => format!(
+                "Record<string, {}>",
+                self.type_of(schema, &format!("{}Value", name), parent)
+            ),
+            SchemaState::ExtendedJson(extended) => {
+                let key = match extended {
+                    ExtendedJsonType::ObjectId => "$oid",
+                    ExtendedJsonType::Date => "$date",
+                    ExtendedJsonType::Long { .. } => "$numberLong",
+                };
+                inline_object(&[(key, "string".to_string())])
+            }
+            SchemaState::Coordinates(positions) => format!(
+                "[{}]",
+                positions
+                    .iter()
+                    .map(|_| "number")
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SchemaState::Tuple(positions) => {
+                let positions: Vec<String> = positions
+                    .iter()
+                    .map(|position| self.type_of(position, &format!("{}Item", name), parent))
+                    .collect();
+                format!("[{}]", positions.join(", "))
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                ..
+            } => inline_object(&[
+                (latitude_key, "number".to_string()),
+                (longitude_key, "number".to_string()),
+            ]),
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                currencies,
+                ..
+            } => inline_object(&[
+                (amount_key, "number".to_string()),
+                (
+                    currency_key,
+                    string_type(&StringType::Enum {
+                        variants: currencies.clone(),
+                    }),
+                ),
+            ]),
+            SchemaState::Union(variants) => union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variant)| {
+                        self.type_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect(),
+            ),
+            SchemaState::Mixed(variants) => union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (variant, _))| {
+                        self.type_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect(),
+            ),
+            SchemaState::TaggedUnion { tag, variants } => union(
+                variants
+                    .iter()
+                    .map(|(value, variant)| {
+                        // the tag field of each variant has just its value, which tells them apart
+                        let mut variant = variant.clone();
+                        if let SchemaState::Object { required, .. } = &mut variant {
+                            required.insert(
+                                tag.clone(),
+                                SchemaState::String(StringType::Enum {
+                                    variants: std::iter::once(value.clone()).collect(),
+                                }),
+                            );
+                        }
+                        self.type_of(&variant, &format!("{}{}", name, pascal_case(value)), parent)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Renders a schema as TypeScript type definitions: an exported interface for each object
+/// shape, named after the field it is in, and a type alias for the root, named `name`, unless
+/// it is an object itself. A recursive definition of an object is an interface whose fields
+/// refer to it by name. Optional fields are marked with `?`, read-only ones as `readonly`,
+/// and nullable values are unions with `null`. Enums are unions of their values, and the
+/// variants of tagged unions are interfaces named after the values of their tag, whose tag
+/// fields have just that value.
+pub(crate) fn to_typescript(schema: &SchemaState, name: &str) -> String {
+    let mut declarations = Declarations {
+        interfaces: codegen::Declarations::new(&[]),
+    };
+    let mut output: Vec<String> = vec![];
+    match schema {
+        SchemaState::Object { .. } => {
+            declarations.interface(schema, name, "", None);
+        }
+        SchemaState::Recursive {
+            name: definition,
+            schema,
+        } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+            declarations.interface(schema, name, "", Some(definition));
+        }
+        _ => {
+            let root = declarations.type_of(schema, name, "");
+            output.push(format!("export type {} = {};", name, root));
+        }
+    }
+    for (name, fields) in declarations.interfaces.into_reached() {
+        let body: String = fields
+            .iter()
+            .map(|field| format!("  {}\n", field))
+            .collect();
+        output.push(format!("export interface {} {{\n{}}}", name, body));
+    }
+    output.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn declares_an_interface_for_each_object_shape() {
+        let address = object_schema(
+            vec![("city", SchemaState::String(StringType::Email))],
+            vec![],
+        );
+        let schema = array(object_schema(
+            vec![
+                (
+                    "id",
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+                ),
+                ("status", strings(&["open", "closed"])),
+                ("shipping_address", address.clone()),
+                ("billing-address", SchemaState::Nullable(Box::new(address))),
+            ],
+            vec![
+                ("tags", array(SchemaState::String(StringType::UUID))),
+                (
+                    "lines",
+                    array(object_schema(
+                        vec![(
+                            "address",
+                            object_schema(vec![("zip", SchemaState::Null)], vec![]),
+                        )],
+                        vec![],
+                    )),
+                ),
+            ],
+        ));
+        assert_eq!(
+            to_typescript(&schema, "Order"),
+            r#"export type Order = OrderItem[];
+
+export interface OrderItem {
+  id: number;
+  status: "closed" | "open";
+  shipping_address: ShippingAddress;
+  "billing-address": ShippingAddress | null;
+  tags?: string[];
+  lines?: LinesItem[];
+}
+
+export interface ShippingAddress {
+  city: string;
+}
+
+export interface LinesItem {
+  address: Address;
+}
+
+export interface Address {
+  zip: null;
+}"#
+        );
+    }
+
+    #[test]
+    fn keeps_the_variants_of_unions_apart() {
+        let click = object_schema(
+            vec![
+                ("type", strings(&["click"])),
+                (
+                    "x",
+                    SchemaState::Number(NumberType::Integer { min: 0, max: 1 }),
+                ),
+            ],
+            vec![],
+        );
+        let key = object_schema(
+            vec![("type", strings(&["key"])), ("code", strings(&["a", "b"]))],
+            vec![],
+        );
+        let mut schema = object_schema(
+            vec![
+                (
+                    "event",
+                    SchemaState::TaggedUnion {
+                        tag: "type".to_string(),
+                        variants: BTreeMap::from_iter([
+                            ("click".to_string(), click),
+                            ("key-press".to_string(), key),
+                        ]),
+                    },
+                ),
+                (
+                    "value",
+                    SchemaState::Union(vec![
+                        SchemaState::Boolean,
+                        object_schema(vec![("1st", SchemaState::Boolean)], vec![]),
+                    ]),
+                ),
+                (
+                    "price",
+                    SchemaState::Money {
+                        amount_key: "amount".to_string(),
+                        currency_key: "currency".to_string(),
+                        amount: NumberType::Integer { min: 1, max: 2 },
+                        currencies: BTreeSet::from_iter(["EUR".to_string()]),
+                    },
+                ),
+                (
+                    "counts",
+                    SchemaState::Map {
+                        min_length: 1,
+                        max_length: 1,
+                        keys: StringType::UUID,
+                        schema: Box::new(SchemaState::Number(NumberType::Enum {
+                            variants: BTreeMap::from_iter([(1, 1), (2, 1)]),
+                        })),
+                    },
+                ),
+            ],
+            vec![],
+        );
+        if let SchemaState::Object { access, .. } = &mut schema {
+            access.insert("price".to_string(), FieldAccess::ReadOnly);
+        }
+        assert_eq!(
+            to_typescript(&schema, "Root"),
+            r#"export interface Root {
+  event: EventClick | EventKeyPress;
+  value: boolean | ValueVariant2;
+  readonly price: { amount: number; currency: "EUR" };
+  counts: Record<string, 1 | 2>;
+}
+
+export interface EventClick {
+  type: "click";
+  x: number;
+}
+
+export interface EventKeyPress {
+  type: "key-press";
+  code: "a" | "b";
+}
+
+export interface ValueVariant2 {
+  "1st": boolean;
+}"#
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_interfaces() {
+        let node = |children| SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(object_schema(vec![("children", children)], vec![])),
+        };
+        let tree = node(array(SchemaState::Reference("node".to_string())));
+        assert_eq!(
+            to_typescript(&tree, "InferredSchema"),
+            r#"export interface InferredSchema {
+  children: InferredSchema[];
+}"#
+        );
+        assert_eq!(
+            to_typescript(&object_schema(vec![("root", tree)], vec![]), "Forest"),
+            r#"export interface Forest {
+  root: Root;
+}
+
+export interface Root {
+  children: Root[];
+}"#
+        );
+    }
+}
@@ -0,0 +1,781 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::barcode::infer_barcode;
+use crate::date_format::infer_date_format;
+use crate::duration::infer_duration;
+use crate::extended_json::infer_extended_json;
+use crate::financial::infer_financial;
+use crate::infer_string::{infer_numeric_string, infer_string_type};
+use crate::money::is_currency;
+use crate::pattern::matches_pattern;
+use crate::produce_options::field_path;
+use crate::{infer_schema_from_iter, InferenceOptions, NumberType, SchemaState, StringType};
+
+/// How a value doesn't fit its schema, for a [`ValidationError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The value is of another JSON type than the schema, such as a string for an integer.
+    WrongType {
+        expected: String,
+        found: &'static str,
+    },
+    /// A required field of an object is missing.
+    MissingField,
+    /// An object has a field that its schema doesn't have.
+    UnexpectedField,
+    /// The value is not one of the values of an enum.
+    NotInEnum,
+    /// A string doesn't have the format of its schema, such as a UUID or a date.
+    InvalidFormat { expected: String },
+    /// A string or array is shorter or longer than its schema allows.
+    InvalidLength {
+        length: usize,
+        min: usize,
+        max: usize,
+    },
+    /// An array that should have unique items has the same item twice.
+    DuplicateItems,
+    /// The value fits none of the variants of a union.
+    NoMatchingVariant,
+}
+
+/// A value that doesn't fit its schema: where it is, and how it doesn't fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The index of the record that holds the value, or 0 for a single document.
+    pub index: usize,
+    /// The path of the value in the record, such as `orders[2].total`, where the root is the
+    /// empty path.
+    pub path: String,
+    pub kind: ValidationErrorKind,
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationErrorKind::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ValidationErrorKind::MissingField => write!(f, "required field is missing"),
+            ValidationErrorKind::UnexpectedField => write!(f, "field is not in the schema"),
+            ValidationErrorKind::NotInEnum => write!(f, "value is not one of the enum"),
+            ValidationErrorKind::InvalidFormat { expected } => {
+                write!(f, "expected a string of format {}", expected)
+            }
+            ValidationErrorKind::InvalidLength { length, min, max } => {
+                write!(f, "length {} is not within {}-{}", length, min, max)
+            }
+            ValidationErrorKind::DuplicateItems => write!(f, "array has duplicate items"),
+            ValidationErrorKind::NoMatchingVariant => {
+                write!(f, "value fits none of the variants of the union")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "record {}: {}", self.index, self.kind)
+        } else {
+            write!(f, "record {}, {}: {}", self.index, self.path, self.kind)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// The name of the JSON type of a value.
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_integer(value: &Value) -> bool {
+    value.is_i64() || value.is_u64()
+}
+
+/// Whether a string has the format of its type, as checked by its JSON Schema `format` or
+/// `pattern`.
+fn has_format(string_type: &StringType, s: &str) -> bool {
+    match string_type {
+        StringType::Unknown { .. } | StringType::OpenEnum { .. } => true,
+        StringType::Enum { variants } => variants.contains(s),
+        StringType::IsoDate
+        | StringType::DateTimeISO8601
+        | StringType::DateTimeRFC2822
+        | StringType::UUID
+        | StringType::Email
+        | StringType::Url
+        | StringType::Hostname
+        | StringType::Ipv4
+        | StringType::Ipv6 => &infer_string_type(s) == string_type,
+        StringType::CustomDateTime { formats } => infer_date_format(s, formats).is_some(),
+        StringType::FixedWidthNumeric { width } => {
+            s.len() == *width && s.bytes().all(|b| b.is_ascii_digit())
+        }
+        StringType::Numeric { number } => match infer_numeric_string(&[s.to_string()]) {
+            Some(StringType::Numeric { number: found }) => {
+                matches!(number, NumberType::Float { .. })
+                    || !matches!(found, NumberType::Float { .. })
+            }
+            _ => false,
+        },
+        StringType::CreditCard | StringType::Iban => {
+            infer_financial(s).as_ref() == Some(string_type)
+        }
+        StringType::Isbn10 | StringType::Isbn13 => infer_barcode(s).as_ref() == Some(string_type),
+        // ISBN-13s are the EAN-13s of books
+        StringType::Ean13 => matches!(
+            infer_barcode(s),
+            Some(StringType::Ean13 | StringType::Isbn13)
+        ),
+        StringType::Duration => infer_duration(s).is_some(),
+        StringType::Charset { class, .. } => {
+            let chars = class.chars();
+            s.chars().all(|c| chars.contains(&c))
+        }
+        StringType::Pattern { pattern, .. } => matches_pattern(pattern, s),
+    }
+}
+
+/// The bounds of the length of strings of a type, if its JSON Schema has them.
+fn string_length_bounds(string_type: &StringType) -> Option<(usize, usize)> {
+    match string_type {
+        StringType::Charset {
+            min_length,
+            max_length,
+            ..
+        } => Some((*min_length, *max_length)),
+        StringType::Pattern {
+            min_length,
+            max_length,
+            ..
+        } if min_length.is_some() || max_length.is_some() => {
+            Some((min_length.unwrap_or(0), max_length.unwrap_or(usize::MAX)))
+        }
+        _ => None,
+    }
+}
+
+/// The JSON type of the values of a schema, as expected by validation errors.
+fn expected_type(schema: &SchemaState) -> String {
+    match schema {
+        SchemaState::Initial | SchemaState::Indefinite => "any value".to_string(),
+        SchemaState::Null => "null".to_string(),
+        SchemaState::Nullable(inner) => format!("{} or null", expected_type(inner)),
+        SchemaState::String(_) => "string".to_string(),
+        SchemaState::Number(
+            NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+        ) => "number".to_string(),
+        SchemaState::Number(_) => "integer".to_string(),
+        SchemaState::Boolean => "boolean".to_string(),
+        SchemaState::Literal(values) => expected_type(&SchemaState::literal_type(values)),
+        SchemaState::Recursive { schema, .. } => expected_type(schema),
+        SchemaState::Reference(name) => format!("recursive {}", name),
+        SchemaState::Array { .. } | SchemaState::Coordinates(_) | SchemaState::Tuple(_) => {
+            "array".to_string()
+        }
+        SchemaState::Union(variants) => {
+            let mut types = variants.iter().map(expected_type).collect::<Vec<_>>();
+            types.dedup();
+            types.join(" or ")
+        }
+        SchemaState::Mixed(variants) => variants
+            .iter()
+            .map(|(variant, _)| expected_type(variant))
+            .collect::<Vec<_>>()
+            .join(" or "),
+        SchemaState::ExtendedJson(_)
+        | SchemaState::GeoPoint { .. }
+        | SchemaState::Money { .. }
+        | SchemaState::Object { .. }
+        | SchemaState::Map { .. }
+        | SchemaState::TaggedUnion { .. } => "object".to_string(),
+    }
+}
+
+/// Options that customise what [`SchemaState::validate_with`] checks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Whether to also check the lengths of arrays, maps and strings of no particular format
+    /// against their bounds, such as the `minItems` and `maxItems` of a JSON Schema. These are
+    /// the lengths seen for an inferred schema, so they only fit data like it.
+    pub lengths: bool,
+}
+
+/// Collects the ways in which `value`, at `path`, doesn't fit `schema`.
+struct Validator<'s> {
+    index: usize,
+    options: ValidationOptions,
+    errors: Vec<ValidationError>,
+    /// The recursive definitions that the value is within, innermost last, which the
+    /// [`SchemaState::Reference`]s within them are values of.
+    recursion: Vec<(&'s str, &'s SchemaState)>,
+}
+
+impl<'s> Validator<'s> {
+    fn error(&mut self, path: &str, kind: ValidationErrorKind) {
+        self.errors.push(ValidationError {
+            index: self.index,
+            path: path.to_string(),
+            kind,
+        });
+    }
+
+    fn wrong_type(&mut self, path: &str, expected: String, value: &Value) {
+        let found = json_type(value);
+        self.error(path, ValidationErrorKind::WrongType { expected, found });
+    }
+
+    fn check_length(&mut self, path: &str, length: usize, (min, max): (usize, usize)) {
+        if length < min || length > max {
+            self.error(
+                path,
+                ValidationErrorKind::InvalidLength { length, min, max },
+            );
+        }
+    }
+
+    /// Whether `value` fits `schema`, without collecting its errors, such as for a variant of
+    /// a union.
+    fn fits(&self, schema: &'s SchemaState, value: &Value) -> bool {
+        let mut validator = Validator {
+            index: self.index,
+            options: self.options,
+            errors: vec![],
+            recursion: self.recursion.clone(),
+        };
+        validator.validate(schema, "", value);
+        validator.errors.is_empty()
+    }
+
+    /// Validates a number of a schema that holds numbers of its own type, such as coordinates.
+    fn validate_number(&mut self, number_type: &NumberType, path: &str, value: &Value) {
+        let schema = SchemaState::Number(number_type.clone());
+        let mut validator = Validator {
+            index: self.index,
+            options: self.options,
+            errors: vec![],
+            recursion: vec![],
+        };
+        validator.validate(&schema, path, value);
+        self.errors.append(&mut validator.errors);
+    }
+
+    fn validate(&mut self, schema: &'s SchemaState, path: &str, value: &Value) {
+        match (schema, value) {
+            (SchemaState::Initial | SchemaState::Indefinite, _) => {}
+            (SchemaState::Recursive { name, schema }, _) => {
+                self.recursion.push((name, schema));
+                self.validate(schema, path, value);
+                self.recursion.pop();
+            }
+            (SchemaState::Reference(name), _) => {
+                let definition = self.recursion.iter().rev().find(|(n, _)| n == name);
+                if let Some(&(_, definition)) = definition {
+                    self.validate(definition, path, value);
+                }
+            }
+            (SchemaState::Null | SchemaState::Nullable(_), Value::Null) => {}
+            (SchemaState::Nullable(inner), _) => self.validate(inner, path, value),
+            (SchemaState::Boolean, Value::Bool(_)) => {}
+            (SchemaState::Literal(values), _) => {
+                if !values.contains(value) {
+                    self.error(path, ValidationErrorKind::NotInEnum);
+                }
+            }
+            (SchemaState::Number(NumberType::Float { non_finite, .. }), Value::String(s))
+                if non_finite.iter().any(|n| n.token() == s) => {}
+            (
+                SchemaState::Number(
+                    NumberType::Float { .. } | NumberType::Multiple { integer: false, .. },
+                ),
+                Value::Number(_),
+            ) => {}
+            (SchemaState::Number(NumberType::Enum { variants }), Value::Number(n)) => {
+                if !n.as_i64().is_some_and(|n| variants.contains_key(&n)) {
+                    self.error(path, ValidationErrorKind::NotInEnum);
+                }
+            }
+            (SchemaState::Number(_), value) if is_integer(value) => {}
+            (SchemaState::String(string_type), Value::String(s)) => {
+                if matches!(string_type, StringType::Enum { .. }) {
+                    if !has_format(string_type, s) {
+                        self.error(path, ValidationErrorKind::NotInEnum);
+                    }
+                } else if !has_format(string_type, s) {
+                    let expected = string_type.to_string();
+                    self.error(path, ValidationErrorKind::InvalidFormat { expected });
+                } else if let Some(bounds) = string_length_bounds(string_type) {
+                    self.check_length(path, s.chars().count(), bounds);
+                } else if let (
+                    true,
+                    StringType::Unknown {
+                        min_length,
+                        max_length,
+                        ..
+                    },
+                ) = (self.options.lengths, string_type)
+                {
+                    let bounds = (min_length.unwrap_or(0), max_length.unwrap_or(usize::MAX));
+                    self.check_length(path, s.chars().count(), bounds);
+                }
+            }
+            (
+                SchemaState::Array {
+                    min_length,
+                    max_length,
+                    unique_items,
+                    schema: items,
+                    ..
+                },
+                Value::Array(values),
+            ) => {
+                if self.options.lengths {
+                    self.check_length(path, values.len(), (*min_length, *max_length));
+                }
+                if *unique_items && (1..values.len()).any(|i| values[..i].contains(&values[i])) {
+                    self.error(path, ValidationErrorKind::DuplicateItems);
+                }
+                for (i, item) in values.iter().enumerate() {
+                    self.validate(items, &format!("{}[{}]", path, i), item);
+                }
+            }
+            (SchemaState::Coordinates(positions), Value::Array(values)) => {
+                let length = positions.len();
+                self.check_length(path, values.len(), (length, length));
+                for (i, (position, item)) in positions.iter().zip(values).enumerate() {
+                    self.validate_number(position, &format!("{}[{}]", path, i), item);
+                }
+            }
+            (SchemaState::Tuple(positions), Value::Array(values)) => {
+                let length = positions.len();
+                self.check_length(path, values.len(), (length, length));
+                for (i, (position, item)) in positions.iter().zip(values).enumerate() {
+                    self.validate(position, &format!("{}[{}]", path, i), item);
+                }
+            }
+            (SchemaState::ExtendedJson(extended_type), Value::Object(object)) => {
+                let fits = infer_extended_json(object).is_some_and(|found| {
+                    std::mem::discriminant(&found) == std::mem::discriminant(extended_type)
+                });
+                if !fits {
+                    let expected = extended_type.to_string();
+                    self.error(path, ValidationErrorKind::InvalidFormat { expected });
+                }
+            }
+            (
+                SchemaState::GeoPoint {
+                    latitude_key,
+                    longitude_key,
+                    ..
+                },
+                Value::Object(object),
+            ) => {
+                for key in [latitude_key, longitude_key] {
+                    let field = field_path(path, key);
+                    match object.get(key) {
+                        Some(Value::Number(_)) => {}
+                        Some(other) => self.wrong_type(&field, "number".to_string(), other),
+                        None => self.error(&field, ValidationErrorKind::MissingField),
+                    }
+                }
+                for key in object.keys() {
+                    if key != latitude_key && key != longitude_key {
+                        self.error(&field_path(path, key), ValidationErrorKind::UnexpectedField);
+                    }
+                }
+            }
+            (
+                SchemaState::Money {
+                    amount_key,
+                    currency_key,
+                    amount,
+                    currencies,
+                },
+                Value::Object(object),
+            ) => {
+                let field = field_path(path, amount_key);
+                match object.get(amount_key) {
+                    Some(value) => self.validate_number(amount, &field, value),
+                    None => self.error(&field, ValidationErrorKind::MissingField),
+                }
+                let field = field_path(path, currency_key);
+                match object.get(currency_key) {
+                    Some(Value::String(s)) if !currencies.is_empty() => {
+                        if !currencies.contains(s) {
+                            self.error(&field, ValidationErrorKind::NotInEnum);
+                        }
+                    }
+                    Some(Value::String(s)) => {
+                        if !is_currency(s) {
+                            let expected = "ISO 4217 currency code".to_string();
+                            self.error(&field, ValidationErrorKind::InvalidFormat { expected });
+                        }
+                    }
+                    Some(other) => self.wrong_type(&field, "string".to_string(), other),
+                    None => self.error(&field, ValidationErrorKind::MissingField),
+                }
+                for key in object.keys() {
+                    if key != amount_key && key != currency_key {
+                        self.error(&field_path(path, key), ValidationErrorKind::UnexpectedField);
+                    }
+                }
+            }
+            (
+                SchemaState::Object {
+                    required, optional, ..
+                },
+                Value::Object(object),
+            ) => {
+                let mut missing: Vec<_> = required
+                    .keys()
+                    .filter(|key| !object.contains_key(*key))
+                    .collect();
+                missing.sort();
+                for key in missing {
+                    self.error(&field_path(path, key), ValidationErrorKind::MissingField);
+                }
+                for (key, field) in object {
+                    let field_schema = required.get(key).or_else(|| optional.get(key));
+                    match field_schema {
+                        Some(field_schema) => {
+                            self.validate(field_schema, &field_path(path, key), field)
+                        }
+                        None => {
+                            self.error(&field_path(path, key), ValidationErrorKind::UnexpectedField)
+                        }
+                    }
+                }
+            }
+            (
+                SchemaState::Map {
+                    min_length,
+                    max_length,
+                    keys,
+                    schema,
+                },
+                Value::Object(object),
+            ) => {
+                if self.options.lengths {
+                    self.check_length(path, object.len(), (*min_length, *max_length));
+                }
+                for (key, field) in object {
+                    let key_path = field_path(path, key);
+                    if !has_format(keys, key) {
+                        let expected = keys.to_string();
+                        self.error(&key_path, ValidationErrorKind::InvalidFormat { expected });
+                    }
+                    self.validate(schema, &key_path, field);
+                }
+            }
+            (SchemaState::Union(variants), _) => {
+                if !variants.iter().any(|variant| self.fits(variant, value)) {
+                    self.error(path, ValidationErrorKind::NoMatchingVariant);
+                }
+            }
+            (SchemaState::Mixed(variants), _) => {
+                if !variants
+                    .iter()
+                    .any(|(variant, _)| self.fits(variant, value))
+                {
+                    self.error(path, ValidationErrorKind::NoMatchingVariant);
+                }
+            }
+            (SchemaState::TaggedUnion { tag, variants }, Value::Object(object)) => {
+                match object.get(tag).and_then(Value::as_str) {
+                    Some(tag_value) => match variants.get(tag_value) {
+                        Some(variant) => self.validate(variant, path, value),
+                        None => self.error(&field_path(path, tag), ValidationErrorKind::NotInEnum),
+                    },
+                    None => {
+                        let tag_path = field_path(path, tag);
+                        match object.get(tag) {
+                            Some(other) => self.wrong_type(&tag_path, "string".to_string(), other),
+                            None => self.error(&tag_path, ValidationErrorKind::MissingField),
+                        }
+                    }
+                }
+            }
+            _ => self.wrong_type(path, expected_type(schema), value),
+        }
+    }
+}
+
+impl SchemaState {
+    /// Validates a value against this schema, returning the ways in which it doesn't fit, or
+    /// nothing if it does. It checks what the [JSON Schema](crate::ToJsonSchema) of the schema
+    /// asserts: the types of values, that objects have all of their required fields and no
+    /// others, that strings have their format and are one of their enum, and the lengths of
+    /// strings of a character class. The ranges of numbers and lengths seen are not checked,
+    /// since they are only those of the data that the schema was inferred from, though
+    /// [`SchemaState::validate_with`] can check the lengths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{NumberType, SchemaState, ValidationErrorKind};
+    /// use serde_json::json;
+    ///
+    /// let schema = SchemaState::Array {
+    ///     min_length: 1,
+    ///     max_length: 1,
+    ///     lengths: None,
+    ///     unique_items: false,
+    ///     schema: Box::new(SchemaState::Number(NumberType::Integer { min: 1, max: 9 })),
+    /// };
+    /// assert!(schema.validate(&json!([1, 200])).is_empty());
+    ///
+    /// let errors = schema.validate(&json!([1, "2"]));
+    /// assert_eq!(errors[0].path, "[1]");
+    /// assert_eq!(errors[0].to_string(), "record 0, [1]: expected integer, found string");
+    /// ```
+    pub fn validate(&self, value: &Value) -> Vec<ValidationError> {
+        self.validate_with(value, &ValidationOptions::default())
+    }
+
+    /// Validates a value against this schema like [`SchemaState::validate`], with the checks
+    /// enabled by the given options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use drivel::{SchemaState, ValidationErrorKind, ValidationOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = SchemaState::Array {
+    ///     min_length: 1,
+    ///     max_length: 2,
+    ///     lengths: None,
+    ///     unique_items: false,
+    ///     schema: Box::new(SchemaState::Boolean),
+    /// };
+    /// let options = ValidationOptions { lengths: true };
+    /// assert_eq!(
+    ///     schema.validate_with(&json!([true, false, true]), &options)[0].kind,
+    ///     ValidationErrorKind::InvalidLength { length: 3, min: 1, max: 2 }
+    /// );
+    /// ```
+    pub fn validate_with(
+        &self,
+        value: &Value,
+        options: &ValidationOptions,
+    ) -> Vec<ValidationError> {
+        validate_record(self, 0, value, options)
+    }
+}
+
+fn validate_record(
+    schema: &SchemaState,
+    index: usize,
+    value: &Value,
+    options: &ValidationOptions,
+) -> Vec<ValidationError> {
+    let mut validator = Validator {
+        index,
+        options: *options,
+        errors: vec![],
+        recursion: vec![],
+    };
+    validator.validate(schema, "", value);
+    validator.errors
+}
+
+/// Validates each of the records against the schema, returning the ways in which they don't
+/// fit it, with the index of the record that each is in. See [`SchemaState::validate`].
+pub fn validate_records(
+    schema: &SchemaState,
+    records: impl IntoIterator<Item = Value>,
+) -> Vec<ValidationError> {
+    validate_records_with(schema, records, &ValidationOptions::default())
+}
+
+/// Validates each of the records against the schema like [`validate_records`], with the checks
+/// enabled by the given options.
+pub fn validate_records_with(
+    schema: &SchemaState,
+    records: impl IntoIterator<Item = Value>,
+    options: &ValidationOptions,
+) -> Vec<ValidationError> {
+    records
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, record)| validate_record(schema, index, &record, options))
+        .collect()
+}
+
+/// Infers a schema from the `training` records and validates the `testing` records against it,
+/// such as to check new data for the same shape as earlier data, returning the ways in which the
+/// testing records don't fit, with the index of the record that each is in.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{infer_then_validate, InferenceOptions, ValidationErrorKind};
+/// use serde_json::json;
+///
+/// let training = vec![json!({"id": 1, "name": "Ann"}), json!({"id": 2, "name": "Bob"})];
+/// let testing = vec![json!({"id": 3, "name": "Cas"}), json!({"id": "4"})];
+/// let options = InferenceOptions::default();
+///
+/// let errors = infer_then_validate(training, testing, &options);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!((errors[0].index, errors[0].path.as_str()), (1, "name"));
+/// assert_eq!(errors[0].kind, ValidationErrorKind::MissingField);
+/// assert_eq!(errors[1].to_string(), "record 1, id: expected integer, found string");
+/// ```
+pub fn infer_then_validate(
+    training: impl IntoIterator<Item = Value>,
+    testing: impl IntoIterator<Item = Value>,
+    options: &InferenceOptions,
+) -> Vec<ValidationError> {
+    let schema = infer_schema_from_iter(training.into_iter().collect(), options);
+    validate_records(&schema, testing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options() -> InferenceOptions {
+        InferenceOptions {
+            enum_inference: None,
+            format_inference: None,
+            max_object_fields: None,
+            union_objects: false,
+            merge_threshold: None,
+            discriminator: None,
+            length_distribution: false,
+            extended_json: false,
+            coordinates: false,
+            tuples: false,
+            financial_identifiers: false,
+            barcodes: false,
+            money: false,
+            durations: false,
+            numeric_strings: false,
+            non_finite: false,
+            date_formats: vec![],
+            sequences: false,
+            bitflags: false,
+            vocabularies: vec![],
+            max_depth: None,
+            max_object_keys: None,
+            maps: false,
+            union_types: false,
+        }
+    }
+
+    fn users() -> Vec<Value> {
+        vec![
+            json!({"id": 1, "email": "ann@example.com", "address": {"city": "Oslo"}, "tags": ["a"]}),
+            json!({"id": 2, "email": "bob@example.com", "address": {"city": "Rome"}, "tags": []}),
+        ]
+    }
+
+    #[test]
+    fn valid_records_have_no_errors() {
+        let testing = vec![
+            json!({"id": 3, "email": "cas@example.org", "address": {"city": "Bern"}, "tags": ["b", "c"]}),
+        ];
+        assert_eq!(infer_then_validate(users(), testing, &options()), vec![]);
+    }
+
+    #[test]
+    fn reports_new_fields_and_changed_types_with_their_record_and_path() {
+        let testing = vec![
+            json!({"id": 3, "email": "cas@example.org", "address": {"city": "Bern"}, "tags": []}),
+            json!({"id": 4, "email": "dee@example.org", "address": {"city": "Graz", "zip": "8010"}, "tags": [], "note": "new"}),
+            json!({"id": "5", "email": "eve@example.org", "address": {"city": "Lyon"}, "tags": ["x", 6]}),
+            json!({"email": "not an email", "address": null, "tags": []}),
+        ];
+        let errors = infer_then_validate(users(), testing, &options());
+        let found = errors
+            .iter()
+            .map(|error| (error.index, error.path.as_str(), error.kind.clone()))
+            .collect::<Vec<_>>();
+
+        let wrong_type = |expected: &str, found| ValidationErrorKind::WrongType {
+            expected: expected.to_string(),
+            found,
+        };
+        for expected in [
+            (1, "address.zip", ValidationErrorKind::UnexpectedField),
+            (1, "note", ValidationErrorKind::UnexpectedField),
+            (2, "id", wrong_type("integer", "string")),
+            (2, "tags[1]", wrong_type("string", "integer")),
+            (3, "id", ValidationErrorKind::MissingField),
+            (
+                3,
+                "email",
+                ValidationErrorKind::InvalidFormat {
+                    expected: "string (email)".to_string(),
+                },
+            ),
+            (3, "address", wrong_type("object", "null")),
+        ] {
+            assert!(
+                found.contains(&expected),
+                "{:?} not in {:?}",
+                expected,
+                found
+            );
+        }
+        assert_eq!(errors.len(), 7, "{:?}", found);
+        assert!(!found.iter().any(|(index, ..)| *index == 0));
+    }
+
+    #[test]
+    fn validates_enums_nullable_values_and_unions() {
+        let schema = SchemaState::Union(vec![
+            SchemaState::Nullable(Box::new(SchemaState::String(StringType::Enum {
+                variants: ["on", "off"].into_iter().map(String::from).collect(),
+            }))),
+            SchemaState::Boolean,
+        ]);
+        for valid in [json!("on"), json!(null), json!(true)] {
+            assert_eq!(schema.validate(&valid), vec![], "{}", valid);
+        }
+        assert_eq!(
+            schema.validate(&json!("dimmed"))[0].kind,
+            ValidationErrorKind::NoMatchingVariant
+        );
+        let SchemaState::Union(variants) = &schema else {
+            unreachable!()
+        };
+        assert_eq!(
+            variants[0].validate(&json!("dimmed"))[0].kind,
+            ValidationErrorKind::NotInEnum
+        );
+    }
+
+    #[test]
+    fn checks_lengths_only_when_asked_to() {
+        let schema = infer_schema_from_iter(users(), &options());
+        let testing = vec![
+            json!({"id": 3, "email": "cy@example.com", "address": {"city": "Lisbon"}, "tags": ["a", "b"]}),
+        ];
+        assert_eq!(validate_records(&schema, testing.clone()), vec![]);
+
+        let errors = validate_records_with(&schema, testing, &ValidationOptions { lengths: true });
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "record 0, address.city: length 6 is not within 4-4",
+                "record 0, tags: length 2 is not within 0-1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::produce_options::{field_path, items_path};
+
+/// How many distinct values a field may have for its values to be counted by default, as with
+/// `--value-counts`.
+pub const DEFAULT_MAX_DISTINCT_VALUES: usize = 20;
+
+/// How often each value occurred at the paths of some data that had few distinct values there,
+/// as a profile of their distribution. Paths are as used by
+/// [`ProduceOptions`](crate::ProduceOptions), such as `orders[].status`.
+///
+/// Only strings, numbers, booleans and `null`s are counted. Once a path has more distinct values
+/// than the cap, its counts are dropped, so that high-cardinality fields such as ids don't hold
+/// on to every value seen.
+///
+/// # Example
+///
+/// ```
+/// use drivel::ValueCounts;
+/// use serde_json::json;
+///
+/// let mut counts = ValueCounts::new(2);
+/// counts.add(&json!([
+///     {"status": "open", "id": 1},
+///     {"status": "closed", "id": 2},
+///     {"status": "open", "id": 3},
+/// ]));
+///
+/// let status = counts.get("[].status").unwrap();
+/// assert_eq!(status, vec![(json!("open"), 2), (json!("closed"), 1)]);
+/// // three distinct ids are more than the cap of two
+/// assert_eq!(counts.get("[].id"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueCounts {
+    max_distinct: usize,
+    /// The number of times each value occurred at each path, by the value as JSON, or `None` if
+    /// it had more than `max_distinct` distinct values.
+    paths: HashMap<String, Option<BTreeMap<String, usize>>>,
+}
+
+impl Default for ValueCounts {
+    fn default() -> Self {
+        ValueCounts::new(DEFAULT_MAX_DISTINCT_VALUES)
+    }
+}
+
+impl ValueCounts {
+    /// Counts the values of the paths with at most `max_distinct` distinct values.
+    pub fn new(max_distinct: usize) -> Self {
+        ValueCounts {
+            max_distinct,
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Adds the values in a value, at the root of the data, to the counts. Values that are all
+    /// inferred as one schema, such as JSON lines, are added one by one.
+    pub fn add(&mut self, value: &Value) {
+        self.add_at(value, "");
+    }
+
+    fn add_at(&mut self, value: &Value, path: &str) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    self.add_at(field, &field_path(path, key));
+                }
+            }
+            Value::Array(items) => {
+                let path = items_path(path);
+                for item in items {
+                    self.add_at(item, &path);
+                }
+            }
+            scalar => {
+                let max_distinct = self.max_distinct;
+                let entry = self
+                    .paths
+                    .entry(path.to_string())
+                    .or_insert_with(|| Some(BTreeMap::new()));
+                if let Some(counts) = entry {
+                    *counts.entry(scalar.to_string()).or_insert(0) += 1;
+                    if counts.len() > max_distinct {
+                        *entry = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The values seen at `path` and how often each was seen, from the most to the least common
+    /// and in the order of their JSON otherwise, if there were any and few enough of them.
+    pub fn get(&self, path: &str) -> Option<Vec<(Value, usize)>> {
+        let counts = self.paths.get(path)?.as_ref()?;
+        let mut counts: Vec<(&String, usize)> = counts
+            .iter()
+            .map(|(value, count)| (value, *count))
+            .collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        Some(
+            counts
+                .into_iter()
+                .map(|(value, count)| (serde_json::from_str(value).unwrap(), count))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counts_values_of_nested_fields_and_array_elements() {
+        let mut counts = ValueCounts::new(3);
+        counts.add(&json!({"tags": ["a", "b"], "address": {"country": "NL"}, "score": 1}));
+        counts.add(&json!({"tags": ["a"], "address": {"country": null}, "score": 1.5}));
+        counts.add(&json!({"tags": [], "address": {"country": "NL"}, "score": "1"}));
+
+        assert_eq!(
+            counts.get("tags[]"),
+            Some(vec![(json!("a"), 2), (json!("b"), 1)])
+        );
+        assert_eq!(
+            counts.get("address.country"),
+            Some(vec![(json!("NL"), 2), (json!(null), 1)])
+        );
+        // values of different types are told apart
+        assert_eq!(counts.get("score").unwrap().len(), 3);
+        assert_eq!(counts.get("address"), None);
+        assert_eq!(counts.get("missing"), None);
+    }
+
+    #[test]
+    fn drops_the_counts_of_paths_with_too_many_values() {
+        let mut counts = ValueCounts::new(5);
+        for id in 0..100 {
+            counts.add(&json!({"id": id, "kind": id % 2 == 0}));
+        }
+        assert_eq!(counts.get("id"), None);
+        assert_eq!(counts.paths["id"], None);
+        assert_eq!(
+            counts.get("kind"),
+            Some(vec![(json!(false), 50), (json!(true), 50)])
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::delimited::{column_type, typed, ColumnType};
+use crate::DrivelError;
+
+/// How [`parse_xml`] reads the attributes of elements and the text of elements that also have
+/// child elements or attributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlOptions {
+    /// The prefix of the fields of attributes, which are otherwise read like child elements,
+    /// such as `@id` for `id="1"` with the default of `@`. With an empty prefix, attributes and
+    /// child elements are fields alike; with `None`, attributes are left out.
+    pub attribute_prefix: Option<String>,
+    /// The field of the text of an element that also has child elements or attributes.
+    /// Default = `#text`.
+    pub text_key: String,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        XmlOptions {
+            attribute_prefix: Some("@".to_string()),
+            text_key: "#text".to_string(),
+        }
+    }
+}
+
+/// Parses an XML document, such as a SOAP response or an RSS feed, into a JSON value, so that
+/// its schema can be inferred like that of JSON or YAML: an object with a field for the root
+/// element.
+///
+/// Each element is a field of the object of its parent, named with its qualified name, such as
+/// `soap:Body`. Elements with neither child elements nor attributes are the value of their
+/// text, and others are objects of their attributes, child elements and text, as of
+/// [`XmlOptions`]. Elements of a name that occurs more than once under any one parent are
+/// arrays under every parent, so that a list with one element is still a list. As with
+/// [`parse_delimited`](crate::parse_delimited), the texts at each path are typed by all of
+/// them together, as integers, floats or booleans if they all are, or strings otherwise, while
+/// empty elements are `null`.
+///
+/// Text is trimmed, CDATA sections are text, and the predefined and numeric character
+/// references are decoded. The XML declaration, comments, processing instructions, the
+/// doctype and the `xmlns` attributes that declare namespaces are left out.
+///
+/// # Example
+///
+/// ```
+/// use drivel::{parse_xml, XmlOptions};
+/// use serde_json::json;
+///
+/// let input = r#"<?xml version="1.0"?>
+/// <catalog>
+///   <book id="1"><title>Dune</title><price currency="EUR">9.50</price></book>
+///   <book id="2"><title>Emma</title><price currency="GBP">7</price></book>
+/// </catalog>"#;
+/// assert_eq!(
+///     parse_xml(input, &XmlOptions::default()).unwrap(),
+///     json!({"catalog": {"book": [
+///         {"@id": 1, "title": "Dune", "price": {"@currency": "EUR", "#text": 9.5}},
+///         {"@id": 2, "title": "Emma", "price": {"@currency": "GBP", "#text": 7.0}},
+///     ]}})
+/// );
+/// ```
+pub fn parse_xml(input: &str, options: &XmlOptions) -> Result<Value, DrivelError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        position: 0,
+        options,
+    };
+    let root = parser.document().map_err(|message| {
+        DrivelError::Parse(format!("XML error at line {}: {}", parser.line(), message))
+    })?;
+
+    let mut repeated = HashSet::new();
+    let mut texts = HashMap::new();
+    gather(&root, &root.name, options, &mut repeated, &mut texts);
+    let types = texts
+        .into_iter()
+        .map(|(path, values)| (path, column_type(&values)))
+        .collect();
+    let tree = Tree {
+        options,
+        repeated,
+        types,
+    };
+    let mut document = Map::new();
+    document.insert(root.name.clone(), tree.convert(&root, &root.name));
+    Ok(Value::Object(document))
+}
+
+/// An element, with its attributes as child elements named with the attribute prefix.
+struct Element {
+    name: String,
+    children: Vec<Element>,
+    text: String,
+}
+
+fn child_path(path: &str, name: &str) -> String {
+    // which can't be part of a name
+    format!("{}/{}", path, name)
+}
+
+/// Collects the paths of the elements that occur more than once under a parent, and the texts
+/// at each path.
+fn gather(
+    element: &Element,
+    path: &str,
+    options: &XmlOptions,
+    repeated: &mut HashSet<String>,
+    texts: &mut HashMap<String, Vec<String>>,
+) {
+    if element.children.is_empty() {
+        if !element.text.is_empty() {
+            texts
+                .entry(path.to_string())
+                .or_default()
+                .push(element.text.clone());
+        }
+        return;
+    }
+    if !element.text.is_empty() {
+        texts
+            .entry(child_path(path, &options.text_key))
+            .or_default()
+            .push(element.text.clone());
+    }
+    let mut seen = HashSet::new();
+    for child in &element.children {
+        let path = child_path(path, &child.name);
+        if !seen.insert(child.name.as_str()) {
+            repeated.insert(path.clone());
+        }
+        gather(child, &path, options, repeated, texts);
+    }
+}
+
+struct Tree<'a> {
+    options: &'a XmlOptions,
+    repeated: HashSet<String>,
+    types: HashMap<String, ColumnType>,
+}
+
+impl Tree<'_> {
+    fn text(&self, text: &str, path: &str) -> Value {
+        let column_type = self.types.get(path).copied().unwrap_or(ColumnType::String);
+        typed(text.to_string(), column_type)
+    }
+
+    fn convert(&self, element: &Element, path: &str) -> Value {
+        if element.children.is_empty() {
+            return self.text(&element.text, path);
+        }
+        let mut fields = Map::new();
+        for child in &element.children {
+            let path = child_path(path, &child.name);
+            let value = self.convert(child, &path);
+            if self.repeated.contains(&path) {
+                // every element at the path is added here, so the field is always an array
+                if let Value::Array(values) = fields
+                    .entry(child.name.clone())
+                    .or_insert_with(|| Value::Array(vec![]))
+                {
+                    values.push(value);
+                }
+            } else {
+                fields.insert(child.name.clone(), value);
+            }
+        }
+        if !element.text.is_empty() {
+            let key = &self.options.text_key;
+            let text = self.text(&element.text, &child_path(path, key));
+            fields.insert(key.clone(), text);
+        }
+        Value::Object(fields)
+    }
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    options: &'a XmlOptions,
+}
+
+impl Parser<'_> {
+    /// The line of the current position, counting from 1.
+    fn line(&self) -> usize {
+        1 + self.chars[..self.position.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.position + i) == Some(&c))
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let eaten = self.starts_with(s);
+        if eaten {
+            self.position += s.chars().count();
+        }
+        eaten
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}', found the end", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips everything up to and including `end`.
+    fn skip_past(&mut self, end: &str, what: &str) -> Result<(), String> {
+        while !self.eat(end) {
+            if self.peek().is_none() {
+                return Err(format!("{} is never closed", what));
+            }
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    /// Skips whitespace, comments, processing instructions and doctypes, as between the
+    /// elements of a document.
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.skip_past("-->", "a comment")?;
+            } else if self.eat("<?") {
+                self.skip_past("?>", "a processing instruction")?;
+            } else if self.eat("<!DOCTYPE") {
+                // which may have an internal subset of declarations in brackets
+                let mut depth = 0;
+                loop {
+                    match self.peek() {
+                        Some('[') => depth += 1,
+                        Some(']') => depth -= 1,
+                        Some('>') if depth == 0 => break,
+                        None => return Err("the doctype is never closed".to_string()),
+                        _ => {}
+                    }
+                    self.position += 1;
+                }
+                self.position += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<Element, String> {
+        self.eat("\u{feff}");
+        self.skip_misc()?;
+        if self.peek() != Some('<') {
+            return Err("expected an element".to_string());
+        }
+        let root = self.element()?;
+        self.skip_misc()?;
+        match self.peek() {
+            None => Ok(root),
+            Some(_) => Err("expected the end of the document after the root element".to_string()),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !"<>/=\"'&".contains(c))
+        {
+            self.position += 1;
+        }
+        if self.position == start {
+            return Err(match self.peek() {
+                Some(c) => format!("expected a name, found '{}'", c),
+                None => "expected a name, found the end".to_string(),
+            });
+        }
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    /// Text up to the next `<`, or up to the closing quote of an attribute value, with its
+    /// character references decoded.
+    fn text_until(&mut self, end: char) -> Result<String, String> {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c == end {
+                break;
+            }
+            if c == '&' {
+                text.push_str(&self.reference()?);
+            } else {
+                text.push(c);
+                self.position += 1;
+            }
+        }
+        Ok(text)
+    }
+
+    /// A character or entity reference, such as `&amp;` or `&#233;`. Entities other than the
+    /// predefined ones, which only a doctype could declare, are kept as they are.
+    fn reference(&mut self) -> Result<String, String> {
+        let start = self.position;
+        self.position += 1;
+        while self
+            .peek()
+            .is_some_and(|c| c != ';' && !c.is_whitespace() && c != '<')
+        {
+            self.position += 1;
+        }
+        if self.peek() != Some(';') {
+            return Err("expected ';' at the end of a reference".to_string());
+        }
+        let name: String = self.chars[start + 1..self.position].iter().collect();
+        self.position += 1;
+        let decoded = match name.as_str() {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match name.strip_prefix('#') {
+                Some(code) => {
+                    let code = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16),
+                        None => code.parse(),
+                    };
+                    let c = code.ok().and_then(char::from_u32);
+                    Some(c.ok_or_else(|| format!("invalid character reference &{};", name))?)
+                }
+                None => None,
+            },
+        };
+        Ok(match decoded {
+            Some(c) => c.to_string(),
+            None => format!("&{};", name),
+        })
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Element>, String> {
+        let mut attributes = vec![];
+        loop {
+            self.skip_whitespace();
+            if matches!(self.peek(), Some('>' | '/') | None) {
+                return Ok(attributes);
+            }
+            let name = self.name()?;
+            self.skip_whitespace();
+            self.expect('=')?;
+            self.skip_whitespace();
+            let quote = match self.peek() {
+                Some(c @ ('"' | '\'')) => c,
+                _ => return Err(format!("expected a quoted value of attribute {}", name)),
+            };
+            self.position += 1;
+            let value = self.text_until(quote)?;
+            self.expect(quote)?;
+            let Some(prefix) = &self.options.attribute_prefix else {
+                continue;
+            };
+            if name == "xmlns" || name.starts_with("xmlns:") {
+                continue;
+            }
+            attributes.push(Element {
+                name: format!("{}{}", prefix, name),
+                children: vec![],
+                text: value.trim().to_string(),
+            });
+        }
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect('<')?;
+        let name = self.name()?;
+        let mut children = self.attributes()?;
+        if self.eat("/>") {
+            return Ok(Element {
+                name,
+                children,
+                text: String::new(),
+            });
+        }
+        self.expect('>')?;
+
+        let mut text = String::new();
+        loop {
+            if self.eat("</") {
+                let closing = self.name()?;
+                if closing != name {
+                    return Err(format!(
+                        "expected the closing tag of {}, found that of {}",
+                        name, closing
+                    ));
+                }
+                self.skip_whitespace();
+                self.expect('>')?;
+                return Ok(Element {
+                    name,
+                    children,
+                    text: text.trim().to_string(),
+                });
+            } else if self.eat("<!--") {
+                self.skip_past("-->", "a comment")?;
+            } else if self.eat("<![CDATA[") {
+                let start = self.position;
+                self.skip_past("]]>", "a CDATA section")?;
+                text.extend(&self.chars[start..self.position - 3]);
+            } else if self.eat("<?") {
+                self.skip_past("?>", "a processing instruction")?;
+            } else if self.peek() == Some('<') {
+                children.push(self.element()?);
+            } else if self.peek().is_none() {
+                return Err(format!("element {} is never closed", name));
+            } else {
+                text.push_str(&self.text_until('<')?);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_a_soap_envelope() {
+        let input = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- a response -->
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <m:GetUserResponse xmlns:m="urn:users">
+      <m:User active="true">
+        <m:Id>0042</m:Id>
+        <m:Name>Ada &amp; co</m:Name>
+        <m:Note><![CDATA[<b>bold</b>]]></m:Note>
+        <m:Email/>
+      </m:User>
+    </m:GetUserResponse>
+  </soap:Body>
+</soap:Envelope>
+"#;
+        assert_eq!(
+            parse_xml(input, &XmlOptions::default()).unwrap(),
+            json!({"soap:Envelope": {"soap:Body": {"m:GetUserResponse": {"m:User": {
+                "@active": true,
+                "m:Id": "0042",
+                "m:Name": "Ada & co",
+                "m:Note": "<b>bold</b>",
+                "m:Email": null,
+            }}}}})
+        );
+    }
+
+    #[test]
+    fn elements_repeated_anywhere_are_arrays_everywhere() {
+        let input = "<orders><order><item>1</item><item>2</item></order>\
+                     <order><item>x</item></order><order/></orders>";
+        assert_eq!(
+            parse_xml(input, &XmlOptions::default()).unwrap(),
+            json!({"orders": {"order": [
+                {"item": ["1", "2"]},
+                {"item": ["x"]},
+                null,
+            ]}})
+        );
+    }
+
+    #[test]
+    fn attributes_can_be_plain_fields_or_left_out() {
+        let input = r#"<user id="7" role="admin">Ada<team>core</team></user>"#;
+        let plain = XmlOptions {
+            attribute_prefix: Some(String::new()),
+            text_key: "value".to_string(),
+        };
+        assert_eq!(
+            parse_xml(input, &plain).unwrap(),
+            json!({"user": {"id": 7, "role": "admin", "team": "core", "value": "Ada"}})
+        );
+        let ignored = XmlOptions {
+            attribute_prefix: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_xml(r#"<user id="7">Ada</user>"#, &ignored).unwrap(),
+            json!({"user": "Ada"})
+        );
+    }
+
+    #[test]
+    fn reports_where_the_document_is_malformed() {
+        let err = parse_xml("<a>\n<b>1</c>\n</a>", &XmlOptions::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            DrivelError::Parse(
+                "XML error at line 2: expected the closing tag of b, found that of c".to_string()
+            )
+            .to_string()
+        );
+        assert!(parse_xml("<a>1</a><b/>", &XmlOptions::default()).is_err());
+        assert!(parse_xml("<a>1", &XmlOptions::default()).is_err());
+    }
+}
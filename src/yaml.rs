@@ -0,0 +1,235 @@
+use serde_json::{Map, Value};
+use yaml_rust2::{yaml::Hash, ScanError, Yaml, YamlEmitter, YamlLoader};
+
+use crate::float_format::fixed_point;
+
+fn to_yaml_node(value: &Value, fixed_point_floats: bool) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(
+                n.as_f64()
+                    .filter(|_| fixed_point_floats && n.is_f64())
+                    .and_then(fixed_point)
+                    .unwrap_or_else(|| n.to_string()),
+            ),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(array) => Yaml::Array(
+            array
+                .iter()
+                .map(|v| to_yaml_node(v, fixed_point_floats))
+                .collect(),
+        ),
+        Value::Object(object) => {
+            let mut hash = Hash::new();
+            for (k, v) in object {
+                hash.insert(Yaml::String(k.clone()), to_yaml_node(v, fixed_point_floats));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+/// Serializes a JSON value as a YAML document.
+///
+/// Object keys are emitted in the same order in which they appear in the JSON value, and
+/// strings that would otherwise be read back as a different type (e.g. `"true"` or `"42"`)
+/// are quoted, so that parsing the output again yields the original value.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+///
+/// let yaml = drivel::to_yaml_string(&json!({ "name": "drivel", "tags": ["json", "yaml"] }));
+/// assert_eq!(yaml, "---\nname: drivel\ntags:\n  - json\n  - yaml");
+/// ```
+pub fn to_yaml_string(value: &Value) -> String {
+    to_yaml_string_with(value, false)
+}
+
+/// Serializes a JSON value as a YAML document, like [`to_yaml_string`], optionally writing
+/// floats in fixed-point notation where feasible, as with
+/// [`FixedPointFormatter`](crate::FixedPointFormatter).
+pub fn to_yaml_string_with(value: &Value, fixed_point_floats: bool) -> String {
+    let mut out = String::new();
+    let mut emitter = YamlEmitter::new(&mut out);
+    // writing into a String cannot fail, and every node we construct is emittable
+    emitter
+        .dump(&to_yaml_node(value, fixed_point_floats))
+        .unwrap();
+    out
+}
+
+/// The key of a YAML merge, as in `<<: *defaults`, which merges the fields of another mapping
+/// into the one it is in.
+const MERGE_KEY: &str = "<<";
+
+fn yaml_key(key: &Yaml) -> String {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Null => "null".to_string(),
+        // mappings and sequences as keys have no JSON equivalent, so they are written as JSON
+        key => from_yaml_node(key).to_string(),
+    }
+}
+
+fn from_yaml_node(node: &Yaml) -> Value {
+    match node {
+        Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => Value::Null,
+        Yaml::Boolean(b) => Value::Bool(*b),
+        Yaml::Integer(i) => Value::from(*i),
+        // like serde_json, infinities and NaN become null
+        Yaml::Real(_) => node
+            .as_f64()
+            .and_then(serde_json::Number::from_f64)
+            .map_or(Value::Null, Value::Number),
+        Yaml::String(s) => Value::String(s.clone()),
+        Yaml::Array(array) => Value::Array(array.iter().map(from_yaml_node).collect()),
+        Yaml::Hash(hash) => {
+            let mut object = Map::new();
+            let mut merged = vec![];
+            for (key, value) in hash {
+                match (key, value) {
+                    (Yaml::String(key), Yaml::Hash(_)) if key == MERGE_KEY => merged.push(value),
+                    (Yaml::String(key), Yaml::Array(values)) if key == MERGE_KEY => {
+                        merged.extend(values)
+                    }
+                    _ => {
+                        object.insert(yaml_key(key), from_yaml_node(value));
+                    }
+                }
+            }
+            // fields of the mapping itself win over merged ones, and earlier merged mappings
+            // win over later ones
+            for value in merged {
+                if let Value::Object(fields) = from_yaml_node(value) {
+                    for (key, value) in fields {
+                        object.entry(key).or_insert(value);
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+    }
+}
+
+/// Parses a YAML stream into its documents, which are separated by `---`. Anchors and aliases
+/// are expanded into copies of the anchored node, and merge keys (`<<: *defaults`) into the
+/// fields of the merged mappings.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+///
+/// let yaml = "defaults: &defaults\n  retries: 3\nservice:\n  <<: *defaults\n  name: api\n---\nnull\n";
+/// assert_eq!(
+///     drivel::parse_yaml_documents(yaml).unwrap(),
+///     vec![
+///         json!({"defaults": {"retries": 3}, "service": {"retries": 3, "name": "api"}}),
+///         json!(null),
+///     ]
+/// );
+/// ```
+pub fn parse_yaml_documents(s: &str) -> Result<Vec<Value>, ScanError> {
+    let documents = YamlLoader::load_from_str(s)?;
+    Ok(documents.iter().map(from_yaml_node).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(value: &Value) -> Value {
+        let yaml = to_yaml_string(value);
+        parse_yaml_documents(&yaml).unwrap().remove(0)
+    }
+
+    #[test]
+    fn serializes_scalars() {
+        assert_eq!(to_yaml_string(&json!(null)), "---\n~");
+        assert_eq!(to_yaml_string(&json!(true)), "---\ntrue");
+        assert_eq!(to_yaml_string(&json!(42)), "---\n42");
+        assert_eq!(to_yaml_string(&json!(1.5)), "---\n1.5");
+        assert_eq!(to_yaml_string(&json!("foo")), "---\nfoo");
+    }
+
+    #[test]
+    fn serializes_floats_in_fixed_point() {
+        let value = json!([1e-7, 1.5, 1e30]);
+        assert_eq!(to_yaml_string(&value), "---\n- 1e-7\n- 1.5\n- 1e30");
+        assert_eq!(
+            to_yaml_string_with(&value, true),
+            "---\n- 0.0000001\n- 1.5\n- 1e30"
+        );
+        assert_eq!(round_trip(&json!(1e-7)), json!(1e-7));
+    }
+
+    #[test]
+    fn quotes_ambiguous_strings() {
+        assert_eq!(to_yaml_string(&json!("true")), "---\n\"true\"");
+        assert_eq!(to_yaml_string(&json!("007")), "---\n\"007\"");
+    }
+
+    #[test]
+    fn preserves_field_order() {
+        let yaml = to_yaml_string(&json!({ "b": 1, "a": 2, "c": 3 }));
+        let positions: Vec<_> = ["b:", "a:", "c:"]
+            .iter()
+            .map(|key| yaml.find(key).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", yaml);
+    }
+
+    #[test]
+    fn round_trips_nested_values() {
+        let value = json!({
+            "string": "it's",
+            "multiline": "foo\nbar",
+            "int": -10,
+            "float": 10.25,
+            "bool": false,
+            "nothing": null,
+            "array": [1, "two", { "three": 3 }],
+            "empty_array": [],
+            "empty_object": {},
+            "object": { "nested": { "deep": [true] } }
+        });
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn expands_aliases() {
+        let yaml =
+            "base: &base\n  x: 1\n  tags: [a, b]\nother: *base\nmerged:\n  <<: *base\n  x: 2\n";
+        assert_eq!(
+            parse_yaml_documents(yaml).unwrap(),
+            vec![json!({
+                "base": {"x": 1, "tags": ["a", "b"]},
+                "other": {"x": 1, "tags": ["a", "b"]},
+                "merged": {"x": 2, "tags": ["a", "b"]}
+            })]
+        );
+    }
+
+    #[test]
+    fn parses_every_document_of_a_stream() {
+        let yaml = "---\nid: 1\n---\nid: 2\nname: two\n---\n- 3\n";
+        assert_eq!(
+            parse_yaml_documents(yaml).unwrap(),
+            vec![
+                json!({"id": 1}),
+                json!({"id": 2, "name": "two"}),
+                json!([3])
+            ]
+        );
+        assert!(parse_yaml_documents("a: [1").is_err());
+    }
+}
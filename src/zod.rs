@@ -0,0 +1,486 @@
+use crate::codegen::{self, pascal_case};
+use crate::typescript::property_name;
+use crate::{ExtendedJsonType, NumberType, SchemaState, StringType};
+
+/// Names that the generated code imports, which its own schemas mustn't shadow.
+const RESERVED_NAMES: &[&str] = &["z"];
+
+/// The Zod schema of a union of schemas, or the schema itself if there is just one.
+fn union(schemas: Vec<String>) -> String {
+    let mut distinct: Vec<String> = vec![];
+    for schema in schemas {
+        if !distinct.contains(&schema) {
+            distinct.push(schema);
+        }
+    }
+    if distinct.len() == 1 {
+        return distinct.pop().unwrap();
+    }
+    format!("z.union([{}])", distinct.join(", "))
+}
+
+/// An inline object schema of the given fields, such as `z.object({ lat: z.number() })`.
+fn inline_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, schema)| format!("{}: {}", property_name(key), schema))
+        .collect();
+    format!("z.object({{ {} }})", fields.join(", "))
+}
+
+fn string_schema(string_type: &StringType) -> String {
+    match string_type {
+        StringType::Enum { variants } if variants.len() == 1 => format!(
+            "z.literal({})",
+            serde_json::to_string(variants.first().unwrap()).unwrap()
+        ),
+        StringType::Enum { variants } => format!(
+            "z.enum([{}])",
+            variants
+                .iter()
+                .map(|variant| serde_json::to_string(variant).unwrap())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        StringType::UUID => "z.string().uuid()".to_string(),
+        StringType::Email => "z.string().email()".to_string(),
+        StringType::Url => "z.string().url()".to_string(),
+        StringType::Ipv4 => "z.string().ip({ version: \"v4\" })".to_string(),
+        StringType::Ipv6 => "z.string().ip({ version: \"v6\" })".to_string(),
+        _ => "z.string()".to_string(),
+    }
+}
+
+fn number_schema(number_type: &NumberType) -> String {
+    match number_type {
+        NumberType::Enum { variants } => union(
+            variants
+                .keys()
+                .map(|value| format!("z.literal({})", value))
+                .collect(),
+        ),
+        NumberType::Float { .. } => "z.number()".to_string(),
+        NumberType::Multiple {
+            step,
+            integer: false,
+            ..
+        } => format!("z.number().multipleOf({})", step),
+        NumberType::Multiple { step, .. } => format!("z.number().int().multipleOf({})", step),
+        _ => "z.number().int()".to_string(),
+    }
+}
+
+/// The declarations of Zod output, a schema for each object shape.
+struct Declarations {
+    /// The lines of the fields of each object schema.
+    objects: codegen::Declarations<Vec<String>>,
+    /// The names of the schemas of recursive definitions, whose types can't be inferred.
+    recursive_names: Vec<String>,
+}
+
+impl Declarations {
+    /// Declares a schema for an object, with a property for each of its fields, optional ones
+    /// marked with `.optional()`. The schema of a recursive definition, `recursive`, is named
+    /// before its fields are rendered, so that the values of it within it can refer to it.
+    fn object(
+        &mut self,
+        schema: &SchemaState,
+        name: &str,
+        parent: &str,
+        recursive: Option<&str>,
+    ) -> String {
+        let SchemaState::Object {
+            required, optional, ..
+        } = schema
+        else {
+            unreachable!("only objects are declared as schemas")
+        };
+        let index = self.objects.start();
+        if let Some(definition) = recursive {
+            let declared = self.objects.name(index, definition, name, parent);
+            self.recursive_names.push(declared);
+        }
+        let keys: Vec<(&String, bool)> = required
+            .keys()
+            .map(|key| (key, false))
+            .chain(optional.keys().map(|key| (key, true)))
+            .collect();
+        let lines = keys
+            .into_iter()
+            .map(|(key, is_optional)| {
+                let field = if is_optional {
+                    &optional[key]
+                } else {
+                    &required[key]
+                };
+                format!(
+                    "{}: {}{},",
+                    property_name(key),
+                    self.schema_of(field, &pascal_case(key), name),
+                    if is_optional { ".optional()" } else { "" }
+                )
+            })
+            .collect();
+        self.objects.finish(index, lines, name, parent)
+    }
+
+    /// The Zod schema of the values of a schema, declaring schemas for the objects among them,
+    /// named after `name`, the name of the field they are in as a type.
+    fn schema_of(&mut self, schema: &SchemaState, name: &str, parent: &str) -> String {
+        match schema {
+            SchemaState::Initial | SchemaState::Indefinite => "z.unknown()".to_string(),
+            SchemaState::Recursive {
+                name: definition,
+                schema,
+            } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+                self.object(schema, name, parent, Some(definition))
+            }
+            SchemaState::Recursive { schema, .. } => self.schema_of(schema, name, parent),
+            // only objects are declared, so another recursive definition can't be referred to
+            SchemaState::Reference(definition) => self.objects.recursive(definition).map_or_else(
+                || "z.unknown()".to_string(),
+                |declared| format!("z.lazy(() => {})", declared),
+            ),
+            SchemaState::Null => "z.null()".to_string(),
+            SchemaState::Nullable(inner) => {
+                format!("{}.nullable()", self.schema_of(inner, name, parent))
+            }
+            SchemaState::String(string) => string_schema(string),
+            SchemaState::Number(number) => number_schema(number),
+            SchemaState::Boolean => "z.boolean()".to_string(),
+            SchemaState::Literal(values)
+                if values.iter().all(|v| !v.is_array() && !v.is_object()) =>
+            {
+                union(
+                    values
+                        .iter()
+                        .map(|value| format!("z.literal({})", value))
+                        .collect(),
+                )
+            }
+            SchemaState::Literal(values) => {
+                self.schema_of(&SchemaState::literal_type(values), name, parent)
+            }
+            SchemaState::Array { schema, .. } => format!(
+                "z.array({})",
+                self.schema_of(schema, &format!("{}Item", name), parent)
+            ),
+            SchemaState::Object { .. } => self.object(schema, name, parent, None),
+            SchemaState::Map { schema, .. } => format!(
+                "z.record(z.string(), {})",
+                self.schema_of(schema, &format!("{}Value", name), parent)
+            ),
+            SchemaState::ExtendedJson(extended) => {
+                let key = match extended {
+                    ExtendedJsonType::ObjectId => "$oid",
+                    ExtendedJsonType::Date => "$date",
+                    ExtendedJsonType::Long { .. } => "$numberLong",
+                };
+                inline_object(&[(key, "z.string()".to_string())])
+            }
+            SchemaState::Coordinates(positions) => format!(
+                "z.tuple([{}])",
+                positions
+                    .iter()
+                    .map(|_| "z.number()")
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SchemaState::Tuple(positions) => {
+                let positions: Vec<String> = positions
+                    .iter()
+                    .map(|position| self.schema_of(position, &format!("{}Item", name), parent))
+                    .collect();
+                format!("z.tuple([{}])", positions.join(", "))
+            }
+            SchemaState::GeoPoint {
+                latitude_key,
+                longitude_key,
+                ..
+            } => inline_object(&[
+                (latitude_key, "z.number()".to_string()),
+                (longitude_key, "z.number()".to_string()),
+            ]),
+            SchemaState::Money {
+                amount_key,
+                currency_key,
+                currencies,
+                ..
+            } => inline_object(&[
+                (amount_key, "z.number()".to_string()),
+                (
+                    currency_key,
+                    string_schema(&StringType::Enum {
+                        variants: currencies.clone(),
+                    }),
+                ),
+            ]),
+            SchemaState::Union(variants) => union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variant)| {
+                        self.schema_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect(),
+            ),
+            SchemaState::Mixed(variants) => union(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (variant, _))| {
+                        self.schema_of(variant, &format!("{}Variant{}", name, i + 1), parent)
+                    })
+                    .collect(),
+            ),
+            SchemaState::TaggedUnion { tag, variants } => {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|(value, variant)| {
+                        // the tag field of each variant has just its value, which tells them apart
+                        let mut variant = variant.clone();
+                        if let SchemaState::Object {
+                            required, optional, ..
+                        } = &mut variant
+                        {
+                            optional.shift_remove(tag);
+                            required.insert(
+                                tag.clone(),
+                                SchemaState::String(StringType::Enum {
+                                    variants: std::iter::once(value.clone()).collect(),
+                                }),
+                            );
+                        }
+                        self.schema_of(&variant, &format!("{}{}", name, pascal_case(value)), parent)
+                    })
+                    .collect();
+                format!(
+                    "z.discriminatedUnion({}, [{}])",
+                    serde_json::to_string(tag).unwrap(),
+                    variants.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Renders a schema as TypeScript schemas of Zod: an exported schema for each object shape,
+/// named after the field it is in, and one for the root, named `name`, each with a type of the
+/// same name inferred from it. Schemas come before the schemas that refer to them. Optional
+/// fields are marked with `.optional()` and nullable values with `.nullable()`. Enums of
+/// strings are `z.enum`s, and tagged unions are discriminated unions of their tag field. The
+/// values of a recursive definition within it refer to its schema with `z.lazy`, and as
+/// TypeScript can't infer the type of a schema that refers to itself, it is `z.ZodTypeAny`.
+pub(crate) fn to_zod(schema: &SchemaState, name: &str) -> String {
+    let mut declarations = Declarations {
+        objects: codegen::Declarations::new(RESERVED_NAMES),
+        recursive_names: vec![],
+    };
+    let root = match schema {
+        SchemaState::Object { .. } => {
+            declarations.object(schema, name, "", None);
+            None
+        }
+        SchemaState::Recursive {
+            name: definition,
+            schema,
+        } if matches!(schema.as_ref(), SchemaState::Object { .. }) => {
+            declarations.object(schema, name, "", Some(definition));
+            None
+        }
+        _ => Some(declarations.schema_of(schema, name, "")),
+    };
+    let recursive_names = std::mem::take(&mut declarations.recursive_names);
+    let declare = |name: &str, schema: String| {
+        let annotation = if recursive_names.iter().any(|recursive| recursive == name) {
+            ": z.ZodTypeAny"
+        } else {
+            ""
+        };
+        format!(
+            "export const {}{} = {};\nexport type {} = z.infer<typeof {}>;",
+            name, annotation, schema, name, name
+        )
+    };
+    let mut output = vec!["import { z } from \"zod\";".to_string()];
+    for (name, lines) in declarations.objects.into_finished() {
+        let body: String = lines.iter().map(|line| format!("  {}\n", line)).collect();
+        output.push(declare(&name, format!("z.object({{\n{}}})", body)));
+    }
+    if let Some(root) = root.filter(|root| root != name) {
+        output.push(declare(name, root));
+    }
+    output.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_helpers::object_schema;
+    use std::collections::BTreeMap;
+
+    fn array(schema: SchemaState) -> SchemaState {
+        SchemaState::Array {
+            min_length: 1,
+            max_length: 2,
+            lengths: None,
+            unique_items: false,
+            schema: Box::new(schema),
+        }
+    }
+
+    fn strings(values: &[&str]) -> SchemaState {
+        SchemaState::String(StringType::Enum {
+            variants: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn declares_objects_before_the_objects_they_are_in() {
+        let address = object_schema(
+            vec![("city", SchemaState::String(StringType::Email))],
+            vec![],
+        );
+        let schema = array(object_schema(
+            vec![
+                (
+                    "id",
+                    SchemaState::Number(NumberType::Integer { min: 1, max: 9 }),
+                ),
+                ("status", strings(&["open", "closed"])),
+                ("shipping_address", address.clone()),
+                ("billing-address", SchemaState::Nullable(Box::new(address))),
+            ],
+            vec![
+                ("tags", array(SchemaState::String(StringType::UUID))),
+                (
+                    "lines",
+                    array(object_schema(
+                        vec![(
+                            "address",
+                            object_schema(vec![("zip", SchemaState::Null)], vec![]),
+                        )],
+                        vec![],
+                    )),
+                ),
+            ],
+        ));
+        assert_eq!(
+            to_zod(&schema, "Order"),
+            r#"import { z } from "zod";
+
+export const ShippingAddress = z.object({
+  city: z.string().email(),
+});
+export type ShippingAddress = z.infer<typeof ShippingAddress>;
+
+export const Address = z.object({
+  zip: z.null(),
+});
+export type Address = z.infer<typeof Address>;
+
+export const LinesItem = z.object({
+  address: Address,
+});
+export type LinesItem = z.infer<typeof LinesItem>;
+
+export const OrderItem = z.object({
+  id: z.number().int(),
+  status: z.enum(["closed", "open"]),
+  shipping_address: ShippingAddress,
+  "billing-address": ShippingAddress.nullable(),
+  tags: z.array(z.string().uuid()).optional(),
+  lines: z.array(LinesItem).optional(),
+});
+export type OrderItem = z.infer<typeof OrderItem>;
+
+export const Order = z.array(OrderItem);
+export type Order = z.infer<typeof Order>;"#
+        );
+    }
+
+    #[test]
+    fn discriminates_tagged_unions_by_their_tag() {
+        let click = object_schema(
+            vec![
+                ("type", strings(&["click"])),
+                (
+                    "x",
+                    SchemaState::Number(NumberType::Integer { min: 0, max: 1 }),
+                ),
+            ],
+            vec![],
+        );
+        let schema = object_schema(
+            vec![
+                (
+                    "event",
+                    SchemaState::TaggedUnion {
+                        tag: "type".to_string(),
+                        variants: BTreeMap::from_iter([
+                            ("click".to_string(), click),
+                            (
+                                "key".to_string(),
+                                object_schema(vec![("type", strings(&["key"]))], vec![]),
+                            ),
+                        ]),
+                    },
+                ),
+                (
+                    "value",
+                    SchemaState::Union(vec![
+                        SchemaState::Boolean,
+                        SchemaState::Number(NumberType::Enum {
+                            variants: BTreeMap::from_iter([(1, 1), (2, 1)]),
+                        }),
+                    ]),
+                ),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            to_zod(&schema, "Log"),
+            r#"import { z } from "zod";
+
+export const EventClick = z.object({
+  type: z.literal("click"),
+  x: z.number().int(),
+});
+export type EventClick = z.infer<typeof EventClick>;
+
+export const EventKey = z.object({
+  type: z.literal("key"),
+});
+export type EventKey = z.infer<typeof EventKey>;
+
+export const Log = z.object({
+  event: z.discriminatedUnion("type", [EventClick, EventKey]),
+  value: z.union([z.boolean(), z.union([z.literal(1), z.literal(2)])]),
+});
+export type Log = z.infer<typeof Log>;"#
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_refer_to_their_schemas_lazily() {
+        let tree = SchemaState::Recursive {
+            name: "node".to_string(),
+            schema: Box::new(object_schema(
+                vec![(
+                    "children",
+                    array(SchemaState::Reference("node".to_string())),
+                )],
+                vec![],
+            )),
+        };
+        assert_eq!(
+            to_zod(&tree, "Node"),
+            r#"import { z } from "zod";
+
+export const Node: z.ZodTypeAny = z.object({
+  children: z.array(z.lazy(() => Node)),
+});
+export type Node = z.infer<typeof Node>;"#
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+/// Runs drivel with the given arguments on the given input.
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_drivel"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Runs drivel with the given arguments on the given input, and parses what it writes as JSON.
+fn drivel(args: &[&str], input: &str) -> Value {
+    let output = run(args, input);
+    assert!(
+        output.status.success(),
+        "drivel {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn length(value: Value) -> usize {
+    value.as_array().expect("an array").len()
+}
+
+/// Records of a schema whose root array has four to six elements.
+const RECORDS: &str =
+    r#"{"type": "array", "minItems": 4, "maxItems": 6, "items": {"type": "boolean"}}"#;
+
+#[test]
+fn produces_n_elements_and_one_without_n() {
+    let produce = |n: &[&str]| {
+        let args = [&["--from-schema", "produce", "--seed", "1"], n].concat();
+        length(drivel(&args, RECORDS))
+    };
+    assert_eq!(produce(&[]), 1);
+    assert_eq!(produce(&["-n", "1"]), 1);
+    assert_eq!(produce(&["-n", "3"]), 3);
+    // `-n` wins over the bounds of the schema
+    assert_eq!(produce(&["-n", "1", "--n-from-schema"]), 1);
+}
+
+#[test]
+fn produces_as_many_elements_as_the_schema_allows_with_n_from_schema() {
+    for seed in ["1", "2", "3"] {
+        let args = [
+            "--from-schema",
+            "produce",
+            "--n-from-schema",
+            "--seed",
+            seed,
+        ];
+        let n = length(drivel(&args, RECORDS));
+        assert!((4..=6).contains(&n), "{} elements", n);
+    }
+    // a schema without an array at the root is produced once
+    let value = drivel(
+        &["--from-schema", "produce", "--n-from-schema"],
+        r#"{"type": "boolean"}"#,
+    );
+    assert!(value.is_boolean());
+}
+
+#[test]
+fn keeps_the_order_of_keys_unless_they_are_sorted() {
+    let input = r#"{"b": 1, "a": 2}"#;
+    let keys = |value: &Value| {
+        value
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    for (sort_keys, order) in [(&[][..], ["b", "a"]), (&["--sort-keys"][..], ["a", "b"])] {
+        let produced = drivel(&[&["produce", "--seed", "1"], sort_keys].concat(), input);
+        assert_eq!(keys(&produced), order);
+        let schema = drivel(
+            &[&["describe", "--format", "json-schema"], sort_keys].concat(),
+            input,
+        );
+        assert_eq!(keys(&schema["properties"]), order);
+        let description = run(&[&["describe"], sort_keys].concat(), input);
+        let description = String::from_utf8_lossy(&description.stdout);
+        let position = |key: &str| description.find(&format!("\"{}\"", key)).unwrap();
+        assert!(position(order[0]) < position(order[1]), "{}", description);
+    }
+}
+
+#[test]
+fn describes_only_records_of_the_same_fields_as_a_table() {
+    let describe = |input: &str| {
+        let output = run(&["describe", "--as-table"], input);
+        assert!(output.status.success());
+        (
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    };
+
+    let (table, warnings) = describe(r#"[{"a": 1, "b": 2}, {"a": 3, "b": 4}]"#);
+    assert!(table.starts_with("record (2 rows)"), "{}", table);
+    assert_eq!(warnings, "");
+
+    let (description, warnings) = describe(r#"[{"a": 1}, {"b": 2}]"#);
+    assert!(description.starts_with('['), "{}", description);
+    assert!(
+        description.contains("\"a\": optional int (1)"),
+        "{}",
+        description
+    );
+    assert!(
+        warnings.contains("don't all have the same fields"),
+        "{}",
+        warnings
+    );
+}
+
+/// Serves each request with the response of its path, a status line and headers and body, or
+/// with a 404 if it has none, on a port of its own, whose address is returned. Each connection
+/// is closed after one response.
+#[cfg(feature = "fetch")]
+fn serve(responses: &'static [(&'static str, &'static str)]) -> String {
+    use std::io::{BufRead, BufReader};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let Ok(reader) = stream.try_clone() else {
+                continue;
+            };
+            let mut lines = BufReader::new(reader).lines().map_while(Result::ok);
+            let request_line = lines.next().unwrap_or_default();
+            // the headers of the request end with a blank line
+            lines.take_while(|line| !line.is_empty()).for_each(drop);
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            let (status, headers) = responses
+                .iter()
+                .find(|(served, _)| *served == path)
+                .and_then(|(_, response)| response.split_once("\r\n"))
+                .unwrap_or(("404 Not Found", "Content-Length: 0\r\n\r\n"));
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nConnection: close\r\n{}",
+                status, headers
+            );
+        }
+    });
+    address
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetches_urls_and_fails_on_statuses_and_redirects_that_are_not_successes() {
+    let address = serve(&[
+        ("/orders", "200 OK\r\nContent-Length: 9\r\n\r\n{\"id\": 1}"),
+        (
+            "/moved",
+            "301 Moved Permanently\r\nLocation: /orders\r\nContent-Length: 0\r\n\r\n",
+        ),
+        (
+            "/loop",
+            "302 Found\r\nLocation: /loop\r\nContent-Length: 0\r\n\r\n",
+        ),
+    ]);
+    let url = |path: &str| format!("{}{}", address, path);
+    let describe = |path: &str| run(&["--url", &url(path), "describe"], "");
+
+    let output = describe("/moved");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"id\": int (1)"));
+    for (path, error) in [
+        ("/missing", "the server responded with 404 Not Found"),
+        ("/loop", "it redirected more than 10 times"),
+    ] {
+        let output = describe(path);
+        assert_eq!(output.status.code(), Some(74));
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains(error),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}